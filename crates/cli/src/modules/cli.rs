@@ -398,6 +398,32 @@ pub enum ServerCommands {
     /// Perform database maintenance
     DatabaseMaintenance {},
 
+    /// Check the data store for inconsistencies
+    Fsck {
+        /// Account to check, or all accounts if omitted
+        account: Option<String>,
+        /// Repair the inconsistencies found
+        #[clap(short, long)]
+        repair: bool,
+    },
+
+    /// Recalculate the used quota of accounts and tenants
+    RecalculateQuota {
+        /// Account to recalculate, or all accounts and tenants if omitted
+        account: Option<String>,
+        /// Correct the used quotas that do not match
+        #[clap(short, long)]
+        repair: bool,
+    },
+
+    /// Move an account to a different data store shard
+    MoveAccount {
+        /// Account to move
+        account: String,
+        /// Destination shard store
+        shard: String,
+    },
+
     /// Reload TLS certificates
     ReloadCertificates {},
 
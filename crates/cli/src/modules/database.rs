@@ -30,6 +30,41 @@ pub enum UpdateSettings {
     },
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct FsckReport {
+    pub accounts: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckIssue {
+    pub account_id: u32,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default)]
+    pub document_id: Option<u32>,
+    pub details: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct QuotaReport {
+    pub accounts: u64,
+    pub tenants: u64,
+    pub mismatches: Vec<QuotaMismatch>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct QuotaMismatch {
+    pub id: u32,
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub stored: i64,
+    pub actual: i64,
+    pub repaired: bool,
+}
+
 impl ServerCommands {
     pub async fn exec(self, client: Client) {
         match self {
@@ -39,6 +74,121 @@ impl ServerCommands {
                     .await;
                 eprintln!("Success.");
             }
+            ServerCommands::Fsck { account, repair } => {
+                let report = client
+                    .http_request::<FsckReport, String>(
+                        Method::GET,
+                        &format!(
+                            "/api/store/fsck{}?repair={repair}",
+                            account
+                                .map(|account| format!("/{account}"))
+                                .unwrap_or_default()
+                        ),
+                        None,
+                    )
+                    .await;
+
+                if !report.issues.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("Account").with_style(Attr::Bold),
+                        Cell::new("Document").with_style(Attr::Bold),
+                        Cell::new("Issue").with_style(Attr::Bold),
+                        Cell::new("Details").with_style(Attr::Bold),
+                        Cell::new("Repaired").with_style(Attr::Bold),
+                    ]));
+
+                    for issue in &report.issues {
+                        table.add_row(Row::new(vec![
+                            Cell::new(&issue.account_id.to_string()),
+                            Cell::new(
+                                &issue
+                                    .document_id
+                                    .map(|id| id.to_string())
+                                    .unwrap_or_default(),
+                            ),
+                            Cell::new(&issue.typ),
+                            Cell::new(&issue.details),
+                            Cell::new(if issue.repaired { "Yes" } else { "No" }),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                eprintln!(
+                    "\n\n{} issue{} found in {} account{}.\n",
+                    report.issues.len(),
+                    if report.issues.len() == 1 { "" } else { "s" },
+                    report.accounts,
+                    if report.accounts == 1 { "" } else { "s" }
+                );
+            }
+            ServerCommands::RecalculateQuota { account, repair } => {
+                let report = client
+                    .http_request::<QuotaReport, String>(
+                        Method::GET,
+                        &format!(
+                            "/api/store/quota{}?repair={repair}",
+                            account
+                                .map(|account| format!("/{account}"))
+                                .unwrap_or_default()
+                        ),
+                        None,
+                    )
+                    .await;
+
+                if !report.mismatches.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("Id").with_style(Attr::Bold),
+                        Cell::new("Type").with_style(Attr::Bold),
+                        Cell::new("Stored").with_style(Attr::Bold),
+                        Cell::new("Actual").with_style(Attr::Bold),
+                        Cell::new("Repaired").with_style(Attr::Bold),
+                    ]));
+
+                    for mismatch in &report.mismatches {
+                        table.add_row(Row::new(vec![
+                            Cell::new(&mismatch.id.to_string()),
+                            Cell::new(&mismatch.typ),
+                            Cell::new(&mismatch.stored.to_string()),
+                            Cell::new(&mismatch.actual.to_string()),
+                            Cell::new(if mismatch.repaired { "Yes" } else { "No" }),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                eprintln!(
+                    "\n\n{} mismatch{} found in {} account{} and {} tenant{}.\n",
+                    report.mismatches.len(),
+                    if report.mismatches.len() == 1 {
+                        ""
+                    } else {
+                        "es"
+                    },
+                    report.accounts,
+                    if report.accounts == 1 { "" } else { "s" },
+                    report.tenants,
+                    if report.tenants == 1 { "" } else { "s" }
+                );
+            }
+            ServerCommands::MoveAccount { account, shard } => {
+                client
+                    .http_request::<Value, String>(
+                        Method::GET,
+                        &format!("/api/store/shard/{account}/{shard}"),
+                        None,
+                    )
+                    .await;
+                eprintln!("Successfully requested account move to shard {shard}.");
+            }
             ServerCommands::ReloadCertificates {} => {
                 client
                     .http_request::<Value, String>(Method::GET, "/api/reload/certificate", None)
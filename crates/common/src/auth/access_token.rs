@@ -60,6 +60,12 @@ impl Server {
         // Apply principal permissions
         let mut permissions = role_permissions.finalize();
 
+        // Enforce the account lifecycle state
+        self.core
+            .jmap
+            .account_lifecycle
+            .restrict(principal.status(), &mut permissions);
+
         // SPDX-SnippetBegin
         // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
         // SPDX-License-Identifier: LicenseRef-SEL
@@ -94,12 +100,16 @@ impl Server {
 
         // SPDX-SnippetEnd
 
+        // Obtain nested and dynamic group memberships
+        let member_of = self
+            .directory()
+            .expand_member_of(&principal)
+            .await
+            .caused_by(trc::location!())?;
+
         Ok(AccessToken {
             primary_id: principal.id(),
-            member_of: principal
-                .iter_int(PrincipalField::MemberOf)
-                .map(|v| v as u32)
-                .collect(),
+            member_of,
             access_to: VecMap::new(),
             tenant,
             name: principal.take_str(PrincipalField::Name).unwrap_or_default(),
@@ -107,6 +117,12 @@ impl Server {
             emails: principal
                 .take_str_array(PrincipalField::Emails)
                 .unwrap_or_default(),
+            forward_to: principal
+                .take_str_array(PrincipalField::ForwardTo)
+                .unwrap_or_default(),
+            domain_scope: principal
+                .take_str_array(PrincipalField::DomainScope)
+                .unwrap_or_default(),
             quota: principal.quota(),
             permissions,
         })
@@ -358,11 +374,26 @@ impl AccessToken {
                 jmap_proto::method::get::RequestArguments::VacationResponse => {
                     Permission::JmapVacationResponseGet
                 }
+                jmap_proto::method::get::RequestArguments::MailRules => {
+                    Permission::JmapMailRulesGet
+                }
                 jmap_proto::method::get::RequestArguments::Principal => {
                     Permission::JmapPrincipalGet
                 }
                 jmap_proto::method::get::RequestArguments::Quota => Permission::JmapQuotaGet,
                 jmap_proto::method::get::RequestArguments::Blob(_) => Permission::JmapBlobGet,
+                jmap_proto::method::get::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationGet
+                }
+                jmap_proto::method::get::RequestArguments::EmailImportTask => {
+                    Permission::JmapEmailImport
+                }
+                jmap_proto::method::get::RequestArguments::EmailTemplate => {
+                    Permission::JmapEmailTemplateGet
+                }
+                jmap_proto::method::get::RequestArguments::SavedSearch => {
+                    Permission::JmapSavedSearchGet
+                }
             },
             RequestMethod::Set(m) => match &m.arguments {
                 jmap_proto::method::set::RequestArguments::Email => Permission::JmapEmailSet,
@@ -380,6 +411,22 @@ impl AccessToken {
                 jmap_proto::method::set::RequestArguments::VacationResponse => {
                     Permission::JmapVacationResponseSet
                 }
+                jmap_proto::method::set::RequestArguments::MailRules => {
+                    Permission::JmapMailRulesSet
+                }
+                jmap_proto::method::set::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationSet
+                }
+                jmap_proto::method::set::RequestArguments::EmailImportTask => {
+                    Permission::JmapEmailImport
+                }
+                jmap_proto::method::set::RequestArguments::EmailTemplate => {
+                    Permission::JmapEmailTemplateSet
+                }
+                jmap_proto::method::set::RequestArguments::SavedSearch => {
+                    Permission::JmapSavedSearchSet
+                }
+                jmap_proto::method::set::RequestArguments::Thread => Permission::JmapThreadSet,
             },
             RequestMethod::Changes(m) => match m.arguments {
                 jmap_proto::method::changes::RequestArguments::Email => {
@@ -400,6 +447,18 @@ impl AccessToken {
                 jmap_proto::method::changes::RequestArguments::Quota => {
                     Permission::JmapQuotaChanges
                 }
+                jmap_proto::method::changes::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationChanges
+                }
+                jmap_proto::method::changes::RequestArguments::EmailImportTask => {
+                    Permission::JmapEmailImport
+                }
+                jmap_proto::method::changes::RequestArguments::EmailTemplate => {
+                    Permission::JmapEmailTemplateChanges
+                }
+                jmap_proto::method::changes::RequestArguments::SavedSearch => {
+                    Permission::JmapSavedSearchChanges
+                }
             },
             RequestMethod::Copy(m) => match m.arguments {
                 jmap_proto::method::copy::RequestArguments::Email => Permission::JmapEmailCopy,
@@ -426,6 +485,9 @@ impl AccessToken {
                 jmap_proto::method::query::RequestArguments::Quota => {
                     Permission::JmapQuotaQueryChanges
                 }
+                jmap_proto::method::query::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationQuery
+                }
             },
             RequestMethod::Query(m) => match m.arguments {
                 jmap_proto::method::query::RequestArguments::Email(_) => Permission::JmapEmailQuery,
@@ -442,11 +504,16 @@ impl AccessToken {
                     Permission::JmapPrincipalQuery
                 }
                 jmap_proto::method::query::RequestArguments::Quota => Permission::JmapQuotaQuery,
+                jmap_proto::method::query::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationQuery
+                }
             },
             RequestMethod::SearchSnippet(_) => Permission::JmapSearchSnippet,
             RequestMethod::ValidateScript(_) => Permission::JmapSieveScriptValidate,
             RequestMethod::LookupBlob(_) => Permission::JmapBlobLookup,
             RequestMethod::UploadBlob(_) => Permission::JmapBlobUpload,
+            RequestMethod::ConvertBlob(_) => Permission::JmapBlobConvert,
+            RequestMethod::MergeSubmission(_) => Permission::JmapEmailSubmissionMerge,
             RequestMethod::Echo(_) => Permission::JmapEcho,
             RequestMethod::Error(_) => return Ok(()),
         };
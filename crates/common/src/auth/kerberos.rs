@@ -0,0 +1,468 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, sync::Arc};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use directory::{Directory, Permission, QueryBy};
+use utils::config::Config;
+
+use crate::Server;
+
+use super::AccessToken;
+
+// RFC 4752 security layer: no protection, no maximum message size
+const SASL_NO_SECURITY_LAYER: u8 = 0x01;
+
+#[derive(Clone)]
+pub struct KerberosConfig {
+    pub mapping: PrincipalMapping,
+    #[cfg(feature = "kerberos")]
+    pub credentials: libgssapi::credential::Cred,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrincipalMapping {
+    pub realms: Vec<String>,
+    pub strip_realm: bool,
+}
+
+pub struct GssapiSession {
+    #[cfg(feature = "kerberos")]
+    context: libgssapi::context::ServerCtx,
+    state: GssapiState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "kerberos"), allow(dead_code))]
+enum GssapiState {
+    Negotiate { sasl: bool },
+    AwaitEmpty,
+    SecurityLayer,
+    Done,
+}
+
+/// Result of a GSSAPI exchange step, tokens are base64 encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GssapiStep {
+    Continue(String),
+    Authenticated {
+        principal: String,
+        authz_id: Option<String>,
+        response: Option<String>,
+    },
+}
+
+impl KerberosConfig {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default::<bool>("authentication.kerberos.enable", "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        let service_principal = config
+            .value("authentication.kerberos.service-principal")
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string());
+
+        // Only principals from the service realm are accepted unless other realms are listed
+        let mut realms = config
+            .values("authentication.kerberos.realms")
+            .map(|(_, realm)| realm.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        if realms.is_empty() {
+            if let Some((_, realm)) = service_principal
+                .as_deref()
+                .and_then(|name| name.rsplit_once('@'))
+                .filter(|(_, realm)| !realm.is_empty())
+            {
+                realms.push(realm.to_ascii_uppercase());
+            } else {
+                config.new_parse_error(
+                    "authentication.kerberos.realms",
+                    "At least one realm is required when the service principal has no realm",
+                );
+                return None;
+            }
+        }
+        let mapping = PrincipalMapping {
+            realms,
+            strip_realm: config
+                .property_or_default("authentication.kerberos.strip-realm", "true")
+                .unwrap_or(true),
+        };
+
+        #[cfg(feature = "kerberos")]
+        {
+            let keytab = config
+                .value("authentication.kerberos.keytab")
+                .filter(|keytab| !keytab.is_empty())
+                .map(|keytab| keytab.to_string());
+
+            match acquire_credentials(service_principal.as_deref(), keytab.as_deref()) {
+                Ok(credentials) => KerberosConfig {
+                    mapping,
+                    credentials,
+                }
+                .into(),
+                Err(err) => {
+                    config.new_build_error(
+                        "authentication.kerberos",
+                        format!("Failed to acquire Kerberos credentials from keytab: {err}"),
+                    );
+                    None
+                }
+            }
+        }
+
+        #[cfg(not(feature = "kerberos"))]
+        {
+            let _ = (mapping, service_principal);
+            config.new_build_error(
+                "authentication.kerberos.enable",
+                "Kerberos support is not available in this build",
+            );
+            None
+        }
+    }
+}
+
+impl PrincipalMapping {
+    /// Maps a Kerberos principal name to a directory principal name.
+    pub fn map(&self, principal: &str) -> Option<String> {
+        let (name, realm) = principal.rsplit_once('@')?;
+
+        // Reject empty names and multi-component (service or admin instance) principals
+        if name.is_empty() || name.contains('/') || realm.is_empty() {
+            return None;
+        }
+
+        if !self.realms.iter().any(|r| r.eq_ignore_ascii_case(realm)) {
+            return None;
+        }
+
+        Some(if self.strip_realm {
+            name.to_lowercase()
+        } else {
+            format!("{}@{}", name, realm).to_lowercase()
+        })
+    }
+}
+
+impl GssapiSession {
+    /// Creates a session for the SASL GSSAPI mechanism (RFC 4752).
+    pub fn sasl(config: &KerberosConfig) -> Self {
+        Self::new(config, true)
+    }
+
+    /// Creates a session for HTTP Negotiate (RFC 4559) authentication.
+    pub fn negotiate(config: &KerberosConfig) -> Self {
+        Self::new(config, false)
+    }
+
+    fn new(config: &KerberosConfig, sasl: bool) -> Self {
+        #[cfg(not(feature = "kerberos"))]
+        let _ = config;
+
+        GssapiSession {
+            #[cfg(feature = "kerberos")]
+            context: libgssapi::context::ServerCtx::new(config.credentials.clone().into()),
+            state: GssapiState::Negotiate { sasl },
+        }
+    }
+
+    /// Processes a base64 encoded token received from the client.
+    pub fn step(&mut self, token: &str) -> trc::Result<GssapiStep> {
+        let token = token.trim();
+        let token = if !token.is_empty() && token != "=" {
+            STANDARD.decode(token).map_err(|_| {
+                trc::AuthEvent::Error
+                    .into_err()
+                    .details("Failed to decode GSSAPI token")
+            })?
+        } else {
+            vec![]
+        };
+
+        #[cfg(feature = "kerberos")]
+        {
+            use libgssapi::context::SecurityContext;
+
+            match self.state {
+                GssapiState::Negotiate { sasl } => {
+                    let response = self
+                        .context
+                        .step(&token)
+                        .map_err(gssapi_error)?
+                        .map(|response| STANDARD.encode(&response[..]));
+
+                    if !self.context.is_complete() {
+                        Ok(GssapiStep::Continue(response.unwrap_or_default()))
+                    } else if !sasl {
+                        self.state = GssapiState::Done;
+                        Ok(GssapiStep::Authenticated {
+                            principal: self.source_name()?,
+                            authz_id: None,
+                            response,
+                        })
+                    } else if let Some(response) = response {
+                        // The client acknowledges the final context token with an empty response
+                        self.state = GssapiState::AwaitEmpty;
+                        Ok(GssapiStep::Continue(response))
+                    } else {
+                        self.security_layer()
+                    }
+                }
+                GssapiState::AwaitEmpty => {
+                    if token.is_empty() {
+                        self.security_layer()
+                    } else {
+                        Err(trc::AuthEvent::Error
+                            .into_err()
+                            .details("Unexpected GSSAPI token"))
+                    }
+                }
+                GssapiState::SecurityLayer => {
+                    let message = self.context.unwrap(&token).map_err(gssapi_error)?;
+                    if message.len() < 4 || message[0] & SASL_NO_SECURITY_LAYER == 0 {
+                        return Err(trc::AuthEvent::Error
+                            .into_err()
+                            .details("Unsupported GSSAPI security layer"));
+                    }
+                    let authz_id = std::str::from_utf8(&message[4..])
+                        .map_err(|_| {
+                            trc::AuthEvent::Error
+                                .into_err()
+                                .details("Invalid GSSAPI authorization identity")
+                        })?
+                        .trim_end_matches('\0');
+
+                    self.state = GssapiState::Done;
+                    Ok(GssapiStep::Authenticated {
+                        principal: self.source_name()?,
+                        authz_id: (!authz_id.is_empty()).then(|| authz_id.to_string()),
+                        response: None,
+                    })
+                }
+                GssapiState::Done => Err(trc::AuthEvent::Error
+                    .into_err()
+                    .details("GSSAPI exchange already completed")),
+            }
+        }
+
+        #[cfg(not(feature = "kerberos"))]
+        {
+            let _ = (token, self.state, SASL_NO_SECURITY_LAYER);
+            Err(trc::AuthEvent::Error
+                .into_err()
+                .details("Kerberos support is not available in this build"))
+        }
+    }
+
+    #[cfg(feature = "kerberos")]
+    fn security_layer(&mut self) -> trc::Result<GssapiStep> {
+        use libgssapi::context::SecurityContext;
+
+        let challenge = self
+            .context
+            .wrap(false, &[SASL_NO_SECURITY_LAYER, 0, 0, 0])
+            .map_err(gssapi_error)?;
+        self.state = GssapiState::SecurityLayer;
+        Ok(GssapiStep::Continue(STANDARD.encode(&challenge[..])))
+    }
+
+    #[cfg(feature = "kerberos")]
+    fn source_name(&mut self) -> trc::Result<String> {
+        use libgssapi::context::SecurityContext;
+
+        self.context
+            .source_name()
+            .and_then(|name| name.display_name())
+            .map_err(gssapi_error)
+            .and_then(|name| {
+                String::from_utf8(name.to_vec()).map_err(|_| {
+                    trc::AuthEvent::Error
+                        .into_err()
+                        .details("Invalid Kerberos principal name")
+                })
+            })
+    }
+}
+
+/// Acquires the acceptor credentials, reading the keys from the configured keytab
+/// through the credential store rather than the process environment.
+#[cfg(feature = "kerberos")]
+fn acquire_credentials(
+    service_principal: Option<&str>,
+    keytab: Option<&str>,
+) -> Result<libgssapi::credential::Cred, String> {
+    use libgssapi::{
+        error::{Error, MajorFlags},
+        oid::{Oid, GSS_NT_KRB5_PRINCIPAL},
+    };
+    use libgssapi_sys::{
+        gss_OID, gss_acquire_cred_from, gss_buffer_desc, gss_cred_id_t, gss_cred_usage_t,
+        gss_import_name, gss_key_value_element_desc, gss_key_value_set_desc, gss_name_t,
+        gss_release_name, OM_uint32, _GSS_C_INDEFINITE, GSS_C_ACCEPT, GSS_S_COMPLETE,
+    };
+    use std::{ffi::CString, ptr};
+
+    let to_string = |major: OM_uint32, minor: OM_uint32| {
+        Error {
+            major: MajorFlags::from_bits_retain(major),
+            minor,
+        }
+        .to_string()
+    };
+    let keytab = keytab
+        .map(CString::new)
+        .transpose()
+        .map_err(|_| "Invalid keytab path".to_string())?;
+    let mut minor: OM_uint32 = GSS_S_COMPLETE;
+
+    // Import the service principal name
+    let mut name: gss_name_t = ptr::null_mut();
+    if let Some(service_principal) = service_principal {
+        let mut buffer = gss_buffer_desc {
+            length: service_principal.len() as _,
+            value: service_principal.as_ptr() as *mut _,
+        };
+        // Oid is a transparent wrapper around gss_OID_desc
+        let name_type = &GSS_NT_KRB5_PRINCIPAL as *const Oid as gss_OID;
+        let major = unsafe { gss_import_name(&mut minor, &mut buffer, name_type, &mut name) };
+        if major != GSS_S_COMPLETE {
+            return Err(format!(
+                "Invalid service principal: {}",
+                to_string(major, minor)
+            ));
+        }
+    }
+
+    // Use the default keytab when none is configured
+    let mut elements = keytab
+        .iter()
+        .map(|keytab| gss_key_value_element_desc {
+            key: c"keytab".as_ptr(),
+            value: keytab.as_ptr(),
+        })
+        .collect::<Vec<_>>();
+    let cred_store = gss_key_value_set_desc {
+        count: elements.len() as _,
+        elements: elements.as_mut_ptr(),
+    };
+
+    let mut cred: gss_cred_id_t = ptr::null_mut();
+    let major = unsafe {
+        gss_acquire_cred_from(
+            &mut minor,
+            name,
+            _GSS_C_INDEFINITE,
+            ptr::null_mut(),
+            GSS_C_ACCEPT as gss_cred_usage_t,
+            if elements.is_empty() {
+                ptr::null()
+            } else {
+                &cred_store as *const gss_key_value_set_desc
+            },
+            &mut cred,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    if !name.is_null() {
+        let mut release_minor: OM_uint32 = GSS_S_COMPLETE;
+        unsafe { gss_release_name(&mut release_minor, &mut name) };
+    }
+
+    if major == GSS_S_COMPLETE {
+        Ok(cred.into())
+    } else {
+        Err(to_string(major, minor))
+    }
+}
+
+#[cfg(feature = "kerberos")]
+fn gssapi_error(err: libgssapi::error::Error) -> trc::Error {
+    trc::AuthEvent::Error
+        .into_err()
+        .details("GSSAPI error")
+        .reason(err)
+}
+
+impl Server {
+    /// Starts a GSSAPI exchange, failing if Kerberos authentication is not enabled.
+    pub fn gssapi_session(&self, sasl: bool) -> trc::Result<GssapiSession> {
+        self.core
+            .jmap
+            .kerberos
+            .as_ref()
+            .map(|config| {
+                if sasl {
+                    GssapiSession::sasl(config)
+                } else {
+                    GssapiSession::negotiate(config)
+                }
+            })
+            .ok_or_else(|| {
+                trc::AuthEvent::Error
+                    .into_err()
+                    .details("Kerberos authentication is not enabled.")
+            })
+    }
+
+    /// Obtains an access token for a principal authenticated by the Kerberos KDC.
+    pub async fn authenticate_kerberos(
+        &self,
+        principal: &str,
+        authz_id: Option<&str>,
+        directory: Option<&Directory>,
+        session_id: u64,
+        remote_ip: IpAddr,
+    ) -> trc::Result<Arc<AccessToken>> {
+        let directory = directory.unwrap_or(&self.core.storage.directory);
+        let name = self
+            .core
+            .jmap
+            .kerberos
+            .as_ref()
+            .and_then(|config| config.mapping.map(principal))
+            .filter(|name| {
+                // Proxy authorization is not supported
+                authz_id.is_none_or(|authz_id| {
+                    authz_id.eq_ignore_ascii_case(name) || authz_id.eq_ignore_ascii_case(principal)
+                })
+            })
+            .ok_or_else(|| {
+                trc::AuthEvent::Failed
+                    .ctx(trc::Key::RemoteIp, remote_ip)
+                    .ctx(trc::Key::AccountName, principal.to_string())
+            })?;
+
+        let principal = directory
+            .query(QueryBy::Name(&name), false)
+            .await?
+            .ok_or_else(|| {
+                trc::AuthEvent::Failed
+                    .ctx(trc::Key::RemoteIp, remote_ip)
+                    .ctx(trc::Key::AccountName, name.clone())
+            })?;
+
+        trc::event!(
+            Auth(trc::AuthEvent::Success),
+            AccountName = name,
+            AccountId = principal.id(),
+            SpanId = session_id,
+            Type = principal.typ().as_str(),
+        );
+
+        let access_token = self.get_cached_access_token(principal.id()).await?;
+        access_token
+            .assert_has_permission(Permission::Authenticate)
+            .map(|_| access_token)
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::{AccountStatus, Permission, Permissions};
+use utils::config::Config;
+
+#[derive(Debug, Clone)]
+pub struct AccountLifecycle {
+    pub suspended_receive: bool,
+    pub deletion_grace_period: Duration,
+}
+
+impl AccountLifecycle {
+    pub fn parse(config: &mut Config) -> Self {
+        AccountLifecycle {
+            suspended_receive: config
+                .property_or_default("account.suspended.receive", "true")
+                .unwrap_or(true),
+            deletion_grace_period: config
+                .property_or_default("account.deletion.grace-period", "30d")
+                .unwrap_or(Duration::from_secs(30 * 86400)),
+        }
+    }
+
+    /// Restricts the permissions of an account according to its lifecycle state.
+    pub fn restrict(&self, status: AccountStatus, permissions: &mut Permissions) {
+        match status {
+            AccountStatus::Active => (),
+            AccountStatus::ReadOnly => {
+                permissions.clear(Permission::EmailSend.id());
+                permissions.clear(Permission::JmapEmailSubmissionSet.id());
+                permissions.clear(Permission::JmapEmailSubmissionMerge.id());
+            }
+            AccountStatus::Suspended | AccountStatus::PendingDeletion => {
+                // Suspended accounts can keep receiving mail, pending deletions bounce it
+                let receive = status == AccountStatus::Suspended
+                    && self.suspended_receive
+                    && permissions.get(Permission::EmailReceive.id());
+                permissions.clear_all();
+                if receive {
+                    permissions.set(Permission::EmailReceive.id());
+                }
+            }
+        }
+    }
+}
+
+impl Default for AccountLifecycle {
+    fn default() -> Self {
+        AccountLifecycle {
+            suspended_receive: true,
+            deletion_grace_period: Duration::from_secs(30 * 86400),
+        }
+    }
+}
@@ -17,9 +17,14 @@ use utils::map::{bitmap::Bitmap, ttl_dashmap::TtlMap, vec_map::VecMap};
 use crate::Server;
 
 pub mod access_token;
+pub mod kerberos;
+pub mod lifecycle;
 pub mod oauth;
+pub mod password;
 pub mod roles;
 pub mod sasl;
+pub mod self_service;
+pub mod webauthn;
 
 #[derive(Debug, Clone, Default)]
 pub struct AccessToken {
@@ -29,9 +34,11 @@ pub struct AccessToken {
     pub name: String,
     pub description: Option<String>,
     pub emails: Vec<String>,
+    pub forward_to: Vec<String>,
     pub quota: u64,
     pub permissions: Permissions,
     pub tenant: Option<TenantInfo>,
+    pub domain_scope: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -102,12 +109,33 @@ impl Server {
         req: &AuthRequest<'_>,
         directory: &Directory,
     ) -> trc::Result<Principal> {
+        // Reject logins to locked accounts, even with valid credentials
+        let login = req.credentials.login();
+        if let Some(login) = login {
+            if self.is_account_locked(login).await? {
+                return Err(trc::AuthEvent::AccountLocked
+                    .ctx(trc::Key::RemoteIp, req.remote_ip)
+                    .ctx(trc::Key::AccountName, login.to_string()));
+            }
+        }
+
         // First try to authenticate the user against the default directory
         let result = match directory
             .query(QueryBy::Credentials(&req.credentials), req.return_member_of)
             .await
         {
             Ok(Some(principal)) => {
+                if let Some(login) = login {
+                    self.reset_login_failures(login).await?;
+                }
+
+                if self.is_password_expired(&principal).await? {
+                    return Err(trc::AuthEvent::PasswordExpired
+                        .ctx(trc::Key::RemoteIp, req.remote_ip)
+                        .ctx(trc::Key::AccountName, principal.name().to_string())
+                        .ctx(trc::Key::AccountId, principal.id()));
+                }
+
                 trc::event!(
                     Auth(trc::AuthEvent::Success),
                     AccountName = principal.name().to_string(),
@@ -172,10 +200,18 @@ impl Server {
             _ => {}
         }
 
+        // Count failed attempts towards the account lockout
+        if let (Ok(()), Some(login)) = (&result, login) {
+            if self.record_login_failure(login).await? {
+                return Err(trc::AuthEvent::AccountLocked
+                    .ctx(trc::Key::RemoteIp, req.remote_ip)
+                    .ctx(trc::Key::AccountName, login.to_string()));
+            }
+        }
+
         if let Err(err) = result {
             Err(err)
         } else if self.has_auth_fail2ban() {
-            let login = req.credentials.login();
             if self.is_auth_fail2banned(req.remote_ip, login).await? {
                 Err(trc::SecurityEvent::AuthenticationBan
                     .into_err()
@@ -189,10 +225,7 @@ impl Server {
         } else {
             Err(trc::AuthEvent::Failed
                 .ctx(trc::Key::RemoteIp, req.remote_ip)
-                .ctx_opt(
-                    trc::Key::AccountName,
-                    req.credentials.login().map(|s| s.to_string()),
-                ))
+                .ctx_opt(trc::Key::AccountName, login.map(|s| s.to_string())))
         }
     }
 
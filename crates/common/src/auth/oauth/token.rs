@@ -204,6 +204,16 @@ impl Server {
                     .reason(err)
             })?;
 
+        // Validate revocation
+        if self
+            .is_session_revoked(account_id, issued_at + OAUTH_EPOCH)
+            .await?
+        {
+            return Err(trc::AuthEvent::Error
+                .into_err()
+                .details("Token was revoked"));
+        }
+
         // Success
         Ok(TokenInfo {
             grant_type,
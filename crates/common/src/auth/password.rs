@@ -0,0 +1,403 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, ManageDirectory},
+        PasswordState, PrincipalAction, PrincipalField, PrincipalInfo, PrincipalUpdate,
+        PrincipalValue, SpecialSecrets,
+    },
+    core::secret::{is_hashed_secret, verify_secret_hash},
+    DirectoryInner, Principal, QueryBy, Type,
+};
+use store::{
+    write::{now, BatchBuilder, DirectoryClass, MaybeDynamicId, ValueClass},
+    Serialize, ValueKey,
+};
+use trc::AddContext;
+use utils::config::Config;
+
+use crate::Server;
+
+#[derive(Debug, Clone, Default)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub history: usize,
+    pub max_age: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountLockout {
+    pub max_attempts: u64,
+    pub window: Duration,
+    pub duration: Duration,
+}
+
+impl PasswordPolicy {
+    pub fn parse(config: &mut Config) -> Self {
+        PasswordPolicy {
+            min_length: config
+                .property_or_default("authentication.password.min-length", "0")
+                .unwrap_or(0),
+            max_length: config
+                .property_or_default("authentication.password.max-length", "0")
+                .unwrap_or(0),
+            require_uppercase: config
+                .property_or_default("authentication.password.require.uppercase", "false")
+                .unwrap_or(false),
+            require_lowercase: config
+                .property_or_default("authentication.password.require.lowercase", "false")
+                .unwrap_or(false),
+            require_digit: config
+                .property_or_default("authentication.password.require.digit", "false")
+                .unwrap_or(false),
+            require_special: config
+                .property_or_default("authentication.password.require.special", "false")
+                .unwrap_or(false),
+            history: config
+                .property_or_default::<usize>("authentication.password.history", "0")
+                .unwrap_or(0)
+                .min(manage::MAX_PASSWORD_HISTORY),
+            max_age: config
+                .property_or_default::<Option<Duration>>("authentication.password.max-age", "false")
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the reason why a plain-text password does not comply with the policy.
+    pub fn validate(&self, password: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            Err(format!(
+                "Password must be at least {} characters long",
+                self.min_length
+            ))
+        } else if self.max_length > 0 && length > self.max_length {
+            Err(format!(
+                "Password must be at most {} characters long",
+                self.max_length
+            ))
+        } else if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            Err("Password must contain at least one uppercase letter".to_string())
+        } else if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            Err("Password must contain at least one lowercase letter".to_string())
+        } else if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            Err("Password must contain at least one digit".to_string())
+        } else if self.require_special && password.chars().all(|c| c.is_alphanumeric()) {
+            Err("Password must contain at least one special character".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl AccountLockout {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        let max_attempts = config
+            .property_or_default::<u64>("authentication.lockout.max-attempts", "0")
+            .unwrap_or(0);
+
+        if max_attempts > 0 {
+            AccountLockout {
+                max_attempts,
+                window: config
+                    .property_or_default("authentication.lockout.window", "15m")
+                    .unwrap_or_else(|| Duration::from_secs(15 * 60)),
+                duration: config
+                    .property_or_default("authentication.lockout.duration", "15m")
+                    .unwrap_or_else(|| Duration::from_secs(15 * 60)),
+            }
+            .into()
+        } else {
+            None
+        }
+    }
+}
+
+impl Server {
+    /// Validates the passwords added by a set of principal changes against the password policy.
+    pub async fn assert_password_updates(
+        &self,
+        principal_id: Option<u32>,
+        changes: &[PrincipalUpdate],
+    ) -> trc::Result<()> {
+        for change in changes {
+            if change.field == PrincipalField::Secrets
+                && matches!(
+                    change.action,
+                    PrincipalAction::Set | PrincipalAction::AddItem
+                )
+            {
+                match &change.value {
+                    PrincipalValue::String(secret) => {
+                        self.assert_password_policy(principal_id, secret).await?;
+                    }
+                    PrincipalValue::StringList(secrets) => {
+                        for secret in secrets {
+                            self.assert_password_policy(principal_id, secret).await?;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the passwords of a principal that is about to be created.
+    pub async fn assert_principal_passwords(&self, principal: &Principal) -> trc::Result<()> {
+        for secret in principal.iter_str(PrincipalField::Secrets) {
+            self.assert_password_policy(None, secret).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn assert_password_policy(
+        &self,
+        principal_id: Option<u32>,
+        password: &str,
+    ) -> trc::Result<()> {
+        if password.is_empty() || !password.is_password() {
+            return Ok(());
+        }
+
+        // Pre-hashed passwords can only be checked against the history
+        let policy = &self.core.jmap.password_policy;
+        let is_hashed = is_hashed_secret(password);
+        if !is_hashed {
+            policy
+                .validate(password)
+                .map_err(|reason| manage::error("Password policy violation", reason.into()))?;
+        }
+
+        // Make sure the password was not used recently
+        if let Some(principal_id) = principal_id.filter(|_| policy.history > 0) {
+            let mut previous = self
+                .core
+                .storage
+                .data
+                .query(QueryBy::Id(principal_id), false)
+                .await
+                .caused_by(trc::location!())?
+                .map(|principal| {
+                    principal
+                        .iter_str(PrincipalField::Secrets)
+                        .filter(|secret| secret.is_password())
+                        .map(|secret| secret.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            if let Some(state) = self
+                .core
+                .storage
+                .data
+                .get_value::<PasswordState>(ValueKey::from(ValueClass::Directory(
+                    DirectoryClass::PasswordState(principal_id),
+                )))
+                .await
+                .caused_by(trc::location!())?
+            {
+                previous.extend(state.history.into_iter().take(policy.history));
+            }
+
+            for hash in previous {
+                if hash == password
+                    || (!is_hashed && verify_secret_hash(&hash, password).await.unwrap_or(false))
+                {
+                    return Err(manage::error(
+                        "Password policy violation",
+                        "Password was used recently".into(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn is_password_expired(&self, principal: &Principal) -> trc::Result<bool> {
+        let Some(max_age) = self.core.jmap.password_policy.max_age else {
+            return Ok(false);
+        };
+        if principal.typ() != Type::Individual
+            || !matches!(
+                self.core.storage.directory.store,
+                DirectoryInner::Internal(_)
+            )
+        {
+            return Ok(false);
+        }
+
+        let store = &self.core.storage.data;
+        match store
+            .get_value::<PasswordState>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::PasswordState(principal.id()),
+            )))
+            .await
+            .caused_by(trc::location!())?
+        {
+            Some(state) => Ok(state.changed + max_age.as_secs() < now()),
+            None => {
+                // Passwords set before expiration was enabled start aging now,
+                // the state is written in the background to keep logins fast.
+                let store = store.clone();
+                let account_id = principal.id();
+                tokio::spawn(async move {
+                    let class = ValueClass::Directory(DirectoryClass::PasswordState(
+                        MaybeDynamicId::Static(account_id),
+                    ));
+                    let mut batch = BatchBuilder::new();
+                    batch.assert_value(class.clone(), ()).set(
+                        class,
+                        (&PasswordState {
+                            changed: now(),
+                            ..Default::default()
+                        })
+                            .serialize(),
+                    );
+                    if let Err(err) = store.write(batch.build()).await {
+                        if !err.is_assertion_failure() {
+                            trc::error!(err
+                                .details("Failed to initialize password state.")
+                                .account_id(account_id)
+                                .caused_by(trc::location!()));
+                        }
+                    }
+                });
+
+                Ok(false)
+            }
+        }
+    }
+
+    pub(crate) async fn is_account_locked(&self, login: &str) -> trc::Result<bool> {
+        if self.core.jmap.account_lockout.is_some() {
+            let subject = self.lockout_subject(login).await?;
+            self.lookup_store()
+                .key_exists(lockout_key(&subject))
+                .await
+                .caused_by(trc::location!())
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Counts a failed login attempt, returns `true` when the account was locked as a result.
+    pub(crate) async fn record_login_failure(&self, login: &str) -> trc::Result<bool> {
+        let Some(lockout) = &self.core.jmap.account_lockout else {
+            return Ok(false);
+        };
+
+        let subject = self.lockout_subject(login).await?;
+        let attempts = self
+            .lookup_store()
+            .counter_incr(
+                attempts_key(&subject),
+                1,
+                lockout.window.as_secs().into(),
+                true,
+            )
+            .await
+            .caused_by(trc::location!())?;
+        if attempts >= lockout.max_attempts as i64 {
+            self.lookup_store()
+                .key_set(
+                    lockout_key(&subject),
+                    vec![],
+                    lockout.duration.as_secs().into(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+            self.lookup_store()
+                .counter_delete(attempts_key(&subject))
+                .await
+                .caused_by(trc::location!())?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub(crate) async fn reset_login_failures(&self, login: &str) -> trc::Result<()> {
+        if self.core.jmap.account_lockout.is_some() {
+            let subject = self.lockout_subject(login).await?;
+            self.lookup_store()
+                .counter_delete(attempts_key(&subject))
+                .await
+                .caused_by(trc::location!())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Removes the lockout and failed attempt counters of an account.
+    pub async fn unlock_account(&self, account_id: u32) -> trc::Result<()> {
+        let subject = LockoutSubject::Account(account_id);
+        self.lookup_store()
+            .key_delete(lockout_key(&subject))
+            .await
+            .caused_by(trc::location!())?;
+        self.lookup_store()
+            .counter_delete(attempts_key(&subject))
+            .await
+            .caused_by(trc::location!())
+    }
+
+    /// Lockouts are tracked per account so that all the login names and
+    /// addresses of an account share the same counters.
+    async fn lockout_subject(&self, login: &str) -> trc::Result<LockoutSubject> {
+        let login = login.to_lowercase();
+        let store = &self.core.storage.data;
+        if let Some(account_id) = store
+            .get_principal_id(&login)
+            .await
+            .caused_by(trc::location!())?
+        {
+            return Ok(LockoutSubject::Account(account_id));
+        }
+        if let Some(info) = store
+            .get_value::<PrincipalInfo>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::EmailToId(login.as_bytes().to_vec()),
+            )))
+            .await
+            .caused_by(trc::location!())?
+            .filter(|info| info.typ != Type::List)
+        {
+            return Ok(LockoutSubject::Account(info.id));
+        }
+
+        Ok(LockoutSubject::Login(login))
+    }
+}
+
+enum LockoutSubject {
+    Account(u32),
+    Login(String),
+}
+
+fn lockout_key(subject: &LockoutSubject) -> Vec<u8> {
+    match subject {
+        LockoutSubject::Account(account_id) => format!("L#{account_id}").into_bytes(),
+        LockoutSubject::Login(login) => format!("L:{login}").into_bytes(),
+    }
+}
+
+fn attempts_key(subject: &LockoutSubject) -> Vec<u8> {
+    match subject {
+        LockoutSubject::Account(account_id) => format!("l#{account_id}").into_bytes(),
+        LockoutSubject::Login(login) => format!("l:{login}").into_bytes(),
+    }
+}
@@ -9,7 +9,7 @@ use std::sync::{Arc, LazyLock};
 use ahash::AHashSet;
 use directory::{
     backend::internal::{lookup::DirectoryStore, PrincipalField},
-    Permission, Permissions, QueryBy, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
+    Permission, Permissions, QueryBy, ROLE_ADMIN, ROLE_DOMAIN_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
 };
 use trc::AddContext;
 
@@ -25,6 +25,8 @@ static USER_PERMISSIONS: LazyLock<Arc<RolePermissions>> = LazyLock::new(user_per
 static ADMIN_PERMISSIONS: LazyLock<Arc<RolePermissions>> = LazyLock::new(admin_permissions);
 static TENANT_ADMIN_PERMISSIONS: LazyLock<Arc<RolePermissions>> =
     LazyLock::new(tenant_admin_permissions);
+static DOMAIN_ADMIN_PERMISSIONS: LazyLock<Arc<RolePermissions>> =
+    LazyLock::new(domain_admin_permissions);
 
 impl Server {
     pub async fn get_role_permissions(&self, role_id: u32) -> trc::Result<Arc<RolePermissions>> {
@@ -32,6 +34,7 @@ impl Server {
             ROLE_USER => Ok(USER_PERMISSIONS.clone()),
             ROLE_ADMIN => Ok(ADMIN_PERMISSIONS.clone()),
             ROLE_TENANT_ADMIN => Ok(TENANT_ADMIN_PERMISSIONS.clone()),
+            ROLE_DOMAIN_ADMIN => Ok(DOMAIN_ADMIN_PERMISSIONS.clone()),
             role_id => {
                 if let Some(role_permissions) = self.inner.data.permissions.get(&role_id) {
                     Ok(role_permissions.clone())
@@ -79,6 +82,14 @@ impl Server {
                             .disabled
                             .union(&TENANT_ADMIN_PERMISSIONS.disabled);
                     }
+                    ROLE_DOMAIN_ADMIN => {
+                        return_permissions
+                            .enabled
+                            .union(&DOMAIN_ADMIN_PERMISSIONS.enabled);
+                        return_permissions
+                            .disabled
+                            .union(&DOMAIN_ADMIN_PERMISSIONS.disabled);
+                    }
                     role_id => {
                         // Try with the cache
                         if let Some(role_permissions) = self.inner.data.permissions.get(&role_id) {
@@ -185,6 +196,22 @@ fn tenant_admin_permissions() -> Arc<RolePermissions> {
     Arc::new(permissions)
 }
 
+fn domain_admin_permissions() -> Arc<RolePermissions> {
+    let mut permissions = RolePermissions {
+        enabled: Permissions::new(),
+        disabled: Permissions::new(),
+    };
+
+    for permission_id in 0..Permission::COUNT {
+        let permission = Permission::from_id(permission_id).unwrap();
+        if permission.is_domain_admin_permission() {
+            permissions.enabled.set(permission_id);
+        }
+    }
+
+    Arc::new(permissions)
+}
+
 fn user_permissions() -> Arc<RolePermissions> {
     let mut permissions = RolePermissions {
         enabled: Permissions::new(),
@@ -0,0 +1,431 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PasswordState, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
+    },
+    core::secret::{
+        generate_otp_url, generate_recovery_code, hash_recovery_code, hash_secret, verify_otp,
+    },
+    Principal, QueryBy,
+};
+use store::{
+    rand::{distributions::Alphanumeric, thread_rng, Rng},
+    write::{now, BatchBuilder, DirectoryClass, MaybeDynamicId, ValueClass},
+    Serialize, ValueKey,
+};
+use trc::AddContext;
+use utils::config::{utils::ParseValue, Config};
+
+use crate::Server;
+
+use super::AccessToken;
+
+const APP_PASSWORD_LEN: usize = 24;
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct SelfServiceConfig {
+    pub alias_policy: AliasPolicy,
+    pub max_aliases: usize,
+    pub forwarding_enabled: bool,
+    pub max_forward_to: usize,
+    pub max_app_passwords: usize,
+    pub recovery_codes: usize,
+    pub totp_issuer: String,
+    pub totp_enroll_expiry: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasPolicy {
+    Disabled,
+    SameDomain,
+    AnyDomain,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPassword {
+    pub name: String,
+    pub last_used: Option<u64>,
+}
+
+impl SelfServiceConfig {
+    pub fn parse(config: &mut Config) -> Self {
+        SelfServiceConfig {
+            alias_policy: config
+                .property_or_default("account.alias.policy", "disabled")
+                .unwrap_or(AliasPolicy::Disabled),
+            max_aliases: config
+                .property_or_default("account.alias.max", "5")
+                .unwrap_or(5),
+            forwarding_enabled: config
+                .property_or_default("account.forward.enable", "true")
+                .unwrap_or(true),
+            max_forward_to: config
+                .property_or_default("account.forward.max-recipients", "5")
+                .unwrap_or(5),
+            max_app_passwords: config
+                .property_or_default("account.app-password.max", "20")
+                .unwrap_or(20),
+            recovery_codes: config
+                .property_or_default::<usize>("account.totp.recovery-codes", "10")
+                .unwrap_or(10)
+                .clamp(1, 50),
+            totp_issuer: config
+                .value("account.totp.issuer")
+                .unwrap_or("Stalwart Mail Server")
+                .to_string(),
+            totp_enroll_expiry: config
+                .property_or_default("account.totp.enroll-expiry", "10m")
+                .unwrap_or_else(|| Duration::from_secs(10 * 60)),
+        }
+    }
+}
+
+impl Default for SelfServiceConfig {
+    fn default() -> Self {
+        SelfServiceConfig {
+            alias_policy: AliasPolicy::Disabled,
+            max_aliases: 5,
+            forwarding_enabled: true,
+            max_forward_to: 5,
+            max_app_passwords: 20,
+            recovery_codes: 10,
+            totp_issuer: "Stalwart Mail Server".to_string(),
+            totp_enroll_expiry: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl ParseValue for AliasPolicy {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "disabled" | "false" => Ok(AliasPolicy::Disabled),
+            "same-domain" => Ok(AliasPolicy::SameDomain),
+            "any-domain" => Ok(AliasPolicy::AnyDomain),
+            other => Err(format!("Unknown alias policy {other:?}")),
+        }
+    }
+}
+
+impl Server {
+    /// Validates an alias requested by a user against the configured domain policy.
+    pub fn assert_alias_policy(&self, access_token: &AccessToken, alias: &str) -> trc::Result<()> {
+        let config = &self.core.jmap.self_service;
+        let domain = alias
+            .rsplit_once('@')
+            .filter(|(local, domain)| !local.is_empty() && domain.contains('.'))
+            .map(|(_, domain)| domain)
+            .ok_or_else(|| manage::error("Invalid email", "Email address is invalid".into()))?;
+
+        match config.alias_policy {
+            AliasPolicy::Disabled => {
+                return Err(manage::error(
+                    "Alias requests are disabled",
+                    "Contact your administrator to add aliases".into(),
+                ));
+            }
+            AliasPolicy::SameDomain
+                if !access_token.emails.iter().any(|email| {
+                    email
+                        .rsplit_once('@')
+                        .is_some_and(|(_, d)| d.eq_ignore_ascii_case(domain))
+                }) =>
+            {
+                return Err(manage::error(
+                    "Alias policy violation",
+                    "Aliases must belong to the domain of your primary address".into(),
+                ));
+            }
+            _ => {}
+        }
+
+        if access_token.emails.len().saturating_sub(1) >= config.max_aliases {
+            Err(manage::error(
+                "Alias policy violation",
+                format!("Accounts are limited to {} aliases", config.max_aliases).into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Starts a TOTP enrolment, the secret is only added to the account once confirmed.
+    pub async fn enroll_totp(&self, access_token: &AccessToken) -> trc::Result<String> {
+        let url = generate_otp_url(&self.core.jmap.self_service.totp_issuer, &access_token.name)?;
+        self.lookup_store()
+            .key_set(
+                totp_key(access_token.primary_id()),
+                url.clone().into_bytes(),
+                self.core
+                    .jmap
+                    .self_service
+                    .totp_enroll_expiry
+                    .as_secs()
+                    .into(),
+            )
+            .await
+            .caused_by(trc::location!())
+            .map(|_| url)
+    }
+
+    /// Verifies a token against a pending TOTP enrolment and enables TOTP,
+    /// returning a new set of recovery codes.
+    pub async fn confirm_totp(
+        &self,
+        access_token: &AccessToken,
+        token: &str,
+    ) -> trc::Result<Vec<String>> {
+        let key = totp_key(access_token.primary_id());
+        let url = self
+            .lookup_store()
+            .key_get::<String>(key.clone())
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| {
+                manage::error(
+                    "TOTP enrolment failed",
+                    "No pending enrolment or enrolment expired".into(),
+                )
+            })?;
+        if !verify_otp(&url, token.trim()) {
+            return Err(manage::error(
+                "TOTP enrolment failed",
+                "Invalid verification code".into(),
+            ));
+        }
+        self.lookup_store()
+            .key_delete(key)
+            .await
+            .caused_by(trc::location!())?;
+
+        let (codes, mut changes) = self.recovery_code_updates();
+        changes.insert(
+            0,
+            PrincipalUpdate::remove_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String("otpauth://".to_string()),
+            ),
+        );
+        changes.insert(
+            1,
+            PrincipalUpdate::add_item(PrincipalField::Secrets, PrincipalValue::String(url)),
+        );
+        self.update_account_secrets(access_token, changes)
+            .await
+            .map(|_| codes)
+    }
+
+    /// Replaces the recovery codes of an account with TOTP enabled.
+    pub async fn regenerate_recovery_codes(
+        &self,
+        access_token: &AccessToken,
+    ) -> trc::Result<Vec<String>> {
+        if !self
+            .account_principal(access_token.primary_id())
+            .await?
+            .iter_str(PrincipalField::Secrets)
+            .any(|secret| secret.is_otp_auth())
+        {
+            return Err(manage::error(
+                "TOTP is not enabled",
+                "Recovery codes require TOTP to be enabled".into(),
+            ));
+        }
+
+        let (codes, changes) = self.recovery_code_updates();
+        self.update_account_secrets(access_token, changes)
+            .await
+            .map(|_| codes)
+    }
+
+    /// Returns the number of unused recovery codes of an account.
+    pub async fn recovery_codes_left(&self, account_id: u32) -> trc::Result<usize> {
+        self.account_principal(account_id).await.map(|principal| {
+            principal
+                .iter_str(PrincipalField::Secrets)
+                .filter(|secret| secret.is_recovery_code())
+                .count()
+        })
+    }
+
+    /// Generates an app password, the plain-text password is only returned once.
+    pub async fn create_app_password(
+        &self,
+        access_token: &AccessToken,
+        name: &str,
+    ) -> trc::Result<String> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_LABEL_LEN || name.contains('$') {
+            return Err(manage::error(
+                "Invalid app password name",
+                "Names must be between 1 and 64 characters and cannot contain '$'".into(),
+            ));
+        }
+        let existing = self.app_passwords(access_token.primary_id()).await?;
+        if existing.iter().any(|app| app.name == name) {
+            return Err(manage::error(
+                "App password already exists",
+                name.to_string().into(),
+            ));
+        } else if existing.len() >= self.core.jmap.self_service.max_app_passwords {
+            return Err(manage::error(
+                "Too many app passwords",
+                format!(
+                    "Accounts are limited to {} app passwords",
+                    self.core.jmap.self_service.max_app_passwords
+                )
+                .into(),
+            ));
+        }
+
+        let password = thread_rng()
+            .sample_iter(Alphanumeric)
+            .take(APP_PASSWORD_LEN)
+            .map(char::from)
+            .collect::<String>();
+        self.update_account_secrets(
+            access_token,
+            vec![PrincipalUpdate::add_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(format!("$app${name}${}", hash_secret(&password))),
+            )],
+        )
+        .await
+        .map(|_| password)
+    }
+
+    /// Lists the app passwords of an account along with the time they were last used.
+    pub async fn app_passwords(&self, account_id: u32) -> trc::Result<Vec<AppPassword>> {
+        let principal = self.account_principal(account_id).await?;
+        let state = self.password_state(account_id).await?.unwrap_or_default();
+
+        Ok(principal
+            .iter_str(PrincipalField::Secrets)
+            .filter_map(|secret| {
+                let (name, _) = secret.strip_prefix("$app$")?.split_once('$')?;
+                Some(AppPassword {
+                    name: name.to_string(),
+                    last_used: state
+                        .app_last_used
+                        .iter()
+                        .find(|(app, _)| app == name)
+                        .map(|(_, last_used)| *last_used),
+                })
+            })
+            .collect())
+    }
+
+    /// Invalidates all OAuth tokens issued to an account and clears its cached sessions.
+    pub async fn revoke_sessions(&self, account_id: u32) -> trc::Result<()> {
+        let mut state = self
+            .password_state(account_id)
+            .await?
+            .unwrap_or_else(|| PasswordState {
+                changed: now(),
+                ..Default::default()
+            });
+        state.revoked_at = now();
+
+        let mut batch = BatchBuilder::new();
+        batch.set(
+            ValueClass::Directory(DirectoryClass::PasswordState(MaybeDynamicId::Static(
+                account_id,
+            ))),
+            (&state).serialize(),
+        );
+        self.store()
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+
+        self.inner
+            .data
+            .http_auth_cache
+            .retain(|_, id| id.item != account_id);
+        self.inner.data.access_tokens.remove(&account_id);
+
+        Ok(())
+    }
+
+    /// Returns `true` if the sessions of an account were revoked at or after the given time.
+    pub async fn is_session_revoked(&self, account_id: u32, issued_at: u64) -> trc::Result<bool> {
+        if account_id != u32::MAX {
+            self.password_state(account_id)
+                .await
+                .map(|state| state.is_some_and(|state| state.revoked_at >= issued_at))
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn password_state(&self, account_id: u32) -> trc::Result<Option<PasswordState>> {
+        self.store()
+            .get_value::<PasswordState>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::PasswordState(account_id),
+            )))
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn account_principal(&self, account_id: u32) -> trc::Result<Principal> {
+        self.store()
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| manage::not_found(account_id))
+    }
+
+    async fn update_account_secrets(
+        &self,
+        access_token: &AccessToken,
+        changes: Vec<PrincipalUpdate>,
+    ) -> trc::Result<()> {
+        self.store()
+            .update_principal(
+                UpdatePrincipal::by_id(access_token.primary_id())
+                    .with_updates(changes)
+                    .with_tenant(access_token.tenant.map(|t| t.id)),
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        self.inner
+            .data
+            .http_auth_cache
+            .retain(|_, id| id.item != access_token.primary_id());
+
+        Ok(())
+    }
+
+    fn recovery_code_updates(&self) -> (Vec<String>, Vec<PrincipalUpdate>) {
+        let codes = (0..self.core.jmap.self_service.recovery_codes)
+            .map(|_| generate_recovery_code())
+            .collect::<Vec<_>>();
+        let mut changes = vec![PrincipalUpdate::remove_item(
+            PrincipalField::Secrets,
+            PrincipalValue::String("$recovery$".to_string()),
+        )];
+        changes.extend(codes.iter().map(|code| {
+            PrincipalUpdate::add_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(format!("$recovery${}", hash_recovery_code(code))),
+            )
+        }));
+
+        (codes, changes)
+    }
+}
+
+fn totp_key(account_id: u32) -> Vec<u8> {
+    format!("totp:{account_id}").into_bytes()
+}
@@ -0,0 +1,817 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+    },
+    Permission, Principal, QueryBy,
+};
+use ring::signature::{
+    RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ED25519,
+    RSA_PKCS1_2048_8192_SHA256,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use store::rand::{thread_rng, Rng};
+use trc::AddContext;
+use utils::config::Config;
+
+use crate::Server;
+
+use super::AccessToken;
+
+pub const WEBAUTHN_PREFIX: &str = "$webauthn$";
+pub const ALG_ES256: i64 = -7;
+pub const ALG_EDDSA: i64 = -8;
+pub const ALG_RS256: i64 = -257;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_DATA: u8 = 0x40;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct WebAuthnConfig {
+    pub rp_id: Option<String>,
+    pub rp_name: String,
+    pub origins: Vec<String>,
+    pub require_user_verification: bool,
+    pub challenge_expiry: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+    pub origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebAuthnCredential {
+    pub name: String,
+    pub id: String,
+    pub public_key: PublicKey,
+    pub counter: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    Es256(Vec<u8>),
+    EdDsa(Vec<u8>),
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssertionCredential {
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(rename = "userHandle")]
+    #[serde(default)]
+    pub user_handle: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    typ: String,
+    challenge: String,
+    origin: String,
+    #[serde(rename = "crossOrigin")]
+    #[serde(default)]
+    cross_origin: bool,
+}
+
+struct AuthenticatorData<'x> {
+    rp_id_hash: &'x [u8],
+    flags: u8,
+    counter: u32,
+    credential: Option<(&'x [u8], Cbor)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cbor {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Bool(bool),
+    Null,
+    Float,
+}
+
+impl WebAuthnConfig {
+    pub fn parse(config: &mut Config) -> Self {
+        WebAuthnConfig {
+            rp_id: config
+                .value("authentication.webauthn.rp-id")
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string()),
+            rp_name: config
+                .value("authentication.webauthn.rp-name")
+                .unwrap_or("Stalwart Mail Server")
+                .to_string(),
+            origins: config
+                .values("authentication.webauthn.origins")
+                .map(|(_, origin)| origin.trim_end_matches('/').to_string())
+                .collect(),
+            require_user_verification: config
+                .property_or_default("authentication.webauthn.user-verification", "false")
+                .unwrap_or(false),
+            challenge_expiry: config
+                .property_or_default("authentication.webauthn.challenge-expiry", "5m")
+                .unwrap_or_else(|| Duration::from_secs(5 * 60)),
+        }
+    }
+
+    /// Builds the relying party, defaulting to the host and origin of the server URL.
+    pub fn relying_party(&self, base_url: &str) -> RelyingParty {
+        let base_url = base_url.trim_end_matches('/');
+        RelyingParty {
+            id: self.rp_id.clone().unwrap_or_else(|| {
+                let host = base_url
+                    .split_once("://")
+                    .map_or(base_url, |(_, host)| host)
+                    .split('/')
+                    .next()
+                    .unwrap_or_default();
+                host.rsplit_once(':')
+                    .filter(|(_, port)| port.as_bytes().iter().all(|b| b.is_ascii_digit()))
+                    .map_or(host, |(host, _)| host)
+                    .to_string()
+            }),
+            name: self.rp_name.clone(),
+            origins: if !self.origins.is_empty() {
+                self.origins.clone()
+            } else {
+                vec![base_url.to_string()]
+            },
+        }
+    }
+}
+
+impl Default for WebAuthnConfig {
+    fn default() -> Self {
+        WebAuthnConfig {
+            rp_id: None,
+            rp_name: "Stalwart Mail Server".to_string(),
+            origins: vec![],
+            require_user_verification: false,
+            challenge_expiry: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl WebAuthnCredential {
+    pub fn parse(secret: &str) -> Option<Self> {
+        let mut parts = secret.strip_prefix(WEBAUTHN_PREFIX)?.split('$');
+        let name = parts.next()?.to_string();
+        let id = parts.next()?.to_string();
+        let alg = parts.next()?;
+        let key = parts.next()?;
+        let counter = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let public_key = match alg {
+            "es256" => PublicKey::Es256(decode_b64(key)?),
+            "ed25519" => PublicKey::EdDsa(decode_b64(key)?),
+            "rs256" => {
+                let (n, e) = key.split_once('.')?;
+                PublicKey::Rs256 {
+                    n: decode_b64(n)?,
+                    e: decode_b64(e)?,
+                }
+            }
+            _ => return None,
+        };
+
+        Some(WebAuthnCredential {
+            name,
+            id,
+            public_key,
+            counter,
+        })
+    }
+
+    pub fn to_secret(&self) -> String {
+        let (alg, key) = match &self.public_key {
+            PublicKey::Es256(key) => ("es256", URL_SAFE_NO_PAD.encode(key)),
+            PublicKey::EdDsa(key) => ("ed25519", URL_SAFE_NO_PAD.encode(key)),
+            PublicKey::Rs256 { n, e } => (
+                "rs256",
+                format!(
+                    "{}.{}",
+                    URL_SAFE_NO_PAD.encode(n),
+                    URL_SAFE_NO_PAD.encode(e)
+                ),
+            ),
+        };
+
+        format!(
+            "{WEBAUTHN_PREFIX}{}${}${alg}${key}${}",
+            self.name, self.id, self.counter
+        )
+    }
+}
+
+impl PublicKey {
+    fn from_cose(key: &Cbor) -> Result<Self, &'static str> {
+        let alg = key.get(3).and_then(|v| v.as_integer());
+        let kty = key.get(1).and_then(|v| v.as_integer());
+        let bytes = |label: i64| key.get(label).and_then(|v| v.as_bytes());
+
+        match (kty, alg) {
+            (Some(2), Some(ALG_ES256)) => {
+                match (
+                    key.get(-1).and_then(|v| v.as_integer()),
+                    bytes(-2),
+                    bytes(-3),
+                ) {
+                    (Some(1), Some(x), Some(y)) if x.len() == 32 && y.len() == 32 => {
+                        let mut point = Vec::with_capacity(65);
+                        point.push(0x04);
+                        point.extend_from_slice(x);
+                        point.extend_from_slice(y);
+                        Ok(PublicKey::Es256(point))
+                    }
+                    _ => Err("Invalid P-256 public key"),
+                }
+            }
+            (Some(1), Some(ALG_EDDSA)) => {
+                match (key.get(-1).and_then(|v| v.as_integer()), bytes(-2)) {
+                    (Some(6), Some(x)) if x.len() == 32 => Ok(PublicKey::EdDsa(x.to_vec())),
+                    _ => Err("Invalid Ed25519 public key"),
+                }
+            }
+            (Some(3), Some(ALG_RS256)) => match (bytes(-1), bytes(-2)) {
+                (Some(n), Some(e)) if n.len() >= 256 && !e.is_empty() => Ok(PublicKey::Rs256 {
+                    n: n.to_vec(),
+                    e: e.to_vec(),
+                }),
+                _ => Err("Invalid RSA public key"),
+            },
+            _ => Err("Unsupported public key algorithm"),
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            PublicKey::Es256(key) => UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, key)
+                .verify(message, signature)
+                .is_ok(),
+            PublicKey::EdDsa(key) => UnparsedPublicKey::new(&ED25519, key)
+                .verify(message, signature)
+                .is_ok(),
+            PublicKey::Rs256 { n, e } => RsaPublicKeyComponents { n, e }
+                .verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok(),
+        }
+    }
+}
+
+impl Server {
+    /// Issues a single-use challenge, optionally bound to an account.
+    pub async fn webauthn_challenge(&self, account_id: Option<u32>) -> trc::Result<String> {
+        let challenge = URL_SAFE_NO_PAD.encode(thread_rng().gen::<[u8; 32]>());
+        self.lookup_store()
+            .key_set(
+                challenge_key(&challenge),
+                account_id.unwrap_or(u32::MAX).to_string().into_bytes(),
+                self.core.jmap.webauthn.challenge_expiry.as_secs().into(),
+            )
+            .await
+            .caused_by(trc::location!())
+            .map(|_| challenge)
+    }
+
+    /// Verifies a passkey attestation and adds the credential to the account.
+    pub async fn register_webauthn_credential(
+        &self,
+        rp: &RelyingParty,
+        account_id: u32,
+        name: String,
+        credential: &RegistrationCredential,
+    ) -> trc::Result<WebAuthnCredential> {
+        if name.is_empty() || name.len() > MAX_NAME_LEN || name.contains('$') {
+            return Err(registration_error("Invalid passkey name"));
+        }
+
+        // Validate client data
+        let client_data_json = decode_b64(&credential.response.client_data_json)
+            .ok_or_else(|| registration_error("Invalid client data encoding"))?;
+        let client_data = parse_client_data(rp, &client_data_json, "webauthn.create")
+            .map_err(registration_error)?;
+        if self
+            .consume_webauthn_challenge(&client_data.challenge)
+            .await?
+            != Some(account_id)
+        {
+            return Err(registration_error("Invalid or expired challenge"));
+        }
+
+        // Parse attestation
+        let attestation = decode_b64(&credential.response.attestation_object)
+            .and_then(|bytes| Cbor::decode(&bytes))
+            .ok_or_else(|| registration_error("Invalid attestation object"))?;
+        let auth_data = attestation
+            .get_text("authData")
+            .and_then(|v| v.as_bytes())
+            .ok_or_else(|| registration_error("Missing authenticator data"))?;
+        let auth_data = AuthenticatorData::parse(auth_data)
+            .ok_or_else(|| registration_error("Invalid authenticator data"))?;
+        self.verify_authenticator_data(rp, &auth_data)
+            .map_err(registration_error)?;
+        let (credential_id, public_key) = auth_data
+            .credential
+            .as_ref()
+            .filter(|_| auth_data.flags & FLAG_ATTESTED_DATA != 0)
+            .ok_or_else(|| registration_error("Missing attested credential data"))?;
+        let credential_id = URL_SAFE_NO_PAD.encode(credential_id);
+        if credential_id != credential.id.trim_end_matches('=') {
+            return Err(registration_error("Credential id mismatch"));
+        }
+        let credential = WebAuthnCredential {
+            name,
+            id: credential_id,
+            public_key: PublicKey::from_cose(public_key).map_err(registration_error)?,
+            counter: auth_data.counter,
+        };
+
+        // Make sure the credential and name are not in use
+        let principal = self.webauthn_principal(account_id).await?;
+        for existing in principal
+            .iter_str(PrincipalField::Secrets)
+            .filter_map(|secret| WebAuthnCredential::parse(secret))
+        {
+            if existing.id == credential.id {
+                return Err(registration_error("Passkey is already registered"));
+            } else if existing.name == credential.name {
+                return Err(registration_error(
+                    "A passkey with this name already exists",
+                ));
+            }
+        }
+
+        self.core
+            .storage
+            .data
+            .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+                PrincipalUpdate::add_item(
+                    PrincipalField::Secrets,
+                    PrincipalValue::String(credential.to_secret()),
+                ),
+            ]))
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(credential)
+    }
+
+    /// Verifies a passkey assertion and returns the access token of its owner.
+    pub async fn authenticate_webauthn(
+        &self,
+        rp: &RelyingParty,
+        credential: &AssertionCredential,
+        remote_ip: IpAddr,
+        session_id: u64,
+    ) -> trc::Result<Arc<AccessToken>> {
+        // Validate client data and challenge
+        let response = &credential.response;
+        let client_data_json = decode_b64(&response.client_data_json)
+            .ok_or_else(|| assertion_error("Invalid client data encoding"))?;
+        let client_data =
+            parse_client_data(rp, &client_data_json, "webauthn.get").map_err(assertion_error)?;
+        let bound_id = self
+            .consume_webauthn_challenge(&client_data.challenge)
+            .await?
+            .ok_or_else(|| assertion_error("Invalid or expired challenge"))?;
+
+        // Resolve account from the challenge or the user handle
+        let user_id = response
+            .user_handle
+            .as_deref()
+            .filter(|handle| !handle.is_empty())
+            .map(|handle| {
+                decode_b64(handle)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(u32::from_be_bytes)
+                    .ok_or_else(|| assertion_error("Invalid user handle"))
+            })
+            .transpose()?;
+        let account_id = match (bound_id, user_id) {
+            (u32::MAX, Some(user_id)) => user_id,
+            (bound_id, None) if bound_id != u32::MAX => bound_id,
+            (bound_id, Some(user_id)) if bound_id == user_id => bound_id,
+            _ => return Err(assertion_error("Unable to determine account")),
+        };
+        let principal = self
+            .core
+            .storage
+            .data
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| assertion_error("Unknown account"))?;
+        let login = principal.name().to_string();
+        if self.is_account_locked(&login).await? {
+            return Err(trc::AuthEvent::AccountLocked
+                .ctx(trc::Key::RemoteIp, remote_ip)
+                .ctx(trc::Key::AccountName, login));
+        }
+
+        // Verify signature
+        let result = principal
+            .iter_str(PrincipalField::Secrets)
+            .filter_map(|secret| WebAuthnCredential::parse(secret))
+            .find(|stored| stored.id == credential.id.trim_end_matches('='))
+            .ok_or("Unknown credential")
+            .and_then(|stored| {
+                let auth_data_bytes =
+                    decode_b64(&response.authenticator_data).ok_or("Invalid authenticator data")?;
+                let auth_data = AuthenticatorData::parse(&auth_data_bytes)
+                    .ok_or("Invalid authenticator data")?;
+                self.verify_authenticator_data(rp, &auth_data)?;
+                let signature = decode_b64(&response.signature).ok_or("Invalid signature")?;
+                let mut message = auth_data_bytes.clone();
+                message.extend_from_slice(&Sha256::digest(&client_data_json));
+                if !stored.public_key.verify(&message, &signature) {
+                    Err("Invalid signature")
+                } else if (stored.counter != 0 || auth_data.counter != 0)
+                    && auth_data.counter <= stored.counter
+                {
+                    Err("Signature counter did not increase, authenticator may be cloned")
+                } else {
+                    Ok((stored, auth_data.counter))
+                }
+            });
+
+        let (stored, counter) = match result {
+            Ok(result) => result,
+            Err(reason) => {
+                return Err(if self.record_login_failure(&login).await? {
+                    trc::AuthEvent::AccountLocked
+                        .ctx(trc::Key::RemoteIp, remote_ip)
+                        .ctx(trc::Key::AccountName, login)
+                } else {
+                    trc::AuthEvent::Failed
+                        .ctx(trc::Key::RemoteIp, remote_ip)
+                        .ctx(trc::Key::AccountName, login)
+                        .reason(reason)
+                });
+            }
+        };
+
+        // Store the updated signature counter
+        if counter != stored.counter {
+            self.core
+                .storage
+                .data
+                .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+                    PrincipalUpdate::remove_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(stored.to_secret()),
+                    ),
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(
+                            WebAuthnCredential { counter, ..stored }.to_secret(),
+                        ),
+                    ),
+                ]))
+                .await
+                .caused_by(trc::location!())?;
+        }
+        self.reset_login_failures(&login).await?;
+
+        trc::event!(
+            Auth(trc::AuthEvent::Success),
+            AccountName = login,
+            AccountId = account_id,
+            SpanId = session_id,
+        );
+
+        let access_token = self.get_cached_access_token(account_id).await?;
+        access_token
+            .assert_has_permission(Permission::Authenticate)
+            .map(|_| access_token)
+    }
+
+    /// Returns a stable fake credential id for usernames without passkeys,
+    /// so that challenge responses do not reveal which accounts exist.
+    pub fn webauthn_decoy_credential(&self, username: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.core.oauth.oauth_key.as_bytes());
+        hasher.update(b"webauthn-decoy");
+        hasher.update(username.to_lowercase().as_bytes());
+        URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+
+    /// Returns the passkeys registered to an account.
+    pub async fn webauthn_credentials(
+        &self,
+        account_id: u32,
+    ) -> trc::Result<Vec<WebAuthnCredential>> {
+        self.webauthn_principal(account_id).await.map(|principal| {
+            principal
+                .iter_str(PrincipalField::Secrets)
+                .filter_map(|secret| WebAuthnCredential::parse(secret))
+                .collect()
+        })
+    }
+
+    /// Returns whether a secret is one of the app passwords of an account,
+    /// app passwords are not protected by 2FA.
+    pub async fn is_app_password_login(&self, account_id: u32, secret: &str) -> trc::Result<bool> {
+        self.webauthn_principal(account_id)
+            .await?
+            .matches_app_password(secret)
+            .await
+    }
+
+    async fn webauthn_principal(&self, account_id: u32) -> trc::Result<Principal> {
+        self.core
+            .storage
+            .data
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| manage::not_found(account_id))
+    }
+
+    async fn consume_webauthn_challenge(&self, challenge: &str) -> trc::Result<Option<u32>> {
+        let key = challenge_key(challenge);
+        let Some(account_id) = self
+            .lookup_store()
+            .key_get::<String>(key.clone())
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(None);
+        };
+
+        // Atomically claim the challenge so that concurrent requests cannot reuse it
+        let claims = self
+            .lookup_store()
+            .counter_incr(
+                challenge_claim_key(challenge),
+                1,
+                self.core.jmap.webauthn.challenge_expiry.as_secs().into(),
+                true,
+            )
+            .await
+            .caused_by(trc::location!())?;
+        if claims != 1 {
+            return Ok(None);
+        }
+
+        self.lookup_store()
+            .key_delete(key)
+            .await
+            .caused_by(trc::location!())?;
+        Ok(account_id.parse().ok())
+    }
+
+    fn verify_authenticator_data(
+        &self,
+        rp: &RelyingParty,
+        auth_data: &AuthenticatorData<'_>,
+    ) -> Result<(), &'static str> {
+        if auth_data.rp_id_hash != Sha256::digest(rp.id.as_bytes()).as_slice() {
+            Err("Relying party id mismatch")
+        } else if auth_data.flags & FLAG_USER_PRESENT == 0 {
+            Err("User presence flag not set")
+        } else if self.core.jmap.webauthn.require_user_verification
+            && auth_data.flags & FLAG_USER_VERIFIED == 0
+        {
+            Err("User verification required")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'x> AuthenticatorData<'x> {
+    fn parse(bytes: &'x [u8]) -> Option<Self> {
+        let rp_id_hash = bytes.get(0..32)?;
+        let flags = *bytes.get(32)?;
+        let counter = u32::from_be_bytes(bytes.get(33..37)?.try_into().ok()?);
+        let credential = if flags & FLAG_ATTESTED_DATA != 0 {
+            // Skip AAGUID
+            let id_len = u16::from_be_bytes(bytes.get(53..55)?.try_into().ok()?) as usize;
+            let id = bytes.get(55..55 + id_len)?;
+            let key = CborReader {
+                bytes,
+                pos: 55 + id_len,
+            }
+            .read_value(0)?;
+            Some((id, key))
+        } else {
+            None
+        };
+
+        Some(AuthenticatorData {
+            rp_id_hash,
+            flags,
+            counter,
+            credential,
+        })
+    }
+}
+
+fn parse_client_data(
+    rp: &RelyingParty,
+    bytes: &[u8],
+    typ: &str,
+) -> Result<ClientData, &'static str> {
+    let client_data =
+        serde_json::from_slice::<ClientData>(bytes).map_err(|_| "Invalid client data")?;
+    if client_data.typ != typ {
+        Err("Invalid client data type")
+    } else if client_data.cross_origin
+        || !rp
+            .origins
+            .iter()
+            .any(|origin| origin == client_data.origin.trim_end_matches('/'))
+    {
+        Err("Origin not allowed")
+    } else {
+        Ok(client_data)
+    }
+}
+
+fn challenge_key(challenge: &str) -> Vec<u8> {
+    format!("wa:{challenge}").into_bytes()
+}
+
+fn challenge_claim_key(challenge: &str) -> Vec<u8> {
+    format!("wc:{challenge}").into_bytes()
+}
+
+fn decode_b64(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()
+}
+
+fn registration_error(reason: &'static str) -> trc::Error {
+    manage::error("Passkey registration failed", reason.into())
+}
+
+fn assertion_error(reason: &'static str) -> trc::Error {
+    trc::AuthEvent::Failed.into_err().reason(reason)
+}
+
+impl Cbor {
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        CborReader { bytes, pos: 0 }.read_value(0)
+    }
+
+    fn get(&self, label: i64) -> Option<&Cbor> {
+        match self {
+            Cbor::Map(items) => items
+                .iter()
+                .find(|(key, _)| matches!(key, Cbor::Integer(key) if *key == label))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn get_text(&self, label: &str) -> Option<&Cbor> {
+        match self {
+            Cbor::Map(items) => items
+                .iter()
+                .find(|(key, _)| matches!(key, Cbor::Text(key) if key == label))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Cbor::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Cbor::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+struct CborReader<'x> {
+    bytes: &'x [u8],
+    pos: usize,
+}
+
+impl<'x> CborReader<'x> {
+    fn read_bytes(&mut self, len: usize) -> Option<&'x [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    fn read_argument(&mut self, info: u8) -> Option<u64> {
+        match info {
+            0..=23 => Some(info as u64),
+            24 => self.read_bytes(1).map(|b| b[0] as u64),
+            25 => self
+                .read_bytes(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as u64),
+            26 => self
+                .read_bytes(4)
+                .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64),
+            27 => self
+                .read_bytes(8)
+                .and_then(|b| b.try_into().ok())
+                .map(u64::from_be_bytes),
+            // Indefinite lengths are not used by authenticators
+            _ => None,
+        }
+    }
+
+    fn read_value(&mut self, depth: usize) -> Option<Cbor> {
+        if depth > 16 {
+            return None;
+        }
+
+        let byte = *self.read_bytes(1)?.first()?;
+        let (major, info) = (byte >> 5, byte & 0x1f);
+        if major == 7 {
+            return match info {
+                20 => Some(Cbor::Bool(false)),
+                21 => Some(Cbor::Bool(true)),
+                22 | 23 => Some(Cbor::Null),
+                25 => self.read_bytes(2).map(|_| Cbor::Float),
+                26 => self.read_bytes(4).map(|_| Cbor::Float),
+                27 => self.read_bytes(8).map(|_| Cbor::Float),
+                _ => None,
+            };
+        }
+
+        let arg = self.read_argument(info)?;
+        match major {
+            0 => i64::try_from(arg).ok().map(Cbor::Integer),
+            1 => i64::try_from(arg).ok().map(|v| Cbor::Integer(-1 - v)),
+            2 => self
+                .read_bytes(usize::try_from(arg).ok()?)
+                .map(|b| Cbor::Bytes(b.to_vec())),
+            3 => self
+                .read_bytes(usize::try_from(arg).ok()?)
+                .and_then(|b| String::from_utf8(b.to_vec()).ok())
+                .map(Cbor::Text),
+            4 => {
+                let mut items = Vec::with_capacity((arg as usize).min(32));
+                for _ in 0..arg {
+                    items.push(self.read_value(depth + 1)?);
+                }
+                Some(Cbor::Array(items))
+            }
+            5 => {
+                let mut items = Vec::with_capacity((arg as usize).min(32));
+                for _ in 0..arg {
+                    items.push((self.read_value(depth + 1)?, self.read_value(depth + 1)?));
+                }
+                Some(Cbor::Map(items))
+            }
+            // Tags are ignored
+            6 => self.read_value(depth + 1),
+            _ => None,
+        }
+    }
+}
@@ -9,7 +9,7 @@ use jmap_proto::{
     request::capability::{
         BlobCapabilities, Capabilities, Capability, CoreCapabilities, EmptyCapabilities,
         MailCapabilities, SieveAccountCapabilities, SieveSessionCapabilities,
-        SubmissionCapabilities,
+        SubmissionCapabilities, WebPushVapidCapabilities,
     },
     types::type_state::DataType,
 };
@@ -162,9 +162,42 @@ impl JmapConfig {
                     DataType::SieveScript,
                 ],
                 supported_digest_algorithms: vec!["sha", "sha-256", "sha-512"],
+                supported_convert_types: vec!["image/jpeg", "image/webp"],
             }),
         );
 
+        // Add Web Push VAPID capabilities
+        if let Some(vapid) = &self.push_vapid {
+            self.capabilities.session.append(
+                Capability::WebPushVapid,
+                Capabilities::WebPushVapid(WebPushVapidCapabilities {
+                    application_server_key: vapid.public_key.clone(),
+                }),
+            );
+        }
+
+        // Add S/MIME verification capabilities
+        if self.smime_verify.is_some() {
+            self.capabilities.session.append(
+                Capability::SmimeVerify,
+                Capabilities::Empty(EmptyCapabilities::default()),
+            );
+            self.capabilities.account.append(
+                Capability::SmimeVerify,
+                Capabilities::Empty(EmptyCapabilities::default()),
+            );
+        }
+
+        // Add Principals capabilities
+        self.capabilities.session.append(
+            Capability::Principals,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Principals,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
         // Add Quota capabilities
         self.capabilities.session.append(
             Capability::Quota,
@@ -5,4 +5,6 @@
  */
 
 pub mod capabilities;
+pub mod push;
 pub mod settings;
+pub mod smime;
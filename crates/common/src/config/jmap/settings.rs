@@ -11,6 +11,15 @@ use mail_parser::HeaderName;
 use nlp::language::Language;
 use utils::config::{cron::SimpleCron, utils::ParseValue, Config, Rate};
 
+use super::{push::VapidKey, smime::SmimeVerifyConfig};
+use crate::auth::{
+    kerberos::KerberosConfig,
+    lifecycle::AccountLifecycle,
+    password::{AccountLockout, PasswordPolicy},
+    self_service::SelfServiceConfig,
+    webauthn::WebAuthnConfig,
+};
+
 #[derive(Default, Clone)]
 pub struct JmapConfig {
     pub default_language: Language,
@@ -34,12 +43,18 @@ pub struct JmapConfig {
     pub upload_tmp_quota_amount: usize,
     pub upload_tmp_ttl: u64,
 
+    pub convert_max_size: usize,
+    pub convert_max_dimension: u32,
+
     pub mailbox_max_depth: usize,
     pub mailbox_name_max_len: usize,
     pub mail_attachments_max_size: usize,
     pub mail_parse_max_items: usize,
+    pub mail_import_max_items: usize,
+    pub mail_import_max_size: usize,
     pub mail_max_size: usize,
     pub mail_autoexpunge_after: Option<Duration>,
+    pub mail_snooze_interval: Duration,
 
     pub sieve_max_script_name: usize,
     pub sieve_max_scripts: usize,
@@ -57,6 +72,10 @@ pub struct JmapConfig {
     pub push_timeout: Duration,
     pub push_verify_timeout: Duration,
     pub push_throttle: Duration,
+    pub push_ttl: Duration,
+    pub push_vapid: Option<VapidKey>,
+
+    pub smime_verify: Option<SmimeVerifyConfig>,
 
     pub web_socket_throttle: Duration,
     pub web_socket_timeout: Duration,
@@ -64,10 +83,17 @@ pub struct JmapConfig {
 
     pub fallback_admin: Option<(String, String)>,
     pub master_user: Option<(String, String)>,
+    pub password_policy: PasswordPolicy,
+    pub account_lockout: Option<AccountLockout>,
+    pub account_lifecycle: AccountLifecycle,
+    pub kerberos: Option<KerberosConfig>,
+    pub webauthn: WebAuthnConfig,
+    pub self_service: SelfServiceConfig,
 
     pub spam_header: Option<(HeaderName<'static>, String)>,
     pub default_folders: Vec<DefaultFolder>,
     pub shared_folder: String,
+    pub search_folder: String,
 
     pub http_headers: Vec<(hyper::header::HeaderName, hyper::header::HeaderValue)>,
     pub http_use_forwarded: bool,
@@ -78,6 +104,8 @@ pub struct JmapConfig {
     pub capabilities: BaseCapabilities,
     pub session_purge_frequency: SimpleCron,
     pub account_purge_frequency: SimpleCron,
+    pub quota_recalculate_frequency: Option<SimpleCron>,
+    pub quota_recalculate_repair: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -97,7 +125,9 @@ pub enum SpecialUse {
     Drafts,
     Archive,
     Sent,
+    Snoozed,
     Shared,
+    Searches,
     None,
 }
 
@@ -136,6 +166,7 @@ impl JmapConfig {
         // Parse default folders
         let mut default_folders = Vec::new();
         let mut shared_folder = "Shared Folders".to_string();
+        let mut search_folder = "Saved Searches".to_string();
         for key in config
             .sub_keys("jmap.folders", ".name")
             .map(|v| v.to_string())
@@ -147,6 +178,15 @@ impl JmapConfig {
                         shared_folder = value.to_string();
                     }
                 }
+                Ok(SpecialUse::Searches) => {
+                    if let Some(value) = config
+                        .value(("jmap.folders", key.as_str(), "name"))
+                        .map(|name| name.trim())
+                        .filter(|name| !name.is_empty())
+                    {
+                        search_folder = value.to_string();
+                    }
+                }
                 Ok(special_use) => {
                     let subscribe = config
                         .property_or_default(("jmap.folders", key.as_str(), "subscribe"), "true")
@@ -282,6 +322,12 @@ impl JmapConfig {
                 .property_or_default::<Duration>("jmap.protocol.upload.ttl", "1h")
                 .unwrap_or_else(|| Duration::from_secs(3600))
                 .as_secs(),
+            convert_max_size: config
+                .property("jmap.protocol.convert.max-size")
+                .unwrap_or(20000000),
+            convert_max_dimension: config
+                .property("jmap.protocol.convert.max-dimension")
+                .unwrap_or(1024),
             mailbox_max_depth: config.property("jmap.mailbox.max-depth").unwrap_or(10),
             mailbox_name_max_len: config
                 .property("jmap.mailbox.max-name-length")
@@ -291,9 +337,18 @@ impl JmapConfig {
                 .unwrap_or(50000000),
             mail_max_size: config.property("jmap.email.max-size").unwrap_or(75000000),
             mail_parse_max_items: config.property("jmap.email.parse.max-items").unwrap_or(10),
+            mail_import_max_items: config
+                .property("jmap.email.import.max-items")
+                .unwrap_or(100000),
+            mail_import_max_size: config
+                .property("jmap.email.import.max-size")
+                .unwrap_or(2147483648),
             mail_autoexpunge_after: config
                 .property_or_default::<Option<Duration>>("jmap.email.auto-expunge", "30d")
                 .unwrap_or_default(),
+            mail_snooze_interval: config
+                .property_or_default("jmap.email.snooze.interval", "1m")
+                .unwrap_or_else(|| Duration::from_secs(60)),
             sieve_max_script_name: config
                 .property("sieve.untrusted.limits.name-length")
                 .unwrap_or(512),
@@ -367,12 +422,22 @@ impl JmapConfig {
             push_throttle: config
                 .property_or_default("jmap.push.throttle", "1s")
                 .unwrap_or_else(|| Duration::from_secs(1)),
+            push_ttl: config
+                .property_or_default("jmap.push.ttl", "1d")
+                .unwrap_or_else(|| Duration::from_secs(86400)),
+            push_vapid: VapidKey::parse(config),
+            smime_verify: SmimeVerifyConfig::parse(config),
             session_purge_frequency: config
                 .property_or_default::<SimpleCron>("jmap.session.purge.frequency", "15 * *")
                 .unwrap_or_else(|| SimpleCron::parse_value("15 * *").unwrap()),
             account_purge_frequency: config
                 .property_or_default::<SimpleCron>("jmap.account.purge.frequency", "0 0 *")
                 .unwrap_or_else(|| SimpleCron::parse_value("0 0 *").unwrap()),
+            quota_recalculate_frequency: config
+                .property::<SimpleCron>("jmap.quota.recalculate.frequency"),
+            quota_recalculate_repair: config
+                .property_or_default("jmap.quota.recalculate.repair", "false")
+                .unwrap_or(false),
             fallback_admin: config
                 .value("authentication.fallback-admin.user")
                 .and_then(|u| {
@@ -385,8 +450,15 @@ impl JmapConfig {
                     .value("authentication.master.secret")
                     .map(|p| (u.to_string(), p.to_string()))
             }),
+            password_policy: PasswordPolicy::parse(config),
+            account_lockout: AccountLockout::parse(config),
+            account_lifecycle: AccountLifecycle::parse(config),
+            kerberos: KerberosConfig::parse(config),
+            webauthn: WebAuthnConfig::parse(config),
+            self_service: SelfServiceConfig::parse(config),
             default_folders,
             shared_folder,
+            search_folder,
         };
 
         // Add capabilities
@@ -404,7 +476,9 @@ impl ParseValue for SpecialUse {
             "drafts" => Ok(SpecialUse::Drafts),
             "archive" => Ok(SpecialUse::Archive),
             "sent" => Ok(SpecialUse::Sent),
+            "snoozed" => Ok(SpecialUse::Snoozed),
             "shared" => Ok(SpecialUse::Shared),
+            "searches" => Ok(SpecialUse::Searches),
             //"none" => Ok(SpecialUse::None),
             other => Err(format!("Unknown folder role {other:?}")),
         }
@@ -0,0 +1,110 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{io::Cursor, sync::Arc, time::Duration};
+
+use jmap_proto::types::{date::UTCDate, value::Value};
+use rustls_pemfile::certs;
+use utils::config::Config;
+
+// S/MIME signature verification settings (RFC 9219)
+#[derive(Debug, Clone)]
+pub struct SmimeVerifyConfig {
+    pub trust_store: Arc<Vec<Vec<u8>>>,
+    pub verify_at_delivery: bool,
+    pub cache_expiry: Duration,
+}
+
+impl SmimeVerifyConfig {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default("jmap.smime.enable", "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        // Parse trusted root certificates
+        let mut trust_store = Vec::new();
+        for (key, pem) in config
+            .values("jmap.smime.trust-store")
+            .map(|(key, pem)| (key.to_string(), pem.to_string()))
+            .collect::<Vec<_>>()
+        {
+            match certs(&mut Cursor::new(pem.as_bytes())).collect::<Result<Vec<_>, _>>() {
+                Ok(certs) if !certs.is_empty() => {
+                    trust_store.extend(certs.into_iter().map(|cert| cert.as_ref().to_vec()));
+                }
+                Ok(_) => {
+                    config.new_parse_error(key, "No certificates found.");
+                }
+                Err(err) => {
+                    config.new_parse_error(key, format!("Failed to read certificates: {err}"));
+                }
+            }
+        }
+
+        Some(SmimeVerifyConfig {
+            trust_store: Arc::new(trust_store),
+            verify_at_delivery: config
+                .property_or_default("jmap.smime.verify-at-delivery", "true")
+                .unwrap_or(true),
+            cache_expiry: config
+                .property_or_default("jmap.smime.cache-expiry", "1d")
+                .unwrap_or_else(|| Duration::from_secs(86400)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SmimeStatus {
+    Unknown,
+    SignedVerified,
+    SignedFailed,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SmimeVerification {
+    pub status: SmimeStatus,
+    pub errors: Vec<String>,
+    pub verified_at: u64,
+}
+
+impl SmimeVerification {
+    pub fn is_verified(&self) -> bool {
+        self.status == SmimeStatus::SignedVerified
+    }
+
+    pub fn status(&self) -> Value {
+        Value::Text(self.status.as_str().to_string())
+    }
+
+    pub fn errors(&self) -> Value {
+        if !self.errors.is_empty() {
+            Value::List(self.errors.iter().cloned().map(Value::Text).collect())
+        } else {
+            Value::Null
+        }
+    }
+
+    pub fn verified_at(&self) -> Value {
+        if self.status != SmimeStatus::Unknown {
+            Value::Date(UTCDate::from_timestamp(self.verified_at as i64))
+        } else {
+            Value::Null
+        }
+    }
+}
+
+impl SmimeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmimeStatus::Unknown => "unknown",
+            SmimeStatus::SignedVerified => "signed/verified",
+            SmimeStatus::SignedFailed => "signed/failed",
+        }
+    }
+}
@@ -43,6 +43,10 @@ pub(crate) const CONNECTION_VARS: &[u32; 7] = &[
     V_TLS,
 ];
 
+#[cfg(feature = "enterprise")]
+pub(crate) const SHARD_VARS: &[u32; 4] =
+    &[V_ACCOUNT_NAME, V_ACCOUNT_DOMAIN, V_ACCOUNT_TYPE, V_TENANT];
+
 impl Core {
     pub async fn parse(
         config: &mut Config,
@@ -188,6 +192,12 @@ impl Core {
                 lookups: stores.lookup_stores,
                 blobs: stores.blob_stores,
                 ftss: stores.fts_stores,
+                #[cfg(feature = "enterprise")]
+                shard: crate::expr::if_block::IfBlock::try_parse(
+                    config,
+                    "storage.shard",
+                    &crate::expr::tokenizer::TokenMap::default().with_variables(SHARD_VARS),
+                ),
             },
         }
     }
@@ -899,6 +899,7 @@ impl ParseValue for Mechanism {
             "PLAIN" => AUTH_PLAIN,
             "XOAUTH2" => AUTH_XOAUTH2,
             "OAUTHBEARER" => AUTH_OAUTHBEARER,
+            "GSSAPI" => AUTH_GSSAPI,
             /*"SCRAM-SHA-256-PLUS" => AUTH_SCRAM_SHA_256_PLUS,
             "SCRAM-SHA-256" => AUTH_SCRAM_SHA_256,
             "SCRAM-SHA-1-PLUS" => AUTH_SCRAM_SHA_1_PLUS,
@@ -918,7 +919,6 @@ impl ParseValue for Mechanism {
             "GS2-KRB5" => AUTH_GS2_KRB5,
             "GS2-KRB5-PLUS" => AUTH_GS2_KRB5_PLUS,
             "GSS-SPNEGO" => AUTH_GSS_SPNEGO,
-            "GSSAPI" => AUTH_GSSAPI,
             "KERBEROS_V4" => AUTH_KERBEROS_V4,
             "KERBEROS_V5" => AUTH_KERBEROS_V5,
             "NMAS-SAMBA-AUTH" => AUTH_NMAS_SAMBA_AUTH,
@@ -977,7 +977,8 @@ impl ConstantValue for Mechanism {
             .add_constant("login", Mechanism(AUTH_LOGIN))
             .add_constant("plain", Mechanism(AUTH_PLAIN))
             .add_constant("xoauth2", Mechanism(AUTH_XOAUTH2))
-            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER));
+            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER))
+            .add_constant("gssapi", Mechanism(AUTH_GSSAPI));
     }
 }
 
@@ -27,4 +27,7 @@ pub struct Storage {
     pub blobs: AHashMap<String, BlobStore>,
     pub lookups: AHashMap<String, LookupStore>,
     pub ftss: AHashMap<String, FtsStore>,
+
+    #[cfg(feature = "enterprise")]
+    pub shard: Option<crate::expr::if_block::IfBlock>,
 }
@@ -11,6 +11,7 @@
 pub mod alerts;
 pub mod config;
 pub mod license;
+pub mod shard;
 pub mod undelete;
 
 use std::time::Duration;
@@ -0,0 +1,144 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: LicenseRef-SEL
+ *
+ * This file is subject to the Stalwart Enterprise License Agreement (SEL) and
+ * is NOT open source software.
+ *
+ */
+
+use directory::{
+    backend::internal::{lookup::DirectoryStore, PrincipalField},
+    Principal, QueryBy, Type,
+};
+use store::Store;
+use trc::AddContext;
+
+use crate::{
+    expr::{
+        functions::ResolveVariable, Variable, V_ACCOUNT_DOMAIN, V_ACCOUNT_NAME, V_ACCOUNT_TYPE,
+        V_TENANT,
+    },
+    Server,
+};
+
+struct AccountResolver<'x> {
+    name: &'x str,
+    domain: &'x str,
+    typ: Type,
+    tenant: Option<String>,
+}
+
+impl Server {
+    /// Assigns a newly created account to a shard
+    pub async fn assign_principal_shard(&self, account_id: u32) -> trc::Result<()> {
+        if !matches!(self.store(), Store::Sharded(_)) {
+            return Ok(());
+        }
+
+        match self
+            .store()
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+        {
+            Some(principal)
+                if matches!(
+                    principal.typ(),
+                    Type::Individual | Type::Group | Type::Resource | Type::Location
+                ) =>
+            {
+                self.assign_account_shard(&principal).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn assign_account_shard(&self, principal: &Principal) -> trc::Result<()> {
+        let Store::Sharded(store) = self.store() else {
+            return Ok(());
+        };
+        let account_id = principal.id();
+        if store
+            .account_shard_id(account_id)
+            .await
+            .caused_by(trc::location!())?
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        // Evaluate the shard expression
+        let mut shard_id = None;
+        if let Some(if_block) = &self.core.storage.shard {
+            let tenant = if let Some(tenant_id) = principal.tenant() {
+                self.store()
+                    .query(QueryBy::Id(tenant_id), false)
+                    .await
+                    .caused_by(trc::location!())?
+                    .map(|tenant| tenant.name().to_string())
+            } else {
+                None
+            };
+            let name = principal.name();
+            let domain = name
+                .rsplit_once('@')
+                .map(|(_, domain)| domain)
+                .or_else(|| {
+                    principal
+                        .get_str_array(PrincipalField::Emails)
+                        .and_then(|emails| emails.first())
+                        .and_then(|email| email.rsplit_once('@'))
+                        .map(|(_, domain)| domain)
+                })
+                .unwrap_or_default();
+
+            shard_id = self
+                .eval_if::<String, _>(
+                    if_block,
+                    &AccountResolver {
+                        name,
+                        domain,
+                        typ: principal.typ(),
+                        tenant,
+                    },
+                    0,
+                )
+                .await;
+        }
+
+        // Unknown shards fall back to the primary store
+        let primary_id = store.shard_ids().next().unwrap_or_default();
+        let shard_id = match shard_id {
+            Some(shard_id) if store.shard_ids().any(|id| id == shard_id) => shard_id,
+            Some(shard_id) => {
+                trc::error!(trc::StoreEvent::NotFound
+                    .into_err()
+                    .details("Shard not found, assigning account to the primary store")
+                    .id(shard_id)
+                    .account_id(account_id));
+                primary_id.to_string()
+            }
+            None => primary_id.to_string(),
+        };
+
+        store
+            .assign_account(account_id, &shard_id)
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+}
+
+impl ResolveVariable for AccountResolver<'_> {
+    fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        match variable {
+            V_ACCOUNT_NAME => Variable::from(self.name),
+            V_ACCOUNT_DOMAIN => Variable::from(self.domain),
+            V_ACCOUNT_TYPE => Variable::from(self.typ.to_jmap()),
+            V_TENANT => Variable::from(self.tenant.as_deref().unwrap_or_default()),
+            _ => Variable::default(),
+        }
+    }
+}
@@ -35,6 +35,10 @@ pub const V_URL: u32 = 21;
 pub const V_URL_PATH: u32 = 22;
 pub const V_HEADERS: u32 = 23;
 pub const V_METHOD: u32 = 24;
+pub const V_ACCOUNT_NAME: u32 = 25;
+pub const V_ACCOUNT_DOMAIN: u32 = 26;
+pub const V_ACCOUNT_TYPE: u32 = 27;
+pub const V_TENANT: u32 = 28;
 
 pub const VARIABLES_MAP: &[(&str, u32)] = &[
     ("rcpt", V_RECIPIENT),
@@ -62,6 +66,10 @@ pub const VARIABLES_MAP: &[(&str, u32)] = &[
     ("url_path", V_URL_PATH),
     ("headers", V_HEADERS),
     ("method", V_METHOD),
+    ("account", V_ACCOUNT_NAME),
+    ("account_domain", V_ACCOUNT_DOMAIN),
+    ("account_type", V_ACCOUNT_TYPE),
+    ("tenant", V_TENANT),
 ];
 
 use regex::Regex;
@@ -110,6 +110,7 @@ pub struct PushSubscription {
     pub expires: u64,
     pub types: Bitmap<DataType>,
     pub keys: Option<EncryptionKeys>,
+    pub email_summaries: bool,
 }
 
 #[derive(Debug, Clone)]
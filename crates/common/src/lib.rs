@@ -146,6 +146,7 @@ pub struct Account {
     pub mailbox_state: AHashMap<u32, Mailbox>,
     pub state_email: Option<u64>,
     pub state_mailbox: Option<u64>,
+    pub state_search: Option<u64>,
 }
 
 #[derive(Debug, Default, Clone)]
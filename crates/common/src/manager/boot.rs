@@ -19,7 +19,7 @@ use utils::{
 };
 
 use crate::{
-    config::{server::Listeners, telemetry::Telemetry},
+    config::{jmap::push::VapidKey, server::Listeners, telemetry::Telemetry},
     ipc::{DeliveryEvent, HousekeeperEvent, QueueEvent, ReportingEvent, StateEvent},
     Core, Data, Inner, Ipc, IPC_CHANNEL_BUFFER,
 };
@@ -229,6 +229,18 @@ impl BootManager {
                     )));
                 }
 
+                // Generate a VAPID key for Web Push if missing
+                if config
+                    .value("jmap.push.vapid.private-key")
+                    .filter(|v| !v.is_empty())
+                    .is_none()
+                {
+                    insert_keys.push(ConfigKey::from((
+                        "jmap.push.vapid.private-key",
+                        VapidKey::generate_pem(),
+                    )));
+                }
+
                 // Download SPAM filters if missing
                 if config
                     .value("version.spam-filter")
@@ -234,6 +234,18 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                                             .expect("Failed to read principal id"),
                                     ),
                                 },
+                                7 => DirectoryClass::Shard(
+                                    key.get(1..)
+                                        .expect("Failed to read principal id")
+                                        .deserialize_leb128()
+                                        .expect("Failed to read principal id"),
+                                ),
+                                8 => DirectoryClass::PasswordState(MaybeDynamicId::Static(
+                                    key.get(1..)
+                                        .expect("Failed to read principal id")
+                                        .deserialize_leb128::<u32>()
+                                        .expect("Failed to read principal id"),
+                                )),
 
                                 _ => failed("Invalid directory key"),
                             };
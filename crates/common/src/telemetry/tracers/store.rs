@@ -373,6 +373,7 @@ impl StoreTracer {
                         MessageIngestEvent::Ham
                             | MessageIngestEvent::Spam
                             | MessageIngestEvent::Duplicate
+                            | MessageIngestEvent::Forward
                             | MessageIngestEvent::Error
                     ) | EventType::Smtp(_)
                         | EventType::Delivery(_)
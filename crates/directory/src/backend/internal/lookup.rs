@@ -6,14 +6,17 @@
 
 use mail_send::Credentials;
 use store::{
-    write::{DirectoryClass, ValueClass},
-    Deserialize, IterateParams, Store, ValueKey,
+    write::{
+        assert::{AssertValue, HashedValue},
+        now, BatchBuilder, DirectoryClass, MaybeDynamicId, ValueClass,
+    },
+    Deserialize, IterateParams, Serialize, Store, ValueKey,
 };
 use trc::AddContext;
 
-use crate::{Principal, QueryBy, Type};
+use crate::{core::secret::SecretMatch, Principal, QueryBy, Type};
 
-use super::{manage::ManageDirectory, PrincipalField, PrincipalInfo};
+use super::{manage::ManageDirectory, PasswordState, PrincipalField, PrincipalInfo};
 
 #[allow(async_fn_in_trait)]
 pub trait DirectoryStore: Sync + Send {
@@ -54,10 +57,43 @@ impl DirectoryStore for Store {
         };
 
         if let Some(account_id) = account_id {
-            if let Some(mut principal) = self.get_principal(account_id).await? {
+            if let Some(principal) = self
+                .get_value::<HashedValue<Principal>>(ValueKey::from(ValueClass::Directory(
+                    DirectoryClass::Principal(account_id),
+                )))
+                .await
+                .caused_by(trc::location!())?
+            {
+                let principal_hash = principal.hash;
+                let mut principal = principal.inner;
+                principal.id = account_id;
+
                 if let Some(secret) = secret {
-                    if !principal.verify_secret(secret).await? {
-                        return Ok(None);
+                    match principal.match_secret(secret).await? {
+                        Some(SecretMatch::Password) => (),
+                        Some(SecretMatch::AppPassword(app_name)) => {
+                            record_app_password_use(self, &principal, &app_name).await?;
+                        }
+                        Some(SecretMatch::RecoveryCode(recovery_code)) => {
+                            // Recovery codes are single use, the code is removed only if the
+                            // principal was not modified since it was read.
+                            principal.retain_str(PrincipalField::Secrets, |secret| {
+                                *secret != recovery_code
+                            });
+                            let class = ValueClass::Directory(DirectoryClass::Principal(
+                                MaybeDynamicId::Static(account_id),
+                            ));
+                            let mut batch = BatchBuilder::new();
+                            batch
+                                .assert_value(class.clone(), AssertValue::Hash(principal_hash))
+                                .set(class, (&principal).serialize());
+                            match self.write(batch.build()).await {
+                                Ok(_) => (),
+                                Err(err) if err.is_assertion_failure() => return Ok(None),
+                                Err(err) => return Err(err.caused_by(trc::location!())),
+                            }
+                        }
+                        None => return Ok(None),
                     }
                 }
 
@@ -164,3 +200,67 @@ impl DirectoryStore for Store {
         Ok(results)
     }
 }
+
+/// Minimum number of seconds between updates of the last use time of an app password.
+const APP_PASSWORD_USE_INTERVAL: u64 = 3600;
+
+/// Keeps track of when each app password was last used, updated at most once an hour.
+async fn record_app_password_use(
+    store: &Store,
+    principal: &Principal,
+    app_name: &str,
+) -> trc::Result<()> {
+    let now = now();
+    let current = store
+        .get_value::<HashedValue<PasswordState>>(ValueKey::from(ValueClass::Directory(
+            DirectoryClass::PasswordState(principal.id()),
+        )))
+        .await
+        .caused_by(trc::location!())?;
+    let (assert_value, mut state) = match current {
+        Some(current) => (AssertValue::Hash(current.hash), current.inner),
+        None => (
+            AssertValue::None,
+            PasswordState {
+                changed: now,
+                ..Default::default()
+            },
+        ),
+    };
+
+    match state
+        .app_last_used
+        .iter_mut()
+        .find(|(name, _)| name == app_name)
+    {
+        Some((_, last_used)) if now.saturating_sub(*last_used) < APP_PASSWORD_USE_INTERVAL => {
+            return Ok(())
+        }
+        Some((_, last_used)) => *last_used = now,
+        None => state.app_last_used.push((app_name.to_string(), now)),
+    }
+
+    // Remove entries of deleted app passwords
+    state.app_last_used.retain(|(name, _)| {
+        principal.iter_str(PrincipalField::Secrets).any(|secret| {
+            secret
+                .strip_prefix("$app$")
+                .and_then(|s| s.split_once('$'))
+                .is_some_and(|(app, _)| app == name)
+        })
+    });
+
+    // Concurrent updates take precedence, such as a session revocation
+    let class = ValueClass::Directory(DirectoryClass::PasswordState(MaybeDynamicId::Static(
+        principal.id(),
+    )));
+    let mut batch = BatchBuilder::new();
+    batch
+        .assert_value(class.clone(), assert_value)
+        .set(class, (&state).serialize());
+    match store.write(batch.build()).await {
+        Ok(_) => Ok(()),
+        Err(err) if err.is_assertion_failure() => Ok(()),
+        Err(err) => Err(err.caused_by(trc::location!())),
+    }
+}
@@ -8,22 +8,27 @@ use ahash::AHashSet;
 use jmap_proto::types::collection::Collection;
 use store::{
     write::{
-        assert::HashedValue, key::DeserializeBigEndian, AssignedIds, BatchBuilder, DirectoryClass,
-        MaybeDynamicId, MaybeDynamicValue, SerializeWithId, ValueClass,
+        assert::HashedValue, key::DeserializeBigEndian, now, AssignedIds, BatchBuilder,
+        DirectoryClass, MaybeDynamicId, MaybeDynamicValue, SerializeWithId, ValueClass,
     },
     Deserialize, IterateParams, Serialize, Store, ValueKey, U32_LEN,
 };
 use trc::AddContext;
 
 use crate::{
-    Permission, Principal, QueryBy, Type, MAX_TYPE_ID, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
+    core::{groups::MemberFilter, secret::hash_secret},
+    AccountStatus, Permission, Principal, QueryBy, Type, MAX_TYPE_ID, ROLE_ADMIN,
+    ROLE_DOMAIN_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
 };
 
 use super::{
-    lookup::DirectoryStore, PrincipalAction, PrincipalField, PrincipalInfo, PrincipalUpdate,
-    PrincipalValue, SpecialSecrets,
+    lookup::DirectoryStore, PasswordState, PrincipalAction, PrincipalField, PrincipalInfo,
+    PrincipalUpdate, PrincipalValue, SpecialSecrets,
 };
 
+/// Maximum number of previous passwords kept for history checks.
+pub const MAX_PASSWORD_HISTORY: usize = 24;
+
 pub struct MemberOf {
     pub principal_id: u32,
     pub typ: Type,
@@ -66,6 +71,17 @@ pub trait ManageDirectory: Sized {
         page: usize,
         limit: usize,
     ) -> trc::Result<PrincipalList>;
+    #[allow(clippy::too_many_arguments)]
+    async fn list_scoped_principals(
+        &self,
+        filter: Option<&str>,
+        tenant_id: Option<u32>,
+        domains: &[String],
+        types: &[Type],
+        fields: &[PrincipalField],
+        page: usize,
+        limit: usize,
+    ) -> trc::Result<PrincipalList>;
     async fn count_principals(
         &self,
         filter: Option<&str>,
@@ -87,6 +103,11 @@ trait ValidateDirectory: Sized {
         tenant_id: Option<u32>,
         create_if_missing: bool,
     ) -> trc::Result<()>;
+    async fn validate_domain_scope(
+        &self,
+        domains: Vec<String>,
+        tenant_id: Option<u32>,
+    ) -> trc::Result<Vec<String>>;
 }
 
 impl ManageDirectory for Store {
@@ -308,6 +329,18 @@ impl ManageDirectory for Store {
 
         principal.set(PrincipalField::Name, name);
 
+        // Validate dynamic membership
+        if let Some(filter) = principal.get_str(PrincipalField::MemberFilter) {
+            if matches!(principal.typ(), Type::Group | Type::List) {
+                validate_member_filter(filter)?;
+            } else {
+                return Err(error(
+                    "Invalid memberFilter value",
+                    "Only groups and lists can have dynamic members".into(),
+                ));
+            }
+        }
+
         // Map member names
         let mut members = Vec::new();
         let mut member_of = Vec::new();
@@ -368,6 +401,37 @@ impl ManageDirectory for Store {
             }
         }
 
+        // Validate domain scope
+        if let Some(domains) = principal.take_str_array(PrincipalField::DomainScope) {
+            if principal.typ != Type::Individual {
+                return Err(error(
+                    "Invalid domainScope value",
+                    "Only individuals can have a domain scope".into(),
+                ));
+            }
+            let domains = self.validate_domain_scope(domains, tenant_id).await?;
+            if !domains.is_empty() {
+                principal.set(PrincipalField::DomainScope, domains);
+            }
+        }
+
+        // Validate account status
+        if let Some(status) = principal.take_str(PrincipalField::Status) {
+            match validate_status(&status)? {
+                AccountStatus::Active => (),
+                status if principal.typ == Type::Individual => {
+                    principal.set(PrincipalField::Status, status.as_str());
+                    principal.set(PrincipalField::StatusSince, now());
+                }
+                _ => {
+                    return Err(error(
+                        "Invalid status value",
+                        "Only individuals can have a status".into(),
+                    ));
+                }
+            }
+        }
+
         // Make sure the e-mail is not taken and validate domain
         if principal.typ != Type::OauthClient {
             for email in principal.iter_mut_str(PrincipalField::Emails) {
@@ -415,6 +479,21 @@ impl ManageDirectory for Store {
                 pinfo_name,
             );
 
+        // Keep track of the password age
+        if principal
+            .iter_str(PrincipalField::Secrets)
+            .any(|secret| secret.is_password())
+        {
+            batch.set(
+                ValueClass::Directory(DirectoryClass::PasswordState(MaybeDynamicId::Dynamic(0))),
+                (&PasswordState {
+                    changed: now(),
+                    ..Default::default()
+                })
+                    .serialize(),
+            );
+        }
+
         // Write email to id mapping
         if let Some(emails) = principal
             .take(PrincipalField::Emails)
@@ -633,7 +712,10 @@ impl ManageDirectory for Store {
             .clear(DirectoryClass::Principal(MaybeDynamicId::Static(
                 principal_id,
             )))
-            .clear(DirectoryClass::UsedQuota(principal_id));
+            .clear(DirectoryClass::UsedQuota(principal_id))
+            .clear(DirectoryClass::PasswordState(MaybeDynamicId::Static(
+                principal_id,
+            )));
 
         if let Some(emails) = principal.take_str_array(PrincipalField::Emails) {
             for email in emails {
@@ -776,7 +858,7 @@ impl ManageDirectory for Store {
                 Type::Group,
                 Type::Other,
             ][..],
-            Type::List => &[Type::Individual, Type::Group][..],
+            Type::List => &[Type::Individual, Type::Group, Type::List][..],
             Type::Other
             | Type::Domain
             | Type::Tenant
@@ -787,6 +869,22 @@ impl ManageDirectory for Store {
         };
         let mut valid_domains = AHashSet::new();
 
+        // Obtain current passwords
+        let old_passwords = if changes
+            .iter()
+            .any(|c| matches!(c.field, PrincipalField::Secrets))
+        {
+            principal
+                .inner
+                .iter_str(PrincipalField::Secrets)
+                .filter(|secret| secret.is_password())
+                .map(|secret| secret.to_string())
+                .collect::<Vec<_>>()
+                .into()
+        } else {
+            None
+        };
+
         // Process changes
         for change in changes {
             match (change.action, change.field, change.value) {
@@ -934,7 +1032,11 @@ impl ManageDirectory for Store {
                     PrincipalField::Secrets,
                     PrincipalValue::String(secret),
                 ) => {
-                    if secret.is_app_password() || secret.is_otp_auth() {
+                    if secret.is_app_password()
+                        || secret.is_otp_auth()
+                        || secret.is_webauthn()
+                        || secret.is_recovery_code()
+                    {
                         principal.inner.retain_str(PrincipalField::Secrets, |v| {
                             *v != secret && !v.starts_with(&secret)
                         });
@@ -1075,7 +1177,11 @@ impl ManageDirectory for Store {
                             .get_principal_info(&member)
                             .await
                             .caused_by(trc::location!())?
-                            .filter(|p| p.has_tenant_access(tenant_id))
+                            .filter(|p| {
+                                p.has_tenant_access(tenant_id)
+                                    && (change.field != PrincipalField::Roles
+                                        || p.typ == Type::Role)
+                            })
                             .or_else(|| change.field.map_internal_roles(&member))
                             .ok_or_else(|| not_found(member.clone()))?;
 
@@ -1087,6 +1193,17 @@ impl ManageDirectory for Store {
                         )?;
 
                         if !member_of.contains(&member_info.id) {
+                            if matches!(principal.inner.typ, Type::Group | Type::List) {
+                                validate_no_cycle(
+                                    self,
+                                    change.field,
+                                    member_info.id,
+                                    principal_id,
+                                    &member,
+                                )
+                                .await?;
+                            }
+
                             batch.set(
                                 ValueClass::Directory(DirectoryClass::MemberOf {
                                     principal_id: MaybeDynamicId::Static(principal_id),
@@ -1130,7 +1247,10 @@ impl ManageDirectory for Store {
                         .get_principal_info(&member)
                         .await
                         .caused_by(trc::location!())?
-                        .filter(|p| p.has_tenant_access(tenant_id))
+                        .filter(|p| {
+                            p.has_tenant_access(tenant_id)
+                                && (change.field != PrincipalField::Roles || p.typ == Type::Role)
+                        })
                         .or_else(|| change.field.map_internal_roles(&member))
                         .ok_or_else(|| not_found(member.clone()))?;
 
@@ -1141,6 +1261,16 @@ impl ManageDirectory for Store {
                             member_info.typ,
                             &member,
                         )?;
+                        if matches!(principal.inner.typ, Type::Group | Type::List) {
+                            validate_no_cycle(
+                                self,
+                                change.field,
+                                member_info.id,
+                                principal_id,
+                                &member,
+                            )
+                            .await?;
+                        }
 
                         batch.set(
                             ValueClass::Directory(DirectoryClass::MemberOf {
@@ -1219,6 +1349,17 @@ impl ManageDirectory for Store {
                         }
 
                         if !members.contains(&member_info.id) {
+                            if matches!(member_info.typ, Type::Group | Type::List) {
+                                validate_no_cycle(
+                                    self,
+                                    change.field,
+                                    principal_id,
+                                    member_info.id,
+                                    &member,
+                                )
+                                .await?;
+                            }
+
                             batch.set(
                                 ValueClass::Directory(DirectoryClass::MemberOf {
                                     principal_id: MaybeDynamicId::Static(member_info.id),
@@ -1280,6 +1421,16 @@ impl ManageDirectory for Store {
                                 .into(),
                             ));
                         }
+                        if matches!(member_info.typ, Type::Group | Type::List) {
+                            validate_no_cycle(
+                                self,
+                                change.field,
+                                principal_id,
+                                member_info.id,
+                                &member,
+                            )
+                            .await?;
+                        }
 
                         batch.set(
                             ValueClass::Directory(DirectoryClass::MemberOf {
@@ -1404,6 +1555,99 @@ impl ManageDirectory for Store {
                         principal.inner.retain_str(change.field, |v| *v != url);
                     }
                 }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::MemberFilter,
+                    PrincipalValue::String(filter),
+                ) if matches!(principal.inner.typ, Type::Group | Type::List) => {
+                    if !filter.is_empty() {
+                        validate_member_filter(&filter)?;
+                        principal.inner.set(change.field, filter);
+                    } else {
+                        principal.inner.remove(change.field);
+                    }
+                }
+                (PrincipalAction::Set, PrincipalField::Status, PrincipalValue::String(status))
+                    if principal.inner.typ == Type::Individual =>
+                {
+                    let status = validate_status(&status)?;
+                    if status != principal.inner.status() {
+                        if status != AccountStatus::Active {
+                            principal.inner.set(change.field, status.as_str());
+                            principal.inner.set(PrincipalField::StatusSince, now());
+                        } else {
+                            principal.inner.remove(change.field);
+                            principal.inner.remove(PrincipalField::StatusSince);
+                        }
+                    }
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::DomainScope,
+                    PrincipalValue::StringList(domains),
+                ) if principal.inner.typ == Type::Individual => {
+                    let domains = self.validate_domain_scope(domains, tenant_id).await?;
+                    if !domains.is_empty() {
+                        principal.inner.set(change.field, domains);
+                    } else {
+                        principal.inner.remove(change.field);
+                    }
+                }
+                (
+                    PrincipalAction::AddItem,
+                    PrincipalField::DomainScope,
+                    PrincipalValue::String(domain),
+                ) if principal.inner.typ == Type::Individual => {
+                    for domain in self.validate_domain_scope(vec![domain], tenant_id).await? {
+                        if !principal.inner.has_str_value(change.field, &domain) {
+                            principal.inner.append_str(change.field, domain);
+                        }
+                    }
+                }
+                (
+                    PrincipalAction::RemoveItem,
+                    PrincipalField::DomainScope,
+                    PrincipalValue::String(domain),
+                ) => {
+                    let domain = domain.to_lowercase();
+                    principal.inner.retain_str(change.field, |v| *v != domain);
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::ForwardTo,
+                    PrincipalValue::StringList(addresses),
+                ) => {
+                    let mut forward_to = Vec::with_capacity(addresses.len());
+                    for address in addresses {
+                        let address = validate_forward_address(address)?;
+                        if !forward_to.contains(&address) {
+                            forward_to.push(address);
+                        }
+                    }
+                    if !forward_to.is_empty() {
+                        principal.inner.set(change.field, forward_to);
+                    } else {
+                        principal.inner.remove(change.field);
+                    }
+                }
+                (
+                    PrincipalAction::AddItem,
+                    PrincipalField::ForwardTo,
+                    PrincipalValue::String(address),
+                ) => {
+                    let address = validate_forward_address(address)?;
+                    if !principal.inner.has_str_value(change.field, &address) {
+                        principal.inner.append_str(change.field, address);
+                    }
+                }
+                (
+                    PrincipalAction::RemoveItem,
+                    PrincipalField::ForwardTo,
+                    PrincipalValue::String(address),
+                ) => {
+                    let address = address.to_lowercase();
+                    principal.inner.retain_str(change.field, |v| *v != address);
+                }
 
                 (_, field, value) => {
                     return Err(error(
@@ -1414,6 +1658,45 @@ impl ManageDirectory for Store {
             }
         }
 
+        // Update the password history when passwords were changed
+        if let Some(old_passwords) = old_passwords {
+            let new_passwords = principal
+                .inner
+                .iter_str(PrincipalField::Secrets)
+                .filter(|secret| secret.is_password())
+                .collect::<Vec<_>>();
+
+            if old_passwords.len() != new_passwords.len()
+                || old_passwords
+                    .iter()
+                    .any(|secret| !new_passwords.contains(&secret))
+            {
+                let mut state = self
+                    .get_value::<PasswordState>(ValueKey::from(ValueClass::Directory(
+                        DirectoryClass::PasswordState(principal_id),
+                    )))
+                    .await
+                    .caused_by(trc::location!())?
+                    .unwrap_or_default();
+                let mut history = old_passwords
+                    .iter()
+                    .filter(|secret| !new_passwords.contains(secret))
+                    .map(|secret| hash_secret(secret))
+                    .collect::<Vec<_>>();
+                history.append(&mut state.history);
+                history.truncate(MAX_PASSWORD_HISTORY);
+                state.history = history;
+                state.changed = now();
+
+                batch.set(
+                    ValueClass::Directory(DirectoryClass::PasswordState(MaybeDynamicId::Static(
+                        principal_id,
+                    ))),
+                    (&state).serialize(),
+                );
+            }
+        }
+
         if update_principal {
             batch.set(
                 ValueClass::Directory(DirectoryClass::Principal(MaybeDynamicId::Static(
@@ -1438,6 +1721,20 @@ impl ManageDirectory for Store {
         fields: &[PrincipalField],
         page: usize,
         limit: usize,
+    ) -> trc::Result<PrincipalList> {
+        self.list_scoped_principals(filter, tenant_id, &[], types, fields, page, limit)
+            .await
+    }
+
+    async fn list_scoped_principals(
+        &self,
+        filter: Option<&str>,
+        tenant_id: Option<u32>,
+        domains: &[String],
+        types: &[Type],
+        fields: &[PrincipalField],
+        page: usize,
+        limit: usize,
     ) -> trc::Result<PrincipalList> {
         let from_key = ValueKey::from(ValueClass::Directory(DirectoryClass::NameToId(vec![])));
         let to_key = ValueKey::from(ValueClass::Directory(DirectoryClass::NameToId(vec![
@@ -1466,6 +1763,7 @@ impl ManageDirectory for Store {
         .caused_by(trc::location!())?;
 
         if filter.is_none()
+            && domains.is_empty()
             && !fields.is_empty()
             && fields.iter().all(|f| matches!(f, PrincipalField::Name))
         {
@@ -1510,7 +1808,7 @@ impl ManageDirectory for Store {
             });
 
         for mut principal in results {
-            if !is_done || filters.is_some() {
+            if !is_done || filters.is_some() || !domains.is_empty() {
                 principal = self
                     .query(QueryBy::Id(principal.id), map_principals)
                     .await
@@ -1518,9 +1816,11 @@ impl ManageDirectory for Store {
                     .ok_or_else(|| not_found(principal.name().to_string()))?;
             }
 
-            if filters.as_ref().map_or(true, |filters| {
-                filters.iter().all(|f| principal.find_str(f))
-            }) {
+            if principal.has_domain_access(domains)
+                && filters.as_ref().map_or(true, |filters| {
+                    filters.iter().all(|f| principal.find_str(f))
+                })
+            {
                 result.total += 1;
 
                 if offset == 0 {
@@ -1654,6 +1954,9 @@ impl ManageDirectory for Store {
                         ROLE_USER if field == PrincipalField::Roles => {
                             principal.append_str(field, "user");
                         }
+                        ROLE_DOMAIN_ADMIN if field == PrincipalField::Roles => {
+                            principal.append_str(field, "domain-admin");
+                        }
                         principal_id => {
                             if let Some(name) = self
                                 .get_principal(principal_id)
@@ -1820,6 +2123,27 @@ impl ValidateDirectory for Store {
             Err(error("Invalid email", "Email address is invalid".into()))
         }
     }
+
+    async fn validate_domain_scope(
+        &self,
+        domains: Vec<String>,
+        tenant_id: Option<u32>,
+    ) -> trc::Result<Vec<String>> {
+        let mut scope = Vec::with_capacity(domains.len());
+        for domain in domains {
+            let domain = domain.trim().to_lowercase();
+            if !scope.contains(&domain) {
+                self.get_principal_info(&domain)
+                    .await
+                    .caused_by(trc::location!())?
+                    .filter(|v| v.typ == Type::Domain && v.has_tenant_access(tenant_id))
+                    .ok_or_else(|| not_found(domain.clone()))?;
+                scope.push(domain);
+            }
+        }
+
+        Ok(scope)
+    }
 }
 
 impl PrincipalField {
@@ -1828,6 +2152,7 @@ impl PrincipalField {
             (PrincipalField::Roles, "admin") => Some(ROLE_ADMIN),
             (PrincipalField::Roles, "tenant-admin") => Some(ROLE_TENANT_ADMIN),
             (PrincipalField::Roles, "user") => Some(ROLE_USER),
+            (PrincipalField::Roles, "domain-admin") => Some(ROLE_DOMAIN_ADMIN),
             _ => None,
         }
     }
@@ -1896,7 +2221,7 @@ fn validate_member_of(
     let expected_types = match (field, typ) {
         (PrincipalField::MemberOf, Type::Individual) => &[Type::Group, Type::Individual][..],
         (PrincipalField::MemberOf, Type::Group) => &[Type::Group][..],
-        (PrincipalField::Lists, Type::Individual | Type::Group) => &[Type::List][..],
+        (PrincipalField::Lists, Type::Individual | Type::Group | Type::List) => &[Type::List][..],
         (PrincipalField::Roles, Type::Individual | Type::Tenant | Type::Role) => &[Type::Role][..],
         _ => &[][..],
     };
@@ -1958,6 +2283,72 @@ pub fn err_exists(field: impl Into<trc::Value>, value: impl Into<trc::Value>) ->
         .ctx(trc::Key::Value, value)
 }
 
+fn validate_status(status: &str) -> trc::Result<AccountStatus> {
+    AccountStatus::parse(status).ok_or_else(|| {
+        error(
+            "Invalid status value",
+            format!("Status {status:?} is invalid").into(),
+        )
+    })
+}
+
+fn validate_member_filter(filter: &str) -> trc::Result<()> {
+    MemberFilter::parse(filter).map(|_| ()).map_err(|reason| {
+        error(
+            format!("Invalid {} value", PrincipalField::MemberFilter.as_str()),
+            reason.into(),
+        )
+    })
+}
+
+/// Fails if `member_id` is `group_id` or one of its ancestors, as adding it to
+/// `group_id` would then create a membership cycle.
+async fn validate_no_cycle(
+    store: &Store,
+    field: PrincipalField,
+    group_id: u32,
+    member_id: u32,
+    member_name: &str,
+) -> trc::Result<()> {
+    let mut seen = AHashSet::new();
+    let mut pending = vec![group_id];
+    while let Some(id) = pending.pop() {
+        if id == member_id {
+            return Err(error(
+                format!("Invalid {} value", field.as_str()),
+                format!("Principal {member_name:?} would create a membership cycle.").into(),
+            ));
+        } else if seen.insert(id) {
+            pending.extend(
+                store
+                    .get_member_of(id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .into_iter()
+                    .filter(|member| matches!(member.typ, Type::Group | Type::List))
+                    .map(|member| member.principal_id),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_forward_address(address: String) -> trc::Result<String> {
+    let address = address.trim().to_lowercase();
+    if address
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+    {
+        Ok(address)
+    } else {
+        Err(error(
+            "Invalid parameter",
+            format!("Invalid forwarding address {address:?}").into(),
+        ))
+    }
+}
+
 pub fn not_found(value: impl Into<trc::Value>) -> trc::Error {
     trc::ManageEvent::NotFound.ctx(trc::Key::Key, value)
 }
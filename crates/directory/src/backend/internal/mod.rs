@@ -31,6 +31,14 @@ pub struct PrincipalInfo {
     pub tenant: Option<u32>,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PasswordState {
+    pub changed: u64,
+    pub history: Vec<String>,
+    pub revoked_at: u64,
+    pub app_last_used: Vec<(String, u64)>,
+}
+
 impl Serialize for Principal {
     fn serialize(self) -> Vec<u8> {
         (&self).serialize()
@@ -167,6 +175,72 @@ impl PrincipalInfo {
     }
 }
 
+impl Serialize for &PasswordState {
+    fn serialize(self) -> Vec<u8> {
+        let mut serializer = KeySerializer::new(
+            U32_LEN * 4
+                + self.history.iter().map(|v| v.len() + 2).sum::<usize>()
+                + self
+                    .app_last_used
+                    .iter()
+                    .map(|(v, _)| v.len() + U32_LEN + 2)
+                    .sum::<usize>(),
+        )
+        .write_leb128(self.changed)
+        .write_leb128(self.history.len());
+        for hash in &self.history {
+            serializer = serializer.write_leb128(hash.len()).write(hash.as_bytes());
+        }
+        serializer = serializer
+            .write_leb128(self.revoked_at)
+            .write_leb128(self.app_last_used.len());
+        for (name, last_used) in &self.app_last_used {
+            serializer = serializer
+                .write_leb128(name.len())
+                .write(name.as_bytes())
+                .write_leb128(*last_used);
+        }
+        serializer.finalize()
+    }
+}
+
+impl Deserialize for PasswordState {
+    fn deserialize(bytes_: &[u8]) -> trc::Result<Self> {
+        let mut bytes = bytes_.iter();
+        (|| {
+            let changed = bytes.next_leb128()?;
+            let num_hashes = bytes.next_leb128::<usize>()?;
+            let mut history = Vec::with_capacity(num_hashes);
+            for _ in 0..num_hashes {
+                history.push(deserialize_string(&mut bytes)?);
+            }
+            let mut state = PasswordState {
+                changed,
+                history,
+                ..Default::default()
+            };
+
+            // Revocation and app password usage were added later
+            if !bytes.as_slice().is_empty() {
+                state.revoked_at = bytes.next_leb128()?;
+                let num_apps = bytes.next_leb128::<usize>()?;
+                for _ in 0..num_apps {
+                    state
+                        .app_last_used
+                        .push((deserialize_string(&mut bytes)?, bytes.next_leb128()?));
+                }
+            }
+
+            Some(state)
+        })()
+        .ok_or_else(|| {
+            trc::StoreEvent::DataCorruption
+                .caused_by(trc::location!())
+                .ctx(trc::Key::Value, bytes_)
+        })
+    }
+}
+
 fn deserialize(bytes: &[u8]) -> Option<Principal> {
     let mut bytes = bytes.iter();
 
@@ -409,6 +483,11 @@ pub enum PrincipalField {
     DisabledPermissions,
     Picture,
     Urls,
+    ForwardTo,
+    MemberFilter,
+    DomainScope,
+    Status,
+    StatusSince,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -488,6 +567,11 @@ impl PrincipalField {
             PrincipalField::UsedQuota => 13,
             PrincipalField::Picture => 14,
             PrincipalField::Urls => 15,
+            PrincipalField::ForwardTo => 16,
+            PrincipalField::MemberFilter => 17,
+            PrincipalField::DomainScope => 18,
+            PrincipalField::Status => 19,
+            PrincipalField::StatusSince => 20,
         }
     }
 
@@ -509,6 +593,11 @@ impl PrincipalField {
             13 => Some(PrincipalField::UsedQuota),
             14 => Some(PrincipalField::Picture),
             15 => Some(PrincipalField::Urls),
+            16 => Some(PrincipalField::ForwardTo),
+            17 => Some(PrincipalField::MemberFilter),
+            18 => Some(PrincipalField::DomainScope),
+            19 => Some(PrincipalField::Status),
+            20 => Some(PrincipalField::StatusSince),
             _ => None,
         }
     }
@@ -531,6 +620,11 @@ impl PrincipalField {
             PrincipalField::DisabledPermissions => "disabledPermissions",
             PrincipalField::Picture => "picture",
             PrincipalField::Urls => "urls",
+            PrincipalField::ForwardTo => "forwardTo",
+            PrincipalField::MemberFilter => "memberFilter",
+            PrincipalField::DomainScope => "domainScope",
+            PrincipalField::Status => "status",
+            PrincipalField::StatusSince => "statusSince",
         }
     }
 
@@ -552,6 +646,11 @@ impl PrincipalField {
             "disabledPermissions" => Some(PrincipalField::DisabledPermissions),
             "picture" => Some(PrincipalField::Picture),
             "urls" => Some(PrincipalField::Urls),
+            "forwardTo" => Some(PrincipalField::ForwardTo),
+            "memberFilter" => Some(PrincipalField::MemberFilter),
+            "domainScope" => Some(PrincipalField::DomainScope),
+            "status" => Some(PrincipalField::Status),
+            "statusSince" => Some(PrincipalField::StatusSince),
             _ => None,
         }
     }
@@ -569,6 +668,8 @@ fn deserialize_string(bytes: &mut Iter<'_, u8>) -> Option<String> {
 pub trait SpecialSecrets {
     fn is_otp_auth(&self) -> bool;
     fn is_app_password(&self) -> bool;
+    fn is_webauthn(&self) -> bool;
+    fn is_recovery_code(&self) -> bool;
     fn is_password(&self) -> bool;
 }
 
@@ -584,7 +685,18 @@ where
         self.as_ref().starts_with("$app$")
     }
 
+    fn is_webauthn(&self) -> bool {
+        self.as_ref().starts_with("$webauthn$")
+    }
+
+    fn is_recovery_code(&self) -> bool {
+        self.as_ref().starts_with("$recovery$")
+    }
+
     fn is_password(&self) -> bool {
-        !self.is_otp_auth() && !self.is_app_password()
+        !self.is_otp_auth()
+            && !self.is_app_password()
+            && !self.is_webauthn()
+            && !self.is_recovery_code()
     }
 }
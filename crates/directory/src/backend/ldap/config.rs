@@ -12,7 +12,10 @@ use utils::config::{utils::AsKey, Config};
 
 use crate::core::config::build_pool;
 
-use super::{Bind, LdapConnectionManager, LdapDirectory, LdapFilter, LdapMappings};
+use super::{
+    Bind, LdapConnectionManager, LdapDirectory, LdapFilter, LdapMappings, LdapPasswordUpdate,
+    LdapWriteBack,
+};
 
 impl LdapDirectory {
     pub fn from_config(config: &mut Config, prefix: impl AsKey, data_store: Store) -> Option<Self> {
@@ -55,6 +58,9 @@ impl LdapDirectory {
             filter_verify: LdapFilter::from_config(config, (&prefix, "filter.verify")),
             filter_expand: LdapFilter::from_config(config, (&prefix, "filter.expand")),
             filter_domains: LdapFilter::from_config(config, (&prefix, "filter.domains")),
+            filter_list: config
+                .value((&prefix, "filter.list"))
+                .map(|filter| filter.to_string()),
             attr_name: config
                 .values((&prefix, "attributes.name"))
                 .map(|(_, v)| v.to_string())
@@ -88,6 +94,7 @@ impl LdapDirectory {
                 .map(|(_, v)| v.to_string())
                 .collect(),
             attrs_principal: vec!["objectClass".to_string()],
+            write_back: None,
         };
 
         for attr in [
@@ -103,6 +110,60 @@ impl LdapDirectory {
             mappings.attrs_principal.extend(attr.iter().cloned());
         }
 
+        // Parse write-back settings
+        if config
+            .property_or_default::<bool>((&prefix, "write-back.enable"), "false")
+            .unwrap_or_default()
+        {
+            let password = match config
+                .value((&prefix, "write-back.password.method"))
+                .unwrap_or("password-modify")
+            {
+                "password-modify" => LdapPasswordUpdate::PasswordModify,
+                "replace" => LdapPasswordUpdate::Replace {
+                    hash: match config
+                        .value((&prefix, "write-back.password.hash"))
+                        .unwrap_or("crypt")
+                    {
+                        "crypt" => true,
+                        "plain" => false,
+                        other => {
+                            let err = format!("Invalid password hash {other:?}");
+                            config.new_parse_error((&prefix, "write-back.password.hash"), err);
+                            true
+                        }
+                    },
+                },
+                "disabled" => LdapPasswordUpdate::Disabled,
+                other => {
+                    let err = format!("Invalid password update method {other:?}");
+                    config.new_parse_error((&prefix, "write-back.password.method"), err);
+                    LdapPasswordUpdate::Disabled
+                }
+            };
+
+            let mut write_back = LdapWriteBack {
+                password,
+                attr_secret: mappings.attr_secret.first().cloned(),
+                attr_email_address: mappings.attr_email_address.first().cloned(),
+                attr_email_alias: mappings.attr_email_alias.first().cloned(),
+                attr_quota: mappings.attr_quota.first().cloned(),
+                attr_description: mappings.attr_description.first().cloned(),
+            };
+            for (key, attr) in [
+                ("secret", &mut write_back.attr_secret),
+                ("email", &mut write_back.attr_email_address),
+                ("email-alias", &mut write_back.attr_email_alias),
+                ("quota", &mut write_back.attr_quota),
+                ("description", &mut write_back.attr_description),
+            ] {
+                if let Some(value) = config.value((prefix.as_str(), "write-back.attributes", key)) {
+                    *attr = Some(value.to_string());
+                }
+            }
+            mappings.write_back = Some(Box::new(write_back));
+        }
+
         let auth_bind = if config
             .property_or_default::<bool>((&prefix, "bind.auth.enable"), "false")
             .unwrap_or_default()
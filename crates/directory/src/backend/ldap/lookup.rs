@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use ahash::AHashSet;
 use ldap3::{Ldap, LdapConnAsync, Scope, SearchEntry};
 use mail_send::Credentials;
 use trc::AddContext;
@@ -14,6 +15,7 @@ use crate::{
         manage::{self, ManageDirectory, UpdatePrincipal},
         PrincipalField,
     },
+    core::groups::MAX_GROUP_DEPTH,
     IntoError, Principal, QueryBy, Type, ROLE_ADMIN, ROLE_USER,
 };
 
@@ -135,33 +137,7 @@ impl LdapDirectory {
         match external_principal.take_str_array(PrincipalField::MemberOf) {
             Some(names) if return_member_of => {
                 let mut member_of = Vec::with_capacity(names.len());
-                for mut name in names {
-                    if name.contains('=') {
-                        let (rs, _res) = conn
-                            .search(
-                                &name,
-                                Scope::Base,
-                                "objectClass=*",
-                                &self.mappings.attr_name,
-                            )
-                            .await
-                            .map_err(|err| err.into_error().caused_by(trc::location!()))?
-                            .success()
-                            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
-                        for entry in rs {
-                            'outer: for (attr, value) in SearchEntry::construct(entry).attrs {
-                                if self.mappings.attr_name.contains(&attr) {
-                                    if let Some(group) = value.into_iter().next() {
-                                        if !group.is_empty() {
-                                            name = group;
-                                            break 'outer;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
+                for name in self.resolve_group_names(&mut conn, names).await? {
                     member_of.push(
                         self.data_store
                             .get_or_create_principal_id(&name, Type::Group)
@@ -334,6 +310,33 @@ impl LdapDirectory {
     }
 
     pub async fn expn(&self, address: &str) -> trc::Result<Vec<String>> {
+        // Addresses that expand to other addresses are nested lists, the visited
+        // set breaks any cycles between them
+        let mut emails = Vec::new();
+        let mut seen = AHashSet::from_iter([address.to_lowercase()]);
+        let mut pending = self.expn_list(address).await?;
+        for _ in 0..MAX_GROUP_DEPTH {
+            if pending.is_empty() {
+                break;
+            }
+            let mut next = Vec::new();
+            for email in pending {
+                if seen.insert(email.to_lowercase()) {
+                    let members = self.expn_list(&email).await?;
+                    if members.is_empty() {
+                        emails.push(email);
+                    } else {
+                        next.extend(members);
+                    }
+                }
+            }
+            pending = next;
+        }
+
+        Ok(emails)
+    }
+
+    async fn expn_list(&self, address: &str) -> trc::Result<Vec<String>> {
         let filter = self.mappings.filter_expand.build(address);
         let mut stream = self
             .pool
@@ -411,6 +414,88 @@ impl LdapDirectory {
 }
 
 impl LdapDirectory {
+    /// Fetches all principals matching the list filter, group memberships are returned as names.
+    pub async fn list_principals(&self) -> trc::Result<Vec<Principal>> {
+        let filter = self.mappings.filter_list.as_deref().ok_or_else(|| {
+            trc::StoreEvent::NotConfigured
+                .into_err()
+                .details("LDAP list filter is not configured")
+        })?;
+        let mut conn = self.pool.get().await.map_err(|err| err.into_error())?;
+        let (rs, _res) = conn
+            .search(
+                &self.mappings.base_dn,
+                Scope::Subtree,
+                filter,
+                &self.mappings.attrs_principal,
+            )
+            .await
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?
+            .success()
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+        trc::event!(
+            Store(trc::StoreEvent::LdapQuery),
+            Details = filter.to_string(),
+            Result = rs.len(),
+        );
+
+        let mut principals = Vec::with_capacity(rs.len());
+        for entry in rs {
+            let mut principal = self
+                .mappings
+                .entry_to_principal(SearchEntry::construct(entry));
+            if principal.name().is_empty() {
+                continue;
+            }
+            if let Some(names) = principal.take_str_array(PrincipalField::MemberOf) {
+                let names = self.resolve_group_names(&mut conn, names).await?;
+                principal.set(PrincipalField::MemberOf, names);
+            }
+            principals.push(principal);
+        }
+
+        Ok(principals)
+    }
+
+    async fn resolve_group_names(
+        &self,
+        conn: &mut Ldap,
+        names: Vec<String>,
+    ) -> trc::Result<Vec<String>> {
+        let mut group_names = Vec::with_capacity(names.len());
+        for mut name in names {
+            if name.contains('=') {
+                let (rs, _res) = conn
+                    .search(
+                        &name,
+                        Scope::Base,
+                        "objectClass=*",
+                        &self.mappings.attr_name,
+                    )
+                    .await
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                    .success()
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+                for entry in rs {
+                    'outer: for (attr, value) in SearchEntry::construct(entry).attrs {
+                        if self.mappings.attr_name.contains(&attr) {
+                            if let Some(group) = value.into_iter().next() {
+                                if !group.is_empty() {
+                                    name = group;
+                                    break 'outer;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            group_names.push(name);
+        }
+
+        Ok(group_names)
+    }
+
     async fn find_principal(
         &self,
         conn: &mut Ldap,
@@ -11,6 +11,7 @@ use store::Store;
 pub mod config;
 pub mod lookup;
 pub mod pool;
+pub mod update;
 
 pub struct LdapDirectory {
     pool: Pool<LdapConnectionManager>,
@@ -27,6 +28,7 @@ pub struct LdapMappings {
     filter_verify: LdapFilter,
     filter_expand: LdapFilter,
     filter_domains: LdapFilter,
+    filter_list: Option<String>,
     attr_name: Vec<String>,
     attr_type: Vec<String>,
     attr_groups: Vec<String>,
@@ -36,6 +38,24 @@ pub struct LdapMappings {
     attr_email_alias: Vec<String>,
     attr_quota: Vec<String>,
     attrs_principal: Vec<String>,
+    write_back: Option<Box<LdapWriteBack>>,
+}
+
+#[derive(Debug)]
+pub struct LdapWriteBack {
+    password: LdapPasswordUpdate,
+    attr_secret: Option<String>,
+    attr_email_address: Option<String>,
+    attr_email_alias: Option<String>,
+    attr_quota: Option<String>,
+    attr_description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LdapPasswordUpdate {
+    Disabled,
+    PasswordModify,
+    Replace { hash: bool },
 }
 
 #[derive(Debug, Default)]
@@ -0,0 +1,259 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::HashSet;
+
+use ldap3::{exop::PasswordModify, Mod, Scope, SearchEntry};
+
+use crate::{
+    backend::internal::{
+        manage, PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
+    },
+    core::secret::{hash_secret, is_hashed_secret},
+    IntoError,
+};
+
+use super::{LdapDirectory, LdapPasswordUpdate, LdapWriteBack};
+
+impl LdapDirectory {
+    /// Returns whether a principal change can be written back to the LDAP server.
+    pub fn supports_update(&self, change: &PrincipalUpdate) -> bool {
+        let Some(write_back) = &self.mappings.write_back else {
+            return false;
+        };
+
+        match (&change.action, change.field, &change.value) {
+            (
+                PrincipalAction::Set,
+                PrincipalField::Secrets,
+                PrincipalValue::StringList(secrets),
+            ) => secrets.len() == 1 && write_back.supports_password(&secrets[0]),
+            (PrincipalAction::AddItem, PrincipalField::Secrets, PrincipalValue::String(secret)) => {
+                write_back.supports_password(secret)
+            }
+            (
+                PrincipalAction::RemoveItem,
+                PrincipalField::Secrets,
+                PrincipalValue::String(secret),
+            ) => write_back.password != LdapPasswordUpdate::Disabled && secret.is_password(),
+            (PrincipalAction::Set, PrincipalField::Emails, PrincipalValue::StringList(_)) => {
+                write_back.attr_email_address.is_some() && write_back.attr_email_alias.is_some()
+            }
+            (
+                PrincipalAction::AddItem | PrincipalAction::RemoveItem,
+                PrincipalField::Emails,
+                PrincipalValue::String(_),
+            ) => write_back.attr_email_alias.is_some(),
+            (PrincipalAction::Set, PrincipalField::Quota, PrincipalValue::Integer(_)) => {
+                write_back.attr_quota.is_some()
+            }
+            (PrincipalAction::Set, PrincipalField::Description, PrincipalValue::String(_)) => {
+                write_back.attr_description.is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes password, alias, quota and description changes back to the LDAP server.
+    pub async fn write_back(&self, name: &str, changes: &[PrincipalUpdate]) -> trc::Result<()> {
+        let write_back = self
+            .mappings
+            .write_back
+            .as_ref()
+            .ok_or_else(|| manage::unsupported("LDAP write-back is not enabled"))?;
+        let mut conn = self
+            .pool
+            .get()
+            .await
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+        // Obtain the entry's DN
+        let filter = self.mappings.filter_name.build(name);
+        let dn = conn
+            .search(&self.mappings.base_dn, Scope::Subtree, &filter, vec!["1.1"])
+            .await
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?
+            .success()
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?
+            .0
+            .into_iter()
+            .next()
+            .map(|entry| SearchEntry::construct(entry).dn)
+            .ok_or_else(|| manage::not_found(name.to_string()))?;
+
+        // Build modifications
+        let mut mods = Vec::new();
+        let mut new_password = None;
+        for change in changes {
+            if !self.supports_update(change) {
+                return Err(manage::unsupported(format!(
+                    "Field {} cannot be written back to the LDAP directory",
+                    change.field.as_str()
+                )));
+            }
+
+            match (&change.action, change.field, &change.value) {
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::Secrets,
+                    PrincipalValue::StringList(secrets),
+                ) => {
+                    new_password = secrets.last().cloned();
+                }
+                (
+                    PrincipalAction::AddItem,
+                    PrincipalField::Secrets,
+                    PrincipalValue::String(secret),
+                ) => {
+                    new_password = Some(secret.clone());
+                }
+                (PrincipalAction::RemoveItem, PrincipalField::Secrets, _) => {
+                    // Passwords are replaced rather than removed
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::Emails,
+                    PrincipalValue::StringList(emails),
+                ) => {
+                    let mut emails = emails.iter().cloned();
+                    mods.push(Mod::Replace(
+                        write_back.attr_email_address.clone().unwrap_or_default(),
+                        emails.next().into_iter().collect(),
+                    ));
+                    mods.push(Mod::Replace(
+                        write_back.attr_email_alias.clone().unwrap_or_default(),
+                        emails.collect(),
+                    ));
+                }
+                (
+                    PrincipalAction::AddItem,
+                    PrincipalField::Emails,
+                    PrincipalValue::String(email),
+                ) => {
+                    mods.push(Mod::Add(
+                        write_back.attr_email_alias.clone().unwrap_or_default(),
+                        HashSet::from([email.clone()]),
+                    ));
+                }
+                (
+                    PrincipalAction::RemoveItem,
+                    PrincipalField::Emails,
+                    PrincipalValue::String(email),
+                ) => {
+                    mods.push(Mod::Delete(
+                        write_back.attr_email_alias.clone().unwrap_or_default(),
+                        HashSet::from([email.clone()]),
+                    ));
+                }
+                (PrincipalAction::Set, PrincipalField::Quota, PrincipalValue::Integer(quota)) => {
+                    mods.push(Mod::Replace(
+                        write_back.attr_quota.clone().unwrap_or_default(),
+                        if *quota > 0 {
+                            HashSet::from([quota.to_string()])
+                        } else {
+                            HashSet::new()
+                        },
+                    ));
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::Description,
+                    PrincipalValue::String(description),
+                ) => {
+                    mods.push(Mod::Replace(
+                        write_back.attr_description.clone().unwrap_or_default(),
+                        if !description.is_empty() {
+                            HashSet::from([description.clone()])
+                        } else {
+                            HashSet::new()
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Update password
+        if let Some(password) = new_password {
+            match write_back.password {
+                LdapPasswordUpdate::PasswordModify => {
+                    conn.extended(PasswordModify {
+                        user_id: Some(&dn),
+                        old_pass: None,
+                        new_pass: Some(&password),
+                    })
+                    .await
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                    .success()
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+                    trc::event!(
+                        Store(trc::StoreEvent::LdapModify),
+                        Details = dn.clone(),
+                        Result = "password-modify"
+                    );
+                }
+                LdapPasswordUpdate::Replace { hash } => {
+                    // Pre-hashed secrets are stored using the LDAP scheme syntax
+                    mods.push(Mod::Replace(
+                        write_back.attr_secret.clone().unwrap_or_default(),
+                        HashSet::from([if is_hashed_secret(&password) {
+                            if password.starts_with('{') {
+                                password
+                            } else {
+                                format!("{{CRYPT}}{password}")
+                            }
+                        } else if hash {
+                            format!("{{CRYPT}}{}", hash_secret(&password))
+                        } else {
+                            password
+                        }]),
+                    ));
+                }
+                LdapPasswordUpdate::Disabled => (),
+            }
+        }
+
+        if !mods.is_empty() {
+            let attrs = mods
+                .iter()
+                .map(|m| match m {
+                    Mod::Add(attr, _)
+                    | Mod::Delete(attr, _)
+                    | Mod::Replace(attr, _)
+                    | Mod::Increment(attr, _) => trc::Value::from(attr.clone()),
+                })
+                .collect::<Vec<_>>();
+
+            conn.modify(&dn, mods)
+                .await
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                .success()
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+            trc::event!(
+                Store(trc::StoreEvent::LdapModify),
+                Details = dn,
+                Result = attrs
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl LdapWriteBack {
+    /// Hashed secrets cannot be sent using the password modify operation,
+    /// as the server would store them as plain-text passwords.
+    fn supports_password(&self, secret: &str) -> bool {
+        secret.is_password()
+            && match self.password {
+                LdapPasswordUpdate::Disabled => false,
+                LdapPasswordUpdate::PasswordModify => !is_hashed_secret(secret),
+                LdapPasswordUpdate::Replace { .. } => true,
+            }
+    }
+}
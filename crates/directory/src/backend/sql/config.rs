@@ -54,6 +54,7 @@ impl SqlDirectory {
             ("verify", &mut mappings.query_verify),
             ("expand", &mut mappings.query_expand),
             ("domains", &mut mappings.query_domains),
+            ("list", &mut mappings.query_list),
         ] {
             *query = config
                 .value(("store", store_id.as_str(), "query", query_id))
@@ -104,25 +104,14 @@ impl SqlDirectory {
 
         // Obtain members
         if return_member_of && !self.mappings.query_members.is_empty() {
-            for row in self
-                .store
-                .query::<Rows>(
-                    &self.mappings.query_members,
-                    vec![external_principal.name().into()],
-                )
-                .await
-                .caused_by(trc::location!())?
-                .rows
-            {
-                if let Some(Value::Text(account_id)) = row.values.first() {
-                    external_principal.append_int(
-                        PrincipalField::MemberOf,
-                        self.data_store
-                            .get_or_create_principal_id(account_id, Type::Group)
-                            .await
-                            .caused_by(trc::location!())?,
-                    );
-                }
+            for name in self.member_of_names(external_principal.name()).await? {
+                external_principal.append_int(
+                    PrincipalField::MemberOf,
+                    self.data_store
+                        .get_or_create_principal_id(&name, Type::Group)
+                        .await
+                        .caused_by(trc::location!())?,
+                );
             }
         }
 
@@ -130,16 +119,7 @@ impl SqlDirectory {
         if !self.mappings.query_emails.is_empty() {
             external_principal.set(
                 PrincipalField::Emails,
-                PrincipalValue::StringList(
-                    self.store
-                        .query::<Rows>(
-                            &self.mappings.query_emails,
-                            vec![external_principal.name().into()],
-                        )
-                        .await
-                        .caused_by(trc::location!())?
-                        .into(),
-                ),
+                PrincipalValue::StringList(self.email_addresses(external_principal.name()).await?),
             );
         }
 
@@ -176,6 +156,81 @@ impl SqlDirectory {
         Ok(Some(principal))
     }
 
+    /// Fetches all principals returned by the list query, group memberships are returned as names.
+    pub async fn list_principals(&self) -> trc::Result<Vec<Principal>> {
+        if self.mappings.query_list.is_empty() {
+            return Err(trc::StoreEvent::NotConfigured
+                .into_err()
+                .details("SQL list query is not configured"));
+        }
+
+        let rows = self
+            .store
+            .query::<Rows>(&self.mappings.query_list, vec![])
+            .await
+            .caused_by(trc::location!())?;
+        let mut principals = Vec::with_capacity(rows.rows.len());
+
+        for row in rows.rows {
+            let Some(name) = row
+                .values
+                .into_iter()
+                .next()
+                .map(|v| v.into_string())
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            let Some(mut principal) = self
+                .mappings
+                .row_to_principal(
+                    self.store
+                        .query::<NamedRows>(&self.mappings.query_name, vec![name.as_str().into()])
+                        .await
+                        .caused_by(trc::location!())?,
+                )
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+
+            if !self.mappings.query_members.is_empty() {
+                principal.set(PrincipalField::MemberOf, self.member_of_names(&name).await?);
+            }
+            if !self.mappings.query_emails.is_empty() {
+                principal.set(PrincipalField::Emails, self.email_addresses(&name).await?);
+            }
+
+            principals.push(principal.with_field(PrincipalField::Name, name));
+        }
+
+        Ok(principals)
+    }
+
+    async fn member_of_names(&self, name: &str) -> trc::Result<Vec<String>> {
+        self.store
+            .query::<Rows>(&self.mappings.query_members, vec![name.into()])
+            .await
+            .caused_by(trc::location!())
+            .map(|rows| {
+                rows.rows
+                    .into_iter()
+                    .filter_map(|row| match row.values.into_iter().next() {
+                        Some(Value::Text(name)) => Some(name.into_owned()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+    }
+
+    async fn email_addresses(&self, name: &str) -> trc::Result<Vec<String>> {
+        self.store
+            .query::<Rows>(&self.mappings.query_emails, vec![name.into()])
+            .await
+            .caused_by(trc::location!())
+            .map(Into::into)
+    }
+
     pub async fn email_to_ids(&self, address: &str) -> trc::Result<Vec<u32>> {
         let names = self
             .store
@@ -24,6 +24,7 @@ pub(crate) struct SqlMappings {
     query_domains: String,
     query_verify: String,
     query_expand: String,
+    query_list: String,
     column_description: String,
     column_secret: Vec<String>,
     column_quota: String,
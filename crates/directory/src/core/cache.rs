@@ -7,17 +7,30 @@
 use std::{
     borrow::Borrow,
     hash::Hash,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
+use ahash::AHashMap;
 use parking_lot::Mutex;
 use utils::config::{utils::AsKey, Config};
 
+use super::groups::DynamicGroup;
+
 pub struct CachedDirectory {
     cached_domains: Mutex<LookupCache<String>>,
     cached_rcpts: Mutex<LookupCache<String>>,
 }
 
+/// Caches the dynamic group definitions and the expanded members of mailing lists.
+#[allow(clippy::type_complexity)]
+pub struct GroupCache {
+    dynamic: Mutex<Option<(Arc<Vec<DynamicGroup>>, Instant)>>,
+    dynamic_members: Mutex<Option<(Arc<AHashMap<u32, Vec<u32>>>, Instant)>>,
+    lists: Mutex<lru_cache::LruCache<u32, (Arc<Vec<u32>>, Instant), ahash::RandomState>>,
+    ttl: Duration,
+}
+
 #[allow(clippy::type_complexity)]
 #[derive(Debug)]
 pub struct LookupCache<T: Hash + Eq> {
@@ -77,6 +90,91 @@ impl CachedDirectory {
     }
 }
 
+impl GroupCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            dynamic: Mutex::new(None),
+            dynamic_members: Mutex::new(None),
+            lists: Mutex::new(lru_cache::LruCache::with_hasher(
+                capacity,
+                ahash::RandomState::new(),
+            )),
+            ttl,
+        }
+    }
+
+    pub fn from_config(config: &mut Config, prefix: impl AsKey) -> Self {
+        let prefix = prefix.as_key();
+        Self::new(
+            config
+                .property((&prefix, "cache.groups.size"))
+                .unwrap_or(1024),
+            config
+                .property((&prefix, "cache.groups.ttl"))
+                .unwrap_or(Duration::from_secs(300)),
+        )
+    }
+
+    pub fn get_dynamic(&self) -> Option<Arc<Vec<DynamicGroup>>> {
+        self.dynamic
+            .lock()
+            .as_ref()
+            .filter(|(_, valid_until)| *valid_until >= Instant::now())
+            .map(|(groups, _)| groups.clone())
+    }
+
+    pub fn set_dynamic(&self, groups: Arc<Vec<DynamicGroup>>) {
+        *self.dynamic.lock() = Some((groups, Instant::now() + self.ttl));
+    }
+
+    pub fn get_dynamic_members(&self) -> Option<Arc<AHashMap<u32, Vec<u32>>>> {
+        self.dynamic_members
+            .lock()
+            .as_ref()
+            .filter(|(_, valid_until)| *valid_until >= Instant::now())
+            .map(|(members, _)| members.clone())
+    }
+
+    pub fn set_dynamic_members(&self, members: Arc<AHashMap<u32, Vec<u32>>>) {
+        *self.dynamic_members.lock() = Some((members, Instant::now() + self.ttl));
+    }
+
+    pub fn get_list(&self, list_id: u32) -> Option<Arc<Vec<u32>>> {
+        let mut lists = self.lists.lock();
+        let (members, valid_until) = lists.get_mut(&list_id)?;
+        if *valid_until >= Instant::now() {
+            Some(members.clone())
+        } else {
+            lists.remove(&list_id);
+            None
+        }
+    }
+
+    pub fn set_list(&self, list_id: u32, members: Arc<Vec<u32>>) {
+        self.lists
+            .lock()
+            .insert(list_id, (members, Instant::now() + self.ttl));
+    }
+
+    pub fn clear(&self) {
+        *self.dynamic.lock() = None;
+        self.clear_members();
+    }
+
+    /// Removes the expanded list members, which can change when any principal
+    /// is created, modified or deleted.
+    pub fn clear_members(&self) {
+        *self.dynamic_members.lock() = None;
+        self.lists.lock().clear();
+    }
+}
+
+impl Default for GroupCache {
+    fn default() -> Self {
+        Self::new(1024, Duration::from_secs(300))
+    }
+}
+
 impl<T: Hash + Eq> LookupCache<T> {
     pub fn new(capacity: usize, ttl_pos: Duration, ttl_neg: Duration) -> Self {
         Self {
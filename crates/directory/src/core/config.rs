@@ -22,7 +22,10 @@ use crate::{
     Directories, Directory, DirectoryInner,
 };
 
-use super::cache::CachedDirectory;
+use super::{
+    cache::{CachedDirectory, GroupCache},
+    sync::DirectorySync,
+};
 
 impl Directories {
     pub async fn parse(
@@ -111,6 +114,8 @@ impl Directories {
                 let directory = Arc::new(Directory {
                     store,
                     cache: CachedDirectory::try_from_config(config, ("directory", id)),
+                    groups: GroupCache::from_config(config, ("directory", id)),
+                    sync: DirectorySync::parse(config, id),
                 });
 
                 // Add directory
@@ -7,7 +7,8 @@
 use trc::AddContext;
 
 use crate::{
-    backend::internal::lookup::DirectoryStore, Directory, DirectoryInner, Principal, QueryBy,
+    backend::internal::{lookup::DirectoryStore, PrincipalUpdate},
+    Directory, DirectoryInner, Principal, QueryBy,
 };
 
 impl Directory {
@@ -31,7 +32,7 @@ impl Directory {
 
     pub async fn email_to_ids(&self, email: &str) -> trc::Result<Vec<u32>> {
         match &self.store {
-            DirectoryInner::Internal(store) => store.email_to_ids(email).await,
+            DirectoryInner::Internal(store) => self.internal_email_to_ids(store, email).await,
             DirectoryInner::Ldap(store) => store.email_to_ids(email).await,
             DirectoryInner::Sql(store) => store.email_to_ids(email).await,
             DirectoryInner::Imap(store) => store.email_to_ids(email).await,
@@ -115,7 +116,7 @@ impl Directory {
 
     pub async fn expn(&self, address: &str) -> trc::Result<Vec<String>> {
         match &self.store {
-            DirectoryInner::Internal(store) => store.expn(address).await,
+            DirectoryInner::Internal(store) => self.internal_expn(store, address).await,
             DirectoryInner::Ldap(store) => store.expn(address).await,
             DirectoryInner::Sql(store) => store.expn(address).await,
             DirectoryInner::Imap(store) => store.expn(address).await,
@@ -127,6 +128,44 @@ impl Directory {
         .caused_by(trc::location!())
     }
 
+    /// Returns whether the principal changes can be stored by this directory.
+    pub fn supports_updates(&self, changes: &[PrincipalUpdate]) -> bool {
+        match &self.store {
+            DirectoryInner::Internal(_) => true,
+            DirectoryInner::Ldap(store) => changes.iter().all(|c| store.supports_update(c)),
+            DirectoryInner::Sql(_)
+            | DirectoryInner::Imap(_)
+            | DirectoryInner::Smtp(_)
+            | DirectoryInner::Memory(_) => false,
+            #[cfg(feature = "enterprise")]
+            DirectoryInner::OpenId(_) => false,
+        }
+    }
+
+    /// Writes the supported principal changes back to external directories,
+    /// returns `true` if any changes were written.
+    pub async fn write_back(&self, name: &str, changes: &[PrincipalUpdate]) -> trc::Result<bool> {
+        match &self.store {
+            DirectoryInner::Ldap(store) => {
+                let changes = changes
+                    .iter()
+                    .filter(|c| store.supports_update(c))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !changes.is_empty() {
+                    store
+                        .write_back(name, &changes)
+                        .await
+                        .caused_by(trc::location!())?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
     pub fn has_bearer_token_support(&self) -> bool {
         match &self.store {
             DirectoryInner::Internal(_)
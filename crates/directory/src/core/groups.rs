@@ -0,0 +1,638 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use ahash::{AHashMap, AHashSet};
+use store::{
+    write::{DirectoryClass, ValueClass},
+    Store, ValueKey,
+};
+use trc::AddContext;
+use utils::glob::GlobPattern;
+
+use crate::{
+    backend::internal::{
+        lookup::DirectoryStore, manage::ManageDirectory, PrincipalField, PrincipalInfo,
+    },
+    Directory, DirectoryInner, Principal, QueryBy, Type,
+};
+
+/// Maximum nesting level followed when expanding groups and mailing lists.
+pub const MAX_GROUP_DEPTH: usize = 16;
+
+const MAX_FILTER_DEPTH: usize = 32;
+
+/// Membership expression of a dynamic group or mailing list, for example
+/// `domain = "example.org" and not role = "admin"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberFilter {
+    And(Vec<MemberFilter>),
+    Or(Vec<MemberFilter>),
+    Not(Box<MemberFilter>),
+    Condition {
+        field: FilterField,
+        value: FilterValue,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterField {
+    Name,
+    Type,
+    Email,
+    Domain,
+    Description,
+    Role,
+    Group,
+    Tenant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterValue {
+    Pattern(GlobPattern),
+    Type(Type),
+    Name(String),
+    Id(Option<u32>),
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicGroup {
+    pub id: u32,
+    pub typ: Type,
+    pub tenant: Option<u32>,
+    pub filter: MemberFilter,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Equal,
+    NotEqual,
+    Word(String),
+    Quoted(String),
+}
+
+impl MemberFilter {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let tokens = tokenize(expr)?;
+        let mut pos = 0;
+        let filter = parse_or(&tokens, &mut pos, 0)?;
+        if pos == tokens.len() {
+            Ok(filter)
+        } else {
+            Err(format!("Unexpected token at position {}", pos + 1))
+        }
+    }
+
+    pub fn matches(&self, principal: &Principal) -> bool {
+        match self {
+            MemberFilter::And(filters) => filters.iter().all(|f| f.matches(principal)),
+            MemberFilter::Or(filters) => filters.iter().any(|f| f.matches(principal)),
+            MemberFilter::Not(filter) => !filter.matches(principal),
+            MemberFilter::Condition { field, value } => match (field, value) {
+                (FilterField::Name, FilterValue::Pattern(pattern)) => {
+                    pattern.matches(principal.name())
+                }
+                (FilterField::Description, FilterValue::Pattern(pattern)) => principal
+                    .description()
+                    .is_some_and(|value| pattern.matches(value)),
+                (FilterField::Email, FilterValue::Pattern(pattern)) => principal
+                    .iter_str(PrincipalField::Emails)
+                    .any(|email| pattern.matches(email)),
+                (FilterField::Domain, FilterValue::Pattern(pattern)) => principal
+                    .iter_str(PrincipalField::Emails)
+                    .filter_map(|email| email.rsplit_once('@'))
+                    .any(|(_, domain)| pattern.matches(domain)),
+                (FilterField::Type, FilterValue::Type(typ)) => principal.typ() == *typ,
+                (FilterField::Role, FilterValue::Id(Some(id))) => {
+                    principal.has_int_value(PrincipalField::Roles, *id as u64)
+                }
+                (FilterField::Group, FilterValue::Id(Some(id))) => {
+                    principal.has_int_value(PrincipalField::MemberOf, *id as u64)
+                }
+                (FilterField::Tenant, FilterValue::Id(Some(id))) => principal.tenant() == Some(*id),
+                _ => false,
+            },
+        }
+    }
+
+    /// Maps the role, group and tenant names referenced by the filter to their ids.
+    pub async fn resolve(&mut self, store: &Store) -> trc::Result<()> {
+        let mut names = Vec::new();
+        self.visit_mut(&mut |field, value| {
+            if let FilterValue::Name(name) = value {
+                names.push((*field, name.clone()));
+            }
+        });
+
+        let mut ids = AHashMap::with_capacity(names.len());
+        for (field, name) in names {
+            let id = match store
+                .get_principal_info(&name)
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(info)
+                    if matches!(
+                        (field, info.typ),
+                        (FilterField::Role, Type::Role)
+                            | (FilterField::Group, Type::Group)
+                            | (FilterField::Tenant, Type::Tenant)
+                    ) =>
+                {
+                    Some(info.id)
+                }
+                _ if field == FilterField::Role => {
+                    PrincipalField::Roles.map_internal_role_name(&name)
+                }
+                _ => None,
+            };
+            ids.insert((field, name), id);
+        }
+
+        self.visit_mut(&mut |field, value| {
+            if let FilterValue::Name(name) = value {
+                *value =
+                    FilterValue::Id(ids.get(&(*field, std::mem::take(name))).copied().flatten());
+            }
+        });
+
+        Ok(())
+    }
+
+    fn visit_mut(&mut self, cb: &mut impl FnMut(&FilterField, &mut FilterValue)) {
+        match self {
+            MemberFilter::And(filters) | MemberFilter::Or(filters) => {
+                for filter in filters {
+                    filter.visit_mut(cb);
+                }
+            }
+            MemberFilter::Not(filter) => filter.visit_mut(cb),
+            MemberFilter::Condition { field, value } => cb(field, value),
+        }
+    }
+}
+
+impl FilterField {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(FilterField::Name),
+            "type" => Some(FilterField::Type),
+            "email" => Some(FilterField::Email),
+            "domain" => Some(FilterField::Domain),
+            "description" => Some(FilterField::Description),
+            "role" => Some(FilterField::Role),
+            "group" => Some(FilterField::Group),
+            "tenant" => Some(FilterField::Tenant),
+            _ => None,
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '=' => tokens.push(Token::Equal),
+            '!' if chars.next_if_eq(&'=').is_some() => tokens.push(Token::NotEqual),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            if let Some(ch) = chars.next() {
+                                value.push(ch);
+                            }
+                        }
+                        Some('"') => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err("Unterminated quoted string".to_string()),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            ch if ch.is_whitespace() => (),
+            _ => {
+                let mut value = String::from(ch);
+                while let Some(ch) =
+                    chars.next_if(|ch| !ch.is_whitespace() && !matches!(ch, '(' | ')' | '=' | '!'))
+                {
+                    value.push(ch);
+                }
+                tokens.push(Token::Word(value));
+            }
+        }
+    }
+
+    if !tokens.is_empty() {
+        Ok(tokens)
+    } else {
+        Err("Empty filter".to_string())
+    }
+}
+
+fn parse_or(tokens: &[Token], pos: &mut usize, depth: usize) -> Result<MemberFilter, String> {
+    let mut filters = vec![parse_and(tokens, pos, depth)?];
+    while matches!(tokens.get(*pos), Some(Token::Word(word)) if word.eq_ignore_ascii_case("or")) {
+        *pos += 1;
+        filters.push(parse_and(tokens, pos, depth)?);
+    }
+
+    Ok(if filters.len() == 1 {
+        filters.pop().unwrap()
+    } else {
+        MemberFilter::Or(filters)
+    })
+}
+
+fn parse_and(tokens: &[Token], pos: &mut usize, depth: usize) -> Result<MemberFilter, String> {
+    let mut filters = vec![parse_unary(tokens, pos, depth)?];
+    while matches!(tokens.get(*pos), Some(Token::Word(word)) if word.eq_ignore_ascii_case("and")) {
+        *pos += 1;
+        filters.push(parse_unary(tokens, pos, depth)?);
+    }
+
+    Ok(if filters.len() == 1 {
+        filters.pop().unwrap()
+    } else {
+        MemberFilter::And(filters)
+    })
+}
+
+fn parse_unary(tokens: &[Token], pos: &mut usize, depth: usize) -> Result<MemberFilter, String> {
+    if depth >= MAX_FILTER_DEPTH {
+        return Err("Filter is nested too deeply".to_string());
+    }
+
+    match tokens.get(*pos) {
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("not") => {
+            *pos += 1;
+            parse_unary(tokens, pos, depth + 1).map(|filter| MemberFilter::Not(Box::new(filter)))
+        }
+        Some(Token::Open) => {
+            *pos += 1;
+            let filter = parse_or(tokens, pos, depth + 1)?;
+            if tokens.get(*pos) == Some(&Token::Close) {
+                *pos += 1;
+                Ok(filter)
+            } else {
+                Err("Missing closing parenthesis".to_string())
+            }
+        }
+        Some(Token::Word(word)) => {
+            let field = FilterField::parse(&word.to_lowercase())
+                .ok_or_else(|| format!("Unknown field {word:?}"))?;
+            let is_negated = match tokens.get(*pos + 1) {
+                Some(Token::Equal) => false,
+                Some(Token::NotEqual) => true,
+                _ => return Err(format!("Expected '=' or '!=' after {word:?}")),
+            };
+            let value = match tokens.get(*pos + 2) {
+                Some(Token::Word(value) | Token::Quoted(value)) => value,
+                _ => return Err(format!("Missing value for {word:?}")),
+            };
+            *pos += 3;
+
+            let value = match field {
+                FilterField::Name
+                | FilterField::Email
+                | FilterField::Domain
+                | FilterField::Description => {
+                    FilterValue::Pattern(GlobPattern::compile(value, true))
+                }
+                FilterField::Type => FilterValue::Type(
+                    Type::parse(value)
+                        .ok_or_else(|| format!("Invalid principal type {value:?}"))?,
+                ),
+                FilterField::Role | FilterField::Group | FilterField::Tenant => {
+                    FilterValue::Name(value.to_lowercase())
+                }
+            };
+            let filter = MemberFilter::Condition { field, value };
+
+            Ok(if is_negated {
+                MemberFilter::Not(Box::new(filter))
+            } else {
+                filter
+            })
+        }
+        Some(_) => Err(format!("Unexpected token at position {}", *pos + 1)),
+        None => Err("Unexpected end of filter".to_string()),
+    }
+}
+
+impl Directory {
+    /// Returns the groups a principal belongs to, following nested groups and
+    /// including the dynamic groups whose filter matches the principal.
+    pub async fn expand_member_of(&self, principal: &Principal) -> trc::Result<Vec<u32>> {
+        let mut member_of = principal
+            .iter_int(PrincipalField::MemberOf)
+            .map(|id| id as u32)
+            .collect::<Vec<_>>();
+
+        // Only groups are followed, an account can also be a member of another account
+        let mut pending = match &self.store {
+            DirectoryInner::Internal(store) => store
+                .get_member_of(principal.id())
+                .await
+                .caused_by(trc::location!())?
+                .into_iter()
+                .filter(|member| member.typ == Type::Group)
+                .map(|member| member.principal_id)
+                .collect(),
+            _ => member_of.clone(),
+        };
+
+        // Add dynamic groups
+        for group in self.dynamic_groups().await?.iter() {
+            if group.typ == Type::Group
+                && group.id != principal.id()
+                && !member_of.contains(&group.id)
+                && group.matches(principal)
+            {
+                member_of.push(group.id);
+                pending.push(group.id);
+            }
+        }
+
+        // Follow nested groups, the visited set breaks any membership cycles
+        let mut seen = member_of.iter().copied().collect::<AHashSet<_>>();
+        seen.insert(principal.id());
+        for _ in 0..MAX_GROUP_DEPTH {
+            if pending.is_empty() {
+                break;
+            }
+            let mut next = Vec::new();
+            for group_id in pending {
+                for parent_id in self.parent_groups(group_id).await? {
+                    if seen.insert(parent_id) {
+                        member_of.push(parent_id);
+                        next.push(parent_id);
+                    }
+                }
+            }
+            pending = next;
+        }
+
+        Ok(member_of)
+    }
+
+    /// Returns the dynamic groups and mailing lists defined in the internal directory.
+    pub async fn dynamic_groups(&self) -> trc::Result<Arc<Vec<DynamicGroup>>> {
+        let DirectoryInner::Internal(store) = &self.store else {
+            return Ok(Arc::new(Vec::new()));
+        };
+        if let Some(groups) = self.groups.get_dynamic() {
+            return Ok(groups);
+        }
+
+        let mut groups = Vec::new();
+        for principal in store
+            .list_principals(
+                None,
+                None,
+                &[Type::Group, Type::List],
+                &[PrincipalField::Name],
+                0,
+                0,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .items
+        {
+            let Some(principal) = store
+                .get_principal(principal.id())
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let Some(filter) = principal.get_str(PrincipalField::MemberFilter) else {
+                continue;
+            };
+
+            match MemberFilter::parse(filter) {
+                Ok(mut filter) => {
+                    filter.resolve(store).await?;
+                    groups.push(DynamicGroup {
+                        id: principal.id(),
+                        typ: principal.typ(),
+                        tenant: principal.tenant(),
+                        filter,
+                    });
+                }
+                Err(reason) => {
+                    trc::event!(
+                        Store(trc::StoreEvent::UnexpectedError),
+                        Id = principal.id(),
+                        Details = "Invalid member filter",
+                        Reason = reason,
+                    );
+                }
+            }
+        }
+
+        let groups = Arc::new(groups);
+        self.groups.set_dynamic(groups.clone());
+        Ok(groups)
+    }
+
+    /// Returns the accounts a mailing list delivers to, following nested lists.
+    pub async fn expand_list(&self, list_id: u32) -> trc::Result<Arc<Vec<u32>>> {
+        let DirectoryInner::Internal(store) = &self.store else {
+            return Ok(Arc::new(Vec::new()));
+        };
+        if let Some(members) = self.groups.get_list(list_id) {
+            return Ok(members);
+        }
+
+        let mut members = Vec::new();
+        let mut seen = AHashSet::from_iter([list_id]);
+        let mut pending = vec![list_id];
+        for _ in 0..MAX_GROUP_DEPTH {
+            if pending.is_empty() {
+                break;
+            }
+            let mut next = Vec::new();
+            for list_id in pending {
+                for member_id in self.list_members(store, list_id).await? {
+                    if seen.insert(member_id) {
+                        let is_list = store
+                            .get_principal(member_id)
+                            .await
+                            .caused_by(trc::location!())?
+                            .is_some_and(|p| p.typ() == Type::List);
+                        if is_list {
+                            next.push(member_id);
+                        } else {
+                            members.push(member_id);
+                        }
+                    }
+                }
+            }
+            pending = next;
+        }
+
+        let members = Arc::new(members);
+        self.groups.set_list(list_id, members.clone());
+        Ok(members)
+    }
+
+    pub(crate) async fn internal_email_to_ids(
+        &self,
+        store: &Store,
+        email: &str,
+    ) -> trc::Result<Vec<u32>> {
+        match store
+            .get_value::<PrincipalInfo>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::EmailToId(email.as_bytes().to_vec()),
+            )))
+            .await?
+        {
+            Some(info) if info.typ == Type::List => {
+                self.expand_list(info.id).await.map(|ids| ids.to_vec())
+            }
+            Some(info) => Ok(vec![info.id]),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub(crate) async fn internal_expn(
+        &self,
+        store: &Store,
+        address: &str,
+    ) -> trc::Result<Vec<String>> {
+        let mut results = Vec::new();
+        if let Some(info) = store
+            .get_value::<PrincipalInfo>(ValueKey::from(ValueClass::Directory(
+                DirectoryClass::EmailToId(address.as_bytes().to_vec()),
+            )))
+            .await?
+            .filter(|p| p.typ == Type::List)
+        {
+            for account_id in self.expand_list(info.id).await?.iter() {
+                if let Some(email) = store
+                    .get_principal(*account_id)
+                    .await?
+                    .and_then(|mut p| p.take_str(PrincipalField::Emails))
+                {
+                    results.push(email);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn parent_groups(&self, group_id: u32) -> trc::Result<Vec<u32>> {
+        match &self.store {
+            DirectoryInner::Internal(store) => store
+                .get_member_of(group_id)
+                .await
+                .caused_by(trc::location!())
+                .map(|member_of| {
+                    member_of
+                        .into_iter()
+                        .filter(|member| member.typ == Type::Group)
+                        .map(|member| member.principal_id)
+                        .collect()
+                }),
+            _ => self
+                .query(QueryBy::Id(group_id), true)
+                .await
+                .map(|principal| {
+                    principal
+                        .map(|principal| {
+                            principal
+                                .iter_int(PrincipalField::MemberOf)
+                                .map(|id| id as u32)
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                }),
+        }
+    }
+
+    async fn list_members(&self, store: &Store, list_id: u32) -> trc::Result<Vec<u32>> {
+        let mut members = store
+            .get_members(list_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        if let Some(dynamic_members) = self.dynamic_list_members(store).await?.get(&list_id) {
+            for member_id in dynamic_members {
+                if !members.contains(member_id) {
+                    members.push(*member_id);
+                }
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Returns the members of all dynamic mailing lists, which are obtained
+    /// with a single pass over the accounts rather than once per list.
+    async fn dynamic_list_members(
+        &self,
+        store: &Store,
+    ) -> trc::Result<Arc<AHashMap<u32, Vec<u32>>>> {
+        if let Some(members) = self.groups.get_dynamic_members() {
+            return Ok(members);
+        }
+
+        let lists = self
+            .dynamic_groups()
+            .await?
+            .iter()
+            .filter(|group| group.typ == Type::List)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut members: AHashMap<u32, Vec<u32>> = AHashMap::with_capacity(lists.len());
+        if !lists.is_empty() {
+            for principal in store
+                .list_principals(
+                    None,
+                    None,
+                    &[Type::Individual],
+                    &[PrincipalField::Name],
+                    0,
+                    0,
+                )
+                .await
+                .caused_by(trc::location!())?
+                .items
+            {
+                if let Some(principal) = store
+                    .query(QueryBy::Id(principal.id()), true)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    for list in lists.iter().filter(|list| list.matches(&principal)) {
+                        members.entry(list.id).or_default().push(principal.id());
+                    }
+                }
+            }
+        }
+
+        let members = Arc::new(members);
+        self.groups.set_dynamic_members(members.clone());
+        Ok(members)
+    }
+}
+
+impl DynamicGroup {
+    pub fn matches(&self, principal: &Principal) -> bool {
+        self.tenant
+            .is_none_or(|tenant| principal.tenant() == Some(tenant))
+            && self.filter.matches(principal)
+    }
+}
@@ -9,8 +9,10 @@ use crate::Permission;
 pub mod cache;
 pub mod config;
 pub mod dispatch;
+pub mod groups;
 pub mod principal;
 pub mod secret;
+pub mod sync;
 
 impl Permission {
     pub fn description(&self) -> &'static str {
@@ -197,6 +199,29 @@ impl Permission {
             Permission::OauthClientCreate => "Create new OAuth clients",
             Permission::OauthClientUpdate => "Modify OAuth clients",
             Permission::OauthClientDelete => "Remove OAuth clients",
+            Permission::StoreFsck => "Check and repair store consistency",
+            Permission::StoreShardMove => "Move accounts between data store shards",
+            Permission::StoreQuotaRecalculate => "Recalculate used quotas of accounts and tenants",
+            Permission::ManageProfile => "Manage own display name and mail forwarding",
+            Permission::ManageAliases => "Request email aliases for own account",
+            Permission::DirectorySync => "Synchronize external directories",
+            Permission::JmapShareNotificationGet => "Retrieve share notifications via JMAP",
+            Permission::JmapShareNotificationSet => "Dismiss share notifications via JMAP",
+            Permission::JmapShareNotificationChanges => "Track share notification changes via JMAP",
+            Permission::JmapShareNotificationQuery => "Perform share notification queries via JMAP",
+            Permission::JmapBlobConvert => "Generate blob thumbnails and previews via JMAP",
+            Permission::JmapEmailTemplateGet => "Retrieve email templates via JMAP",
+            Permission::JmapEmailTemplateSet => "Create, modify or delete email templates via JMAP",
+            Permission::JmapEmailTemplateChanges => "Track email template changes via JMAP",
+            Permission::JmapEmailSubmissionMerge => {
+                "Send templated emails to multiple recipients via JMAP"
+            }
+            Permission::JmapMailRulesGet => "Retrieve mail rules via JMAP",
+            Permission::JmapMailRulesSet => "Modify mail rules via JMAP",
+            Permission::JmapSavedSearchGet => "Retrieve saved searches via JMAP",
+            Permission::JmapSavedSearchSet => "Create, modify or delete saved searches via JMAP",
+            Permission::JmapSavedSearchChanges => "Track saved search changes via JMAP",
+            Permission::JmapThreadSet => "Mute threads and apply actions to whole threads via JMAP",
         }
     }
 }
@@ -15,7 +15,7 @@ use store::U64_LEN;
 
 use crate::{
     backend::internal::{PrincipalField, PrincipalUpdate, PrincipalValue},
-    Permission, Principal, Type, ROLE_ADMIN,
+    AccountStatus, Permission, Principal, Type, ROLE_ADMIN,
 };
 
 impl Principal {
@@ -55,6 +55,12 @@ impl Principal {
     }
     // SPDX-SnippetEnd
 
+    pub fn status(&self) -> AccountStatus {
+        self.get_str(PrincipalField::Status)
+            .and_then(AccountStatus::parse)
+            .unwrap_or_default()
+    }
+
     pub fn description(&self) -> Option<&str> {
         self.get_str(PrincipalField::Description)
     }
@@ -310,6 +316,27 @@ impl Principal {
         self.fields.values().any(|v| v.find_str(value))
     }
 
+    /// Returns `true` if the principal falls within the given domain scope,
+    /// that is, if it is one of the domains or all its e-mail addresses
+    /// belong to them. An empty scope grants access to every principal.
+    pub fn has_domain_access(&self, domains: &[String]) -> bool {
+        if domains.is_empty() {
+            return true;
+        } else if self.typ == Type::Domain {
+            return domains
+                .iter()
+                .any(|domain| domain.eq_ignore_ascii_case(self.name()));
+        }
+
+        let mut emails = self.iter_str(PrincipalField::Emails).peekable();
+        emails.peek().is_some()
+            && emails.all(|email| {
+                email.rsplit_once('@').is_some_and(|(_, domain)| {
+                    domains.iter().any(|d| d.eq_ignore_ascii_case(domain))
+                })
+            })
+    }
+
     pub fn field_len(&self, key: PrincipalField) -> usize {
         self.fields.get(&key).map_or(0, |v| match v {
             PrincipalValue::String(_) => 1,
@@ -579,6 +606,27 @@ impl From<Vec<u32>> for PrincipalValue {
     }
 }
 
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Suspended => "suspended",
+            AccountStatus::ReadOnly => "read-only",
+            AccountStatus::PendingDeletion => "pending-deletion",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(AccountStatus::Active),
+            "suspended" => Some(AccountStatus::Suspended),
+            "read-only" => Some(AccountStatus::ReadOnly),
+            "pending-deletion" => Some(AccountStatus::PendingDeletion),
+            _ => None,
+        }
+    }
+}
+
 impl Type {
     pub fn to_jmap(&self) -> &'static str {
         match self {
@@ -818,7 +866,9 @@ impl<'de> serde::Deserialize<'de> for Principal {
                         }
                         PrincipalField::Description
                         | PrincipalField::Tenant
-                        | PrincipalField::Picture => {
+                        | PrincipalField::Picture
+                        | PrincipalField::MemberFilter
+                        | PrincipalField::Status => {
                             if let Some(v) = map.next_value::<Option<String>>()? {
                                 if v.len() <= MAX_STRING_LEN {
                                     PrincipalValue::String(v)
@@ -844,7 +894,9 @@ impl<'de> serde::Deserialize<'de> for Principal {
                         | PrincipalField::Lists
                         | PrincipalField::EnabledPermissions
                         | PrincipalField::DisabledPermissions
-                        | PrincipalField::Urls => match map.next_value::<StringOrMany>()? {
+                        | PrincipalField::Urls
+                        | PrincipalField::ForwardTo
+                        | PrincipalField::DomainScope => match map.next_value::<StringOrMany>()? {
                             StringOrMany::One(v) => PrincipalValue::StringList(vec![v]),
                             StringOrMany::Many(v) => {
                                 if !v.is_empty() {
@@ -854,7 +906,7 @@ impl<'de> serde::Deserialize<'de> for Principal {
                                 }
                             }
                         },
-                        PrincipalField::UsedQuota => {
+                        PrincipalField::UsedQuota | PrincipalField::StatusSince => {
                             // consume and ignore
                             map.next_value::<IgnoredAny>()?;
                             continue;
@@ -1000,6 +1052,8 @@ impl Permission {
                 | Permission::EmailReceive
                 | Permission::ManageEncryption
                 | Permission::ManagePasswords
+                | Permission::ManageProfile
+                | Permission::ManageAliases
                 | Permission::JmapEmailGet
                 | Permission::JmapMailboxGet
                 | Permission::JmapThreadGet
@@ -1008,21 +1062,34 @@ impl Permission {
                 | Permission::JmapPushSubscriptionGet
                 | Permission::JmapSieveScriptGet
                 | Permission::JmapVacationResponseGet
+                | Permission::JmapMailRulesGet
                 | Permission::JmapQuotaGet
                 | Permission::JmapBlobGet
+                | Permission::JmapShareNotificationGet
+                | Permission::JmapEmailTemplateGet
+                | Permission::JmapSavedSearchGet
                 | Permission::JmapEmailSet
                 | Permission::JmapMailboxSet
                 | Permission::JmapIdentitySet
                 | Permission::JmapEmailSubmissionSet
+                | Permission::JmapEmailSubmissionMerge
                 | Permission::JmapPushSubscriptionSet
                 | Permission::JmapSieveScriptSet
                 | Permission::JmapVacationResponseSet
+                | Permission::JmapMailRulesSet
+                | Permission::JmapShareNotificationSet
+                | Permission::JmapEmailTemplateSet
+                | Permission::JmapSavedSearchSet
+                | Permission::JmapThreadSet
                 | Permission::JmapEmailChanges
                 | Permission::JmapMailboxChanges
                 | Permission::JmapThreadChanges
                 | Permission::JmapIdentityChanges
                 | Permission::JmapEmailSubmissionChanges
                 | Permission::JmapQuotaChanges
+                | Permission::JmapShareNotificationChanges
+                | Permission::JmapEmailTemplateChanges
+                | Permission::JmapSavedSearchChanges
                 | Permission::JmapEmailCopy
                 | Permission::JmapBlobCopy
                 | Permission::JmapEmailImport
@@ -1037,10 +1104,12 @@ impl Permission {
                 | Permission::JmapEmailSubmissionQuery
                 | Permission::JmapSieveScriptQuery
                 | Permission::JmapQuotaQuery
+                | Permission::JmapShareNotificationQuery
                 | Permission::JmapSearchSnippet
                 | Permission::JmapSieveScriptValidate
                 | Permission::JmapBlobLookup
                 | Permission::JmapBlobUpload
+                | Permission::JmapBlobConvert
                 | Permission::JmapEcho
                 | Permission::ImapAuthenticate
                 | Permission::ImapAclGet
@@ -1088,6 +1157,29 @@ impl Permission {
         )
     }
 
+    pub const fn is_domain_admin_permission(&self) -> bool {
+        matches!(
+            self,
+            Permission::IndividualList
+                | Permission::IndividualGet
+                | Permission::IndividualUpdate
+                | Permission::IndividualDelete
+                | Permission::IndividualCreate
+                | Permission::GroupList
+                | Permission::GroupGet
+                | Permission::GroupUpdate
+                | Permission::GroupDelete
+                | Permission::GroupCreate
+                | Permission::MailingListList
+                | Permission::MailingListGet
+                | Permission::MailingListCreate
+                | Permission::MailingListUpdate
+                | Permission::MailingListDelete
+                | Permission::DomainList
+                | Permission::DomainGet
+        ) || self.is_user_permission()
+    }
+
     // SPDX-SnippetBegin
     // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
     // SPDX-License-Identifier: LicenseRef-SEL
@@ -15,25 +15,54 @@ use sha1::Digest;
 use sha1::Sha1;
 use sha2::Sha256;
 use sha2::Sha512;
+use store::rand::{thread_rng, Rng};
 use tokio::sync::oneshot;
-use totp_rs::TOTP;
+use totp_rs::{Algorithm, TOTP};
 
 use crate::backend::internal::PrincipalField;
 use crate::backend::internal::SpecialSecrets;
 use crate::Principal;
 
+/// The kind of secret that was used to authenticate a principal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretMatch {
+    Password,
+    AppPassword(String),
+    RecoveryCode(String),
+}
+
 impl Principal {
-    pub async fn verify_secret(&self, mut code: &str) -> trc::Result<bool> {
+    pub async fn verify_secret(&self, code: &str) -> trc::Result<bool> {
+        self.match_secret(code).await.map(|result| result.is_some())
+    }
+
+    pub async fn match_secret(&self, mut code: &str) -> trc::Result<Option<SecretMatch>> {
         let mut totp_token = None;
         let mut is_totp_token_missing = false;
         let mut is_totp_required = false;
         let mut is_totp_verified = false;
         let mut is_authenticated = false;
-        let mut is_app_authenticated = false;
+        let mut app_authenticated = None;
+
+        // Recovery codes can be used in place of a TOTP token
+        let mut recovery_code = None;
+        if let Some((_code, _recovery_code)) = code
+            .rsplit_once('$')
+            .filter(|(c, r)| !c.is_empty() && is_recovery_code(r))
+        {
+            let hash = format!("$recovery${}", hash_recovery_code(_recovery_code));
+            if self.has_str_value(PrincipalField::Secrets, &hash) {
+                recovery_code = Some(hash);
+                code = _code;
+            }
+        }
 
         for secret in self.iter_str(PrincipalField::Secrets) {
             if secret.is_otp_auth() {
-                if !is_totp_verified && !is_totp_token_missing {
+                if recovery_code.is_some() {
+                    is_totp_required = true;
+                    is_totp_verified = true;
+                } else if !is_totp_verified && !is_totp_token_missing {
                     is_totp_required = true;
 
                     let totp_token = if let Some(totp_token) = totp_token {
@@ -63,11 +92,16 @@ impl Principal {
                         .check_current(totp_token)
                         .unwrap_or(false);
                 }
-            } else if !is_authenticated && !is_app_authenticated {
-                if let Some((_, app_secret)) =
+            } else if secret.is_webauthn() || secret.is_recovery_code() {
+                // Passkeys and recovery codes are verified separately
+                continue;
+            } else if !is_authenticated && app_authenticated.is_none() {
+                if let Some((app_name, app_secret)) =
                     secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
                 {
-                    is_app_authenticated = verify_secret_hash(app_secret, code).await?;
+                    if verify_secret_hash(app_secret, code).await? {
+                        app_authenticated = Some(app_name.to_string());
+                    }
                 } else {
                     is_authenticated = verify_secret_hash(secret, code).await?;
                 }
@@ -78,36 +112,113 @@ impl Principal {
             if !is_totp_required {
                 // Authenticated without TOTP enabled
 
-                Ok(true)
+                Ok(Some(SecretMatch::Password))
             } else if is_totp_token_missing {
                 // Only let the client know if the TOTP code is missing
                 // if the password is correct
 
                 Err(trc::AuthEvent::MissingTotp.into_err())
-            } else {
-                // Return the TOTP verification status
+            } else if !is_totp_verified {
+                Ok(None)
+            } else if let Some(recovery_code) = recovery_code {
+                // Recovery codes can only be used once
 
-                Ok(is_totp_verified)
+                Ok(Some(SecretMatch::RecoveryCode(recovery_code)))
+            } else {
+                Ok(Some(SecretMatch::Password))
             }
-        } else if is_app_authenticated {
+        } else if let Some(app_name) = app_authenticated {
             // App passwords do not require TOTP
 
-            Ok(true)
+            Ok(Some(SecretMatch::AppPassword(app_name)))
         } else {
-            if is_totp_verified {
+            if is_totp_verified && recovery_code.is_none() {
                 // TOTP URL appeared after password hash in secrets list
                 for secret in self.iter_str(PrincipalField::Secrets) {
                     if secret.is_password() && verify_secret_hash(secret, code).await? {
-                        return Ok(true);
+                        return Ok(Some(SecretMatch::Password));
                     }
                 }
             }
 
-            Ok(false)
+            Ok(None)
         }
     }
+
+    /// Returns whether the secret matches one of the principal's app passwords.
+    pub async fn matches_app_password(&self, code: &str) -> trc::Result<bool> {
+        for secret in self.iter_str(PrincipalField::Secrets) {
+            if let Some((_, app_secret)) =
+                secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
+            {
+                if verify_secret_hash(app_secret, code).await? {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Generates a new TOTP secret and returns its `otpauth://` URL.
+pub fn generate_otp_url(issuer: &str, account_name: &str) -> trc::Result<String> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        thread_rng().gen::<[u8; 20]>().to_vec(),
+        Some(issuer.to_string()),
+        account_name.to_string(),
+    )
+    .map(|totp| totp.get_url())
+    .map_err(|err| trc::AuthEvent::Error.reason(err))
+}
+
+/// Verifies a TOTP token against an `otpauth://` URL.
+pub fn verify_otp(url: &str, token: &str) -> bool {
+    TOTP::from_url(url)
+        .ok()
+        .and_then(|totp| totp.check_current(token).ok())
+        .unwrap_or(false)
 }
 
+/// Generates a one-time recovery code formatted as `xxxxx-xxxxx`.
+pub fn generate_recovery_code() -> String {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = thread_rng();
+    let mut code = String::with_capacity(RECOVERY_CODE_LEN);
+    for pos in 0..RECOVERY_CODE_LEN {
+        if pos == RECOVERY_CODE_LEN / 2 {
+            code.push('-');
+        } else {
+            code.push(char::from(ALPHABET[rng.gen_range(0..ALPHABET.len())]));
+        }
+    }
+    code
+}
+
+pub fn hash_recovery_code(code: &str) -> String {
+    Sha256::digest(code.to_ascii_lowercase().as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn is_recovery_code(code: &str) -> bool {
+    code.len() == RECOVERY_CODE_LEN
+        && code.bytes().enumerate().all(|(pos, b)| {
+            if pos == RECOVERY_CODE_LEN / 2 {
+                b == b'-'
+            } else {
+                b.is_ascii_alphanumeric()
+            }
+        })
+}
+
+const RECOVERY_CODE_LEN: usize = 11;
+
 async fn verify_hash_prefix(hashed_secret: &str, secret: &str) -> trc::Result<bool> {
     if hashed_secret.starts_with("$argon2")
         || hashed_secret.starts_with("$pbkdf2")
@@ -268,3 +379,64 @@ pub async fn verify_secret_hash(hashed_secret: &str, secret: &str) -> trc::Resul
         Ok(hashed_secret == secret)
     }
 }
+
+/// Returns whether a secret is already hashed using one of the crypt or `{SCHEME}`
+/// formats supported by `verify_secret_hash`.
+pub fn is_hashed_secret(secret: &str) -> bool {
+    if let Some(secret) = secret.strip_prefix('{') {
+        secret.split_once('}').is_some_and(|(scheme, hash)| {
+            !hash.is_empty()
+                && matches!(
+                    scheme,
+                    "ARGON2"
+                        | "ARGON2I"
+                        | "ARGON2ID"
+                        | "PBKDF2"
+                        | "SHA"
+                        | "SSHA"
+                        | "SHA256"
+                        | "SSHA256"
+                        | "SHA512"
+                        | "SSHA512"
+                        | "MD5"
+                        | "CRYPT"
+                        | "crypt"
+                )
+        })
+    } else if let Some(hash) = secret.strip_prefix('_') {
+        // Enhanced DES-based hash
+        hash.len() == 19
+            && hash
+                .bytes()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == b'.' || ch == b'/')
+    } else {
+        [
+            "$argon2i$",
+            "$argon2d$",
+            "$argon2id$",
+            "$pbkdf2$",
+            "$pbkdf2-sha256$",
+            "$pbkdf2-sha512$",
+            "$scrypt$",
+            "$2a$",
+            "$2b$",
+            "$2x$",
+            "$2y$",
+            "$6$",
+            "$5$",
+            "$sha1$",
+            "$1$",
+        ]
+        .iter()
+        .any(|prefix| secret.len() > prefix.len() && secret.starts_with(prefix))
+    }
+}
+
+/// Hashes a plain-text secret using SHA-512 crypt, leaving already hashed secrets untouched.
+pub fn hash_secret(secret: &str) -> String {
+    if is_hashed_secret(secret) {
+        secret.to_string()
+    } else {
+        sha512_crypt::hash(secret).unwrap_or_else(|_| secret.to_string())
+    }
+}
@@ -0,0 +1,562 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{collections::BTreeSet, time::Instant};
+
+use ahash::AHashSet;
+use store::{LookupStore, Store};
+use trc::AddContext;
+use utils::config::{cron::SimpleCron, utils::ParseValue, Config};
+
+use crate::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
+    },
+    Directory, DirectoryInner, Permission, Principal, QueryBy, Type, ROLE_ADMIN,
+};
+
+#[derive(Debug, Clone)]
+pub struct DirectorySync {
+    pub id: String,
+    pub frequency: SimpleCron,
+    pub deletion: DeletionPolicy,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionPolicy {
+    Disable,
+    Delete,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub changes: Vec<SyncChange>,
+    pub errors: Vec<SyncError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncChange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: Type,
+    pub action: SyncAction,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub updates: Vec<PrincipalUpdate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncAction {
+    Create,
+    Update,
+    Enable,
+    Disable,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncError {
+    pub name: String,
+    pub reason: String,
+}
+
+// Principals imported by a previous run, used to detect removals and to
+// re-enable accounts that were disabled by the synchronization
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct SyncState {
+    managed: BTreeSet<String>,
+    disabled: BTreeSet<String>,
+}
+
+impl DirectorySync {
+    pub fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default::<bool>(("directory", id, "sync.enable"), "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        DirectorySync {
+            id: id.to_string(),
+            frequency: config
+                .property_or_default::<SimpleCron>(("directory", id, "sync.frequency"), "0 * *")
+                .unwrap_or_else(|| SimpleCron::parse_value("0 * *").unwrap()),
+            deletion: match config
+                .value(("directory", id, "sync.deletion"))
+                .unwrap_or("disable")
+            {
+                "disable" => DeletionPolicy::Disable,
+                "delete" => DeletionPolicy::Delete,
+                other => {
+                    let err = format!("Invalid deletion policy {other:?}");
+                    config.new_parse_error(("directory", id, "sync.deletion"), err);
+                    DeletionPolicy::Disable
+                }
+            },
+            dry_run: config
+                .property_or_default(("directory", id, "sync.dry-run"), "false")
+                .unwrap_or(false),
+        }
+        .into()
+    }
+}
+
+impl Directory {
+    /// Imports the users and groups of an LDAP or SQL directory into the internal
+    /// directory. On a dry run the changes are computed and reported but not applied.
+    pub async fn synchronize(&self, dry_run: bool) -> trc::Result<SyncReport> {
+        let sync = self
+            .sync
+            .as_ref()
+            .ok_or_else(|| manage::unsupported("Directory synchronization is not enabled"))?;
+        let (mut principals, store) = match &self.store {
+            DirectoryInner::Ldap(directory) => {
+                (directory.list_principals().await?, &directory.data_store)
+            }
+            DirectoryInner::Sql(directory) => {
+                (directory.list_principals().await?, &directory.data_store)
+            }
+            _ => {
+                return Err(manage::unsupported(
+                    "Only LDAP and SQL directories can be synchronized",
+                ))
+            }
+        };
+        let started = Instant::now();
+
+        // Load the state of the previous run
+        let state_key = format!("dsync:{}", sync.id).into_bytes();
+        let lookup = LookupStore::Store(store.clone());
+        let state = lookup
+            .key_get::<String>(state_key.clone())
+            .await
+            .caused_by(trc::location!())?
+            .and_then(|state| serde_json::from_str::<SyncState>(&state).ok())
+            .unwrap_or_default();
+
+        // An empty result is far more likely to be a broken filter or query than
+        // an intentional removal of every account
+        if principals.is_empty() && !state.managed.is_empty() {
+            return Err(manage::error(
+                "Directory synchronization aborted",
+                "The external directory returned no principals".into(),
+            ));
+        }
+
+        // Groups are imported first so memberships can be linked
+        principals.retain(|p| matches!(p.typ(), Type::Individual | Type::Group));
+        principals.sort_by_key(|p| p.typ() != Type::Group);
+
+        let external_names = principals
+            .iter()
+            .map(|p| p.name().to_lowercase())
+            .collect::<AHashSet<_>>();
+        let managed = external_names
+            .iter()
+            .cloned()
+            .chain(state.managed.iter().cloned())
+            .collect::<AHashSet<_>>();
+
+        let mut report = SyncReport {
+            dry_run,
+            total: principals.len(),
+            ..Default::default()
+        };
+        let mut new_state = SyncState::default();
+
+        for external in principals {
+            let name = external.name().to_lowercase();
+            let was_disabled = state.disabled.contains(&name);
+
+            // Local accounts that were not imported by this synchronization are never modified
+            let id = store
+                .get_principal_id(&name)
+                .await
+                .caused_by(trc::location!())?;
+            if id.is_some() && !state.managed.contains(&name) {
+                let err = manage::error(
+                    "Principal not managed by directory synchronization",
+                    "A local principal with the same name already exists".into(),
+                );
+                report.errors.push(SyncError {
+                    name: name.clone(),
+                    reason: err.to_string(),
+                });
+                trc::error!(err.ctx(trc::Key::AccountName, name));
+                continue;
+            }
+
+            match sync_principal(store, id, external, &managed, was_disabled, dry_run).await {
+                Ok(Some(change)) => report.changes.push(change),
+                Ok(None) => (),
+                Err(err) => {
+                    if was_disabled {
+                        new_state.disabled.insert(name.clone());
+                    }
+                    report.errors.push(SyncError {
+                        name: name.clone(),
+                        reason: err.to_string(),
+                    });
+                    trc::error!(err
+                        .ctx(trc::Key::AccountName, name.clone())
+                        .details("Failed to synchronize principal"));
+                }
+            }
+
+            new_state.managed.insert(name);
+        }
+
+        // Handle principals no longer present in the external directory
+        let removed = state
+            .managed
+            .difference(&new_state.managed)
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in &removed {
+            let Some(info) = store
+                .get_principal_info(name)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+
+            let change = match sync.deletion {
+                DeletionPolicy::Disable => {
+                    // Disabled principals are kept so they can be enabled if they reappear
+                    new_state.managed.insert(name.clone());
+                    if info.typ != Type::Individual {
+                        continue;
+                    } else if state.disabled.contains(name) {
+                        new_state.disabled.insert(name.clone());
+                        continue;
+                    }
+                    SyncChange {
+                        id: info.id.into(),
+                        name: name.clone(),
+                        typ: info.typ,
+                        action: SyncAction::Disable,
+                        updates: vec![PrincipalUpdate::add_item(
+                            PrincipalField::DisabledPermissions,
+                            PrincipalValue::String(Permission::Authenticate.name().to_string()),
+                        )],
+                    }
+                }
+                DeletionPolicy::Delete => SyncChange {
+                    id: info.id.into(),
+                    name: name.clone(),
+                    typ: info.typ,
+                    action: SyncAction::Delete,
+                    updates: vec![],
+                },
+            };
+
+            if !dry_run {
+                let result = if change.action == SyncAction::Delete {
+                    store.delete_principal(QueryBy::Id(info.id)).await
+                } else {
+                    store
+                        .update_principal(
+                            UpdatePrincipal::by_id(info.id).with_updates(change.updates.clone()),
+                        )
+                        .await
+                };
+
+                if let Err(err) = result {
+                    report.errors.push(SyncError {
+                        name: name.clone(),
+                        reason: err.to_string(),
+                    });
+                    trc::error!(err
+                        .ctx(trc::Key::AccountName, name.clone())
+                        .details("Failed to synchronize principal"));
+                    continue;
+                }
+            }
+
+            if change.action == SyncAction::Disable {
+                new_state.disabled.insert(name.clone());
+            }
+            report.changes.push(change);
+        }
+
+        for change in &report.changes {
+            trc::event!(
+                Store(trc::StoreEvent::DirectorySyncChange),
+                Id = sync.id.clone(),
+                AccountName = change.name.clone(),
+                Type = change.action.as_str(),
+                Details = change
+                    .updates
+                    .iter()
+                    .map(|update| trc::Value::from(update.field.as_str()))
+                    .collect::<Vec<_>>(),
+                Result = dry_run,
+            );
+        }
+
+        if !dry_run {
+            lookup
+                .key_set(
+                    state_key,
+                    serde_json::to_string(&new_state)
+                        .unwrap_or_default()
+                        .into_bytes(),
+                    None,
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        trc::event!(
+            Store(trc::StoreEvent::DirectorySyncComplete),
+            Id = sync.id.clone(),
+            Total = report.total,
+            TotalSuccesses = report.changes.len(),
+            TotalFailures = report.errors.len(),
+            Result = dry_run,
+            Elapsed = started.elapsed(),
+        );
+
+        Ok(report)
+    }
+}
+
+async fn sync_principal(
+    store: &Store,
+    id: Option<u32>,
+    external: Principal,
+    managed: &AHashSet<String>,
+    was_disabled: bool,
+    dry_run: bool,
+) -> trc::Result<Option<SyncChange>> {
+    let name = external.name().to_lowercase();
+    let typ = external.typ();
+    let current = if let Some(id) = id {
+        let mut current = store
+            .query(QueryBy::Id(id), true)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| manage::not_found(id))?;
+        if current.typ() != typ {
+            return Err(manage::error(
+                "Principal type mismatch",
+                format!(
+                    "Internal principal is of type {:?} but external principal is of type {:?}",
+                    current.typ().as_str(),
+                    typ.as_str()
+                )
+                .into(),
+            ));
+        }
+        store
+            .map_field_ids(&mut current, &[PrincipalField::MemberOf])
+            .await
+            .caused_by(trc::location!())?;
+        Some(current)
+    } else {
+        None
+    };
+
+    let mut updates = sync_updates(current.as_ref(), &external, managed);
+    let action = if current.is_none() {
+        SyncAction::Create
+    } else if was_disabled {
+        updates.push(PrincipalUpdate::remove_item(
+            PrincipalField::DisabledPermissions,
+            PrincipalValue::String(Permission::Authenticate.name().to_string()),
+        ));
+        SyncAction::Enable
+    } else if !updates.is_empty() {
+        SyncAction::Update
+    } else {
+        return Ok(None);
+    };
+
+    let mut id = current.as_ref().map(|p| p.id());
+    if !dry_run {
+        let principal_id = if let Some(id) = id {
+            id
+        } else {
+            store
+                .get_or_create_principal_id(&name, typ)
+                .await
+                .caused_by(trc::location!())?
+        };
+
+        // Groups outside the list filter or query are created on demand
+        for update in &updates {
+            if let (
+                PrincipalAction::AddItem,
+                PrincipalField::MemberOf,
+                PrincipalValue::String(group),
+            ) = (&update.action, update.field, &update.value)
+            {
+                store
+                    .get_or_create_principal_id(group, Type::Group)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        if !updates.is_empty() {
+            store
+                .update_principal(
+                    UpdatePrincipal::by_id(principal_id)
+                        .with_updates(updates.clone())
+                        .create_domains(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+        id = Some(principal_id);
+    }
+
+    // Never expose secrets in reports or events
+    for update in &mut updates {
+        if update.field == PrincipalField::Secrets {
+            update.value = PrincipalValue::String("[redacted]".to_string());
+        }
+    }
+
+    Ok(Some(SyncChange {
+        id,
+        name,
+        typ,
+        action,
+        updates,
+    }))
+}
+
+fn sync_updates(
+    current: Option<&Principal>,
+    external: &Principal,
+    managed: &AHashSet<String>,
+) -> Vec<PrincipalUpdate> {
+    let mut updates = Vec::new();
+
+    if let Some(description) = external.description() {
+        if current.and_then(|p| p.description()) != Some(description) {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Description,
+                PrincipalValue::String(description.to_string()),
+            ));
+        }
+    }
+
+    // Only passwords are synchronized, app passwords and second factors are kept
+    if let Some(passwords) = external
+        .get_str_array(PrincipalField::Secrets)
+        .filter(|s| !s.is_empty())
+    {
+        let current_secrets = current
+            .and_then(|p| p.get_str_array(PrincipalField::Secrets))
+            .unwrap_or_default();
+        if !current_secrets
+            .iter()
+            .filter(|s| s.is_password())
+            .eq(passwords.iter())
+        {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Secrets,
+                PrincipalValue::StringList(
+                    passwords
+                        .iter()
+                        .chain(current_secrets.iter().filter(|s| !s.is_password()))
+                        .cloned()
+                        .collect(),
+                ),
+            ));
+        }
+    }
+
+    if let Some(emails) = external
+        .get_str_array(PrincipalField::Emails)
+        .filter(|s| !s.is_empty())
+    {
+        let emails = emails.iter().map(|e| e.to_lowercase()).collect::<Vec<_>>();
+        if current.and_then(|p| p.get_str_array(PrincipalField::Emails)) != Some(emails.as_slice())
+        {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Emails,
+                PrincipalValue::StringList(emails),
+            ));
+        }
+    }
+
+    if let Some(quota) = external.get_int(PrincipalField::Quota) {
+        if current.and_then(|p| p.get_int(PrincipalField::Quota)) != Some(quota) {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Quota,
+                PrincipalValue::Integer(quota),
+            ));
+        }
+    }
+
+    // Memberships of groups not imported by the synchronization are left untouched
+    let groups = external
+        .get_str_array(PrincipalField::MemberOf)
+        .unwrap_or_default()
+        .iter()
+        .map(|g| g.to_lowercase())
+        .collect::<BTreeSet<_>>();
+    let current_groups = current
+        .and_then(|p| p.get_str_array(PrincipalField::MemberOf))
+        .unwrap_or_default();
+    for group in &groups {
+        if !current_groups.contains(group) {
+            updates.push(PrincipalUpdate::add_item(
+                PrincipalField::MemberOf,
+                PrincipalValue::String(group.clone()),
+            ));
+        }
+    }
+    for group in current_groups {
+        if !groups.contains(group) && managed.contains(group) {
+            updates.push(PrincipalUpdate::remove_item(
+                PrincipalField::MemberOf,
+                PrincipalValue::String(group.clone()),
+            ));
+        }
+    }
+
+    // Roles are only assigned on creation, they can be changed by administrators afterwards
+    if current.is_none()
+        && external
+            .get_int_array(PrincipalField::Roles)
+            .is_some_and(|roles| roles.contains(&(ROLE_ADMIN as u64)))
+    {
+        updates.push(PrincipalUpdate::add_item(
+            PrincipalField::Roles,
+            PrincipalValue::String("admin".to_string()),
+        ));
+    }
+
+    updates
+}
+
+impl SyncAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncAction::Create => "create",
+            SyncAction::Update => "update",
+            SyncAction::Enable => "enable",
+            SyncAction::Disable => "disable",
+            SyncAction::Delete => "delete",
+        }
+    }
+}
@@ -4,7 +4,10 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use core::cache::CachedDirectory;
+use core::{
+    cache::{CachedDirectory, GroupCache},
+    sync::DirectorySync,
+};
 use std::{fmt::Debug, sync::Arc};
 
 use ahash::AHashMap;
@@ -29,6 +32,8 @@ pub mod core;
 pub struct Directory {
     pub store: DirectoryInner,
     pub cache: Option<CachedDirectory>,
+    pub groups: GroupCache,
+    pub sync: Option<DirectorySync>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -58,6 +63,16 @@ pub enum Type {
 
 pub const MAX_TYPE_ID: usize = 11;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    Suspended,
+    ReadOnly,
+    PendingDeletion,
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, EnumMethods,
 )]
@@ -262,6 +277,43 @@ pub enum Permission {
     // OAuth client registration
     OauthClientRegistration,
     OauthClientOverride,
+
+    // Store maintenance
+    StoreFsck,
+    StoreShardMove,
+    StoreQuotaRecalculate,
+
+    // Self-service account management
+    ManageProfile,
+    ManageAliases,
+
+    // Directory synchronization
+    DirectorySync,
+
+    // JMAP sharing
+    JmapShareNotificationGet,
+    JmapShareNotificationSet,
+    JmapShareNotificationChanges,
+    JmapShareNotificationQuery,
+    JmapBlobConvert,
+
+    // JMAP templates
+    JmapEmailTemplateGet,
+    JmapEmailTemplateSet,
+    JmapEmailTemplateChanges,
+    JmapEmailSubmissionMerge,
+
+    // JMAP mail rules
+    JmapMailRulesGet,
+    JmapMailRulesSet,
+
+    // JMAP saved searches
+    JmapSavedSearchGet,
+    JmapSavedSearchSet,
+    JmapSavedSearchChanges,
+
+    // JMAP thread actions
+    JmapThreadSet,
     // WARNING: add new ids at the end (TODO: use static ids)
 }
 
@@ -272,6 +324,7 @@ pub type Permissions = Bitset<
 pub const ROLE_ADMIN: u32 = u32::MAX;
 pub const ROLE_TENANT_ADMIN: u32 = u32::MAX - 1;
 pub const ROLE_USER: u32 = u32::MAX - 2;
+pub const ROLE_DOMAIN_ADMIN: u32 = u32::MAX - 3;
 
 pub enum DirectoryInner {
     Internal(Store),
@@ -295,6 +348,8 @@ impl Default for Directory {
         Self {
             store: DirectoryInner::Internal(Store::None),
             cache: None,
+            groups: GroupCache::default(),
+            sync: None,
         }
     }
 }
@@ -46,6 +46,8 @@ impl Request<Command> {
                             "junk"
                         } else if value.eq_ignore_ascii_case(b"\\Sent") {
                             "sent"
+                        } else if value.eq_ignore_ascii_case(b"\\Snoozed") {
+                            "snoozed"
                         } else if value.eq_ignore_ascii_case(b"\\Trash") {
                             "trash"
                         } else if value.eq_ignore_ascii_case(b"\\Important") {
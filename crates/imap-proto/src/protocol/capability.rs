@@ -97,7 +97,11 @@ impl Capability {
         });
     }
 
-    pub fn all_capabilities(is_authenticated: bool, offer_tls: bool) -> Vec<Capability> {
+    pub fn all_capabilities(
+        is_authenticated: bool,
+        offer_tls: bool,
+        offer_gssapi: bool,
+    ) -> Vec<Capability> {
         let mut capabilities = vec![
             Capability::IMAP4rev2,
             Capability::IMAP4rev1,
@@ -142,6 +146,9 @@ impl Capability {
                 Capability::Auth(Mechanism::OAuthBearer),
                 Capability::Auth(Mechanism::Plain),
             ]);
+            if offer_gssapi {
+                capabilities.push(Capability::Auth(Mechanism::Gssapi));
+            }
         }
         if offer_tls {
             capabilities.push(Capability::StartTLS);
@@ -69,6 +69,7 @@ pub enum Attribute {
     Flagged,
     Junk,
     Sent,
+    Snoozed,
     Trash,
     Important,
 }
@@ -140,6 +141,7 @@ impl Attribute {
             Attribute::Flagged => b"\\Flagged",
             Attribute::Junk => b"\\Junk",
             Attribute::Sent => b"\\Sent",
+            Attribute::Snoozed => b"\\Snoozed",
             Attribute::Trash => b"\\Trash",
             Attribute::Important => b"\\Important",
         });
@@ -155,6 +157,7 @@ impl TryFrom<&str> for Attribute {
             "drafts" => Ok(Attribute::Drafts),
             "junk" => Ok(Attribute::Junk),
             "sent" => Ok(Attribute::Sent),
+            "snoozed" => Ok(Attribute::Snoozed),
             "trash" => Ok(Attribute::Trash),
             "important" => Ok(Attribute::Important),
             _ => Err(()),
@@ -505,6 +505,9 @@ impl SerializeResponse for trc::Error {
                     Some(ResponseCode::OverQuota.as_str())
                 }
                 trc::EventType::Limit(_) => Some(ResponseCode::Limit.as_str()),
+                trc::EventType::Auth(trc::AuthEvent::PasswordExpired) => {
+                    Some(ResponseCode::Expired.as_str())
+                }
                 trc::EventType::Auth(_) => Some(ResponseCode::AuthenticationFailed.as_str()),
                 trc::EventType::Security(_) => Some(ResponseCode::AuthorizationFailed.as_str()),
                 _ => None,
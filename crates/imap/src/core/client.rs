@@ -12,10 +12,10 @@ use common::{
 };
 use imap_proto::{
     receiver::{self, Request},
-    Command, ResponseType, StatusResponse,
+    Command, ResponseCode, ResponseType, StatusResponse,
 };
 
-use super::{SelectedMailbox, Session, SessionData, State};
+use super::{mailbox::is_saved_search, SelectedMailbox, Session, SessionData, State};
 
 impl<T: SessionStream> Session<T> {
     pub async fn ingest(&mut self, bytes: &[u8]) -> SessionResult {
@@ -366,6 +366,12 @@ impl<T: SessionStream> Session<T> {
                         )
                     {
                         Ok(request)
+                    } else if is_saved_search(&mailbox.id) {
+                        Err(trc::ImapEvent::Error
+                            .into_err()
+                            .details("Saved search mailboxes are read-only.")
+                            .code(ResponseCode::Cannot)
+                            .id(request.tag))
                     } else {
                         Err(trc::ImapEvent::Error
                             .into_err()
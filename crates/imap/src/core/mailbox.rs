@@ -11,11 +11,12 @@ use common::{
     AccountId, Mailbox,
 };
 use directory::{backend::internal::PrincipalField, QueryBy};
-use imap_proto::protocol::list::Attribute;
+use imap_proto::{protocol::list::Attribute, ResponseCode};
 use jmap::{
     auth::acl::{AclMethods, EffectiveAcl},
     changes::get::ChangesLookup,
     mailbox::{get::MailboxGet, set::MailboxSet, INBOX_ID},
+    saved_search::{saved_search_document_id, saved_search_mailbox_id, SavedSearchResults},
     JmapMethods,
 };
 use jmap_proto::{
@@ -23,7 +24,10 @@ use jmap_proto::{
     types::{acl::Acl, collection::Collection, id::Id, property::Property, value::Value},
 };
 use parking_lot::Mutex;
-use store::query::log::{Change, Query};
+use store::{
+    query::log::{Change, Query},
+    roaring::RoaringBitmap,
+};
 use trc::AddContext;
 use utils::lru_cache::LruCached;
 
@@ -108,6 +112,17 @@ impl<T: SessionStream> SessionData<T> {
             .get_last_change_id(account_id, Collection::Email)
             .await
             .caused_by(trc::location!())?;
+        let state_search = if mailbox_prefix.is_none() {
+            self.server
+                .core
+                .storage
+                .data
+                .get_last_change_id(account_id, Collection::SavedSearch)
+                .await
+                .caused_by(trc::location!())?
+        } else {
+            None
+        };
         let cached_account_id = AccountId {
             account_id,
             primary_id: access_token.primary_id(),
@@ -121,6 +136,7 @@ impl<T: SessionStream> SessionData<T> {
             .and_then(|cached_account| {
                 if cached_account.state_mailbox == state_mailbox
                     && cached_account.state_email == state_email
+                    && cached_account.state_search == state_search
                 {
                     Some(cached_account)
                 } else {
@@ -166,6 +182,7 @@ impl<T: SessionStream> SessionData<T> {
                     "drafts" => SpecialUse::Drafts,
                     "junk" => SpecialUse::Junk,
                     "sent" => SpecialUse::Sent,
+                    "snoozed" => SpecialUse::Snoozed,
                     "trash" => SpecialUse::Trash,
                     "inbox" => SpecialUse::Inbox,
                     _ => SpecialUse::None,
@@ -212,6 +229,7 @@ impl<T: SessionStream> SessionData<T> {
             mailbox_state: AHashMap::with_capacity(mailboxes.len()),
             state_mailbox,
             state_email,
+            state_search,
         };
 
         loop {
@@ -319,6 +337,42 @@ impl<T: SessionStream> SessionData<T> {
             }
         }
 
+        // Add saved searches as virtual mailboxes
+        if account.prefix.is_none() {
+            let search_ids = self
+                .server
+                .get_document_ids(account_id, Collection::SavedSearch)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
+            for (document_id, search) in self
+                .server
+                .get_properties::<Object<Value>, _, _>(
+                    account_id,
+                    Collection::SavedSearch,
+                    &search_ids,
+                    Property::Value,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                if let Some(name) = search.get(&Property::Name).as_string() {
+                    let mailbox_id = saved_search_mailbox_id(document_id);
+                    account.mailbox_state.insert(
+                        mailbox_id,
+                        Mailbox {
+                            is_subscribed: true,
+                            ..Default::default()
+                        },
+                    );
+                    account.mailbox_names.insert(
+                        format!("{}/{}", self.server.core.jmap.search_folder, name),
+                        mailbox_id,
+                    );
+                }
+            }
+        }
+
         // Update cache
         self.server
             .inner
@@ -418,9 +472,9 @@ impl<T: SessionStream> SessionData<T> {
             .mailboxes
             .lock()
             .iter()
-            .map(|m| (m.account_id, m.state_mailbox))
+            .map(|m| (m.account_id, m.state_mailbox, m.state_search))
             .collect::<Vec<_>>();
-        for (account_id, last_state) in account_states {
+        for (account_id, last_state, last_search_state) in account_states {
             let changelog = self
                 .server
                 .changes_(
@@ -429,8 +483,21 @@ impl<T: SessionStream> SessionData<T> {
                     last_state.map(Query::Since).unwrap_or(Query::All),
                 )
                 .await?;
-            if !changelog.changes.is_empty() {
-                let mut has_changes = false;
+
+            // Saved searches are listed as mailboxes of the primary account
+            let has_search_changes = access_token.is_primary_id(account_id)
+                && self
+                    .server
+                    .core
+                    .storage
+                    .data
+                    .get_last_change_id(account_id, Collection::SavedSearch)
+                    .await
+                    .caused_by(trc::location!())?
+                    != last_search_state;
+
+            if !changelog.changes.is_empty() || has_search_changes {
+                let mut has_changes = has_search_changes;
                 let mut has_child_changes = false;
 
                 for change in changelog.changes {
@@ -641,4 +708,66 @@ impl<T: SessionStream> SessionData<T> {
                         .details("Mailbox no longer exists.")
                 })?)
     }
+    pub async fn get_saved_search(
+        &self,
+        mailbox: &MailboxId,
+    ) -> trc::Result<Option<Object<Value>>> {
+        if let Some(document_id) = saved_search_document_id(mailbox.mailbox_id) {
+            self.server
+                .get_property::<Object<Value>>(
+                    mailbox.account_id,
+                    Collection::SavedSearch,
+                    document_id,
+                    &Property::Value,
+                )
+                .await?
+                .ok_or_else(|| {
+                    trc::ImapEvent::Error
+                        .into_err()
+                        .details("Mailbox no longer exists.")
+                        .code(ResponseCode::NonExistent)
+                })
+                .map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_mailbox_message_ids(
+        &self,
+        mailbox: &MailboxId,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        if let Some(search) = self.get_saved_search(mailbox).await? {
+            // Virtual mailboxes contain the results of a saved search
+            let access_token = self.get_access_token().await?;
+            self.server
+                .saved_search_results(mailbox.account_id, &search, &access_token)
+                .await
+                .map(Some)
+        } else {
+            self.server
+                .get_tag(
+                    mailbox.account_id,
+                    Collection::Email,
+                    Property::MailboxIds,
+                    mailbox.mailbox_id,
+                )
+                .await
+        }
+    }
+}
+
+pub fn is_saved_search(mailbox: &MailboxId) -> bool {
+    saved_search_document_id(mailbox.mailbox_id).is_some()
+}
+
+pub fn assert_not_saved_search(mailbox: &MailboxId) -> trc::Result<()> {
+    if !is_saved_search(mailbox) {
+        Ok(())
+    } else {
+        Err(trc::ImapEvent::Error
+            .into_err()
+            .details("Saved search mailboxes are read-only.")
+            .code(ResponseCode::Cannot))
+    }
 }
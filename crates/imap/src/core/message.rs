@@ -18,7 +18,7 @@ use store::write::assert::HashedValue;
 use trc::AddContext;
 use utils::lru_cache::LruCached;
 
-use crate::core::ImapId;
+use crate::core::{mailbox::is_saved_search, ImapId};
 
 use super::{ImapUidToId, MailboxId, MailboxState, SelectedMailbox, SessionData};
 
@@ -26,6 +26,10 @@ pub(crate) const MAX_RETRIES: usize = 10;
 
 impl<T: SessionStream> SessionData<T> {
     pub async fn fetch_messages(&self, mailbox: &MailboxId) -> trc::Result<MailboxState> {
+        if is_saved_search(mailbox) {
+            return self.fetch_saved_search_messages(mailbox).await;
+        }
+
         // Obtain message ids
         let message_ids = self
             .server
@@ -116,6 +120,49 @@ impl<T: SessionStream> SessionData<T> {
         })
     }
 
+    async fn fetch_saved_search_messages(&self, mailbox: &MailboxId) -> trc::Result<MailboxState> {
+        // Obtain search results
+        let message_ids = self
+            .get_mailbox_message_ids(mailbox)
+            .await?
+            .unwrap_or_default();
+
+        // Obtain UID validity
+        let uid_validity = self.get_uid_validity(mailbox).await?;
+
+        // Obtain current state
+        let modseq = self.get_modseq(mailbox.account_id).await?;
+
+        // Virtual mailboxes have no UID counter, UIDs are derived from the message ids
+        let mut uid_max = 0;
+        let mut id_to_imap = AHashMap::with_capacity(message_ids.len() as usize);
+        let mut uid_to_id = AHashMap::with_capacity(message_ids.len() as usize);
+
+        for (seqnum, message_id) in message_ids.into_iter().enumerate() {
+            let uid = message_id + 1;
+            uid_max = uid;
+            id_to_imap.insert(
+                message_id,
+                ImapId {
+                    uid,
+                    seqnum: seqnum as u32 + 1,
+                },
+            );
+            uid_to_id.insert(uid, message_id);
+        }
+
+        Ok(MailboxState {
+            uid_next: uid_max + 1,
+            uid_validity,
+            total_messages: id_to_imap.len(),
+            id_to_imap,
+            uid_to_id,
+            uid_max,
+            modseq,
+            next_state: None,
+        })
+    }
+
     pub async fn synchronize_messages(
         &self,
         mailbox: &SelectedMailbox,
@@ -223,6 +270,10 @@ impl<T: SessionStream> SessionData<T> {
     }
 
     pub async fn get_uid_validity(&self, mailbox: &MailboxId) -> trc::Result<u32> {
+        if let Some(search) = self.get_saved_search(mailbox).await? {
+            return Ok(search.get(&Property::Cid).as_uint().unwrap_or(1) as u32);
+        }
+
         self.server
             .get_property::<Object<Value>>(
                 mailbox.account_id,
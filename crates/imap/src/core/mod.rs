@@ -10,7 +10,7 @@ use std::{
 };
 
 use common::{
-    auth::AccessToken,
+    auth::{kerberos::GssapiSession, AccessToken},
     listener::{limiter::InFlight, ServerInstance, SessionStream},
     Account, ImapId, Inner, MailboxId, MailboxState, Server,
 };
@@ -51,6 +51,7 @@ pub struct Session<T: SessionStream> {
     pub in_flight: InFlight,
     pub remote_addr: IpAddr,
     pub session_id: u64,
+    pub gssapi: Option<Box<GssapiSession>>,
 }
 
 pub struct SessionData<T: SessionStream> {
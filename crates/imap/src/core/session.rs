@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc};
 
 use common::{
     core::BuildServer,
@@ -17,7 +17,7 @@ use imap_proto::{
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_rustls::server::TlsStream;
 
-use crate::{GREETING_WITHOUT_TLS, GREETING_WITH_TLS};
+use crate::{greeting, GREETING_WITHOUT_TLS, GREETING_WITH_TLS};
 
 use super::{ImapSessionManager, Session, State};
 
@@ -120,14 +120,18 @@ impl<T: SessionStream> Session<T> {
         manager: ImapSessionManager,
     ) -> Result<Session<T>, ()> {
         // Write greeting
+        let server = manager.inner.build_server();
         let is_tls = session.stream.is_tls();
-        let greeting = if !is_tls && session.instance.acceptor.is_tls() {
-            &GREETING_WITH_TLS
+        let offer_tls = !is_tls && session.instance.acceptor.is_tls();
+        let greeting = if server.core.jmap.kerberos.is_some() {
+            Cow::Owned(greeting(offer_tls, true))
+        } else if offer_tls {
+            Cow::Borrowed(GREETING_WITH_TLS.as_slice())
         } else {
-            &GREETING_WITHOUT_TLS
+            Cow::Borrowed(GREETING_WITHOUT_TLS.as_slice())
         };
 
-        if let Err(err) = session.stream.write_all(greeting).await {
+        if let Err(err) = session.stream.write_all(&greeting).await {
             trc::event!(
                 Network(trc::NetworkEvent::WriteError),
                 Reason = err.to_string(),
@@ -140,7 +144,6 @@ impl<T: SessionStream> Session<T> {
 
         // Split stream into read and write halves
         let (stream_rx, stream_tx) = tokio::io::split(session.stream);
-        let server = manager.inner.build_server();
 
         Ok(Session {
             receiver: Receiver::with_max_request_size(server.core.imap.max_request_size),
@@ -156,6 +159,7 @@ impl<T: SessionStream> Session<T> {
             remote_addr: session.remote_ip,
             stream_rx,
             stream_tx: Arc::new(tokio::sync::Mutex::new(stream_tx)),
+            gssapi: None,
         })
     }
 
@@ -210,6 +214,7 @@ impl<T: SessionStream> Session<T> {
             remote_addr: self.remote_addr,
             stream_rx,
             stream_tx,
+            gssapi: None,
         })
     }
 }
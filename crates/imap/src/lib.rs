@@ -13,20 +13,17 @@ pub mod op;
 
 static SERVER_GREETING: &str = "Stalwart IMAP4rev2 at your service.";
 
-pub(crate) static GREETING_WITH_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    StatusResponse::ok(SERVER_GREETING)
-        .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, true),
-        })
-        .into_bytes()
-});
+pub(crate) static GREETING_WITH_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| greeting(true, false));
+
+pub(crate) static GREETING_WITHOUT_TLS: LazyLock<Vec<u8>> =
+    LazyLock::new(|| greeting(false, false));
 
-pub(crate) static GREETING_WITHOUT_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
+pub(crate) fn greeting(offer_tls: bool, offer_gssapi: bool) -> Vec<u8> {
     StatusResponse::ok(SERVER_GREETING)
         .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, false),
+            capabilities: Capability::all_capabilities(false, offer_tls, offer_gssapi),
         })
         .into_bytes()
-});
+}
 
 pub struct ImapError;
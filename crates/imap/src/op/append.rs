@@ -14,7 +14,7 @@ use imap_proto::{
 };
 
 use crate::{
-    core::{ImapUidToId, SelectedMailbox, Session, SessionData},
+    core::{mailbox::assert_not_saved_search, ImapUidToId, SelectedMailbox, Session, SessionData},
     spawn_op,
 };
 use common::{listener::SessionStream, MailboxId};
@@ -76,6 +76,7 @@ impl<T: SessionStream> SessionData<T> {
         // Verify ACLs
         let account_id = mailbox.account_id;
         let mailbox_id = mailbox.mailbox_id;
+        assert_not_saved_search(&mailbox).imap_ctx(&arguments.tag, trc::location!())?;
         if !self
             .check_mailbox_acl(account_id, mailbox_id, Acl::AddItems)
             .await
@@ -6,8 +6,9 @@
 
 use common::{
     auth::{
+        kerberos::GssapiStep,
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::SessionStream,
 };
@@ -63,6 +64,59 @@ impl<T: SessionStream> Session<T> {
                     self.write_bytes(b"+ \"\"\r\n".to_vec()).await
                 }
             }
+            Mechanism::Gssapi => {
+                let response = args.params.pop();
+                let mut gssapi = match self.gssapi.take() {
+                    Some(gssapi) => gssapi,
+                    None => {
+                        let gssapi = self
+                            .server
+                            .gssapi_session(true)
+                            .map_err(|err| err.id(args.tag.clone()).code(ResponseCode::Cannot))?;
+                        if response.is_none() {
+                            self.gssapi = Some(Box::new(gssapi));
+                            return self
+                                .continue_authenticate(args.tag, args.mechanism, "")
+                                .await;
+                        }
+                        Box::new(gssapi)
+                    }
+                };
+
+                match gssapi
+                    .step(response.as_deref().unwrap_or_default())
+                    .map_err(|err| err.id(args.tag.clone()))?
+                {
+                    GssapiStep::Continue(challenge) => {
+                        self.gssapi = Some(gssapi);
+                        self.continue_authenticate(args.tag, args.mechanism, &challenge)
+                            .await
+                    }
+                    GssapiStep::Authenticated {
+                        principal,
+                        authz_id,
+                        ..
+                    } => {
+                        // Throttle authentication requests
+                        self.server
+                            .is_auth_allowed_soft(&self.remote_addr)
+                            .await
+                            .map_err(|err| err.id(args.tag.clone()))?;
+
+                        let result = self
+                            .server
+                            .authenticate_kerberos(
+                                &principal,
+                                authz_id.as_deref(),
+                                None,
+                                self.session_id,
+                                self.remote_addr,
+                            )
+                            .await;
+                        self.finish_authenticate(result, args.tag).await
+                    }
+                }
+            }
             _ => Err(trc::AuthEvent::Error
                 .into_err()
                 .details("Authentication mechanism not supported.")
@@ -71,6 +125,22 @@ impl<T: SessionStream> Session<T> {
         }
     }
 
+    async fn continue_authenticate(
+        &mut self,
+        tag: String,
+        mechanism: Mechanism,
+        challenge: &str,
+    ) -> trc::Result<()> {
+        self.receiver.request = receiver::Request {
+            tag,
+            command: Command::Authenticate,
+            tokens: vec![receiver::Token::Argument(mechanism.into_bytes())],
+        };
+        self.receiver.state = receiver::State::Argument { last_ch: b' ' };
+        self.write_bytes(format!("+ {challenge}\r\n").into_bytes())
+            .await
+    }
+
     pub async fn authenticate(
         &mut self,
         credentials: Credentials<String>,
@@ -83,14 +153,24 @@ impl<T: SessionStream> Session<T> {
             .map_err(|err| err.id(tag.clone()))?;
 
         // Authenticate
-        let access_token = self
+        let result = self
             .server
             .authenticate(&AuthRequest::from_credentials(
                 credentials,
                 self.session_id,
                 self.remote_addr,
             ))
-            .await
+            .await;
+
+        self.finish_authenticate(result, tag).await
+    }
+
+    async fn finish_authenticate(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+        tag: String,
+    ) -> trc::Result<()> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     let auth_failures = self.state.auth_failures();
@@ -139,6 +219,7 @@ impl<T: SessionStream> Session<T> {
                     capabilities: Capability::all_capabilities(
                         true,
                         !self.is_tls && self.instance.acceptor.is_tls(),
+                        false,
                     ),
                 })
                 .with_tag(tag)
@@ -40,6 +40,7 @@ impl<T: SessionStream> Session<T> {
                         capabilities: Capability::all_capabilities(
                             self.state.is_authenticated(),
                             !self.is_tls && self.instance.acceptor.is_tls(),
+                            self.server.core.jmap.kerberos.is_some(),
                         ),
                     }
                     .serialize(),
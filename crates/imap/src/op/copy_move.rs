@@ -13,7 +13,10 @@ use imap_proto::{
 };
 
 use crate::{
-    core::{SelectedMailbox, Session, SessionData},
+    core::{
+        mailbox::{assert_not_saved_search, is_saved_search},
+        SelectedMailbox, Session, SessionData,
+    },
     spawn_op,
 };
 use common::{listener::SessionStream, MailboxId};
@@ -75,6 +78,13 @@ impl<T: SessionStream> Session<T> {
                         .id(arguments.tag));
                 };
 
+            // Saved searches are read-only, messages can only be copied from them.
+            assert_not_saved_search(&dest_mailbox).imap_ctx(&arguments.tag, trc::location!())?;
+            if is_move {
+                assert_not_saved_search(&src_mailbox.id)
+                    .imap_ctx(&arguments.tag, trc::location!())?;
+            }
+
             // Check that the destination mailbox is not the same as the source mailbox.
             if src_mailbox.id.account_id == dest_mailbox.account_id
                 && src_mailbox.id.mailbox_id == dest_mailbox.mailbox_id
@@ -188,9 +198,10 @@ impl<T: SessionStream> SessionData<T> {
                 };
 
                 // Make sure the message still belongs to this mailbox
-                if !mailboxes
-                    .current()
-                    .contains(&UidMailbox::new_unassigned(src_mailbox.id.mailbox_id))
+                if (!is_saved_search(&src_mailbox.id)
+                    && !mailboxes
+                        .current()
+                        .contains(&UidMailbox::new_unassigned(src_mailbox.id.mailbox_id)))
                     || mailboxes.current().contains(&dest_mailbox_id)
                 {
                     continue;
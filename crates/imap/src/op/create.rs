@@ -297,6 +297,12 @@ impl<T: SessionStream> SessionData<T> {
         let (account_id, path) = {
             let mailboxes = self.mailboxes.lock();
             let first_path_item = path.first().unwrap();
+            if first_path_item == &self.server.core.jmap.search_folder {
+                return Err(trc::ImapEvent::Error
+                    .into_err()
+                    .details("Mailboxes under the saved searches folder are not allowed.")
+                    .code(ResponseCode::Cannot));
+            }
             let account = if first_path_item == &self.server.core.jmap.shared_folder {
                 // Shared Folders/<username>/<folder>
                 if path.len() < 3 {
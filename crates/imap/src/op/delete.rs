@@ -7,7 +7,7 @@
 use std::time::Instant;
 
 use crate::{
-    core::{Session, SessionData},
+    core::{mailbox::assert_not_saved_search, Session, SessionData},
     spawn_op,
 };
 use common::listener::SessionStream;
@@ -61,6 +61,7 @@ impl<T: SessionStream> SessionData<T> {
         // Validate mailbox
         let (account_id, mailbox_id) =
             if let Some(mailbox) = self.get_mailbox_by_name(&arguments.mailbox_name) {
+                assert_not_saved_search(&mailbox).imap_ctx(&arguments.tag, trc::location!())?;
                 (mailbox.account_id, mailbox.mailbox_id)
             } else {
                 return Err(trc::ImapEvent::Error
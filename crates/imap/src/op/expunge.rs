@@ -15,7 +15,9 @@ use imap_proto::{
 };
 use trc::AddContext;
 
-use crate::core::{SavedSearch, SelectedMailbox, Session, SessionData};
+use crate::core::{
+    mailbox::assert_not_saved_search, SavedSearch, SelectedMailbox, Session, SessionData,
+};
 use common::{listener::SessionStream, ImapId};
 use jmap::{
     changes::write::ChangeLog,
@@ -48,6 +50,7 @@ impl<T: SessionStream> Session<T> {
         let (data, mailbox) = self.state.select_data();
 
         // Validate ACL
+        assert_not_saved_search(&mailbox.id).imap_ctx(&request.tag, trc::location!())?;
         if !data
             .check_mailbox_acl(
                 mailbox.id.account_id,
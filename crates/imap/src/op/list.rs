@@ -22,6 +22,7 @@ use imap_proto::{
     receiver::Request,
     Command, StatusResponse,
 };
+use jmap::saved_search::saved_search_document_id;
 
 use super::ImapContext;
 
@@ -198,6 +199,23 @@ impl<T: SessionStream> SessionData<T> {
                         tags: vec![],
                     });
                 }
+            } else if !filter_subscribed
+                && matches_pattern(&patterns, &self.server.core.jmap.search_folder)
+                && account
+                    .mailbox_names
+                    .values()
+                    .any(|mailbox_id| saved_search_document_id(*mailbox_id).is_some())
+            {
+                // Saved searches are listed under a non-selectable parent folder
+                list_items.push(ListItem {
+                    mailbox_name: self.server.core.jmap.search_folder.clone(),
+                    attributes: if include_children {
+                        vec![Attribute::HasChildren, Attribute::NoSelect]
+                    } else {
+                        vec![Attribute::NoSelect]
+                    },
+                    tags: vec![],
+                });
             }
 
             for (mailbox_name, mailbox_id) in &account.mailbox_names {
@@ -7,10 +7,10 @@
 use std::{collections::BTreeMap, time::Instant};
 
 use crate::{
-    core::{Session, SessionData},
+    core::{mailbox::assert_not_saved_search, Session, SessionData},
     spawn_op,
 };
-use common::listener::SessionStream;
+use common::{listener::SessionStream, MailboxId};
 use directory::Permission;
 use imap_proto::{
     protocol::rename::Arguments, receiver::Request, Command, ResponseCode, StatusResponse,
@@ -83,6 +83,11 @@ impl<T: SessionStream> SessionData<T> {
                 }
             }
             if let Some(mailbox_id) = mailbox_id {
+                assert_not_saved_search(&MailboxId {
+                    account_id: params.account_id,
+                    mailbox_id,
+                })
+                .imap_ctx(&arguments.tag, trc::location!())?;
                 mailbox_id
             } else {
                 return Err(trc::ImapEvent::Error
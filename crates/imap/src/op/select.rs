@@ -18,7 +18,7 @@ use imap_proto::{
     Command, ResponseCode, StatusResponse,
 };
 
-use crate::core::{SavedSearch, SelectedMailbox, Session, State};
+use crate::core::{mailbox::is_saved_search, SavedSearch, SelectedMailbox, Session, State};
 use common::listener::SessionStream;
 use jmap_proto::types::id::Id;
 use utils::lru_cache::LruCached;
@@ -46,6 +46,10 @@ impl<T: SessionStream> Session<T> {
             .imap_ctx(&arguments.tag, trc::location!())?;
 
         if let Some(mailbox) = data.get_mailbox_by_name(&arguments.mailbox_name) {
+            // Saved searches can only be opened in read-only mode
+            let is_saved_search = is_saved_search(&mailbox);
+            let is_select = is_select && !is_saved_search;
+
             // Try obtaining the mailbox from the cache
             let state =
                 {
@@ -57,7 +61,9 @@ impl<T: SessionStream> Session<T> {
                     if let Some(cached_state) =
                         self.server.inner.data.mailbox_cache.get(&mailbox).and_then(
                             |cached_state| {
-                                if cached_state.modseq.unwrap_or(0) >= modseq.unwrap_or(0) {
+                                if !is_saved_search
+                                    && cached_state.modseq.unwrap_or(0) >= modseq.unwrap_or(0)
+                                {
                                     Some(cached_state)
                                 } else {
                                     None
@@ -7,7 +7,7 @@
 use std::{sync::Arc, time::Instant};
 
 use crate::{
-    core::{Session, SessionData},
+    core::{mailbox::is_saved_search, Session, SessionData},
     op::ImapContext,
     spawn_op,
 };
@@ -88,6 +88,7 @@ impl<T: SessionStream> SessionData<T> {
         } else {
             // Some IMAP clients will try to get the status of a mailbox with the NoSelect flag
             return if mailbox_name == self.server.core.jmap.shared_folder
+                || mailbox_name == self.server.core.jmap.search_folder
                 || mailbox_name
                     .split_once('/')
                     .map_or(false, |(base_name, path)| {
@@ -212,13 +213,7 @@ impl<T: SessionStream> SessionData<T> {
             // Retrieve latest values
             let mut values_update = Vec::with_capacity(items_update.len());
             let mailbox_message_ids = self
-                .server
-                .get_tag(
-                    mailbox.account_id,
-                    Collection::Email,
-                    Property::MailboxIds,
-                    mailbox.mailbox_id,
-                )
+                .get_mailbox_message_ids(&mailbox)
                 .await
                 .caused_by(trc::location!())?
                 .map(Arc::new);
@@ -231,6 +226,13 @@ impl<T: SessionStream> SessionData<T> {
             for item in items_update {
                 let result = match item {
                     Status::Messages => mailbox_message_ids.as_ref().map(|v| v.len()).unwrap_or(0),
+                    Status::UidNext if is_saved_search(&mailbox) => mailbox_message_ids
+                        .as_ref()
+                        .and_then(|v| v.max())
+                        .map_or(1, |uid_max| uid_max as u64 + 2),
+                    Status::UidValidity if is_saved_search(&mailbox) => {
+                        self.get_uid_validity(&mailbox).await? as u64
+                    }
                     Status::UidNext => {
                         (self
                             .server
@@ -7,7 +7,10 @@
 use std::{sync::Arc, time::Instant};
 
 use crate::{
-    core::{message::MAX_RETRIES, SelectedMailbox, Session, SessionData},
+    core::{
+        mailbox::assert_not_saved_search, message::MAX_RETRIES, SelectedMailbox, Session,
+        SessionData,
+    },
     spawn_op,
 };
 use ahash::AHashSet;
@@ -53,6 +56,7 @@ impl<T: SessionStream> Session<T> {
         let arguments = request.parse_store()?;
         let (data, mailbox) = self.state.select_data();
         let is_condstore = self.is_condstore || mailbox.is_condstore;
+        assert_not_saved_search(&mailbox.id).imap_ctx(&arguments.tag, trc::location!())?;
 
         spawn_op!(data, {
             let response = data
@@ -7,7 +7,7 @@
 use std::time::Instant;
 
 use crate::{
-    core::{Session, SessionData},
+    core::{mailbox::assert_not_saved_search, Session, SessionData},
     spawn_op,
 };
 use common::listener::SessionStream;
@@ -73,7 +73,10 @@ impl<T: SessionStream> SessionData<T> {
 
         // Validate mailbox
         let (account_id, mailbox_id) = match self.get_mailbox_by_name(&mailbox_name) {
-            Some(mailbox) => (mailbox.account_id, mailbox.mailbox_id),
+            Some(mailbox) => {
+                assert_not_saved_search(&mailbox).imap_ctx(&tag, trc::location!())?;
+                (mailbox.account_id, mailbox.mailbox_id)
+            }
             None => {
                 return Err(trc::ImapEvent::Error
                     .into_err()
@@ -51,6 +51,10 @@ pub enum RequestArguments {
     Identity,
     EmailSubmission,
     Quota,
+    ShareNotification,
+    EmailImportTask,
+    EmailTemplate,
+    SavedSearch,
 }
 
 impl JsonObjectParser for ChangesRequest {
@@ -66,6 +70,10 @@ impl JsonObjectParser for ChangesRequest {
                 MethodObject::Identity => RequestArguments::Identity,
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
+                MethodObject::EmailImportTask => RequestArguments::EmailImportTask,
+                MethodObject::EmailTemplate => RequestArguments::EmailTemplate,
+                MethodObject::SavedSearch => RequestArguments::SavedSearch,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
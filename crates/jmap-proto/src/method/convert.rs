@@ -0,0 +1,128 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::set::SetError,
+    parser::{json::Parser, Ignore, JsonObjectParser, Token},
+    request::RequestProperty,
+    types::{blob::BlobId, id::Id},
+};
+
+#[derive(Debug, Clone)]
+pub struct BlobConvertRequest {
+    pub account_id: Id,
+    pub create: VecMap<String, ConvertObject>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvertObject {
+    pub blob_id: Option<BlobId>,
+    pub type_: Option<String>,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct BlobConvertResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "created")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub created: VecMap<String, BlobConvertResponseObject>,
+
+    #[serde(rename = "notCreated")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_created: VecMap<String, SetError>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct BlobConvertResponseObject {
+    pub id: BlobId,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub size: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl JsonObjectParser for BlobConvertRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = BlobConvertRequest {
+            account_id: Id::default(),
+            create: VecMap::new(),
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x6574_6165_7263 if !key.is_ref => {
+                    request.create = <VecMap<String, ConvertObject>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl JsonObjectParser for ConvertObject {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = ConvertObject::default();
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x6449_626f_6c62 if !key.is_ref => {
+                    request.blob_id = parser
+                        .next_token::<BlobId>()?
+                        .unwrap_string("blobId")?
+                        .into();
+                }
+                0x6570_7974 if !key.is_ref => {
+                    request.type_ = parser
+                        .next_token::<String>()?
+                        .unwrap_string_or_null("type")?;
+                }
+                0x0068_7464_6977 if !key.is_ref => {
+                    request.width = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("width")?;
+                }
+                0x7468_6769_6568 if !key.is_ref => {
+                    request.height = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("height")?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
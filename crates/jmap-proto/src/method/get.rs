@@ -33,9 +33,14 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript,
     VacationResponse,
+    MailRules,
     Principal,
     Quota,
     Blob(blob::GetArguments),
+    ShareNotification,
+    EmailImportTask,
+    EmailTemplate,
+    SavedSearch,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -68,9 +73,14 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
+                MethodObject::MailRules => RequestArguments::MailRules,
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Blob => RequestArguments::Blob(Default::default()),
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
+                MethodObject::EmailImportTask => RequestArguments::EmailImportTask,
+                MethodObject::EmailTemplate => RequestArguments::EmailTemplate,
+                MethodObject::SavedSearch => RequestArguments::SavedSearch,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
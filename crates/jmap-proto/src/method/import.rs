@@ -39,6 +39,17 @@ pub struct ImportEmail {
     pub mailbox_ids: MaybeReference<Vec<MaybeReference<Id, String>>, ResultReference>,
     pub keywords: Vec<Keyword>,
     pub received_at: Option<UTCDate>,
+    pub format: Option<ArchiveFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "mbox")]
+    Mbox,
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "maildir")]
+    Maildir,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -113,6 +124,7 @@ impl JsonObjectParser for ImportEmail {
             mailbox_ids: MaybeReference::Value(vec![]),
             keywords: vec![],
             received_at: None,
+            format: None,
         };
 
         parser
@@ -141,6 +153,11 @@ impl JsonObjectParser for ImportEmail {
                         .next_token::<UTCDate>()?
                         .unwrap_string_or_null("receivedAt")?;
                 }
+                0x7461_6d72_6f66 if !key.is_ref => {
+                    request.format = parser
+                        .next_token::<ArchiveFormat>()?
+                        .unwrap_string_or_null("format")?;
+                }
                 _ => {
                     parser.skip_token(parser.depth_array, parser.depth_dict)?;
                 }
@@ -151,10 +168,50 @@ impl JsonObjectParser for ImportEmail {
     }
 }
 
+impl JsonObjectParser for ArchiveFormat {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut hash = 0;
+        let mut shift = 0;
+
+        while let Some(ch) = parser.next_unescaped()? {
+            if shift < 128 {
+                hash |= (ch as u128) << shift;
+                shift += 8;
+            } else {
+                return Err(parser.error_value());
+            }
+        }
+
+        match hash {
+            0x786f_626d => Ok(ArchiveFormat::Mbox),
+            0x0070_697a => Ok(ArchiveFormat::Zip),
+            0x0072_6964_6c69_616d => Ok(ArchiveFormat::Maildir),
+            _ => Err(parser.error_value()),
+        }
+    }
+}
+
+impl ArchiveFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Mbox => "mbox",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Maildir => "maildir",
+        }
+    }
+}
+
 impl ImportEmailResponse {
     pub fn update_created_ids(&self, response: &mut Response) {
         for (user_id, obj) in &self.created {
-            if let Some(id) = obj.get(&Property::Id).as_id() {
+            if let Some(id) = obj
+                .get(&Property::Id)
+                .as_id()
+                .or_else(|| obj.get(&Property::TaskId).as_id())
+            {
                 response.created_ids.insert(user_id.clone(), (*id).into());
             }
         }
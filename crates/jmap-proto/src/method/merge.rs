@@ -0,0 +1,178 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::set::SetError,
+    object::Object,
+    parser::{json::Parser, JsonObjectParser, Token},
+    request::{reference::MaybeReference, RequestProperty},
+    response::Response,
+    types::{
+        date::UTCDate,
+        id::Id,
+        property::Property,
+        state::{State, StateChange},
+        value::{SetValueMap, Value},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct MergeSubmissionRequest {
+    pub account_id: Id,
+    pub template_id: MaybeReference<Id, String>,
+    pub identity_id: MaybeReference<Id, String>,
+    pub mailbox_ids: Vec<MaybeReference<Id, String>>,
+    pub variables: VecMap<String, String>,
+    pub send_at: Option<UTCDate>,
+    pub recipients: VecMap<String, MergeRecipient>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeRecipient {
+    pub email: String,
+    pub name: Option<String>,
+    pub variables: VecMap<String, String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MergeSubmissionResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "newState")]
+    pub new_state: State,
+
+    #[serde(rename = "created")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub created: VecMap<String, Object<Value>>,
+
+    #[serde(rename = "notCreated")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_created: VecMap<String, SetError>,
+
+    #[serde(skip)]
+    pub state_change: Option<StateChange>,
+}
+
+impl JsonObjectParser for MergeSubmissionRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = MergeSubmissionRequest {
+            account_id: Id::default(),
+            template_id: MaybeReference::Value(Id::default()),
+            identity_id: MaybeReference::Value(Id::default()),
+            mailbox_ids: vec![],
+            variables: VecMap::new(),
+            send_at: None,
+            recipients: VecMap::new(),
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x6449_6574_616c_706d_6574 if !key.is_ref => {
+                    request.template_id = parser
+                        .next_token::<MaybeReference<Id, String>>()?
+                        .unwrap_string("templateId")?;
+                }
+                0x6449_7974_6974_6e65_6469 if !key.is_ref => {
+                    request.identity_id = parser
+                        .next_token::<MaybeReference<Id, String>>()?
+                        .unwrap_string("identityId")?;
+                }
+                0x7364_4978_6f62_6c69_616d if !key.is_ref => {
+                    request.mailbox_ids =
+                        <SetValueMap<MaybeReference<Id, String>>>::parse(parser)?.values;
+                }
+                0x0073_656c_6261_6972_6176 if !key.is_ref => {
+                    request.variables = parse_variables(parser)?;
+                }
+                0x7441_646e_6573 if !key.is_ref => {
+                    request.send_at = parser
+                        .next_token::<UTCDate>()?
+                        .unwrap_string_or_null("sendAt")?;
+                }
+                0x7374_6e65_6970_6963_6572 if !key.is_ref => {
+                    request.recipients = <VecMap<String, MergeRecipient>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl JsonObjectParser for MergeRecipient {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut recipient = MergeRecipient::default();
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x006c_6961_6d65 if !key.is_ref => {
+                    recipient.email = parser.next_token::<String>()?.unwrap_string("email")?;
+                }
+                0x656d_616e if !key.is_ref => {
+                    recipient.name = parser
+                        .next_token::<String>()?
+                        .unwrap_string_or_null("name")?;
+                }
+                0x0073_656c_6261_6972_6176 if !key.is_ref => {
+                    recipient.variables = parse_variables(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(recipient)
+    }
+}
+
+fn parse_variables(parser: &mut Parser<'_>) -> trc::Result<VecMap<String, String>> {
+    let mut variables = VecMap::new();
+
+    parser
+        .next_token::<String>()?
+        .assert_jmap(Token::DictStart)?;
+
+    while let Some(name) = parser.next_dict_key::<String>()? {
+        let value = parser.next_token::<String>()?.unwrap_string("variables")?;
+        variables.append(name, value);
+    }
+
+    Ok(variables)
+}
+
+impl MergeSubmissionResponse {
+    pub fn update_created_ids(&self, response: &mut Response) {
+        for (user_id, obj) in &self.created {
+            if let Value::Id(id) = obj.get(&Property::Id) {
+                response.created_ids.insert(user_id.clone(), (*id).into());
+            }
+        }
+    }
+}
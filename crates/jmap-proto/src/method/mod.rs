@@ -7,10 +7,12 @@
 use ahash::AHashMap;
 
 pub mod changes;
+pub mod convert;
 pub mod copy;
 pub mod get;
 pub mod import;
 pub mod lookup;
+pub mod merge;
 pub mod parse;
 pub mod query;
 pub mod query_changes;
@@ -7,6 +7,7 @@
 use utils::map::vec_map::VecMap;
 
 use crate::{
+    method::import::ArchiveFormat,
     object::Object,
     parser::{json::Parser, Ignore, JsonObjectParser, Token},
     request::RequestProperty,
@@ -23,6 +24,7 @@ pub struct ParseEmailRequest {
     pub fetch_html_body_values: Option<bool>,
     pub fetch_all_body_values: Option<bool>,
     pub max_body_value_bytes: Option<usize>,
+    pub format: Option<ArchiveFormat>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -57,6 +59,7 @@ impl JsonObjectParser for ParseEmailRequest {
             fetch_html_body_values: None,
             fetch_all_body_values: None,
             max_body_value_bytes: None,
+            format: None,
         };
 
         parser
@@ -97,6 +100,11 @@ impl JsonObjectParser for ParseEmailRequest {
                         .next_token::<Ignore>()?
                         .unwrap_usize_or_null("maxBodyValueBytes")?;
                 }
+                (0x7461_6d72_6f66, _) if !key.is_ref => {
+                    request.format = parser
+                        .next_token::<ArchiveFormat>()?
+                        .unwrap_string_or_null("format")?;
+                }
                 _ => {
                     parser.skip_token(parser.depth_array, parser.depth_dict)?;
                 }
@@ -81,6 +81,9 @@ pub enum Filter {
     HasKeyword(Keyword),
     NotKeyword(Keyword),
     HasAttachment(bool),
+    HasSmime(bool),
+    HasVerifiedSmime(bool),
+    HasVerifiedSmimeAtDelivery(bool),
     From(String),
     To(String),
     Cc(String),
@@ -99,6 +102,8 @@ pub enum Filter {
     IsActive(bool),
     Scope(String),
     ResourceType(String),
+    ObjectType(String),
+    ObjectAccountId(Id),
     _T(String),
 
     And,
@@ -136,6 +141,7 @@ pub enum SortProperty {
     AllInThreadHaveKeyword,
     SomeInThreadHaveKeyword,
     Used,
+    Created,
     _T(String),
 }
 
@@ -147,6 +153,7 @@ pub enum RequestArguments {
     SieveScript,
     Principal,
     Quota,
+    ShareNotification,
 }
 
 impl JsonObjectParser for QueryRequest<RequestArguments> {
@@ -162,6 +169,7 @@ impl JsonObjectParser for QueryRequest<RequestArguments> {
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -366,6 +374,21 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                                 .next_token::<String>()?
                                 .unwrap_bool("hasAttachment")?,
                         ),
+                        (0x656d_696d_5373_6168, _) => Filter::HasSmime(
+                            parser.next_token::<String>()?.unwrap_bool("hasSmime")?,
+                        ),
+                        (0x656d_696d_5364_6569_6669_7265_5673_6168, 0) => Filter::HasVerifiedSmime(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_bool("hasVerifiedSmime")?,
+                        ),
+                        (0x656d_696d_5364_6569_6669_7265_5673_6168, 0x7972_6576_696c_6544_7441) => {
+                            Filter::HasVerifiedSmimeAtDelivery(
+                                parser
+                                    .next_token::<String>()?
+                                    .unwrap_bool("hasVerifiedSmimeAtDelivery")?,
+                            )
+                        }
                         (0x6d6f_7266, _) => {
                             Filter::From(parser.next_token::<String>()?.unwrap_string("from")?)
                         }
@@ -424,6 +447,14 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                                 .next_token::<String>()?
                                 .unwrap_string("resourceType")?,
                         ),
+                        (0x6570_7954_7463_656a_626f, _) => Filter::ObjectType(
+                            parser.next_token::<String>()?.unwrap_string("objectType")?,
+                        ),
+                        (0x0064_4974_6e75_6f63_6341_7463_656a_626f, _) => Filter::ObjectAccountId(
+                            parser
+                                .next_token::<Id>()?
+                                .unwrap_string("objectAccountId")?,
+                        ),
                         _ => {
                             if parser.is_eof || parser.skip_string() {
                                 let filter = Filter::_T(
@@ -566,6 +597,7 @@ impl JsonObjectParser for SortProperty {
             0x4b65_7661_4864_6165_7268_546e_496c_6c61 => Ok(SortProperty::AllInThreadHaveKeyword),
             0x6576_6148_6461_6572_6854_6e49_656d_6f73 => Ok(SortProperty::SomeInThreadHaveKeyword),
             0x6465_7375 => Ok(SortProperty::Used),
+            0x0064_6574_6165_7263 => Ok(SortProperty::Created),
             _ => {
                 if parser.is_eof || parser.skip_string() {
                     Ok(SortProperty::_T(
@@ -610,6 +642,9 @@ impl Display for Filter {
             Filter::HasKeyword(_) => "hasKeyword",
             Filter::NotKeyword(_) => "notKeyword",
             Filter::HasAttachment(_) => "hasAttachment",
+            Filter::HasSmime(_) => "hasSmime",
+            Filter::HasVerifiedSmime(_) => "hasVerifiedSmime",
+            Filter::HasVerifiedSmimeAtDelivery(_) => "hasVerifiedSmimeAtDelivery",
             Filter::From(_) => "from",
             Filter::To(_) => "to",
             Filter::Cc(_) => "cc",
@@ -628,6 +663,8 @@ impl Display for Filter {
             Filter::IsActive(_) => "isActive",
             Filter::ResourceType(_) => "resourceType",
             Filter::Scope(_) => "scope",
+            Filter::ObjectType(_) => "objectType",
+            Filter::ObjectAccountId(_) => "objectAccountId",
             Filter::_T(v) => v.as_str(),
             Filter::And => "and",
             Filter::Or => "or",
@@ -659,6 +696,7 @@ impl Display for SortProperty {
             SortProperty::AllInThreadHaveKeyword => "allInThreadHaveKeyword",
             SortProperty::SomeInThreadHaveKeyword => "someInThreadHaveKeyword",
             SortProperty::Used => "used",
+            SortProperty::Created => "created",
             SortProperty::_T(s) => s,
         })
     }
@@ -694,6 +732,8 @@ impl Filter {
                 | Filter::Id(_)
                 | Filter::SentBefore(_)
                 | Filter::SentAfter(_)
+                | Filter::ObjectType(_)
+                | Filter::ObjectAccountId(_)
         )
     }
 }
@@ -727,6 +767,7 @@ impl Comparator {
                 | SortProperty::To
                 | SortProperty::Subject
                 | SortProperty::Cc
+                | SortProperty::Created
         )
     }
 }
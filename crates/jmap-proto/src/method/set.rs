@@ -51,6 +51,12 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript(sieve::SetArguments),
     VacationResponse,
+    MailRules,
+    ShareNotification,
+    EmailImportTask,
+    EmailTemplate,
+    SavedSearch,
+    Thread,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -110,7 +116,13 @@ impl JsonObjectParser for SetRequest<RequestArguments> {
                 }
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
+                MethodObject::MailRules => RequestArguments::MailRules,
                 MethodObject::SieveScript => RequestArguments::SieveScript(Default::default()),
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
+                MethodObject::EmailImportTask => RequestArguments::EmailImportTask,
+                MethodObject::EmailTemplate => RequestArguments::EmailTemplate,
+                MethodObject::SavedSearch => RequestArguments::SavedSearch,
+                MethodObject::Thread => RequestArguments::Thread,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -239,7 +251,9 @@ impl JsonObjectParser for Object<SetValue> {
                     Property::HasAttachment
                     | Property::IsSubscribed
                     | Property::IsEnabled
-                    | Property::IsActive => parser
+                    | Property::IsActive
+                    | Property::IsMuted
+                    | Property::EmailSummaries => parser
                         .next_token::<String>()?
                         .unwrap_bool_or_null("")?
                         .map(|bool| SetValue::Value(Value::Bool(bool)))
@@ -310,6 +324,7 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::Capabilities
                     | Property::Cc
                     | Property::Envelope
+                    | Property::Filter
                     | Property::From
                     | Property::Headers
                     | Property::InReplyTo
@@ -317,7 +332,9 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::MessageId
                     | Property::References
                     | Property::ReplyTo
+                    | Property::Rules
                     | Property::Sender
+                    | Property::Snoozed
                     | Property::SubParts
                     | Property::To
                     | Property::UndoStatus
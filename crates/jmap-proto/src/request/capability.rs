@@ -71,6 +71,12 @@ pub enum Capability {
     Blob = 1 << 8,
     #[serde(rename(serialize = "urn:ietf:params:jmap:quota"))]
     Quota = 1 << 9,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:webpush-vapid"))]
+    WebPushVapid = 1 << 10,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:smimeverify"))]
+    SmimeVerify = 1 << 11,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:principals"))]
+    Principals = 1 << 12,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -84,6 +90,7 @@ pub enum Capabilities {
     SieveAccount(SieveAccountCapabilities),
     SieveSession(SieveSessionCapabilities),
     Blob(BlobCapabilities),
+    WebPushVapid(WebPushVapidCapabilities),
     Empty(EmptyCapabilities),
 }
 
@@ -173,6 +180,14 @@ pub struct BlobCapabilities {
     pub supported_type_names: Vec<DataType>,
     #[serde(rename(serialize = "supportedDigestAlgorithms"))]
     pub supported_digest_algorithms: Vec<&'static str>,
+    #[serde(rename(serialize = "supportedConvertTypes"))]
+    pub supported_convert_types: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WebPushVapidCapabilities {
+    #[serde(rename(serialize = "applicationServerKey"))]
+    pub application_server_key: String,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -340,6 +355,9 @@ impl JsonObjectParser for Capability {
                 0x0065_7665_6973 => Ok(Capability::Sieve),
                 0x626f_6c62 => Ok(Capability::Blob),
                 0x0061_746f_7571 => Ok(Capability::Quota),
+                0x0064_6970_6176_2d68_7375_7062_6577 => Ok(Capability::WebPushVapid),
+                0x0079_6669_7265_7665_6d69_6d73 => Ok(Capability::SmimeVerify),
+                0x736c_6170_6963_6e69_7270 => Ok(Capability::Principals),
                 _ => Err(parser.error_capability()),
             },
             Err(err) if err.is_jmap_method_error() => Err(parser.error_capability()),
@@ -29,6 +29,11 @@ pub enum MethodObject {
     SieveScript,
     Principal,
     Quota,
+    ShareNotification,
+    EmailImportTask,
+    EmailTemplate,
+    MailRules,
+    SavedSearch,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,6 +49,8 @@ pub enum MethodFunction {
     Validate,
     Lookup,
     Upload,
+    Convert,
+    Merge,
     Echo,
 }
 
@@ -54,6 +61,7 @@ impl JsonObjectParser for MethodName {
     {
         let mut shift = 0;
         let mut obj_hash: u128 = 0;
+        let mut obj_hash_ext: u128 = 0;
         let mut fnc_hash: u128 = 0;
 
         loop {
@@ -64,6 +72,9 @@ impl JsonObjectParser for MethodName {
                 if shift < 128 {
                     obj_hash |= (ch as u128) << shift;
                     shift += 8;
+                } else if shift < 256 {
+                    obj_hash_ext |= (ch as u128) << (shift - 128);
+                    shift += 8;
                 } else {
                     return Err(parser.error_value());
                 }
@@ -84,14 +95,22 @@ impl JsonObjectParser for MethodName {
 
         Ok(MethodName {
             obj: match obj_hash {
+                0x6f69_7461_6369_6669_746f_4e65_7261_6853 if obj_hash_ext == 0x6e => {
+                    MethodObject::ShareNotification
+                }
+                _ if obj_hash_ext != 0 => return Err(parser.error_value()),
                 0x006c_6961_6d45 => MethodObject::Email,
                 0x0078_6f62_6c69_614d => MethodObject::Mailbox,
+                0x006b_7361_5474_726f_706d_496c_6961_6d45 => MethodObject::EmailImportTask,
+                0x0065_7461_6c70_6d65_546c_6961_6d45 => MethodObject::EmailTemplate,
+                0x0068_6372_6165_5364_6576_6153 => MethodObject::SavedSearch,
                 0x6461_6572_6854 => MethodObject::Thread,
                 0x626f_6c42 => MethodObject::Blob,
                 0x006e_6f69_7373_696d_6275_536c_6961_6d45 => MethodObject::EmailSubmission,
                 0x0074_6570_7069_6e53_6863_7261_6553 => MethodObject::SearchSnippet,
                 0x7974_6974_6e65_6449 => MethodObject::Identity,
                 0x6573_6e6f_7073_6552_6e6f_6974_6163_6156 => MethodObject::VacationResponse,
+                0x0073_656c_7552_6c69_614d => MethodObject::MailRules,
                 0x6e6f_6974_7069_7263_7362_7553_6873_7550 => MethodObject::PushSubscription,
                 0x0074_7069_7263_5365_7665_6953 => MethodObject::SieveScript,
                 0x006c_6170_6963_6e69_7250 => MethodObject::Principal,
@@ -111,6 +130,8 @@ impl JsonObjectParser for MethodName {
                 0x6574_6164_696c_6176 => MethodFunction::Validate,
                 0x7075_6b6f_6f6c => MethodFunction::Lookup,
                 0x6461_6f6c_7075 => MethodFunction::Upload,
+                0x0074_7265_766e_6f63 => MethodFunction::Convert,
+                0x0065_6772_656d => MethodFunction::Merge,
                 0x6f68_6365 => MethodFunction::Echo,
                 _ => return Err(parser.error_value()),
             },
@@ -149,6 +170,7 @@ impl MethodName {
 
             (MethodFunction::Get, MethodObject::Thread) => "Thread/get",
             (MethodFunction::Changes, MethodObject::Thread) => "Thread/changes",
+            (MethodFunction::Set, MethodObject::Thread) => "Thread/set",
 
             (MethodFunction::Get, MethodObject::Email) => "Email/get",
             (MethodFunction::Changes, MethodObject::Email) => "Email/changes",
@@ -172,10 +194,14 @@ impl MethodName {
                 "EmailSubmission/queryChanges"
             }
             (MethodFunction::Set, MethodObject::EmailSubmission) => "EmailSubmission/set",
+            (MethodFunction::Merge, MethodObject::EmailSubmission) => "EmailSubmission/merge",
 
             (MethodFunction::Get, MethodObject::VacationResponse) => "VacationResponse/get",
             (MethodFunction::Set, MethodObject::VacationResponse) => "VacationResponse/set",
 
+            (MethodFunction::Get, MethodObject::MailRules) => "MailRules/get",
+            (MethodFunction::Set, MethodObject::MailRules) => "MailRules/set",
+
             (MethodFunction::Get, MethodObject::SieveScript) => "SieveScript/get",
             (MethodFunction::Set, MethodObject::SieveScript) => "SieveScript/set",
             (MethodFunction::Query, MethodObject::SieveScript) => "SieveScript/query",
@@ -190,10 +216,30 @@ impl MethodName {
             (MethodFunction::Query, MethodObject::Quota) => "Quota/query",
             (MethodFunction::QueryChanges, MethodObject::Quota) => "Quota/queryChanges",
 
+            (MethodFunction::Get, MethodObject::ShareNotification) => "ShareNotification/get",
+            (MethodFunction::Changes, MethodObject::ShareNotification) => {
+                "ShareNotification/changes"
+            }
+            (MethodFunction::Set, MethodObject::ShareNotification) => "ShareNotification/set",
+            (MethodFunction::Query, MethodObject::ShareNotification) => "ShareNotification/query",
+
+            (MethodFunction::Get, MethodObject::EmailImportTask) => "EmailImportTask/get",
+            (MethodFunction::Changes, MethodObject::EmailImportTask) => "EmailImportTask/changes",
+            (MethodFunction::Set, MethodObject::EmailImportTask) => "EmailImportTask/set",
+
+            (MethodFunction::Get, MethodObject::EmailTemplate) => "EmailTemplate/get",
+            (MethodFunction::Changes, MethodObject::EmailTemplate) => "EmailTemplate/changes",
+            (MethodFunction::Set, MethodObject::EmailTemplate) => "EmailTemplate/set",
+
+            (MethodFunction::Get, MethodObject::SavedSearch) => "SavedSearch/get",
+            (MethodFunction::Changes, MethodObject::SavedSearch) => "SavedSearch/changes",
+            (MethodFunction::Set, MethodObject::SavedSearch) => "SavedSearch/set",
+
             (MethodFunction::Get, MethodObject::Blob) => "Blob/get",
             (MethodFunction::Copy, MethodObject::Blob) => "Blob/copy",
             (MethodFunction::Lookup, MethodObject::Blob) => "Blob/lookup",
             (MethodFunction::Upload, MethodObject::Blob) => "Blob/upload",
+            (MethodFunction::Convert, MethodObject::Blob) => "Blob/convert",
 
             (MethodFunction::Echo, MethodObject::Core) => "Core/echo",
             _ => "error",
@@ -209,6 +255,7 @@ impl Display for MethodObject {
             MethodObject::SearchSnippet => "SearchSnippet",
             MethodObject::Identity => "Identity",
             MethodObject::VacationResponse => "VacationResponse",
+            MethodObject::MailRules => "MailRules",
             MethodObject::PushSubscription => "PushSubscription",
             MethodObject::SieveScript => "SieveScript",
             MethodObject::Principal => "Principal",
@@ -217,6 +264,10 @@ impl Display for MethodObject {
             MethodObject::Thread => "Thread",
             MethodObject::Email => "Email",
             MethodObject::Quota => "Quota",
+            MethodObject::ShareNotification => "ShareNotification",
+            MethodObject::EmailImportTask => "EmailImportTask",
+            MethodObject::EmailTemplate => "EmailTemplate",
+            MethodObject::SavedSearch => "SavedSearch",
         })
     }
 }
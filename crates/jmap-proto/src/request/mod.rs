@@ -19,10 +19,12 @@ use std::{
 use crate::{
     method::{
         changes::ChangesRequest,
+        convert::BlobConvertRequest,
         copy::{self, CopyBlobRequest, CopyRequest},
         get::{self, GetRequest},
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
+        merge::MergeSubmissionRequest,
         parse::ParseEmailRequest,
         query::{self, QueryRequest},
         query_changes::QueryChangesRequest,
@@ -72,6 +74,8 @@ pub enum RequestMethod {
     ValidateScript(ValidateSieveScriptRequest),
     LookupBlob(BlobLookupRequest),
     UploadBlob(BlobUploadRequest),
+    ConvertBlob(BlobConvertRequest),
+    MergeSubmission(MergeSubmissionRequest),
     Echo(Echo),
     Error(trc::Error),
 }
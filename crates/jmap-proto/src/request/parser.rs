@@ -9,10 +9,12 @@ use std::collections::HashMap;
 use crate::{
     method::{
         changes::ChangesRequest,
+        convert::BlobConvertRequest,
         copy::{CopyBlobRequest, CopyRequest},
         get::GetRequest,
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
+        merge::MergeSubmissionRequest,
         parse::ParseEmailRequest,
         query::QueryRequest,
         query_changes::QueryChangesRequest,
@@ -129,9 +131,14 @@ impl Request {
                                 | MethodObject::EmailSubmission
                                 | MethodObject::PushSubscription
                                 | MethodObject::VacationResponse
+                                | MethodObject::MailRules
                                 | MethodObject::SieveScript
                                 | MethodObject::Principal
                                 | MethodObject::Quota
+                                | MethodObject::ShareNotification
+                                | MethodObject::EmailImportTask
+                                | MethodObject::EmailTemplate
+                                | MethodObject::SavedSearch
                                 | MethodObject::Blob,
                             ) => GetRequest::parse(parser).map(RequestMethod::Get),
                             (MethodFunction::Get, MethodObject::SearchSnippet) => {
@@ -162,6 +169,13 @@ impl Request {
                             (MethodFunction::Upload, MethodObject::Blob) => {
                                 BlobUploadRequest::parse(parser).map(RequestMethod::UploadBlob)
                             }
+                            (MethodFunction::Convert, MethodObject::Blob) => {
+                                BlobConvertRequest::parse(parser).map(RequestMethod::ConvertBlob)
+                            }
+                            (MethodFunction::Merge, MethodObject::EmailSubmission) => {
+                                MergeSubmissionRequest::parse(parser)
+                                    .map(RequestMethod::MergeSubmission)
+                            }
                             (MethodFunction::Import, MethodObject::Email) => {
                                 ImportEmailRequest::parse(parser).map(RequestMethod::ImportEmail)
                             }
@@ -13,10 +13,12 @@ use crate::{
     error::method::MethodErrorWrapper,
     method::{
         changes::ChangesResponse,
+        convert::BlobConvertResponse,
         copy::{CopyBlobResponse, CopyResponse},
         get::GetResponse,
         import::ImportEmailResponse,
         lookup::BlobLookupResponse,
+        merge::MergeSubmissionResponse,
         parse::ParseEmailResponse,
         query::QueryResponse,
         query_changes::QueryChangesResponse,
@@ -47,6 +49,8 @@ pub enum ResponseMethod {
     ValidateScript(ValidateSieveScriptResponse),
     LookupBlob(BlobLookupResponse),
     UploadBlob(BlobUploadResponse),
+    ConvertBlob(BlobConvertResponse),
+    MergeSubmission(MergeSubmissionResponse),
     Echo(Echo),
     Error(MethodErrorWrapper),
 }
@@ -184,6 +188,18 @@ impl From<BlobUploadResponse> for ResponseMethod {
     }
 }
 
+impl From<BlobConvertResponse> for ResponseMethod {
+    fn from(convert_blob: BlobConvertResponse) -> Self {
+        ResponseMethod::ConvertBlob(convert_blob)
+    }
+}
+
+impl From<MergeSubmissionResponse> for ResponseMethod {
+    fn from(merge_submission: MergeSubmissionResponse) -> Self {
+        ResponseMethod::MergeSubmission(merge_submission)
+    }
+}
+
 impl From<BlobLookupResponse> for ResponseMethod {
     fn from(lookup_blob: BlobLookupResponse) -> Self {
         ResponseMethod::LookupBlob(lookup_blob)
@@ -131,6 +131,17 @@ impl Response {
                     }
                 }
             }
+            RequestMethod::MergeSubmission(request) => {
+                // Resolve template, identity and mailbox references
+                for id in [&mut request.template_id, &mut request.identity_id]
+                    .into_iter()
+                    .chain(request.mailbox_ids.iter_mut())
+                {
+                    if let MaybeReference::Reference(ir) = id {
+                        *id = MaybeReference::Value(self.eval_id_reference(ir)?);
+                    }
+                }
+            }
             RequestMethod::SearchSnippet(request) => {
                 // Resolve emailIds references
                 if let MaybeReference::Reference(reference) = &request.email_ids {
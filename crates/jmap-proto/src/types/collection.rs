@@ -24,7 +24,11 @@ pub enum Collection {
     SieveScript = 5,
     PushSubscription = 6,
     Principal = 7,
-    None = 8,
+    ShareNotification = 8,
+    EmailImportTask = 9,
+    EmailTemplate = 10,
+    SavedSearch = 11,
+    None = 12,
 }
 
 impl From<u8> for Collection {
@@ -38,6 +42,10 @@ impl From<u8> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::ShareNotification,
+            9 => Collection::EmailImportTask,
+            10 => Collection::EmailTemplate,
+            11 => Collection::SavedSearch,
             _ => Collection::None,
         }
     }
@@ -54,6 +62,10 @@ impl From<u64> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::ShareNotification,
+            9 => Collection::EmailImportTask,
+            10 => Collection::EmailTemplate,
+            11 => Collection::SavedSearch,
             _ => Collection::None,
         }
     }
@@ -83,6 +95,10 @@ impl TryFrom<Collection> for DataType {
             Collection::EmailSubmission => Ok(DataType::EmailSubmission),
             Collection::SieveScript => Ok(DataType::SieveScript),
             Collection::PushSubscription => Ok(DataType::PushSubscription),
+            Collection::ShareNotification => Ok(DataType::ShareNotification),
+            Collection::EmailImportTask => Ok(DataType::EmailImportTask),
+            Collection::EmailTemplate => Ok(DataType::EmailTemplate),
+            Collection::SavedSearch => Ok(DataType::SavedSearch),
             _ => Err(()),
         }
     }
@@ -105,6 +121,10 @@ impl Collection {
             Collection::EmailSubmission => "emailSubmission",
             Collection::SieveScript => "sieveScript",
             Collection::Principal => "principal",
+            Collection::ShareNotification => "shareNotification",
+            Collection::EmailImportTask => "emailImportTask",
+            Collection::EmailTemplate => "emailTemplate",
+            Collection::SavedSearch => "savedSearch",
             Collection::None => "",
         }
     }
@@ -123,6 +143,10 @@ impl FromStr for Collection {
             "emailSubmission" => Ok(Collection::EmailSubmission),
             "sieveScript" => Ok(Collection::SieveScript),
             "principal" => Ok(Collection::Principal),
+            "shareNotification" => Ok(Collection::ShareNotification),
+            "emailImportTask" => Ok(Collection::EmailImportTask),
+            "emailTemplate" => Ok(Collection::EmailTemplate),
+            "savedSearch" => Ok(Collection::SavedSearch),
             _ => Err(()),
         }
     }
@@ -25,8 +25,10 @@ pub enum Property {
     BodyValues,
     Capabilities,
     Cc,
+    ChangedBy,
     Charset,
     Cid,
+    Created,
     DeliveryStatus,
     Description,
     DeviceClientId,
@@ -35,8 +37,13 @@ pub enum Property {
     Email,
     EmailId,
     EmailIds,
+    EmailSummaries,
     Envelope,
+    Error,
     Expires,
+    FailedEmails,
+    Filter,
+    Format,
     From,
     FromDate,
     HasAttachment,
@@ -46,9 +53,11 @@ pub enum Property {
     HtmlSignature,
     Id,
     IdentityId,
+    ImportedEmails,
     InReplyTo,
     IsActive,
     IsEnabled,
+    IsMuted,
     IsSubscribed,
     Keys,
     Keywords,
@@ -59,25 +68,40 @@ pub enum Property {
     MdnBlobIds,
     Members,
     MessageId,
+    MoveToMailboxId,
     MyRights,
     Name,
+    NewRights,
+    ObjectAccountId,
+    ObjectId,
+    ObjectType,
+    OldRights,
     ParentId,
     PartId,
     Picture,
     Preview,
+    PrincipalId,
     Quota,
     ReceivedAt,
     References,
     ReplyTo,
     Role,
+    Rules,
     Secret,
     SendAt,
     Sender,
     SentAt,
     Size,
+    SmimeErrors,
+    SmimeStatus,
+    SmimeStatusAtDelivery,
+    SmimeVerifiedAt,
+    Snoozed,
     SortOrder,
+    Status,
     Subject,
     SubParts,
+    TaskId,
     TextBody,
     TextSignature,
     ThreadId,
@@ -91,6 +115,7 @@ pub enum Property {
     UndoStatus,
     UnreadEmails,
     UnreadThreads,
+    Until,
     Url,
     VerificationCode,
     Addresses,
@@ -165,7 +190,7 @@ impl JsonObjectParser for Property {
                         hash |= (ch as u128) << shift;
                         shift += 8;
                     } else {
-                        return parser.invalid_property();
+                        return parser.invalid_property().map(parse_long_property);
                     }
                 } else {
                     first_char = ch;
@@ -347,8 +372,10 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         b'c' => match hash {
             0x0073_6569_7469_6c69_6261_7061 => Property::Capabilities,
             0x63 => Property::Cc,
+            0x7942_6465_676e_6168 => Property::ChangedBy,
             0x7465_7372_6168 => Property::Charset,
             0x6469 => Property::Cid,
+            0x6465_7461_6572 => Property::Created,
             _ => return None,
         },
         b'd' => match hash {
@@ -364,11 +391,16 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6c69_616d => Property::Email,
             0x6449_6c69_616d => Property::EmailId,
             0x0073_6449_6c69_616d => Property::EmailIds,
+            0x0073_6569_7261_6d6d_7553_6c69_616d => Property::EmailSummaries,
             0x0065_706f_6c65_766e => Property::Envelope,
+            0x726f_7272 => Property::Error,
             0x7365_7269_7078 => Property::Expires,
             _ => return None,
         },
         b'f' => match hash {
+            0x0073_6c69_616d_4564_656c_6961 => Property::FailedEmails,
+            0x0072_6574_6c69 => Property::Filter,
+            0x0074_616d_726f => Property::Format,
             0x006d_6f72 => Property::From,
             0x0065_7461_446d_6f72 => Property::FromDate,
             _ => return None,
@@ -383,9 +415,11 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         b'i' => match hash {
             0x64 => Property::Id,
             0x0064_4979_7469_746e_6564 => Property::IdentityId,
+            0x0073_6c69_616d_4564_6574_726f_706d => Property::ImportedEmails,
             0x6f54_796c_7065_526e => Property::InReplyTo,
             0x0065_7669_7463_4173 => Property::IsActive,
             0x6465_6c62_616e_4573 => Property::IsEnabled,
+            0x6465_7475_4d73 => Property::IsMuted,
             0x0064_6562_6972_6373_6275_5373 => Property::IsSubscribed,
             _ => return None,
         },
@@ -405,11 +439,20 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6449_626f_6c42_6e64 => Property::MdnBlobIds,
             0x7372_6562_6d65 => Property::Members,
             0x6449_6567_6173_7365 => Property::MessageId,
+            0x6449_786f_626c_6961_4d6f_5465_766f => Property::MoveToMailboxId,
             0x0073_7468_6769_5279 => Property::MyRights,
             _ => return None,
         },
         b'n' => match hash {
             0x0065_6d61 => Property::Name,
+            0x7374_6867_6952_7765 => Property::NewRights,
+            _ => return None,
+        },
+        b'o' => match hash {
+            0x6449_746e_756f_6363_4174_6365_6a62 => Property::ObjectAccountId,
+            0x0064_4974_6365_6a62 => Property::ObjectId,
+            0x0065_7079_5474_6365_6a62 => Property::ObjectType,
+            0x7374_6867_6952_646c => Property::OldRights,
             _ => return None,
         },
         b'p' => match hash {
@@ -417,6 +460,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4974_7261 => Property::PartId,
             0x6572_7574_6369 => Property::Picture,
             0x7765_6976_6572 => Property::Preview,
+            0x6449_6c61_7069_636e_6972 => Property::PrincipalId,
             _ => return None,
         },
         b'q' => match hash {
@@ -428,6 +472,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6563_6e65_7265_6665 => Property::References,
             0x6f54_796c_7065 => Property::ReplyTo,
             0x0065_6c6f => Property::Role,
+            0x7365_6c75 => Property::Rules,
             _ => return None,
         },
         b's' => match hash {
@@ -436,12 +481,18 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0072_6564_6e65 => Property::Sender,
             0x0074_4174_6e65 => Property::SentAt,
             0x0065_7a69 => Property::Size,
+            0x7372_6f72_7245_656d_696d => Property::SmimeErrors,
+            0x7375_7461_7453_656d_696d => Property::SmimeStatus,
+            0x7441_6465_6966_6972_6556_656d_696d => Property::SmimeVerifiedAt,
+            0x6465_7a6f_6f6e => Property::Snoozed,
             0x7265_6472_4f74_726f => Property::SortOrder,
+            0x0073_7574_6174 => Property::Status,
             0x7463_656a_6275 => Property::Subject,
             0x7374_7261_5062_7573 => Property::SubParts,
             _ => return None,
         },
         b't' => match hash {
+            0x0064_496b_7361 => Property::TaskId,
             0x0079_646f_4274_7865 => Property::TextBody,
             0x6572_7574_616e_6769_5374_7865 => Property::TextSignature,
             0x0064_4964_6165_7268 => Property::ThreadId,
@@ -458,6 +509,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_7574_6174_536f_646e => Property::UndoStatus,
             0x0073_6c69_616d_4564_6165_726e => Property::UnreadEmails,
             0x7364_6165_7268_5464_6165_726e => Property::UnreadThreads,
+            0x6c69_746e => Property::Until,
             0x6c72 => Property::Url,
             _ => return None,
         },
@@ -469,6 +521,14 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
     })
 }
 
+// Property names that do not fit in the hash
+fn parse_long_property(property: Property) -> Property {
+    match property {
+        Property::_T(name) if name == "smimeStatusAtDelivery" => Property::SmimeStatusAtDelivery,
+        property => property,
+    }
+}
+
 fn parse_header_property(parser: &mut Parser) -> trc::Result<Property> {
     let hdr_start_pos = parser.pos;
     let mut has_next = false;
@@ -660,6 +720,7 @@ impl JsonObjectParser for ObjectProperty {
                 0x656d_616e_6552_7961 => Property::MayRename,
                 0x6574_656c_6544_7961 => Property::MayDelete,
                 0x7469_6d62_7553_7961 => Property::MaySubmit,
+                0x6449_786f_626c_6961_4d6f_5465_766f => Property::MoveToMailboxId,
                 _ => parser.invalid_property()?,
             },
             b'n' => match hash {
@@ -690,6 +751,7 @@ impl JsonObjectParser for ObjectProperty {
                 _ => parser.invalid_property()?,
             },
             b'u' => match hash {
+                0x6c69_746e => Property::Until,
                 0x0064_6573 => Property::Used,
                 _ => parser.invalid_property()?,
             },
@@ -732,7 +794,7 @@ impl Property {
                         hash |= (ch as u128) << shift;
                         shift += 8;
                     } else {
-                        return Property::_T(value.to_string());
+                        return parse_long_property(Property::_T(value.to_string()));
                     }
                 } else {
                     first_char = ch;
@@ -779,6 +841,8 @@ impl Display for Property {
             Property::BodyValues => write!(f, "bodyValues"),
             Property::Capabilities => write!(f, "capabilities"),
             Property::Cc => write!(f, "cc"),
+            Property::ChangedBy => write!(f, "changedBy"),
+            Property::Created => write!(f, "created"),
             Property::Charset => write!(f, "charset"),
             Property::Cid => write!(f, "cid"),
             Property::DeliveryStatus => write!(f, "deliveryStatus"),
@@ -789,8 +853,13 @@ impl Display for Property {
             Property::Email => write!(f, "email"),
             Property::EmailId => write!(f, "emailId"),
             Property::EmailIds => write!(f, "emailIds"),
+            Property::EmailSummaries => write!(f, "emailSummaries"),
             Property::Envelope => write!(f, "envelope"),
+            Property::Error => write!(f, "error"),
             Property::Expires => write!(f, "expires"),
+            Property::FailedEmails => write!(f, "failedEmails"),
+            Property::Filter => write!(f, "filter"),
+            Property::Format => write!(f, "format"),
             Property::From => write!(f, "from"),
             Property::FromDate => write!(f, "fromDate"),
             Property::HasAttachment => write!(f, "hasAttachment"),
@@ -800,9 +869,11 @@ impl Display for Property {
             Property::HtmlSignature => write!(f, "htmlSignature"),
             Property::Id => write!(f, "id"),
             Property::IdentityId => write!(f, "identityId"),
+            Property::ImportedEmails => write!(f, "importedEmails"),
             Property::InReplyTo => write!(f, "inReplyTo"),
             Property::IsActive => write!(f, "isActive"),
             Property::IsEnabled => write!(f, "isEnabled"),
+            Property::IsMuted => write!(f, "isMuted"),
             Property::IsSubscribed => write!(f, "isSubscribed"),
             Property::Keys => write!(f, "keys"),
             Property::Keywords => write!(f, "keywords"),
@@ -813,25 +884,40 @@ impl Display for Property {
             Property::MdnBlobIds => write!(f, "mdnBlobIds"),
             Property::Members => write!(f, "members"),
             Property::MessageId => write!(f, "messageId"),
+            Property::MoveToMailboxId => write!(f, "moveToMailboxId"),
             Property::MyRights => write!(f, "myRights"),
             Property::Name => write!(f, "name"),
+            Property::NewRights => write!(f, "newRights"),
+            Property::ObjectAccountId => write!(f, "objectAccountId"),
+            Property::ObjectId => write!(f, "objectId"),
+            Property::ObjectType => write!(f, "objectType"),
+            Property::OldRights => write!(f, "oldRights"),
             Property::ParentId => write!(f, "parentId"),
             Property::PartId => write!(f, "partId"),
             Property::Picture => write!(f, "picture"),
             Property::Preview => write!(f, "preview"),
+            Property::PrincipalId => write!(f, "principalId"),
             Property::Quota => write!(f, "quota"),
             Property::ReceivedAt => write!(f, "receivedAt"),
             Property::References => write!(f, "references"),
             Property::ReplyTo => write!(f, "replyTo"),
             Property::Role => write!(f, "role"),
+            Property::Rules => write!(f, "rules"),
             Property::Secret => write!(f, "secret"),
             Property::SendAt => write!(f, "sendAt"),
             Property::Sender => write!(f, "sender"),
             Property::SentAt => write!(f, "sentAt"),
             Property::Size => write!(f, "size"),
+            Property::SmimeErrors => write!(f, "smimeErrors"),
+            Property::SmimeStatus => write!(f, "smimeStatus"),
+            Property::SmimeStatusAtDelivery => write!(f, "smimeStatusAtDelivery"),
+            Property::SmimeVerifiedAt => write!(f, "smimeVerifiedAt"),
+            Property::Snoozed => write!(f, "snoozed"),
             Property::SortOrder => write!(f, "sortOrder"),
+            Property::Status => write!(f, "status"),
             Property::Subject => write!(f, "subject"),
             Property::SubParts => write!(f, "subParts"),
+            Property::TaskId => write!(f, "taskId"),
             Property::TextBody => write!(f, "textBody"),
             Property::TextSignature => write!(f, "textSignature"),
             Property::ThreadId => write!(f, "threadId"),
@@ -845,6 +931,7 @@ impl Display for Property {
             Property::UndoStatus => write!(f, "undoStatus"),
             Property::UnreadEmails => write!(f, "unreadEmails"),
             Property::UnreadThreads => write!(f, "unreadThreads"),
+            Property::Until => write!(f, "until"),
             Property::Url => write!(f, "url"),
             Property::VerificationCode => write!(f, "verificationCode"),
             Property::Parameters => write!(f, "parameters"),
@@ -1063,6 +1150,31 @@ impl From<&Property> for u8 {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::EmailSummaries => 104,
+            Property::SmimeErrors => 105,
+            Property::SmimeStatus => 106,
+            Property::SmimeStatusAtDelivery => 107,
+            Property::SmimeVerifiedAt => 108,
+            Property::Snoozed => 109,
+            Property::Until => 110,
+            Property::MoveToMailboxId => 111,
+            Property::ChangedBy => 112,
+            Property::Created => 113,
+            Property::NewRights => 114,
+            Property::ObjectAccountId => 115,
+            Property::ObjectId => 116,
+            Property::ObjectType => 117,
+            Property::OldRights => 118,
+            Property::PrincipalId => 119,
+            Property::Error => 120,
+            Property::FailedEmails => 121,
+            Property::Format => 122,
+            Property::ImportedEmails => 123,
+            Property::Status => 124,
+            Property::TaskId => 125,
+            Property::Rules => 126,
+            Property::Filter => 127,
+            Property::IsMuted => 128,
             Property::Digest(_) | Property::Data(_) => unreachable!("invalid property"),
         }
     }
@@ -1205,6 +1317,31 @@ impl SerializeInto for Property {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::EmailSummaries => 104,
+            Property::SmimeErrors => 105,
+            Property::SmimeStatus => 106,
+            Property::SmimeStatusAtDelivery => 107,
+            Property::SmimeVerifiedAt => 108,
+            Property::Snoozed => 109,
+            Property::Until => 110,
+            Property::MoveToMailboxId => 111,
+            Property::ChangedBy => 112,
+            Property::Created => 113,
+            Property::NewRights => 114,
+            Property::ObjectAccountId => 115,
+            Property::ObjectId => 116,
+            Property::ObjectType => 117,
+            Property::OldRights => 118,
+            Property::PrincipalId => 119,
+            Property::Error => 120,
+            Property::FailedEmails => 121,
+            Property::Format => 122,
+            Property::ImportedEmails => 123,
+            Property::Status => 124,
+            Property::TaskId => 125,
+            Property::Rules => 126,
+            Property::Filter => 127,
+            Property::IsMuted => 128,
             Property::Digest(_) | Property::Data(_) => {
                 unreachable!("Property::Digest and Property::Data are not serializable")
             }
@@ -1323,6 +1460,31 @@ impl DeserializeFrom for Property {
             101 => Some(Property::WarnLimit),
             102 => Some(Property::SoftLimit),
             103 => Some(Property::Scope),
+            104 => Some(Property::EmailSummaries),
+            105 => Some(Property::SmimeErrors),
+            106 => Some(Property::SmimeStatus),
+            107 => Some(Property::SmimeStatusAtDelivery),
+            108 => Some(Property::SmimeVerifiedAt),
+            109 => Some(Property::Snoozed),
+            110 => Some(Property::Until),
+            111 => Some(Property::MoveToMailboxId),
+            112 => Some(Property::ChangedBy),
+            113 => Some(Property::Created),
+            114 => Some(Property::NewRights),
+            115 => Some(Property::ObjectAccountId),
+            116 => Some(Property::ObjectId),
+            117 => Some(Property::ObjectType),
+            118 => Some(Property::OldRights),
+            119 => Some(Property::PrincipalId),
+            120 => Some(Property::Error),
+            121 => Some(Property::FailedEmails),
+            122 => Some(Property::Format),
+            123 => Some(Property::ImportedEmails),
+            124 => Some(Property::Status),
+            125 => Some(Property::TaskId),
+            126 => Some(Property::Rules),
+            127 => Some(Property::Filter),
+            128 => Some(Property::IsMuted),
             _ => None,
         }
     }
@@ -41,7 +41,15 @@ pub enum DataType {
     Quota = 11,
     #[serde(rename = "SieveScript")]
     SieveScript = 12,
-    None = 13,
+    #[serde(rename = "ShareNotification")]
+    ShareNotification = 13,
+    #[serde(rename = "EmailImportTask")]
+    EmailImportTask = 14,
+    #[serde(rename = "EmailTemplate")]
+    EmailTemplate = 15,
+    #[serde(rename = "SavedSearch")]
+    SavedSearch = 16,
+    None = 17,
 }
 
 impl BitmapItem for DataType {
@@ -70,6 +78,10 @@ impl From<u64> for DataType {
             10 => DataType::Mdn,
             11 => DataType::Quota,
             12 => DataType::SieveScript,
+            13 => DataType::ShareNotification,
+            14 => DataType::EmailImportTask,
+            15 => DataType::EmailTemplate,
+            16 => DataType::SavedSearch,
             _ => {
                 debug_assert!(false, "Invalid type_state value: {}", value);
                 DataType::None
@@ -90,21 +102,32 @@ impl JsonObjectParser for DataType {
         Self: Sized,
     {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         while let Some(ch) = parser.next_unescaped()? {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
                 shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
+                shift += 8;
             } else {
                 return Err(parser.error_value());
             }
         }
 
         match hash {
+            0x6f69_7461_6369_6669_746f_4e65_7261_6853 if hash_ext == 0x6e => {
+                Ok(DataType::ShareNotification)
+            }
+            _ if hash_ext != 0 => Err(parser.error_value()),
             0x006c_6961_6d45 => Ok(DataType::Email),
             0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(DataType::EmailDelivery),
             0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(DataType::EmailSubmission),
+            0x006b_7361_5474_726f_706d_496c_6961_6d45 => Ok(DataType::EmailImportTask),
+            0x0065_7461_6c70_6d65_546c_6961_6d45 => Ok(DataType::EmailTemplate),
+            0x0068_6372_6165_5364_6576_6153 => Ok(DataType::SavedSearch),
             0x0078_6f62_6c69_614d => Ok(DataType::Mailbox),
             0x6461_6572_6854 => Ok(DataType::Thread),
             0x7974_6974_6e65_6449 => Ok(DataType::Identity),
@@ -125,21 +148,32 @@ impl TryFrom<&str> for DataType {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         for &ch in value.as_bytes() {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
                 shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
+                shift += 8;
             } else {
                 return Err(());
             }
         }
 
         match hash {
+            0x6f69_7461_6369_6669_746f_4e65_7261_6853 if hash_ext == 0x6e => {
+                Ok(DataType::ShareNotification)
+            }
+            _ if hash_ext != 0 => Err(()),
             0x006c_6961_6d45 => Ok(DataType::Email),
             0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(DataType::EmailDelivery),
             0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(DataType::EmailSubmission),
+            0x006b_7361_5474_726f_706d_496c_6961_6d45 => Ok(DataType::EmailImportTask),
+            0x0065_7461_6c70_6d65_546c_6961_6d45 => Ok(DataType::EmailTemplate),
+            0x0068_6372_6165_5364_6576_6153 => Ok(DataType::SavedSearch),
             0x0078_6f62_6c69_614d => Ok(DataType::Mailbox),
             0x6461_6572_6854 => Ok(DataType::Thread),
             0x7974_6974_6e65_6449 => Ok(DataType::Identity),
@@ -171,6 +205,10 @@ impl DataType {
             DataType::Mdn => "MDN",
             DataType::Quota => "Quota",
             DataType::SieveScript => "SieveScript",
+            DataType::ShareNotification => "ShareNotification",
+            DataType::EmailImportTask => "EmailImportTask",
+            DataType::EmailTemplate => "EmailTemplate",
+            DataType::SavedSearch => "SavedSearch",
             DataType::None => "",
         }
     }
@@ -204,6 +242,10 @@ impl DeserializeFrom for DataType {
             10 => Some(DataType::Mdn),
             11 => Some(DataType::Quota),
             12 => Some(DataType::SieveScript),
+            13 => Some(DataType::ShareNotification),
+            14 => Some(DataType::EmailImportTask),
+            15 => Some(DataType::EmailTemplate),
+            16 => Some(DataType::SavedSearch),
             _ => None,
         }
     }
@@ -142,6 +142,17 @@ impl Value {
                 .map(Value::Text)
                 .unwrap_or(Value::Null)),
 
+            Property::Until => Ok(parser
+                .next_token::<UTCDate>()?
+                .unwrap_string_or_null("")?
+                .map(Value::Date)
+                .unwrap_or(Value::Null)),
+            Property::MoveToMailboxId => Ok(parser
+                .next_token::<Id>()?
+                .unwrap_string_or_null("")?
+                .map(Value::Id)
+                .unwrap_or(Value::Null)),
+
             Property::Header(h) => {
                 if matches!(h.form, HeaderForm::Date) {
                     Value::parse::<ObjectProperty, UTCDate>(parser.next_token()?, parser)
@@ -42,6 +42,7 @@ use crate::{
             token::TokenHandler, FormData,
         },
         rate_limit::RateLimiter,
+        webauthn::WebAuthnHandler,
     },
     blob::{download::BlobDownload, upload::BlobUpload, DownloadResponse, UploadResponse},
     websocket::upgrade::WebSocketUpgrade,
@@ -53,6 +54,7 @@ use super::{
     form::FormHandler,
     management::{ManagementApi, ManagementApiError},
     request::RequestHandler,
+    scim::ScimApi,
     session::SessionHandler,
     HtmlResponse, HttpRequest, HttpResponse, HttpResponseBody, JmapSessionManager, JsonResponse,
 };
@@ -286,6 +288,19 @@ impl ParseHttp for Server {
 
                     return self.handle_token_request(&mut req, session).await;
                 }
+                ("webauthn", &Method::POST) => {
+                    self.is_anonymous_allowed(&session.remote_ip).await?;
+
+                    match path.next().unwrap_or_default() {
+                        "challenge" => {
+                            return self.handle_webauthn_challenge(&mut req, session).await;
+                        }
+                        "code" => {
+                            return self.handle_webauthn_code(&mut req, session).await;
+                        }
+                        _ => (),
+                    }
+                }
                 ("introspect", &Method::POST) => {
                     // Authenticate request
                     let (_in_flight, access_token) =
@@ -377,6 +392,16 @@ impl ParseHttp for Server {
                     }
                 }
             }
+            "scim" => {
+                if path.next().unwrap_or_default() == "v2" {
+                    // Authenticate API key
+                    let (_, access_token) = self.authenticate_headers(&req, &session, true).await?;
+
+                    return self
+                        .handle_scim_request(&mut req, access_token, &session)
+                        .await;
+                }
+            }
             "mail" => {
                 if req.method() == Method::GET
                     && path.next().unwrap_or_default() == "config-v1.1.xml"
@@ -636,6 +661,16 @@ async fn handle_session<T: SessionStream>(inner: Arc<Inner>, session: SessionDat
                     // Build response
                     let mut response = response.build();
 
+                    // Offer Kerberos authentication
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && server.core.jmap.kerberos.is_some()
+                    {
+                        response.headers_mut().insert(
+                            header::WWW_AUTHENTICATE,
+                            header::HeaderValue::from_static("Negotiate"),
+                        );
+                    }
+
                     // Add custom headers
                     if !server.core.jmap.http_headers.is_empty() {
                         let headers = response.headers_mut();
@@ -990,6 +1025,12 @@ impl ToRequestError for trc::Error {
                     RequestError::blank(402, "TOTP code required", cause.message())
                 }
                 trc::AuthEvent::TooManyAttempts => RequestError::too_many_auth_attempts(),
+                trc::AuthEvent::AccountLocked => {
+                    RequestError::blank(401, "Account Locked", cause.message())
+                }
+                trc::AuthEvent::PasswordExpired => {
+                    RequestError::blank(401, "Password Expired", cause.message())
+                }
                 _ => RequestError::unauthorized(),
             },
             trc::EventType::Security(cause) => match cause {
@@ -0,0 +1,430 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{auth::AccessToken, Server};
+use directory::{
+    backend::internal::{manage, PrincipalField, PrincipalUpdate, PrincipalValue},
+    QueryBy,
+};
+use hyper::{header, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+
+use crate::api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse};
+
+use super::{decode_path_element, principal::PrincipalManager};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub emails: Vec<String>,
+    #[serde(default)]
+    pub forward_to: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProfileUpdate {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub forward_to: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AliasRequest {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppPasswordRequest {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TotpConfirmRequest {
+    code: String,
+}
+
+pub trait AccountManager: Sync + Send {
+    fn handle_account_profile(
+        &self,
+        req: &HttpRequest,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_account_aliases(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_account_app_passwords(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_account_totp(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_account_sessions(
+        &self,
+        req: &HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl AccountManager for Server {
+    async fn handle_account_profile(
+        &self,
+        req: &HttpRequest,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let account_id = assert_account(&access_token)?;
+
+        match *req.method() {
+            Method::GET => {
+                let mut principal = self
+                    .core
+                    .storage
+                    .directory
+                    .query(QueryBy::Id(account_id), false)
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": AccountProfile {
+                        name: principal.take_str(PrincipalField::Name).unwrap_or_default(),
+                        description: principal.take_str(PrincipalField::Description),
+                        emails: principal
+                            .take_str_array(PrincipalField::Emails)
+                            .unwrap_or_default(),
+                        forward_to: principal
+                            .take_str_array(PrincipalField::ForwardTo)
+                            .unwrap_or_default(),
+                    },
+                }))
+                .into_http_response())
+            }
+            Method::PATCH => {
+                let request = parse_body::<AccountProfileUpdate>(body.as_deref())?;
+
+                let mut changes = Vec::with_capacity(2);
+                if let Some(description) = request.description {
+                    changes.push(PrincipalUpdate::set(
+                        PrincipalField::Description,
+                        PrincipalValue::String(description.trim().to_string()),
+                    ));
+                }
+                if let Some(forward_to) = request.forward_to {
+                    let config = &self.core.jmap.self_service;
+                    if !forward_to.is_empty() && !config.forwarding_enabled {
+                        return Err(manage::error(
+                            "Forwarding is disabled",
+                            "Contact your administrator to enable forwarding".into(),
+                        ));
+                    } else if forward_to.len() > config.max_forward_to {
+                        return Err(manage::error(
+                            "Too many forwarding addresses",
+                            format!(
+                                "Accounts are limited to {} forwarding addresses",
+                                config.max_forward_to
+                            )
+                            .into(),
+                        ));
+                    } else if let Some(address) = forward_to.iter().find(|address| {
+                        access_token
+                            .emails
+                            .iter()
+                            .any(|email| email.eq_ignore_ascii_case(address.trim()))
+                    }) {
+                        return Err(manage::error(
+                            "Invalid forwarding address",
+                            format!("Cannot forward to your own address {address:?}").into(),
+                        ));
+                    }
+
+                    changes.push(PrincipalUpdate::set(
+                        PrincipalField::ForwardTo,
+                        PrincipalValue::StringList(forward_to),
+                    ));
+                }
+
+                if !changes.is_empty() {
+                    // Make sure the current directory supports updates
+                    self.assert_supported_updates(&changes)?;
+
+                    update_account(self, &access_token, changes).await?;
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+
+    async fn handle_account_aliases(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        assert_account(&access_token)?;
+
+        let change = match (path.get(2).copied(), req.method()) {
+            (None, &Method::POST) => {
+                let address = parse_body::<AliasRequest>(body.as_deref())?
+                    .address
+                    .trim()
+                    .to_lowercase();
+                self.assert_alias_policy(&access_token, &address)?;
+
+                PrincipalUpdate::add_item(PrincipalField::Emails, PrincipalValue::String(address))
+            }
+            (Some(address), &Method::DELETE) => {
+                let address = decode_path_element(address).to_lowercase();
+                match access_token
+                    .emails
+                    .iter()
+                    .position(|email| *email == address)
+                {
+                    Some(0) => {
+                        return Err(manage::error(
+                            "Cannot remove primary address",
+                            address.into(),
+                        ));
+                    }
+                    Some(_) => {}
+                    None => return Err(manage::not_found(address)),
+                }
+
+                PrincipalUpdate::remove_item(
+                    PrincipalField::Emails,
+                    PrincipalValue::String(address),
+                )
+            }
+            _ => return Err(trc::ResourceEvent::NotFound.into_err()),
+        };
+
+        // Make sure the current directory supports updates
+        self.assert_supported_updates(std::slice::from_ref(&change))?;
+
+        update_account(self, &access_token, vec![change]).await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": (),
+        }))
+        .into_http_response())
+    }
+
+    async fn handle_account_app_passwords(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let account_id = assert_account(&access_token)?;
+
+        match (path.get(2).copied(), req.method()) {
+            (None, &Method::GET) => Ok(JsonResponse::new(json!({
+                "data": self.app_passwords(account_id).await?,
+            }))
+            .into_http_response()),
+            (None, &Method::POST) => {
+                let request = parse_body::<AppPasswordRequest>(body.as_deref())?;
+
+                // Make sure the current directory supports updates
+                self.assert_supported_directory()?;
+
+                let password = self
+                    .create_app_password(&access_token, &request.name)
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "name": request.name.trim(),
+                        "password": password,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(name), &Method::DELETE) => {
+                let name = decode_path_element(name);
+
+                // Make sure the current directory supports updates
+                self.assert_supported_directory()?;
+
+                if !self
+                    .app_passwords(account_id)
+                    .await?
+                    .iter()
+                    .any(|app| app.name == name)
+                {
+                    return Err(manage::not_found(name.into_owned()));
+                }
+
+                update_account(
+                    self,
+                    &access_token,
+                    vec![PrincipalUpdate::remove_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(format!("$app${name}$")),
+                    )],
+                )
+                .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+
+    async fn handle_account_totp(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let account_id = assert_account(&access_token)?;
+
+        // Make sure the user authenticated using Basic auth
+        if !is_basic_auth(req) {
+            return Err(manage::error(
+                "TOTP changes only allowed using Basic auth",
+                None::<u32>,
+            ));
+        }
+
+        // Make sure the current directory supports updates
+        self.assert_supported_directory()?;
+
+        let data = match (path.get(2).copied(), req.method()) {
+            (Some("enroll"), &Method::POST) => json!({
+                "url": self.enroll_totp(&access_token).await?,
+            }),
+            (Some("confirm"), &Method::POST) => {
+                let request = parse_body::<TotpConfirmRequest>(body.as_deref())?;
+                json!({
+                    "recoveryCodes": self.confirm_totp(&access_token, &request.code).await?,
+                })
+            }
+            (Some("recovery-codes"), &Method::GET) => json!({
+                "remaining": self.recovery_codes_left(account_id).await?,
+            }),
+            (Some("recovery-codes"), &Method::POST) => json!({
+                "recoveryCodes": self.regenerate_recovery_codes(&access_token).await?,
+            }),
+            _ => return Err(trc::ResourceEvent::NotFound.into_err()),
+        };
+
+        Ok(JsonResponse::new(json!({
+            "data": data,
+        }))
+        .into_http_response())
+    }
+
+    async fn handle_account_sessions(
+        &self,
+        req: &HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let account_id = assert_account(&access_token)?;
+
+        match *req.method() {
+            Method::DELETE => {
+                self.revoke_sessions(account_id).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
+
+async fn update_account(
+    server: &Server,
+    access_token: &AccessToken,
+    changes: Vec<PrincipalUpdate>,
+) -> trc::Result<()> {
+    server
+        .update_principal_with_write_back(
+            &access_token.name,
+            access_token.primary_id(),
+            access_token.tenant.map(|t| t.id),
+            changes,
+        )
+        .await?;
+
+    // Remove entries from cache
+    server
+        .inner
+        .data
+        .http_auth_cache
+        .retain(|_, id| id.item != access_token.primary_id());
+    server
+        .inner
+        .data
+        .access_tokens
+        .remove(&access_token.primary_id());
+    server.directory().groups.clear_members();
+
+    Ok(())
+}
+
+fn assert_account(access_token: &AccessToken) -> trc::Result<u32> {
+    if access_token.primary_id() != u32::MAX {
+        Ok(access_token.primary_id())
+    } else {
+        Err(manage::error(
+            "Fallback administrator accounts do not support self-service",
+            None::<u32>,
+        ))
+    }
+}
+
+fn is_basic_auth(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|header| header.to_lowercase().starts_with("basic "))
+}
+
+fn parse_body<'x, T: Deserialize<'x>>(body: Option<&'x [u8]>) -> trc::Result<T> {
+    serde_json::from_slice::<T>(body.unwrap_or_default()).map_err(|err| {
+        trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+    })
+}
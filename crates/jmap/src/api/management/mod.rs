@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod account;
 pub mod dkim;
 pub mod dns;
 #[cfg(feature = "enterprise")]
@@ -19,6 +20,7 @@ pub mod stores;
 
 use std::{borrow::Cow, str::FromStr, sync::Arc};
 
+use account::AccountManager;
 use common::{auth::AccessToken, Server};
 use directory::{backend::internal::manage, Permission};
 use dkim::DkimManagement;
@@ -37,7 +39,10 @@ use sieve::SieveHandler;
 use store::write::now;
 use stores::ManageStore;
 
-use crate::{auth::oauth::auth::OAuthApiHandler, email::crypto::CryptoHandler};
+use crate::{
+    auth::{oauth::auth::OAuthApiHandler, webauthn::WebAuthnHandler},
+    email::crypto::CryptoHandler,
+};
 
 use super::{
     http::{fetch_body, HttpSessionData},
@@ -152,6 +157,46 @@ impl ManagementApi for Server {
 
                     self.handle_account_auth_post(req, access_token, body).await
                 }
+                ("passkey", &Method::POST) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManagePasswords)?;
+
+                    self.handle_passkey_request(req, path, body, access_token, session)
+                        .await
+                }
+                ("profile", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManageProfile)?;
+
+                    self.handle_account_profile(req, body, access_token).await
+                }
+                ("aliases", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManageAliases)?;
+
+                    self.handle_account_aliases(req, path, body, access_token)
+                        .await
+                }
+                ("app-passwords", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManagePasswords)?;
+
+                    self.handle_account_app_passwords(req, path, body, access_token)
+                        .await
+                }
+                ("totp", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManagePasswords)?;
+
+                    self.handle_account_totp(req, path, body, access_token)
+                        .await
+                }
+                ("sessions", _) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::ManagePasswords)?;
+
+                    self.handle_account_sessions(req, access_token).await
+                }
                 _ => Err(trc::ResourceEvent::NotFound.into_err()),
             },
             // SPDX-SnippetBegin
@@ -6,14 +6,17 @@
 
 use std::sync::{atomic::Ordering, Arc};
 
-use common::{auth::AccessToken, Server};
+use common::{
+    auth::{webauthn::WebAuthnCredential, AccessToken},
+    Server,
+};
 use directory::{
     backend::internal::{
         lookup::DirectoryStore,
         manage::{self, not_found, ManageDirectory, UpdatePrincipal},
         PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
     },
-    DirectoryInner, Permission, Principal, QueryBy, Type,
+    DirectoryInner, Permission, Permissions, Principal, QueryBy, Type,
 };
 
 use hyper::{header, Method};
@@ -35,6 +38,7 @@ pub enum AccountAuthRequest {
     DisableOtpAuth { url: Option<String> },
     AddAppPassword { name: String, password: String },
     RemoveAppPassword { name: String },
+    RemovePasskey { name: String },
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -43,6 +47,8 @@ pub struct AccountAuthResponse {
     pub otp_auth: bool,
     #[serde(rename = "appPasswords")]
     pub app_passwords: Vec<String>,
+    #[serde(default)]
+    pub passkeys: Vec<String>,
 }
 
 pub trait PrincipalManager: Sync + Send {
@@ -66,7 +72,30 @@ pub trait PrincipalManager: Sync + Send {
         body: Option<Vec<u8>>,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
 
+    fn assert_domain_access(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        name: &str,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn assert_domain_scope(
+        &self,
+        access_token: &AccessToken,
+        changes: &[PrincipalUpdate],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
     fn assert_supported_directory(&self) -> trc::Result<()>;
+
+    fn assert_supported_updates(&self, changes: &[PrincipalUpdate]) -> trc::Result<()>;
+
+    fn update_principal_with_write_back(
+        &self,
+        name: &str,
+        account_id: u32,
+        tenant_id: Option<u32>,
+        changes: Vec<PrincipalUpdate>,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
 }
 
 impl PrincipalManager for Server {
@@ -114,18 +143,78 @@ impl PrincipalManager for Server {
 
                 // SPDX-SnippetEnd
 
+                // Domain administrators can only create principals within their domains
+                if !access_token.domain_scope.is_empty() {
+                    if !principal.has_domain_access(&access_token.domain_scope) {
+                        trc::bail!(trc::SecurityEvent::Unauthorized
+                            .into_err()
+                            .details(principal.name().to_string())
+                            .ctx(trc::Key::Reason, "Principal is outside the domain scope"));
+                    }
+
+                    let mut changes = principal
+                        .get_str(PrincipalField::Tenant)
+                        .map(|tenant| {
+                            vec![PrincipalUpdate::set(
+                                PrincipalField::Tenant,
+                                PrincipalValue::String(tenant.to_string()),
+                            )]
+                        })
+                        .unwrap_or_default();
+                    if let Some(filter) = principal.get_str(PrincipalField::MemberFilter) {
+                        changes.push(PrincipalUpdate::set(
+                            PrincipalField::MemberFilter,
+                            PrincipalValue::String(filter.to_string()),
+                        ));
+                    }
+                    for field in [
+                        PrincipalField::DomainScope,
+                        PrincipalField::EnabledPermissions,
+                        PrincipalField::Roles,
+                        PrincipalField::MemberOf,
+                        PrincipalField::Lists,
+                        PrincipalField::Members,
+                    ] {
+                        if let Some(values) = principal.get_str_array(field) {
+                            changes.push(PrincipalUpdate::set(
+                                field,
+                                PrincipalValue::StringList(values.to_vec()),
+                            ));
+                        }
+                    }
+                    self.assert_domain_scope(access_token, &changes).await?;
+                }
+
                 // Make sure the current directory supports updates
                 if matches!(principal.typ(), Type::Individual) {
                     self.assert_supported_directory()?;
+                    self.assert_principal_passwords(&principal).await?;
                 }
 
                 // Create principal
+                let is_group = matches!(principal.typ(), Type::Group | Type::List);
                 let result = self
                     .core
                     .storage
                     .data
                     .create_principal(principal, access_token.tenant.map(|t| t.id))
                     .await?;
+                if is_group {
+                    self.directory().groups.clear();
+                } else {
+                    self.directory().groups.clear_members();
+                }
+
+                // SPDX-SnippetBegin
+                // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+                // SPDX-License-Identifier: LicenseRef-SEL
+
+                #[cfg(feature = "enterprise")]
+                self.assign_principal_shard(result)
+                    .await
+                    .caused_by(trc::location!())?;
+
+                // SPDX-SnippetEnd
 
                 Ok(JsonResponse::new(json!({
                     "data": result,
@@ -226,7 +315,15 @@ impl PrincipalManager for Server {
                     .core
                     .storage
                     .data
-                    .list_principals(filter, tenant, &types, &fields, page, limit)
+                    .list_scoped_principals(
+                        filter,
+                        tenant,
+                        &access_token.domain_scope,
+                        &types,
+                        &fields,
+                        page,
+                        limit,
+                    )
                     .await?;
 
                 if count {
@@ -238,6 +335,30 @@ impl PrincipalManager for Server {
                 }))
                 .into_http_response())
             }
+            (Some(name), &Method::DELETE) if path.get(2) == Some(&"lockout") => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::IndividualUpdate)?;
+
+                // Remove the lockout of the account
+                let name = decode_path_element(name);
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_info(name.as_ref())
+                    .await?
+                    .filter(|p| p.has_tenant_access(access_token.tenant.map(|t| t.id)))
+                    .map(|p| p.id)
+                    .ok_or_else(|| not_found(name.to_string()))?;
+                self.assert_domain_access(access_token, account_id, name.as_ref())
+                    .await?;
+                self.unlock_account(account_id).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
             (Some(name), method) => {
                 // Fetch, update or delete principal
                 let name = decode_path_element(name);
@@ -250,6 +371,8 @@ impl PrincipalManager for Server {
                     .filter(|p| p.has_tenant_access(access_token.tenant.map(|t| t.id)))
                     .map(|p| (p.id, p.typ))
                     .ok_or_else(|| not_found(name.to_string()))?;
+                self.assert_domain_access(access_token, account_id, name.as_ref())
+                    .await?;
 
                 // SPDX-SnippetBegin
                 // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
@@ -341,6 +464,12 @@ impl PrincipalManager for Server {
                                 .data
                                 .permissions_version
                                 .fetch_add(1, Ordering::Relaxed);
+                        } else if matches!(typ, Type::Group | Type::List) {
+                            // Update nested and dynamic group cache
+                            self.directory().groups.clear();
+                        } else {
+                            // Update the members of dynamic lists
+                            self.directory().groups.clear_members();
                         }
 
                         Ok(JsonResponse::new(json!({
@@ -378,6 +507,7 @@ impl PrincipalManager for Server {
                         let mut expire_session = false;
                         let mut expire_token = false;
                         let mut is_role_change = false;
+                        let mut is_group_change = matches!(typ, Type::Group | Type::List);
 
                         for change in &changes {
                             match change.field {
@@ -385,17 +515,25 @@ impl PrincipalManager for Server {
                                     expire_session = true;
                                     needs_assert = true;
                                 }
+                                PrincipalField::Status => {
+                                    expire_session = true;
+                                    expire_token = true;
+                                }
                                 PrincipalField::Name
                                 | PrincipalField::Emails
                                 | PrincipalField::Quota
                                 | PrincipalField::UsedQuota
+                                | PrincipalField::StatusSince
                                 | PrincipalField::Description
                                 | PrincipalField::Type
                                 | PrincipalField::Picture
-                                | PrincipalField::MemberOf
+                                | PrincipalField::Urls => (),
+                                PrincipalField::MemberOf
                                 | PrincipalField::Members
                                 | PrincipalField::Lists
-                                | PrincipalField::Urls => (),
+                                | PrincipalField::MemberFilter => {
+                                    is_group_change = true;
+                                }
                                 PrincipalField::Tenant => {
                                     // Tenants are not allowed to change their tenantId
                                     if access_token.tenant.is_some() {
@@ -408,6 +546,9 @@ impl PrincipalManager for Server {
                                             ));
                                     }
                                 }
+                                PrincipalField::ForwardTo | PrincipalField::DomainScope => {
+                                    expire_token = true;
+                                }
                                 PrincipalField::Roles
                                 | PrincipalField::EnabledPermissions
                                 | PrincipalField::DisabledPermissions => {
@@ -420,20 +561,37 @@ impl PrincipalManager for Server {
                             }
                         }
 
+                        self.assert_domain_scope(access_token, &changes).await?;
+
                         if needs_assert {
-                            self.assert_supported_directory()?;
+                            self.assert_supported_updates(&changes)?;
+
+                            if typ == Type::Individual {
+                                self.assert_password_updates(Some(account_id), &changes)
+                                    .await?;
+                            }
                         }
 
                         // Update principal
-                        self.core
-                            .storage
-                            .data
-                            .update_principal(
-                                UpdatePrincipal::by_id(account_id)
-                                    .with_updates(changes)
-                                    .with_tenant(access_token.tenant.map(|t| t.id)),
+                        if typ == Type::Individual {
+                            self.update_principal_with_write_back(
+                                name.as_ref(),
+                                account_id,
+                                access_token.tenant.map(|t| t.id),
+                                changes,
                             )
                             .await?;
+                        } else {
+                            self.core
+                                .storage
+                                .data
+                                .update_principal(
+                                    UpdatePrincipal::by_id(account_id)
+                                        .with_updates(changes)
+                                        .with_tenant(access_token.tenant.map(|t| t.id)),
+                                )
+                                .await?;
+                        }
 
                         if expire_session {
                             // Remove entries from cache
@@ -456,6 +614,14 @@ impl PrincipalManager for Server {
                             self.inner.data.access_tokens.remove(&account_id);
                         }
 
+                        if is_group_change {
+                            // Update nested and dynamic group cache
+                            self.directory().groups.clear();
+                        } else {
+                            // Update the members of dynamic lists
+                            self.directory().groups.clear_members();
+                        }
+
                         Ok(JsonResponse::new(json!({
                             "data": (),
                         }))
@@ -476,6 +642,7 @@ impl PrincipalManager for Server {
         let mut response = AccountAuthResponse {
             otp_auth: false,
             app_passwords: Vec::new(),
+            passkeys: Vec::new(),
         };
 
         if access_token.primary_id() != u32::MAX {
@@ -494,6 +661,8 @@ impl PrincipalManager for Server {
                     secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
                 {
                     response.app_passwords.push(app_name.to_string());
+                } else if let Some(passkey) = WebAuthnCredential::parse(secret) {
+                    response.passkeys.push(passkey.name);
                 }
             }
         }
@@ -547,6 +716,7 @@ impl PrincipalManager for Server {
         if access_token.primary_id() == u32::MAX {
             match requests.into_iter().next().unwrap() {
                 AccountAuthRequest::SetPassword { password } => {
+                    self.assert_password_policy(None, &password).await?;
                     self.core
                         .storage
                         .config
@@ -566,16 +736,13 @@ impl PrincipalManager for Server {
                 }
                 _ => {
                     return Err(manage::error(
-                        "Fallback administrator accounts do not support 2FA or AppPasswords",
+                        "Fallback administrator accounts do not support 2FA, AppPasswords or passkeys",
                         None::<u32>,
                     ));
                 }
             }
         }
 
-        // Make sure the current directory supports updates
-        self.assert_supported_directory()?;
-
         // Build actions
         let mut actions = Vec::with_capacity(requests.len());
         for request in requests {
@@ -590,16 +757,27 @@ impl PrincipalManager for Server {
                     (PrincipalAction::AddItem, password)
                 }
                 AccountAuthRequest::EnableOtpAuth { url } => (PrincipalAction::AddItem, url),
-                AccountAuthRequest::DisableOtpAuth { url } => (
-                    PrincipalAction::RemoveItem,
-                    url.unwrap_or_else(|| "otpauth://".to_string()),
-                ),
+                AccountAuthRequest::DisableOtpAuth { url } => {
+                    // Recovery codes are only valid while TOTP is enabled
+                    actions.push(PrincipalUpdate::remove_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String("$recovery$".to_string()),
+                    ));
+
+                    (
+                        PrincipalAction::RemoveItem,
+                        url.unwrap_or_else(|| "otpauth://".to_string()),
+                    )
+                }
                 AccountAuthRequest::AddAppPassword { name, password } => {
                     (PrincipalAction::AddItem, format!("$app${name}${password}"))
                 }
                 AccountAuthRequest::RemoveAppPassword { name } => {
                     (PrincipalAction::RemoveItem, format!("$app${name}"))
                 }
+                AccountAuthRequest::RemovePasskey { name } => {
+                    (PrincipalAction::RemoveItem, format!("$webauthn${name}$"))
+                }
             };
 
             actions.push(PrincipalUpdate {
@@ -609,17 +787,22 @@ impl PrincipalManager for Server {
             });
         }
 
-        // Update password
-        self.core
-            .storage
-            .data
-            .update_principal(
-                UpdatePrincipal::by_id(access_token.primary_id())
-                    .with_updates(actions)
-                    .with_tenant(access_token.tenant.map(|t| t.id)),
-            )
+        // Make sure the current directory supports updates
+        self.assert_supported_updates(&actions)?;
+
+        // Enforce the password policy
+        self.assert_password_updates(Some(access_token.primary_id()), &actions)
             .await?;
 
+        // Update password
+        self.update_principal_with_write_back(
+            &access_token.name,
+            access_token.primary_id(),
+            access_token.tenant.map(|t| t.id),
+            actions,
+        )
+        .await?;
+
         // Remove entries from cache
         self.inner
             .data
@@ -632,6 +815,125 @@ impl PrincipalManager for Server {
         .into_http_response())
     }
 
+    async fn assert_domain_access(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        name: &str,
+    ) -> trc::Result<()> {
+        if access_token.domain_scope.is_empty() {
+            return Ok(());
+        }
+
+        // Principals outside the domain scope are reported as missing
+        let principal = self
+            .core
+            .storage
+            .data
+            .query(QueryBy::Id(account_id), true)
+            .await?
+            .filter(|p| p.has_domain_access(&access_token.domain_scope))
+            .ok_or_else(|| not_found(name.to_string()))?;
+
+        // Domain administrators cannot manage other administrators
+        let mut permissions = Permissions::new();
+        for role_id in principal.iter_int(PrincipalField::Roles) {
+            permissions.union(&self.get_role_permissions(role_id as u32).await?.enabled);
+        }
+        for permission in principal.iter_int(PrincipalField::EnabledPermissions) {
+            if (permission as usize) < Permission::COUNT {
+                permissions.set(permission as usize);
+            }
+        }
+        if principal.has_field(PrincipalField::DomainScope)
+            || Permission::all().any(|p| permissions.get(p.id()) && !p.is_user_permission())
+        {
+            trc::bail!(trc::SecurityEvent::Unauthorized
+                .into_err()
+                .details(name.to_string())
+                .ctx(
+                    trc::Key::Reason,
+                    "Domain administrators cannot manage other administrators"
+                ));
+        }
+
+        Ok(())
+    }
+
+    async fn assert_domain_scope(
+        &self,
+        access_token: &AccessToken,
+        changes: &[PrincipalUpdate],
+    ) -> trc::Result<()> {
+        let scope = &access_token.domain_scope;
+        if scope.is_empty() {
+            return Ok(());
+        }
+
+        for change in changes {
+            if !matches!(
+                change.action,
+                PrincipalAction::Set | PrincipalAction::AddItem
+            ) {
+                continue;
+            }
+
+            let reason = match change.field {
+                // Dynamic member filters can match principals outside the scope
+                PrincipalField::Tenant
+                | PrincipalField::DomainScope
+                | PrincipalField::EnabledPermissions
+                | PrincipalField::MemberFilter => {
+                    format!(
+                        "Domain administrators cannot change {}",
+                        change.field.as_str()
+                    )
+                }
+                PrincipalField::Roles => {
+                    if let Some(role) = change.value.iter_str().find(|role| *role != "user") {
+                        format!("Domain administrators cannot assign role {role:?}")
+                    } else {
+                        continue;
+                    }
+                }
+                PrincipalField::Emails => {
+                    if let Some(email) = change.value.iter_str().find(|email| {
+                        !email.rsplit_once('@').is_some_and(|(_, domain)| {
+                            scope.iter().any(|d| d.eq_ignore_ascii_case(domain))
+                        })
+                    }) {
+                        format!("Address {email:?} is outside the domain scope")
+                    } else {
+                        continue;
+                    }
+                }
+                PrincipalField::MemberOf | PrincipalField::Lists | PrincipalField::Members => {
+                    for name in change.value.iter_str() {
+                        let store = &self.core.storage.data;
+                        let account_id = store
+                            .get_principal_id(name)
+                            .await?
+                            .ok_or_else(|| not_found(name.to_string()))?;
+                        store
+                            .query(QueryBy::Id(account_id), false)
+                            .await?
+                            .filter(|p| p.has_domain_access(scope))
+                            .ok_or_else(|| not_found(name.to_string()))?;
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            trc::bail!(trc::SecurityEvent::Unauthorized
+                .into_err()
+                .details(change.field.as_str())
+                .ctx(trc::Key::Reason, reason));
+        }
+
+        Ok(())
+    }
+
     fn assert_supported_directory(&self) -> trc::Result<()> {
         let class = match &self.core.storage.directory.store {
             DirectoryInner::Internal(_) => return Ok(()),
@@ -653,4 +955,56 @@ impl PrincipalManager for Server {
             class
         )))
     }
+
+    fn assert_supported_updates(&self, changes: &[PrincipalUpdate]) -> trc::Result<()> {
+        if self.core.storage.directory.supports_updates(changes) {
+            Ok(())
+        } else {
+            self.assert_supported_directory()
+        }
+    }
+
+    async fn update_principal_with_write_back(
+        &self,
+        name: &str,
+        account_id: u32,
+        tenant_id: Option<u32>,
+        changes: Vec<PrincipalUpdate>,
+    ) -> trc::Result<()> {
+        // External directories are updated first as they are the source of truth
+        let written_back = self
+            .core
+            .storage
+            .directory
+            .write_back(name, &changes)
+            .await?;
+
+        match self
+            .core
+            .storage
+            .data
+            .update_principal(
+                UpdatePrincipal::by_id(account_id)
+                    .with_updates(changes)
+                    .with_tenant(tenant_id),
+            )
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(err) if written_back => {
+                trc::error!(err
+                    .clone()
+                    .account_id(account_id)
+                    .details("Failed to store changes written back to the external directory"));
+
+                Err(manage::error(
+                    "Partial update",
+                    Some(
+                        "Changes were written back to the external directory but could not be stored locally",
+                    ),
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
 }
@@ -24,7 +24,9 @@ use crate::{
         http::{HttpSessionData, ToHttpResponse},
         HttpRequest, HttpResponse, JsonResponse,
     },
-    services::index::Indexer,
+    services::{
+        fsck::StoreCheck, index::Indexer, quota::QuotaRecalculation, sync::DirectorySynchronization,
+    },
 };
 
 use super::{decode_path_element, enterprise::undelete::UndeleteApi};
@@ -184,6 +186,77 @@ impl ManageStore for Server {
                 }))
                 .into_http_response())
             }
+            (Some("fsck"), id, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::StoreFsck)?;
+
+                let account_id = if let Some(id) = id {
+                    self.core
+                        .storage
+                        .data
+                        .get_principal_id(decode_path_element(id).as_ref())
+                        .await?
+                        .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?
+                        .into()
+                } else {
+                    None
+                };
+                let repair = UrlParams::new(req.uri().query())
+                    .parse("repair")
+                    .unwrap_or(false);
+                let report = self
+                    .fsck(account_id, access_token.tenant.map(|t| t.id), repair)
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": report,
+                }))
+                .into_http_response())
+            }
+            (Some("quota"), id, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::StoreQuotaRecalculate)?;
+
+                let tenant_id = access_token.tenant.map(|t| t.id);
+                let account_id = if let Some(id) = id {
+                    self.core
+                        .storage
+                        .data
+                        .get_principal_info(decode_path_element(id).as_ref())
+                        .await?
+                        .filter(|principal| principal.has_tenant_access(tenant_id))
+                        .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?
+                        .id
+                        .into()
+                } else {
+                    None
+                };
+                let repair = UrlParams::new(req.uri().query())
+                    .parse("repair")
+                    .unwrap_or(false);
+                let report = self
+                    .recalculate_quotas(account_id, tenant_id, repair)
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": report,
+                }))
+                .into_http_response())
+            }
+            (Some("sync"), Some(id), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::DirectorySync)?;
+
+                let dry_run = UrlParams::new(req.uri().query()).parse("dry-run");
+                let report = self
+                    .synchronize_directory(decode_path_element(id).as_ref(), dry_run)
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": report,
+                }))
+                .into_http_response())
+            }
             // SPDX-SnippetBegin
             // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
             // SPDX-License-Identifier: LicenseRef-SEL
@@ -208,6 +281,38 @@ impl ManageStore for Server {
                     Err(manage::enterprise())
                 }
             }
+            #[cfg(feature = "enterprise")]
+            (Some("shard"), Some(account), Some(shard_id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::StoreShardMove)?;
+
+                let store::Store::Sharded(store) = self.store().clone() else {
+                    return Err(manage::unsupported("Data store is not sharded"));
+                };
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let shard_id = decode_path_element(shard_id).into_owned();
+                if !store.shard_ids().any(|id| id == shard_id) {
+                    return Err(manage::not_found(shard_id));
+                }
+
+                let lookup = self.core.storage.lookup.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = store.move_account(&lookup, account_id, &shard_id).await {
+                        trc::error!(err.details("Failed to move account"));
+                    }
+                });
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
             // SPDX-SnippetEnd
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
@@ -18,6 +18,7 @@ pub mod form;
 pub mod http;
 pub mod management;
 pub mod request;
+pub mod scim;
 pub mod session;
 
 #[derive(Clone)]
@@ -70,6 +71,35 @@ pub struct StateChangeResponse {
     #[serde(rename = "@type")]
     pub type_: StateChangeType,
     pub changed: VecMap<Id, VecMap<DataType, State>>,
+    #[serde(
+        rename = "emailSummaries",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub email_summaries: Vec<EmailSummary>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EmailSummary {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+    #[serde(rename = "emailId")]
+    pub email_id: Id,
+    #[serde(rename = "threadId")]
+    pub thread_id: Id,
+    pub from: Vec<EmailSummaryAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub preview: String,
+    #[serde(rename = "receivedAt")]
+    pub received_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EmailSummaryAddress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub email: String,
 }
 
 impl StateChangeResponse {
@@ -77,6 +107,7 @@ impl StateChangeResponse {
         Self {
             type_: StateChangeType::StateChange,
             changed: VecMap::new(),
+            email_summaries: Vec::new(),
         }
     }
 }
@@ -19,24 +19,34 @@ use jmap_proto::{
 use trc::JmapEvent;
 
 use crate::{
-    blob::{copy::BlobCopy, get::BlobOperations, upload::BlobUpload},
+    blob::{convert::BlobConvert, copy::BlobCopy, get::BlobOperations, upload::BlobUpload},
     changes::{get::ChangesLookup, query::QueryChanges},
     email::{
         copy::EmailCopy, get::EmailGet, import::EmailImport, parse::EmailParse, query::EmailQuery,
         set::EmailSet, snippet::EmailSearchSnippet,
     },
     identity::{get::IdentityGet, set::IdentitySet},
+    import_task::{get::EmailImportTaskGet, set::EmailImportTaskSet},
     mailbox::{get::MailboxGet, query::MailboxQuery, set::MailboxSet},
     principal::{get::PrincipalGet, query::PrincipalQuery},
     push::{get::PushSubscriptionFetch, set::PushSubscriptionSet},
     quota::{get::QuotaGet, query::QuotaQuery},
+    rules::{get::MailRulesGet, set::MailRulesSet},
+    saved_search::{get::SavedSearchGet, set::SavedSearchSet},
     services::state::StateManager,
+    share_notification::{
+        get::ShareNotificationGet, query::ShareNotificationQuery, set::ShareNotificationSet,
+    },
     sieve::{
         get::SieveScriptGet, query::SieveScriptQuery, set::SieveScriptSet,
         validate::SieveScriptValidate,
     },
-    submission::{get::EmailSubmissionGet, query::EmailSubmissionQuery, set::EmailSubmissionSet},
-    thread::get::ThreadGet,
+    submission::{
+        get::EmailSubmissionGet, merge::EmailSubmissionMerge, query::EmailSubmissionQuery,
+        set::EmailSubmissionSet,
+    },
+    template::{get::EmailTemplateGet, set::EmailTemplateSet},
+    thread::{get::ThreadGet, set::ThreadSet},
     vacation::{get::VacationResponseGet, set::VacationResponseSet},
 };
 
@@ -121,6 +131,15 @@ impl RequestHandler for Server {
                                     self.broadcast_state_change(state_change).await;
                                 }
                             }
+                            ResponseMethod::MergeSubmission(merge_response) => {
+                                // Add created ids
+                                merge_response.update_created_ids(&mut response);
+
+                                // Publish state changes
+                                if let Some(state_change) = merge_response.state_change.take() {
+                                    self.broadcast_state_change(state_change).await;
+                                }
+                            }
                             ResponseMethod::Copy(copy_response) => {
                                 // Publish state changes
                                 if let Some(state_change) = copy_response.state_change.take() {
@@ -222,6 +241,11 @@ impl RequestHandler for Server {
 
                     self.vacation_response_get(req).await?.into()
                 }
+                get::RequestArguments::MailRules => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.mail_rules_get(req).await?.into()
+                }
                 get::RequestArguments::Principal => self.principal_get(req).await?.into(),
                 get::RequestArguments::Quota => {
                     access_token.assert_is_member(req.account_id)?;
@@ -235,6 +259,26 @@ impl RequestHandler for Server {
                         .await?
                         .into()
                 }
+                get::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_get(req).await?.into()
+                }
+                get::RequestArguments::EmailImportTask => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.email_import_task_get(req).await?.into()
+                }
+                get::RequestArguments::EmailTemplate => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.email_template_get(req).await?.into()
+                }
+                get::RequestArguments::SavedSearch => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.saved_search_get(req, access_token).await?.into()
+                }
             },
             RequestMethod::Query(mut req) => match req.take_arguments() {
                 query::RequestArguments::Email(arguments) => {
@@ -269,6 +313,11 @@ impl RequestHandler for Server {
 
                     self.quota_query(req, access_token).await?.into()
                 }
+                query::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_query(req).await?.into()
+                }
             },
             RequestMethod::Set(mut req) => match req.take_arguments() {
                 set::RequestArguments::Email => {
@@ -314,6 +363,36 @@ impl RequestHandler for Server {
 
                     self.vacation_response_set(req, access_token).await?.into()
                 }
+                set::RequestArguments::MailRules => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.mail_rules_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_set(req).await?.into()
+                }
+                set::RequestArguments::EmailImportTask => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.email_import_task_set(req).await?.into()
+                }
+                set::RequestArguments::EmailTemplate => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.email_template_set(req).await?.into()
+                }
+                set::RequestArguments::SavedSearch => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.saved_search_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::Thread => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.thread_set(req).await?.into()
+                }
             },
             RequestMethod::Changes(req) => self.changes(req, access_token).await?.into(),
             RequestMethod::Copy(req) => {
@@ -361,6 +440,23 @@ impl RequestHandler for Server {
 
                 self.blob_upload_many(req, access_token).await?.into()
             }
+            RequestMethod::ConvertBlob(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.blob_convert(req, access_token).await?.into()
+            }
+            RequestMethod::MergeSubmission(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.email_submission_merge(
+                    req,
+                    access_token,
+                    &session.instance,
+                    session.session_id,
+                )
+                .await?
+                .into()
+            }
             RequestMethod::Echo(req) => req.into(),
             RequestMethod::Error(error) => return Err(error),
         };
@@ -0,0 +1,447 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{iter::Peekable, str::Chars};
+
+use serde_json::Value;
+
+use super::{scim_error, SCHEMA_GROUP, SCHEMA_USER};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Present(AttrPath),
+    Compare {
+        path: AttrPath,
+        op: Operator,
+        value: Value,
+    },
+    ValuePath {
+        path: AttrPath,
+        filter: Box<Filter>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Contains,
+    StartsWith,
+    EndsWith,
+    GreaterThan,
+    GreaterEqualThan,
+    LowerThan,
+    LowerEqualThan,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrPath {
+    pub schema: Option<String>,
+    pub attr: String,
+    pub sub_attr: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchPath {
+    pub path: AttrPath,
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Value(Value),
+    ParenOpen,
+    ParenClose,
+    BracketOpen,
+    BracketClose,
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> trc::Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(filter)?.into_iter().peekable(),
+        };
+        let filter = parser.parse_or()?;
+        if parser.tokens.next().is_none() {
+            Ok(filter)
+        } else {
+            Err(invalid_filter("Unexpected trailing tokens"))
+        }
+    }
+
+    pub fn matches(&self, resource: &Value) -> bool {
+        match self {
+            Filter::And(left, right) => left.matches(resource) && right.matches(resource),
+            Filter::Or(left, right) => left.matches(resource) || right.matches(resource),
+            Filter::Not(filter) => !filter.matches(resource),
+            Filter::Present(path) => path.resolve(resource).into_iter().any(|value| match value {
+                Value::Null => false,
+                Value::String(value) => !value.is_empty(),
+                Value::Array(values) => !values.is_empty(),
+                _ => true,
+            }),
+            Filter::Compare {
+                path,
+                op: Operator::NotEqual,
+                value,
+            } => !path
+                .resolve(resource)
+                .into_iter()
+                .any(|item| compare(item, Operator::Equal, value)),
+            Filter::Compare { path, op, value } => path
+                .resolve(resource)
+                .into_iter()
+                .any(|item| compare(item, *op, value)),
+            Filter::ValuePath { path, filter } => path
+                .resolve_container(resource)
+                .and_then(|value| value.as_array())
+                .is_some_and(|items| items.iter().any(|item| filter.matches(item))),
+        }
+    }
+
+    /// Returns the attribute and value of simple equality filters,
+    /// used to create missing values when patching multi-valued attributes.
+    pub fn as_equality(&self) -> Option<(&str, &Value)> {
+        match self {
+            Filter::Compare {
+                path,
+                op: Operator::Equal,
+                value,
+            } if path.sub_attr.is_none() => Some((path.attr.as_str(), value)),
+            _ => None,
+        }
+    }
+}
+
+impl AttrPath {
+    pub fn parse(path: &str) -> trc::Result<Self> {
+        let (schema, path) = match path.rsplit_once(':') {
+            Some((schema, path)) if schema.starts_with("urn:") => {
+                if schema.eq_ignore_ascii_case(SCHEMA_USER)
+                    || schema.eq_ignore_ascii_case(SCHEMA_GROUP)
+                {
+                    (None, path)
+                } else {
+                    (Some(schema.to_string()), path)
+                }
+            }
+            _ => (None, path),
+        };
+        let (attr, sub_attr) = match path.split_once('.') {
+            Some((attr, sub_attr)) => (attr, Some(sub_attr.to_string())),
+            None => (path, None),
+        };
+
+        if !attr.is_empty()
+            && attr
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '$'))
+        {
+            Ok(AttrPath {
+                schema,
+                attr: attr.to_string(),
+                sub_attr,
+            })
+        } else {
+            Err(scim_error(
+                "invalidPath",
+                format!("Invalid attribute path {path:?}"),
+            ))
+        }
+    }
+
+    pub fn resolve_container<'x>(&self, resource: &'x Value) -> Option<&'x Value> {
+        let resource = if let Some(schema) = &self.schema {
+            get_ignore_case(resource, schema)?
+        } else {
+            resource
+        };
+        get_ignore_case(resource, &self.attr)
+    }
+
+    fn resolve<'x>(&self, resource: &'x Value) -> Vec<&'x Value> {
+        let Some(value) = self.resolve_container(resource) else {
+            return vec![];
+        };
+
+        // Multi-valued attributes are compared by their "value" sub-attribute
+        // unless a different sub-attribute is requested
+        match (value, &self.sub_attr) {
+            (Value::Array(items), sub_attr) => items
+                .iter()
+                .filter_map(|item| {
+                    if item.is_object() {
+                        get_ignore_case(item, sub_attr.as_deref().unwrap_or("value"))
+                    } else if sub_attr.is_none() {
+                        Some(item)
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            (Value::Object(_), Some(sub_attr)) => {
+                get_ignore_case(value, sub_attr).into_iter().collect()
+            }
+            (value, None) => vec![value],
+            _ => vec![],
+        }
+    }
+}
+
+impl PatchPath {
+    pub fn parse(value: &str) -> trc::Result<Self> {
+        if let Some((attr, rest)) = value.split_once('[') {
+            let (filter, sub_attr) = rest.rsplit_once(']').ok_or_else(|| {
+                scim_error("invalidPath", format!("Invalid attribute path {value:?}"))
+            })?;
+            let mut path = AttrPath::parse(attr)?;
+            if path.sub_attr.is_some() {
+                return Err(scim_error(
+                    "invalidPath",
+                    format!("Invalid attribute path {value:?}"),
+                ));
+            }
+            if let Some(sub_attr) = sub_attr.strip_prefix('.') {
+                path.sub_attr = Some(sub_attr.to_string());
+            } else if !sub_attr.is_empty() {
+                return Err(scim_error(
+                    "invalidPath",
+                    format!("Invalid attribute path {value:?}"),
+                ));
+            }
+
+            Ok(PatchPath {
+                path,
+                filter: Some(Filter::parse(filter)?),
+            })
+        } else {
+            Ok(PatchPath {
+                path: AttrPath::parse(value)?,
+                filter: None,
+            })
+        }
+    }
+}
+
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn parse_or(&mut self) -> trc::Result<Filter> {
+        let mut filter = self.parse_and()?;
+        while self.next_is_word("or") {
+            self.tokens.next();
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> trc::Result<Filter> {
+        let mut filter = self.parse_unary()?;
+        while self.next_is_word("and") {
+            self.tokens.next();
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_unary(&mut self) -> trc::Result<Filter> {
+        match self.tokens.next() {
+            Some(Token::ParenOpen) => {
+                let filter = self.parse_or()?;
+                self.expect(Token::ParenClose)?;
+                Ok(filter)
+            }
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("not") => {
+                self.expect(Token::ParenOpen)?;
+                let filter = self.parse_or()?;
+                self.expect(Token::ParenClose)?;
+                Ok(Filter::Not(Box::new(filter)))
+            }
+            Some(Token::Word(word)) => {
+                let path = AttrPath::parse(&word)?;
+
+                match self.tokens.next() {
+                    Some(Token::BracketOpen) => {
+                        let filter = self.parse_or()?;
+                        self.expect(Token::BracketClose)?;
+                        Ok(Filter::ValuePath {
+                            path,
+                            filter: Box::new(filter),
+                        })
+                    }
+                    Some(Token::Word(op)) if op.eq_ignore_ascii_case("pr") => {
+                        Ok(Filter::Present(path))
+                    }
+                    Some(Token::Word(op)) => {
+                        let op = match op.to_ascii_lowercase().as_str() {
+                            "eq" => Operator::Equal,
+                            "ne" => Operator::NotEqual,
+                            "co" => Operator::Contains,
+                            "sw" => Operator::StartsWith,
+                            "ew" => Operator::EndsWith,
+                            "gt" => Operator::GreaterThan,
+                            "ge" => Operator::GreaterEqualThan,
+                            "lt" => Operator::LowerThan,
+                            "le" => Operator::LowerEqualThan,
+                            _ => {
+                                return Err(invalid_filter(format!("Unknown operator {op:?}")));
+                            }
+                        };
+                        let value = match self.tokens.next() {
+                            Some(Token::Value(value)) => value,
+                            Some(Token::Word(word)) => match word.as_str() {
+                                "true" => Value::Bool(true),
+                                "false" => Value::Bool(false),
+                                "null" => Value::Null,
+                                _ => {
+                                    return Err(invalid_filter(format!(
+                                        "Invalid comparison value {word:?}"
+                                    )));
+                                }
+                            },
+                            _ => return Err(invalid_filter("Missing comparison value")),
+                        };
+
+                        Ok(Filter::Compare { path, op, value })
+                    }
+                    _ => Err(invalid_filter("Missing operator")),
+                }
+            }
+            _ => Err(invalid_filter("Expected attribute path")),
+        }
+    }
+
+    fn next_is_word(&mut self, expected: &str) -> bool {
+        matches!(self.tokens.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(expected))
+    }
+
+    fn expect(&mut self, expected: Token) -> trc::Result<()> {
+        if self.tokens.next().as_ref() == Some(&expected) {
+            Ok(())
+        } else {
+            Err(invalid_filter(format!("Expected {expected:?}")))
+        }
+    }
+}
+
+fn tokenize(filter: &str) -> trc::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' => tokens.push(Token::ParenOpen),
+            ')' => tokens.push(Token::ParenClose),
+            '[' => tokens.push(Token::BracketOpen),
+            ']' => tokens.push(Token::BracketClose),
+            '"' => tokens.push(Token::Value(Value::String(parse_string(&mut chars)?))),
+            ch if ch.is_whitespace() => (),
+            ch => {
+                let mut word = String::from(ch);
+                while let Some(ch) = chars.peek() {
+                    if ch.is_whitespace() || matches!(ch, '(' | ')' | '[' | ']' | '"') {
+                        break;
+                    }
+                    word.push(*ch);
+                    chars.next();
+                }
+
+                if let Some(number) = word
+                    .starts_with(|ch: char| ch.is_ascii_digit() || ch == '-')
+                    .then(|| serde_json::from_str::<serde_json::Number>(&word).ok())
+                    .flatten()
+                {
+                    tokens.push(Token::Value(Value::Number(number)));
+                } else {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_string(chars: &mut Peekable<Chars<'_>>) -> trc::Result<String> {
+    let mut value = String::from('"');
+    let mut is_escaped = false;
+
+    for ch in chars.by_ref() {
+        value.push(ch);
+        if is_escaped {
+            is_escaped = false;
+        } else if ch == '\\' {
+            is_escaped = true;
+        } else if ch == '"' {
+            return serde_json::from_str(&value)
+                .map_err(|_| invalid_filter(format!("Invalid string {value}")));
+        }
+    }
+
+    Err(invalid_filter("Unterminated string"))
+}
+
+fn compare(item: &Value, op: Operator, value: &Value) -> bool {
+    match (item, value) {
+        (Value::String(item), Value::String(value)) => {
+            let item = item.to_lowercase();
+            let value = value.to_lowercase();
+            match op {
+                Operator::Equal => item == value,
+                Operator::NotEqual => item != value,
+                Operator::Contains => item.contains(&value),
+                Operator::StartsWith => item.starts_with(&value),
+                Operator::EndsWith => item.ends_with(&value),
+                Operator::GreaterThan => item > value,
+                Operator::GreaterEqualThan => item >= value,
+                Operator::LowerThan => item < value,
+                Operator::LowerEqualThan => item <= value,
+            }
+        }
+        (Value::Number(item), Value::Number(value)) => {
+            let (Some(item), Some(value)) = (item.as_f64(), value.as_f64()) else {
+                return false;
+            };
+            match op {
+                Operator::Equal => item == value,
+                Operator::NotEqual => item != value,
+                Operator::GreaterThan => item > value,
+                Operator::GreaterEqualThan => item >= value,
+                Operator::LowerThan => item < value,
+                Operator::LowerEqualThan => item <= value,
+                Operator::Contains | Operator::StartsWith | Operator::EndsWith => false,
+            }
+        }
+        // Resource ids are serialized as strings but may be compared against numbers
+        (Value::String(_), Value::Number(number)) => {
+            compare(item, op, &Value::String(number.to_string()))
+        }
+        (item, value) => match op {
+            Operator::Equal => item == value,
+            Operator::NotEqual => item != value,
+            _ => false,
+        },
+    }
+}
+
+pub fn get_ignore_case<'x>(value: &'x Value, key: &str) -> Option<&'x Value> {
+    value.as_object().and_then(|object| {
+        object
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    })
+}
+
+fn invalid_filter(details: impl Into<String>) -> trc::Error {
+    scim_error("invalidFilter", details.into())
+}
@@ -0,0 +1,825 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+pub mod filter;
+pub mod patch;
+pub mod resource;
+
+use std::sync::Arc;
+
+use common::{auth::AccessToken, Server};
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{not_found, ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+    },
+    Permission, Principal, QueryBy, Type,
+};
+use filter::Filter;
+use hyper::{Method, StatusCode};
+use patch::PatchRequest;
+use resource::{user_schemas, Group, MultiValue, Name, Reference, User, UserExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use trc::AddContext;
+use utils::url_params::UrlParams;
+
+use crate::api::{
+    http::{fetch_body, HttpContext, HttpSessionData, ToRequestError},
+    management::principal::PrincipalManager,
+    HttpRequest, HttpResponse,
+};
+use std::future::Future;
+
+pub const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+pub const SCHEMA_USER_EXTENSION: &str = "urn:ietf:params:scim:schemas:extension:stalwart:2.0:User";
+pub const SCHEMA_LIST_RESPONSE: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const SCHEMA_PATCH_OP: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+pub const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+pub const SCHEMA_SERVICE_PROVIDER_CONFIG: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+pub const SCHEMA_RESOURCE_TYPE: &str = "urn:ietf:params:scim:schemas:core:2.0:ResourceType";
+
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceType {
+    User,
+    Group,
+}
+
+pub trait ScimApi: Sync + Send {
+    fn handle_scim_request(
+        &self,
+        req: &mut HttpRequest,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl ScimApi for Server {
+    async fn handle_scim_request(
+        &self,
+        req: &mut HttpRequest,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        let body = fetch_body(req, 1024 * 1024, session.session_id).await;
+        let base_url = format!(
+            "{}/scim/v2",
+            HttpContext::new(session, req)
+                .resolve_response_url(self)
+                .await
+        );
+
+        match handle_request(self, req, body, &base_url, &access_token).await {
+            Ok(response) => Ok(response),
+            Err(err) => {
+                // SCIM clients expect errors in the SCIM format
+                let response = error_response(&err);
+                trc::error!(err.span_id(session.session_id));
+                Ok(response)
+            }
+        }
+    }
+}
+
+async fn handle_request(
+    server: &Server,
+    req: &HttpRequest,
+    body: Option<Vec<u8>>,
+    base_url: &str,
+    access_token: &AccessToken,
+) -> trc::Result<HttpResponse> {
+    // Path is /scim/v2/<resource>[/<id>]
+    let path = req.uri().path().split('/').skip(3).collect::<Vec<_>>();
+    let typ = match path.first().copied().unwrap_or_default() {
+        "Users" => ResourceType::User,
+        "Groups" => ResourceType::Group,
+        "ServiceProviderConfig" if req.method() == Method::GET => {
+            return Ok(scim_response(
+                StatusCode::OK,
+                service_provider_config(base_url),
+            ));
+        }
+        "ResourceTypes" if req.method() == Method::GET => {
+            return Ok(scim_response(StatusCode::OK, resource_types(base_url)));
+        }
+        _ => return Err(trc::ResourceEvent::NotFound.into_err()),
+    };
+
+    match (
+        path.get(1).copied().filter(|id| !id.is_empty()),
+        req.method(),
+    ) {
+        (None, &Method::GET) => {
+            access_token.assert_has_permission(typ.permission(Method::GET, true))?;
+
+            list_resources(server, req, typ, base_url, access_token).await
+        }
+        (None, &Method::POST) => {
+            access_token.assert_has_permission(typ.permission(Method::POST, false))?;
+
+            let id = match typ {
+                ResourceType::User => {
+                    create_user(server, parse_body(body.as_deref())?, access_token).await?
+                }
+                ResourceType::Group => {
+                    create_group(server, parse_body(body.as_deref())?, access_token).await?
+                }
+            };
+            let principal = fetch_principal(server, id, typ, access_token).await?;
+            Ok(scim_response(
+                StatusCode::CREATED,
+                to_resource(server, principal, typ, base_url).await?,
+            ))
+        }
+        (Some(id), method) => {
+            access_token.assert_has_permission(typ.permission(method.clone(), false))?;
+
+            let id = id.parse::<u32>().map_err(|_| not_found(id.to_string()))?;
+            let principal = fetch_principal(server, id, typ, access_token).await?;
+
+            match *method {
+                Method::GET => Ok(scim_response(
+                    StatusCode::OK,
+                    to_resource(server, principal, typ, base_url).await?,
+                )),
+                Method::PUT | Method::PATCH => {
+                    let current = to_resource(server, principal, typ, base_url).await?;
+                    let new = if *method == Method::PUT {
+                        parse_body::<Value>(body.as_deref())?
+                    } else {
+                        let mut new = current.clone();
+                        parse_body::<PatchRequest>(body.as_deref())?.apply(&mut new)?;
+                        new
+                    };
+
+                    let updates = match typ {
+                        ResourceType::User => {
+                            from_value::<User>(current)?.updates(&from_value(new)?)
+                        }
+                        ResourceType::Group => {
+                            group_updates(server, &from_value(current)?, &from_value(new)?).await?
+                        }
+                    };
+                    update_principal(server, id, updates, access_token).await?;
+
+                    let principal = fetch_principal(server, id, typ, access_token).await?;
+                    Ok(scim_response(
+                        StatusCode::OK,
+                        to_resource(server, principal, typ, base_url).await?,
+                    ))
+                }
+                Method::DELETE => {
+                    server
+                        .core
+                        .storage
+                        .data
+                        .delete_principal(QueryBy::Id(id))
+                        .await?;
+                    server.core.storage.fts.remove_all(id).await?;
+
+                    // Remove entries from cache
+                    server
+                        .inner
+                        .data
+                        .http_auth_cache
+                        .retain(|_, cached_id| cached_id.item != id);
+                    server.inner.data.access_tokens.remove(&id);
+                    server.directory().groups.clear();
+
+                    Ok(HttpResponse::new_empty(StatusCode::NO_CONTENT))
+                }
+                _ => Err(trc::ResourceEvent::NotFound.into_err()),
+            }
+        }
+        _ => Err(trc::ResourceEvent::NotFound.into_err()),
+    }
+}
+
+async fn list_resources(
+    server: &Server,
+    req: &HttpRequest,
+    typ: ResourceType,
+    base_url: &str,
+    access_token: &AccessToken,
+) -> trc::Result<HttpResponse> {
+    let params = UrlParams::new(req.uri().query());
+    let filter = params.get("filter").map(Filter::parse).transpose()?;
+    let start_index = params.parse::<usize>("startIndex").unwrap_or(1).max(1);
+    let count = params
+        .parse::<usize>("count")
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .min(MAX_PAGE_SIZE);
+    let tenant_id = access_token.tenant.map(|t| t.id);
+
+    // Identity providers look up users by name before provisioning them,
+    // avoid loading the whole directory for these requests
+    let ids = match filter.as_ref().and_then(|filter| filter.as_equality()) {
+        Some(("userName", Value::String(name))) if typ == ResourceType::User => server
+            .core
+            .storage
+            .data
+            .get_principal_info(&name.to_lowercase())
+            .await?
+            .filter(|p| p.typ == Type::Individual && p.has_tenant_access(tenant_id))
+            .map(|p| vec![p.id])
+            .unwrap_or_default(),
+        _ => server
+            .core
+            .storage
+            .data
+            .list_scoped_principals(
+                None,
+                tenant_id,
+                &access_token.domain_scope,
+                &[typ.principal_type()],
+                &[PrincipalField::Name],
+                0,
+                0,
+            )
+            .await?
+            .items
+            .into_iter()
+            .map(|p| p.id())
+            .collect(),
+    };
+
+    let mut total = 0;
+    let mut resources = Vec::new();
+    if let Some(filter) = filter {
+        for id in ids {
+            let Some(principal) = server
+                .core
+                .storage
+                .data
+                .query(QueryBy::Id(id), true)
+                .await?
+                .filter(|p| p.has_domain_access(&access_token.domain_scope))
+            else {
+                continue;
+            };
+            let resource = to_resource(server, principal, typ, base_url).await?;
+            if filter.matches(&resource) {
+                total += 1;
+                if total >= start_index && resources.len() < count {
+                    resources.push(resource);
+                }
+            }
+        }
+    } else {
+        total = ids.len();
+        for id in ids.into_iter().skip(start_index - 1).take(count) {
+            if let Some(principal) = server
+                .core
+                .storage
+                .data
+                .query(QueryBy::Id(id), true)
+                .await?
+            {
+                resources.push(to_resource(server, principal, typ, base_url).await?);
+            }
+        }
+    }
+
+    Ok(scim_response(
+        StatusCode::OK,
+        json!({
+            "schemas": [SCHEMA_LIST_RESPONSE],
+            "totalResults": total,
+            "startIndex": start_index,
+            "itemsPerPage": resources.len(),
+            "Resources": resources,
+        }),
+    ))
+}
+
+async fn create_user(server: &Server, user: User, access_token: &AccessToken) -> trc::Result<u32> {
+    if user.user_name.is_empty() {
+        return Err(scim_error("invalidValue", "Missing userName"));
+    }
+
+    // Make sure the current directory supports updates
+    server.assert_supported_directory()?;
+
+    let mut principal = Principal::new(u32::MAX, Type::Individual)
+        .with_field(PrincipalField::Name, user.user_name.clone())
+        .with_field(
+            PrincipalField::Roles,
+            PrincipalValue::StringList(vec!["user".to_string()]),
+        );
+    if let Some(description) = user.description() {
+        principal.set(PrincipalField::Description, description);
+    }
+    let emails = user.email_addresses();
+    if !emails.is_empty() {
+        principal.set(PrincipalField::Emails, PrincipalValue::StringList(emails));
+    }
+    if user.quota() > 0 {
+        principal.set(PrincipalField::Quota, user.quota());
+    }
+    if let Some(password) = user.password.filter(|p| !p.is_empty()) {
+        server.assert_password_policy(None, &password).await?;
+        principal.set(PrincipalField::Secrets, password);
+    }
+    if !user.active {
+        principal.set(
+            PrincipalField::DisabledPermissions,
+            PrincipalValue::StringList(vec![Permission::Authenticate.name().to_string()]),
+        );
+    }
+    assert_create_scope(server, &principal, access_token).await?;
+
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_principal(principal, access_token.tenant.map(|t| t.id))
+        .await
+        .map_err(invalid_reference)?;
+    assign_shard(server, account_id).await?;
+    Ok(account_id)
+}
+
+async fn create_group(
+    server: &Server,
+    group: Group,
+    access_token: &AccessToken,
+) -> trc::Result<u32> {
+    if group.display_name.is_empty() {
+        return Err(scim_error("invalidValue", "Missing displayName"));
+    }
+
+    let mut principal = Principal::new(u32::MAX, Type::Group)
+        .with_field(PrincipalField::Name, group.display_name.clone())
+        .with_field(PrincipalField::Description, group.display_name.clone());
+    let members = member_names(server, &group.member_ids()).await?;
+    if !members.is_empty() {
+        principal.set(PrincipalField::Members, PrincipalValue::StringList(members));
+    }
+    assert_create_scope(server, &principal, access_token).await?;
+
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_principal(principal, access_token.tenant.map(|t| t.id))
+        .await
+        .map_err(invalid_reference)?;
+    server.directory().groups.clear();
+    assign_shard(server, account_id).await?;
+    Ok(account_id)
+}
+
+// SPDX-SnippetBegin
+// SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+// SPDX-License-Identifier: LicenseRef-SEL
+
+async fn assign_shard(server: &Server, account_id: u32) -> trc::Result<()> {
+    #[cfg(feature = "enterprise")]
+    server
+        .assign_principal_shard(account_id)
+        .await
+        .caused_by(trc::location!())?;
+    #[cfg(not(feature = "enterprise"))]
+    let _ = (server, account_id);
+
+    Ok(())
+}
+
+// SPDX-SnippetEnd
+
+// Domain administrators can only provision principals within their domains
+async fn assert_create_scope(
+    server: &Server,
+    principal: &Principal,
+    access_token: &AccessToken,
+) -> trc::Result<()> {
+    if access_token.domain_scope.is_empty() {
+        return Ok(());
+    }
+
+    if !principal.has_domain_access(&access_token.domain_scope) {
+        trc::bail!(trc::SecurityEvent::Unauthorized
+            .into_err()
+            .details(principal.name().to_string())
+            .ctx(trc::Key::Reason, "Principal is outside the domain scope"));
+    }
+
+    let changes = [
+        PrincipalField::Emails,
+        PrincipalField::Roles,
+        PrincipalField::Members,
+    ]
+    .into_iter()
+    .filter_map(|field| {
+        principal
+            .get_str_array(field)
+            .map(|values| PrincipalUpdate::set(field, PrincipalValue::StringList(values.to_vec())))
+    })
+    .collect::<Vec<_>>();
+
+    server.assert_domain_scope(access_token, &changes).await
+}
+
+async fn group_updates(
+    server: &Server,
+    current: &Group,
+    new: &Group,
+) -> trc::Result<Vec<PrincipalUpdate>> {
+    let mut updates = Vec::new();
+
+    if !new.display_name.is_empty() && new.display_name != current.display_name {
+        updates.push(PrincipalUpdate::set(
+            PrincipalField::Description,
+            PrincipalValue::String(new.display_name.clone()),
+        ));
+    }
+
+    let mut current_ids = current.member_ids();
+    let mut new_ids = new.member_ids();
+    current_ids.sort_unstable();
+    new_ids.sort_unstable();
+    if current_ids != new_ids {
+        updates.push(PrincipalUpdate::set(
+            PrincipalField::Members,
+            PrincipalValue::StringList(member_names(server, &new_ids).await?),
+        ));
+    }
+
+    Ok(updates)
+}
+
+async fn update_principal(
+    server: &Server,
+    id: u32,
+    updates: Vec<PrincipalUpdate>,
+    access_token: &AccessToken,
+) -> trc::Result<()> {
+    if updates.is_empty() {
+        return Ok(());
+    }
+
+    server.assert_domain_scope(access_token, &updates).await?;
+    if updates
+        .iter()
+        .any(|update| update.field == PrincipalField::Secrets)
+    {
+        server.assert_supported_directory()?;
+        server.assert_password_updates(Some(id), &updates).await?;
+    }
+    let has_member_changes = updates
+        .iter()
+        .any(|update| update.field == PrincipalField::Members);
+
+    server
+        .core
+        .storage
+        .data
+        .update_principal(
+            UpdatePrincipal::by_id(id)
+                .with_updates(updates)
+                .with_tenant(access_token.tenant.map(|t| t.id)),
+        )
+        .await
+        .map_err(invalid_reference)?;
+
+    // Remove entries from cache
+    server
+        .inner
+        .data
+        .http_auth_cache
+        .retain(|_, cached_id| cached_id.item != id);
+    server.inner.data.access_tokens.remove(&id);
+    if has_member_changes {
+        server.directory().groups.clear();
+    } else {
+        server.directory().groups.clear_members();
+    }
+
+    Ok(())
+}
+
+async fn fetch_principal(
+    server: &Server,
+    id: u32,
+    typ: ResourceType,
+    access_token: &AccessToken,
+) -> trc::Result<Principal> {
+    let tenant_id = access_token.tenant.map(|t| t.id);
+
+    let principal = server
+        .core
+        .storage
+        .data
+        .query(QueryBy::Id(id), true)
+        .await?
+        .filter(|principal| {
+            principal.typ() == typ.principal_type()
+                && tenant_id.is_none_or(|tenant_id| principal.tenant() == Some(tenant_id))
+        })
+        .ok_or_else(|| not_found(id.to_string()))?;
+    server
+        .assert_domain_access(access_token, id, principal.name())
+        .await?;
+
+    Ok(principal)
+}
+
+async fn to_resource(
+    server: &Server,
+    principal: Principal,
+    typ: ResourceType,
+    base_url: &str,
+) -> trc::Result<Value> {
+    match typ {
+        ResourceType::User => to_value(&to_user(server, principal, base_url).await?),
+        ResourceType::Group => to_value(&to_group(server, principal, base_url).await?),
+    }
+}
+
+async fn to_user(server: &Server, principal: Principal, base_url: &str) -> trc::Result<User> {
+    let mut user = User::new(principal.id(), principal.name().to_string(), base_url);
+    if let Some(description) = principal.description().filter(|d| !d.is_empty()) {
+        user.display_name = Some(description.to_string());
+        user.name = Some(Name {
+            formatted: Some(description.to_string()),
+            ..Default::default()
+        });
+    }
+    user.emails = principal
+        .iter_str(PrincipalField::Emails)
+        .enumerate()
+        .map(|(idx, email)| MultiValue::new(email.to_string(), idx == 0))
+        .collect();
+    user.active = !principal.has_int_value(
+        PrincipalField::DisabledPermissions,
+        Permission::Authenticate.id() as u64,
+    );
+    if principal.quota() > 0 {
+        user.extension = Some(UserExtension {
+            quota: principal.quota(),
+        });
+    }
+    user.schemas = user_schemas(principal.quota());
+
+    for group_id in principal.iter_int(PrincipalField::MemberOf) {
+        if let Some(group) = server
+            .core
+            .storage
+            .data
+            .get_principal(group_id as u32)
+            .await
+            .caused_by(trc::location!())?
+            .filter(|group| group.typ() == Type::Group)
+        {
+            user.groups.push(Reference::new(
+                group.id(),
+                group
+                    .description()
+                    .filter(|d| !d.is_empty())
+                    .unwrap_or(group.name())
+                    .to_string(),
+                "Group",
+                base_url,
+            ));
+        }
+    }
+
+    Ok(user)
+}
+
+async fn to_group(server: &Server, principal: Principal, base_url: &str) -> trc::Result<Group> {
+    let mut group = Group::new(
+        principal.id(),
+        principal
+            .description()
+            .filter(|d| !d.is_empty())
+            .unwrap_or(principal.name())
+            .to_string(),
+        base_url,
+    );
+
+    for member_id in server
+        .core
+        .storage
+        .data
+        .get_members(principal.id())
+        .await
+        .caused_by(trc::location!())?
+    {
+        if let Some(member) = server
+            .core
+            .storage
+            .data
+            .get_principal(member_id)
+            .await
+            .caused_by(trc::location!())?
+        {
+            let typ = match member.typ() {
+                Type::Individual => "User",
+                Type::Group => "Group",
+                _ => continue,
+            };
+            group.members.push(Reference::new(
+                member_id,
+                member.name().to_string(),
+                typ,
+                base_url,
+            ));
+        }
+    }
+
+    Ok(group)
+}
+
+async fn member_names(server: &Server, ids: &[u32]) -> trc::Result<Vec<String>> {
+    let mut names = Vec::with_capacity(ids.len());
+    for id in ids {
+        let member = server
+            .core
+            .storage
+            .data
+            .get_principal(*id)
+            .await
+            .caused_by(trc::location!())?
+            .filter(|member| matches!(member.typ(), Type::Individual | Type::Group))
+            .ok_or_else(|| scim_error("invalidValue", format!("Member {id} does not exist")))?;
+        names.push(member.name().to_string());
+    }
+    Ok(names)
+}
+
+impl ResourceType {
+    fn principal_type(&self) -> Type {
+        match self {
+            ResourceType::User => Type::Individual,
+            ResourceType::Group => Type::Group,
+        }
+    }
+
+    fn permission(&self, method: Method, is_collection: bool) -> Permission {
+        match (self, method, is_collection) {
+            (ResourceType::User, Method::GET, true) => Permission::IndividualList,
+            (ResourceType::User, Method::GET, false) => Permission::IndividualGet,
+            (ResourceType::User, Method::POST, _) => Permission::IndividualCreate,
+            (ResourceType::User, Method::DELETE, _) => Permission::IndividualDelete,
+            (ResourceType::User, _, _) => Permission::IndividualUpdate,
+            (ResourceType::Group, Method::GET, true) => Permission::GroupList,
+            (ResourceType::Group, Method::GET, false) => Permission::GroupGet,
+            (ResourceType::Group, Method::POST, _) => Permission::GroupCreate,
+            (ResourceType::Group, Method::DELETE, _) => Permission::GroupDelete,
+            (ResourceType::Group, _, _) => Permission::GroupUpdate,
+        }
+    }
+}
+
+fn service_provider_config(base_url: &str) -> Value {
+    json!({
+        "schemas": [SCHEMA_SERVICE_PROVIDER_CONFIG],
+        "patch": {"supported": true},
+        "bulk": {"supported": false, "maxOperations": 0, "maxPayloadSize": 0},
+        "filter": {"supported": true, "maxResults": MAX_PAGE_SIZE},
+        "changePassword": {"supported": true},
+        "sort": {"supported": false},
+        "etag": {"supported": false},
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "API Key",
+            "description": "Authentication using an API key issued for an API key principal",
+            "primary": true
+        }],
+        "meta": {
+            "resourceType": "ServiceProviderConfig",
+            "location": format!("{base_url}/ServiceProviderConfig")
+        }
+    })
+}
+
+fn resource_types(base_url: &str) -> Value {
+    let resources = [
+        json!({
+            "schemas": [SCHEMA_RESOURCE_TYPE],
+            "id": "User",
+            "name": "User",
+            "endpoint": "/Users",
+            "schema": SCHEMA_USER,
+            "schemaExtensions": [{"schema": SCHEMA_USER_EXTENSION, "required": false}],
+            "meta": {
+                "resourceType": "ResourceType",
+                "location": format!("{base_url}/ResourceTypes/User")
+            }
+        }),
+        json!({
+            "schemas": [SCHEMA_RESOURCE_TYPE],
+            "id": "Group",
+            "name": "Group",
+            "endpoint": "/Groups",
+            "schema": SCHEMA_GROUP,
+            "meta": {
+                "resourceType": "ResourceType",
+                "location": format!("{base_url}/ResourceTypes/Group")
+            }
+        }),
+    ];
+
+    json!({
+        "schemas": [SCHEMA_LIST_RESPONSE],
+        "totalResults": resources.len(),
+        "startIndex": 1,
+        "itemsPerPage": resources.len(),
+        "Resources": resources,
+    })
+}
+
+pub(crate) fn scim_error(scim_type: &'static str, details: impl Into<trc::Value>) -> trc::Error {
+    trc::ResourceEvent::BadParameters
+        .into_err()
+        .details(details)
+        .ctx(trc::Key::Type, scim_type)
+}
+
+// Unknown domains, members or roles referenced by a resource are client errors
+fn invalid_reference(err: trc::Error) -> trc::Error {
+    if err.matches(trc::EventType::Manage(trc::ManageEvent::NotFound)) {
+        let details = format!(
+            "{} does not exist",
+            err.value_as_str(trc::Key::Key)
+                .unwrap_or("Referenced value")
+        );
+        scim_error("invalidValue", details)
+    } else {
+        err
+    }
+}
+
+fn error_response(err: &trc::Error) -> HttpResponse {
+    let (status, scim_type) = match err.as_ref() {
+        trc::EventType::Manage(cause) => match cause {
+            trc::ManageEvent::NotFound => (StatusCode::NOT_FOUND, None),
+            trc::ManageEvent::AlreadyExists => (StatusCode::CONFLICT, Some("uniqueness")),
+            trc::ManageEvent::NotSupported => (StatusCode::NOT_IMPLEMENTED, None),
+            trc::ManageEvent::MissingParameter
+            | trc::ManageEvent::AssertFailed
+            | trc::ManageEvent::Error => (StatusCode::BAD_REQUEST, Some("invalidValue")),
+        },
+        trc::EventType::Resource(trc::ResourceEvent::BadParameters) => (
+            StatusCode::BAD_REQUEST,
+            Some(err.value_as_str(trc::Key::Type).unwrap_or("invalidSyntax")),
+        ),
+        _ => (
+            StatusCode::from_u16(err.to_request_error().status)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            None,
+        ),
+    };
+    let detail = err
+        .value_as_str(trc::Key::Details)
+        .or_else(|| err.value_as_str(trc::Key::Reason))
+        .or_else(|| err.value_as_str(trc::Key::Key))
+        .unwrap_or_else(|| err.as_ref().message());
+
+    let mut response = json!({
+        "schemas": [SCHEMA_ERROR],
+        "status": status.as_u16().to_string(),
+        "detail": detail,
+    });
+    if let Some(scim_type) = scim_type {
+        response["scimType"] = Value::String(scim_type.to_string());
+    }
+
+    scim_response(status, response)
+}
+
+fn scim_response(status: StatusCode, body: impl Serialize) -> HttpResponse {
+    HttpResponse::new_text(
+        status,
+        SCIM_CONTENT_TYPE,
+        serde_json::to_string(&body).unwrap_or_default(),
+    )
+}
+
+fn parse_body<T: DeserializeOwned>(body: Option<&[u8]>) -> trc::Result<T> {
+    serde_json::from_slice(body.unwrap_or_default())
+        .map_err(|err| scim_error("invalidSyntax", err.to_string()))
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> trc::Result<T> {
+    serde_json::from_value(value).map_err(|err| scim_error("invalidValue", err.to_string()))
+}
+
+fn to_value<T: Serialize>(value: &T) -> trc::Result<Value> {
+    serde_json::to_value(value).map_err(|err| {
+        trc::EventType::Resource(trc::ResourceEvent::Error)
+            .from_json_error(err)
+            .caused_by(trc::location!())
+    })
+}
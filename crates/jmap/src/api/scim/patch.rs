@@ -0,0 +1,308 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use serde_json::{Map, Value};
+
+use super::{
+    filter::{get_ignore_case, AttrPath, PatchPath},
+    scim_error,
+};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PatchRequest {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(rename = "Operations", alias = "operations")]
+    pub operations: Vec<PatchOperation>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PatchOperation {
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl PatchRequest {
+    pub fn apply(self, resource: &mut Value) -> trc::Result<()> {
+        for operation in self.operations {
+            let op = match operation.op.to_ascii_lowercase().as_str() {
+                "add" => Op::Add,
+                "replace" => Op::Replace,
+                "remove" => Op::Remove,
+                _ => {
+                    return Err(scim_error(
+                        "invalidSyntax",
+                        format!("Unknown patch operation {:?}", operation.op),
+                    ));
+                }
+            };
+
+            match (operation.path, operation.value) {
+                (Some(path), value) => {
+                    apply_path(resource, op, &PatchPath::parse(&path)?, value)?;
+                }
+                (None, Some(Value::Object(values))) if op != Op::Remove => {
+                    // Attributes are either plain names, paths or extension schemas
+                    for (key, value) in values {
+                        match value {
+                            Value::Object(values) if key.starts_with("urn:") => {
+                                for (sub_key, value) in values {
+                                    apply_path(
+                                        resource,
+                                        op,
+                                        &PatchPath::parse(&format!("{key}:{sub_key}"))?,
+                                        Some(value),
+                                    )?;
+                                }
+                            }
+                            value => {
+                                apply_path(resource, op, &PatchPath::parse(&key)?, Some(value))?;
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(scim_error(
+                        if op == Op::Remove {
+                            "noTarget"
+                        } else {
+                            "invalidValue"
+                        },
+                        "Missing path or value in patch operation",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn apply_path(
+    resource: &mut Value,
+    op: Op,
+    path: &PatchPath,
+    value: Option<Value>,
+) -> trc::Result<()> {
+    let container = container_mut(resource, &path.path, op != Op::Remove)?;
+    let Some(container) = container else {
+        return Ok(());
+    };
+
+    if let Some(filter) = &path.filter {
+        // Multi-valued attribute with a value filter
+        let key = canonical_key(container, &path.path.attr);
+        let items = match container.get_mut(&key) {
+            Some(Value::Array(items)) => items,
+            _ if op == Op::Remove => return Ok(()),
+            _ => {
+                container.insert(key.clone(), Value::Array(vec![]));
+                container.get_mut(&key).unwrap().as_array_mut().unwrap()
+            }
+        };
+
+        let mut matched = false;
+        let mut idx = 0;
+        while idx < items.len() {
+            if !filter.matches(&items[idx]) {
+                idx += 1;
+                continue;
+            }
+            matched = true;
+
+            match (op, &path.path.sub_attr) {
+                (Op::Remove, None) => {
+                    items.remove(idx);
+                    continue;
+                }
+                (Op::Remove, Some(sub_attr)) => {
+                    if let Some(item) = items[idx].as_object_mut() {
+                        let key = canonical_key(item, sub_attr);
+                        item.remove(&key);
+                    }
+                }
+                (_, None) => {
+                    items[idx] = required(value.clone())?;
+                }
+                (_, Some(sub_attr)) => {
+                    if let Some(item) = items[idx].as_object_mut() {
+                        let key = canonical_key(item, sub_attr);
+                        item.insert(key, required(value.clone())?);
+                    }
+                }
+            }
+            idx += 1;
+        }
+
+        // Create the value when the filter selects it by equality, e.g. emails[type eq "work"]
+        if !matched && op != Op::Remove {
+            let (filter_attr, filter_value) = filter
+                .as_equality()
+                .ok_or_else(|| scim_error("noTarget", "Patch filter did not match any values"))?;
+            let mut item = Map::new();
+            item.insert(canonical_name(filter_attr), filter_value.clone());
+            match &path.path.sub_attr {
+                Some(sub_attr) => {
+                    item.insert(canonical_name(sub_attr), required(value)?);
+                }
+                None => {
+                    if let Value::Object(values) = required(value)? {
+                        item.extend(values);
+                    }
+                }
+            }
+            items.push(Value::Object(item));
+        }
+    } else if let Some(sub_attr) = &path.path.sub_attr {
+        // Complex attribute, e.g. name.givenName
+        let key = canonical_key(container, &path.path.attr);
+        match (op, container.get_mut(&key)) {
+            (Op::Remove, Some(Value::Object(object))) => {
+                let key = canonical_key(object, sub_attr);
+                object.remove(&key);
+            }
+            (Op::Remove, _) => (),
+            (_, Some(Value::Object(object))) => {
+                let key = canonical_key(object, sub_attr);
+                object.insert(key, required(value)?);
+            }
+            (_, Some(Value::Array(items))) => {
+                let value = required(value)?;
+                for item in items.iter_mut().filter_map(|item| item.as_object_mut()) {
+                    let key = canonical_key(item, sub_attr);
+                    item.insert(key, value.clone());
+                }
+            }
+            _ => {
+                let mut object = Map::new();
+                object.insert(canonical_name(sub_attr), required(value)?);
+                container.insert(key, Value::Object(object));
+            }
+        }
+    } else {
+        let key = canonical_key(container, &path.path.attr);
+        match (op, container.get_mut(&key), value) {
+            // Removing specific values from a multi-valued attribute, e.g. group members
+            (Op::Remove, Some(Value::Array(items)), Some(value)) => {
+                let remove = match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                };
+                items.retain(|item| {
+                    !remove.iter().any(|remove| {
+                        item == remove
+                            || (get_ignore_case(remove, "value").is_some()
+                                && get_ignore_case(item, "value")
+                                    == get_ignore_case(remove, "value"))
+                    })
+                });
+            }
+            (Op::Remove, _, _) => {
+                container.remove(&key);
+            }
+            // Adding values to a multi-valued attribute
+            (Op::Add, Some(Value::Array(items)), Some(value)) => {
+                let add = match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                };
+                for value in add {
+                    if !items.contains(&value) {
+                        items.push(value);
+                    }
+                }
+            }
+            // Adding sub-attributes to a complex attribute
+            (Op::Add, Some(Value::Object(object)), Some(Value::Object(values))) => {
+                for (key, value) in values {
+                    let key = canonical_key(object, &key);
+                    object.insert(key, value);
+                }
+            }
+            (_, _, value) => {
+                container.insert(key, required(value)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn container_mut<'x>(
+    resource: &'x mut Value,
+    path: &AttrPath,
+    create: bool,
+) -> trc::Result<Option<&'x mut Map<String, Value>>> {
+    let resource = resource
+        .as_object_mut()
+        .ok_or_else(|| scim_error("invalidSyntax", "Resource is not an object"))?;
+
+    if let Some(schema) = &path.schema {
+        let key = canonical_key(resource, schema);
+        if !resource.contains_key(&key) {
+            if create {
+                resource.insert(key.clone(), Value::Object(Map::new()));
+            } else {
+                return Ok(None);
+            }
+        }
+        Ok(resource
+            .get_mut(&key)
+            .and_then(|value| value.as_object_mut()))
+    } else {
+        Ok(Some(resource))
+    }
+}
+
+fn required(value: Option<Value>) -> trc::Result<Value> {
+    value.ok_or_else(|| scim_error("invalidValue", "Missing value in patch operation"))
+}
+
+/// Returns the existing key matching the attribute name ignoring case,
+/// or the canonical name of the attribute when it does not exist.
+fn canonical_key(object: &Map<String, Value>, name: &str) -> String {
+    object
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case(name))
+        .cloned()
+        .unwrap_or_else(|| canonical_name(name))
+}
+
+fn canonical_name(name: &str) -> String {
+    [
+        "userName",
+        "displayName",
+        "externalId",
+        "givenName",
+        "familyName",
+        "formatted",
+        "name",
+        "emails",
+        "active",
+        "password",
+        "groups",
+        "members",
+        "value",
+        "display",
+        "primary",
+        "type",
+        "quota",
+    ]
+    .iter()
+    .find(|canonical| canonical.eq_ignore_ascii_case(name))
+    .map(|canonical| canonical.to_string())
+    .unwrap_or_else(|| name.to_string())
+}
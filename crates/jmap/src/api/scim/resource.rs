@@ -0,0 +1,316 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{
+    backend::internal::{PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue},
+    Permission,
+};
+use serde::{Deserialize, Deserializer};
+
+use super::{SCHEMA_GROUP, SCHEMA_USER, SCHEMA_USER_EXTENSION};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub user_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Name>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<MultiValue>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Reference>,
+    #[serde(default = "default_active", deserialize_with = "deserialize_bool")]
+    pub active: bool,
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    #[serde(
+        default,
+        rename = "urn:ietf:params:scim:schemas:extension:stalwart:2.0:User",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub extension: Option<UserExtension>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub members: Vec<Reference>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Name {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MultiValue {
+    pub value: String,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_bool")]
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Reference {
+    pub value: String,
+    #[serde(default, rename = "$ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UserExtension {
+    #[serde(default, deserialize_with = "deserialize_u64")]
+    pub quota: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    pub resource_type: String,
+    pub location: String,
+}
+
+impl User {
+    pub fn new(id: u32, user_name: String, base_url: &str) -> Self {
+        User {
+            schemas: vec![SCHEMA_USER.to_string()],
+            id: id.to_string().into(),
+            user_name,
+            active: true,
+            meta: Meta {
+                resource_type: "User".to_string(),
+                location: format!("{base_url}/Users/{id}"),
+            }
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.display_name
+            .as_ref()
+            .or_else(|| self.name.as_ref().and_then(|name| name.formatted.as_ref()))
+            .filter(|name| !name.is_empty())
+            .cloned()
+            .or_else(|| {
+                let name = self.name.as_ref()?;
+                let name = [name.given_name.as_deref(), name.family_name.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .filter(|name| !name.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (!name.is_empty()).then_some(name)
+            })
+    }
+
+    /// Email addresses with the primary address first.
+    pub fn email_addresses(&self) -> Vec<String> {
+        let mut emails: Vec<String> = Vec::with_capacity(self.emails.len());
+        for email in self
+            .emails
+            .iter()
+            .filter(|email| email.primary)
+            .chain(self.emails.iter().filter(|email| !email.primary))
+        {
+            let email = email.value.trim().to_lowercase();
+            if !email.is_empty() && !emails.contains(&email) {
+                emails.push(email);
+            }
+        }
+        emails
+    }
+
+    pub fn quota(&self) -> u64 {
+        self.extension.as_ref().map_or(0, |ext| ext.quota)
+    }
+
+    /// Builds the principal updates required to turn this user into the new one.
+    pub fn updates(&self, new: &User) -> Vec<PrincipalUpdate> {
+        let mut updates = Vec::new();
+
+        if !new.user_name.is_empty() && !new.user_name.eq_ignore_ascii_case(&self.user_name) {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Name,
+                PrincipalValue::String(new.user_name.clone()),
+            ));
+        }
+        let description = new.description();
+        if description != self.description() {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Description,
+                PrincipalValue::String(description.unwrap_or_default()),
+            ));
+        }
+        let emails = new.email_addresses();
+        if emails != self.email_addresses() {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Emails,
+                PrincipalValue::StringList(emails),
+            ));
+        }
+        // Quotas are only managed by clients aware of the extension schema
+        if new.extension.is_some() && new.quota() != self.quota() {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::Quota,
+                PrincipalValue::Integer(new.quota()),
+            ));
+        }
+        if new.active != self.active {
+            updates.push(PrincipalUpdate {
+                action: if new.active {
+                    PrincipalAction::RemoveItem
+                } else {
+                    PrincipalAction::AddItem
+                },
+                field: PrincipalField::DisabledPermissions,
+                value: PrincipalValue::String(Permission::Authenticate.name().to_string()),
+            });
+        }
+        if let Some(password) = new.password.as_ref().filter(|p| !p.is_empty()) {
+            updates.push(PrincipalUpdate::remove_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(String::new()),
+            ));
+            updates.push(PrincipalUpdate::add_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(password.clone()),
+            ));
+        }
+
+        updates
+    }
+}
+
+impl Group {
+    pub fn new(id: u32, display_name: String, base_url: &str) -> Self {
+        Group {
+            schemas: vec![SCHEMA_GROUP.to_string()],
+            id: id.to_string().into(),
+            display_name,
+            members: vec![],
+            meta: Meta {
+                resource_type: "Group".to_string(),
+                location: format!("{base_url}/Groups/{id}"),
+            }
+            .into(),
+        }
+    }
+
+    pub fn member_ids(&self) -> Vec<u32> {
+        let mut ids = Vec::with_capacity(self.members.len());
+        for member in &self.members {
+            if let Ok(id) = member.value.parse::<u32>() {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+}
+
+impl Reference {
+    pub fn new(id: u32, display: String, typ: &str, base_url: &str) -> Self {
+        Reference {
+            value: id.to_string(),
+            reference: format!("{base_url}/{typ}s/{id}").into(),
+            display: display.into(),
+            typ: typ.to_string().into(),
+        }
+    }
+}
+
+impl MultiValue {
+    pub fn new(value: String, primary: bool) -> Self {
+        MultiValue {
+            value,
+            typ: Some("work".to_string()),
+            primary,
+        }
+    }
+}
+
+pub fn user_schemas(quota: u64) -> Vec<String> {
+    if quota > 0 {
+        vec![SCHEMA_USER.to_string(), SCHEMA_USER_EXTENSION.to_string()]
+    } else {
+        vec![SCHEMA_USER.to_string()]
+    }
+}
+
+fn default_active() -> bool {
+    true
+}
+
+// Some identity providers send booleans and numbers as strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Lenient {
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Null,
+}
+
+fn deserialize_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Lenient::deserialize(deserializer)? {
+        Lenient::Bool(value) => Ok(value),
+        Lenient::Number(value) => Ok(value != 0),
+        Lenient::String(value) if value.eq_ignore_ascii_case("true") => Ok(true),
+        Lenient::String(value) if value.eq_ignore_ascii_case("false") => Ok(false),
+        Lenient::Null => Ok(false),
+        Lenient::String(value) => Err(serde::de::Error::custom(format!(
+            "Invalid boolean value {value:?}"
+        ))),
+    }
+}
+
+fn deserialize_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Lenient::deserialize(deserializer)? {
+        Lenient::Number(value) => Ok(value),
+        Lenient::Null => Ok(0),
+        Lenient::String(value) => value
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("Invalid integer value {value:?}"))),
+        Lenient::Bool(_) => Err(serde::de::Error::custom("Invalid integer value")),
+    }
+}
@@ -6,7 +6,11 @@
 
 use std::sync::Arc;
 
-use common::{auth::AuthRequest, listener::limiter::InFlight, Server};
+use common::{
+    auth::{kerberos::GssapiStep, AuthRequest},
+    listener::limiter::InFlight,
+    Server,
+};
 use hyper::header;
 use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
@@ -39,6 +43,20 @@ impl Authenticator for Server {
             let access_token =
                 if let Some(account_id) = self.inner.data.http_auth_cache.get_with_ttl(token) {
                     self.get_cached_access_token(account_id).await?
+                } else if mechanism.eq_ignore_ascii_case("negotiate") {
+                    // Throttle authentication requests
+                    self.is_auth_allowed_soft(&session.remote_ip).await?;
+
+                    // Kerberos tokens are single-use, so they are not cached
+                    match authenticate_negotiate(self, token, session).await {
+                        Ok(access_token) => access_token,
+                        Err(err) => {
+                            if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
+                                let _ = self.is_auth_allowed_hard(&session.remote_ip).await;
+                            }
+                            return Err(err);
+                        }
+                    }
                 } else {
                     let credentials = if mechanism.eq_ignore_ascii_case("basic") {
                         // Throttle authentication requests
@@ -137,7 +155,31 @@ impl HttpHeaders for HttpRequest {
     }
 }
 
-fn decode_plain_auth(token: &str) -> Option<Credentials<String>> {
+async fn authenticate_negotiate(
+    server: &Server,
+    token: &str,
+    session: &HttpSessionData,
+) -> trc::Result<Arc<AccessToken>> {
+    match server.gssapi_session(false)?.step(token)? {
+        GssapiStep::Authenticated { principal, .. } => {
+            server
+                .authenticate_kerberos(
+                    &principal,
+                    None,
+                    None,
+                    session.session_id,
+                    session.remote_ip,
+                )
+                .await
+        }
+        GssapiStep::Continue(_) => Err(trc::AuthEvent::Error
+            .into_err()
+            .details("Multi-step Negotiate authentication is not supported.")
+            .caused_by(trc::location!())),
+    }
+}
+
+pub(crate) fn decode_plain_auth(token: &str) -> Option<Credentials<String>> {
     base64_decode(token.as_bytes())
         .and_then(|token| String::from_utf8(token).ok())
         .and_then(|token| {
@@ -8,3 +8,4 @@ pub mod acl;
 pub mod authenticate;
 pub mod oauth;
 pub mod rate_limit;
+pub mod webauthn;
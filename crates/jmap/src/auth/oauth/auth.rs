@@ -64,6 +64,13 @@ pub trait OAuthApiHandler: Sync + Send {
         req: HttpRequest,
         session: HttpSessionData,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn issue_authorization_code(
+        &self,
+        access_token: &AccessToken,
+        client_id: String,
+        redirect_uri: Option<String>,
+    ) -> impl Future<Output = trc::Result<serde_json::Value>> + Send;
 }
 
 impl OAuthApiHandler for Server {
@@ -83,59 +90,8 @@ impl OAuthApiHandler for Server {
                 client_id,
                 redirect_uri,
             } => {
-                // Validate clientId
-                if client_id.len() > CLIENT_ID_MAX_LEN {
-                    return Err(trc::ManageEvent::Error
-                        .into_err()
-                        .details("Client ID is invalid."));
-                } else if redirect_uri
-                    .as_ref()
-                    .map_or(false, |uri| uri.starts_with("http://"))
-                {
-                    return Err(trc::ManageEvent::Error
-                        .into_err()
-                        .details("Redirect URI must be HTTPS."));
-                }
-
-                // Generate client code
-                let client_code = thread_rng()
-                    .sample_iter(Alphanumeric)
-                    .take(DEVICE_CODE_LEN)
-                    .map(char::from)
-                    .collect::<String>();
-
-                // Serialize OAuth code
-                let value = Bincode::new(OAuthCode {
-                    status: OAuthStatus::Authorized,
-                    account_id: access_token.primary_id(),
-                    client_id,
-                    params: redirect_uri.unwrap_or_default(),
-                })
-                .serialize();
-
-                // Insert client code
-                self.core
-                    .storage
-                    .lookup
-                    .key_set(
-                        format!("oauth:{client_code}").into_bytes(),
-                        value,
-                        self.core.oauth.oauth_expiry_auth_code.into(),
-                    )
-                    .await?;
-
-                #[cfg(not(feature = "enterprise"))]
-                let is_enterprise = false;
-                #[cfg(feature = "enterprise")]
-                let is_enterprise = self.core.is_enterprise_edition();
-
-                json!({
-                    "data": {
-                        "code": client_code,
-                        "permissions": access_token.permissions(),
-                        "isEnterprise": is_enterprise,
-                    },
-                })
+                self.issue_authorization_code(&access_token, client_id, redirect_uri)
+                    .await?
             }
             OAuthCodeRequest::Device { code } => {
                 let mut success = false;
@@ -304,4 +260,65 @@ impl OAuthApiHandler for Server {
         })
         .into_http_response())
     }
+
+    async fn issue_authorization_code(
+        &self,
+        access_token: &AccessToken,
+        client_id: String,
+        redirect_uri: Option<String>,
+    ) -> trc::Result<serde_json::Value> {
+        // Validate clientId
+        if client_id.len() > CLIENT_ID_MAX_LEN {
+            return Err(trc::ManageEvent::Error
+                .into_err()
+                .details("Client ID is invalid."));
+        } else if redirect_uri
+            .as_ref()
+            .map_or(false, |uri| uri.starts_with("http://"))
+        {
+            return Err(trc::ManageEvent::Error
+                .into_err()
+                .details("Redirect URI must be HTTPS."));
+        }
+
+        // Generate client code
+        let client_code = thread_rng()
+            .sample_iter(Alphanumeric)
+            .take(DEVICE_CODE_LEN)
+            .map(char::from)
+            .collect::<String>();
+
+        // Serialize OAuth code
+        let value = Bincode::new(OAuthCode {
+            status: OAuthStatus::Authorized,
+            account_id: access_token.primary_id(),
+            client_id,
+            params: redirect_uri.unwrap_or_default(),
+        })
+        .serialize();
+
+        // Insert client code
+        self.core
+            .storage
+            .lookup
+            .key_set(
+                format!("oauth:{client_code}").into_bytes(),
+                value,
+                self.core.oauth.oauth_expiry_auth_code.into(),
+            )
+            .await?;
+
+        #[cfg(not(feature = "enterprise"))]
+        let is_enterprise = false;
+        #[cfg(feature = "enterprise")]
+        let is_enterprise = self.core.is_enterprise_edition();
+
+        Ok(json!({
+            "data": {
+                "code": client_code,
+                "permissions": access_token.permissions(),
+                "isEnterprise": is_enterprise,
+            },
+        }))
+    }
 }
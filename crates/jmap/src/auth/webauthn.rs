@@ -0,0 +1,313 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use common::{
+    auth::{
+        webauthn::{AssertionCredential, RegistrationCredential, ALG_EDDSA, ALG_ES256, ALG_RS256},
+        AccessToken,
+    },
+    Server,
+};
+use directory::{
+    backend::internal::{manage, manage::ManageDirectory},
+    Permission,
+};
+use mail_send::Credentials;
+use serde::Deserialize;
+use serde_json::json;
+use std::future::Future;
+
+use crate::{
+    api::{
+        http::{fetch_body, HttpContext, HttpSessionData, ToHttpResponse},
+        management::principal::PrincipalManager,
+        HttpRequest, HttpResponse, JsonResponse,
+    },
+    auth::{
+        authenticate::{decode_plain_auth, HttpHeaders},
+        oauth::auth::OAuthApiHandler,
+        rate_limit::RateLimiter,
+    },
+};
+
+const MAX_POST_LEN: usize = 8192;
+
+#[derive(Debug, Deserialize)]
+struct PasskeyRegisterRequest {
+    name: String,
+    credential: RegistrationCredential,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WebAuthnChallengeRequest {
+    #[serde(default)]
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebAuthnCodeRequest {
+    client_id: String,
+    #[serde(default)]
+    redirect_uri: Option<String>,
+    credential: AssertionCredential,
+}
+
+pub trait WebAuthnHandler: Sync + Send {
+    fn handle_passkey_request(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_webauthn_challenge(
+        &self,
+        req: &mut HttpRequest,
+        session: HttpSessionData,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn handle_webauthn_code(
+        &self,
+        req: &mut HttpRequest,
+        session: HttpSessionData,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl WebAuthnHandler for Server {
+    async fn handle_passkey_request(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        // Make sure the user authenticated using Basic auth
+        let Some(Credentials::Plain { secret, .. }) =
+            req.authorization_basic().and_then(decode_plain_auth)
+        else {
+            return Err(manage::error(
+                "Passkey registration only allowed using Basic auth",
+                None::<u32>,
+            ));
+        };
+        if access_token.primary_id() == u32::MAX {
+            return Err(manage::error(
+                "Fallback administrator accounts do not support passkeys",
+                None::<u32>,
+            ));
+        }
+
+        // Make sure the current directory supports updates
+        self.assert_supported_directory()?;
+
+        // App passwords skip 2FA, passkeys can only be registered
+        // by sessions authenticated with the account password
+        if self
+            .is_app_password_login(access_token.primary_id(), &secret)
+            .await?
+        {
+            return Err(manage::error(
+                "Passkey registration not allowed using app passwords",
+                None::<u32>,
+            ));
+        }
+
+        let rp = self.core.jmap.webauthn.relying_party(
+            &HttpContext::new(session, req)
+                .resolve_response_url(self)
+                .await,
+        );
+        let account_id = access_token.primary_id();
+
+        match path.get(2).copied().unwrap_or_default() {
+            "options" => {
+                let challenge = self.webauthn_challenge(account_id.into()).await?;
+                let exclude_credentials = self
+                    .webauthn_credentials(account_id)
+                    .await?
+                    .into_iter()
+                    .map(|credential| json!({"type": "public-key", "id": credential.id}))
+                    .collect::<Vec<_>>();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "challenge": challenge,
+                        "rp": {
+                            "id": rp.id,
+                            "name": rp.name,
+                        },
+                        "user": {
+                            "id": URL_SAFE_NO_PAD.encode(account_id.to_be_bytes()),
+                            "name": access_token.name,
+                            "displayName": access_token
+                                .description
+                                .as_deref()
+                                .unwrap_or(&access_token.name),
+                        },
+                        "pubKeyCredParams": [
+                            {"type": "public-key", "alg": ALG_ES256},
+                            {"type": "public-key", "alg": ALG_EDDSA},
+                            {"type": "public-key", "alg": ALG_RS256},
+                        ],
+                        "timeout": self.core.jmap.webauthn.challenge_expiry.as_millis() as u64,
+                        "excludeCredentials": exclude_credentials,
+                        "authenticatorSelection": {
+                            "residentKey": "preferred",
+                            "userVerification": user_verification(self),
+                        },
+                        "attestation": "none",
+                    },
+                }))
+                .into_http_response())
+            }
+            "register" => {
+                let request = serde_json::from_slice::<PasskeyRegisterRequest>(
+                    body.as_deref().unwrap_or_default(),
+                )
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+                let credential = self
+                    .register_webauthn_credential(
+                        &rp,
+                        account_id,
+                        request.name,
+                        &request.credential,
+                    )
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "name": credential.name,
+                        "id": credential.id,
+                    },
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+
+    async fn handle_webauthn_challenge(
+        &self,
+        req: &mut HttpRequest,
+        session: HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        let request = match fetch_body(req, MAX_POST_LEN, session.session_id).await {
+            Some(body) if !body.is_empty() => {
+                serde_json::from_slice::<WebAuthnChallengeRequest>(&body).map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?
+            }
+            _ => WebAuthnChallengeRequest::default(),
+        };
+
+        // Bind the challenge to the account when a username is provided, unknown
+        // usernames receive an unbound challenge and a decoy credential to avoid
+        // disclosing them.
+        let username = request.username.filter(|u| !u.is_empty());
+        let account_id = if let Some(username) = &username {
+            self.core.storage.data.get_principal_id(username).await?
+        } else {
+            None
+        };
+        let mut allow_credentials = if let Some(account_id) = account_id {
+            self.webauthn_credentials(account_id)
+                .await?
+                .into_iter()
+                .map(|credential| json!({"type": "public-key", "id": credential.id}))
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+        if let (Some(username), true) = (&username, allow_credentials.is_empty()) {
+            allow_credentials.push(json!({
+                "type": "public-key",
+                "id": self.webauthn_decoy_credential(username),
+            }));
+        }
+        let challenge = self.webauthn_challenge(account_id).await?;
+        let rp = self.core.jmap.webauthn.relying_party(
+            &HttpContext::new(&session, req)
+                .resolve_response_url(self)
+                .await,
+        );
+
+        Ok(JsonResponse::new(json!({
+            "data": {
+                "challenge": challenge,
+                "rpId": rp.id,
+                "timeout": self.core.jmap.webauthn.challenge_expiry.as_millis() as u64,
+                "userVerification": user_verification(self),
+                "allowCredentials": allow_credentials,
+            },
+        }))
+        .into_http_response())
+    }
+
+    async fn handle_webauthn_code(
+        &self,
+        req: &mut HttpRequest,
+        session: HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        // Throttle authentication requests
+        self.is_auth_allowed_soft(&session.remote_ip).await?;
+
+        let request = serde_json::from_slice::<WebAuthnCodeRequest>(
+            fetch_body(req, MAX_POST_LEN, session.session_id)
+                .await
+                .as_deref()
+                .unwrap_or_default(),
+        )
+        .map_err(|err| {
+            trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+        })?;
+        let rp = self.core.jmap.webauthn.relying_party(
+            &HttpContext::new(&session, req)
+                .resolve_response_url(self)
+                .await,
+        );
+
+        // Verify assertion
+        let access_token = match self
+            .authenticate_webauthn(
+                &rp,
+                &request.credential,
+                session.remote_ip,
+                session.session_id,
+            )
+            .await
+        {
+            Ok(access_token) => access_token,
+            Err(err) => {
+                if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
+                    let _ = self.is_auth_allowed_hard(&session.remote_ip).await;
+                }
+                return Err(err);
+            }
+        };
+        access_token.assert_has_permission(Permission::AuthenticateOauth)?;
+
+        self.issue_authorization_code(&access_token, request.client_id, request.redirect_uri)
+            .await
+            .map(|response| JsonResponse::new(response).into_http_response())
+    }
+}
+
+fn user_verification(server: &Server) -> &'static str {
+    if server.core.jmap.webauthn.require_user_verification {
+        "required"
+    } else {
+        "preferred"
+    }
+}
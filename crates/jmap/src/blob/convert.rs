@@ -0,0 +1,437 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::io::{Cursor, Read};
+
+use common::{auth::AccessToken, Server};
+use flate2::read::ZlibDecoder;
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    error::ImageError,
+    DynamicImage, GrayImage, ImageFormat, ImageReader, Limits, RgbImage,
+};
+use jmap_proto::{
+    error::set::SetError,
+    method::convert::{BlobConvertRequest, BlobConvertResponse, BlobConvertResponseObject},
+    types::blob::BlobId,
+};
+use lopdf::Document;
+use std::future::Future;
+use store::{
+    write::{now, BatchBuilder, Bincode, BlobOp},
+    BlobClass, Serialize,
+};
+use trc::AddContext;
+use utils::{map::vec_map::VecMap, BlobHash};
+
+use crate::JmapMethods;
+
+use super::{download::BlobDownload, upload::BlobUpload};
+
+const JPEG_QUALITY: u8 = 80;
+const MAX_SOURCE_DIMENSION: u32 = 16384;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+
+pub trait BlobConvert: Sync + Send {
+    fn blob_convert(
+        &self,
+        request: BlobConvertRequest,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<BlobConvertResponse>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConvertType {
+    Jpeg,
+    WebP,
+}
+
+enum ConvertError {
+    Unsupported,
+    NoPdfImage,
+    TooLarge,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ConvertedBlob {
+    hash: BlobHash,
+    size: usize,
+    width: u32,
+    height: u32,
+}
+
+impl BlobConvert for Server {
+    async fn blob_convert(
+        &self,
+        request: BlobConvertRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<BlobConvertResponse> {
+        let mut response = BlobConvertResponse {
+            account_id: request.account_id,
+            created: VecMap::with_capacity(request.create.len()),
+            not_created: VecMap::new(),
+        };
+        let account_id = request.account_id.document_id();
+        let max_dimension = self.core.jmap.convert_max_dimension;
+        let max_size = self.core.jmap.convert_max_size;
+
+        if request.create.len() > self.core.jmap.set_max_objects {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+
+        for (create_id, object) in request.create {
+            // Validate request
+            let blob_id = if let Some(blob_id) = object.blob_id {
+                blob_id
+            } else {
+                response.not_created.append(
+                    create_id,
+                    SetError::invalid_properties().with_description("Missing blobId property."),
+                );
+                continue;
+            };
+            let convert_type = match object.type_.as_deref() {
+                Some(type_) => {
+                    if let Some(convert_type) = ConvertType::parse(type_) {
+                        convert_type
+                    } else {
+                        response.not_created.append(
+                            create_id,
+                            SetError::invalid_properties().with_description(format!(
+                                "Unsupported conversion type {type_:?}."
+                            )),
+                        );
+                        continue;
+                    }
+                }
+                None => ConvertType::Jpeg,
+            };
+            let width = object
+                .width
+                .map_or(max_dimension, |width| {
+                    std::cmp::min(width, max_dimension as usize) as u32
+                })
+                .max(1);
+            let height = object
+                .height
+                .map_or(max_dimension, |height| {
+                    std::cmp::min(height, max_dimension as usize) as u32
+                })
+                .max(1);
+
+            if !self.has_access_blob(&blob_id, access_token).await? {
+                response.not_created.append(
+                    create_id,
+                    SetError::blob_not_found().with_description(
+                        "blobId does not exist or not enough permissions to access it.",
+                    ),
+                );
+                continue;
+            }
+
+            // Reuse previous conversions of the same source blob
+            let cache_key = cache_key(&blob_id, convert_type, width, height);
+            let cached = match self
+                .core
+                .storage
+                .lookup
+                .key_get::<Bincode<ConvertedBlob>>(cache_key.clone())
+                .await?
+            {
+                Some(cached)
+                    if self
+                        .core
+                        .storage
+                        .data
+                        .blob_exists(&cached.inner.hash)
+                        .await
+                        .caused_by(trc::location!())? =>
+                {
+                    Some(cached.inner)
+                }
+                _ => None,
+            };
+
+            if let Some(cached) = cached {
+                let mut batch = BatchBuilder::new();
+                let until = now() + self.core.jmap.upload_tmp_ttl;
+                batch.with_account_id(account_id).set(
+                    BlobOp::Reserve {
+                        until,
+                        hash: cached.hash.clone(),
+                    },
+                    0u32.serialize(),
+                );
+                self.write_batch(batch).await?;
+
+                response.created.append(
+                    create_id,
+                    BlobConvertResponseObject {
+                        id: BlobId {
+                            hash: cached.hash,
+                            class: BlobClass::Reserved {
+                                account_id,
+                                expires: until,
+                            },
+                            section: None,
+                        },
+                        type_: convert_type.as_str().to_string(),
+                        size: cached.size,
+                        width: cached.width,
+                        height: cached.height,
+                    },
+                );
+                continue;
+            }
+
+            // Fetch source blob
+            let bytes = if let Some(section) = &blob_id.section {
+                if section.size <= max_size {
+                    self.get_blob_section(&blob_id.hash, section).await?
+                } else {
+                    None
+                }
+            } else {
+                self.get_blob(&blob_id.hash, 0..max_size.saturating_add(1))
+                    .await?
+            };
+            let bytes = match bytes {
+                Some(bytes) if bytes.len() <= max_size => bytes,
+                None if blob_id
+                    .section
+                    .as_ref()
+                    .is_none_or(|section| section.size <= max_size) =>
+                {
+                    response.not_created.append(
+                        create_id,
+                        SetError::blob_not_found()
+                            .with_description(format!("BlobId {blob_id} not found.")),
+                    );
+                    continue;
+                }
+                _ => {
+                    response.not_created.append(
+                        create_id,
+                        SetError::too_large().with_description(format!(
+                            "Source blob exceeds the maximum of {max_size} bytes."
+                        )),
+                    );
+                    continue;
+                }
+            };
+
+            // Decode, resize and encode
+            let converted = tokio::task::spawn_blocking(move || {
+                convert_blob(&bytes, convert_type, width, height)
+            })
+            .await
+            .map_err(|err| {
+                trc::EventType::Server(trc::ServerEvent::ThreadError)
+                    .into_err()
+                    .reason(err)
+                    .caused_by(trc::location!())
+            })?;
+            let (data, width, height) = match converted {
+                Ok(converted) => converted,
+                Err(err) => {
+                    response.not_created.append(
+                        create_id,
+                        match err {
+                            ConvertError::Unsupported => SetError::invalid_properties()
+                                .with_description("Blob is not a supported image or PDF document."),
+                            ConvertError::NoPdfImage => SetError::invalid_properties()
+                                .with_description(
+                                    "Previews are only available for PDF pages containing images.",
+                                ),
+                            ConvertError::TooLarge => SetError::too_large()
+                                .with_description("Source image exceeds the maximum decoded size."),
+                        },
+                    );
+                    continue;
+                }
+            };
+
+            // Store the derived blob and cache it by source
+            let id = self.put_blob(account_id, &data, false).await?;
+            self.core
+                .storage
+                .lookup
+                .key_set(
+                    cache_key,
+                    Bincode::new(ConvertedBlob {
+                        hash: id.hash.clone(),
+                        size: data.len(),
+                        width,
+                        height,
+                    })
+                    .serialize(),
+                    self.core.jmap.upload_tmp_ttl.into(),
+                )
+                .await?;
+
+            response.created.append(
+                create_id,
+                BlobConvertResponseObject {
+                    id,
+                    type_: convert_type.as_str().to_string(),
+                    size: data.len(),
+                    width,
+                    height,
+                },
+            );
+        }
+
+        Ok(response)
+    }
+}
+
+impl ConvertType {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "image/jpeg" => Some(ConvertType::Jpeg),
+            "image/webp" => Some(ConvertType::WebP),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ConvertType::Jpeg => "image/jpeg",
+            ConvertType::WebP => "image/webp",
+        }
+    }
+}
+
+fn cache_key(blob_id: &BlobId, convert_type: ConvertType, width: u32, height: u32) -> Vec<u8> {
+    let mut key = format!("convert:{}", blob_id.hash.to_hex());
+    if let Some(section) = &blob_id.section {
+        key.push_str(&format!(
+            ":{}:{}:{}",
+            section.offset_start, section.size, section.encoding
+        ));
+    }
+    key.push_str(&format!(":{}:{width}x{height}", convert_type.as_str()));
+    key.into_bytes()
+}
+
+fn convert_blob(
+    bytes: &[u8],
+    convert_type: ConvertType,
+    width: u32,
+    height: u32,
+) -> Result<(Vec<u8>, u32, u32), ConvertError> {
+    let image = if bytes.starts_with(b"%PDF-") {
+        pdf_preview(bytes)?
+    } else {
+        let mut reader = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|_| ConvertError::Unsupported)?;
+        reader.limits(decode_limits());
+        reader.decode().map_err(ConvertError::from)?
+    };
+
+    // Never upscale, only shrink to fit within the requested bounds
+    let image = if image.width() > width || image.height() > height {
+        image.thumbnail(width, height)
+    } else {
+        image
+    };
+
+    let mut data = Vec::new();
+    match convert_type {
+        ConvertType::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)),
+        ConvertType::WebP => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut data)),
+    }
+    .map_err(|_| ConvertError::Unsupported)?;
+
+    Ok((data, image.width(), image.height()))
+}
+
+// Renders the first page of a PDF by extracting its largest embedded image,
+// which covers scanned documents and most image-based pages. Pages made only
+// of text or vector graphics are not rasterized and are reported as unsupported.
+fn pdf_preview(bytes: &[u8]) -> Result<DynamicImage, ConvertError> {
+    let document = Document::load_mem(bytes).map_err(|_| ConvertError::Unsupported)?;
+    let page_id = *document
+        .get_pages()
+        .values()
+        .next()
+        .ok_or(ConvertError::Unsupported)?;
+    let image = document
+        .get_page_images(page_id)
+        .map_err(|_| ConvertError::Unsupported)?
+        .into_iter()
+        .max_by_key(|image| image.width.saturating_mul(image.height))
+        .ok_or(ConvertError::NoPdfImage)?;
+
+    match image.filters.as_deref() {
+        Some([filter]) if filter == "DCTDecode" => {
+            let mut reader =
+                ImageReader::with_format(Cursor::new(image.content), ImageFormat::Jpeg);
+            reader.limits(decode_limits());
+            reader.decode().map_err(ConvertError::from)
+        }
+        filters => {
+            if image.bits_per_component != Some(8) {
+                return Err(ConvertError::Unsupported);
+            }
+            let channels = match image.color_space.as_deref() {
+                Some("DeviceRGB") => 3,
+                Some("DeviceGray") => 1,
+                _ => return Err(ConvertError::Unsupported),
+            };
+            let width = u32::try_from(image.width).map_err(|_| ConvertError::Unsupported)?;
+            let height = u32::try_from(image.height).map_err(|_| ConvertError::Unsupported)?;
+            if width > MAX_SOURCE_DIMENSION || height > MAX_SOURCE_DIMENSION {
+                return Err(ConvertError::TooLarge);
+            }
+            let size = width as u64 * height as u64 * channels;
+            if size > MAX_DECODE_ALLOC {
+                return Err(ConvertError::TooLarge);
+            }
+
+            // Inflate at most the bytes the image needs instead of the whole stream
+            let content = match filters {
+                None => image.content.to_vec(),
+                Some([filter]) if filter == "FlateDecode" => {
+                    let mut content = Vec::new();
+                    ZlibDecoder::new(image.content)
+                        .take(size)
+                        .read_to_end(&mut content)
+                        .map_err(|_| ConvertError::Unsupported)?;
+                    content
+                }
+                _ => return Err(ConvertError::Unsupported),
+            };
+
+            if channels == 3 {
+                RgbImage::from_raw(width, height, content).map(DynamicImage::ImageRgb8)
+            } else {
+                GrayImage::from_raw(width, height, content).map(DynamicImage::ImageLuma8)
+            }
+            .ok_or(ConvertError::Unsupported)
+        }
+    }
+}
+
+fn decode_limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    limits
+}
+
+impl From<ImageError> for ConvertError {
+    fn from(err: ImageError) -> Self {
+        match err {
+            ImageError::Limits(_) => ConvertError::TooLarge,
+            _ => ConvertError::Unsupported,
+        }
+    }
+}
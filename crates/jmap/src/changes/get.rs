@@ -65,6 +65,26 @@ impl ChangesLookup for Server {
 
                 return Err(trc::JmapEvent::CannotCalculateChanges.into_err());
             }
+            RequestArguments::ShareNotification => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::ShareNotification
+            }
+            RequestArguments::EmailImportTask => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::EmailImportTask
+            }
+            RequestArguments::EmailTemplate => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::EmailTemplate
+            }
+            RequestArguments::SavedSearch => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::SavedSearch
+            }
         };
 
         let max_changes = if self.core.jmap.changes_max_results > 0
@@ -14,7 +14,7 @@ use std::future::Future;
 
 use crate::{
     email::query::EmailQuery, mailbox::query::MailboxQuery, quota::query::QuotaQuery,
-    submission::query::EmailSubmissionQuery,
+    share_notification::query::ShareNotificationQuery, submission::query::EmailSubmissionQuery,
 };
 
 use super::get::ChangesLookup;
@@ -47,6 +47,9 @@ impl QueryChanges for Server {
                             changes::RequestArguments::EmailSubmission
                         }
                         query::RequestArguments::Quota => changes::RequestArguments::Quota,
+                        query::RequestArguments::ShareNotification => {
+                            changes::RequestArguments::ShareNotification
+                        }
                         _ => {
                             return Err(trc::JmapEvent::UnknownMethod
                                 .into_err()
@@ -98,6 +101,9 @@ impl QueryChanges for Server {
                     self.email_submission_query(query).await?
                 }
                 query::RequestArguments::Quota => self.quota_query(query, access_token).await?,
+                query::RequestArguments::ShareNotification => {
+                    self.share_notification_query(query).await?
+                }
                 _ => unreachable!(),
             };
 
@@ -0,0 +1,268 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    cell::Cell,
+    fmt::Display,
+    io::{Cursor, Read},
+};
+
+use flate2::read::GzDecoder;
+use jmap_proto::{method::import::ArchiveFormat, types::keyword::Keyword};
+use mail_parser::mailbox::mbox::MessageIterator;
+
+pub struct ArchiveMessage {
+    pub contents: Vec<u8>,
+    pub keywords: Vec<Keyword>,
+    pub received_at: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct Archive {
+    pub total: usize,
+    pub skipped: usize,
+    size: usize,
+}
+
+pub enum ArchiveError {
+    TooManyMessages(usize),
+    TooLarge(usize),
+    Invalid(String),
+}
+
+pub struct ArchiveLimits {
+    pub max_items: usize,
+    pub max_message_size: usize,
+    pub max_size: usize,
+}
+
+// Limits the number of bytes that can be decompressed from an archive
+struct SizeLimit<'x, R> {
+    reader: R,
+    remaining: usize,
+    exceeded: &'x Cell<bool>,
+}
+
+impl Archive {
+    /// Splits an archive into messages, passing each one to `on_message` as soon
+    /// as it is read. Parsing stops early when `on_message` returns false.
+    pub fn parse(
+        bytes: &[u8],
+        format: ArchiveFormat,
+        limits: &ArchiveLimits,
+        mut on_message: impl FnMut(ArchiveMessage) -> bool,
+    ) -> Result<Self, ArchiveError> {
+        let mut archive = Archive::default();
+
+        match format {
+            ArchiveFormat::Mbox => {
+                for message in MessageIterator::new(bytes) {
+                    let message = message.map_err(|_| {
+                        ArchiveError::Invalid("Failed to parse mbox file.".to_string())
+                    })?;
+                    let received_at = Some(message.internal_date()).filter(|date| *date > 0);
+                    if !archive.push(
+                        message.unwrap_contents(),
+                        vec![],
+                        received_at,
+                        limits,
+                        &mut on_message,
+                    )? {
+                        break;
+                    }
+                }
+            }
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+                    .map_err(|err| ArchiveError::invalid("Failed to open zip archive", err))?;
+                for idx in 0..zip.len() {
+                    let mut file = zip
+                        .by_index(idx)
+                        .map_err(|err| ArchiveError::invalid("Failed to read zip archive", err))?;
+                    let name = file.name();
+                    if !file.is_file()
+                        || name.starts_with("__MACOSX/")
+                        || !name.to_ascii_lowercase().ends_with(".eml")
+                    {
+                        continue;
+                    }
+
+                    let contents = read_limited(&mut file, limits.max_message_size)
+                        .map_err(|err| ArchiveError::invalid("Failed to read zip archive", err))?;
+                    if !archive.push(contents, vec![], None, limits, &mut on_message)? {
+                        break;
+                    }
+                }
+            }
+            ArchiveFormat::Maildir => {
+                // Maildir folders are uploaded as tarballs, optionally gzip compressed.
+                // Skipped entries are decompressed as well, so the whole stream is limited.
+                let exceeded = Cell::new(false);
+                let map_err = |err: std::io::Error| {
+                    if exceeded.get() {
+                        ArchiveError::TooLarge(limits.max_size)
+                    } else {
+                        ArchiveError::invalid("Failed to read tar archive", err)
+                    }
+                };
+                let reader: Box<dyn Read + '_> = if bytes.starts_with(&[0x1f, 0x8b]) {
+                    Box::new(GzDecoder::new(bytes))
+                } else {
+                    Box::new(bytes)
+                };
+                let mut tar = tar::Archive::new(SizeLimit {
+                    reader,
+                    remaining: limits.max_size.saturating_add(1),
+                    exceeded: &exceeded,
+                });
+                let entries = tar.entries().map_err(map_err)?;
+                for entry in entries {
+                    let mut entry = entry.map_err(map_err)?;
+                    if !entry.header().entry_type().is_file() {
+                        continue;
+                    }
+                    let path = entry.path().map_err(map_err)?;
+                    let (folder, name) = match (
+                        path.parent()
+                            .and_then(|folder| folder.file_name())
+                            .and_then(|folder| folder.to_str()),
+                        path.file_name().and_then(|name| name.to_str()),
+                    ) {
+                        (Some(folder), Some(name))
+                            if matches!(folder, "cur" | "new") && !name.starts_with('.') =>
+                        {
+                            (folder, name)
+                        }
+                        _ => continue,
+                    };
+                    let keywords = if folder == "cur" {
+                        maildir_keywords(name)
+                    } else {
+                        vec![]
+                    };
+                    let received_at = entry.header().mtime().ok().filter(|date| *date > 0);
+
+                    let contents =
+                        read_limited(&mut entry, limits.max_message_size).map_err(map_err)?;
+                    if !archive.push(contents, keywords, received_at, limits, &mut on_message)? {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if archive.total > 0 {
+            Ok(archive)
+        } else {
+            Err(ArchiveError::Invalid(format!(
+                "No messages found in {} archive.",
+                format.as_str()
+            )))
+        }
+    }
+
+    fn push(
+        &mut self,
+        contents: Vec<u8>,
+        keywords: Vec<Keyword>,
+        received_at: Option<u64>,
+        limits: &ArchiveLimits,
+        on_message: &mut impl FnMut(ArchiveMessage) -> bool,
+    ) -> Result<bool, ArchiveError> {
+        if self.total >= limits.max_items {
+            return Err(ArchiveError::TooManyMessages(limits.max_items));
+        }
+        self.size += contents.len();
+        if self.size > limits.max_size {
+            return Err(ArchiveError::TooLarge(limits.max_size));
+        }
+        self.total += 1;
+
+        if !contents.is_empty() && contents.len() <= limits.max_message_size {
+            Ok(on_message(ArchiveMessage {
+                contents: normalize_line_endings(contents),
+                keywords,
+                received_at,
+            }))
+        } else {
+            self.skipped += 1;
+            Ok(true)
+        }
+    }
+}
+
+impl<R: Read> Read for SizeLimit<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.remaining);
+        let read = self.reader.read(&mut buf[..len])?;
+        self.remaining -= read;
+        if self.remaining == 0 {
+            self.exceeded.set(true);
+            Err(std::io::Error::other("Archive exceeds the maximum size"))
+        } else {
+            Ok(read)
+        }
+    }
+}
+
+impl ArchiveError {
+    fn invalid(context: &str, err: impl Display) -> Self {
+        ArchiveError::Invalid(format!("{context}: {err}"))
+    }
+}
+
+impl Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::TooManyMessages(max_items) => {
+                write!(f, "Archive exceeds the maximum of {max_items} messages.")
+            }
+            ArchiveError::TooLarge(max_size) => {
+                write!(f, "Archive exceeds the maximum size of {max_size} bytes.")
+            }
+            ArchiveError::Invalid(reason) => f.write_str(reason),
+        }
+    }
+}
+
+fn read_limited(reader: &mut impl Read, max_size: usize) -> std::io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    reader
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn maildir_keywords(name: &str) -> Vec<Keyword> {
+    let mut keywords = Vec::new();
+    if let Some((_, flags)) = name.rsplit_once(":2,") {
+        for &ch in flags.as_bytes() {
+            keywords.push(match ch {
+                b'P' => Keyword::Other("$passed".to_string()),
+                b'R' => Keyword::Answered,
+                b'S' => Keyword::Seen,
+                b'T' => Keyword::Deleted,
+                b'D' => Keyword::Draft,
+                b'F' => Keyword::Flagged,
+                _ => continue,
+            });
+        }
+    }
+    keywords
+}
+
+fn normalize_line_endings(contents: Vec<u8>) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(contents.len());
+    let mut last_ch = 0;
+    for ch in contents {
+        if ch == b'\n' && last_ch != b'\r' {
+            normalized.push(b'\r');
+        }
+        normalized.push(ch);
+        last_ch = ch;
+    }
+    normalized
+}
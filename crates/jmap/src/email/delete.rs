@@ -7,6 +7,10 @@
 use std::time::Duration;
 
 use common::Server;
+use directory::{
+    backend::internal::{lookup::DirectoryStore, manage::ManageDirectory, PrincipalField},
+    AccountStatus, QueryBy, Type,
+};
 use jmap_proto::types::{
     collection::Collection, id::Id, keyword::Keyword, property::Property, state::StateChange,
     type_state::DataType,
@@ -15,7 +19,7 @@ use store::{
     ahash::AHashMap,
     roaring::RoaringBitmap,
     write::{
-        log::ChangeLogBuilder, BatchBuilder, Bincode, BitmapClass, MaybeDynamicId, TagValue,
+        log::ChangeLogBuilder, now, BatchBuilder, Bincode, BitmapClass, MaybeDynamicId, TagValue,
         ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
     },
     BitmapKey, IterateParams, ValueKey, U32_LEN,
@@ -27,10 +31,11 @@ use crate::{
     changes::write::ChangeLog,
     mailbox::{UidMailbox, JUNK_ID, TOMBSTONE_ID, TRASH_ID},
     services::state::StateManager,
+    thread::muted_keyword,
     JmapMethods,
 };
 
-use super::{index::EmailIndexBuilder, metadata::MessageMetadata};
+use super::{index::EmailIndexBuilder, metadata::MessageMetadata, smime::SmimeResults};
 use rand::prelude::SliceRandom;
 use std::future::Future;
 
@@ -45,6 +50,11 @@ pub trait EmailDeletion: Sync + Send {
 
     fn purge_account(&self, account_id: u32) -> impl Future<Output = ()> + Send;
 
+    fn purge_pending_deletion(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+
     fn emails_auto_expunge(
         &self,
         account_id: u32,
@@ -213,12 +223,13 @@ impl EmailDeletion for Server {
             }
         }
 
-        // Delete threadIds
+        // Delete threadIds and their mute state
         for (thread_id, thread_count) in thread_ids {
             if thread_count == 0 {
                 batch
                     .with_collection(Collection::Thread)
-                    .delete_document(thread_id);
+                    .delete_document(thread_id)
+                    .tag(Property::Keywords, muted_keyword(), F_CLEAR);
                 changes.log_delete(Collection::Thread, thread_id);
             }
         }
@@ -244,7 +255,16 @@ impl EmailDeletion for Server {
             account_ids.shuffle(&mut rand::thread_rng());
 
             for account_id in account_ids {
-                self.purge_account(account_id).await;
+                match self.purge_pending_deletion(account_id).await {
+                    Ok(true) => (),
+                    Ok(false) => self.purge_account(account_id).await,
+                    Err(err) => {
+                        trc::error!(err
+                            .details("Failed to purge account pending deletion.")
+                            .account_id(account_id));
+                        self.purge_account(account_id).await;
+                    }
+                }
             }
         }
     }
@@ -317,6 +337,68 @@ impl EmailDeletion for Server {
         }
     }
 
+    async fn purge_pending_deletion(&self, account_id: u32) -> trc::Result<bool> {
+        let Some(principal) = self
+            .core
+            .storage
+            .data
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(false);
+        };
+
+        // Accounts are removed once their deletion grace period has expired
+        if principal.typ() != Type::Individual
+            || principal.status() != AccountStatus::PendingDeletion
+            || principal
+                .get_int(PrincipalField::StatusSince)
+                .unwrap_or_default()
+                .saturating_add(
+                    self.core
+                        .jmap
+                        .account_lifecycle
+                        .deletion_grace_period
+                        .as_secs(),
+                )
+                > now()
+        {
+            return Ok(false);
+        }
+
+        // Delete account
+        self.core
+            .storage
+            .data
+            .delete_principal(QueryBy::Id(account_id))
+            .await
+            .caused_by(trc::location!())?;
+
+        // Remove FTS index
+        self.core
+            .storage
+            .fts
+            .remove_all(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Remove entries from cache
+        self.inner
+            .data
+            .http_auth_cache
+            .retain(|_, id| id.item != account_id);
+        self.inner.data.access_tokens.remove(&account_id);
+
+        trc::event!(
+            Purge(trc::PurgeEvent::AccountDeleted),
+            AccountId = account_id,
+            AccountName = principal.name().to_string(),
+        );
+
+        Ok(true)
+    }
+
     async fn emails_auto_expunge(&self, account_id: u32, period: Duration) -> trc::Result<()> {
         let deletion_candidates = self
             .get_tag(
@@ -472,6 +554,22 @@ impl EmailDeletion for Server {
                 );
             }
 
+            // Remove S/MIME verification results
+            if let Some(smime) = self
+                .core
+                .storage
+                .data
+                .get_value::<Bincode<SmimeResults>>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(Property::SmimeStatus.into()),
+                })
+                .await?
+            {
+                smime.inner.build(&mut batch, false);
+            }
+
             // Remove message metadata
             if let Some(metadata) = self
                 .core
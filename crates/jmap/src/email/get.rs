@@ -34,6 +34,8 @@ use super::{
     cache::ThreadCache,
     headers::IntoForm,
     metadata::{MessageMetadata, MetadataPartType},
+    smime::SmimeVerify,
+    snooze::SnoozeDetails,
 };
 
 pub trait EmailGet: Sync + Send {
@@ -139,6 +141,17 @@ impl EmailGet for Server {
             }
         }
 
+        // Check if we need to verify S/MIME signatures
+        let needs_smime = properties.iter().any(|property| {
+            matches!(
+                property,
+                Property::SmimeStatus
+                    | Property::SmimeStatusAtDelivery
+                    | Property::SmimeErrors
+                    | Property::SmimeVerifiedAt
+            )
+        });
+
         'outer: for id in ids {
             // Obtain the email object
             if !message_ids.contains(id.document_id()) {
@@ -161,6 +174,14 @@ impl EmailGet for Server {
                 }
             };
 
+            // Obtain S/MIME verification results
+            let smime = if needs_smime {
+                self.smime_results(account_id, id.document_id(), &metadata)
+                    .await?
+            } else {
+                None
+            };
+
             // Retrieve raw message if needed
             let raw_message = if needs_body {
                 if let Some(raw_message) = self.get_blob(&metadata.blob_hash, 0..usize::MAX).await?
@@ -291,6 +312,44 @@ impl EmailGet for Server {
                     Property::HasAttachment => {
                         email.append(Property::HasAttachment, metadata.has_attachments);
                     }
+                    Property::SmimeStatus | Property::SmimeErrors | Property::SmimeVerifiedAt => {
+                        email.append(
+                            property.clone(),
+                            smime
+                                .as_ref()
+                                .and_then(|smime| smime.latest.as_ref())
+                                .map(|verification| match property {
+                                    Property::SmimeStatus => verification.status(),
+                                    Property::SmimeErrors => verification.errors(),
+                                    _ => verification.verified_at(),
+                                })
+                                .unwrap_or_default(),
+                        );
+                    }
+                    Property::SmimeStatusAtDelivery => {
+                        email.append(
+                            Property::SmimeStatusAtDelivery,
+                            smime
+                                .as_ref()
+                                .and_then(|smime| smime.at_delivery.as_ref())
+                                .map(|verification| verification.status())
+                                .unwrap_or_default(),
+                        );
+                    }
+                    Property::Snoozed => {
+                        email.append(
+                            Property::Snoozed,
+                            self.get_property::<Bincode<SnoozeDetails>>(
+                                account_id,
+                                Collection::Email,
+                                id.document_id(),
+                                &Property::Snoozed,
+                            )
+                            .await?
+                            .map(|snooze| snooze.inner.into_value())
+                            .unwrap_or_default(),
+                        );
+                    }
                     Property::Subject => {
                         email.append(
                             Property::Subject,
@@ -8,6 +8,7 @@ use common::{auth::AccessToken, Server};
 use jmap_proto::{
     error::set::{SetError, SetErrorType},
     method::import::{ImportEmailRequest, ImportEmailResponse},
+    object::Object,
     types::{
         acl::Acl,
         collection::Collection,
@@ -18,11 +19,20 @@ use jmap_proto::{
     },
 };
 use mail_parser::MessageParser;
+use store::write::log::ChangeLogBuilder;
 use utils::map::vec_map::VecMap;
 
 use crate::{
-    api::http::HttpSessionData, auth::acl::AclMethods, blob::download::BlobDownload,
-    changes::state::StateManager, mailbox::set::MailboxSet, JmapMethods,
+    api::http::HttpSessionData,
+    auth::acl::AclMethods,
+    blob::download::BlobDownload,
+    changes::{state::StateManager, write::ChangeLog},
+    import_task::{
+        run::{EmailImportTaskRun, ImportTask},
+        set::EmailImportTaskSet,
+    },
+    mailbox::set::MailboxSet,
+    JmapMethods,
 };
 
 use super::ingest::{EmailIngest, IngestEmail, IngestSource};
@@ -70,6 +80,9 @@ impl EmailImport for Server {
             not_created: VecMap::new(),
             state_change: None,
         };
+        let mut task_changes = ChangeLogBuilder::new();
+        let mut import_tasks = Vec::new();
+        let mut has_emails = false;
 
         'outer: for (id, email) in request.emails {
             // Validate mailboxIds
@@ -125,6 +138,35 @@ impl EmailImport for Server {
                 }
             };
 
+            // Archives are split and imported in the background
+            if let Some(format) = email.format {
+                let document_id = self
+                    .email_import_task_create(
+                        account_id,
+                        format,
+                        email.blob_id,
+                        &mailbox_ids,
+                        &mut task_changes,
+                    )
+                    .await?;
+                import_tasks.push(ImportTask {
+                    account_id,
+                    document_id,
+                    format,
+                    raw_archive: raw_message,
+                    mailbox_ids,
+                    keywords: email.keywords,
+                    received_at: email.received_at.map(|r| r.into()),
+                    resource: resource_token.clone(),
+                    session_id: session.session_id,
+                });
+                response.created.append(
+                    id,
+                    Object::with_capacity(1).with_property(Property::TaskId, Id::from(document_id)),
+                );
+                continue;
+            }
+
             // Import message
             match self
                 .email_ingest(IngestEmail {
@@ -142,6 +184,7 @@ impl EmailImport for Server {
             {
                 Ok(email) => {
                     response.created.append(id, email.into());
+                    has_emails = true;
                 }
                 Err(mut err) => match err.as_ref() {
                     trc::EventType::Limit(trc::LimitEvent::Quota) => {
@@ -169,16 +212,30 @@ impl EmailImport for Server {
         }
 
         // Update state
-        if !response.created.is_empty() {
+        let mut state_change = StateChange::new(account_id);
+        if has_emails {
             response.new_state = self.get_state(account_id, Collection::Email).await?;
             if let State::Exact(change_id) = &response.new_state {
-                response.state_change = StateChange::new(account_id)
+                state_change = state_change
                     .with_change(DataType::Email, *change_id)
                     .with_change(DataType::Mailbox, *change_id)
-                    .with_change(DataType::Thread, *change_id)
-                    .into()
+                    .with_change(DataType::Thread, *change_id);
             }
         }
+        if !task_changes.is_empty() {
+            let change_id = self.commit_changes(account_id, task_changes).await?;
+            state_change = state_change.with_change(DataType::EmailImportTask, change_id);
+
+            for task in import_tasks {
+                let server = self.clone();
+                tokio::spawn(async move {
+                    server.email_import_task_run(task).await;
+                });
+            }
+        }
+        if state_change.has_changes() {
+            response.state_change = state_change.into();
+        }
 
         Ok(response)
     }
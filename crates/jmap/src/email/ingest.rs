@@ -40,8 +40,9 @@ use crate::{
     blob::upload::BlobUpload,
     changes::write::ChangeLog,
     email::index::{IndexMessage, VisitValues, MAX_ID_LENGTH},
-    mailbox::{UidMailbox, INBOX_ID, JUNK_ID},
+    mailbox::{get::MailboxGet, UidMailbox, INBOX_ID, JUNK_ID},
     services::index::Indexer,
+    thread::muted_keyword,
     JmapMethods,
 };
 
@@ -49,6 +50,7 @@ use super::{
     cache::ThreadCache,
     crypto::{EncryptMessage, EncryptMessageError, EncryptionParams},
     index::{TrimTextValue, MAX_SORT_FIELD_LENGTH},
+    smime::SmimeResults,
 };
 
 #[derive(Default)]
@@ -221,6 +223,39 @@ impl EmailIngest for Server {
             }
         };
 
+        // Replies to muted threads skip the inbox
+        if let Some(thread_id) = thread_id {
+            if params.source == IngestSource::Smtp
+                && params.mailbox_ids == [INBOX_ID]
+                && self
+                    .get_tag(
+                        account_id,
+                        Collection::Thread,
+                        Property::Keywords,
+                        muted_keyword(),
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_some_and(|muted_ids| muted_ids.contains(thread_id))
+            {
+                if let Some(archive_id) = self
+                    .mailbox_get_by_role(account_id, "archive")
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    params.mailbox_ids[0] = archive_id;
+                }
+            }
+        }
+
+        // Verify S/MIME signatures
+        let smime = self
+            .core
+            .jmap
+            .smime_verify
+            .as_ref()
+            .and_then(|config| SmimeResults::new(config, &message));
+
         // Encrypt message
         if params.encrypt && !message.is_encrypted() {
             if let Some(encrypt_params) = self
@@ -344,6 +379,9 @@ impl EmailIngest for Server {
                 }),
                 0u64.serialize(),
             );
+        if let Some(smime) = smime {
+            smime.build(&mut batch, true);
+        }
 
         // Insert and obtain ids
         let ids = self
@@ -481,13 +519,35 @@ impl EmailIngest for Server {
             batch
                 .with_account_id(account_id)
                 .with_collection(Collection::Thread);
+            let muted_ids = self
+                .get_tag(
+                    account_id,
+                    Collection::Thread,
+                    Property::Keywords,
+                    muted_keyword(),
+                )
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
+            let mut is_muted = false;
             for &delete_thread_id in thread_counts.keys() {
                 if delete_thread_id != thread_id {
                     batch.delete_document(delete_thread_id);
                     changes.log_delete(Collection::Thread, delete_thread_id);
+                    if muted_ids.contains(delete_thread_id) {
+                        batch.tag(Property::Keywords, muted_keyword(), F_CLEAR);
+                        is_muted = true;
+                    }
                 }
             }
 
+            // The merged thread remains muted if any of its parts was
+            if is_muted && !muted_ids.contains(thread_id) {
+                batch
+                    .update_document(thread_id)
+                    .tag(Property::Keywords, muted_keyword(), 0);
+            }
+
             // Move messages to the new threadId
             batch.with_collection(Collection::Email);
             for old_thread_id in thread_ids
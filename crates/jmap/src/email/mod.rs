@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod archive;
 pub mod body;
 pub mod cache;
 pub mod copy;
@@ -18,4 +19,6 @@ pub mod metadata;
 pub mod parse;
 pub mod query;
 pub mod set;
+pub mod smime;
 pub mod snippet;
+pub mod snooze;
@@ -16,9 +16,10 @@ use mail_parser::{
 use std::future::Future;
 use utils::map::vec_map::VecMap;
 
-use crate::blob::download::BlobDownload;
+use crate::blob::{download::BlobDownload, upload::BlobUpload};
 
 use super::{
+    archive::{Archive, ArchiveError, ArchiveLimits},
     body::{ToBodyPart, TruncateBody},
     headers::HeaderToValue,
     index::PREVIEW_LENGTH,
@@ -91,8 +92,10 @@ impl EmailParse for Server {
             not_found: vec![],
         };
 
+        // Fetch raw messages to parse
+        let account_id = request.account_id.document_id();
+        let mut raw_messages = Vec::with_capacity(request.blob_ids.len());
         for blob_id in request.blob_ids {
-            // Fetch raw message to parse
             let raw_message = match self.blob_download(&blob_id, access_token).await? {
                 Some(raw_message) => raw_message,
                 None => {
@@ -100,6 +103,54 @@ impl EmailParse for Server {
                     continue;
                 }
             };
+            let format = if let Some(format) = request.format {
+                format
+            } else {
+                raw_messages.push((blob_id, raw_message));
+                continue;
+            };
+
+            // Split archives and store each message as a blob that can be imported later
+            let limits = ArchiveLimits {
+                max_items: self
+                    .core
+                    .jmap
+                    .mail_parse_max_items
+                    .saturating_sub(raw_messages.len()),
+                max_message_size: self.core.jmap.mail_max_size,
+                max_size: self.core.jmap.mail_max_size,
+            };
+            let messages = match tokio::task::spawn_blocking(move || {
+                let mut messages = Vec::new();
+                Archive::parse(&raw_message, format, &limits, |message| {
+                    messages.push(message);
+                    true
+                })
+                .map(|_| messages)
+            })
+            .await
+            .map_err(|err| {
+                trc::EventType::Server(trc::ServerEvent::ThreadError)
+                    .into_err()
+                    .reason(err)
+                    .caused_by(trc::location!())
+            })? {
+                Ok(messages) => messages,
+                Err(ArchiveError::TooManyMessages(_) | ArchiveError::TooLarge(_)) => {
+                    return Err(trc::JmapEvent::RequestTooLarge.into_err());
+                }
+                Err(ArchiveError::Invalid(_)) => {
+                    response.not_parsable.push(blob_id);
+                    continue;
+                }
+            };
+            for message in messages {
+                let blob_id = self.put_blob(account_id, &message.contents, false).await?;
+                raw_messages.push((blob_id, message.contents));
+            }
+        }
+
+        for (blob_id, raw_message) in raw_messages {
             let message = if let Some(message) = MessageParser::new().parse(&raw_message) {
                 message
             } else {
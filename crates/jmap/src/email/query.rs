@@ -15,7 +15,7 @@ use nlp::language::Language;
 use std::future::Future;
 use store::{
     fts::{Field, FilterGroup, FtsFilter, IntoFilterGroup},
-    query::{self},
+    query::{self, ResultSet},
     roaring::RoaringBitmap,
     write::ValueClass,
     ValueKey,
@@ -32,6 +32,13 @@ pub trait EmailQuery: Sync + Send {
         access_token: &AccessToken,
     ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
 
+    fn email_query_results(
+        &self,
+        account_id: u32,
+        filter: Vec<Filter>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<ResultSet>> + Send;
+
     fn thread_keywords(
         &self,
         account_id: u32,
@@ -47,9 +54,113 @@ impl EmailQuery for Server {
         access_token: &AccessToken,
     ) -> trc::Result<QueryResponse> {
         let account_id = request.account_id.document_id();
-        let mut filters = Vec::with_capacity(request.filter.len());
+        let result_set = self
+            .email_query_results(
+                account_id,
+                std::mem::take(&mut request.filter),
+                access_token,
+            )
+            .await?;
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
 
-        for cond_group in std::mem::take(&mut request.filter).into_filter_group() {
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::descending(SortProperty::ReceivedAt)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::ReceivedAt => {
+                        query::Comparator::field(Property::ReceivedAt, comparator.is_ascending)
+                    }
+                    SortProperty::Size => {
+                        query::Comparator::field(Property::Size, comparator.is_ascending)
+                    }
+                    SortProperty::From => {
+                        query::Comparator::field(Property::From, comparator.is_ascending)
+                    }
+                    SortProperty::To => {
+                        query::Comparator::field(Property::To, comparator.is_ascending)
+                    }
+                    SortProperty::Subject => {
+                        query::Comparator::field(Property::Subject, comparator.is_ascending)
+                    }
+                    SortProperty::SentAt => {
+                        query::Comparator::field(Property::SentAt, comparator.is_ascending)
+                    }
+                    SortProperty::HasKeyword => query::Comparator::set(
+                        self.get_tag(
+                            account_id,
+                            Collection::Email,
+                            Property::Keywords,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                        )
+                        .await?
+                        .unwrap_or_default(),
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::AllInThreadHaveKeyword => query::Comparator::set(
+                        self.thread_keywords(
+                            account_id,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                            true,
+                        )
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::SomeInThreadHaveKeyword => query::Comparator::set(
+                        self.thread_keywords(
+                            account_id,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                            false,
+                        )
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    // Non-standard
+                    SortProperty::Cc => {
+                        query::Comparator::field(Property::Cc, comparator.is_ascending)
+                    }
+
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()))
+                    }
+                });
+            }
+
+            // Sort results
+            self.sort(
+                result_set,
+                comparators,
+                paginate
+                    .with_prefix_key(ValueKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: 0,
+                        class: ValueClass::Property(Property::ThreadId.into()),
+                    })
+                    .with_prefix_unique(request.arguments.collapse_threads.unwrap_or(false)),
+                response,
+            )
+            .await
+        } else {
+            Ok(response)
+        }
+    }
+
+    async fn email_query_results(
+        &self,
+        account_id: u32,
+        filter: Vec<Filter>,
+        access_token: &AccessToken,
+    ) -> trc::Result<ResultSet> {
+        let mut filters = Vec::with_capacity(filter.len());
+
+        for cond_group in filter.into_filter_group() {
             match cond_group {
                 FilterGroup::Fts(conds) => {
                     let mut fts_filters = Vec::with_capacity(filters.len());
@@ -249,6 +360,22 @@ impl EmailQuery for Server {
                                 filters.push(query::Filter::End);
                             }
                         }
+                        Filter::HasSmime(is_set)
+                        | Filter::HasVerifiedSmime(is_set)
+                        | Filter::HasVerifiedSmimeAtDelivery(is_set) => {
+                            let property = match cond {
+                                Filter::HasSmime(_) => Property::SmimeStatus,
+                                Filter::HasVerifiedSmime(_) => Property::SmimeVerifiedAt,
+                                _ => Property::SmimeStatusAtDelivery,
+                            };
+                            if !is_set {
+                                filters.push(query::Filter::Not);
+                            }
+                            filters.push(query::Filter::is_in_bitmap(property, ()));
+                            if !is_set {
+                                filters.push(query::Filter::End);
+                            }
+                        }
 
                         // Non-standard
                         Filter::Id(ids) => {
@@ -289,95 +416,8 @@ impl EmailQuery for Server {
                     .await?,
             );
         }
-        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
-
-        if let Some(paginate) = paginate {
-            // Parse sort criteria
-            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
-            for comparator in request
-                .sort
-                .and_then(|s| if !s.is_empty() { s.into() } else { None })
-                .unwrap_or_else(|| vec![Comparator::descending(SortProperty::ReceivedAt)])
-            {
-                comparators.push(match comparator.property {
-                    SortProperty::ReceivedAt => {
-                        query::Comparator::field(Property::ReceivedAt, comparator.is_ascending)
-                    }
-                    SortProperty::Size => {
-                        query::Comparator::field(Property::Size, comparator.is_ascending)
-                    }
-                    SortProperty::From => {
-                        query::Comparator::field(Property::From, comparator.is_ascending)
-                    }
-                    SortProperty::To => {
-                        query::Comparator::field(Property::To, comparator.is_ascending)
-                    }
-                    SortProperty::Subject => {
-                        query::Comparator::field(Property::Subject, comparator.is_ascending)
-                    }
-                    SortProperty::SentAt => {
-                        query::Comparator::field(Property::SentAt, comparator.is_ascending)
-                    }
-                    SortProperty::HasKeyword => query::Comparator::set(
-                        self.get_tag(
-                            account_id,
-                            Collection::Email,
-                            Property::Keywords,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                        )
-                        .await?
-                        .unwrap_or_default(),
-                        comparator.is_ascending,
-                    ),
-                    SortProperty::AllInThreadHaveKeyword => query::Comparator::set(
-                        self.thread_keywords(
-                            account_id,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                            true,
-                        )
-                        .await?,
-                        comparator.is_ascending,
-                    ),
-                    SortProperty::SomeInThreadHaveKeyword => query::Comparator::set(
-                        self.thread_keywords(
-                            account_id,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                            false,
-                        )
-                        .await?,
-                        comparator.is_ascending,
-                    ),
-                    // Non-standard
-                    SortProperty::Cc => {
-                        query::Comparator::field(Property::Cc, comparator.is_ascending)
-                    }
-
-                    other => {
-                        return Err(trc::JmapEvent::UnsupportedSort
-                            .into_err()
-                            .details(other.to_string()))
-                    }
-                });
-            }
 
-            // Sort results
-            self.sort(
-                result_set,
-                comparators,
-                paginate
-                    .with_prefix_key(ValueKey {
-                        account_id,
-                        collection: Collection::Email.into(),
-                        document_id: 0,
-                        class: ValueClass::Property(Property::ThreadId.into()),
-                    })
-                    .with_prefix_unique(request.arguments.collapse_threads.unwrap_or(false)),
-                response,
-            )
-            .await
-        } else {
-            Ok(response)
-        }
+        Ok(result_set)
     }
 
     async fn thread_keywords(
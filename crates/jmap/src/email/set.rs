@@ -34,8 +34,8 @@ use store::{
     ahash::AHashSet,
     roaring::RoaringBitmap,
     write::{
-        assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, DeserializeFrom, SerializeInto,
-        ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
+        assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, Bincode, DeserializeFrom,
+        SerializeInto, ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
     },
     Serialize,
 };
@@ -46,7 +46,7 @@ use crate::{
     auth::acl::AclMethods,
     blob::download::BlobDownload,
     changes::{state::StateManager, write::ChangeLog},
-    mailbox::{set::MailboxSet, UidMailbox},
+    mailbox::{get::MailboxGet, set::MailboxSet, UidMailbox},
     JmapMethods,
 };
 use std::future::Future;
@@ -55,6 +55,7 @@ use super::{
     delete::EmailDeletion,
     headers::{BuildHeader, ValueToHeader},
     ingest::{EmailIngest, IngestEmail, IngestSource},
+    snooze::{SnoozeDetails, SNOOZED_KEYWORD, SNOOZED_ROLE},
 };
 
 pub trait EmailSet: Sync + Send {
@@ -796,6 +797,7 @@ impl EmailSet for Server {
                 .with_account_id(account_id)
                 .with_collection(Collection::Email);
 
+            let mut snooze_update = None;
             for (property, value) in object.properties {
                 let value = match response.eval_object_references(value) {
                     Ok(value) => value,
@@ -841,6 +843,14 @@ impl EmailSet for Server {
                             );
                         }
                     }
+                    (Property::Snoozed, MaybePatchValue::Value(value)) => {
+                        if let Some(snooze) = SnoozeDetails::from_value(value) {
+                            snooze_update = Some(snooze);
+                        } else {
+                            response.invalid_property_update(id, Property::Snoozed);
+                            continue 'update;
+                        }
+                    }
                     (property, _) => {
                         response.invalid_property_update(id, property);
                         continue 'update;
@@ -848,7 +858,64 @@ impl EmailSet for Server {
                 }
             }
 
-            if !mailboxes.has_changes() && !keywords.has_changes() {
+            // Process snooze
+            let mut snooze_change = None;
+            if let Some(snooze) = snooze_update {
+                // Verify permissions on shared accounts
+                if matches!(&can_modify_message_ids, Some(ids) if !ids.contains(document_id)) {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to snooze this message."),
+                    );
+                    continue 'update;
+                }
+
+                let current = self
+                    .get_property::<Bincode<SnoozeDetails>>(
+                        account_id,
+                        Collection::Email,
+                        document_id,
+                        Property::Snoozed,
+                    )
+                    .await?
+                    .map(|snooze| snooze.inner);
+
+                let snooze_keyword = Keyword::Other(SNOOZED_KEYWORD.to_string());
+                if let Some(snooze) = &snooze {
+                    if let Some(move_to) = snooze.move_to {
+                        if !mailbox_ids.contains(move_to) {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::Snoozed)
+                                    .with_description(format!(
+                                        "moveToMailboxId {move_to} does not exist."
+                                    )),
+                            );
+                            continue 'update;
+                        }
+                    }
+
+                    // Move the message to the Snoozed mailbox unless the client already did
+                    if !mailboxes.has_changes() {
+                        if let Some(snoozed_id) =
+                            self.mailbox_get_by_role(account_id, SNOOZED_ROLE).await?
+                        {
+                            mailboxes.set(vec![UidMailbox::new_unassigned(snoozed_id)]);
+                        }
+                    }
+                    keywords.update(snooze_keyword, true);
+                } else {
+                    keywords.update(snooze_keyword, false);
+                }
+
+                if current != snooze {
+                    snooze_change = Some((current, snooze));
+                }
+            }
+
+            if !mailboxes.has_changes() && !keywords.has_changes() && snooze_change.is_none() {
                 response.not_updated.append(
                     id,
                     SetError::invalid_properties()
@@ -970,6 +1037,16 @@ impl EmailSet for Server {
                 mailboxes.update_batch(&mut batch, Property::MailboxIds);
             }
 
+            // Update snooze details
+            if let Some((current, snooze)) = snooze_change {
+                if let Some(current) = current {
+                    current.build(&mut batch, false);
+                }
+                if let Some(snooze) = snooze {
+                    snooze.build(&mut batch, true);
+                }
+            }
+
             // Log mailbox changes
             for mailbox_id in changed_mailboxes {
                 changes.log_child_update(Collection::Mailbox, mailbox_id);
@@ -0,0 +1,785 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{borrow::Cow, future::Future};
+
+use common::{
+    config::jmap::smime::{SmimeStatus, SmimeVerification, SmimeVerifyConfig},
+    Server,
+};
+use jmap_proto::types::{collection::Collection, property::Property};
+use mail_parser::{Message, MessageParser, MimeHeaders, PartType};
+use ring::{
+    digest,
+    signature::{self, UnparsedPublicKey, VerificationAlgorithm},
+};
+use store::write::{assert::AssertValue, now, BatchBuilder, Bincode, F_CLEAR, F_VALUE};
+use trc::AddContext;
+use x509_parser::{
+    certificate::X509Certificate, extensions::ParsedExtension, prelude::FromDer, time::ASN1Time,
+    x509::SubjectPublicKeyInfo,
+};
+
+use crate::{blob::download::BlobDownload, JmapMethods};
+
+use super::metadata::MessageMetadata;
+
+const MAX_CHAIN_DEPTH: usize = 8;
+const MAX_DER_DEPTH: usize = 32;
+
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+const OID_SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA1_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const OID_CURVE_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_CURVE_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SmimeResults {
+    pub at_delivery: Option<SmimeVerification>,
+    pub latest: Option<SmimeVerification>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmimeType {
+    Signed,
+    SignedData,
+    Enveloped,
+}
+
+pub trait SmimeVerify: Sync + Send {
+    fn smime_results(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        metadata: &MessageMetadata<'_>,
+    ) -> impl Future<Output = trc::Result<Option<SmimeResults>>> + Send;
+}
+
+impl SmimeVerify for Server {
+    async fn smime_results(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        metadata: &MessageMetadata<'_>,
+    ) -> trc::Result<Option<SmimeResults>> {
+        let Some(config) = &self.core.jmap.smime_verify else {
+            return Ok(None);
+        };
+        let results = self
+            .get_property::<Bincode<SmimeResults>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                &Property::SmimeStatus,
+            )
+            .await?
+            .map(|results| results.inner);
+
+        // Reuse cached results unless they have expired
+        let now = now();
+        let refresh = match smime_type(metadata.contents.root_part()) {
+            Some(SmimeType::Signed | SmimeType::SignedData) => results
+                .as_ref()
+                .and_then(|results| results.latest.as_ref())
+                .is_none_or(|latest| latest.verified_at + config.cache_expiry.as_secs() <= now),
+            Some(SmimeType::Enveloped) => results.is_none(),
+            None => false,
+        };
+        if !refresh {
+            return Ok(results);
+        }
+
+        // Verify the signature against the stored message
+        let Some(raw_message) = self
+            .get_blob(&metadata.blob_hash, 0..usize::MAX)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(results);
+        };
+        let Some(verification) = MessageParser::default()
+            .parse(&raw_message)
+            .and_then(|message| verify_message(config, &message, now))
+        else {
+            return Ok(results);
+        };
+
+        // Store the latest results with the message, results obtained
+        // at delivery are left untouched
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email)
+            .update_document(document_id)
+            .assert_value(Property::BodyStructure, AssertValue::Some);
+        let was_verified = match &results {
+            Some(results) => results.is_verified(),
+            None => {
+                batch.tag(Property::SmimeStatus, (), 0);
+                false
+            }
+        };
+        let mut results = results.unwrap_or_default();
+        results.latest = Some(verification);
+        if was_verified != results.is_verified() {
+            batch.tag(
+                Property::SmimeVerifiedAt,
+                (),
+                if results.is_verified() { 0 } else { F_CLEAR },
+            );
+        }
+        batch.value(
+            Property::SmimeStatus,
+            Bincode::new(results.clone()),
+            F_VALUE,
+        );
+
+        // Failing to store the results does not fail the request,
+        // messages deleted in the meantime are skipped
+        if let Err(err) = self.core.storage.data.write(batch.build()).await {
+            if !err.is_assertion_failure() {
+                trc::error!(err
+                    .account_id(account_id)
+                    .document_id(document_id)
+                    .caused_by(trc::location!()));
+            }
+        }
+
+        Ok(Some(results))
+    }
+}
+
+impl SmimeResults {
+    pub fn new(config: &SmimeVerifyConfig, message: &Message<'_>) -> Option<Self> {
+        smime_type(message.root_part())?;
+
+        let verification = if config.verify_at_delivery {
+            verify_message(config, message, now())
+        } else {
+            None
+        };
+
+        Some(SmimeResults {
+            at_delivery: verification.clone(),
+            latest: verification,
+        })
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.latest.as_ref().is_some_and(|v| v.is_verified())
+    }
+
+    pub fn is_verified_at_delivery(&self) -> bool {
+        self.at_delivery.as_ref().is_some_and(|v| v.is_verified())
+    }
+
+    pub fn build(self, batch: &mut BatchBuilder, set: bool) {
+        let options = if set { 0 } else { F_CLEAR };
+        batch.tag(Property::SmimeStatus, (), options);
+        if self.is_verified() {
+            batch.tag(Property::SmimeVerifiedAt, (), options);
+        }
+        if self.is_verified_at_delivery() {
+            batch.tag(Property::SmimeStatusAtDelivery, (), options);
+        }
+        if set {
+            batch.value(Property::SmimeStatus, Bincode::new(self), F_VALUE);
+        } else {
+            batch.value(Property::SmimeStatus, (), F_VALUE | F_CLEAR);
+        }
+    }
+}
+
+pub fn smime_type<'x>(part: &impl MimeHeaders<'x>) -> Option<SmimeType> {
+    let content_type = part.content_type()?;
+    let subtype = content_type.subtype()?;
+    if content_type.ctype().eq_ignore_ascii_case("multipart")
+        && subtype.eq_ignore_ascii_case("signed")
+    {
+        content_type
+            .attribute("protocol")
+            .filter(|protocol| {
+                protocol.eq_ignore_ascii_case("application/pkcs7-signature")
+                    || protocol.eq_ignore_ascii_case("application/x-pkcs7-signature")
+            })
+            .map(|_| SmimeType::Signed)
+    } else if content_type.ctype().eq_ignore_ascii_case("application")
+        && (subtype.eq_ignore_ascii_case("pkcs7-mime")
+            || subtype.eq_ignore_ascii_case("x-pkcs7-mime"))
+    {
+        if content_type
+            .attribute("smime-type")
+            .is_some_and(|smime_type| smime_type.eq_ignore_ascii_case("signed-data"))
+        {
+            Some(SmimeType::SignedData)
+        } else {
+            Some(SmimeType::Enveloped)
+        }
+    } else {
+        None
+    }
+}
+
+fn verify_message(
+    config: &SmimeVerifyConfig,
+    message: &Message<'_>,
+    now: u64,
+) -> Option<SmimeVerification> {
+    let root = message.root_part();
+    let result = match smime_type(root)? {
+        SmimeType::Signed => {
+            let PartType::Multipart(parts) = &root.body else {
+                return None;
+            };
+            let content = message.part(*parts.first()?)?;
+            let signature = message.part(*parts.get(1)?)?;
+            let content = message
+                .raw_message
+                .get(content.offset_header..content.offset_end)?;
+            verify_cms(
+                config,
+                signature.contents(),
+                Some(&canonicalize(content)),
+                message,
+                now,
+            )
+        }
+        SmimeType::SignedData => verify_cms(config, root.contents(), None, message, now),
+        SmimeType::Enveloped => {
+            return Some(SmimeVerification {
+                status: SmimeStatus::Unknown,
+                errors: vec![],
+                verified_at: now,
+            })
+        }
+    };
+
+    Some(match result {
+        Ok(()) => SmimeVerification {
+            status: SmimeStatus::SignedVerified,
+            errors: vec![],
+            verified_at: now,
+        },
+        Err(errors) => SmimeVerification {
+            status: SmimeStatus::SignedFailed,
+            errors,
+            verified_at: now,
+        },
+    })
+}
+
+fn verify_cms(
+    config: &SmimeVerifyConfig,
+    cms: &[u8],
+    detached: Option<&[u8]>,
+    message: &Message<'_>,
+    now: u64,
+) -> Result<(), Vec<String>> {
+    let signed_data = SignedData::parse(cms)
+        .ok_or_else(|| vec!["Failed to parse S/MIME signature.".to_string()])?;
+    let content = match (&signed_data.content, detached) {
+        (_, Some(detached)) => detached,
+        (Some(content), None) => content.as_ref(),
+        (None, None) => return Err(vec!["Signed content not found.".to_string()]),
+    };
+    let certificates = signed_data
+        .certificates
+        .iter()
+        .filter_map(|cert| X509Certificate::from_der(cert).ok().map(|(_, cert)| cert))
+        .collect::<Vec<_>>();
+    let trust_store = config
+        .trust_store
+        .iter()
+        .filter_map(|cert| X509Certificate::from_der(cert).ok().map(|(_, cert)| cert))
+        .collect::<Vec<_>>();
+    let senders = message
+        .from()
+        .map(|from| {
+            from.iter()
+                .filter_map(|addr| addr.address())
+                .map(|addr| addr.to_lowercase())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let now = ASN1Time::from_timestamp(now as i64)
+        .map_err(|_| vec!["Invalid verification time.".to_string()])?;
+
+    if senders.is_empty() {
+        return Err(vec!["Message has no sender address.".to_string()]);
+    }
+
+    let mut errors = Vec::new();
+    if signed_data.signers.is_empty() {
+        errors.push("No signers found.".to_string());
+    }
+    for signer in &signed_data.signers {
+        if let Err(err) = signer.verify(content, &certificates, &trust_store, &senders, now) {
+            errors.push(err);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+struct SignedData<'x> {
+    content: Option<Cow<'x, [u8]>>,
+    certificates: Vec<&'x [u8]>,
+    signers: Vec<SignerInfo<'x>>,
+}
+
+struct SignerInfo<'x> {
+    issuer: Option<&'x [u8]>,
+    serial: &'x [u8],
+    digest_algorithm: &'x [u8],
+    signed_attrs: Option<&'x [u8]>,
+    signature_algorithm: &'x [u8],
+    signature: &'x [u8],
+}
+
+impl<'x> SignedData<'x> {
+    fn parse(bytes: &'x [u8]) -> Option<Self> {
+        // ContentInfo
+        let (content_info, _) = Der::read(bytes, 0)?;
+        let mut items = content_info.children();
+        if items.next()?.as_oid()? != OID_SIGNED_DATA {
+            return None;
+        }
+        let signed_data = items.next()?.children().next()?;
+
+        // SignedData
+        let mut items = signed_data.children();
+        items.next()?.expect(0x02)?;
+        items.next()?.expect(0x31)?;
+        let content = items
+            .next()?
+            .expect(0x30)?
+            .children()
+            .nth(1)
+            .and_then(|content| content.children().next())
+            .and_then(|content| content.octet_string());
+        let mut certificates = Vec::new();
+        let mut signers = Vec::new();
+        for item in items {
+            match item.tag {
+                0xa0 => {
+                    certificates.extend(
+                        item.children()
+                            .filter(|cert| cert.tag == 0x30)
+                            .map(|cert| cert.raw),
+                    );
+                }
+                0x31 => {
+                    signers.extend(item.children().filter_map(SignerInfo::parse));
+                }
+                _ => (),
+            }
+        }
+
+        Some(SignedData {
+            content,
+            certificates,
+            signers,
+        })
+    }
+}
+
+impl<'x> SignerInfo<'x> {
+    fn parse(item: Der<'x>) -> Option<Self> {
+        let mut items = item.expect(0x30)?.children();
+        items.next()?.expect(0x02)?;
+        let sid = items.next()?;
+        let (issuer, serial) = match sid.tag {
+            0x30 => {
+                let mut sid = sid.children();
+                let issuer = sid.next()?.expect(0x30)?.raw;
+                let serial = sid.next()?.expect(0x02)?.contents;
+                (Some(issuer), serial)
+            }
+            0x80 => (None, sid.contents),
+            _ => return None,
+        };
+        let digest_algorithm = items.next()?.children().next()?.as_oid()?;
+        let mut item = items.next()?;
+        let signed_attrs = if item.tag == 0xa0 {
+            let signed_attrs = item.raw;
+            item = items.next()?;
+            Some(signed_attrs)
+        } else {
+            None
+        };
+        let signature_algorithm = item.children().next()?.as_oid()?;
+        let signature = items.next()?.expect(0x04)?.contents;
+
+        Some(SignerInfo {
+            issuer,
+            serial,
+            digest_algorithm,
+            signed_attrs,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    fn verify(
+        &self,
+        content: &[u8],
+        certificates: &[X509Certificate<'_>],
+        trust_store: &[X509Certificate<'_>],
+        senders: &[String],
+        now: ASN1Time,
+    ) -> Result<(), String> {
+        // Find the signer's certificate
+        let cert = certificates
+            .iter()
+            .find(|cert| match self.issuer {
+                Some(issuer) => {
+                    cert.issuer().as_raw() == issuer && cert.raw_serial() == self.serial
+                }
+                None => subject_key_id(cert) == Some(self.serial),
+            })
+            .ok_or_else(|| "Signer certificate not found.".to_string())?;
+
+        // Verify the signature, SHA-1 is no longer collision resistant
+        if self.digest_algorithm == OID_SHA1 || self.signature_algorithm == OID_SHA1_WITH_RSA {
+            return Err("SHA-1 signatures are not accepted.".to_string());
+        }
+        let digest = digest_algorithm(self.digest_algorithm)
+            .ok_or_else(|| "Unsupported digest algorithm.".to_string())?;
+        let digest_value = digest::digest(digest, content);
+        let signed_data = if let Some(signed_attrs) = self.signed_attrs {
+            let message_digest = Der::read(signed_attrs, 0)
+                .and_then(|(attrs, _)| {
+                    attrs.children().find_map(|attr| {
+                        let mut items = attr.children();
+                        if items.next()?.as_oid()? == OID_MESSAGE_DIGEST {
+                            items.next()?.children().next()?.octet_string()
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .ok_or_else(|| "Message digest attribute not found.".to_string())?;
+            if message_digest.as_ref() != digest_value.as_ref() {
+                return Err("Message digest does not match the signed content.".to_string());
+            }
+
+            // Signed attributes are signed as an explicit SET OF
+            let mut signed_data = signed_attrs.to_vec();
+            signed_data[0] = 0x31;
+            Cow::Owned(signed_data)
+        } else {
+            Cow::Borrowed(content)
+        };
+        verify_signature(
+            cert.public_key(),
+            self.signature_algorithm,
+            Some(digest),
+            &signed_data,
+            self.signature,
+        )
+        .map_err(|err| format!("Invalid signature: {err}"))?;
+
+        // Make sure the certificate can be used for S/MIME
+        if let Ok(Some(eku)) = cert.extended_key_usage() {
+            if !eku.value.email_protection && !eku.value.any {
+                return Err("Signer certificate is not valid for email protection.".to_string());
+            }
+        }
+        let mut emails = cert
+            .subject()
+            .iter_email()
+            .filter_map(|email| email.as_str().ok())
+            .map(|email| email.to_lowercase())
+            .collect::<Vec<_>>();
+        if let Ok(Some(san)) = cert.subject_alternative_name() {
+            for name in &san.value.general_names {
+                if let x509_parser::extensions::GeneralName::RFC822Name(email) = name {
+                    emails.push(email.to_lowercase());
+                }
+            }
+        }
+        if !senders.iter().any(|sender| emails.contains(sender)) {
+            return Err("Signer certificate does not match the sender address.".to_string());
+        }
+
+        verify_chain(cert, certificates, trust_store, now)
+    }
+}
+
+fn verify_chain(
+    signer: &X509Certificate<'_>,
+    certificates: &[X509Certificate<'_>],
+    trust_store: &[X509Certificate<'_>],
+    now: ASN1Time,
+) -> Result<(), String> {
+    let mut cert = signer;
+    for _ in 0..MAX_CHAIN_DEPTH {
+        if !cert.validity().is_valid_at(now) {
+            return Err(format!(
+                "Certificate {:?} is expired or not yet valid.",
+                cert.subject().to_string()
+            ));
+        }
+
+        // Trusted certificates end the chain
+        if trust_store
+            .iter()
+            .any(|anchor| anchor.tbs_certificate.as_ref() == cert.tbs_certificate.as_ref())
+        {
+            return Ok(());
+        }
+        if let Some(anchor) = trust_store.iter().find(|anchor| is_issuer(anchor, cert)) {
+            return if anchor.validity().is_valid_at(now) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Trusted certificate {:?} is expired or not yet valid.",
+                    anchor.subject().to_string()
+                ))
+            };
+        }
+
+        // Continue with intermediate certificates
+        cert = certificates
+            .iter()
+            .find(|issuer| {
+                issuer.tbs_certificate.as_ref() != cert.tbs_certificate.as_ref()
+                    && issuer.is_ca()
+                    && is_issuer(issuer, cert)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Certificate {:?} is not issued by a trusted authority.",
+                    cert.subject().to_string()
+                )
+            })?;
+    }
+
+    Err("Certificate chain is too long.".to_string())
+}
+
+fn is_issuer(issuer: &X509Certificate<'_>, cert: &X509Certificate<'_>) -> bool {
+    issuer.subject().as_raw() == cert.issuer().as_raw()
+        && verify_signature(
+            issuer.public_key(),
+            cert.signature_algorithm.algorithm.as_bytes(),
+            None,
+            cert.tbs_certificate.as_ref(),
+            &cert.signature_value.data,
+        )
+        .is_ok()
+}
+
+fn verify_signature(
+    public_key: &SubjectPublicKeyInfo<'_>,
+    algorithm: &[u8],
+    digest: Option<&'static digest::Algorithm>,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), &'static str> {
+    let algorithm: &dyn VerificationAlgorithm = match algorithm {
+        OID_SHA256_WITH_RSA => &signature::RSA_PKCS1_2048_8192_SHA256,
+        OID_SHA384_WITH_RSA => &signature::RSA_PKCS1_2048_8192_SHA384,
+        OID_SHA512_WITH_RSA => &signature::RSA_PKCS1_2048_8192_SHA512,
+        OID_RSA_ENCRYPTION => match digest {
+            Some(digest) if digest == &digest::SHA256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+            Some(digest) if digest == &digest::SHA384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+            Some(digest) if digest == &digest::SHA512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+            _ => return Err("unsupported RSA digest"),
+        },
+        OID_ECDSA_WITH_SHA256 => ecdsa_algorithm(public_key, &digest::SHA256)?,
+        OID_ECDSA_WITH_SHA384 => ecdsa_algorithm(public_key, &digest::SHA384)?,
+        OID_EC_PUBLIC_KEY => ecdsa_algorithm(public_key, digest.ok_or("missing digest")?)?,
+        _ => return Err("unsupported signature algorithm"),
+    };
+
+    UnparsedPublicKey::new(algorithm, &public_key.subject_public_key.data)
+        .verify(data, signature)
+        .map_err(|_| "signature mismatch")
+}
+
+fn ecdsa_algorithm(
+    public_key: &SubjectPublicKeyInfo<'_>,
+    digest: &'static digest::Algorithm,
+) -> Result<&'static dyn VerificationAlgorithm, &'static str> {
+    let curve = public_key
+        .algorithm
+        .parameters
+        .as_ref()
+        .and_then(|params| params.as_oid().ok())
+        .ok_or("missing curve")?;
+    match (curve.as_bytes(), digest) {
+        (OID_CURVE_P256, digest) if digest == &digest::SHA256 => {
+            Ok(&signature::ECDSA_P256_SHA256_ASN1)
+        }
+        (OID_CURVE_P256, digest) if digest == &digest::SHA384 => {
+            Ok(&signature::ECDSA_P256_SHA384_ASN1)
+        }
+        (OID_CURVE_P384, digest) if digest == &digest::SHA256 => {
+            Ok(&signature::ECDSA_P384_SHA256_ASN1)
+        }
+        (OID_CURVE_P384, digest) if digest == &digest::SHA384 => {
+            Ok(&signature::ECDSA_P384_SHA384_ASN1)
+        }
+        _ => Err("unsupported elliptic curve"),
+    }
+}
+
+fn digest_algorithm(oid: &[u8]) -> Option<&'static digest::Algorithm> {
+    match oid {
+        OID_SHA256 => Some(&digest::SHA256),
+        OID_SHA384 => Some(&digest::SHA384),
+        OID_SHA512 => Some(&digest::SHA512),
+        _ => None,
+    }
+}
+
+fn subject_key_id<'x>(cert: &'x X509Certificate<'_>) -> Option<&'x [u8]> {
+    cert.iter_extensions()
+        .find_map(|ext| match ext.parsed_extension() {
+            ParsedExtension::SubjectKeyIdentifier(key_id) => Some(key_id.0),
+            _ => None,
+        })
+}
+
+// Signed MIME entities are verified using CRLF line endings
+fn canonicalize(content: &[u8]) -> Cow<'_, [u8]> {
+    if content
+        .iter()
+        .enumerate()
+        .any(|(pos, &ch)| ch == b'\n' && (pos == 0 || content[pos - 1] != b'\r'))
+    {
+        let mut canonical = Vec::with_capacity(content.len() + 64);
+        let mut last_ch = 0;
+        for &ch in content {
+            if ch == b'\n' && last_ch != b'\r' {
+                canonical.push(b'\r');
+            }
+            canonical.push(ch);
+            last_ch = ch;
+        }
+        Cow::Owned(canonical)
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+// Minimal BER/DER reader, indefinite lengths are accepted as produced by most S/MIME clients
+#[derive(Clone, Copy)]
+struct Der<'x> {
+    tag: u8,
+    contents: &'x [u8],
+    raw: &'x [u8],
+    depth: usize,
+}
+
+struct DerIter<'x> {
+    bytes: &'x [u8],
+    depth: usize,
+}
+
+impl<'x> Der<'x> {
+    fn read(bytes: &'x [u8], depth: usize) -> Option<(Self, &'x [u8])> {
+        if depth > MAX_DER_DEPTH {
+            return None;
+        }
+        let (&tag, rest) = bytes.split_first()?;
+        let (&len, mut rest) = rest.split_first()?;
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+        let (contents, rest) = if len == 0x80 {
+            if tag & 0x20 == 0 {
+                return None;
+            }
+            let start = rest;
+            while !rest.starts_with(&[0, 0]) {
+                rest = Der::read(rest, depth + 1)?.1;
+            }
+            (&start[..start.len() - rest.len()], &rest[2..])
+        } else {
+            let len = if len & 0x80 == 0 {
+                len as usize
+            } else {
+                let num_bytes = (len & 0x7f) as usize;
+                if num_bytes > 4 || rest.len() < num_bytes {
+                    return None;
+                }
+                let (len, bytes) = rest.split_at(num_bytes);
+                rest = bytes;
+                len.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize)
+            };
+            if rest.len() < len {
+                return None;
+            }
+            rest.split_at(len)
+        };
+
+        Some((
+            Der {
+                tag,
+                contents,
+                raw: &bytes[..bytes.len() - rest.len()],
+                depth,
+            },
+            rest,
+        ))
+    }
+
+    fn children(&self) -> DerIter<'x> {
+        DerIter {
+            bytes: self.contents,
+            depth: self.depth + 1,
+        }
+    }
+
+    fn expect(self, tag: u8) -> Option<Self> {
+        (self.tag == tag).then_some(self)
+    }
+
+    fn as_oid(&self) -> Option<&'x [u8]> {
+        (self.tag == 0x06).then_some(self.contents)
+    }
+
+    fn octet_string(&self) -> Option<Cow<'x, [u8]>> {
+        match self.tag {
+            0x04 => Some(Cow::Borrowed(self.contents)),
+            0x24 => {
+                let mut value = Vec::with_capacity(self.contents.len());
+                for chunk in self.children() {
+                    value.extend_from_slice(&chunk.octet_string()?);
+                }
+                Some(Cow::Owned(value))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'x> Iterator for DerIter<'x> {
+    type Item = Der<'x>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (item, rest) = Der::read(self.bytes, self.depth)?;
+        self.bytes = rest;
+        Some(item)
+    }
+}
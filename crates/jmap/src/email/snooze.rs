@@ -0,0 +1,341 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::Server;
+use jmap_proto::{
+    object::Object,
+    types::{
+        collection::Collection, date::UTCDate, id::Id, keyword::Keyword, property::Property,
+        state::StateChange, type_state::DataType, value::Value,
+    },
+};
+use store::{
+    ahash::AHashMap,
+    write::{
+        assert::HashedValue, key::DeserializeBigEndian, log::ChangeLogBuilder, now, BatchBuilder,
+        Bincode, ValueClass, F_CLEAR, F_VALUE,
+    },
+    IterateParams, ValueKey, U32_LEN, U64_LEN,
+};
+use trc::AddContext;
+
+use crate::{
+    changes::write::ChangeLog,
+    mailbox::{get::MailboxGet, UidMailbox, INBOX_ID},
+    services::state::StateManager,
+    JmapMethods,
+};
+
+use super::{ingest::EmailIngest, set::TagManager};
+
+pub const SNOOZED_KEYWORD: &str = "$snoozed";
+pub const SNOOZED_ROLE: &str = "snoozed";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnoozeDetails {
+    pub until: u64,
+    pub move_to: Option<u32>,
+}
+
+pub trait EmailSnooze: Sync + Send {
+    fn wake_snoozed(&self) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn wake_snoozed_email(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        due: u64,
+        changes: &mut ChangeLogBuilder,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+const SNOOZE_LOCK_EXPIRY: u64 = 300;
+
+impl EmailSnooze for Server {
+    async fn wake_snoozed(&self) -> trc::Result<()> {
+        // Only one node at a time may wake up snoozed messages
+        let lock_key = b"snooze-lock".to_vec();
+        match self
+            .core
+            .storage
+            .lookup
+            .counter_incr(lock_key.clone(), 1, Some(SNOOZE_LOCK_EXPIRY), true)
+            .await
+            .caused_by(trc::location!())?
+        {
+            1 => (),
+            _ => return Ok(()),
+        }
+
+        let result = wake_due_emails(self).await;
+
+        if let Err(err) = self.core.storage.lookup.counter_delete(lock_key).await {
+            trc::error!(err
+                .details("Failed to delete lock.")
+                .caused_by(trc::location!()));
+        }
+
+        result
+    }
+
+    async fn wake_snoozed_email(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        due: u64,
+        changes: &mut ChangeLogBuilder,
+    ) -> trc::Result<()> {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email)
+            .update_document(document_id);
+
+        // Obtain snooze details, mailboxes and keywords
+        let snooze = self
+            .get_property::<Bincode<SnoozeDetails>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Snoozed,
+            )
+            .await?
+            .map(|snooze| snooze.inner)
+            .filter(|snooze| snooze.until == due);
+        let (snooze, mut mailboxes, mut keywords, thread_id) =
+            if let (Some(snooze), Some(mailboxes), Some(keywords), Some(thread_id)) = (
+                snooze,
+                self.get_property::<HashedValue<Vec<UidMailbox>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::MailboxIds,
+                )
+                .await?,
+                self.get_property::<HashedValue<Vec<Keyword>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::Keywords,
+                )
+                .await?,
+                self.get_property::<u32>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::ThreadId,
+                )
+                .await?,
+            ) {
+                (
+                    snooze,
+                    TagManager::new(mailboxes),
+                    TagManager::new(keywords),
+                    thread_id,
+                )
+            } else {
+                // The message was deleted or snoozed again, remove the stale entry
+                batch.clear(ValueClass::Snooze(due));
+                return self
+                    .core
+                    .storage
+                    .data
+                    .write(batch.build())
+                    .await
+                    .caused_by(trc::location!())
+                    .map(|_| ());
+            };
+
+        // Move the message out of the Snoozed mailbox
+        let mailbox_ids = self
+            .get_document_ids(account_id, Collection::Mailbox)
+            .await?
+            .unwrap_or_default();
+        if let Some(snoozed_id) = self.mailbox_get_by_role(account_id, SNOOZED_ROLE).await? {
+            mailboxes.update(UidMailbox::new_unassigned(snoozed_id), false);
+        }
+        mailboxes.update(
+            UidMailbox::new_unassigned(
+                snooze
+                    .move_to
+                    .filter(|mailbox_id| mailbox_ids.contains(*mailbox_id))
+                    .unwrap_or(INBOX_ID),
+            ),
+            true,
+        );
+        for uid_mailbox in mailboxes.inner_tags_mut() {
+            if uid_mailbox.uid == 0 {
+                uid_mailbox.uid = self
+                    .assign_imap_uid(account_id, uid_mailbox.mailbox_id)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        // Mark the message as unread
+        keywords.update(Keyword::Seen, false);
+        keywords.update(Keyword::Other(SNOOZED_KEYWORD.to_string()), false);
+
+        // Write changes
+        if changes.change_id == u64::MAX {
+            changes.change_id = self.assign_change_id(account_id).await?;
+        }
+        batch.value(Property::Cid, changes.change_id, F_VALUE);
+        let changed_mailboxes = mailboxes
+            .current()
+            .iter()
+            .chain(mailboxes.removed())
+            .map(|mailbox| mailbox.mailbox_id)
+            .collect::<Vec<_>>();
+        if mailboxes.has_changes() {
+            mailboxes.update_batch(&mut batch, Property::MailboxIds);
+        }
+        if keywords.has_changes() {
+            keywords.update_batch(&mut batch, Property::Keywords);
+        }
+        snooze.build(&mut batch, false);
+        self.core
+            .storage
+            .data
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+
+        // Log changes
+        changes.log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+        for mailbox_id in changed_mailboxes {
+            changes.log_child_update(Collection::Mailbox, mailbox_id);
+        }
+
+        Ok(())
+    }
+}
+
+async fn wake_due_emails(server: &Server) -> trc::Result<()> {
+    let from_key = ValueKey::<ValueClass<u32>> {
+        account_id: 0,
+        collection: 0,
+        document_id: 0,
+        class: ValueClass::Snooze(0),
+    };
+    let to_key = ValueKey::<ValueClass<u32>> {
+        account_id: u32::MAX,
+        collection: u8::MAX,
+        document_id: u32::MAX,
+        class: ValueClass::Snooze(u64::MAX),
+    };
+    let now = now();
+
+    // Obtain messages that are due
+    let mut due_emails: AHashMap<u32, Vec<(u32, u64)>> = AHashMap::new();
+    server
+        .core
+        .storage
+        .data
+        .iterate(
+            IterateParams::new(from_key, to_key).ascending().no_values(),
+            |key, _| {
+                let account_id = key.deserialize_be_u32(0)?;
+                let due = key.deserialize_be_u64(U32_LEN)?;
+                let document_id = key.deserialize_be_u32(U32_LEN + U64_LEN)?;
+
+                if due <= now {
+                    due_emails
+                        .entry(account_id)
+                        .or_default()
+                        .push((document_id, due));
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    for (account_id, emails) in due_emails {
+        let mut changes = ChangeLogBuilder::new();
+        for (document_id, due) in emails {
+            if let Err(err) = server
+                .wake_snoozed_email(account_id, document_id, due, &mut changes)
+                .await
+            {
+                trc::error!(err
+                    .account_id(account_id)
+                    .document_id(document_id)
+                    .details("Failed to wake up snoozed message."));
+            }
+        }
+
+        // Write and broadcast changes
+        if !changes.is_empty() {
+            let change_id = server.commit_changes(account_id, changes).await?;
+            server
+                .broadcast_state_change(
+                    StateChange::new(account_id)
+                        .with_change(DataType::Email, change_id)
+                        .with_change(DataType::Mailbox, change_id)
+                        .with_change(DataType::Thread, change_id),
+                )
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+impl SnoozeDetails {
+    pub fn from_value(value: Value) -> Option<Option<Self>> {
+        match value {
+            Value::Object(mut object) => {
+                let until = match object.properties.remove(&Property::Until)? {
+                    Value::Date(until) => until.timestamp() as u64,
+                    _ => return None,
+                };
+                let move_to = match object.properties.remove(&Property::MoveToMailboxId) {
+                    Some(Value::Id(id)) => Some(id.document_id()),
+                    Some(Value::Null) | None => None,
+                    _ => return None,
+                };
+
+                if object.properties.is_empty() {
+                    Some(Some(SnoozeDetails { until, move_to }))
+                } else {
+                    None
+                }
+            }
+            Value::Null => Some(None),
+            _ => None,
+        }
+    }
+
+    pub fn into_value(self) -> Value {
+        Value::Object(
+            Object::with_capacity(2)
+                .with_property(
+                    Property::Until,
+                    Value::Date(UTCDate::from_timestamp(self.until as i64)),
+                )
+                .with_property(
+                    Property::MoveToMailboxId,
+                    self.move_to
+                        .map(|id| Value::Id(Id::from(id)))
+                        .unwrap_or_default(),
+                ),
+        )
+    }
+
+    pub fn build(self, batch: &mut BatchBuilder, set: bool) {
+        if set {
+            batch.set(ValueClass::Snooze(self.until), Vec::new());
+            batch.value(Property::Snoozed, Bincode::new(self), F_VALUE);
+        } else {
+            batch.clear(ValueClass::Snooze(self.until));
+            batch.value(Property::Snoozed, (), F_VALUE | F_CLEAR);
+        }
+    }
+}
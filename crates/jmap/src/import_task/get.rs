@@ -0,0 +1,106 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+use std::future::Future;
+
+use crate::{changes::state::StateManager, JmapMethods};
+
+pub trait EmailImportTaskGet: Sync + Send {
+    fn email_import_task_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl EmailImportTaskGet for Server {
+    async fn email_import_task_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Created,
+            Property::Format,
+            Property::BlobId,
+            Property::MailboxIds,
+            Property::Status,
+            Property::TotalEmails,
+            Property::ImportedEmails,
+            Property::FailedEmails,
+            Property::Error,
+        ]);
+        let account_id = request.account_id.document_id();
+        let task_ids = self
+            .get_document_ids(account_id, Collection::EmailImportTask)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            task_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::EmailImportTask)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the task object
+            let document_id = id.document_id();
+            if !task_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut task = if let Some(task) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::EmailImportTask,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::Created => match task.remove(property) {
+                        Value::UnsignedInt(created) => {
+                            Value::Date(UTCDate::from_timestamp(created as i64))
+                        }
+                        _ => Value::Null,
+                    },
+                    property => task.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
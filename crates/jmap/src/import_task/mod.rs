@@ -0,0 +1,60 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::LazyLock;
+
+use common::Server;
+
+pub mod get;
+pub mod run;
+pub mod set;
+
+// Import tasks run on the node that created them while holding a lease
+// in the lookup store, which is renewed on every progress update
+pub(crate) const TASK_LEASE_EXPIRY: u64 = 60 * 60;
+
+static PROCESS_ID: LazyLock<u64> = LazyLock::new(rand::random);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportTaskStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ImportTaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportTaskStatus::Pending => "pending",
+            ImportTaskStatus::Running => "running",
+            ImportTaskStatus::Completed => "completed",
+            ImportTaskStatus::Failed => "failed",
+        }
+    }
+}
+
+pub(crate) fn task_lease_key(account_id: u32, document_id: u32) -> Vec<u8> {
+    format!("import:{account_id}:{document_id}").into_bytes()
+}
+
+pub(crate) trait ImportTaskLease {
+    fn task_lease(&self) -> String;
+    fn is_stale_task_lease(&self, lease: &str) -> bool;
+}
+
+impl ImportTaskLease for Server {
+    fn task_lease(&self) -> String {
+        format!("{}:{}", self.core.network.node_id, *PROCESS_ID)
+    }
+
+    // Leases held by an earlier run of this node are no longer valid
+    fn is_stale_task_lease(&self, lease: &str) -> bool {
+        lease.split_once(':').is_some_and(|(node_id, process_id)| {
+            node_id == self.core.network.node_id.to_string() && process_id != PROCESS_ID.to_string()
+        })
+    }
+}
@@ -0,0 +1,388 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::ResourceToken, Server};
+use jmap_proto::{
+    method::import::ArchiveFormat,
+    object::Object,
+    types::{
+        collection::Collection, keyword::Keyword, property::Property, state::StateChange,
+        type_state::DataType, value::Value,
+    },
+};
+use mail_parser::MessageParser;
+use std::future::Future;
+use store::write::{assert::AssertValue, log::ChangeLogBuilder, BatchBuilder, F_VALUE};
+use tokio::sync::mpsc;
+
+use crate::{
+    changes::write::ChangeLog,
+    email::{
+        archive::{Archive, ArchiveLimits, ArchiveMessage},
+        ingest::{EmailIngest, IngestEmail, IngestSource},
+    },
+    services::state::StateManager,
+    JmapMethods,
+};
+
+use super::{task_lease_key, ImportTaskLease, ImportTaskStatus, TASK_LEASE_EXPIRY};
+
+// Number of imported messages between progress updates
+const PROGRESS_INTERVAL: usize = 50;
+
+// Number of decompressed messages waiting to be imported
+const MESSAGE_BUFFER: usize = 8;
+
+pub struct ImportTask {
+    pub account_id: u32,
+    pub document_id: u32,
+    pub format: ArchiveFormat,
+    pub raw_archive: Vec<u8>,
+    pub mailbox_ids: Vec<u32>,
+    pub keywords: Vec<Keyword>,
+    pub received_at: Option<u64>,
+    pub resource: ResourceToken,
+    pub session_id: u64,
+}
+
+pub struct ImportProgress {
+    status: ImportTaskStatus,
+    total: Option<usize>,
+    imported: usize,
+    failed: usize,
+    error: Option<String>,
+    email_change_id: Option<u64>,
+}
+
+pub trait EmailImportTaskRun: Sync + Send {
+    fn email_import_task_run(&self, task: ImportTask) -> impl Future<Output = ()> + Send;
+
+    fn email_import_task_ingest(
+        &self,
+        task: ImportTask,
+        progress: &mut ImportProgress,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn email_import_task_recover(&self) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn email_import_task_update(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        progress: &ImportProgress,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+}
+
+impl EmailImportTaskRun for Server {
+    async fn email_import_task_run(&self, task: ImportTask) {
+        let account_id = task.account_id;
+        let document_id = task.document_id;
+        let mut progress = ImportProgress {
+            status: ImportTaskStatus::Running,
+            total: None,
+            imported: 0,
+            failed: 0,
+            error: None,
+            email_change_id: None,
+        };
+
+        let result = match self
+            .email_import_task_update(account_id, document_id, &progress)
+            .await
+        {
+            Ok(true) => self.email_import_task_ingest(task, &mut progress).await,
+            Ok(false) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            trc::error!(err
+                .account_id(account_id)
+                .document_id(document_id)
+                .details("Failed to import email archive"));
+
+            progress.status = ImportTaskStatus::Failed;
+            progress.error = Some("Internal server error.".to_string());
+            let _ = self
+                .email_import_task_update(account_id, document_id, &progress)
+                .await;
+        }
+    }
+
+    async fn email_import_task_ingest(
+        &self,
+        task: ImportTask,
+        progress: &mut ImportProgress,
+    ) -> trc::Result<()> {
+        let account_id = task.account_id;
+        let document_id = task.document_id;
+        let limits = ArchiveLimits {
+            max_items: self.core.jmap.mail_import_max_items,
+            max_message_size: self.core.jmap.mail_max_size,
+            max_size: self.core.jmap.mail_import_max_size,
+        };
+
+        // Split the archive into messages, which are streamed to the importer as
+        // they are decompressed. Dropping the receiver stops the parser.
+        let format = task.format;
+        let raw_archive = task.raw_archive;
+        let (tx, mut rx) = mpsc::channel::<ArchiveMessage>(MESSAGE_BUFFER);
+        let parser = tokio::task::spawn_blocking(move || {
+            Archive::parse(&raw_archive, format, &limits, |message| {
+                tx.blocking_send(message).is_ok()
+            })
+        });
+
+        // Import messages
+        let mut over_quota = false;
+        while let Some(message) = rx.recv().await {
+            if over_quota {
+                progress.failed += 1;
+                continue;
+            }
+
+            let mut keywords = task.keywords.clone();
+            for keyword in message.keywords {
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+
+            match self
+                .email_ingest(IngestEmail {
+                    raw_message: &message.contents,
+                    message: MessageParser::new().parse(&message.contents),
+                    resource: task.resource.clone(),
+                    mailbox_ids: task.mailbox_ids.clone(),
+                    keywords,
+                    received_at: message.received_at.or(task.received_at),
+                    source: IngestSource::Jmap,
+                    encrypt: self.core.jmap.encrypt && self.core.jmap.encrypt_append,
+                    session_id: task.session_id,
+                })
+                .await
+            {
+                Ok(email) => {
+                    progress.imported += 1;
+                    progress.email_change_id = Some(email.change_id);
+                }
+                Err(err) => match err.as_ref() {
+                    trc::EventType::Limit(trc::LimitEvent::Quota) => {
+                        // Keep counting the remaining messages as failed
+                        progress.failed += 1;
+                        progress.error = Some("You have exceeded your disk quota.".to_string());
+                        over_quota = true;
+                    }
+                    trc::EventType::MessageIngest(trc::MessageIngestEvent::Error) => {
+                        progress.failed += 1;
+                    }
+                    _ => {
+                        return Err(err);
+                    }
+                },
+            }
+
+            // Report progress, stopping if the task was destroyed
+            if (progress.imported + progress.failed).is_multiple_of(PROGRESS_INTERVAL) {
+                if !self
+                    .email_import_task_update(account_id, document_id, progress)
+                    .await?
+                {
+                    return Ok(());
+                }
+                progress.email_change_id = None;
+            }
+        }
+
+        match parser.await.map_err(|err| {
+            trc::EventType::Server(trc::ServerEvent::ThreadError)
+                .into_err()
+                .reason(err)
+                .caused_by(trc::location!())
+        })? {
+            Ok(archive) => {
+                progress.failed += archive.skipped;
+                progress.total = Some(archive.total);
+            }
+            Err(reason) => {
+                progress.total = Some(progress.imported + progress.failed);
+                progress.error = Some(reason.to_string());
+            }
+        }
+
+        progress.status = if progress.error.is_none() {
+            ImportTaskStatus::Completed
+        } else {
+            ImportTaskStatus::Failed
+        };
+        self.email_import_task_update(account_id, document_id, progress)
+            .await
+            .map(|_| ())
+    }
+
+    async fn email_import_task_recover(&self) -> trc::Result<()> {
+        let Some(account_ids) = self
+            .get_document_ids(u32::MAX, Collection::Principal)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        for account_id in account_ids {
+            let Some(task_ids) = self
+                .get_document_ids(account_id, Collection::EmailImportTask)
+                .await?
+            else {
+                continue;
+            };
+
+            for document_id in task_ids {
+                let Some(task) = self
+                    .get_property::<Object<Value>>(
+                        account_id,
+                        Collection::EmailImportTask,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                else {
+                    continue;
+                };
+                if !matches!(task.get(&Property::Status), Value::Text(status)
+                    if status == ImportTaskStatus::Pending.as_str()
+                        || status == ImportTaskStatus::Running.as_str())
+                {
+                    continue;
+                }
+
+                // Tasks are orphaned when their lease expired or was held by an
+                // earlier run of this node
+                match self
+                    .core
+                    .storage
+                    .lookup
+                    .key_get::<String>(task_lease_key(account_id, document_id))
+                    .await?
+                {
+                    Some(lease) if !self.is_stale_task_lease(&lease) => continue,
+                    _ => (),
+                }
+
+                let count = |property: Property| match task.get(&property) {
+                    Value::UnsignedInt(count) => *count as usize,
+                    _ => 0,
+                };
+                let progress = ImportProgress {
+                    status: ImportTaskStatus::Failed,
+                    total: match task.get(&Property::TotalEmails) {
+                        Value::UnsignedInt(total) => Some(*total as usize),
+                        _ => None,
+                    },
+                    imported: count(Property::ImportedEmails),
+                    failed: count(Property::FailedEmails),
+                    error: Some("Import was interrupted by a server restart.".to_string()),
+                    email_change_id: None,
+                };
+                self.email_import_task_update(account_id, document_id, &progress)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn email_import_task_update(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        progress: &ImportProgress,
+    ) -> trc::Result<bool> {
+        let mut task = if let Some(task) = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::EmailImportTask,
+                document_id,
+                Property::Value,
+            )
+            .await?
+        {
+            task
+        } else {
+            return Ok(false);
+        };
+        task.set(
+            Property::Status,
+            Value::Text(progress.status.as_str().to_string()),
+        );
+        task.set(
+            Property::TotalEmails,
+            progress
+                .total
+                .map(|total| Value::UnsignedInt(total as u64))
+                .unwrap_or_default(),
+        );
+        task.set(
+            Property::ImportedEmails,
+            Value::UnsignedInt(progress.imported as u64),
+        );
+        task.set(
+            Property::FailedEmails,
+            Value::UnsignedInt(progress.failed as u64),
+        );
+        task.set(
+            Property::Error,
+            progress
+                .error
+                .as_ref()
+                .map(|error| Value::Text(error.clone()))
+                .unwrap_or_default(),
+        );
+
+        // Make sure the task was not destroyed in the meantime
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::EmailImportTask)
+            .update_document(document_id)
+            .assert_value(Property::Value, AssertValue::Some)
+            .value(Property::Value, task, F_VALUE);
+        match self.write_batch(batch).await {
+            Ok(_) => (),
+            Err(err) if err.is_assertion_failure() => return Ok(false),
+            Err(err) => return Err(err),
+        }
+
+        // Renew the task lease while it is running
+        let lease_key = task_lease_key(account_id, document_id);
+        if progress.status == ImportTaskStatus::Running {
+            self.core
+                .storage
+                .lookup
+                .key_set(
+                    lease_key,
+                    self.task_lease().into_bytes(),
+                    Some(TASK_LEASE_EXPIRY),
+                )
+                .await?;
+        } else {
+            self.core.storage.lookup.key_delete(lease_key).await?;
+        }
+
+        let mut changes = ChangeLogBuilder::new();
+        changes.log_update(Collection::EmailImportTask, document_id);
+        let change_id = self.commit_changes(account_id, changes).await?;
+        let mut state_change =
+            StateChange::new(account_id).with_change(DataType::EmailImportTask, change_id);
+        if let Some(email_change_id) = progress.email_change_id {
+            state_change = state_change
+                .with_change(DataType::Email, email_change_id)
+                .with_change(DataType::Mailbox, email_change_id)
+                .with_change(DataType::Thread, email_change_id);
+        }
+        self.broadcast_state_change(state_change).await;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    error::set::SetError,
+    method::{
+        import::ArchiveFormat,
+        set::{RequestArguments, SetRequest, SetResponse},
+    },
+    object::Object,
+    types::{
+        blob::BlobId, collection::Collection, id::Id, property::Property, state::StateChange,
+        type_state::DataType, value::Value,
+    },
+};
+use std::future::Future;
+use store::write::{log::ChangeLogBuilder, now, BatchBuilder, F_CLEAR, F_VALUE};
+
+use crate::{changes::write::ChangeLog, JmapMethods};
+
+use super::{task_lease_key, ImportTaskLease, ImportTaskStatus, TASK_LEASE_EXPIRY};
+
+pub trait EmailImportTaskSet: Sync + Send {
+    fn email_import_task_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+
+    fn email_import_task_create(
+        &self,
+        account_id: u32,
+        format: ArchiveFormat,
+        blob_id: BlobId,
+        mailbox_ids: &[u32],
+        changes: &mut ChangeLogBuilder,
+    ) -> impl Future<Output = trc::Result<u32>> + Send;
+}
+
+impl EmailImportTaskSet for Server {
+    async fn email_import_task_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let task_ids = self
+            .get_document_ids(account_id, Collection::EmailImportTask)
+            .await?
+            .unwrap_or_default();
+        let mut response = SetResponse::from_request(&request, self.core.jmap.set_max_objects)?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Import tasks are created with Email/import and updated by the server
+        for (id, _) in request.unwrap_create() {
+            response.not_created.append(
+                id,
+                SetError::forbidden()
+                    .with_description("Import tasks are created using Email/import."),
+            );
+        }
+        for (id, _) in request.unwrap_update() {
+            response.not_updated.append(
+                id,
+                SetError::forbidden().with_description("Import tasks cannot be modified."),
+            );
+        }
+
+        // Process deletions, which also cancel any running import
+        let mut changes = ChangeLogBuilder::new();
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if task_ids.contains(document_id) {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::EmailImportTask)
+                    .delete_document(document_id)
+                    .value(Property::Value, (), F_VALUE | F_CLEAR);
+                self.write_batch(batch).await?;
+                changes.log_delete(Collection::EmailImportTask, document_id);
+                response.destroyed.push(id);
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            response.new_state = Some(change_id.into());
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::EmailImportTask, change_id)
+                .into();
+        }
+
+        Ok(response)
+    }
+
+    async fn email_import_task_create(
+        &self,
+        account_id: u32,
+        format: ArchiveFormat,
+        blob_id: BlobId,
+        mailbox_ids: &[u32],
+        changes: &mut ChangeLogBuilder,
+    ) -> trc::Result<u32> {
+        let task = Object::with_capacity(9)
+            .with_property(Property::Created, Value::UnsignedInt(now()))
+            .with_property(Property::Format, Value::Text(format.as_str().to_string()))
+            .with_property(Property::BlobId, Value::BlobId(blob_id))
+            .with_property(
+                Property::MailboxIds,
+                mailbox_ids.iter().fold(
+                    Object::with_capacity(mailbox_ids.len()),
+                    |obj, mailbox_id| {
+                        obj.with_property(Property::_T(Id::from(*mailbox_id).to_string()), true)
+                    },
+                ),
+            )
+            .with_property(
+                Property::Status,
+                Value::Text(ImportTaskStatus::Pending.as_str().to_string()),
+            )
+            .with_property(Property::TotalEmails, Value::Null)
+            .with_property(Property::ImportedEmails, Value::UnsignedInt(0))
+            .with_property(Property::FailedEmails, Value::UnsignedInt(0))
+            .with_property(Property::Error, Value::Null);
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::EmailImportTask)
+            .create_document()
+            .value(Property::Value, task, F_VALUE);
+        let document_id = self.write_batch_expect_id(batch).await?;
+        changes.log_insert(Collection::EmailImportTask, document_id);
+
+        // Hold the task lease until the import starts running
+        self.core
+            .storage
+            .lookup
+            .key_set(
+                task_lease_key(account_id, document_id),
+                self.task_lease().into_bytes(),
+                Some(TASK_LEASE_EXPIRY),
+            )
+            .await?;
+
+        Ok(document_id)
+    }
+}
@@ -44,13 +44,18 @@ pub mod blob;
 pub mod changes;
 pub mod email;
 pub mod identity;
+pub mod import_task;
 pub mod mailbox;
 pub mod principal;
 pub mod push;
 pub mod quota;
+pub mod rules;
+pub mod saved_search;
 pub mod services;
+pub mod share_notification;
 pub mod sieve;
 pub mod submission;
+pub mod template;
 pub mod thread;
 pub mod vacation;
 pub mod websocket;
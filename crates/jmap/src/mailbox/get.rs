@@ -12,6 +12,7 @@ use jmap_proto::{
 };
 use store::{ahash::AHashSet, query::Filter, roaring::RoaringBitmap};
 use trc::AddContext;
+use utils::map::bitmap::Bitmap;
 
 use crate::{
     auth::acl::{AclMethods, EffectiveAcl},
@@ -209,27 +210,7 @@ impl MailboxGet for Server {
                     ),
                     Property::MyRights => {
                         if access_token.is_shared(account_id) {
-                            let acl = values.effective_acl(access_token);
-                            Object::with_capacity(9)
-                                .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
-                                .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
-                                .with_property(
-                                    Property::MayRemoveItems,
-                                    acl.contains(Acl::RemoveItems),
-                                )
-                                .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
-                                .with_property(
-                                    Property::MaySetKeywords,
-                                    acl.contains(Acl::ModifyItems),
-                                )
-                                .with_property(
-                                    Property::MayCreateChild,
-                                    acl.contains(Acl::CreateChild),
-                                )
-                                .with_property(Property::MayRename, acl.contains(Acl::Modify))
-                                .with_property(Property::MayDelete, acl.contains(Acl::Delete))
-                                .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
-                                .into()
+                            mailbox_rights(&values.effective_acl(access_token))
                         } else {
                             Object::with_capacity(9)
                                 .with_property(Property::MayReadItems, true)
@@ -461,3 +442,17 @@ pub struct ExpandPath<'x> {
     pub path: Vec<&'x str>,
     pub found_names: Vec<(String, u32, u32)>,
 }
+
+pub fn mailbox_rights(acl: &Bitmap<Acl>) -> Value {
+    Object::with_capacity(9)
+        .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+        .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
+        .with_property(Property::MayRemoveItems, acl.contains(Acl::RemoveItems))
+        .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
+        .with_property(Property::MaySetKeywords, acl.contains(Acl::ModifyItems))
+        .with_property(Property::MayCreateChild, acl.contains(Acl::CreateChild))
+        .with_property(Property::MayRename, acl.contains(Acl::Modify))
+        .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+        .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
+        .into()
+}
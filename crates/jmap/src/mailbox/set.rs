@@ -40,6 +40,7 @@ use crate::{
     auth::acl::{AclMethods, EffectiveAcl},
     changes::write::ChangeLog,
     email::delete::EmailDeletion,
+    share_notification::set::{ShareNotificationSet, SharedObject},
     JmapMethods,
 };
 
@@ -133,9 +134,28 @@ impl MailboxSet for Server {
 
         // Process creates
         let mut changes = ChangeLogBuilder::new();
+        let mut acl_changes = Vec::new();
         'create: for (id, object) in request.unwrap_create() {
             match self.mailbox_set_item(object, None, &ctx).await? {
                 Ok(builder) => {
+                    let new_acl = builder
+                        .changes()
+                        .unwrap()
+                        .get(&Property::Acl)
+                        .as_acl()
+                        .filter(|acl| !acl.is_empty())
+                        .map(|acl| {
+                            (
+                                builder
+                                    .changes()
+                                    .unwrap()
+                                    .get(&Property::Name)
+                                    .as_string()
+                                    .unwrap_or_default()
+                                    .to_string(),
+                                acl.clone(),
+                            )
+                        });
                     let mut batch = BatchBuilder::new();
                     batch
                         .with_account_id(account_id)
@@ -166,6 +186,9 @@ impl MailboxSet for Server {
                             changes.log_insert(Collection::Mailbox, document_id);
                             ctx.mailbox_ids.insert(document_id);
                             ctx.response.created(id, document_id);
+                            if let Some((name, new_acl)) = new_acl {
+                                acl_changes.push((document_id, name, Vec::new(), new_acl));
+                            }
                         }
                         Err(err) if err.is_assertion_failure() => {
                             ctx.response.not_created.append(
@@ -212,7 +235,11 @@ impl MailboxSet for Server {
                 // Validate ACL
                 if ctx.is_shared {
                     let acl = mailbox.inner.effective_acl(access_token);
-                    if !acl.contains(Acl::Modify) {
+                    // Grantees with read access may (un)subscribe to shared mailboxes
+                    let is_subscription = object.properties.len() == 1
+                        && object.properties.contains_key(&Property::IsSubscribed)
+                        && acl.contains(Acl::Read);
+                    if !acl.contains(Acl::Modify) && !is_subscription {
                         ctx.response.not_updated.append(
                             id,
                             SetError::forbidden()
@@ -232,11 +259,37 @@ impl MailboxSet for Server {
                     }
                 }
 
+                let old_acl = object.properties.contains_key(&Property::Acl).then(|| {
+                    mailbox
+                        .inner
+                        .get(&Property::Acl)
+                        .as_acl()
+                        .cloned()
+                        .unwrap_or_default()
+                });
+
                 match self
                     .mailbox_set_item(object, (document_id, mailbox).into(), &ctx)
                     .await?
                 {
                     Ok(builder) => {
+                        let acl_change = old_acl.and_then(|old_acl| {
+                            let changes = builder.changes().unwrap();
+                            let current = &builder.current().unwrap().inner;
+                            let new_acl = changes.get(&Property::Acl).as_acl()?;
+                            (new_acl != &old_acl).then(|| {
+                                (
+                                    changes
+                                        .get(&Property::Name)
+                                        .as_string()
+                                        .or_else(|| current.get(&Property::Name).as_string())
+                                        .unwrap_or_default()
+                                        .to_string(),
+                                    old_acl,
+                                    new_acl.clone(),
+                                )
+                            })
+                        });
                         let mut batch = BatchBuilder::new();
                         batch
                             .with_account_id(account_id)
@@ -259,6 +312,9 @@ impl MailboxSet for Server {
                             match self.core.storage.data.write(batch.build()).await {
                                 Ok(_) => {
                                     changes.log_update(Collection::Mailbox, document_id);
+                                    if let Some((name, old_acl, new_acl)) = acl_change {
+                                        acl_changes.push((document_id, name, old_acl, new_acl));
+                                    }
                                 }
                                 Err(err) if err.is_assertion_failure() => {
                                     ctx.response.not_updated.append(id, SetError::forbidden().with_description(
@@ -321,6 +377,29 @@ impl MailboxSet for Server {
             ctx.response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
         }
 
+        // Notify grantees about sharing changes
+        for (document_id, name, old_acl, new_acl) in acl_changes {
+            if let Err(err) = self
+                .share_notification_create(
+                    access_token,
+                    SharedObject {
+                        account_id,
+                        document_id,
+                        object_type: DataType::Mailbox,
+                        name: Some(&name),
+                    },
+                    &old_acl,
+                    &new_acl,
+                )
+                .await
+            {
+                trc::error!(err
+                    .account_id(account_id)
+                    .document_id(document_id)
+                    .details("Failed to create share notification."));
+            }
+        }
+
         Ok(ctx.response)
     }
 
@@ -581,7 +660,7 @@ impl MailboxSet for Server {
                 (Property::Role, MaybePatchValue::Value(Value::Text(value))) => {
                     let role = value.trim().to_lowercase();
                     if [
-                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent",
+                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent", "snoozed",
                     ]
                     .contains(&role.as_str())
                     {
@@ -831,11 +910,15 @@ impl MailboxSet for Server {
                 SpecialUse::Drafts => ("drafts", DRAFTS_ID),
                 SpecialUse::Sent => ("sent", SENT_ID),
                 SpecialUse::Archive => ("archive", ARCHIVE_ID),
+                SpecialUse::Snoozed => {
+                    last_document_id += 1;
+                    ("snoozed", last_document_id)
+                }
                 SpecialUse::None => {
                     last_document_id += 1;
                     ("", last_document_id)
                 }
-                SpecialUse::Shared => unreachable!(),
+                SpecialUse::Shared | SpecialUse::Searches => unreachable!(),
             };
 
             let mut object = Object::with_capacity(4)
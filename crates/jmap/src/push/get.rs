@@ -233,6 +233,13 @@ impl PushSubscriptionFetch for Server {
                         Bitmap::all()
                     };
 
+                    // Email summaries are only delivered over encrypted channels
+                    let email_summaries = keys.is_some()
+                        && matches!(
+                            subscription.properties.get(&Property::EmailSummaries),
+                            Some(Value::Bool(true))
+                        );
+
                     // Add verified subscription
                     subscriptions.push(UpdateSubscription::Verified(PushSubscription {
                         id: document_id,
@@ -240,6 +247,7 @@ impl PushSubscriptionFetch for Server {
                         expires,
                         types,
                         keys,
+                        email_summaries,
                     }));
                 } else {
                     // Add unverified subscription
@@ -5,17 +5,30 @@
  */
 
 use base64::{engine::general_purpose, Engine};
-use common::{core::BuildServer, Inner, IPC_CHANNEL_BUFFER};
-use jmap_proto::types::id::Id;
-use store::ahash::{AHashMap, AHashSet};
+use common::{core::BuildServer, Inner, Server, IPC_CHANNEL_BUFFER};
+use jmap_proto::types::{
+    acl::Acl, collection::Collection, date::UTCDate, id::Id, property::Property,
+    type_state::DataType,
+};
+use mail_parser::HeaderName;
+use store::{
+    ahash::{AHashMap, AHashSet},
+    query::log::{Change, Query},
+    write::{now, Bincode},
+};
 use tokio::sync::mpsc;
-use trc::PushSubscriptionEvent;
+use trc::{AddContext, PushSubscriptionEvent};
 
-use crate::{api::StateChangeResponse, LONG_SLUMBER};
+use crate::{
+    api::{EmailSummary, EmailSummaryAddress, StateChangeResponse},
+    auth::acl::AclMethods,
+    email::metadata::MessageMetadata,
+    JmapMethods, LONG_SLUMBER,
+};
 
 use super::{ece::ece_encrypt, EncryptionKeys, Event, PushServer, PushUpdate};
 
-use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
 use std::{
     collections::hash_map::Entry,
     sync::Arc,
@@ -42,7 +55,6 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
             let push_attempt_interval = server.core.jmap.push_attempt_interval;
             let push_attempts_max = server.core.jmap.push_attempts_max;
             let push_retry_interval = server.core.jmap.push_retry_interval;
-            let push_timeout = server.core.jmap.push_timeout;
             let push_verify_timeout = server.core.jmap.push_verify_timeout;
             let push_throttle = server.core.jmap.push_throttle;
 
@@ -76,20 +88,25 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         })
                                         .unwrap_or(true)
                                     {
+                                        let server = server.clone();
                                         tokio::spawn(async move {
                                             http_request(
-                                                url,
-                                                format!(
-                                                    concat!(
-                                                        "{{\"@type\":\"PushVerification\",",
-                                                        "\"pushSubscriptionId\":\"{}\",",
-                                                        "\"verificationCode\":\"{}\"}}"
+                                                &server,
+                                                PushRequest {
+                                                    url,
+                                                    body: format!(
+                                                        concat!(
+                                                            "{{\"@type\":\"PushVerification\",",
+                                                            "\"pushSubscriptionId\":\"{}\",",
+                                                            "\"verificationCode\":\"{}\"}}"
+                                                        ),
+                                                        Id::from(id),
+                                                        code
                                                     ),
-                                                    Id::from(id),
-                                                    code
-                                                ),
-                                                keys,
-                                                push_timeout,
+                                                    keys,
+                                                    urgency: "normal",
+                                                    topic: None,
+                                                },
                                             )
                                             .await;
                                         });
@@ -107,11 +124,17 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         continue;
                                     }
                                 }
-                                PushUpdate::Register { id, url, keys } => {
-                                    if let Entry::Vacant(entry) = subscriptions.entry(id) {
+                                PushUpdate::Register {
+                                    id,
+                                    url,
+                                    keys,
+                                    email_summaries,
+                                } => match subscriptions.entry(id) {
+                                    Entry::Vacant(entry) => {
                                         entry.insert(PushServer {
                                             url,
                                             keys,
+                                            email_summaries,
                                             num_attempts: 0,
                                             last_request: Instant::now()
                                                 - (push_throttle + Duration::from_millis(1)),
@@ -119,7 +142,10 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                             in_flight: false,
                                         });
                                     }
-                                }
+                                    Entry::Occupied(mut entry) => {
+                                        entry.get_mut().email_summaries = email_summaries;
+                                    }
+                                },
                                 PushUpdate::Unregister { id } => {
                                     subscriptions.remove(&id);
                                 }
@@ -139,7 +165,7 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                             .contains(&subscription.num_attempts)
                                             && last_request > push_attempt_interval))
                                 {
-                                    subscription.send(id, push_tx.clone(), push_timeout);
+                                    subscription.send(id, push_tx.clone(), server.clone());
                                     retry_ids.remove(&id);
                                 } else {
                                     retry_ids.insert(id);
@@ -195,7 +221,7 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         && last_request >= push_attempt_interval))
                             {
                                 if subscription.num_attempts < push_attempts_max {
-                                    subscription.send(*retry_id, push_tx.clone(), push_timeout);
+                                    subscription.send(*retry_id, push_tx.clone(), server.clone());
                                 } else {
                                     trc::event!(
                                         PushSubscription(PushSubscriptionEvent::Error),
@@ -236,10 +262,22 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
     push_tx_
 }
 
+const MAX_SUMMARIES: usize = 5;
+const MAX_SUMMARY_LEN: usize = 120;
+
+struct PushRequest {
+    url: String,
+    body: String,
+    keys: Option<EncryptionKeys>,
+    urgency: &'static str,
+    topic: Option<String>,
+}
+
 impl PushServer {
-    fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, push_timeout: Duration) {
+    fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, server: Server) {
         let url = self.url.clone();
         let keys = self.keys.clone();
+        let email_summaries = self.email_summaries;
         let state_changes = std::mem::take(&mut self.state_changes);
 
         self.in_flight = true;
@@ -247,43 +285,174 @@ impl PushServer {
 
         tokio::spawn(async move {
             let mut response = StateChangeResponse::new();
+            let mut has_delivery = false;
             for state_change in &state_changes {
                 for (type_state, change_id) in &state_change.types {
                     response
                         .changed
                         .get_mut_or_insert(state_change.account_id.into())
                         .set(*type_state, (*change_id).into());
+
+                    if *type_state == DataType::EmailDelivery {
+                        has_delivery = true;
+
+                        if email_summaries && response.email_summaries.len() < MAX_SUMMARIES {
+                            if let Err(err) = fetch_email_summaries(
+                                &server,
+                                id.prefix_id(),
+                                state_change.account_id,
+                                *change_id,
+                                &mut response.email_summaries,
+                            )
+                            .await
+                            {
+                                trc::error!(err
+                                    .details("Failed to fetch email summaries.")
+                                    .account_id(state_change.account_id));
+                            }
+                        }
+                    }
                 }
             }
 
+            // New mail is delivered with high urgency, while plain state changes
+            // share a topic so that push services replace any undelivered ones
+            let request = PushRequest {
+                url,
+                body: serde_json::to_string(&response).unwrap(),
+                keys,
+                urgency: if has_delivery { "high" } else { "normal" },
+                topic: (!has_delivery).then(|| id.to_string()),
+            };
+
             push_tx
-                .send(
-                    if http_request(
-                        url,
-                        serde_json::to_string(&response).unwrap(),
-                        keys,
-                        push_timeout,
-                    )
-                    .await
-                    {
-                        Event::DeliverySuccess { id }
-                    } else {
-                        Event::DeliveryFailure { id, state_changes }
-                    },
-                )
+                .send(if http_request(&server, request).await {
+                    Event::DeliverySuccess { id }
+                } else {
+                    Event::DeliveryFailure { id, state_changes }
+                })
                 .await
                 .ok();
         });
     }
 }
 
-async fn http_request(
-    url: String,
-    mut body: String,
-    keys: Option<EncryptionKeys>,
-    push_timeout: Duration,
-) -> bool {
-    let client_builder = reqwest::Client::builder().timeout(push_timeout);
+async fn fetch_email_summaries(
+    server: &Server,
+    subscriber_id: u32,
+    account_id: u32,
+    change_id: u64,
+    summaries: &mut Vec<EmailSummary>,
+) -> trc::Result<()> {
+    // Subscribers with access to a shared account only receive
+    // summaries of messages in the mailboxes they can read
+    let readable_ids = if subscriber_id != account_id {
+        let access_token = server
+            .get_cached_access_token(subscriber_id)
+            .await
+            .caused_by(trc::location!())?;
+        let readable_ids = server
+            .owned_or_shared_messages(&access_token, account_id, Acl::ReadItems)
+            .await
+            .caused_by(trc::location!())?;
+        if readable_ids.is_empty() {
+            return Ok(());
+        }
+        Some(readable_ids)
+    } else {
+        None
+    };
+
+    let changes = server
+        .core
+        .storage
+        .data
+        .changes(
+            account_id,
+            Collection::Email,
+            Query::RangeInclusive(change_id, change_id),
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    for change in changes.changes {
+        if summaries.len() >= MAX_SUMMARIES {
+            break;
+        }
+        let Change::Insert(id) = change else {
+            continue;
+        };
+        let id = Id::from(id);
+        if readable_ids
+            .as_ref()
+            .is_some_and(|readable_ids| !readable_ids.contains(id.document_id()))
+        {
+            continue;
+        }
+        let Some(metadata) = server
+            .get_property::<Bincode<MessageMetadata>>(
+                account_id,
+                Collection::Email,
+                id.document_id(),
+                &Property::BodyStructure,
+            )
+            .await?
+        else {
+            continue;
+        };
+        let mut metadata = metadata.inner;
+        let (from, subject) = metadata
+            .contents
+            .parts
+            .first_mut()
+            .map(|part| {
+                (
+                    part.remove_header(&HeaderName::From)
+                        .and_then(|value| value.into_address()),
+                    part.remove_header(&HeaderName::Subject)
+                        .and_then(|value| value.into_text()),
+                )
+            })
+            .unwrap_or_default();
+
+        summaries.push(EmailSummary {
+            account_id: account_id.into(),
+            email_id: id,
+            thread_id: Id::from(id.prefix_id()),
+            from: from
+                .map(|from| {
+                    from.iter()
+                        .filter_map(|addr| {
+                            Some(EmailSummaryAddress {
+                                name: addr.name.as_ref().map(|name| truncate(name)),
+                                email: addr.address.as_ref()?.to_string(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            subject: subject.map(|subject| truncate(&subject)),
+            preview: truncate(metadata.preview.trim()),
+            received_at: UTCDate::from_timestamp(metadata.received_at as i64).to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_SUMMARY_LEN).collect()
+}
+
+async fn http_request(server: &Server, request: PushRequest) -> bool {
+    let PushRequest {
+        url,
+        mut body,
+        keys,
+        urgency,
+        topic,
+    } = request;
+    let client_builder = reqwest::Client::builder().timeout(server.core.jmap.push_timeout);
 
     #[cfg(feature = "test_mode")]
     let client_builder = client_builder.danger_accept_invalid_certs(true);
@@ -293,8 +462,22 @@ async fn http_request(
         .unwrap_or_default()
         .post(&url)
         .header(CONTENT_TYPE, "application/json")
-        .header("TTL", "86400");
+        .header("TTL", server.core.jmap.push_ttl.as_secs().to_string())
+        .header("Urgency", urgency);
 
+    if let Some(topic) = topic {
+        client = client.header("Topic", topic);
+    }
+
+    // Identify the server to the push service
+    if let Some(vapid) = &server.core.jmap.push_vapid {
+        if let Some(authorization) = reqwest::Url::parse(&url)
+            .ok()
+            .and_then(|url| vapid.authorization(&url.origin().ascii_serialization(), now() + 43200))
+        {
+            client = client.header(AUTHORIZATION, authorization);
+        }
+    }
     if let Some(keys) = keys {
         match ece_encrypt(&keys.p256dh, &keys.auth, body.as_bytes())
             .map(|b| general_purpose::URL_SAFE.encode(b))
@@ -46,6 +46,7 @@ pub enum PushUpdate {
         id: Id,
         url: String,
         keys: Option<EncryptionKeys>,
+        email_summaries: bool,
     },
     Unregister {
         id: Id,
@@ -56,6 +57,7 @@ pub enum PushUpdate {
 pub struct PushServer {
     url: String,
     keys: Option<EncryptionKeys>,
+    email_summaries: bool,
     num_attempts: u32,
     last_request: Instant,
     state_changes: Vec<StateChange>,
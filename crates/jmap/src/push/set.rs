@@ -91,6 +91,11 @@ impl PushSubscriptionSet for Server {
                 continue 'create;
             }
 
+            if let Err(err) = validate_email_summaries(&push) {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
             // Add expiry time if missing
             let expires = if let Some(expires) = push.properties.get(&Property::Expires) {
                 expires.clone()
@@ -173,6 +178,11 @@ impl PushSubscriptionSet for Server {
                 };
             }
 
+            if let Err(err) = validate_email_summaries(&push) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+
             // Update record
             let mut batch = BatchBuilder::new();
             batch
@@ -278,8 +288,14 @@ fn validate_push_value(
                     .with_description("Verification code does not match.".to_string()));
             }
         }
+        (Property::EmailSummaries, MaybePatchValue::Value(Value::Bool(value))) => {
+            Value::Bool(value)
+        }
         (
-            Property::Keys | Property::Types | Property::VerificationCode,
+            Property::Keys
+            | Property::Types
+            | Property::VerificationCode
+            | Property::EmailSummaries,
             MaybePatchValue::Value(Value::Null),
         ) => Value::Null,
         (property, _) => {
@@ -289,3 +305,17 @@ fn validate_push_value(
         }
     })
 }
+
+fn validate_email_summaries(push: &Object<Value>) -> Result<(), SetError> {
+    if matches!(
+        push.properties.get(&Property::EmailSummaries),
+        Some(Value::Bool(true))
+    ) && !push.properties.contains_key(&Property::Keys)
+    {
+        Err(SetError::invalid_properties()
+            .with_properties([Property::EmailSummaries, Property::Keys])
+            .with_description("Email summaries require an encrypted subscription."))
+    } else {
+        Ok(())
+    }
+}
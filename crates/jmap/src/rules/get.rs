@@ -0,0 +1,175 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    request::reference::MaybeReference,
+    types::{any_id::AnyId, collection::Collection, id::Id, property::Property, value::Value},
+};
+use std::future::Future;
+use store::query::Filter;
+
+use crate::{
+    blob::download::BlobDownload, changes::state::StateManager, sieve::set::ObjectBlobId,
+    JmapMethods,
+};
+
+use super::{parse_rules, MailRule, RULES_SCRIPT_NAME};
+
+pub trait MailRulesGet: Sync + Send {
+    fn mail_rules_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+
+    fn get_mail_rules_script_id(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<Option<u32>>> + Send;
+
+    fn get_mail_rules(
+        &self,
+        account_id: u32,
+        script: &Object<Value>,
+    ) -> impl Future<Output = trc::Result<Vec<MailRule>>> + Send;
+}
+
+impl MailRulesGet for Server {
+    async fn mail_rules_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let account_id = request.account_id.document_id();
+        let properties =
+            request.unwrap_properties(&[Property::Id, Property::IsEnabled, Property::Rules]);
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::SieveScript)
+                .await?
+                .into(),
+            list: Vec::with_capacity(1),
+            not_found: vec![],
+        };
+
+        let do_get = if let Some(MaybeReference::Value(ids)) = request.ids {
+            let mut do_get = false;
+            for id in ids {
+                match id.try_unwrap() {
+                    Some(AnyId::Id(id)) if id.is_singleton() => {
+                        do_get = true;
+                    }
+                    Some(id) => {
+                        response.not_found.push(id);
+                    }
+                    _ => {}
+                }
+            }
+            do_get
+        } else {
+            true
+        };
+        if do_get {
+            if let Some(document_id) = self.get_mail_rules_script_id(account_id).await? {
+                if let Some(mut obj) = self
+                    .get_property::<Object<Value>>(
+                        account_id,
+                        Collection::SieveScript,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    let mut result = Object::with_capacity(properties.len());
+                    for property in &properties {
+                        match property {
+                            Property::Id => {
+                                result.append(Property::Id, Value::Id(Id::singleton()));
+                            }
+                            Property::IsEnabled => {
+                                result
+                                    .append(Property::IsEnabled, obj.remove(&Property::IsEnabled));
+                            }
+                            Property::Rules => {
+                                result.append(
+                                    Property::Rules,
+                                    Value::List(
+                                        self.get_mail_rules(account_id, &obj)
+                                            .await?
+                                            .into_iter()
+                                            .map(MailRule::into_value)
+                                            .collect(),
+                                    ),
+                                );
+                            }
+                            property => {
+                                result.append(property.clone(), Value::Null);
+                            }
+                        }
+                    }
+                    response.list.push(result);
+                } else {
+                    response.not_found.push(Id::singleton().into());
+                }
+            } else {
+                response.not_found.push(Id::singleton().into());
+            }
+        }
+
+        Ok(response)
+    }
+
+    async fn get_mail_rules_script_id(&self, account_id: u32) -> trc::Result<Option<u32>> {
+        self.filter(
+            account_id,
+            Collection::SieveScript,
+            vec![Filter::eq(Property::Name, RULES_SCRIPT_NAME)],
+        )
+        .await
+        .map(|r| r.results.min())
+    }
+
+    async fn get_mail_rules(
+        &self,
+        account_id: u32,
+        script: &Object<Value>,
+    ) -> trc::Result<Vec<MailRule>> {
+        // Rules are obtained from the managed Sieve script
+        let blob_id = script.blob_id().ok_or_else(|| {
+            trc::StoreEvent::NotFound
+                .into_err()
+                .caused_by(trc::location!())
+                .account_id(account_id)
+        })?;
+        let script_size = blob_id.section.as_ref().map_or(0, |section| section.size);
+        let script = self
+            .get_blob(&blob_id.hash, 0..usize::MAX)
+            .await?
+            .and_then(|mut script| {
+                script.truncate(script_size);
+                (script_size > 0).then_some(script)
+            })
+            .ok_or_else(|| {
+                trc::StoreEvent::NotFound
+                    .into_err()
+                    .caused_by(trc::location!())
+                    .account_id(account_id)
+            })?;
+
+        std::str::from_utf8(&script)
+            .map_err(|err| err.to_string())
+            .and_then(parse_rules)
+            .map_err(|err| {
+                trc::StoreEvent::UnexpectedError
+                    .caused_by(trc::location!())
+                    .account_id(account_id)
+                    .reason(err)
+                    .details("Failed to parse mail rules Sieve script.")
+            })
+    }
+}
@@ -0,0 +1,782 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    object::Object,
+    types::{id::Id, property::Property, value::Value},
+};
+
+use crate::identity::set::sanitize_email;
+
+pub mod get;
+pub mod set;
+
+pub const RULES_SCRIPT_NAME: &str = "mailrules";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailRule {
+    pub name: String,
+    pub is_enabled: bool,
+    pub match_any: bool,
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+    pub stop: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCondition {
+    pub field: RuleField,
+    pub operator: RuleOperator,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleField {
+    From,
+    To,
+    Subject,
+    Header(String),
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOperator {
+    Is,
+    Contains,
+    Matches,
+    Over,
+    Under,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleAction {
+    Flag {
+        keyword: String,
+    },
+    Move {
+        mailbox_id: Id,
+    },
+    Forward {
+        email: String,
+        keep_copy: bool,
+    },
+    AutoReply {
+        subject: Option<String>,
+        text_body: String,
+    },
+    Discard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RuleTest {
+    True,
+    False,
+    AllOf(Vec<RuleTest>),
+    AnyOf(Vec<RuleTest>),
+    Condition(RuleCondition),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Tag(String),
+    Text(String),
+    Number(String),
+    Comment(String),
+    Char(u8),
+}
+
+impl MailRule {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let rule = value
+            .as_obj()
+            .ok_or_else(|| "Rules must be objects.".to_string())?;
+        let name = match property(rule, "name") {
+            Value::Text(name) if name.len() < 255 && !name.contains(['\r', '\n']) => {
+                name.trim().to_string()
+            }
+            Value::Null => String::new(),
+            _ => return Err("Invalid rule name.".to_string()),
+        };
+        let is_enabled = match property(rule, "isEnabled") {
+            Value::Bool(is_enabled) => *is_enabled,
+            Value::Null => true,
+            _ => return Err("Invalid isEnabled value.".to_string()),
+        };
+        let match_any = match property(rule, "match") {
+            Value::Text(value) if value == "any" => true,
+            Value::Text(value) if value == "all" => false,
+            Value::Null => false,
+            _ => return Err("Rule match must be either \"all\" or \"any\".".to_string()),
+        };
+        let stop = match property(rule, "stop") {
+            Value::Bool(stop) => *stop,
+            Value::Null => false,
+            _ => return Err("Invalid stop value.".to_string()),
+        };
+
+        let mut conditions = Vec::new();
+        for condition in list(rule, "conditions")? {
+            conditions.push(RuleCondition::parse(condition)?);
+        }
+        let mut actions = Vec::new();
+        for action in list(rule, "actions")? {
+            actions.push(RuleAction::parse(action)?);
+        }
+        if actions.is_empty() && !stop {
+            return Err("Rules must contain at least one action.".to_string());
+        }
+
+        // Flags have to be set before the message is filed or kept
+        actions.sort_by_key(|action| !matches!(action, RuleAction::Flag { .. }));
+
+        Ok(MailRule {
+            name,
+            is_enabled,
+            match_any: match_any && !conditions.is_empty(),
+            conditions,
+            actions,
+            stop,
+        })
+    }
+
+    pub fn into_value(self) -> Value {
+        Object::with_capacity(6)
+            .with_property(Property::Name, self.name)
+            .with_property(Property::IsEnabled, self.is_enabled)
+            .with_property(
+                Property::_T("match".to_string()),
+                if self.match_any { "any" } else { "all" },
+            )
+            .with_property(
+                Property::_T("conditions".to_string()),
+                Value::List(
+                    self.conditions
+                        .into_iter()
+                        .map(RuleCondition::into_value)
+                        .collect(),
+                ),
+            )
+            .with_property(
+                Property::_T("actions".to_string()),
+                Value::List(
+                    self.actions
+                        .into_iter()
+                        .map(RuleAction::into_value)
+                        .collect(),
+                ),
+            )
+            .with_property(Property::_T("stop".to_string()), self.stop)
+            .into()
+    }
+
+    fn from_test(name: String, test: RuleTest) -> Result<Self, String> {
+        // Disabled rules are compiled as 'allof (false, ...)'
+        let (is_enabled, test) = match test {
+            RuleTest::AllOf(mut tests) if tests.first() == Some(&RuleTest::False) => {
+                tests.remove(0);
+                (
+                    false,
+                    if tests.len() == 1 {
+                        tests.pop().unwrap()
+                    } else {
+                        RuleTest::AllOf(tests)
+                    },
+                )
+            }
+            test => (true, test),
+        };
+        let (match_any, tests) = match test {
+            RuleTest::True => (false, vec![]),
+            RuleTest::AllOf(tests) => (false, tests),
+            RuleTest::AnyOf(tests) => (true, tests),
+            test => (false, vec![test]),
+        };
+        let mut conditions = Vec::with_capacity(tests.len());
+        for test in tests {
+            if let RuleTest::Condition(condition) = test {
+                conditions.push(condition);
+            } else {
+                return Err("Nested tests are not supported.".to_string());
+            }
+        }
+
+        Ok(MailRule {
+            name,
+            is_enabled,
+            match_any,
+            conditions,
+            actions: vec![],
+            stop: false,
+        })
+    }
+}
+
+impl RuleCondition {
+    fn parse(value: &Value) -> Result<Self, String> {
+        let condition = value
+            .as_obj()
+            .ok_or_else(|| "Rule conditions must be objects.".to_string())?;
+        let field = match property(condition, "field").as_string() {
+            Some("from") => RuleField::From,
+            Some("to") => RuleField::To,
+            Some("subject") => RuleField::Subject,
+            Some("size") => RuleField::Size,
+            Some("header") => match property(condition, "header").as_string() {
+                Some(header)
+                    if !header.is_empty()
+                        && header.len() < 255
+                        && header
+                            .bytes()
+                            .all(|ch| ch.is_ascii_graphic() && ch != b':' && ch != b'"') =>
+                {
+                    if header.eq_ignore_ascii_case("subject") {
+                        RuleField::Subject
+                    } else {
+                        RuleField::Header(header.to_string())
+                    }
+                }
+                _ => return Err("Invalid or missing header name.".to_string()),
+            },
+            _ => return Err("Invalid or missing condition field.".to_string()),
+        };
+        let operator = match (property(condition, "operator").as_string(), &field) {
+            (Some("over"), RuleField::Size) => RuleOperator::Over,
+            (Some("under"), RuleField::Size) => RuleOperator::Under,
+            (Some("is"), field) if field != &RuleField::Size => RuleOperator::Is,
+            (Some("contains"), field) if field != &RuleField::Size => RuleOperator::Contains,
+            (Some("matches"), field) if field != &RuleField::Size => RuleOperator::Matches,
+            _ => return Err("Invalid or missing condition operator.".to_string()),
+        };
+        let value = match property(condition, "value").as_string() {
+            Some(value) if field == RuleField::Size => {
+                let value = value.to_ascii_uppercase();
+                let digits = value.trim_end_matches(['K', 'M', 'G']);
+                if digits.is_empty()
+                    || value.len() - digits.len() > 1
+                    || !digits.bytes().all(|ch| ch.is_ascii_digit())
+                {
+                    return Err(format!("Invalid size {value:?}."));
+                }
+                value
+            }
+            Some(value) if !value.is_empty() && value.len() < 1024 => value.to_string(),
+            _ => return Err("Invalid or missing condition value.".to_string()),
+        };
+
+        Ok(RuleCondition {
+            field,
+            operator,
+            value,
+        })
+    }
+
+    fn into_value(self) -> Value {
+        let mut condition = Object::with_capacity(4);
+        match self.field {
+            RuleField::From => condition.append(Property::_T("field".to_string()), "from"),
+            RuleField::To => condition.append(Property::_T("field".to_string()), "to"),
+            RuleField::Subject => condition.append(Property::_T("field".to_string()), "subject"),
+            RuleField::Size => condition.append(Property::_T("field".to_string()), "size"),
+            RuleField::Header(header) => {
+                condition.append(Property::_T("field".to_string()), "header");
+                condition.append(Property::_T("header".to_string()), header);
+            }
+        }
+        condition.append(Property::_T("operator".to_string()), self.operator.as_str());
+        condition.append(Property::Value, self.value);
+        condition.into()
+    }
+}
+
+impl RuleOperator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RuleOperator::Is => "is",
+            RuleOperator::Contains => "contains",
+            RuleOperator::Matches => "matches",
+            RuleOperator::Over => "over",
+            RuleOperator::Under => "under",
+        }
+    }
+}
+
+impl RuleAction {
+    fn parse(value: &Value) -> Result<Self, String> {
+        let action = value
+            .as_obj()
+            .ok_or_else(|| "Rule actions must be objects.".to_string())?;
+        match property(action, "type").as_string() {
+            Some("move") => property(action, "mailboxId")
+                .as_string()
+                .and_then(|id| Id::from_bytes(id.as_bytes()))
+                .map(|mailbox_id| RuleAction::Move { mailbox_id })
+                .ok_or_else(|| "Invalid or missing mailboxId.".to_string()),
+            Some("flag") => match property(action, "keyword").as_string() {
+                Some(keyword)
+                    if !keyword.is_empty()
+                        && keyword.len() < 255
+                        && keyword.bytes().all(|ch| ch.is_ascii_graphic()) =>
+                {
+                    Ok(RuleAction::Flag {
+                        keyword: keyword.to_string(),
+                    })
+                }
+                _ => Err("Invalid or missing keyword.".to_string()),
+            },
+            Some("forward") => Ok(RuleAction::Forward {
+                email: property(action, "email")
+                    .as_string()
+                    .and_then(sanitize_email)
+                    .ok_or_else(|| "Invalid or missing forwarding address.".to_string())?,
+                keep_copy: match property(action, "keepCopy") {
+                    Value::Bool(keep_copy) => *keep_copy,
+                    Value::Null => true,
+                    _ => return Err("Invalid keepCopy value.".to_string()),
+                },
+            }),
+            Some("autoReply") => Ok(RuleAction::AutoReply {
+                subject: match property(action, "subject") {
+                    Value::Text(subject) if subject.len() < 512 => {
+                        Some(subject.replace(['\r', '\n'], " "))
+                    }
+                    Value::Null => None,
+                    _ => return Err("Invalid auto-reply subject.".to_string()),
+                },
+                text_body: match property(action, "textBody") {
+                    Value::Text(text_body) if !text_body.is_empty() && text_body.len() < 2048 => {
+                        text_body.clone()
+                    }
+                    _ => return Err("Invalid or missing auto-reply textBody.".to_string()),
+                },
+            }),
+            Some("discard") => Ok(RuleAction::Discard),
+            _ => Err("Invalid or missing action type.".to_string()),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            RuleAction::Flag { keyword } => Object::with_capacity(2)
+                .with_property(Property::Type, "flag")
+                .with_property(Property::_T("keyword".to_string()), keyword),
+            RuleAction::Move { mailbox_id } => Object::with_capacity(2)
+                .with_property(Property::Type, "move")
+                .with_property(Property::_T("mailboxId".to_string()), mailbox_id),
+            RuleAction::Forward { email, keep_copy } => Object::with_capacity(3)
+                .with_property(Property::Type, "forward")
+                .with_property(Property::Email, email)
+                .with_property(Property::_T("keepCopy".to_string()), keep_copy),
+            RuleAction::AutoReply { subject, text_body } => Object::with_capacity(3)
+                .with_property(Property::Type, "autoReply")
+                .with_property(Property::Subject, subject.map_or(Value::Null, Value::Text))
+                .with_property(Property::TextBody, text_body),
+            RuleAction::Discard => {
+                Object::with_capacity(1).with_property(Property::Type, "discard")
+            }
+        }
+        .into()
+    }
+}
+
+pub fn compile_rules(rules: &[MailRule]) -> String {
+    let mut script = String::with_capacity(1024);
+    script.push_str("# Generated by MailRules/set, do not edit.\r\n");
+    script.push_str(concat!(
+        "require [\"fileinto\", \"mailbox\", \"mailboxid\", ",
+        "\"imap4flags\", \"copy\", \"vacation\"];\r\n"
+    ));
+
+    for rule in rules {
+        script.push_str("\r\n# rule: ");
+        script.push_str(&rule.name);
+        script.push_str("\r\nif ");
+
+        // Build test
+        let mut tests = Vec::with_capacity(rule.conditions.len() + 1);
+        if !rule.is_enabled {
+            tests.push("false".to_string());
+        }
+        if rule.match_any {
+            tests.push(format!(
+                "anyof ({})",
+                rule.conditions
+                    .iter()
+                    .map(compile_condition)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        } else if !rule.conditions.is_empty() {
+            tests.extend(rule.conditions.iter().map(compile_condition));
+        } else {
+            tests.push("true".to_string());
+        }
+        if tests.len() > 1 {
+            script.push_str("allof (");
+            script.push_str(&tests.join(", "));
+            script.push(')');
+        } else {
+            script.push_str(&tests[0]);
+        }
+        script.push_str(" {\r\n");
+
+        // Build actions
+        for action in &rule.actions {
+            script.push_str("    ");
+            match action {
+                RuleAction::Flag { keyword } => {
+                    script.push_str("addflag ");
+                    push_string(&mut script, keyword);
+                }
+                RuleAction::Move { mailbox_id } => {
+                    script.push_str("fileinto :mailboxid ");
+                    push_string(&mut script, &mailbox_id.to_string());
+                    script.push_str(" \"INBOX\"");
+                }
+                RuleAction::Forward { email, keep_copy } => {
+                    script.push_str(if *keep_copy {
+                        "redirect :copy "
+                    } else {
+                        "redirect "
+                    });
+                    push_string(&mut script, email);
+                }
+                RuleAction::AutoReply { subject, text_body } => {
+                    script.push_str("vacation ");
+                    if let Some(subject) = subject {
+                        script.push_str(":subject ");
+                        push_string(&mut script, subject);
+                        script.push(' ');
+                    }
+                    push_string(&mut script, text_body);
+                }
+                RuleAction::Discard => {
+                    script.push_str("discard");
+                }
+            }
+            script.push_str(";\r\n");
+        }
+        if rule.stop {
+            script.push_str("    stop;\r\n");
+        }
+        script.push_str("}\r\n");
+    }
+
+    script
+}
+
+fn compile_condition(condition: &RuleCondition) -> String {
+    let mut test = String::with_capacity(condition.value.len() + 32);
+    match &condition.field {
+        RuleField::Size => {
+            test.push_str("size :");
+            test.push_str(condition.operator.as_str());
+            test.push(' ');
+            test.push_str(&condition.value);
+            return test;
+        }
+        RuleField::From | RuleField::To => {
+            test.push_str("address :");
+        }
+        RuleField::Subject | RuleField::Header(_) => {
+            test.push_str("header :");
+        }
+    }
+    test.push_str(condition.operator.as_str());
+    test.push(' ');
+    push_string(
+        &mut test,
+        match &condition.field {
+            RuleField::From => "from",
+            RuleField::To => "to",
+            RuleField::Subject => "subject",
+            RuleField::Header(header) => header,
+            RuleField::Size => unreachable!(),
+        },
+    );
+    test.push(' ');
+    push_string(&mut test, &condition.value);
+    test
+}
+
+fn push_string(script: &mut String, value: &str) {
+    script.push('"');
+    for ch in value.chars() {
+        if ['\\', '"'].contains(&ch) {
+            script.push('\\');
+        }
+        script.push(ch);
+    }
+    script.push('"');
+}
+
+pub fn parse_rules(script: &str) -> Result<Vec<MailRule>, String> {
+    let mut parser = RuleParser {
+        tokens: tokenize(script)?,
+        pos: 0,
+    };
+    let mut rules = Vec::new();
+    let mut name = String::new();
+
+    while let Some(token) = parser.tokens.get(parser.pos).cloned() {
+        parser.pos += 1;
+        match token {
+            Token::Comment(comment) => {
+                if let Some(rule_name) = comment.strip_prefix("rule:") {
+                    name = rule_name.trim().to_string();
+                }
+            }
+            Token::Word(word) if word == "require" => while parser.next()? != Token::Char(b';') {},
+            Token::Word(word) if word == "if" => {
+                let test = parser.parse_test()?;
+                parser.expect(b'{')?;
+                let mut rule = MailRule::from_test(std::mem::take(&mut name), test)?;
+                parser.parse_actions(&mut rule)?;
+                rules.push(rule);
+            }
+            token => return Err(format!("Unexpected token {token:?}.")),
+        }
+    }
+
+    Ok(rules)
+}
+
+struct RuleParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl RuleParser {
+    fn next(&mut self) -> Result<Token, String> {
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            if !matches!(token, Token::Comment(_)) {
+                return Ok(token.clone());
+            }
+        }
+        Err("Unexpected end of script.".to_string())
+    }
+
+    fn expect(&mut self, ch: u8) -> Result<(), String> {
+        match self.next()? {
+            Token::Char(c) if c == ch => Ok(()),
+            token => Err(format!("Expected {:?}, found {token:?}.", ch as char)),
+        }
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Text(text) => Ok(text),
+            token => Err(format!("Expected string, found {token:?}.")),
+        }
+    }
+
+    fn parse_test(&mut self) -> Result<RuleTest, String> {
+        match self.next()? {
+            Token::Word(word) => match word.as_str() {
+                "true" => Ok(RuleTest::True),
+                "false" => Ok(RuleTest::False),
+                "allof" | "anyof" => {
+                    self.expect(b'(')?;
+                    let mut tests = vec![self.parse_test()?];
+                    loop {
+                        match self.next()? {
+                            Token::Char(b',') => tests.push(self.parse_test()?),
+                            Token::Char(b')') => break,
+                            token => return Err(format!("Unexpected token {token:?}.")),
+                        }
+                    }
+                    Ok(if word == "allof" {
+                        RuleTest::AllOf(tests)
+                    } else {
+                        RuleTest::AnyOf(tests)
+                    })
+                }
+                "address" | "header" | "size" => {
+                    let operator = match self.next()? {
+                        Token::Tag(tag) => match tag.as_str() {
+                            "is" => RuleOperator::Is,
+                            "contains" => RuleOperator::Contains,
+                            "matches" => RuleOperator::Matches,
+                            "over" => RuleOperator::Over,
+                            "under" => RuleOperator::Under,
+                            _ => return Err(format!("Unsupported tag {tag:?}.")),
+                        },
+                        token => return Err(format!("Unexpected token {token:?}.")),
+                    };
+                    let (field, value) = if word == "size" {
+                        match self.next()? {
+                            Token::Number(value) => (RuleField::Size, value),
+                            token => return Err(format!("Expected number, found {token:?}.")),
+                        }
+                    } else {
+                        let header = self.text()?;
+                        let field = match (word.as_str(), header.to_ascii_lowercase().as_str()) {
+                            ("address", "from") => RuleField::From,
+                            ("address", "to") => RuleField::To,
+                            ("header", "subject") => RuleField::Subject,
+                            ("header", _) => RuleField::Header(header),
+                            _ => return Err(format!("Unsupported address header {header:?}.")),
+                        };
+                        (field, self.text()?)
+                    };
+                    Ok(RuleTest::Condition(RuleCondition {
+                        field,
+                        operator,
+                        value,
+                    }))
+                }
+                _ => Err(format!("Unsupported test {word:?}.")),
+            },
+            token => Err(format!("Unexpected token {token:?}.")),
+        }
+    }
+
+    fn parse_actions(&mut self, rule: &mut MailRule) -> Result<(), String> {
+        loop {
+            let action = match self.next()? {
+                Token::Char(b'}') => return Ok(()),
+                Token::Word(word) => match word.as_str() {
+                    "addflag" => RuleAction::Flag {
+                        keyword: self.text()?,
+                    },
+                    "fileinto" => {
+                        let mut mailbox_id = None;
+                        loop {
+                            match self.next()? {
+                                Token::Tag(tag) if tag == "mailboxid" => {
+                                    mailbox_id = Id::from_bytes(self.text()?.as_bytes());
+                                }
+                                Token::Text(_) => break,
+                                token => return Err(format!("Unexpected token {token:?}.")),
+                            }
+                        }
+                        RuleAction::Move {
+                            mailbox_id: mailbox_id
+                                .ok_or_else(|| "Missing or invalid mailbox id.".to_string())?,
+                        }
+                    }
+                    "redirect" => {
+                        let mut keep_copy = false;
+                        let email = loop {
+                            match self.next()? {
+                                Token::Tag(tag) if tag == "copy" => keep_copy = true,
+                                Token::Text(email) => break email,
+                                token => return Err(format!("Unexpected token {token:?}.")),
+                            }
+                        };
+                        RuleAction::Forward { email, keep_copy }
+                    }
+                    "vacation" => {
+                        let mut subject = None;
+                        let text_body = loop {
+                            match self.next()? {
+                                Token::Tag(tag) if tag == "subject" => {
+                                    subject = self.text()?.into();
+                                }
+                                Token::Text(text_body) => break text_body,
+                                token => return Err(format!("Unexpected token {token:?}.")),
+                            }
+                        };
+                        RuleAction::AutoReply { subject, text_body }
+                    }
+                    "discard" => RuleAction::Discard,
+                    "stop" => {
+                        rule.stop = true;
+                        self.expect(b';')?;
+                        continue;
+                    }
+                    _ => return Err(format!("Unsupported action {word:?}.")),
+                },
+                token => return Err(format!("Unexpected token {token:?}.")),
+            };
+            self.expect(b';')?;
+            rule.actions.push(action);
+        }
+    }
+}
+
+fn tokenize(script: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let bytes = script.as_bytes();
+    let mut pos = 0;
+
+    while let Some(&ch) = bytes.get(pos) {
+        pos += 1;
+        match ch {
+            b'#' => {
+                let end = bytes[pos..]
+                    .iter()
+                    .position(|&ch| ch == b'\n')
+                    .map_or(bytes.len(), |end| pos + end);
+                tokens.push(Token::Comment(script[pos..end].trim().to_string()));
+                pos = end;
+            }
+            b'"' => {
+                let mut text = Vec::new();
+                loop {
+                    match bytes.get(pos) {
+                        Some(b'\\') => {
+                            text.push(*bytes.get(pos + 1).ok_or("Unterminated string.")?);
+                            pos += 2;
+                        }
+                        Some(b'"') => {
+                            pos += 1;
+                            break;
+                        }
+                        Some(&ch) => {
+                            text.push(ch);
+                            pos += 1;
+                        }
+                        None => return Err("Unterminated string.".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(
+                    String::from_utf8(text).map_err(|_| "Invalid UTF-8 string.".to_string())?,
+                ));
+            }
+            b':' | b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => {
+                let start = if ch == b':' { pos } else { pos - 1 };
+                while bytes
+                    .get(pos)
+                    .is_some_and(|ch| ch.is_ascii_alphanumeric() || *ch == b'_')
+                {
+                    pos += 1;
+                }
+                let word = script[start..pos].to_string();
+                tokens.push(match ch {
+                    b':' => Token::Tag(word.to_ascii_lowercase()),
+                    b'0'..=b'9' => Token::Number(word.to_ascii_uppercase()),
+                    _ => Token::Word(word.to_ascii_lowercase()),
+                });
+            }
+            b'(' | b')' | b'[' | b']' | b'{' | b'}' | b',' | b';' => {
+                tokens.push(Token::Char(ch));
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => {}
+            _ => return Err(format!("Unexpected character {:?}.", ch as char)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn property<'x>(object: &'x Object<Value>, name: &str) -> &'x Value {
+    object
+        .properties
+        .iter()
+        .find(|(property, _)| property.to_string() == name)
+        .map(|(_, value)| value)
+        .unwrap_or(&Value::Null)
+}
+
+fn list<'x>(object: &'x Object<Value>, name: &str) -> Result<&'x [Value], String> {
+    match property(object, name) {
+        Value::List(values) => Ok(values.as_slice()),
+        Value::Null => Ok(&[]),
+        _ => Err(format!("Property {name:?} must be a list.")),
+    }
+}
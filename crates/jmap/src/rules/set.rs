@@ -0,0 +1,353 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{index::ObjectIndexBuilder, Object},
+    response::references::EvalObjectReferences,
+    types::{
+        blob::BlobId,
+        collection::Collection,
+        id::Id,
+        property::Property,
+        value::{MaybePatchValue, Value},
+    },
+};
+use sieve::compiler::ErrorType;
+use std::future::Future;
+use store::write::{
+    assert::HashedValue,
+    log::{Changes, LogInsert},
+    BatchBuilder, BlobOp, DirectoryClass,
+};
+
+use crate::{
+    blob::upload::BlobUpload,
+    changes::write::ChangeLog,
+    mailbox::set::MailboxSet,
+    sieve::set::{ObjectBlobId, SieveScriptSet, SCHEMA},
+    JmapMethods,
+};
+
+use super::{compile_rules, get::MailRulesGet, MailRule, RuleAction, RULES_SCRIPT_NAME};
+
+pub trait MailRulesSet: Sync + Send {
+    fn mail_rules_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl MailRulesSet for Server {
+    async fn mail_rules_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let mut response = self
+            .prepare_set_response(&request, Collection::SieveScript)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+
+        // Process set or update requests
+        let mut create_id = None;
+        let mut changes = None;
+        match (request.create, request.update) {
+            (Some(create), Some(update)) if !create.is_empty() && !update.is_empty() => {
+                return Err(trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details("Creating and updating on the same request is not allowed."));
+            }
+            (Some(create), _) if !create.is_empty() => {
+                for (id, obj) in create {
+                    if will_destroy.contains(&Id::singleton()) {
+                        response.not_created.append(
+                            id,
+                            SetError::new(SetErrorType::WillDestroy)
+                                .with_description("ID will be destroyed."),
+                        );
+                    } else if create_id.is_some() {
+                        response.not_created.append(
+                            id,
+                            SetError::forbidden()
+                                .with_description("Only one object can be created."),
+                        );
+                    } else {
+                        create_id = Some(id);
+                        changes = Some(obj);
+                    }
+                }
+            }
+            (_, Some(update)) if !update.is_empty() => {
+                for (id, obj) in update {
+                    if id.is_singleton() {
+                        if !will_destroy.contains(&id) {
+                            changes = Some(obj);
+                        } else {
+                            response.not_updated.append(
+                                id,
+                                SetError::new(SetErrorType::WillDestroy)
+                                    .with_description("ID will be destroyed."),
+                            );
+                        }
+                    } else {
+                        response.not_updated.append(
+                            id,
+                            SetError::new(SetErrorType::NotFound).with_description("ID not found."),
+                        );
+                    }
+                }
+            }
+            _ => {
+                if will_destroy.is_empty() {
+                    return Ok(response);
+                }
+            }
+        }
+
+        // Prepare write batch
+        let mut batch = BatchBuilder::new();
+        let change_id = self.assign_change_id(account_id).await?;
+        batch
+            .with_change_id(change_id)
+            .with_account_id(account_id)
+            .with_collection(Collection::SieveScript);
+
+        // Process changes
+        if let Some(changes_) = changes {
+            // Parse properties
+            let mut changes = Object::with_capacity(changes_.properties.len());
+            let mut rules = None;
+
+            for (property, value) in changes_.properties {
+                let value = match response.eval_object_references(value) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        return Ok(set_error(response, create_id, err));
+                    }
+                };
+                match (&property, value) {
+                    (Property::IsEnabled, MaybePatchValue::Value(Value::Bool(value))) => {
+                        changes.append(Property::IsEnabled, value);
+                    }
+                    (Property::IsEnabled, MaybePatchValue::Value(Value::Null)) => {
+                        changes.append(Property::IsEnabled, false);
+                    }
+                    (Property::Rules, MaybePatchValue::Value(Value::List(values))) => {
+                        match values.iter().map(MailRule::parse).collect() {
+                            Ok(values) => {
+                                rules = Some(values);
+                            }
+                            Err(err) => {
+                                return Ok(set_error(
+                                    response,
+                                    create_id,
+                                    SetError::invalid_properties()
+                                        .with_property(property)
+                                        .with_description(err),
+                                ));
+                            }
+                        }
+                    }
+                    (Property::Rules, MaybePatchValue::Value(Value::Null)) => {
+                        rules = Some(vec![]);
+                    }
+                    _ => {
+                        return Ok(set_error(
+                            response,
+                            create_id,
+                            SetError::invalid_properties()
+                                .with_property(property)
+                                .with_description("Field could not be set."),
+                        ));
+                    }
+                }
+            }
+
+            // Validate mailboxes
+            if let Some(rules) = &rules {
+                let mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+                for action in rules.iter().flat_map(|rule| rule.actions.iter()) {
+                    if let RuleAction::Move { mailbox_id } = action {
+                        if !mailbox_ids.contains(mailbox_id.document_id()) {
+                            return Ok(set_error(
+                                response,
+                                create_id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::Rules)
+                                    .with_description(format!(
+                                        "Mailbox {mailbox_id} does not exist."
+                                    )),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            // Obtain current script
+            let document_id = self.get_mail_rules_script_id(account_id).await?;
+
+            // Add name, isActive and isEnabled
+            if document_id.is_none() {
+                changes.append(Property::Name, Value::Text(RULES_SCRIPT_NAME.into()));
+                changes.append(Property::IsActive, Value::Bool(false));
+                if !changes.properties.contains_key(&Property::IsEnabled) {
+                    changes.append(Property::IsEnabled, Value::Bool(true));
+                }
+                if rules.is_none() {
+                    rules = Some(vec![]);
+                }
+            }
+            let mut obj = ObjectIndexBuilder::new(SCHEMA)
+                .with_current_opt(if let Some(document_id) = document_id {
+                    self.get_property::<HashedValue<Object<Value>>>(
+                        account_id,
+                        Collection::SieveScript,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                    .ok_or_else(|| {
+                        trc::StoreEvent::NotFound
+                            .into_err()
+                            .caused_by(trc::location!())
+                    })?
+                    .into()
+                } else {
+                    None
+                })
+                .with_changes(changes);
+
+            // Update id
+            if let Some(document_id) = document_id {
+                batch
+                    .update_document(document_id)
+                    .log(Changes::update([document_id]));
+            } else {
+                batch.create_document().log(LogInsert());
+            }
+
+            // Compile rules into a Sieve script
+            if let Some(rules) = rules {
+                let script = compile_rules(&rules);
+                let mut script = script.into_bytes();
+                match self.core.sieve.untrusted_compiler.compile(&script) {
+                    Ok(compiled_script) => {
+                        obj.set(
+                            Property::BlobId,
+                            BlobId::default().with_section_size(script.len()).into(),
+                        );
+                        script.extend(bincode::serialize(&compiled_script).unwrap_or_default());
+                    }
+                    Err(err) => {
+                        return Ok(set_error(
+                            response,
+                            create_id,
+                            SetError::new(if let ErrorType::ScriptTooLong = &err.error_type() {
+                                SetErrorType::TooLarge
+                            } else {
+                                SetErrorType::InvalidProperties
+                            })
+                            .with_property(Property::Rules)
+                            .with_description(err.to_string()),
+                        ));
+                    }
+                }
+
+                // Upload new blob
+                let hash = self.put_blob(account_id, &script, false).await?.hash;
+                let blob_id = obj.changes_mut().unwrap().blob_id_mut().unwrap();
+                blob_id.hash = hash;
+                batch.set(
+                    BlobOp::Link {
+                        hash: blob_id.hash.clone(),
+                    },
+                    Vec::new(),
+                );
+
+                // Update quota
+                let script_size = blob_id.section.as_ref().unwrap().size as i64;
+                let mut quota = script_size;
+                if let Some(current) = obj.current() {
+                    let current_blob_id = current.inner.blob_id().ok_or_else(|| {
+                        trc::StoreEvent::NotFound
+                            .into_err()
+                            .caused_by(trc::location!())
+                            .document_id(document_id.unwrap_or(u32::MAX))
+                    })?;
+                    batch.clear(BlobOp::Link {
+                        hash: current_blob_id.hash.clone(),
+                    });
+                    quota -= current_blob_id.section.as_ref().unwrap().size as i64;
+                }
+                if quota != 0 {
+                    batch.add(DirectoryClass::UsedQuota(account_id), quota);
+
+                    // Update tenant quota
+                    #[cfg(feature = "enterprise")]
+                    if self.core.is_enterprise_edition() {
+                        if let Some(tenant) = resource_token.tenant {
+                            batch.add(DirectoryClass::UsedQuota(tenant.id), quota);
+                        }
+                    }
+                }
+            }
+
+            // Write changes
+            batch.custom(obj);
+            if !batch.is_empty() {
+                self.write_batch(batch).await?;
+                response.new_state = Some(change_id.into());
+            }
+
+            // Add result
+            if let Some(create_id) = create_id {
+                response.created.insert(
+                    create_id,
+                    Object::with_capacity(1).with_property(Property::Id, Id::singleton()),
+                );
+            } else {
+                response.updated.append(Id::singleton(), None);
+            }
+        } else if !will_destroy.is_empty() {
+            for id in will_destroy {
+                if id.is_singleton() {
+                    if let Some(document_id) = self.get_mail_rules_script_id(account_id).await? {
+                        self.sieve_script_delete(&resource_token, document_id, false)
+                            .await?;
+                        batch.log(Changes::delete([document_id]));
+                        response.destroyed.push(id);
+                        continue;
+                    }
+                }
+
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+
+            // Write changes
+            if !batch.is_empty() {
+                self.write_batch(batch).await?;
+                response.new_state = Some(change_id.into());
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+fn set_error(mut response: SetResponse, id: Option<String>, err: SetError) -> SetResponse {
+    if let Some(id) = id {
+        response.not_created.append(id, err);
+    } else {
+        response.not_updated.append(Id::singleton(), err);
+    }
+    response
+}
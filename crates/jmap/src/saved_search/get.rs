@@ -0,0 +1,128 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, keyword::Keyword, property::Property, value::Value},
+};
+use std::future::Future;
+
+use crate::{changes::state::StateManager, JmapMethods};
+
+use super::SavedSearchResults;
+
+pub trait SavedSearchGet: Sync + Send {
+    fn saved_search_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl SavedSearchGet for Server {
+    async fn saved_search_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Filter,
+            Property::TotalEmails,
+            Property::UnreadEmails,
+        ]);
+        let account_id = request.account_id.document_id();
+        let search_ids = self
+            .get_document_ids(account_id, Collection::SavedSearch)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            search_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::SavedSearch)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+        let fetch_results = properties
+            .iter()
+            .any(|p| matches!(p, Property::TotalEmails | Property::UnreadEmails));
+        let seen = if properties.contains(&Property::UnreadEmails) {
+            self.get_tag(
+                account_id,
+                Collection::Email,
+                Property::Keywords,
+                Keyword::Seen,
+            )
+            .await?
+            .unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        for id in ids {
+            // Obtain the saved search object
+            let document_id = id.document_id();
+            if !search_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut search = if let Some(search) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::SavedSearch,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                search
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+
+            // Run the search
+            let results = if fetch_results {
+                self.saved_search_results(account_id, &search, access_token)
+                    .await?
+            } else {
+                Default::default()
+            };
+
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::Name | Property::Filter => search.remove(property),
+                    Property::TotalEmails => Value::UnsignedInt(results.len()),
+                    Property::UnreadEmails => {
+                        Value::UnsignedInt(results.len() - results.intersection_len(&seen))
+                    }
+                    _ => Value::Null,
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    method::query::{parse_filter, Filter},
+    object::Object,
+    parser::{json::Parser, Ignore, Token},
+    types::{property::Property, value::Value},
+};
+use std::future::Future;
+use store::roaring::RoaringBitmap;
+
+use crate::email::query::EmailQuery;
+
+pub mod get;
+pub mod set;
+
+// Saved searches are exposed over IMAP as virtual mailboxes, their ids are
+// allocated from the upper half of the id space to avoid clashing with real mailboxes.
+pub const SAVED_SEARCH_MAILBOX_ID: u32 = 1 << 31;
+
+pub fn saved_search_mailbox_id(document_id: u32) -> u32 {
+    SAVED_SEARCH_MAILBOX_ID | document_id
+}
+
+pub fn saved_search_document_id(mailbox_id: u32) -> Option<u32> {
+    if mailbox_id & SAVED_SEARCH_MAILBOX_ID != 0 && mailbox_id != u32::MAX {
+        Some(mailbox_id & !SAVED_SEARCH_MAILBOX_ID)
+    } else {
+        None
+    }
+}
+
+pub trait SavedSearchResults: Sync + Send {
+    fn saved_search_results(
+        &self,
+        account_id: u32,
+        search: &Object<Value>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<RoaringBitmap>> + Send;
+}
+
+impl SavedSearchResults for Server {
+    async fn saved_search_results(
+        &self,
+        account_id: u32,
+        search: &Object<Value>,
+        access_token: &AccessToken,
+    ) -> trc::Result<RoaringBitmap> {
+        self.email_query_results(
+            account_id,
+            parse_saved_filter(search.get(&Property::Filter))?,
+            access_token,
+        )
+        .await
+        .map(|result_set| result_set.results)
+    }
+}
+
+pub fn parse_saved_filter(filter: &Value) -> trc::Result<Vec<Filter>> {
+    // Filters are stored in their JSON form and parsed by the Email/query parser
+    let filter = serde_json::to_string(filter).map_err(|err| {
+        trc::JmapEvent::InvalidArguments
+            .into_err()
+            .reason(err)
+            .details("Failed to serialize filter")
+    })?;
+    let mut parser = Parser::new(filter.as_bytes());
+
+    match parser.next_token::<Ignore>()? {
+        Token::DictStart => parse_filter(&mut parser),
+        Token::Null => Ok(vec![]),
+        token => Err(token.error("filter", "object or null")),
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::Object,
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        value::{MaybePatchValue, Value},
+    },
+};
+use std::future::Future;
+use store::write::{log::ChangeLogBuilder, BatchBuilder, F_CLEAR, F_VALUE};
+
+use crate::{changes::write::ChangeLog, JmapMethods};
+
+use super::SavedSearchResults;
+
+pub trait SavedSearchSet: Sync + Send {
+    fn saved_search_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl SavedSearchSet for Server {
+    async fn saved_search_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let mut search_ids = self
+            .get_document_ids(account_id, Collection::SavedSearch)
+            .await?
+            .unwrap_or_default();
+        let mut response = SetResponse::from_request(&request, self.core.jmap.set_max_objects)?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Obtain existing names, they are used as IMAP mailbox names and must be unique
+        let mut search_names = Vec::with_capacity(search_ids.len() as usize);
+        for (document_id, search) in self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::SavedSearch,
+                &search_ids,
+                Property::Value,
+            )
+            .await?
+        {
+            if let Some(name) = search.get(&Property::Name).as_string() {
+                search_names.push((document_id, name.to_string()));
+            }
+        }
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        'create: for (id, object) in request.unwrap_create() {
+            let mut search = Object::with_capacity(object.properties.len() + 1);
+
+            for (property, value) in object.properties {
+                match response
+                    .eval_object_references(value)
+                    .and_then(|value| validate_search_value(&property, value))
+                {
+                    Ok(value) => {
+                        search.set(property, value);
+                    }
+                    Err(err) => {
+                        response.not_created.append(id, err);
+                        continue 'create;
+                    }
+                }
+            }
+
+            if let Err(err) = self
+                .validate_search(account_id, &search, None, &search_names, access_token)
+                .await?
+            {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
+            // The UID validity of the virtual mailbox
+            search.set(
+                Property::Cid,
+                Value::UnsignedInt(rand::random::<u32>() as u64),
+            );
+
+            // Insert record
+            let name = search.get(&Property::Name).as_string().unwrap().to_string();
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::SavedSearch)
+                .create_document()
+                .value(Property::Value, search, F_VALUE);
+            let document_id = self.write_batch_expect_id(batch).await?;
+            search_ids.insert(document_id);
+            search_names.push((document_id, name));
+            changes.log_insert(Collection::SavedSearch, document_id);
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain saved search
+            let document_id = id.document_id();
+            let mut search = if let Some(search) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::SavedSearch,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                search
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            let mut filter_changed = false;
+            for (property, value) in object.properties {
+                match response
+                    .eval_object_references(value)
+                    .and_then(|value| validate_search_value(&property, value))
+                {
+                    Ok(value) => {
+                        filter_changed |= property == Property::Filter;
+                        search.set(property, value);
+                    }
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+            }
+
+            if let Err(err) = self
+                .validate_search(
+                    account_id,
+                    &search,
+                    document_id.into(),
+                    &search_names,
+                    access_token,
+                )
+                .await?
+            {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+
+            // Changing the filter invalidates the UIDs of the virtual mailbox
+            if filter_changed {
+                search.set(
+                    Property::Cid,
+                    Value::UnsignedInt(rand::random::<u32>() as u64),
+                );
+            }
+
+            // Update record
+            let name = search.get(&Property::Name).as_string().unwrap().to_string();
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::SavedSearch)
+                .update_document(document_id)
+                .value(Property::Value, search, F_VALUE);
+            self.write_batch(batch).await?;
+            if let Some((_, search_name)) = search_names
+                .iter_mut()
+                .find(|(search_id, _)| *search_id == document_id)
+            {
+                *search_name = name;
+            }
+            changes.log_update(Collection::SavedSearch, document_id);
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if search_ids.contains(document_id) {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::SavedSearch)
+                    .delete_document(document_id)
+                    .value(Property::Value, (), F_VALUE | F_CLEAR);
+                self.write_batch(batch).await?;
+                search_names.retain(|(search_id, _)| *search_id != document_id);
+                changes.log_delete(Collection::SavedSearch, document_id);
+                response.destroyed.push(id);
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+}
+
+trait ValidateSearch: Sync + Send {
+    fn validate_search(
+        &self,
+        account_id: u32,
+        search: &Object<Value>,
+        document_id: Option<u32>,
+        search_names: &[(u32, String)],
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<Result<(), SetError>>> + Send;
+}
+
+impl ValidateSearch for Server {
+    async fn validate_search(
+        &self,
+        account_id: u32,
+        search: &Object<Value>,
+        document_id: Option<u32>,
+        search_names: &[(u32, String)],
+        access_token: &AccessToken,
+    ) -> trc::Result<Result<(), SetError>> {
+        let name = if let Some(name) = search.get(&Property::Name).as_string() {
+            name
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::Name)
+                .with_description("Missing name.")));
+        };
+        if search_names
+            .iter()
+            .any(|(search_id, search_name)| Some(*search_id) != document_id && search_name == name)
+        {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::Name)
+                .with_description(format!(
+                    "A saved search named {name:?} already exists."
+                ))));
+        }
+        if !matches!(search.get(&Property::Filter), Value::Object(_)) {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::Filter)
+                .with_description("Missing filter.")));
+        }
+
+        // Make sure the filter can be executed by the query engine
+        match self
+            .saved_search_results(account_id, search, access_token)
+            .await
+        {
+            Ok(_) => Ok(Ok(())),
+            Err(err) if matches!(err.inner, trc::EventType::Jmap(_)) => {
+                Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::Filter)
+                    .with_description(
+                        err.value_as_str(trc::Key::Details)
+                            .unwrap_or("Invalid filter.")
+                            .to_string(),
+                    )))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn validate_search_value(property: &Property, value: MaybePatchValue) -> Result<Value, SetError> {
+    Ok(match (property, value) {
+        (Property::Name, MaybePatchValue::Value(Value::Text(value)))
+            if !value.trim().is_empty() && value.len() < 255 && !value.contains('/') =>
+        {
+            Value::Text(value.trim().to_string())
+        }
+        (Property::Filter, MaybePatchValue::Value(value @ Value::Object(_))) => value,
+
+        (property, _) => {
+            return Err(SetError::invalid_properties()
+                .with_property(property.clone())
+                .with_description("Field could not be set."));
+        }
+    })
+}
@@ -0,0 +1,667 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::Server;
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Type,
+};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::{
+    ahash::AHashMap,
+    roaring::RoaringBitmap,
+    write::{
+        key::DeserializeBigEndian, BatchBuilder, Bincode, BitmapClass, BlobOp, FtsQueueClass,
+        MaybeDynamicId, TagValue, ValueClass, F_CLEAR,
+    },
+    BitmapKey, BlobClass, IterateParams, Serialize, ValueKey, U32_LEN, U64_LEN,
+};
+use trc::AddContext;
+use utils::{codec::leb128::Leb128Reader, BlobHash};
+
+use crate::{
+    changes::write::ChangeLog,
+    email::metadata::MessageMetadata,
+    mailbox::{UidMailbox, TOMBSTONE_ID},
+    sieve::set::ObjectBlobId,
+    thread::muted_keyword,
+    JmapMethods,
+};
+
+use super::{index::Indexer, quota::QuotaRecalculation};
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckReport {
+    pub accounts: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckIssue {
+    pub account_id: u32,
+    #[serde(rename = "type")]
+    pub typ: FsckIssueType,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<u32>,
+    pub details: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FsckIssueType {
+    MissingMetadata,
+    MissingBlobLink,
+    MissingBlob,
+    UncommittedBlob,
+    MissingMailboxIds,
+    UnknownMailbox,
+    MailboxMembership,
+    MissingThreadId,
+    ThreadMembership,
+    MissingThread,
+    OrphanedThread,
+    QuotaMismatch,
+    MissingFtsIndex,
+}
+
+pub trait StoreCheck: Sync + Send {
+    fn fsck(
+        &self,
+        account_id: Option<u32>,
+        tenant_id: Option<u32>,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<FsckReport>> + Send;
+
+    fn fsck_account(
+        &self,
+        account_id: u32,
+        repair: bool,
+        fts_queued: &RoaringBitmap,
+    ) -> impl Future<Output = trc::Result<Vec<FsckIssue>>> + Send;
+
+    fn fsck_tags(
+        &self,
+        account_id: u32,
+        property: Property,
+    ) -> impl Future<Output = trc::Result<AHashMap<u32, RoaringBitmap>>> + Send;
+}
+
+impl StoreCheck for Server {
+    async fn fsck(
+        &self,
+        account_id: Option<u32>,
+        tenant_id: Option<u32>,
+        repair: bool,
+    ) -> trc::Result<FsckReport> {
+        let accounts = if let Some(account_id) = account_id {
+            vec![account_id]
+        } else {
+            self.core
+                .storage
+                .data
+                .list_principals(
+                    None,
+                    tenant_id,
+                    &[Type::Individual, Type::Group],
+                    &[PrincipalField::Name],
+                    0,
+                    0,
+                )
+                .await
+                .caused_by(trc::location!())?
+                .items
+                .into_iter()
+                .map(|principal| principal.id())
+                .collect()
+        };
+
+        // Obtain messages waiting to be indexed
+        let from_key = ValueKey::<ValueClass<u32>> {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::FtsQueue(FtsQueueClass {
+                seq: 0,
+                hash: BlobHash::default(),
+            }),
+        };
+        let to_key = ValueKey::<ValueClass<u32>> {
+            account_id: u32::MAX,
+            collection: u8::MAX,
+            document_id: u32::MAX,
+            class: ValueClass::FtsQueue(FtsQueueClass {
+                seq: u64::MAX,
+                hash: BlobHash::default(),
+            }),
+        };
+        let mut fts_queued: AHashMap<u32, RoaringBitmap> = AHashMap::new();
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(from_key, to_key).ascending().no_values(),
+                |key, _| {
+                    fts_queued
+                        .entry(key.deserialize_be_u32(U64_LEN)?)
+                        .or_default()
+                        .insert(key.deserialize_be_u32(U64_LEN + U32_LEN + 1)?);
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        let mut report = FsckReport::default();
+        let no_queued = RoaringBitmap::new();
+        for account_id in accounts {
+            report.issues.extend(
+                self.fsck_account(
+                    account_id,
+                    repair,
+                    fts_queued.get(&account_id).unwrap_or(&no_queued),
+                )
+                .await
+                .caused_by(trc::location!())?,
+            );
+            report.accounts += 1;
+        }
+
+        if repair
+            && report
+                .issues
+                .iter()
+                .any(|issue| issue.typ == FsckIssueType::MissingFtsIndex)
+        {
+            self.request_fts_index();
+        }
+
+        Ok(report)
+    }
+
+    async fn fsck_account(
+        &self,
+        account_id: u32,
+        repair: bool,
+        fts_queued: &RoaringBitmap,
+    ) -> trc::Result<Vec<FsckIssue>> {
+        let mut issues = Vec::new();
+        let mut issue =
+            |typ: FsckIssueType, document_id: Option<u32>, details: String, is_repairable: bool| {
+                issues.push(FsckIssue {
+                    account_id,
+                    typ,
+                    document_id,
+                    details,
+                    repaired: repair && is_repairable,
+                });
+            };
+        let mut batch = BatchBuilder::new();
+        batch.with_account_id(account_id);
+
+        let email_ids = self
+            .get_document_ids(account_id, Collection::Email)
+            .await?
+            .unwrap_or_default();
+        let tombstoned_ids = self
+            .get_tag(
+                account_id,
+                Collection::Email,
+                Property::MailboxIds,
+                TagValue::Id(TOMBSTONE_ID),
+            )
+            .await?
+            .unwrap_or_default();
+        let active_ids = &email_ids - &tombstoned_ids;
+
+        // Obtain message metadata
+        let mut email_hashes = AHashMap::new();
+        let mut blob_links = Vec::new();
+        for (document_id, metadata) in self
+            .get_properties::<Bincode<MessageMetadata>, _, _>(
+                account_id,
+                Collection::Email,
+                &email_ids,
+                Property::BodyStructure,
+            )
+            .await?
+        {
+            email_hashes.insert(document_id, metadata.inner.blob_hash.clone());
+            blob_links.push((Collection::Email, document_id, metadata.inner.blob_hash));
+        }
+        for document_id in &email_ids {
+            if !email_hashes.contains_key(&document_id) {
+                issue(
+                    FsckIssueType::MissingMetadata,
+                    document_id.into(),
+                    "Message metadata not found".to_string(),
+                    false,
+                );
+            }
+        }
+
+        // Obtain sieve script blobs
+        let script_ids = self
+            .get_document_ids(account_id, Collection::SieveScript)
+            .await?
+            .unwrap_or_default();
+        for (document_id, script) in self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::SieveScript,
+                &script_ids,
+                Property::Value,
+            )
+            .await?
+        {
+            if let Some(blob_id) = script.blob_id() {
+                blob_links.push((Collection::SieveScript, document_id, blob_id.hash.clone()));
+            }
+        }
+
+        // Verify blob links and blob contents
+        for (collection, document_id, hash) in blob_links {
+            if !self
+                .core
+                .storage
+                .data
+                .blob_has_access(
+                    &hash,
+                    BlobClass::Linked {
+                        account_id,
+                        collection: collection.into(),
+                        document_id,
+                    },
+                )
+                .await?
+            {
+                issue(
+                    FsckIssueType::MissingBlobLink,
+                    document_id.into(),
+                    format!("{collection} is not linked to blob {}", hash.to_hex()),
+                    true,
+                );
+                if repair {
+                    batch
+                        .with_collection(collection)
+                        .update_document(document_id)
+                        .set(BlobOp::Link { hash: hash.clone() }, Vec::new());
+                }
+            }
+
+            if self
+                .core
+                .storage
+                .blob
+                .get_blob(hash.as_slice(), 0..1)
+                .await?
+                .is_none()
+            {
+                issue(
+                    FsckIssueType::MissingBlob,
+                    document_id.into(),
+                    format!("Blob {} not found in blob store", hash.to_hex()),
+                    false,
+                );
+            } else if !self.core.storage.data.blob_exists(&hash).await? {
+                issue(
+                    FsckIssueType::UncommittedBlob,
+                    document_id.into(),
+                    format!("Blob {} is not marked as committed", hash.to_hex()),
+                    true,
+                );
+                if repair {
+                    batch.set(BlobOp::Commit { hash }, Vec::new());
+                }
+            }
+            commit_batch(self, &mut batch, account_id, Collection::Email).await?;
+        }
+
+        // Verify mailbox membership
+        let mailbox_ids = self
+            .get_document_ids(account_id, Collection::Mailbox)
+            .await?
+            .unwrap_or_default();
+        let mut expected_tags: AHashMap<u32, RoaringBitmap> = AHashMap::new();
+        let mut found_ids = RoaringBitmap::new();
+        for (document_id, mailboxes) in self
+            .get_properties::<Vec<UidMailbox>, _, _>(
+                account_id,
+                Collection::Email,
+                &active_ids,
+                Property::MailboxIds,
+            )
+            .await?
+        {
+            found_ids.insert(document_id);
+            for mailbox in mailboxes {
+                if !mailbox_ids.contains(mailbox.mailbox_id) {
+                    issue(
+                        FsckIssueType::UnknownMailbox,
+                        document_id.into(),
+                        format!("Mailbox {} does not exist", mailbox.mailbox_id),
+                        false,
+                    );
+                }
+                expected_tags
+                    .entry(mailbox.mailbox_id)
+                    .or_default()
+                    .insert(document_id);
+            }
+        }
+        for document_id in &active_ids - &found_ids {
+            issue(
+                FsckIssueType::MissingMailboxIds,
+                document_id.into(),
+                "Message does not belong to any mailbox".to_string(),
+                false,
+            );
+        }
+        let mut actual_tags = self
+            .fsck_tags(account_id, Property::MailboxIds)
+            .await
+            .caused_by(trc::location!())?;
+        actual_tags.remove(&TOMBSTONE_ID);
+        for (mailbox_id, missing, unexpected) in diff_tags(&expected_tags, &actual_tags) {
+            issue(
+                FsckIssueType::MailboxMembership,
+                None,
+                format!(
+                    "Mailbox {mailbox_id} bitmap is missing {} and has {} unexpected messages",
+                    missing.len(),
+                    unexpected.len()
+                ),
+                true,
+            );
+            if repair {
+                batch.with_collection(Collection::Email);
+                for (document_ids, options) in [(missing, 0), (unexpected, F_CLEAR)] {
+                    for document_id in document_ids {
+                        batch.update_document(document_id).tag(
+                            Property::MailboxIds,
+                            TagValue::Id(MaybeDynamicId::Static(mailbox_id)),
+                            options,
+                        );
+                        commit_batch(self, &mut batch, account_id, Collection::Email).await?;
+                    }
+                }
+            }
+        }
+
+        // Verify thread membership
+        let thread_ids = self
+            .get_document_ids(account_id, Collection::Thread)
+            .await?
+            .unwrap_or_default();
+        let mut expected_tags: AHashMap<u32, RoaringBitmap> = AHashMap::new();
+        let mut found_ids = RoaringBitmap::new();
+        for (document_id, thread_id) in self
+            .get_properties::<u32, _, _>(
+                account_id,
+                Collection::Email,
+                &active_ids,
+                Property::ThreadId,
+            )
+            .await?
+        {
+            found_ids.insert(document_id);
+            expected_tags
+                .entry(thread_id)
+                .or_default()
+                .insert(document_id);
+        }
+        for document_id in &active_ids - &found_ids {
+            issue(
+                FsckIssueType::MissingThreadId,
+                document_id.into(),
+                "Message does not belong to any thread".to_string(),
+                false,
+            );
+        }
+        let actual_tags = self
+            .fsck_tags(account_id, Property::ThreadId)
+            .await
+            .caused_by(trc::location!())?;
+        for (thread_id, missing, unexpected) in diff_tags(&expected_tags, &actual_tags) {
+            issue(
+                FsckIssueType::ThreadMembership,
+                None,
+                format!(
+                    "Thread {thread_id} bitmap is missing {} and has {} unexpected messages",
+                    missing.len(),
+                    unexpected.len()
+                ),
+                true,
+            );
+            if repair {
+                batch.with_collection(Collection::Email);
+                for (document_ids, options) in [(missing, 0), (unexpected, F_CLEAR)] {
+                    for document_id in document_ids {
+                        batch.update_document(document_id).tag(
+                            Property::ThreadId,
+                            TagValue::Id(MaybeDynamicId::Static(thread_id)),
+                            options,
+                        );
+                        commit_batch(self, &mut batch, account_id, Collection::Email).await?;
+                    }
+                }
+            }
+        }
+        for &thread_id in expected_tags.keys() {
+            if !thread_ids.contains(thread_id) {
+                issue(
+                    FsckIssueType::MissingThread,
+                    thread_id.into(),
+                    format!("Thread {thread_id} does not exist"),
+                    true,
+                );
+                if repair {
+                    batch
+                        .with_collection(Collection::Thread)
+                        .create_document_with_id(thread_id);
+                }
+            }
+            commit_batch(self, &mut batch, account_id, Collection::Thread).await?;
+        }
+        for thread_id in &thread_ids {
+            if !expected_tags.contains_key(&thread_id) {
+                issue(
+                    FsckIssueType::OrphanedThread,
+                    thread_id.into(),
+                    format!("Thread {thread_id} has no messages"),
+                    true,
+                );
+                if repair {
+                    batch
+                        .with_collection(Collection::Thread)
+                        .delete_document(thread_id)
+                        .tag(Property::Keywords, muted_keyword(), F_CLEAR);
+                }
+            }
+            commit_batch(self, &mut batch, account_id, Collection::Thread).await?;
+        }
+
+        // Verify quota
+        if let Some(usage) = self
+            .verify_used_quota(account_id, repair)
+            .await
+            .caused_by(trc::location!())?
+        {
+            if usage.stored != usage.actual {
+                issue(
+                    FsckIssueType::QuotaMismatch,
+                    None,
+                    format!(
+                        "Used quota is {} bytes but should be {} bytes",
+                        usage.stored, usage.actual
+                    ),
+                    !repair || usage.repaired,
+                );
+            }
+        }
+
+        // Verify full-text index coverage
+        if let Some(indexed_ids) = self
+            .core
+            .storage
+            .fts
+            .indexed_documents(account_id, Collection::Email.into())
+            .await?
+        {
+            let mut seq = self.generate_snowflake_id().caused_by(trc::location!())?;
+            for document_id in &active_ids - &indexed_ids - fts_queued {
+                let Some(hash) = email_hashes.remove(&document_id) else {
+                    continue;
+                };
+                issue(
+                    FsckIssueType::MissingFtsIndex,
+                    document_id.into(),
+                    "Message is not in the full-text index".to_string(),
+                    true,
+                );
+                if repair {
+                    batch
+                        .with_collection(Collection::Email)
+                        .update_document(document_id)
+                        .set(
+                            ValueClass::FtsQueue(FtsQueueClass { hash, seq }),
+                            0u64.serialize(),
+                        );
+                    seq += 1;
+                }
+                commit_batch(self, &mut batch, account_id, Collection::Email).await?;
+            }
+        }
+
+        if repair && !batch.is_empty() {
+            self.core
+                .storage
+                .data
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(issues)
+    }
+
+    async fn fsck_tags(
+        &self,
+        account_id: u32,
+        property: Property,
+    ) -> trc::Result<AHashMap<u32, RoaringBitmap>> {
+        // Obtain all tag ids for the property
+        let mut tag_ids = Vec::new();
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    BitmapKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        class: BitmapClass::Tag {
+                            field: property.clone().into(),
+                            value: TagValue::Id(0),
+                        },
+                        document_id: 0,
+                    },
+                    BitmapKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        class: BitmapClass::Tag {
+                            field: property.clone().into(),
+                            value: TagValue::Id(u32::MAX),
+                        },
+                        document_id: u32::MAX,
+                    },
+                )
+                .no_values(),
+                |key, _| {
+                    let (tag_id, _) = key
+                        .get(U32_LEN + 2..)
+                        .and_then(|bytes| bytes.read_leb128::<u32>())
+                        .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?;
+                    tag_ids.push(tag_id);
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        let mut tags = AHashMap::with_capacity(tag_ids.len());
+        for tag_id in tag_ids {
+            if let Some(document_ids) = self
+                .get_tag(
+                    account_id,
+                    Collection::Email,
+                    property.clone(),
+                    TagValue::Id(tag_id),
+                )
+                .await?
+            {
+                tags.insert(tag_id, document_ids);
+            }
+        }
+
+        Ok(tags)
+    }
+}
+
+const MAX_BATCH_SIZE: usize = 1000;
+
+// Repairs are committed in chunks to keep transactions within the store limits
+async fn commit_batch(
+    server: &Server,
+    batch: &mut BatchBuilder,
+    account_id: u32,
+    collection: Collection,
+) -> trc::Result<()> {
+    if batch.len() >= MAX_BATCH_SIZE {
+        server
+            .core
+            .storage
+            .data
+            .write(batch.build_batch())
+            .await
+            .caused_by(trc::location!())?;
+        batch
+            .with_account_id(account_id)
+            .with_collection(collection);
+    }
+
+    Ok(())
+}
+
+fn diff_tags(
+    expected: &AHashMap<u32, RoaringBitmap>,
+    actual: &AHashMap<u32, RoaringBitmap>,
+) -> Vec<(u32, RoaringBitmap, RoaringBitmap)> {
+    let empty = RoaringBitmap::new();
+    expected
+        .keys()
+        .chain(
+            actual
+                .keys()
+                .filter(|tag_id| !expected.contains_key(tag_id)),
+        )
+        .filter_map(|&tag_id| {
+            let expected_ids = expected.get(&tag_id).unwrap_or(&empty);
+            let actual_ids = actual.get(&tag_id).unwrap_or(&empty);
+            (expected_ids != actual_ids)
+                .then(|| (tag_id, expected_ids - actual_ids, actual_ids - expected_ids))
+        })
+        .collect()
+}
@@ -66,6 +66,7 @@ pub struct Peer {
     pub gen_config: GenerationId,
     pub gen_lists: GenerationId,
     pub gen_permissions: GenerationId,
+    pub gen_shards: GenerationId,
     pub state: State,
 
     // Heartbeat state
@@ -84,6 +85,7 @@ pub struct PeerStatus {
     pub gen_config: GenerationId,
     pub gen_lists: GenerationId,
     pub gen_permissions: GenerationId,
+    pub gen_shards: GenerationId,
 }
 
 impl From<&Peer> for PeerStatus {
@@ -94,6 +96,7 @@ impl From<&Peer> for PeerStatus {
             gen_config: peer.gen_config,
             gen_lists: peer.gen_lists,
             gen_permissions: peer.gen_permissions,
+            gen_shards: peer.gen_shards,
         }
     }
 }
@@ -114,6 +117,13 @@ impl From<&Gossiper> for PeerStatus {
                 .data
                 .permissions_version
                 .load(Ordering::Relaxed),
+            gen_shards: cluster
+                .inner
+                .shared_core
+                .load()
+                .storage
+                .data
+                .shard_version(),
         }
     }
 }
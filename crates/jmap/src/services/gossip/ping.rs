@@ -103,6 +103,7 @@ impl Gossiper {
         let mut update_config = false;
         let mut update_lists = false;
         let mut update_permissions = false;
+        let mut update_shards = false;
 
         'outer: for (pos, peer) in peers.into_iter().enumerate() {
             if peer.addr == self.addr {
@@ -152,6 +153,18 @@ impl Gossiper {
                                     update_permissions = true;
                                 }
                             }
+                            if local_peer.gen_shards != peer.gen_shards {
+                                local_peer.gen_shards = peer.gen_shards;
+                                if local_peer.hb_sum > 0 {
+                                    trc::event!(
+                                        Cluster(ClusterEvent::PeerHasChanges),
+                                        RemoteIp = peer.addr,
+                                        Details = "shards"
+                                    );
+
+                                    update_shards = true;
+                                }
+                            }
                         }
 
                         continue 'outer;
@@ -179,6 +192,14 @@ impl Gossiper {
         if update_permissions {
             self.inner.data.permissions.clear();
         }
+        if update_shards {
+            self.inner
+                .shared_core
+                .load()
+                .storage
+                .data
+                .invalidate_shard_cache();
+        }
 
         if update_config || update_lists {
             let server = self.inner.build_server();
@@ -57,6 +57,7 @@ impl Request {
                 gen_config: it.next().copied()?,
                 gen_lists: it.next().copied()?,
                 gen_permissions: it.next().copied()?,
+                gen_shards: it.next().copied()?,
             });
         }
         match flags & !(1 << 7) {
@@ -109,6 +110,7 @@ impl Request {
             bytes.push(peer.gen_config);
             bytes.push(peer.gen_lists);
             bytes.push(peer.gen_permissions);
+            bytes.push(peer.gen_shards);
         }
 
         bytes
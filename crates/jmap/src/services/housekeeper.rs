@@ -29,7 +29,13 @@ use tokio::sync::mpsc;
 use trc::{Collector, MetricType};
 use utils::map::ttl_dashmap::TtlMap;
 
-use crate::{email::delete::EmailDeletion, JmapMethods, LONG_SLUMBER};
+use crate::{
+    email::{delete::EmailDeletion, snooze::EmailSnooze},
+    import_task::run::EmailImportTaskRun,
+    JmapMethods, LONG_SLUMBER,
+};
+
+use super::{quota::QuotaRecalculation, sync::DirectorySynchronization};
 
 #[derive(PartialEq, Eq)]
 struct Action {
@@ -41,8 +47,11 @@ struct Action {
 enum ActionClass {
     Session,
     Account,
+    Quota,
+    Snooze,
     Store(usize),
     Acme(String),
+    DirectorySync(String),
     OtelMetrics,
     #[cfg(feature = "enterprise")]
     InternalMetrics,
@@ -83,6 +92,20 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                 ActionClass::Account,
             );
 
+            // Quota recalculation
+            if let Some(frequency) = &server.core.jmap.quota_recalculate_frequency {
+                queue.schedule(
+                    Instant::now() + frequency.time_to_next(),
+                    ActionClass::Quota,
+                );
+            }
+
+            // Snoozed messages
+            queue.schedule(
+                Instant::now() + server.core.jmap.mail_snooze_interval,
+                ActionClass::Snooze,
+            );
+
             // Store purges
             for (idx, schedule) in server.core.storage.purge_schedules.iter().enumerate() {
                 queue.schedule(
@@ -91,6 +114,16 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                 );
             }
 
+            // Directory synchronizations
+            for (id, directory) in &server.core.storage.directories {
+                if let Some(sync) = &directory.sync {
+                    queue.schedule(
+                        Instant::now() + sync.frequency.time_to_next(),
+                        ActionClass::DirectorySync(id.clone()),
+                    );
+                }
+            }
+
             // OTEL Push Metrics
             if let Some(otel) = &server.core.metrics.otel {
                 OtelMetrics::enable_errors();
@@ -100,6 +133,16 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
             // Calculate expensive metrics
             queue.schedule(Instant::now(), ActionClass::CalculateMetrics);
 
+            // Fail import tasks interrupted by a restart
+            {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = server.email_import_task_recover().await {
+                        trc::error!(err.details("Failed to recover import tasks"));
+                    }
+                });
+            }
+
             // Add all ACME renewals to heap
             for provider in server.core.acme.providers.values() {
                 match server.init_acme(provider).await {
@@ -168,6 +211,19 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                             _ => {}
                         }
 
+                        // Schedule new directory synchronizations
+                        for (id, directory) in &server.core.storage.directories {
+                            if let Some(sync) = &directory.sync {
+                                let action = ActionClass::DirectorySync(id.clone());
+                                if !queue.has_action(&action) {
+                                    queue.schedule(
+                                        Instant::now() + sync.frequency.time_to_next(),
+                                        action,
+                                    );
+                                }
+                            }
+                        }
+
                         // SPDX-SnippetBegin
                         // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
                         // SPDX-License-Identifier: LicenseRef-SEL
@@ -390,6 +446,78 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                     server.purge_accounts().await;
                                 });
                             }
+                            ActionClass::Quota => {
+                                if let Some(frequency) =
+                                    &server.core.jmap.quota_recalculate_frequency
+                                {
+                                    queue.schedule(
+                                        Instant::now() + frequency.time_to_next(),
+                                        ActionClass::Quota,
+                                    );
+                                }
+
+                                let server = server.clone();
+                                tokio::spawn(async move {
+                                    trc::event!(Housekeeper(
+                                        trc::HousekeeperEvent::RecalculateQuotas
+                                    ));
+                                    if let Err(err) = server
+                                        .recalculate_quotas(
+                                            None,
+                                            None,
+                                            server.core.jmap.quota_recalculate_repair,
+                                        )
+                                        .await
+                                    {
+                                        trc::error!(err.details("Failed to recalculate quotas."));
+                                    }
+                                });
+                            }
+                            ActionClass::Snooze => {
+                                queue.schedule(
+                                    Instant::now() + server.core.jmap.mail_snooze_interval,
+                                    ActionClass::Snooze,
+                                );
+
+                                let server = server.clone();
+                                tokio::spawn(async move {
+                                    trc::event!(Housekeeper(trc::HousekeeperEvent::WakeSnoozed));
+                                    if let Err(err) = server.wake_snoozed().await {
+                                        trc::error!(
+                                            err.details("Failed to wake up snoozed messages.")
+                                        );
+                                    }
+                                });
+                            }
+                            ActionClass::DirectorySync(directory_id) => {
+                                if let Some(sync) = server
+                                    .core
+                                    .storage
+                                    .directories
+                                    .get(&directory_id)
+                                    .and_then(|directory| directory.sync.as_ref())
+                                {
+                                    queue.schedule(
+                                        Instant::now() + sync.frequency.time_to_next(),
+                                        ActionClass::DirectorySync(directory_id.clone()),
+                                    );
+
+                                    let server = server.clone();
+                                    tokio::spawn(async move {
+                                        trc::event!(
+                                            Housekeeper(trc::HousekeeperEvent::SyncDirectory),
+                                            Id = directory_id.clone()
+                                        );
+                                        if let Err(err) =
+                                            server.synchronize_directory(&directory_id, None).await
+                                        {
+                                            trc::error!(
+                                                err.details("Failed to synchronize directory.")
+                                            );
+                                        }
+                                    });
+                                }
+                            }
                             ActionClass::Session => {
                                 let server = server.clone();
                                 queue.schedule(
@@ -437,6 +565,9 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                             PurgeStore::Lookup(lookup_store) => {
                                                 ("lookup", lookup_store.purge_lookup_store().await)
                                             }
+                                            PurgeStore::Tiered(tiered_store) => {
+                                                ("tiered", tiered_store.offload_blobs().await)
+                                            }
                                         };
 
                                         match result {
@@ -6,11 +6,13 @@
 
 use common::{
     ipc::{DeliveryResult, IngestMessage},
+    listener::stream::NullIo,
     Server,
 };
 use directory::Permission;
 use jmap_proto::types::{state::StateChange, type_state::DataType};
 use mail_parser::MessageParser;
+use smtp::core::{Session, SessionAddress};
 use std::future::Future;
 use store::ahash::AHashMap;
 
@@ -27,6 +29,15 @@ pub trait MailDelivery: Sync + Send {
         &self,
         message: IngestMessage,
     ) -> impl Future<Output = Vec<DeliveryResult>> + Send;
+
+    fn forward_message(
+        &self,
+        raw_message: &[u8],
+        sender: &str,
+        rcpt: &str,
+        forward_to: Vec<String>,
+        session_id: u64,
+    ) -> impl Future<Output = ()> + Send;
 }
 
 impl MailDelivery for Server {
@@ -95,6 +106,8 @@ impl MailDelivery for Server {
 
         // Deliver to each recipient
         for (uid, (status, rcpt)) in &mut deliver_names {
+            let mut forward_to = Vec::new();
+
             // Obtain access token
             let result = match self.get_cached_access_token(*uid).await.and_then(|token| {
                 token
@@ -102,6 +115,10 @@ impl MailDelivery for Server {
                     .map(|_| token)
             }) {
                 Ok(access_token) => {
+                    if self.core.jmap.self_service.forwarding_enabled {
+                        forward_to.clone_from(&access_token.forward_to);
+                    }
+
                     // Check if there is an active sieve script
                     match self.sieve_script_get_active(*uid).await {
                         Ok(Some(active_script)) => {
@@ -150,6 +167,18 @@ impl MailDelivery for Server {
                         )
                         .await;
                     }
+
+                    // Forward a copy of the message
+                    if !forward_to.is_empty() {
+                        self.forward_message(
+                            &raw_message,
+                            &message.sender_address,
+                            rcpt,
+                            forward_to,
+                            message.session_id,
+                        )
+                        .await;
+                    }
                 }
                 Err(err) => {
                     match err.as_ref() {
@@ -243,4 +272,61 @@ impl MailDelivery for Server {
             })
             .collect()
     }
+
+    async fn forward_message(
+        &self,
+        raw_message: &[u8],
+        sender: &str,
+        rcpt: &str,
+        forward_to: Vec<String>,
+        session_id: u64,
+    ) {
+        // Avoid forwarding loops
+        let is_loop = MessageParser::new()
+            .parse_headers(raw_message)
+            .is_some_and(|message| {
+                message.headers().iter().any(|header| {
+                    header.name.as_str().eq_ignore_ascii_case("Delivered-To")
+                        && header
+                            .value
+                            .as_text()
+                            .is_some_and(|value| value.trim().eq_ignore_ascii_case(rcpt))
+                })
+            });
+        let recipients = forward_to
+            .into_iter()
+            .filter(|address| !address.eq_ignore_ascii_case(rcpt))
+            .map(SessionAddress::new)
+            .collect::<Vec<_>>();
+        if is_loop || recipients.is_empty() {
+            return;
+        }
+
+        trc::event!(
+            MessageIngest(trc::MessageIngestEvent::Forward),
+            From = sender.to_string(),
+            To = recipients
+                .iter()
+                .map(|r| trc::Value::String(r.address_lcase.clone()))
+                .collect::<Vec<_>>(),
+            Size = raw_message.len(),
+            SpanId = session_id
+        );
+
+        let mut message = Vec::with_capacity(raw_message.len() + rcpt.len() + 16);
+        message.extend_from_slice(b"Delivered-To: ");
+        message.extend_from_slice(rcpt.as_bytes());
+        message.extend_from_slice(b"\r\n");
+        message.extend_from_slice(raw_message);
+
+        Session::<NullIo>::sieve(
+            self.clone(),
+            SessionAddress::new(sender.to_string()),
+            recipients,
+            message,
+            session_id,
+        )
+        .queue_message()
+        .await;
+    }
 }
@@ -5,8 +5,11 @@
  */
 
 pub mod delivery;
+pub mod fsck;
 pub mod gossip;
 pub mod housekeeper;
 pub mod index;
 pub mod ingest;
+pub mod quota;
 pub mod state;
+pub mod sync;
@@ -0,0 +1,327 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::Server;
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Type,
+};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::{
+    ahash::AHashMap,
+    write::{BatchBuilder, Bincode, DirectoryClass},
+};
+use trc::AddContext;
+
+use crate::{email::metadata::MessageMetadata, sieve::set::ObjectBlobId, JmapMethods};
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaReport {
+    pub accounts: u64,
+    pub tenants: u64,
+    pub mismatches: Vec<QuotaMismatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaMismatch {
+    pub id: u32,
+    #[serde(rename = "type")]
+    pub typ: QuotaMismatchType,
+    pub stored: i64,
+    pub actual: i64,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotaMismatchType {
+    Account,
+    Tenant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsedQuota {
+    pub stored: i64,
+    pub actual: i64,
+    pub repaired: bool,
+}
+
+const MAX_QUOTA_ATTEMPTS: usize = 3;
+
+pub trait QuotaRecalculation: Sync + Send {
+    fn recalculate_quotas(
+        &self,
+        account_id: Option<u32>,
+        tenant_id: Option<u32>,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<QuotaReport>> + Send;
+
+    fn verify_used_quota(
+        &self,
+        account_id: u32,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<Option<UsedQuota>>> + Send;
+
+    fn calculate_used_quota(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<i64>> + Send;
+}
+
+impl QuotaRecalculation for Server {
+    async fn recalculate_quotas(
+        &self,
+        account_id: Option<u32>,
+        tenant_id: Option<u32>,
+        repair: bool,
+    ) -> trc::Result<QuotaReport> {
+        let mut report = QuotaReport::default();
+        let mut batch = BatchBuilder::new();
+
+        // Recalculate account quotas
+        let account_ids = if let Some(account_id) = account_id {
+            vec![account_id]
+        } else {
+            list_principal_ids(self, tenant_id, &[Type::Individual, Type::Group]).await?
+        };
+        let mut used_quotas = AHashMap::with_capacity(account_ids.len());
+        for account_id in account_ids {
+            let Some(usage) = self
+                .verify_used_quota(account_id, repair)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                // Accounts being purged are counted with their stored quota
+                used_quotas.insert(account_id, self.get_used_quota(account_id).await?);
+                continue;
+            };
+            used_quotas.insert(account_id, usage.actual);
+            report.accounts += 1;
+
+            if usage.stored != usage.actual {
+                report.mismatches.push(QuotaMismatch {
+                    id: account_id,
+                    typ: QuotaMismatchType::Account,
+                    stored: usage.stored,
+                    actual: usage.actual,
+                    repaired: usage.repaired,
+                });
+            }
+        }
+
+        // Tenant quotas are the sum of the quotas used by their members,
+        // so they can only be verified when all accounts were recalculated
+        if account_id.is_none() {
+            let tenant_ids = if let Some(tenant_id) = tenant_id {
+                vec![tenant_id]
+            } else {
+                list_principal_ids(self, None, &[Type::Tenant]).await?
+            };
+            for tenant_id in tenant_ids {
+                let actual =
+                    list_principal_ids(self, tenant_id.into(), &[Type::Individual, Type::Group])
+                        .await?
+                        .into_iter()
+                        .filter_map(|account_id| used_quotas.get(&account_id))
+                        .sum::<i64>();
+                let stored = self.get_used_quota(tenant_id).await?;
+                report.tenants += 1;
+
+                if stored != actual {
+                    report.mismatches.push(QuotaMismatch {
+                        id: tenant_id,
+                        typ: QuotaMismatchType::Tenant,
+                        stored,
+                        actual,
+                        repaired: repair,
+                    });
+                    if repair {
+                        batch.add(DirectoryClass::UsedQuota(tenant_id), actual - stored);
+                    }
+                }
+            }
+        }
+
+        for mismatch in &report.mismatches {
+            trc::event!(
+                Store(trc::StoreEvent::QuotaMismatch),
+                AccountId = mismatch.id,
+                Type = match mismatch.typ {
+                    QuotaMismatchType::Account => "account",
+                    QuotaMismatchType::Tenant => "tenant",
+                },
+                Size = mismatch.stored,
+                Total = mismatch.actual,
+                Result = mismatch.repaired,
+            );
+        }
+
+        if !batch.is_empty() {
+            self.core
+                .storage
+                .data
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(report)
+    }
+
+    async fn verify_used_quota(
+        &self,
+        account_id: u32,
+        repair: bool,
+    ) -> trc::Result<Option<UsedQuota>> {
+        // Hold the account lock so tombstoned messages are not purged meanwhile
+        let lock_key = format!("purge:{account_id}").into_bytes();
+        match self
+            .core
+            .storage
+            .lookup
+            .counter_incr(lock_key.clone(), 1, Some(3600), true)
+            .await
+            .caused_by(trc::location!())?
+        {
+            1 => (),
+            count => {
+                trc::event!(
+                    Purge(trc::PurgeEvent::PurgeActive),
+                    AccountId = account_id,
+                    Total = count,
+                );
+                return Ok(None);
+            }
+        }
+
+        let result = verify_used_quota_locked(self, account_id, repair).await;
+
+        if let Err(err) = self.core.storage.lookup.counter_delete(lock_key).await {
+            trc::error!(err.details("Failed to delete lock.").account_id(account_id));
+        }
+
+        result.map(Some)
+    }
+
+    async fn calculate_used_quota(&self, account_id: u32) -> trc::Result<i64> {
+        // Tombstoned messages still count towards the quota
+        let mut used_quota = 0;
+        let email_ids = self
+            .get_document_ids(account_id, Collection::Email)
+            .await?
+            .unwrap_or_default();
+        for (_, metadata) in self
+            .get_properties::<Bincode<MessageMetadata>, _, _>(
+                account_id,
+                Collection::Email,
+                &email_ids,
+                Property::BodyStructure,
+            )
+            .await?
+        {
+            used_quota += metadata.inner.size as i64;
+        }
+
+        // Sieve scripts are also part of the quota
+        let script_ids = self
+            .get_document_ids(account_id, Collection::SieveScript)
+            .await?
+            .unwrap_or_default();
+        for (_, script) in self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::SieveScript,
+                &script_ids,
+                Property::Value,
+            )
+            .await?
+        {
+            used_quota += script
+                .blob_id()
+                .and_then(|blob_id| blob_id.section.as_ref())
+                .map_or(0, |section| section.size as i64);
+        }
+
+        Ok(used_quota)
+    }
+}
+
+async fn verify_used_quota_locked(
+    server: &Server,
+    account_id: u32,
+    repair: bool,
+) -> trc::Result<UsedQuota> {
+    // Messages may be delivered or deleted while the quota is being calculated,
+    // only trust the result if the stored quota did not change in the meantime
+    let mut stored = server.get_used_quota(account_id).await?;
+    for _ in 0..MAX_QUOTA_ATTEMPTS {
+        let actual = server
+            .calculate_used_quota(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let stored_after = server.get_used_quota(account_id).await?;
+        if stored_after != stored {
+            stored = stored_after;
+            continue;
+        }
+
+        let repaired = repair && stored != actual;
+        if repaired {
+            let mut batch = BatchBuilder::new();
+            batch.add(DirectoryClass::UsedQuota(account_id), actual - stored);
+            server
+                .core
+                .storage
+                .data
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        return Ok(UsedQuota {
+            stored,
+            actual,
+            repaired,
+        });
+    }
+
+    // The account is too busy to obtain a consistent snapshot, report without repairing
+    Ok(UsedQuota {
+        stored,
+        actual: server
+            .calculate_used_quota(account_id)
+            .await
+            .caused_by(trc::location!())?,
+        repaired: false,
+    })
+}
+
+async fn list_principal_ids(
+    server: &Server,
+    tenant_id: Option<u32>,
+    types: &[Type],
+) -> trc::Result<Vec<u32>> {
+    server
+        .core
+        .storage
+        .data
+        .list_principals(None, tenant_id, types, &[PrincipalField::Name], 0, 0)
+        .await
+        .caused_by(trc::location!())
+        .map(|list| {
+            list.items
+                .into_iter()
+                .map(|principal| principal.id())
+                .collect()
+        })
+}
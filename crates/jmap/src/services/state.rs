@@ -308,6 +308,7 @@ pub fn spawn_state_manager(inner: Arc<Inner>, mut change_rx: mpsc::Receiver<Stat
                                     id: Id::from_parts(account_id, verified.id),
                                     url: verified.url,
                                     keys: verified.keys,
+                                    email_summaries: verified.email_summaries,
                                 });
                             }
                         }
@@ -0,0 +1,115 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+use common::Server;
+use directory::{
+    backend::internal::manage,
+    core::sync::{SyncAction, SyncReport},
+    Type,
+};
+use trc::AddContext;
+
+const SYNC_LOCK_EXPIRY: u64 = 3600;
+
+pub trait DirectorySynchronization: Sync + Send {
+    fn synchronize_directory(
+        &self,
+        directory_id: &str,
+        dry_run: Option<bool>,
+    ) -> impl Future<Output = trc::Result<SyncReport>> + Send;
+}
+
+impl DirectorySynchronization for Server {
+    async fn synchronize_directory(
+        &self,
+        directory_id: &str,
+        dry_run: Option<bool>,
+    ) -> trc::Result<SyncReport> {
+        let directory = self
+            .core
+            .storage
+            .directories
+            .get(directory_id)
+            .ok_or_else(|| manage::not_found(directory_id.to_string()))?;
+        let dry_run =
+            dry_run.unwrap_or_else(|| directory.sync.as_ref().is_some_and(|sync| sync.dry_run));
+
+        // Only one node at a time may apply changes
+        let lock_key = format!("dsync-lock:{directory_id}").into_bytes();
+        if !dry_run {
+            match self
+                .core
+                .storage
+                .lookup
+                .counter_incr(lock_key.clone(), 1, Some(SYNC_LOCK_EXPIRY), true)
+                .await
+                .caused_by(trc::location!())?
+            {
+                1 => (),
+                _ => {
+                    return Err(manage::error(
+                        "Directory synchronization already in progress",
+                        Some(directory_id.to_string()),
+                    ));
+                }
+            }
+        }
+        let result = directory.synchronize(dry_run).await;
+        if !dry_run {
+            if let Err(err) = self.core.storage.lookup.counter_delete(lock_key).await {
+                trc::error!(err
+                    .details("Failed to delete lock.")
+                    .caused_by(trc::location!()));
+            }
+        }
+        let report = result?;
+
+        if !report.dry_run && !report.changes.is_empty() {
+            for change in &report.changes {
+                let Some(account_id) = change.id else {
+                    continue;
+                };
+
+                self.inner.data.access_tokens.remove(&account_id);
+
+                // SPDX-SnippetBegin
+                // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+                // SPDX-License-Identifier: LicenseRef-SEL
+
+                #[cfg(feature = "enterprise")]
+                if change.action == SyncAction::Create {
+                    self.assign_principal_shard(account_id)
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+
+                // SPDX-SnippetEnd
+
+                if change.action == SyncAction::Delete {
+                    if matches!(change.typ, Type::Individual | Type::Group) {
+                        self.core
+                            .storage
+                            .fts
+                            .remove_all(account_id)
+                            .await
+                            .caused_by(trc::location!())?;
+                    }
+                    self.inner
+                        .data
+                        .http_auth_cache
+                        .retain(|_, id| id.item != account_id);
+                }
+            }
+
+            // Update nested and dynamic group cache
+            self.directory().groups.clear();
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+use std::future::Future;
+use utils::map::bitmap::Bitmap;
+
+use crate::{changes::state::StateManager, mailbox::get::mailbox_rights, JmapMethods};
+
+pub trait ShareNotificationGet: Sync + Send {
+    fn share_notification_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl ShareNotificationGet for Server {
+    async fn share_notification_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Created,
+            Property::ChangedBy,
+            Property::ObjectType,
+            Property::ObjectAccountId,
+            Property::ObjectId,
+            Property::OldRights,
+            Property::NewRights,
+            Property::Name,
+        ]);
+        let account_id = request.account_id.document_id();
+        let notification_ids = self
+            .get_document_ids(account_id, Collection::ShareNotification)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            notification_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::ShareNotification)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the notification object
+            let document_id = id.document_id();
+            if !notification_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut notification = if let Some(notification) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::ShareNotification,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                notification
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::Created => match notification.remove(property) {
+                        Value::UnsignedInt(created) => {
+                            Value::Date(UTCDate::from_timestamp(created as i64))
+                        }
+                        _ => Value::Null,
+                    },
+                    Property::OldRights | Property::NewRights => {
+                        match notification.remove(property) {
+                            Value::UnsignedInt(rights) => mailbox_rights(&Bitmap::from(rights)),
+                            _ => Value::Null,
+                        }
+                    }
+                    property => notification.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
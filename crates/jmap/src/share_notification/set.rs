@@ -0,0 +1,239 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    types::{
+        collection::Collection,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{AclGrant, Value},
+    },
+};
+use std::future::Future;
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder};
+
+use crate::{changes::write::ChangeLog, services::state::StateManager, JmapMethods};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Created).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::ObjectType).index_as(IndexAs::Text {
+        tokenize: false,
+        index: true,
+    }),
+    IndexProperty::new(Property::ObjectAccountId).index_as(IndexAs::Integer),
+];
+
+pub struct SharedObject<'x> {
+    pub account_id: u32,
+    pub document_id: u32,
+    pub object_type: DataType,
+    pub name: Option<&'x str>,
+}
+
+pub trait ShareNotificationSet: Sync + Send {
+    fn share_notification_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+
+    fn share_notification_create(
+        &self,
+        access_token: &AccessToken,
+        object: SharedObject<'_>,
+        old_acl: &[AclGrant],
+        new_acl: &[AclGrant],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl ShareNotificationSet for Server {
+    async fn share_notification_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let notification_ids = self
+            .get_document_ids(account_id, Collection::ShareNotification)
+            .await?
+            .unwrap_or_default();
+        let mut response = SetResponse::from_request(&request, self.core.jmap.set_max_objects)?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Share notifications are created by the server and are immutable
+        for (id, _) in request.unwrap_create() {
+            response.not_created.append(
+                id,
+                SetError::forbidden()
+                    .with_description("Share notifications cannot be created by clients."),
+            );
+        }
+        for (id, _) in request.unwrap_update() {
+            response.not_updated.append(
+                id,
+                SetError::forbidden().with_description("Share notifications cannot be modified."),
+            );
+        }
+
+        // Process deletions
+        let mut changes = ChangeLogBuilder::new();
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if !notification_ids.contains(document_id) {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            }
+
+            if let Some(notification) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::ShareNotification,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::ShareNotification)
+                    .delete_document(document_id)
+                    .custom(ObjectIndexBuilder::new(SCHEMA).with_current(notification));
+                self.write_batch(batch).await?;
+                changes.log_delete(Collection::ShareNotification, document_id);
+                response.destroyed.push(id);
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            response.new_state = Some(change_id.into());
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::ShareNotification, change_id)
+                .into();
+        }
+
+        Ok(response)
+    }
+
+    async fn share_notification_create(
+        &self,
+        access_token: &AccessToken,
+        object: SharedObject<'_>,
+        old_acl: &[AclGrant],
+        new_acl: &[AclGrant],
+    ) -> trc::Result<()> {
+        // Obtain the grantees whose rights have changed
+        let mut grantees = Vec::new();
+        for grant in new_acl {
+            let old_grants = old_acl
+                .iter()
+                .find(|item| item.account_id == grant.account_id)
+                .map(|item| item.grants);
+            if old_grants != Some(grant.grants) {
+                grantees.push((grant.account_id, old_grants, Some(grant.grants)));
+            }
+        }
+        for grant in old_acl {
+            if !new_acl
+                .iter()
+                .any(|item| item.account_id == grant.account_id)
+            {
+                grantees.push((grant.account_id, Some(grant.grants), None));
+            }
+        }
+
+        let changed_by = Object::with_capacity(3)
+            .with_property(
+                Property::Name,
+                access_token
+                    .description
+                    .as_ref()
+                    .unwrap_or(&access_token.name)
+                    .clone(),
+            )
+            .with_property(
+                Property::Email,
+                access_token
+                    .emails
+                    .first()
+                    .map(|email| Value::Text(email.clone()))
+                    .unwrap_or_default(),
+            )
+            .with_property(
+                Property::PrincipalId,
+                Value::Id(Id::from(access_token.primary_id())),
+            );
+        let created = now();
+
+        for (grantee_id, old_grants, new_grants) in grantees {
+            if grantee_id == access_token.primary_id() {
+                continue;
+            }
+
+            let notification = Object::with_capacity(8)
+                .with_property(Property::Created, Value::UnsignedInt(created))
+                .with_property(Property::ChangedBy, changed_by.clone())
+                .with_property(
+                    Property::ObjectType,
+                    Value::Text(object.object_type.to_string()),
+                )
+                .with_property(
+                    Property::ObjectAccountId,
+                    Value::Id(Id::from(object.account_id)),
+                )
+                .with_property(Property::ObjectId, Value::Id(Id::from(object.document_id)))
+                .with_property(
+                    Property::OldRights,
+                    old_grants
+                        .map(|grants| Value::UnsignedInt(grants.bitmap))
+                        .unwrap_or_default(),
+                )
+                .with_property(
+                    Property::NewRights,
+                    new_grants
+                        .map(|grants| Value::UnsignedInt(grants.bitmap))
+                        .unwrap_or_default(),
+                )
+                .with_property(
+                    Property::Name,
+                    object
+                        .name
+                        .map(|name| Value::Text(name.to_string()))
+                        .unwrap_or_default(),
+                );
+
+            // Store the notification in the grantee's account
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(grantee_id)
+                .with_collection(Collection::ShareNotification)
+                .create_document()
+                .custom(ObjectIndexBuilder::new(SCHEMA).with_changes(notification));
+            let document_id = self.write_batch_expect_id(batch).await?;
+
+            let mut changes = ChangeLogBuilder::new();
+            changes.log_insert(Collection::ShareNotification, document_id);
+            let change_id = self.commit_changes(grantee_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(grantee_id).with_change(DataType::ShareNotification, change_id),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ use store::{
 use crate::{
     blob::{download::BlobDownload, upload::BlobUpload},
     changes::state::StateManager,
+    rules::{get::MailRulesGet, RULES_SCRIPT_NAME},
     sieve::SeenIds,
     JmapMethods,
 };
@@ -148,7 +149,7 @@ impl SieveScriptGet for Server {
 
     async fn sieve_script_get_active(&self, account_id: u32) -> trc::Result<Option<ActiveScript>> {
         // Find the currently active script
-        if let Some(document_id) = self
+        let active_id = self
             .filter(
                 account_id,
                 Collection::SieveScript,
@@ -156,32 +157,101 @@ impl SieveScriptGet for Server {
             )
             .await?
             .results
-            .min()
-        {
-            let (script, mut script_object) =
-                self.sieve_script_compile(account_id, document_id).await?;
-            Ok(Some(ActiveScript {
-                document_id,
-                script: Arc::new(script),
-                script_name: script_object
-                    .properties
-                    .remove(&Property::Name)
-                    .and_then(|name| name.try_unwrap_string())
-                    .unwrap_or_else(|| account_id.to_string()),
-                seen_ids: self
-                    .get_property::<Bincode<SeenIds>>(
+            .min();
+
+        // Mail rules run before the active script
+        let mut rules_id = None;
+        if let Some(document_id) = self.get_mail_rules_script_id(account_id).await? {
+            if active_id != Some(document_id)
+                && self
+                    .get_property::<Object<Value>>(
                         account_id,
                         Collection::SieveScript,
                         document_id,
-                        Property::EmailIds,
+                        Property::Value,
                     )
                     .await?
-                    .map(|seen_ids| seen_ids.inner)
-                    .unwrap_or_default(),
-            }))
-        } else {
-            Ok(None)
+                    .is_some_and(|obj| obj.get(&Property::IsEnabled) == &Value::Bool(true))
+            {
+                rules_id = Some(document_id);
+            }
         }
+
+        let (document_id, script_name, script) = match (active_id, rules_id) {
+            (Some(document_id), None) => {
+                let (script, mut script_object) =
+                    self.sieve_script_compile(account_id, document_id).await?;
+                (
+                    document_id,
+                    script_object
+                        .properties
+                        .remove(&Property::Name)
+                        .and_then(|name| name.try_unwrap_string())
+                        .unwrap_or_else(|| account_id.to_string()),
+                    script,
+                )
+            }
+            (active_id, Some(rules_id)) => {
+                // Include both the mail rules and the active script
+                let mut script = format!(
+                    "require \"include\";\r\ninclude :personal \"{RULES_SCRIPT_NAME}\";\r\n"
+                );
+                if let Some(name) = active_id.map(|document_id| {
+                    self.get_property::<Object<Value>>(
+                        account_id,
+                        Collection::SieveScript,
+                        document_id,
+                        Property::Value,
+                    )
+                }) {
+                    if let Some(Value::Text(name)) = name
+                        .await?
+                        .and_then(|mut obj| obj.properties.remove(&Property::Name))
+                    {
+                        script.push_str("include :personal \"");
+                        for ch in name.chars() {
+                            if ['\\', '"'].contains(&ch) {
+                                script.push('\\');
+                            }
+                            script.push(ch);
+                        }
+                        script.push_str("\";\r\n");
+                    }
+                }
+
+                (
+                    active_id.unwrap_or(rules_id),
+                    account_id.to_string(),
+                    self.core
+                        .sieve
+                        .untrusted_compiler
+                        .compile(script.as_bytes())
+                        .map_err(|err| {
+                            trc::StoreEvent::UnexpectedError
+                                .caused_by(trc::location!())
+                                .reason(err)
+                                .details("Failed to compile Sieve script")
+                        })?,
+                )
+            }
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(ActiveScript {
+            document_id,
+            script: Arc::new(script),
+            script_name,
+            seen_ids: self
+                .get_property::<Bincode<SeenIds>>(
+                    account_id,
+                    Collection::SieveScript,
+                    document_id,
+                    Property::EmailIds,
+                )
+                .await?
+                .map(|seen_ids| seen_ids.inner)
+                .unwrap_or_default(),
+        }))
     }
 
     async fn sieve_script_get_by_name(
@@ -41,6 +41,7 @@ use crate::{
     api::http::HttpSessionData,
     blob::{download::BlobDownload, upload::BlobUpload},
     changes::write::ChangeLog,
+    rules::RULES_SCRIPT_NAME,
     JmapMethods,
 };
 use std::future::Future;
@@ -464,6 +465,15 @@ impl SieveScriptSet for Server {
             ))));
         }
 
+        // Mail rules script cannot be modified
+        if matches!(update.as_ref().and_then(|(_, obj)| obj.inner.properties.get(&Property::Name)), Some(Value::Text ( value )) if value.eq_ignore_ascii_case(RULES_SCRIPT_NAME))
+        {
+            return Ok(Err(SetError::forbidden().with_description(concat!(
+                "The 'mailrules' script cannot be modified, ",
+                "use MailRules/set instead."
+            ))));
+        }
+
         // Parse properties
         let mut changes = Object::with_capacity(changes_.properties.len());
         let mut blob_id = None;
@@ -486,6 +496,12 @@ impl SieveScriptSet for Server {
                             .with_description(
                                 "The 'vacation' name is reserved, please use a different name.",
                             )));
+                    } else if value.eq_ignore_ascii_case(RULES_SCRIPT_NAME) {
+                        return Ok(Err(SetError::forbidden()
+                            .with_property(property)
+                            .with_description(
+                                "The 'mailrules' name is reserved, please use a different name.",
+                            )));
                     } else if update
                         .as_ref()
                         .and_then(|(_, obj)| obj.inner.properties.get(&Property::Name))
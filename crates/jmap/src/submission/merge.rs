@@ -0,0 +1,379 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{auth::AccessToken, listener::ServerInstance, Server};
+use directory::Permission;
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::{
+        merge::{MergeSubmissionRequest, MergeSubmissionResponse},
+        set::SetResponse,
+    },
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        collection::Collection,
+        id::Id,
+        keyword::Keyword,
+        property::Property,
+        state::{State, StateChange},
+        type_state::DataType,
+        value::{SetValue, Value},
+    },
+};
+use mail_builder::{mime::make_boundary, MessageBuilder};
+use mail_parser::MessageParser;
+use store::write::{log::ChangeLogBuilder, BatchBuilder};
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    changes::{state::StateManager, write::ChangeLog},
+    email::ingest::{EmailIngest, IngestEmail, IngestSource},
+    identity::set::sanitize_email,
+    mailbox::{get::MailboxGet, set::MailboxSet},
+    template::render_template,
+    JmapMethods,
+};
+
+use super::set::{EmailSubmissionSet, SCHEMA};
+use std::future::Future;
+
+pub trait EmailSubmissionMerge: Sync + Send {
+    fn email_submission_merge(
+        &self,
+        request: MergeSubmissionRequest,
+        access_token: &AccessToken,
+        instance: &Arc<ServerInstance>,
+        session_id: u64,
+    ) -> impl Future<Output = trc::Result<MergeSubmissionResponse>> + Send;
+}
+
+impl EmailSubmissionMerge for Server {
+    async fn email_submission_merge(
+        &self,
+        request: MergeSubmissionRequest,
+        access_token: &AccessToken,
+        instance: &Arc<ServerInstance>,
+        session_id: u64,
+    ) -> trc::Result<MergeSubmissionResponse> {
+        // Merged messages are submitted for delivery
+        if !access_token.has_permission(Permission::EmailSend)
+            || !access_token.has_permission(Permission::JmapEmailSubmissionSet)
+        {
+            return Err(trc::JmapEvent::Forbidden
+                .into_err()
+                .details("You are not authorized to send messages"));
+        }
+
+        let account_id = request.account_id.document_id();
+        if request.recipients.len() > self.core.jmap.set_max_objects {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+
+        // Obtain template
+        let template_id = request.template_id.unwrap();
+        let template = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::EmailTemplate,
+                template_id.document_id(),
+                Property::Value,
+            )
+            .await?
+            .ok_or_else(|| {
+                trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details(format!("Template {template_id} not found."))
+            })?;
+
+        // Obtain identity
+        let identity_id = request.identity_id.unwrap();
+        let mut identity = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::Identity,
+                identity_id.document_id(),
+                Property::Value,
+            )
+            .await?
+            .ok_or_else(|| {
+                trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details(format!("Identity {identity_id} not found."))
+            })?;
+        let from_email = identity
+            .remove(&Property::Email)
+            .try_unwrap_string()
+            .unwrap_or_default();
+        let from_name = identity
+            .remove(&Property::Name)
+            .try_unwrap_string()
+            .unwrap_or_default();
+        let from_domain = from_email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_string())
+            .unwrap_or_default();
+
+        // Validate mailboxes, storing sent messages in the Sent folder by default
+        let valid_mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        let mailbox_ids = if !request.mailbox_ids.is_empty() {
+            let mut mailbox_ids = Vec::with_capacity(request.mailbox_ids.len());
+            for mailbox_id in request.mailbox_ids {
+                let mailbox_id = mailbox_id.unwrap().document_id();
+                if !valid_mailbox_ids.contains(mailbox_id) {
+                    return Err(trc::JmapEvent::InvalidArguments
+                        .into_err()
+                        .details(format!("Mailbox {} does not exist.", Id::from(mailbox_id))));
+                }
+                mailbox_ids.push(mailbox_id);
+            }
+            mailbox_ids
+        } else if let Some(mailbox_id) = self.mailbox_get_by_role(account_id, "sent").await? {
+            vec![mailbox_id]
+        } else {
+            return Err(trc::JmapEvent::InvalidArguments
+                .into_err()
+                .details("No mailboxIds specified and no Sent mailbox found."));
+        };
+
+        // Scheduled messages are held in the queue using FUTURERELEASE
+        let hold_until = request
+            .send_at
+            .map(|send_at| send_at.timestamp())
+            .filter(|send_at| *send_at > 0);
+
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let mut response = MergeSubmissionResponse {
+            account_id: request.account_id,
+            new_state: State::Initial,
+            created: VecMap::with_capacity(request.recipients.len()),
+            not_created: VecMap::new(),
+            state_change: None,
+        };
+        let mut changes = ChangeLogBuilder::new();
+        let mut email_change_id = None;
+        let set_response = SetResponse::default();
+
+        for (id, recipient) in request.recipients {
+            let rcpt_email = if let Some(rcpt_email) = sanitize_email(&recipient.email) {
+                rcpt_email
+            } else {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::Email)
+                        .with_description(format!("Invalid e-mail address {:?}.", recipient.email)),
+                );
+                continue;
+            };
+            let rcpt_name = recipient.name.as_deref().unwrap_or_default();
+
+            // Render template, recipient variables take precedence over global ones
+            let variables = |name: &str| {
+                recipient
+                    .variables
+                    .get(name)
+                    .or_else(|| request.variables.get(name))
+                    .map(|value| value.as_str())
+                    .or(match name {
+                        "email" => Some(rcpt_email.as_str()),
+                        "name" => Some(rcpt_name),
+                        _ => None,
+                    })
+            };
+            let render = |property: Property, escape_html: bool| match template.get(&property) {
+                Value::Text(text) => render_template(text, variables, escape_html)
+                    .map(Some)
+                    .map_err(|err| (property, err)),
+                _ => Ok(None),
+            };
+            let (subject, text_body, html_body) = match (
+                render(Property::Subject, false),
+                render(Property::TextBody, false),
+                render(Property::HtmlBody, true),
+            ) {
+                (Ok(subject), Ok(text_body), Ok(html_body)) => (
+                    subject.unwrap_or_default().replace(['\r', '\n'], " "),
+                    text_body,
+                    html_body,
+                ),
+                (Err((property, err)), _, _)
+                | (_, Err((property, err)), _)
+                | (_, _, Err((property, err))) => {
+                    response.not_created.append(
+                        id,
+                        SetError::invalid_properties()
+                            .with_property(property)
+                            .with_description(err.to_string()),
+                    );
+                    continue;
+                }
+            };
+
+            // Build message
+            let mut builder = MessageBuilder::new()
+                .from((from_name.as_str(), from_email.as_str()))
+                .to((rcpt_name, rcpt_email.as_str()))
+                .subject(subject)
+                .message_id(format!("{}@{}", make_boundary("."), from_domain));
+            if let Some(text_body) = text_body {
+                builder = builder.text_body(text_body);
+            }
+            if let Some(html_body) = html_body {
+                builder = builder.html_body(html_body);
+            }
+            let raw_message = builder.write_to_vec().unwrap_or_default();
+            if raw_message.len() > self.core.jmap.mail_max_size {
+                response.not_created.append(
+                    id,
+                    SetError::new(SetErrorType::InvalidEmail).with_description(format!(
+                        "Message exceeds maximum size of {} bytes.",
+                        self.core.jmap.mail_max_size
+                    )),
+                );
+                continue;
+            }
+
+            // Store a copy of the message
+            let email = match self
+                .email_ingest(IngestEmail {
+                    raw_message: &raw_message,
+                    message: MessageParser::new().parse(&raw_message),
+                    resource: resource_token.clone(),
+                    mailbox_ids: mailbox_ids.clone(),
+                    keywords: vec![Keyword::Seen],
+                    received_at: None,
+                    source: IngestSource::Jmap,
+                    encrypt: self.core.jmap.encrypt && self.core.jmap.encrypt_append,
+                    session_id,
+                })
+                .await
+            {
+                Ok(email) => {
+                    email_change_id = Some(email.change_id);
+                    email
+                }
+                Err(mut err) => match err.as_ref() {
+                    trc::EventType::Limit(trc::LimitEvent::Quota) => {
+                        response.not_created.append(
+                            id,
+                            SetError::new(SetErrorType::OverQuota)
+                                .with_description("You have exceeded your disk quota."),
+                        );
+                        continue;
+                    }
+                    trc::EventType::MessageIngest(trc::MessageIngestEvent::Error) => {
+                        response.not_created.append(
+                            id,
+                            SetError::new(SetErrorType::InvalidEmail).with_description(
+                                err.take_value(trc::Key::Reason)
+                                    .and_then(|v| v.into_string())
+                                    .unwrap(),
+                            ),
+                        );
+                        continue;
+                    }
+                    _ => {
+                        return Err(err);
+                    }
+                },
+            };
+
+            // Submit message
+            let mut mail_from = Object::with_capacity(2)
+                .with_property(Property::Email, Value::Text(from_email.clone()));
+            if let Some(hold_until) = hold_until {
+                mail_from.append(
+                    Property::Parameters,
+                    Object::with_capacity(1).with_property(
+                        Property::_T("HOLDUNTIL".to_string()),
+                        Value::Text(hold_until.to_string()),
+                    ),
+                );
+            }
+            let envelope = Object::with_capacity(2)
+                .with_property(Property::MailFrom, mail_from)
+                .with_property(
+                    Property::RcptTo,
+                    Value::List(vec![Value::Object(
+                        Object::with_capacity(1).with_property(Property::Email, rcpt_email),
+                    )]),
+                );
+            let submission = Object {
+                properties: VecMap::from_iter([
+                    (Property::EmailId, SetValue::Value(Value::Id(email.id))),
+                    (
+                        Property::IdentityId,
+                        SetValue::Value(Value::Id(identity_id)),
+                    ),
+                    (Property::Envelope, SetValue::Value(Value::Object(envelope))),
+                ]),
+            };
+            match self
+                .send_message(account_id, &set_response, instance, submission)
+                .await?
+            {
+                Ok(submission) => {
+                    // Held messages remain in the queue and can still be canceled
+                    let undo_status = match submission.get(&Property::UndoStatus) {
+                        Value::Text(status) if status == "final" && hold_until.is_some() => {
+                            Value::Text("pending".to_string())
+                        }
+                        status => status.clone(),
+                    };
+                    let submission_status =
+                        (undo_status, submission.get(&Property::SendAt).clone());
+
+                    // Insert record
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::EmailSubmission)
+                        .create_document()
+                        .custom(ObjectIndexBuilder::new(SCHEMA).with_changes(submission));
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    changes.log_insert(Collection::EmailSubmission, document_id);
+                    response.created.append(
+                        id,
+                        Object::with_capacity(5)
+                            .with_property(Property::Id, Id::from(document_id))
+                            .with_property(Property::EmailId, email.id)
+                            .with_property(Property::ThreadId, Id::from(email.id.prefix_id()))
+                            .with_property(Property::UndoStatus, submission_status.0)
+                            .with_property(Property::SendAt, submission_status.1),
+                    );
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        let mut state_change = StateChange::new(account_id);
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            response.new_state = change_id.into();
+            state_change = state_change.with_change(DataType::EmailSubmission, change_id);
+        } else {
+            response.new_state = self
+                .get_state(account_id, Collection::EmailSubmission)
+                .await?;
+        }
+        if let Some(change_id) = email_change_id {
+            state_change = state_change
+                .with_change(DataType::Email, change_id)
+                .with_change(DataType::Mailbox, change_id)
+                .with_change(DataType::Thread, change_id);
+        }
+        if state_change.has_changes() {
+            response.state_change = state_change.into();
+        }
+
+        Ok(response)
+    }
+}
@@ -5,5 +5,6 @@
  */
 
 pub mod get;
+pub mod merge;
 pub mod query;
 pub mod set;
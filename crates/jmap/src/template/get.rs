@@ -0,0 +1,95 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use std::future::Future;
+
+use crate::{changes::state::StateManager, JmapMethods};
+
+pub trait EmailTemplateGet: Sync + Send {
+    fn email_template_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl EmailTemplateGet for Server {
+    async fn email_template_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Subject,
+            Property::TextBody,
+            Property::HtmlBody,
+        ]);
+        let account_id = request.account_id.document_id();
+        let template_ids = self
+            .get_document_ids(account_id, Collection::EmailTemplate)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            template_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::EmailTemplate)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the template object
+            let document_id = id.document_id();
+            if !template_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut template = if let Some(template) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::EmailTemplate,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                template
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    property => template.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,99 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Display;
+
+pub mod get;
+pub mod set;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateItem<'x> {
+    Text(&'x str),
+    Variable(&'x str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    Unterminated,
+    InvalidName(String),
+    Missing(String),
+}
+
+pub fn parse_template(template: &str) -> Result<Vec<TemplateItem<'_>>, TemplateError> {
+    let mut items = Vec::new();
+    let mut text = template;
+
+    while let Some(start) = text.find("{{") {
+        if start > 0 {
+            items.push(TemplateItem::Text(&text[..start]));
+        }
+        let rest = &text[start + 2..];
+        let end = rest.find("}}").ok_or(TemplateError::Unterminated)?;
+        let name = rest[..end].trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.'))
+        {
+            return Err(TemplateError::InvalidName(name.to_string()));
+        }
+        items.push(TemplateItem::Variable(name));
+        text = &rest[end + 2..];
+    }
+
+    if !text.is_empty() {
+        items.push(TemplateItem::Text(text));
+    }
+
+    Ok(items)
+}
+
+pub fn render_template<'x>(
+    template: &str,
+    variables: impl Fn(&str) -> Option<&'x str>,
+    escape_html: bool,
+) -> Result<String, TemplateError> {
+    let mut result = String::with_capacity(template.len());
+
+    for item in parse_template(template)? {
+        match item {
+            TemplateItem::Text(text) => result.push_str(text),
+            TemplateItem::Variable(name) => {
+                let value = variables(name).ok_or_else(|| TemplateError::Missing(name.into()))?;
+                if escape_html {
+                    for ch in value.chars() {
+                        match ch {
+                            '&' => result.push_str("&amp;"),
+                            '<' => result.push_str("&lt;"),
+                            '>' => result.push_str("&gt;"),
+                            '"' => result.push_str("&quot;"),
+                            '\'' => result.push_str("&#39;"),
+                            _ => result.push(ch),
+                        }
+                    }
+                } else {
+                    result.push_str(value);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::Unterminated => f.write_str("Unterminated template variable."),
+            TemplateError::InvalidName(name) => {
+                write!(f, "Invalid template variable name {name:?}.")
+            }
+            TemplateError::Missing(name) => {
+                write!(f, "No value provided for template variable {name:?}.")
+            }
+        }
+    }
+}
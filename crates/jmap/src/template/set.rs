@@ -0,0 +1,229 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::Object,
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        value::{MaybePatchValue, Value},
+    },
+};
+use std::future::Future;
+use store::write::{log::ChangeLogBuilder, BatchBuilder, F_CLEAR, F_VALUE};
+
+use crate::{changes::write::ChangeLog, JmapMethods};
+
+use super::parse_template;
+
+pub trait EmailTemplateSet: Sync + Send {
+    fn email_template_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl EmailTemplateSet for Server {
+    async fn email_template_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let mut template_ids = self
+            .get_document_ids(account_id, Collection::EmailTemplate)
+            .await?
+            .unwrap_or_default();
+        let mut response = SetResponse::from_request(&request, self.core.jmap.set_max_objects)?;
+        let will_destroy = request.unwrap_destroy();
+        let max_size = self.core.jmap.mail_max_size;
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        'create: for (id, object) in request.unwrap_create() {
+            let mut template = Object::with_capacity(object.properties.len());
+
+            for (property, value) in object.properties {
+                match response
+                    .eval_object_references(value)
+                    .and_then(|value| validate_template_value(&property, value, max_size))
+                {
+                    Ok(Value::Null) => (),
+                    Ok(value) => {
+                        template.set(property, value);
+                    }
+                    Err(err) => {
+                        response.not_created.append(id, err);
+                        continue 'create;
+                    }
+                }
+            }
+
+            if let Err(err) = validate_template(&template) {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
+            // Insert record
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::EmailTemplate)
+                .create_document()
+                .value(Property::Value, template, F_VALUE);
+            let document_id = self.write_batch_expect_id(batch).await?;
+            template_ids.insert(document_id);
+            changes.log_insert(Collection::EmailTemplate, document_id);
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain template
+            let document_id = id.document_id();
+            let mut template = if let Some(template) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::EmailTemplate,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                template
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            for (property, value) in object.properties {
+                match response
+                    .eval_object_references(value)
+                    .and_then(|value| validate_template_value(&property, value, max_size))
+                {
+                    Ok(Value::Null) => {
+                        template.remove(&property);
+                    }
+                    Ok(value) => {
+                        template.set(property, value);
+                    }
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+            }
+
+            if let Err(err) = validate_template(&template) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+
+            // Update record
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::EmailTemplate)
+                .update_document(document_id)
+                .value(Property::Value, template, F_VALUE);
+            self.write_batch(batch).await?;
+            changes.log_update(Collection::EmailTemplate, document_id);
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if template_ids.contains(document_id) {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::EmailTemplate)
+                    .delete_document(document_id)
+                    .value(Property::Value, (), F_VALUE | F_CLEAR);
+                self.write_batch(batch).await?;
+                changes.log_delete(Collection::EmailTemplate, document_id);
+                response.destroyed.push(id);
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+}
+
+fn validate_template_value(
+    property: &Property,
+    value: MaybePatchValue,
+    max_size: usize,
+) -> Result<Value, SetError> {
+    Ok(match (property, value) {
+        (Property::Name, MaybePatchValue::Value(Value::Text(value))) if value.len() < 255 => {
+            Value::Text(value)
+        }
+        (Property::Subject, MaybePatchValue::Value(Value::Text(value)))
+            if value.len() < 998 && !value.contains(['\r', '\n']) =>
+        {
+            validate_template_text(property, &value)?;
+            Value::Text(value)
+        }
+        (Property::TextBody | Property::HtmlBody, MaybePatchValue::Value(Value::Text(value)))
+            if value.len() <= max_size =>
+        {
+            validate_template_text(property, &value)?;
+            Value::Text(value)
+        }
+        (
+            Property::Name | Property::TextBody | Property::HtmlBody,
+            MaybePatchValue::Value(Value::Null),
+        ) => Value::Null,
+
+        (property, _) => {
+            return Err(SetError::invalid_properties()
+                .with_property(property.clone())
+                .with_description("Field could not be set."));
+        }
+    })
+}
+
+fn validate_template_text(property: &Property, value: &str) -> Result<(), SetError> {
+    parse_template(value).map(|_| ()).map_err(|err| {
+        SetError::invalid_properties()
+            .with_property(property.clone())
+            .with_description(err.to_string())
+    })
+}
+
+fn validate_template(template: &Object<Value>) -> Result<(), SetError> {
+    if !matches!(template.get(&Property::Subject), Value::Text(_)) {
+        Err(SetError::invalid_properties()
+            .with_property(Property::Subject)
+            .with_description("Missing subject."))
+    } else if !matches!(template.get(&Property::TextBody), Value::Text(_))
+        && !matches!(template.get(&Property::HtmlBody), Value::Text(_))
+    {
+        Err(SetError::invalid_properties()
+            .with_properties([Property::TextBody, Property::HtmlBody])
+            .with_description("At least one of textBody or htmlBody is required."))
+    } else {
+        Ok(())
+    }
+}
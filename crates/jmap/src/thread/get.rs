@@ -16,6 +16,8 @@ use trc::AddContext;
 
 use crate::{changes::state::StateManager, JmapMethods};
 
+use super::muted_keyword;
+
 pub trait ThreadGet: Sync + Send {
     fn thread_get(
         &self,
@@ -40,9 +42,26 @@ impl ThreadGet for Server {
                 .map(Into::into)
                 .collect()
         };
-        let add_email_ids = request
-            .properties
-            .map_or(true, |p| p.unwrap().contains(&Property::EmailIds));
+        let properties = request.properties.map(|p| p.unwrap());
+        let add_email_ids = properties
+            .as_ref()
+            .is_none_or(|p| p.contains(&Property::EmailIds));
+        let muted_ids = if properties
+            .as_ref()
+            .is_none_or(|p| p.contains(&Property::IsMuted))
+        {
+            self.get_tag(
+                account_id,
+                Collection::Thread,
+                Property::Keywords,
+                muted_keyword(),
+            )
+            .await?
+            .unwrap_or_default()
+            .into()
+        } else {
+            None
+        };
         let mut response = GetResponse {
             account_id: request.account_id.into(),
             state: self.get_state(account_id, Collection::Thread).await?.into(),
@@ -56,7 +75,7 @@ impl ThreadGet for Server {
                 .get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
                 .await?
             {
-                let mut thread = Object::with_capacity(2).with_property(Property::Id, id);
+                let mut thread = Object::with_capacity(3).with_property(Property::Id, id);
                 if add_email_ids {
                     thread.append(
                         Property::EmailIds,
@@ -76,6 +95,9 @@ impl ThreadGet for Server {
                             .collect::<Vec<_>>(),
                     );
                 }
+                if let Some(muted_ids) = &muted_ids {
+                    thread.append(Property::IsMuted, muted_ids.contains(thread_id));
+                }
                 response.list.push(thread);
             } else {
                 response.not_found.push(id.into());
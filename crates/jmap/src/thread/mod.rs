@@ -4,4 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use jmap_proto::types::keyword::Keyword;
+
 pub mod get;
+pub mod set;
+
+pub const MUTED_KEYWORD: &str = "$muted";
+
+pub fn muted_keyword() -> Keyword {
+    Keyword::Other(MUTED_KEYWORD.to_string())
+}
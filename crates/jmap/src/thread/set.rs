@@ -0,0 +1,388 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::Server;
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        id::Id,
+        keyword::Keyword,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, Value},
+    },
+};
+use rand::Rng;
+use std::future::Future;
+use store::{
+    ahash::AHashSet,
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, F_CLEAR, F_VALUE},
+};
+use trc::AddContext;
+
+use crate::{
+    changes::write::ChangeLog,
+    email::{ingest::EmailIngest, set::TagManager},
+    mailbox::UidMailbox,
+    JmapMethods,
+};
+
+use super::muted_keyword;
+
+const MAX_RETRIES: u32 = 10;
+
+pub trait ThreadSet: Sync + Send {
+    fn thread_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+#[derive(Default)]
+struct ThreadUpdate {
+    is_muted: Option<bool>,
+    mailboxes: Option<Vec<u32>>,
+    mailbox_patches: Vec<(u32, bool)>,
+    keywords: Option<Vec<Keyword>>,
+    keyword_patches: Vec<(Keyword, bool)>,
+}
+
+impl ThreadSet for Server {
+    async fn thread_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let mut response = self
+            .prepare_set_response(&request, Collection::Thread)
+            .await?;
+        let mailbox_ids = self
+            .get_document_ids(account_id, Collection::Mailbox)
+            .await?
+            .unwrap_or_default();
+
+        // Threads are created and destroyed along with their emails
+        for (id, _) in request.unwrap_create() {
+            response.not_created.append(
+                id,
+                SetError::forbidden().with_description("Threads cannot be created."),
+            );
+        }
+        for id in request.unwrap_destroy() {
+            response.not_destroyed.append(
+                id,
+                SetError::forbidden()
+                    .with_description("Threads cannot be destroyed, destroy their emails instead."),
+            );
+        }
+
+        // Process updates
+        let mut last_change_id = None;
+        'update: for (id, object) in request.unwrap_update() {
+            let mut update = ThreadUpdate::default();
+            for (property, value) in object.properties {
+                let value = match response.eval_object_references(value) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+                match (property, value) {
+                    (Property::IsMuted, MaybePatchValue::Value(Value::Bool(is_muted))) => {
+                        update.is_muted = Some(is_muted);
+                    }
+                    (Property::MailboxIds, MaybePatchValue::Value(Value::List(ids))) => {
+                        update.mailboxes = Some(
+                            ids.into_iter()
+                                .filter_map(|id| id.try_unwrap_id().map(|id| id.document_id()))
+                                .collect(),
+                        );
+                    }
+                    (Property::MailboxIds, MaybePatchValue::Patch(patch)) => {
+                        let mut patch = patch.into_iter();
+                        if let Some(id) = patch.next().unwrap().try_unwrap_id() {
+                            update.mailbox_patches.push((
+                                id.document_id(),
+                                patch.next().unwrap().try_unwrap_bool().unwrap_or_default(),
+                            ));
+                        }
+                    }
+                    (Property::Keywords, MaybePatchValue::Value(Value::List(keywords))) => {
+                        update.keywords = Some(
+                            keywords
+                                .into_iter()
+                                .filter_map(|keyword| keyword.try_unwrap_keyword())
+                                .collect(),
+                        );
+                    }
+                    (Property::Keywords, MaybePatchValue::Patch(patch)) => {
+                        let mut patch = patch.into_iter();
+                        if let Some(keyword) = patch.next().unwrap().try_unwrap_keyword() {
+                            update.keyword_patches.push((
+                                keyword,
+                                patch.next().unwrap().try_unwrap_bool().unwrap_or_default(),
+                            ));
+                        }
+                    }
+                    (property, _) => {
+                        response.invalid_property_update(id, property);
+                        continue 'update;
+                    }
+                }
+            }
+
+            // Make sure all new mailboxIds are valid
+            if let Some(mailbox_id) = update
+                .mailboxes
+                .iter()
+                .flatten()
+                .chain(
+                    update
+                        .mailbox_patches
+                        .iter()
+                        .filter(|(_, add)| *add)
+                        .map(|(mailbox_id, _)| mailbox_id),
+                )
+                .find(|mailbox_id| !mailbox_ids.contains(**mailbox_id))
+            {
+                response.not_updated.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::MailboxIds)
+                        .with_description(format!("mailboxId {mailbox_id} does not exist.")),
+                );
+                continue 'update;
+            }
+
+            // Obtain thread emails
+            let thread_id = id.document_id();
+            let document_ids = if let Some(document_ids) = self
+                .get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
+                .await?
+            {
+                document_ids
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            // Apply the changes to all emails in a single batch
+            let mut try_count = 0;
+            loop {
+                match self
+                    .thread_update(account_id, thread_id, &document_ids, &update)
+                    .await
+                {
+                    Ok(Ok(Some(change_id))) => {
+                        last_change_id = Some(change_id);
+                        response.updated.append(id, None);
+                        break;
+                    }
+                    Ok(Ok(None)) => {
+                        response.not_updated.append(
+                            id,
+                            SetError::invalid_properties()
+                                .with_description("No changes found in request."),
+                        );
+                        break;
+                    }
+                    Ok(Err(err)) => {
+                        response.not_updated.append(id, err);
+                        break;
+                    }
+                    Err(err) if err.is_assertion_failure() && try_count < MAX_RETRIES => {
+                        let backoff = rand::thread_rng().gen_range(50..=300);
+                        tokio::time::sleep(Duration::from_millis(backoff)).await;
+                        try_count += 1;
+                    }
+                    Err(err) if err.is_assertion_failure() => {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this thread, please try again.",
+                            ),
+                        );
+                        break;
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Update state
+        if let Some(change_id) = last_change_id {
+            response.new_state = Some(change_id.into());
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::Email, change_id)
+                .with_change(DataType::Mailbox, change_id)
+                .with_change(DataType::Thread, change_id)
+                .into();
+        }
+
+        Ok(response)
+    }
+}
+
+trait ThreadUpdateBatch: Sync + Send {
+    fn thread_update(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+        document_ids: &RoaringBitmap,
+        update: &ThreadUpdate,
+    ) -> impl Future<Output = trc::Result<Result<Option<u64>, SetError>>> + Send;
+}
+
+impl ThreadUpdateBatch for Server {
+    async fn thread_update(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+        document_ids: &RoaringBitmap,
+        update: &ThreadUpdate,
+    ) -> trc::Result<Result<Option<u64>, SetError>> {
+        let change_id = self.assign_change_id(account_id).await?;
+        let mut changes = ChangeLogBuilder::with_change_id(change_id);
+        let mut changed_mailboxes = AHashSet::new();
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email);
+
+        for document_id in document_ids {
+            // Obtain current keywords and mailboxes
+            let (mut mailboxes, mut keywords) = if let (Some(mailboxes), Some(keywords)) = (
+                self.get_property::<HashedValue<Vec<UidMailbox>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::MailboxIds,
+                )
+                .await?,
+                self.get_property::<HashedValue<Vec<Keyword>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::Keywords,
+                )
+                .await?,
+            ) {
+                (TagManager::new(mailboxes), TagManager::new(keywords))
+            } else {
+                continue;
+            };
+
+            // Apply changes
+            if let Some(mailbox_ids) = &update.mailboxes {
+                mailboxes.set(
+                    mailbox_ids
+                        .iter()
+                        .map(|mailbox_id| UidMailbox::new_unassigned(*mailbox_id))
+                        .collect(),
+                );
+            }
+            for (mailbox_id, add) in &update.mailbox_patches {
+                mailboxes.update(UidMailbox::new_unassigned(*mailbox_id), *add);
+            }
+            if let Some(keyword_list) = &update.keywords {
+                keywords.set(keyword_list.clone());
+            }
+            for (keyword, add) in &update.keyword_patches {
+                keywords.update(keyword.clone(), *add);
+            }
+            if !mailboxes.has_changes() && !keywords.has_changes() {
+                continue;
+            }
+
+            batch.update_document(document_id);
+            changes.log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+
+            // Process keywords
+            if keywords.has_changes() {
+                // Set all current mailboxes as changed if the Seen tag changed
+                if keywords
+                    .changed_tags()
+                    .any(|keyword| keyword == &Keyword::Seen)
+                {
+                    for mailbox_id in mailboxes.current() {
+                        changed_mailboxes.insert(mailbox_id.mailbox_id);
+                    }
+                }
+
+                keywords.update_batch(&mut batch, Property::Keywords);
+                batch.value(Property::Cid, change_id, F_VALUE);
+            }
+
+            // Process mailboxes
+            if mailboxes.has_changes() {
+                // Make sure the message is at least in one mailbox
+                if !mailboxes.has_tags() {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(Property::MailboxIds)
+                        .with_description(
+                            "Messages have to belong to at least one mailbox.",
+                        )));
+                }
+
+                for mailbox_id in mailboxes.changed_tags() {
+                    changed_mailboxes.insert(mailbox_id.mailbox_id);
+                }
+
+                // Obtain IMAP UIDs for added mailboxes
+                for uid_mailbox in mailboxes.inner_tags_mut() {
+                    if uid_mailbox.uid == 0 {
+                        uid_mailbox.uid = self
+                            .assign_imap_uid(account_id, uid_mailbox.mailbox_id)
+                            .await
+                            .caused_by(trc::location!())?;
+                    }
+                }
+
+                mailboxes.update_batch(&mut batch, Property::MailboxIds);
+            }
+        }
+
+        // Log mailbox changes
+        for mailbox_id in changed_mailboxes {
+            changes.log_child_update(Collection::Mailbox, mailbox_id);
+        }
+
+        // Update mute state
+        if let Some(is_muted) = update.is_muted {
+            batch
+                .with_collection(Collection::Thread)
+                .update_document(thread_id)
+                .tag(
+                    Property::Keywords,
+                    muted_keyword(),
+                    if is_muted { 0 } else { F_CLEAR },
+                );
+            changes.log_update(Collection::Thread, thread_id);
+        }
+
+        if batch.is_empty() {
+            return Ok(Ok(None));
+        }
+
+        batch.custom(changes);
+        self.core
+            .storage
+            .data
+            .write(batch.build())
+            .await
+            .map(|_| Ok(Some(change_id)))
+    }
+}
@@ -10,7 +10,7 @@ pub mod session;
 use std::{borrow::Cow, net::IpAddr, sync::Arc};
 
 use common::{
-    auth::AccessToken,
+    auth::{kerberos::GssapiSession, AccessToken},
     listener::{limiter::InFlight, ServerInstance},
     Inner, Server,
 };
@@ -26,6 +26,7 @@ pub struct Session<T: AsyncRead + AsyncWrite> {
     pub stream: T,
     pub session_id: u64,
     pub in_flight: InFlight,
+    pub gssapi: Option<Box<GssapiSession>>,
 }
 
 pub enum State {
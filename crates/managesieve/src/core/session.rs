@@ -34,6 +34,7 @@ impl SessionManager for ManageSieveSessionManager {
                 stream: session.stream,
                 in_flight: session.in_flight,
                 remote_addr: session.remote_ip,
+                gssapi: None,
             };
 
             if session
@@ -146,6 +147,7 @@ impl<T: SessionStream> Session<T> {
             server: self.server,
             receiver: self.receiver,
             remote_addr: self.remote_addr,
+            gssapi: None,
         })
     }
 }
@@ -6,8 +6,9 @@
 
 use common::{
     auth::{
+        kerberos::GssapiStep,
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::{limiter::ConcurrencyLimiter, SessionStream},
     ConcurrencyLimiters,
@@ -55,13 +56,48 @@ impl<T: SessionStream> Session<T> {
                                 .details("Failed to decode challenge.")
                         })?
                 } else {
-                    self.receiver.request = receiver::Request {
-                        tag: String::new(),
-                        command: Command::Authenticate,
-                        tokens: vec![receiver::Token::Argument(mechanism.into_bytes())],
-                    };
-                    self.receiver.state = receiver::State::Argument { last_ch: b' ' };
-                    return Ok(b"{0}\r\n".to_vec());
+                    return Ok(self.continue_authenticate(mechanism, ""));
+                }
+            }
+            Mechanism::Gssapi => {
+                let response = params.pop();
+                let mut gssapi = match self.gssapi.take() {
+                    Some(gssapi) => gssapi,
+                    None => {
+                        let gssapi = Box::new(self.server.gssapi_session(true)?);
+                        if response.is_none() {
+                            self.gssapi = Some(gssapi);
+                            return Ok(self.continue_authenticate(mechanism, ""));
+                        }
+                        gssapi
+                    }
+                };
+
+                match gssapi.step(response.as_deref().unwrap_or_default())? {
+                    GssapiStep::Continue(challenge) => {
+                        self.gssapi = Some(gssapi);
+                        return Ok(self.continue_authenticate(mechanism, &challenge));
+                    }
+                    GssapiStep::Authenticated {
+                        principal,
+                        authz_id,
+                        ..
+                    } => {
+                        // Throttle authentication requests
+                        self.server.is_auth_allowed_soft(&self.remote_addr).await?;
+
+                        let result = self
+                            .server
+                            .authenticate_kerberos(
+                                &principal,
+                                authz_id.as_deref(),
+                                None,
+                                self.session_id,
+                                self.remote_addr,
+                            )
+                            .await;
+                        return self.finish_authenticate(result);
+                    }
                 }
             }
             _ => {
@@ -75,14 +111,38 @@ impl<T: SessionStream> Session<T> {
         self.server.is_auth_allowed_soft(&self.remote_addr).await?;
 
         // Authenticate
-        let access_token = self
+        let result = self
             .server
             .authenticate(&AuthRequest::from_credentials(
                 credentials,
                 self.session_id,
                 self.remote_addr,
             ))
-            .await
+            .await;
+
+        self.finish_authenticate(result)
+    }
+
+    fn continue_authenticate(&mut self, mechanism: Mechanism, challenge: &str) -> Vec<u8> {
+        self.receiver.request = receiver::Request {
+            tag: String::new(),
+            command: Command::Authenticate,
+            tokens: vec![receiver::Token::Argument(mechanism.into_bytes())],
+        };
+        self.receiver.state = receiver::State::Argument { last_ch: b' ' };
+
+        if challenge.is_empty() {
+            b"{0}\r\n".to_vec()
+        } else {
+            format!("\"{challenge}\"\r\n").into_bytes()
+        }
+    }
+
+    fn finish_authenticate(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+    ) -> trc::Result<Vec<u8>> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     match &self.state {
@@ -22,10 +22,14 @@ impl<T: SessionStream> Session<T> {
             response.extend_from_slice(b"\"STARTTLS\"\r\n");
         }
         if self.stream.is_tls() || self.server.core.imap.allow_plain_auth {
-            response.extend_from_slice(b"\"SASL\" \"PLAIN OAUTHBEARER\"\r\n");
+            response.extend_from_slice(b"\"SASL\" \"PLAIN OAUTHBEARER");
         } else {
-            response.extend_from_slice(b"\"SASL\" \"OAUTHBEARER\"\r\n");
+            response.extend_from_slice(b"\"SASL\" \"OAUTHBEARER");
         };
+        if self.server.core.jmap.kerberos.is_some() {
+            response.extend_from_slice(b" GSSAPI");
+        }
+        response.extend_from_slice(b"\"\r\n");
         if let Some(sieve) =
             self.server
                 .core
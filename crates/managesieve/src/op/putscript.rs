@@ -11,6 +11,7 @@ use directory::Permission;
 use imap_proto::receiver::Request;
 use jmap::{
     blob::upload::BlobUpload,
+    rules::RULES_SCRIPT_NAME,
     sieve::set::{ObjectBlobId, SCHEMA},
     JmapMethods,
 };
@@ -277,6 +278,10 @@ impl<T: SessionStream> Session<T> {
             Err(trc::ManageSieveEvent::Error
                 .into_err()
                 .details("The 'vacation' name is reserved, please use a different name."))
+        } else if name.eq_ignore_ascii_case(RULES_SCRIPT_NAME) {
+            Err(trc::ManageSieveEvent::Error
+                .into_err()
+                .details("The 'mailrules' name is reserved, please use a different name."))
         } else {
             Ok(self
                 .server
@@ -7,7 +7,7 @@
 use std::{net::IpAddr, sync::Arc};
 
 use common::{
-    auth::AccessToken,
+    auth::{kerberos::GssapiSession, AccessToken},
     listener::{limiter::InFlight, ServerInstance, SessionStream},
     Inner, Server,
 };
@@ -42,6 +42,7 @@ pub struct Session<T: SessionStream> {
     pub in_flight: InFlight,
     pub remote_addr: IpAddr,
     pub session_id: u64,
+    pub gssapi: Option<Box<GssapiSession>>,
 }
 
 pub enum State {
@@ -6,8 +6,9 @@
 
 use common::{
     auth::{
+        kerberos::GssapiStep,
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::{limiter::ConcurrencyLimiter, SessionStream},
     ConcurrencyLimiters,
@@ -48,17 +49,48 @@ impl<T: SessionStream> Session<T> {
 
                     self.handle_auth(credentials).await
                 } else {
-                    // TODO: This hack is temporary until the SASL library is developed
-                    self.receiver.state = request::State::Argument {
-                        request: Command::Auth {
-                            mechanism: mechanism.as_str().as_bytes().to_vec(),
-                            params: vec![],
-                        },
-                        num: 1,
-                        last_is_space: true,
-                    };
-
-                    self.write_bytes("+\r\n").await
+                    self.continue_sasl(mechanism, "").await
+                }
+            }
+            Mechanism::Gssapi => {
+                let response = params.pop();
+                let mut gssapi = match self.gssapi.take() {
+                    Some(gssapi) => gssapi,
+                    None => {
+                        let gssapi = Box::new(self.server.gssapi_session(true)?);
+                        if response.is_none() {
+                            self.gssapi = Some(gssapi);
+                            return self.continue_sasl(mechanism, "").await;
+                        }
+                        gssapi
+                    }
+                };
+
+                match gssapi.step(response.as_deref().unwrap_or_default())? {
+                    GssapiStep::Continue(challenge) => {
+                        self.gssapi = Some(gssapi);
+                        self.continue_sasl(mechanism, &challenge).await
+                    }
+                    GssapiStep::Authenticated {
+                        principal,
+                        authz_id,
+                        ..
+                    } => {
+                        // Throttle authentication requests
+                        self.server.is_auth_allowed_soft(&self.remote_addr).await?;
+
+                        let result = self
+                            .server
+                            .authenticate_kerberos(
+                                &principal,
+                                authz_id.as_deref(),
+                                None,
+                                self.session_id,
+                                self.remote_addr,
+                            )
+                            .await;
+                        self.finish_auth(result).await
+                    }
                 }
             }
             _ => Err(trc::AuthEvent::Error
@@ -67,19 +99,43 @@ impl<T: SessionStream> Session<T> {
         }
     }
 
+    async fn continue_sasl(&mut self, mechanism: Mechanism, challenge: &str) -> trc::Result<()> {
+        // TODO: This hack is temporary until the SASL library is developed
+        self.receiver.state = request::State::Argument {
+            request: Command::Auth {
+                mechanism: mechanism.as_str().as_bytes().to_vec(),
+                params: vec![],
+            },
+            num: 1,
+            last_is_space: true,
+        };
+
+        if challenge.is_empty() {
+            self.write_bytes("+\r\n").await
+        } else {
+            self.write_bytes(format!("+ {challenge}\r\n")).await
+        }
+    }
+
     pub async fn handle_auth(&mut self, credentials: Credentials<String>) -> trc::Result<()> {
         // Throttle authentication requests
         self.server.is_auth_allowed_soft(&self.remote_addr).await?;
 
         // Authenticate
-        let access_token = self
+        let result = self
             .server
             .authenticate(&AuthRequest::from_credentials(
                 credentials,
                 self.session_id,
                 self.remote_addr,
             ))
-            .await
+            .await;
+
+        self.finish_auth(result).await
+    }
+
+    async fn finish_auth(&mut self, result: trc::Result<Arc<AccessToken>>) -> trc::Result<()> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     match &self.state {
@@ -6,17 +6,19 @@
 
 use common::{
     auth::{
+        kerberos::{GssapiSession, GssapiStep},
         sasl::{
             sasl_decode_challenge_oauth, sasl_decode_challenge_plain, sasl_decode_challenge_xoauth,
         },
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::SessionStream,
 };
 use directory::Permission;
 use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
-use smtp_proto::{IntoString, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2};
+use smtp_proto::{IntoString, AUTH_GSSAPI, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2};
+use std::sync::Arc;
 use trc::{AuthEvent, SmtpEvent};
 
 use crate::core::Session;
@@ -24,6 +26,7 @@ use crate::core::Session;
 pub struct SaslToken {
     mechanism: u64,
     credentials: Credentials<String>,
+    gssapi: Option<Box<GssapiSession>>,
 }
 
 impl SaslToken {
@@ -35,6 +38,7 @@ impl SaslToken {
                     username: String::new(),
                     secret: String::new(),
                 },
+                gssapi: None,
             }
             .into(),
             AUTH_OAUTHBEARER => SaslToken {
@@ -42,6 +46,7 @@ impl SaslToken {
                 credentials: Credentials::OAuthBearer {
                     token: String::new(),
                 },
+                gssapi: None,
             }
             .into(),
             AUTH_XOAUTH2 => SaslToken {
@@ -50,6 +55,13 @@ impl SaslToken {
                     username: String::new(),
                     secret: String::new(),
                 },
+                gssapi: None,
+            }
+            .into(),
+            AUTH_GSSAPI => SaslToken {
+                mechanism,
+                credentials: Credentials::default(),
+                gssapi: None,
             }
             .into(),
             _ => None,
@@ -63,6 +75,10 @@ impl<T: SessionStream> Session<T> {
         token: &mut SaslToken,
         response: &[u8],
     ) -> Result<bool, ()> {
+        if token.mechanism == AUTH_GSSAPI {
+            return self.handle_gssapi_response(token, response).await;
+        }
+
         if response.is_empty() {
             match (token.mechanism, &token.credentials) {
                 (AUTH_PLAIN | AUTH_XOAUTH2 | AUTH_OAUTHBEARER, _) => {
@@ -113,6 +129,73 @@ impl<T: SessionStream> Session<T> {
         self.auth_error(b"500 5.5.6 Invalid challenge.\r\n").await
     }
 
+    async fn handle_gssapi_response(
+        &mut self,
+        token: &mut SaslToken,
+        response: &[u8],
+    ) -> Result<bool, ()> {
+        let gssapi = match &mut token.gssapi {
+            Some(gssapi) => gssapi,
+            None => match self.server.gssapi_session(true) {
+                Ok(gssapi) => {
+                    let gssapi = token.gssapi.insert(Box::new(gssapi));
+                    if response.is_empty() {
+                        self.write(b"334 \r\n").await?;
+                        return Ok(true);
+                    }
+                    gssapi
+                }
+                Err(err) => {
+                    trc::error!(err.span_id(self.data.session_id));
+                    return self
+                        .auth_error(b"454 4.7.0 Temporary authentication failure\r\n")
+                        .await;
+                }
+            },
+        };
+
+        match gssapi.step(std::str::from_utf8(response).unwrap_or_default()) {
+            Ok(GssapiStep::Continue(challenge)) => {
+                self.write(format!("334 {challenge}\r\n").as_bytes())
+                    .await?;
+                Ok(true)
+            }
+            Ok(GssapiStep::Authenticated {
+                principal,
+                authz_id,
+                ..
+            }) => {
+                let Some(directory) = &self.params.auth_directory else {
+                    trc::event!(
+                        Smtp(SmtpEvent::MissingAuthDirectory),
+                        SpanId = self.data.session_id,
+                    );
+                    self.write(b"454 4.7.0 Temporary authentication failure\r\n")
+                        .await?;
+
+                    return Ok(false);
+                };
+
+                let result = self
+                    .server
+                    .authenticate_kerberos(
+                        &principal,
+                        authz_id.as_deref(),
+                        Some(directory.as_ref()),
+                        self.data.session_id,
+                        self.data.remote_ip,
+                    )
+                    .await;
+                self.handle_auth_result(result).await
+            }
+            Err(err) => {
+                trc::error!(err.span_id(self.data.session_id));
+                self.auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
+                    .await
+            }
+        }
+    }
+
     pub async fn authenticate(&mut self, credentials: Credentials<String>) -> Result<bool, ()> {
         if let Some(directory) = &self.params.auth_directory {
             // Authenticate
@@ -126,65 +209,87 @@ impl<T: SessionStream> Session<T> {
                     )
                     .with_directory(directory),
                 )
-                .await
-                .and_then(|access_token| {
-                    access_token
-                        .assert_has_permission(Permission::EmailSend)
-                        .map(|_| access_token)
-                });
-
-            match result {
-                Ok(access_token) => {
-                    self.data.authenticated_as = access_token.into();
-                    self.eval_post_auth_params().await;
-                    self.write(b"235 2.7.0 Authentication succeeded.\r\n")
-                        .await?;
-                    return Ok(false);
-                }
-                Err(err) => {
-                    let reason = *err.as_ref();
+                .await;
 
-                    trc::error!(err.span_id(self.data.session_id));
+            self.handle_auth_result(result).await
+        } else {
+            trc::event!(
+                Smtp(SmtpEvent::MissingAuthDirectory),
+                SpanId = self.data.session_id,
+            );
+            self.write(b"454 4.7.0 Temporary authentication failure\r\n")
+                .await?;
+
+            Ok(false)
+        }
+    }
+
+    async fn handle_auth_result(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+    ) -> Result<bool, ()> {
+        let result = result.and_then(|access_token| {
+            access_token
+                .assert_has_permission(Permission::EmailSend)
+                .map(|_| access_token)
+        });
+
+        match result {
+            Ok(access_token) => {
+                self.data.authenticated_as = access_token.into();
+                self.eval_post_auth_params().await;
+                self.write(b"235 2.7.0 Authentication succeeded.\r\n")
+                    .await?;
+                return Ok(false);
+            }
+            Err(err) => {
+                let reason = *err.as_ref();
 
-                    match reason {
-                        trc::EventType::Auth(trc::AuthEvent::Failed) => {
-                            return self
-                                .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
-                                .await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
-                            return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
-                            return self
+                trc::error!(err.span_id(self.data.session_id));
+
+                match reason {
+                    trc::EventType::Auth(trc::AuthEvent::Failed) => {
+                        return self
+                            .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
+                            .await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
+                        return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::AccountLocked) => {
+                        return self
+                            .auth_error(b"535 5.7.8 Account temporarily locked.\r\n")
+                            .await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::PasswordExpired) => {
+                        return self
+                            .auth_error(b"535 5.7.8 Password expired, please change it.\r\n")
+                            .await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
+                        return self
                             .auth_error(
                                 b"334 5.7.8 Missing TOTP token, try with 'secret$totp_code'.\r\n",
                             )
                             .await;
-                        }
-                        trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
-                            self.write(
-                                concat!(
-                                    "550 5.7.1 Your account is not authorized ",
-                                    "to use this service.\r\n"
-                                )
-                                .as_bytes(),
+                    }
+                    trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
+                        self.write(
+                            concat!(
+                                "550 5.7.1 Your account is not authorized ",
+                                "to use this service.\r\n"
                             )
-                            .await?;
-                            return Ok(false);
-                        }
-                        trc::EventType::Security(_) => {
-                            return Err(());
-                        }
-                        _ => (),
+                            .as_bytes(),
+                        )
+                        .await?;
+                        return Ok(false);
                     }
+                    trc::EventType::Security(_) => {
+                        return Err(());
+                    }
+                    _ => (),
                 }
             }
-        } else {
-            trc::event!(
-                Smtp(SmtpEvent::MissingAuthDirectory),
-                SpanId = self.data.session_id,
-            );
         }
         self.write(b"454 4.7.0 Temporary authentication failure\r\n")
             .await?;
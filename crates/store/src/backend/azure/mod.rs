@@ -0,0 +1,284 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Display, io::Write, ops::Range, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH},
+    Client, Method, StatusCode,
+};
+use ring::hmac;
+use utils::{
+    codec::base32_custom::Base32Writer,
+    config::{utils::AsKey, Config},
+};
+
+use super::http_range;
+
+const API_VERSION: &str = "2021-08-06";
+
+pub struct AzureStore {
+    client: Client,
+    endpoint: String,
+    container: String,
+    account: String,
+    auth: AzureAuth,
+    prefix: Option<String>,
+    max_retries: u32,
+    retry_wait: Duration,
+}
+
+enum AzureAuth {
+    SharedKey(hmac::Key),
+    Sas(String),
+}
+
+impl AzureStore {
+    pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let account = config.value_require((&prefix, "account"))?.to_string();
+        let container = config.value_require((&prefix, "container"))?.to_string();
+        let endpoint = config
+            .value((&prefix, "endpoint"))
+            .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://{account}.blob.core.windows.net"));
+        let auth = if let Some(key) = config.value((&prefix, "access-key")) {
+            let key = STANDARD
+                .decode(key.trim())
+                .map_err(|err| {
+                    config.new_build_error(
+                        (&prefix, "access-key"),
+                        format!("Failed to decode access key: {err}"),
+                    )
+                })
+                .ok()?;
+            AzureAuth::SharedKey(hmac::Key::new(hmac::HMAC_SHA256, &key))
+        } else if let Some(sas_token) = config.value((&prefix, "sas-token")) {
+            AzureAuth::Sas(sas_token.trim_start_matches('?').to_string())
+        } else {
+            config.new_build_error(
+                prefix.as_str(),
+                "Either an access key or a SAS token must be provided",
+            );
+            return None;
+        };
+        let timeout = config
+            .property_or_default::<Duration>((&prefix, "timeout"), "30s")
+            .unwrap_or_else(|| Duration::from_secs(30));
+
+        Some(AzureStore {
+            client: Client::builder()
+                .timeout(timeout)
+                .danger_accept_invalid_certs(
+                    config
+                        .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
+                        .unwrap_or(false),
+                )
+                .build()
+                .map_err(|err| {
+                    config.new_build_error(
+                        prefix.as_str(),
+                        format!("Failed to create HTTP client: {err}"),
+                    )
+                })
+                .ok()?,
+            endpoint,
+            container,
+            account,
+            auth,
+            prefix: config.value((&prefix, "key-prefix")).map(|s| s.to_string()),
+            max_retries: config
+                .property_or_default((&prefix, "max-retries"), "3")
+                .unwrap_or(3),
+            retry_wait: config
+                .property_or_default::<Duration>((&prefix, "retry-wait"), "500ms")
+                .unwrap_or_else(|| Duration::from_millis(500)),
+        })
+    }
+
+    pub(crate) async fn get_blob(
+        &self,
+        key: &[u8],
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        // Empty ranges cannot be expressed as a Range header
+        if range.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+
+        let response = self.send(Method::GET, key, http_range(range), None).await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            // Range starts beyond the end of the blob
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(Some(Vec::new())),
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(|bytes| Some(bytes.to_vec()))
+                .map_err(into_error),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        let response = self.send(Method::PUT, key, None, Some(data)).await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let response = self.send(Method::DELETE, key, None, None).await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &[u8],
+        range: Option<String>,
+        body: Option<&[u8]>,
+    ) -> trc::Result<reqwest::Response> {
+        let path = format!("/{}/{}", self.container, self.build_key(key));
+        let mut url = format!("{}{}", self.endpoint, path);
+        if let AzureAuth::Sas(sas_token) = &self.auth {
+            url.push('?');
+            url.push_str(sas_token);
+        }
+
+        let mut retry = 0;
+        loop {
+            let headers = self.build_headers(&method, &path, range.as_deref(), body)?;
+            let mut request = self.client.request(method.clone(), &url).headers(headers);
+            if let Some(body) = body {
+                request = request.body(body.to_vec());
+            }
+
+            match request.send().await {
+                Ok(response)
+                    if retry < self.max_retries
+                        && (response.status().is_server_error()
+                            || response.status() == StatusCode::TOO_MANY_REQUESTS) => {}
+                Ok(response) => return Ok(response),
+                Err(err) if retry < self.max_retries && (err.is_timeout() || err.is_connect()) => {}
+                Err(err) => return Err(into_error(err)),
+            }
+
+            retry += 1;
+            tokio::time::sleep(self.retry_wait * retry).await;
+        }
+    }
+
+    fn build_headers(
+        &self,
+        method: &Method,
+        path: &str,
+        range: Option<&str>,
+        body: Option<&[u8]>,
+    ) -> trc::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        let date = chrono::Utc::now()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        headers.insert("x-ms-date", header_value(&date)?);
+        headers.insert("x-ms-version", HeaderValue::from_static(API_VERSION));
+        if let Some(range) = range {
+            headers.insert("x-ms-range", header_value(range)?);
+        }
+        let content_length = if let Some(body) = body {
+            headers.insert("x-ms-blob-type", HeaderValue::from_static("BlockBlob"));
+            headers.insert(CONTENT_LENGTH, header_value(&body.len().to_string())?);
+            if !body.is_empty() {
+                body.len().to_string()
+            } else {
+                String::new()
+            }
+        } else {
+            String::new()
+        };
+
+        if let AzureAuth::SharedKey(key) = &self.auth {
+            // Canonicalized headers, sorted by name
+            let mut ms_headers = headers
+                .iter()
+                .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+                .map(|(name, value)| (name.as_str(), value.to_str().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            ms_headers.sort_unstable_by_key(|(name, _)| *name);
+
+            let mut string_to_sign = format!(
+                "{}\n\n\n{}\n\n\n\n\n\n\n\n\n",
+                method.as_str(),
+                content_length
+            );
+            for (name, value) in ms_headers {
+                string_to_sign.push_str(name);
+                string_to_sign.push(':');
+                string_to_sign.push_str(value);
+                string_to_sign.push('\n');
+            }
+            string_to_sign.push('/');
+            string_to_sign.push_str(&self.account);
+            string_to_sign.push_str(&self.resource_path(path));
+
+            let signature = STANDARD.encode(hmac::sign(key, string_to_sign.as_bytes()));
+            headers.insert(
+                AUTHORIZATION,
+                header_value(&format!("SharedKey {}:{}", self.account, signature))?,
+            );
+        }
+
+        Ok(headers)
+    }
+
+    fn resource_path(&self, path: &str) -> String {
+        // Path-style endpoints (such as emulators) include the account name in the URL path
+        match self
+            .endpoint
+            .split_once("://")
+            .and_then(|(_, rest)| rest.split_once('/'))
+        {
+            Some((_, base_path)) if !base_path.is_empty() => format!("/{base_path}{path}"),
+            _ => path.to_string(),
+        }
+    }
+
+    fn build_key(&self, key: &[u8]) -> String {
+        if let Some(prefix) = &self.prefix {
+            let mut writer =
+                Base32Writer::with_raw_capacity(prefix.len() + (key.len().div_ceil(4) * 5));
+            writer.push_string(prefix);
+            writer.write_all(key).unwrap();
+            writer.finalize()
+        } else {
+            Base32Writer::from_bytes(key).finalize()
+        }
+    }
+}
+
+fn header_value(value: &str) -> trc::Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(into_error)
+}
+
+async fn status_error(status: StatusCode, response: reqwest::Response) -> trc::Error {
+    trc::StoreEvent::AzureError
+        .reason(response.text().await.unwrap_or_default())
+        .ctx(trc::Key::Code, status.as_u16())
+}
+
+#[inline(always)]
+fn into_error(err: impl Display) -> trc::Error {
+    trc::StoreEvent::AzureError.reason(err)
+}
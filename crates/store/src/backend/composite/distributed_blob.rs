@@ -29,6 +29,16 @@ impl DistributedBlob {
         let mut blob_stores = Vec::with_capacity(store_ids.len());
         for store_id in store_ids {
             if let Some(store) = stores.blob_stores.get(&store_id) {
+                if matches!(
+                    store.backend,
+                    BlobBackend::Composite(_) | BlobBackend::Tiered(_)
+                ) {
+                    config.new_build_error(
+                        (&prefix, "stores"),
+                        format!("Blob store {store_id} cannot be nested in a sharded blob store"),
+                    );
+                    return None;
+                }
                 blob_stores.push(store.backend.clone());
             } else {
                 config.new_build_error(
@@ -76,12 +86,19 @@ impl DistributedBlob {
                         any(feature = "postgres", feature = "mysql")
                     ))]
                     Store::SQLReadReplica(store) => store.get_blob(key, read_range).await,
+                    Store::Sharded(store) => store.get_blob(key, read_range).await,
                     Store::None => Err(trc::StoreEvent::NotConfigured.into()),
                 },
                 BlobBackend::Fs(store) => store.get_blob(key, read_range).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.get_blob(key, read_range).await,
-                BlobBackend::Composite(_) => unimplemented!(),
+                #[cfg(feature = "azure")]
+                BlobBackend::Azure(store) => store.get_blob(key, read_range).await,
+                #[cfg(feature = "gcs")]
+                BlobBackend::Gcs(store) => store.get_blob(key, read_range).await,
+                BlobBackend::Composite(_) | BlobBackend::Tiered(_) => {
+                    Err(trc::StoreEvent::NotSupported.into())
+                }
             }
         })
         .await
@@ -106,12 +123,19 @@ impl DistributedBlob {
                         any(feature = "postgres", feature = "mysql")
                     ))]
                     Store::SQLReadReplica(store) => store.put_blob(key, data).await,
+                    Store::Sharded(store) => store.put_blob(key, data).await,
                     Store::None => Err(trc::StoreEvent::NotConfigured.into()),
                 },
                 BlobBackend::Fs(store) => store.put_blob(key, data).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.put_blob(key, data).await,
-                BlobBackend::Composite(_) => unimplemented!(),
+                #[cfg(feature = "azure")]
+                BlobBackend::Azure(store) => store.put_blob(key, data).await,
+                #[cfg(feature = "gcs")]
+                BlobBackend::Gcs(store) => store.put_blob(key, data).await,
+                BlobBackend::Composite(_) | BlobBackend::Tiered(_) => {
+                    Err(trc::StoreEvent::NotSupported.into())
+                }
             }
         })
         .await
@@ -136,12 +160,19 @@ impl DistributedBlob {
                         any(feature = "postgres", feature = "mysql")
                     ))]
                     Store::SQLReadReplica(store) => store.delete_blob(key).await,
+                    Store::Sharded(store) => store.delete_blob(key).await,
                     Store::None => Err(trc::StoreEvent::NotConfigured.into()),
                 },
                 BlobBackend::Fs(store) => store.delete_blob(key).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.delete_blob(key).await,
-                BlobBackend::Composite(_) => unimplemented!(),
+                #[cfg(feature = "azure")]
+                BlobBackend::Azure(store) => store.delete_blob(key).await,
+                #[cfg(feature = "gcs")]
+                BlobBackend::Gcs(store) => store.delete_blob(key).await,
+                BlobBackend::Composite(_) | BlobBackend::Tiered(_) => {
+                    Err(trc::StoreEvent::NotSupported.into())
+                }
             }
         })
         .await
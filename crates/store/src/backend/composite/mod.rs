@@ -11,3 +11,4 @@
 pub mod distributed_blob;
 #[cfg(any(feature = "postgres", feature = "mysql"))]
 pub mod read_replica;
+pub mod sharded;
@@ -0,0 +1,969 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: LicenseRef-SEL
+ *
+ * This file is subject to the Stalwart Enterprise License Agreement (SEL) and
+ * is NOT open source software.
+ *
+ */
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicU8, Ordering},
+    time::{Duration, Instant},
+};
+
+use roaring::RoaringBitmap;
+use utils::{
+    codec::leb128::Leb128Reader,
+    config::{utils::AsKey, Config},
+    map::ttl_dashmap::{TtlDashMap, TtlMap},
+};
+
+use crate::{
+    write::{
+        key::{DeserializeBigEndian, KeySerializer},
+        AnyClass, AnyKey, AssignedIds, Batch, BatchBuilder, BitmapClass, BitmapHash,
+        DirectoryClass, MaybeDynamicId, MaybeDynamicValue, Operation, TagValue, ValueClass,
+        ValueOp,
+    },
+    BitmapKey, Deserialize, IterateParams, Key, LookupStore, Store, Stores, ValueKey,
+    COUNTER_COLLECTION, COUNTER_PROPERTY, SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG,
+    SUBSPACE_BITMAP_TEXT, SUBSPACE_COUNTER, SUBSPACE_FTS_INDEX, SUBSPACE_INDEXES, SUBSPACE_LOGS,
+    SUBSPACE_PROPERTY, SUBSPACE_SNOOZE, U32_LEN,
+};
+
+const PRIMARY: usize = 0;
+const CHUNK_SIZE: usize = 1000;
+const LOCK_EXPIRY: u64 = 3600;
+
+// Prefix of the stored shard id while the account is being moved off it
+const MOVING_MARKER: char = '\0';
+
+// Subspaces holding account data, keyed by account id
+const ACCOUNT_SUBSPACES: [u8; 8] = [
+    SUBSPACE_BITMAP_ID,
+    SUBSPACE_BITMAP_TAG,
+    SUBSPACE_BITMAP_TEXT,
+    SUBSPACE_INDEXES,
+    SUBSPACE_LOGS,
+    SUBSPACE_PROPERTY,
+    SUBSPACE_FTS_INDEX,
+    SUBSPACE_SNOOZE,
+];
+
+pub struct ShardedStore {
+    shards: Vec<Shard>,
+    accounts: TtlDashMap<u32, AccountShard>,
+    version: AtomicU8,
+    cache_ttl: Duration,
+    move_delay: Duration,
+}
+
+struct Shard {
+    id: String,
+    store: Store,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AccountShard {
+    Unassigned,
+    Assigned(usize),
+    // Data is being moved off this shard, writes are rejected
+    Moving(usize),
+}
+
+// Sharded stores can only be built on top of concrete backends, dispatching
+// through `Store` would make these futures recursive.
+macro_rules! dispatch {
+    ($store:expr, $backend:ident => $op:expr) => {
+        match $store {
+            #[cfg(feature = "sqlite")]
+            Store::SQLite($backend) => $op,
+            #[cfg(feature = "foundation")]
+            Store::FoundationDb($backend) => $op,
+            #[cfg(feature = "postgres")]
+            Store::PostgreSQL($backend) => $op,
+            #[cfg(feature = "mysql")]
+            Store::MySQL($backend) => $op,
+            #[cfg(feature = "rocks")]
+            Store::RocksDb($backend) => $op,
+            #[cfg(any(feature = "postgres", feature = "mysql"))]
+            Store::SQLReadReplica($backend) => $op,
+            Store::Sharded(_) | Store::None => Err(trc::StoreEvent::NotConfigured.into()),
+        }
+    };
+}
+
+impl ShardedStore {
+    pub async fn open(
+        config: &mut Config,
+        prefix: impl AsKey,
+        stores: &Stores,
+        create_tables: bool,
+    ) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let primary_id = config.value_require((&prefix, "primary"))?.to_string();
+        let shard_ids = config
+            .values((&prefix, "shards"))
+            .map(|(_, v)| v.to_string())
+            .collect::<Vec<_>>();
+
+        let mut shards: Vec<Shard> = Vec::with_capacity(shard_ids.len() + 1);
+        for (property, store_id) in std::iter::once(("primary", primary_id))
+            .chain(shard_ids.into_iter().map(|id| ("shards", id)))
+        {
+            if shards.iter().any(|shard| shard.id == store_id) {
+                continue;
+            }
+
+            match stores.stores.get(&store_id) {
+                Some(Store::Sharded(_) | Store::None) => {
+                    config.new_build_error(
+                        (&prefix, property),
+                        format!("Store {store_id} cannot be used as a shard"),
+                    );
+                    return None;
+                }
+                Some(store) => {
+                    shards.push(Shard {
+                        id: store_id,
+                        store: store.clone(),
+                    });
+                }
+                None => {
+                    config.new_build_error(
+                        (&prefix, property),
+                        format!("Shard store {store_id} not found"),
+                    );
+                    return None;
+                }
+            }
+        }
+
+        if shards.len() < 2 {
+            config.new_build_error((&prefix, "shards"), "No shard stores specified");
+            return None;
+        }
+
+        if create_tables {
+            for shard in &shards {
+                let result = match &shard.store {
+                    #[cfg(feature = "postgres")]
+                    Store::PostgreSQL(store) => store.create_tables().await,
+                    #[cfg(feature = "mysql")]
+                    Store::MySQL(store) => store.create_tables().await,
+                    _ => Ok(()),
+                };
+
+                if let Err(err) = result {
+                    config.new_build_error(
+                        (&prefix, "shards"),
+                        format!("Failed to create tables on shard {}: {err}", shard.id),
+                    );
+                }
+            }
+        }
+
+        Some(Self {
+            shards,
+            accounts: TtlDashMap::with_capacity(1024, 16),
+            version: AtomicU8::new(0),
+            cache_ttl: config
+                .property_or_default::<Duration>((&prefix, "cache.ttl"), "5m")
+                .unwrap_or_else(|| Duration::from_secs(300)),
+            move_delay: config
+                .property_or_default::<Duration>((&prefix, "move.delay"), "10s")
+                .unwrap_or_else(|| Duration::from_secs(10)),
+        })
+    }
+
+    pub fn primary(&self) -> &Store {
+        &self.shards[PRIMARY].store
+    }
+
+    pub fn shard_ids(&self) -> impl Iterator<Item = &str> {
+        self.shards.iter().map(|shard| shard.id.as_str())
+    }
+
+    /// Version of the shard assignments, changes every time an account
+    /// is assigned or moved so that other nodes can invalidate their cache
+    pub fn version(&self) -> u8 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// Drops all cached shard assignments
+    pub fn invalidate_cache(&self) {
+        self.accounts.clear();
+    }
+
+    fn shard_index(&self, shard_id: &str) -> trc::Result<usize> {
+        self.shards
+            .iter()
+            .position(|shard| shard.id == shard_id)
+            .ok_or_else(|| {
+                trc::StoreEvent::NotFound
+                    .into_err()
+                    .details("Shard not found")
+                    .id(shard_id.to_string())
+            })
+    }
+
+    async fn assigned_shard(&self, account_id: u32) -> trc::Result<AccountShard> {
+        if account_id == u32::MAX {
+            return Ok(AccountShard::Assigned(PRIMARY));
+        } else if let Some(shard) = self.accounts.get_with_ttl(&account_id) {
+            return Ok(shard);
+        }
+
+        let shard = match dispatch!(self.primary(), store => store
+            .get_value::<String>(ValueKey::from(DirectoryClass::Shard(account_id)))
+            .await)?
+        {
+            Some(shard_id) => match shard_id.strip_prefix(MOVING_MARKER) {
+                Some(shard_id) => self.shard_index(shard_id).map(AccountShard::Moving),
+                None => self.shard_index(&shard_id).map(AccountShard::Assigned),
+            }
+            .map_err(|err| err.account_id(account_id))?,
+            None => AccountShard::Unassigned,
+        };
+        self.accounts
+            .insert_with_ttl(account_id, shard, Instant::now() + self.cache_ttl);
+
+        Ok(shard)
+    }
+
+    async fn account_shard(&self, account_id: u32) -> trc::Result<usize> {
+        self.assigned_shard(account_id)
+            .await
+            .map(|shard| shard.index().unwrap_or(PRIMARY))
+    }
+
+    async fn key_shard(&self, key: &impl Key) -> trc::Result<usize> {
+        match key.account() {
+            Some(account_id) => self.account_shard(account_id).await,
+            None => Ok(PRIMARY),
+        }
+    }
+
+    // Returns the shard accepting writes for an account
+    async fn writable_shard(&self, account_id: u32) -> trc::Result<usize> {
+        match self.assigned_shard(account_id).await? {
+            AccountShard::Unassigned => Ok(PRIMARY),
+            AccountShard::Assigned(shard) => Ok(shard),
+            AccountShard::Moving(_) => Err(trc::StoreEvent::AccountLocked
+                .into_err()
+                .account_id(account_id)),
+        }
+    }
+
+    // Returns the shard holding a key range, or None if it spans multiple accounts
+    async fn range_shard(&self, from: &impl Key, to: &impl Key) -> trc::Result<Option<usize>> {
+        match (from.account(), to.account()) {
+            (Some(from_account), Some(to_account)) if from_account == to_account => {
+                self.account_shard(from_account).await.map(Some)
+            }
+            _ if ACCOUNT_SUBSPACES.contains(&from.subspace()) => Ok(None),
+            _ => Ok(Some(PRIMARY)),
+        }
+    }
+
+    /// Returns the shard an account is assigned to, if any
+    pub async fn account_shard_id(&self, account_id: u32) -> trc::Result<Option<&str>> {
+        self.assigned_shard(account_id)
+            .await
+            .map(|shard| shard.index().map(|shard| self.shards[shard].id.as_str()))
+    }
+
+    /// Assigns an account to a shard unless it has been assigned already.
+    /// Accounts with existing data on the primary store are pinned to it.
+    pub async fn assign_account(&self, account_id: u32, shard_id: &str) -> trc::Result<&str> {
+        if let Some(shard) = self.assigned_shard(account_id).await?.index() {
+            return Ok(&self.shards[shard].id);
+        }
+
+        let mut shard = self.shard_index(shard_id)?;
+        if shard != PRIMARY {
+            let mut has_data = false;
+            dispatch!(self.primary(), store => store
+                .iterate(
+                    IterateParams::new(
+                        account_key(SUBSPACE_BITMAP_ID, account_id),
+                        account_key(SUBSPACE_BITMAP_ID, account_id + 1),
+                    )
+                    .no_values()
+                    .only_first(),
+                    |_, _| {
+                        has_data = true;
+                        Ok(false)
+                    },
+                )
+                .await)?;
+            if has_data {
+                shard = PRIMARY;
+            }
+        }
+
+        self.set_account_shard(account_id, AccountShard::Assigned(shard))
+            .await?;
+
+        Ok(&self.shards[shard].id)
+    }
+
+    async fn set_account_shard(&self, account_id: u32, shard: AccountShard) -> trc::Result<()> {
+        let mut batch = BatchBuilder::new();
+        match shard {
+            AccountShard::Assigned(index) => {
+                batch.set(
+                    DirectoryClass::Shard(account_id),
+                    self.shards[index].id.as_bytes().to_vec(),
+                );
+            }
+            AccountShard::Moving(index) => {
+                batch.set(
+                    DirectoryClass::Shard(account_id),
+                    format!("{MOVING_MARKER}{}", self.shards[index].id).into_bytes(),
+                );
+            }
+            AccountShard::Unassigned => {
+                batch.clear(DirectoryClass::Shard(account_id));
+            }
+        }
+        dispatch!(self.primary(), store => store.write(batch.build()).await)?;
+        self.accounts
+            .insert_with_ttl(account_id, shard, Instant::now() + self.cache_ttl);
+        self.version.fetch_add(1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Moves all data belonging to an account to a different shard.
+    /// Returns false if the account already lives on the requested shard.
+    pub async fn move_account(
+        &self,
+        lookup: &LookupStore,
+        account_id: u32,
+        shard_id: &str,
+    ) -> trc::Result<bool> {
+        let to = self.shard_index(shard_id)?;
+
+        // Obtain a cluster-wide lock on the account
+        let lock_key = format!("shard:{account_id}").into_bytes();
+        if lookup
+            .counter_incr(lock_key.clone(), 1, Some(LOCK_EXPIRY), true)
+            .await?
+            != 1
+        {
+            return Err(trc::StoreEvent::AccountLocked
+                .into_err()
+                .account_id(account_id));
+        }
+
+        let result = self.move_locked_account(account_id, to).await;
+
+        if let Err(err) = lookup.counter_delete(lock_key).await {
+            trc::error!(err
+                .details("Failed to release account lock")
+                .account_id(account_id));
+        }
+
+        result
+    }
+
+    async fn move_locked_account(&self, account_id: u32, to: usize) -> trc::Result<bool> {
+        // Read the current assignment from the primary store, an interrupted
+        // move leaves the account marked as moving off its source shard
+        self.accounts.remove(&account_id);
+        let from = match self.assigned_shard(account_id).await? {
+            AccountShard::Unassigned => PRIMARY,
+            AccountShard::Assigned(shard) if shard == to => return Ok(false),
+            AccountShard::Assigned(shard) | AccountShard::Moving(shard) => shard,
+        };
+        if from == to {
+            self.set_account_shard(account_id, AccountShard::Assigned(to))
+                .await?;
+            return Ok(false);
+        }
+
+        // Block writes to the account and wait for the other nodes
+        // to drop their cached assignment before copying any data
+        self.set_account_shard(account_id, AccountShard::Moving(from))
+            .await?;
+        tokio::time::sleep(self.move_delay).await;
+
+        let source = &self.shards[from].store;
+        let target = &self.shards[to].store;
+
+        // Remove any leftovers from an earlier interrupted move
+        delete_account_data(target, account_id).await?;
+
+        // Copy account data and counters
+        let mut total = 0;
+        for subspace in ACCOUNT_SUBSPACES {
+            total += copy_subspace(source, target, subspace, account_id).await?;
+        }
+        total += copy_counters(source, target, account_id).await?;
+
+        // Update the account shard and delete the old copy once
+        // no node is reading from it anymore
+        self.set_account_shard(account_id, AccountShard::Assigned(to))
+            .await?;
+        tokio::time::sleep(self.move_delay).await;
+        delete_account_data(source, account_id).await?;
+
+        trc::event!(
+            Store(trc::StoreEvent::AccountMoved),
+            AccountId = account_id,
+            From = self.shards[from].id.clone(),
+            To = self.shards[to].id.clone(),
+            Total = total,
+        );
+
+        Ok(true)
+    }
+
+    pub async fn get_blob(&self, key: &[u8], range: Range<usize>) -> trc::Result<Option<Vec<u8>>> {
+        dispatch!(self.primary(), store => store.get_blob(key, range).await)
+    }
+
+    pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        dispatch!(self.primary(), store => store.put_blob(key, data).await)
+    }
+
+    pub async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        dispatch!(self.primary(), store => store.delete_blob(key).await)
+    }
+
+    pub async fn get_value<U>(&self, key: impl Key) -> trc::Result<Option<U>>
+    where
+        U: Deserialize + 'static,
+    {
+        let shard = self.key_shard(&key).await?;
+        dispatch!(&self.shards[shard].store, store => store.get_value(key).await)
+    }
+
+    pub async fn get_bitmap(
+        &self,
+        key: BitmapKey<BitmapClass<u32>>,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        let shard = self.account_shard(key.account_id).await?;
+        dispatch!(&self.shards[shard].store, store => store.get_bitmap(key).await)
+    }
+
+    pub async fn get_counter(
+        &self,
+        key: impl Into<ValueKey<ValueClass<u32>>> + Sync + Send,
+    ) -> trc::Result<i64> {
+        let key = key.into();
+        let shard = self.key_shard(&key).await?;
+        dispatch!(&self.shards[shard].store, store => store.get_counter(key).await)
+    }
+
+    pub async fn iterate<T: Key>(
+        &self,
+        params: IterateParams<T>,
+        mut cb: impl for<'x> FnMut(&'x [u8], &'x [u8]) -> trc::Result<bool> + Sync + Send,
+    ) -> trc::Result<()> {
+        if let Some(shard) = self.range_shard(&params.begin, &params.end).await? {
+            return dispatch!(&self.shards[shard].store, store => store.iterate(params, cb).await);
+        }
+
+        // Ranges spanning multiple accounts are iterated one shard at a time
+        let first = params.first;
+        let mut is_done = false;
+        for shard in &self.shards {
+            dispatch!(&shard.store, store => store
+                .iterate(params.clone(), |key, value| {
+                    is_done = !cb(key, value)? || first;
+                    Ok(!is_done)
+                })
+                .await)?;
+
+            if is_done {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_range(&self, from: impl Key, to: impl Key) -> trc::Result<()> {
+        if let Some(shard) = self.range_shard(&from, &to).await? {
+            dispatch!(&self.shards[shard].store, store => store.delete_range(from, to).await)
+        } else {
+            for shard in &self.shards {
+                dispatch!(&shard.store, store => store
+                    .delete_range(from.clone(), to.clone())
+                    .await)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    pub async fn purge_store(&self) -> trc::Result<()> {
+        for shard in &self.shards {
+            dispatch!(&shard.store, store => store.purge_store().await)?;
+        }
+
+        Ok(())
+    }
+
+    // Batches are written atomically when all their data lives on a single shard.
+    // Batches that also update data kept elsewhere, such as the quotas and blob
+    // links stored on the primary store, are split and written one shard at a
+    // time (atomically within each shard only). The shard creating documents or
+    // asserting values is written first and the document ids it assigns are
+    // passed on to the remaining shards.
+    pub async fn write(&self, batch: Batch) -> trc::Result<AssignedIds> {
+        let mut targets = Vec::with_capacity(batch.ops.len());
+        let mut account_id = u32::MAX;
+        let mut last_owner = None;
+        for op in &batch.ops {
+            let owner = match op {
+                Operation::AccountId {
+                    account_id: account_id_,
+                } => {
+                    account_id = *account_id_;
+                    targets.push(None);
+                    continue;
+                }
+                Operation::Value { class, .. } | Operation::AssertValue { class, .. } => {
+                    class.account(account_id)
+                }
+                Operation::Index { .. } | Operation::Bitmap { .. } | Operation::Log { .. } => {
+                    Some(account_id)
+                }
+                Operation::Collection { .. }
+                | Operation::DocumentId { .. }
+                | Operation::ChangeId { .. } => {
+                    targets.push(None);
+                    continue;
+                }
+            };
+
+            let shard = match (owner, last_owner) {
+                (Some(owner), Some((last_owner, shard))) if owner == last_owner => shard,
+                (Some(owner), _) => {
+                    let shard = self.writable_shard(owner).await?;
+                    last_owner = Some((owner, shard));
+                    shard
+                }
+                (None, _) => PRIMARY,
+            };
+            targets.push(Some(shard));
+        }
+
+        let mut shards = targets.iter().flatten();
+        let first = shards.next().copied().unwrap_or(PRIMARY);
+        if shards.all(|shard| *shard == first) {
+            dispatch!(&self.shards[first].store, store => store.write(batch).await)
+        } else {
+            self.write_split(batch, targets).await
+        }
+    }
+
+    async fn write_split(
+        &self,
+        batch: Batch,
+        targets: Vec<Option<usize>>,
+    ) -> trc::Result<AssignedIds> {
+        let mut parts: Vec<ShardBatch> = Vec::new();
+        let mut lead = None;
+        let mut account_id = u32::MAX;
+        let mut collection = u8::MAX;
+        let mut document = BatchDocument::Id(u32::MAX);
+        let mut change_id = u64::MAX;
+        let mut created = 0;
+
+        for (op, shard) in batch.ops.into_iter().zip(targets) {
+            match op {
+                Operation::AccountId {
+                    account_id: account_id_,
+                } => {
+                    account_id = account_id_;
+                    continue;
+                }
+                Operation::Collection {
+                    collection: collection_,
+                } => {
+                    collection = collection_;
+                    continue;
+                }
+                Operation::DocumentId { document_id } => {
+                    document = BatchDocument::Id(document_id);
+                    continue;
+                }
+                Operation::ChangeId {
+                    change_id: change_id_,
+                } => {
+                    change_id = change_id_;
+                    continue;
+                }
+                _ => {}
+            }
+            let shard = shard.unwrap_or(PRIMARY);
+            let is_create = document == BatchDocument::Id(u32::MAX)
+                && matches!(
+                    op,
+                    Operation::Bitmap {
+                        class: BitmapClass::DocumentIds,
+                        set: true
+                    }
+                );
+
+            // Document ids can only be assigned and values asserted on a single shard
+            if is_create || matches!(op, Operation::AssertValue { .. }) {
+                match lead {
+                    Some(lead) if lead != shard => {
+                        return Err(trc::StoreEvent::NotSupported
+                            .into_err()
+                            .details("Batch creates or asserts data on multiple shards")
+                            .account_id(account_id));
+                    }
+                    Some(_) => {}
+                    None => {
+                        lead = Some(shard);
+                    }
+                }
+            }
+
+            // Add the operation along with its context to the shard batch
+            let part = if let Some(part) = parts.iter_mut().position(|part| part.shard == shard) {
+                &mut parts[part]
+            } else {
+                parts.push(ShardBatch::new(shard));
+                parts.last_mut().unwrap()
+            };
+            if part.account_id != account_id {
+                part.account_id = account_id;
+                part.ops.push(Operation::AccountId { account_id });
+            }
+            if part.collection != collection {
+                part.collection = collection;
+                part.ops.push(Operation::Collection { collection });
+            }
+            if part.change_id != change_id {
+                part.change_id = change_id;
+                part.ops.push(Operation::ChangeId { change_id });
+            }
+            if part.document != document {
+                part.document = document;
+                let document_id = match document {
+                    BatchDocument::Id(document_id) => document_id,
+                    BatchDocument::Created(idx) => {
+                        part.pending.push((part.ops.len(), idx));
+                        u32::MAX
+                    }
+                };
+                part.ops.push(Operation::DocumentId { document_id });
+            }
+            part.ops.push(op);
+            if is_create {
+                document = BatchDocument::Created(created);
+                part.document = document;
+                created += 1;
+            }
+        }
+
+        // Write the leading shard first
+        if let Some(lead) = lead {
+            parts.sort_by_key(|part| part.shard != lead);
+        }
+        let mut assigned_ids: Option<AssignedIds> = None;
+        for part in parts {
+            let mut ops = part.ops;
+            for (pos, idx) in part.pending {
+                ops[pos] = Operation::DocumentId {
+                    document_id: assigned_ids
+                        .as_ref()
+                        .ok_or_else(|| {
+                            trc::StoreEvent::NotSupported
+                                .into_err()
+                                .details("Batch references documents created on another shard")
+                                .account_id(account_id)
+                        })?
+                        .get_document_id(idx)?,
+                };
+            }
+
+            let ids = dispatch!(&self.shards[part.shard].store, store => store
+                .write(Batch { ops })
+                .await)?;
+            match &mut assigned_ids {
+                Some(assigned_ids) => assigned_ids.counter_ids.extend(ids.counter_ids),
+                None => assigned_ids = Some(ids),
+            }
+        }
+
+        Ok(assigned_ids.unwrap_or_default())
+    }
+}
+
+// Operations of a split batch targeting a single shard
+struct ShardBatch {
+    shard: usize,
+    ops: Vec<Operation>,
+    account_id: u32,
+    collection: u8,
+    document: BatchDocument,
+    change_id: u64,
+    // DocumentId operations waiting for the ids assigned by the leading shard
+    pending: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BatchDocument {
+    Id(u32),
+    // Index of a document created by the batch
+    Created(usize),
+}
+
+impl ShardBatch {
+    fn new(shard: usize) -> Self {
+        Self {
+            shard,
+            ops: Vec::new(),
+            account_id: u32::MAX,
+            collection: u8::MAX,
+            document: BatchDocument::Id(u32::MAX),
+            change_id: u64::MAX,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl AccountShard {
+    fn index(&self) -> Option<usize> {
+        match self {
+            AccountShard::Unassigned => None,
+            AccountShard::Assigned(shard) | AccountShard::Moving(shard) => Some(*shard),
+        }
+    }
+}
+
+fn account_key(subspace: u8, account_id: u32) -> AnyKey<Vec<u8>> {
+    AnyKey {
+        subspace,
+        key: KeySerializer::new(U32_LEN).write(account_id).finalize(),
+    }
+}
+
+async fn delete_account_data(store: &Store, account_id: u32) -> trc::Result<()> {
+    for subspace in ACCOUNT_SUBSPACES {
+        dispatch!(store, store => store
+            .delete_range(
+                account_key(subspace, account_id),
+                account_key(subspace, account_id + 1),
+            )
+            .await)?;
+    }
+
+    let (from_key, to_key) = counter_range(account_id);
+    dispatch!(store, store => store.delete_range(from_key, to_key).await)
+}
+
+async fn copy_subspace(
+    source: &Store,
+    target: &Store,
+    subspace: u8,
+    account_id: u32,
+) -> trc::Result<usize> {
+    let mut from_key = account_key(subspace, account_id);
+    let to_key = account_key(subspace, account_id + 1);
+    let mut total = 0;
+
+    loop {
+        let mut entries = Vec::new();
+        dispatch!(source, store => store
+            .iterate(
+                IterateParams::new(from_key.clone(), to_key.clone()).set_values(matches!(
+                    subspace,
+                    SUBSPACE_PROPERTY | SUBSPACE_FTS_INDEX | SUBSPACE_LOGS
+                )),
+                |key, value| {
+                    entries.push((key.to_vec(), value.to_vec()));
+                    Ok(entries.len() < CHUNK_SIZE)
+                },
+            )
+            .await)?;
+        let Some((last_key, _)) = entries.last() else {
+            break;
+        };
+        let is_last = entries.len() < CHUNK_SIZE;
+        from_key.key = last_key.clone();
+        from_key.key.push(0);
+        total += entries.len();
+
+        let mut batch = BatchBuilder::new();
+        batch.with_account_id(account_id);
+        for (key, value) in entries {
+            copy_key(&mut batch, subspace, key, value).ok_or_else(|| {
+                trc::StoreEvent::DataCorruption
+                    .into_err()
+                    .details("Failed to parse key")
+                    .id(subspace as u64)
+                    .account_id(account_id)
+            })?;
+        }
+        dispatch!(target, store => store.write(batch.build()).await)?;
+
+        if is_last {
+            break;
+        }
+    }
+
+    Ok(total)
+}
+
+fn copy_key(batch: &mut BatchBuilder, subspace: u8, key: Vec<u8>, value: Vec<u8>) -> Option<()> {
+    const BM_MARKER: u8 = 1 << 7;
+
+    match subspace {
+        SUBSPACE_PROPERTY | SUBSPACE_FTS_INDEX | SUBSPACE_SNOOZE => {
+            batch.ops.push(Operation::Value {
+                class: ValueClass::Any(AnyClass { subspace, key }),
+                op: ValueOp::Set(MaybeDynamicValue::Static(value)),
+            });
+            return Some(());
+        }
+        SUBSPACE_LOGS => {
+            batch.ops.push(Operation::Collection {
+                collection: *key.get(U32_LEN)?,
+            });
+            batch.ops.push(Operation::ChangeId {
+                change_id: key.as_slice().deserialize_be_u64(U32_LEN + 1).ok()?,
+            });
+            batch.ops.push(Operation::Log {
+                set: MaybeDynamicValue::Static(value),
+            });
+            return Some(());
+        }
+        _ => {}
+    }
+
+    let document_id = key
+        .as_slice()
+        .deserialize_be_u32(key.len().checked_sub(U32_LEN)?)
+        .ok()?;
+    let payload = key.get(..key.len() - U32_LEN)?;
+    let (collection, op) = match subspace {
+        SUBSPACE_BITMAP_ID => (
+            *payload.get(U32_LEN)?,
+            Operation::Bitmap {
+                class: BitmapClass::DocumentIds,
+                set: true,
+            },
+        ),
+        SUBSPACE_BITMAP_TAG => {
+            let field = *payload.get(U32_LEN + 1)?;
+            let value = payload.get(U32_LEN + 2..)?;
+            (
+                *payload.get(U32_LEN)?,
+                Operation::Bitmap {
+                    class: BitmapClass::Tag {
+                        field: field & !BM_MARKER,
+                        value: if field & BM_MARKER != 0 {
+                            TagValue::Text(value.to_vec())
+                        } else {
+                            TagValue::Id(MaybeDynamicId::Static(value.read_leb128()?.0))
+                        },
+                    },
+                    set: true,
+                },
+            )
+        }
+        SUBSPACE_BITMAP_TEXT => {
+            let (field, collection) = (payload.last()?, payload.get(payload.len() - 2)?);
+            let token = payload.get(U32_LEN..payload.len() - 2)?;
+            let mut hash = [0u8; 8];
+            let len = if token.len() > 8 {
+                hash.copy_from_slice(token.get(..8)?);
+                *token.get(8)?
+            } else {
+                hash.get_mut(..token.len())?.copy_from_slice(token);
+                token.len() as u8
+            };
+            (
+                *collection,
+                Operation::Bitmap {
+                    class: BitmapClass::Text {
+                        field: *field,
+                        token: BitmapHash { hash, len },
+                    },
+                    set: true,
+                },
+            )
+        }
+        SUBSPACE_INDEXES => (
+            *payload.get(U32_LEN)?,
+            Operation::Index {
+                field: *payload.get(U32_LEN + 1)?,
+                key: payload.get(U32_LEN + 2..)?.to_vec(),
+                set: true,
+            },
+        ),
+        _ => return None,
+    };
+
+    batch.ops.push(Operation::Collection { collection });
+    batch.ops.push(Operation::DocumentId { document_id });
+    batch.ops.push(op);
+
+    Some(())
+}
+
+async fn copy_counters(source: &Store, target: &Store, account_id: u32) -> trc::Result<usize> {
+    let (from_key, to_key) = counter_range(account_id);
+    let mut document_ids = Vec::new();
+    dispatch!(source, store => store
+        .iterate(
+            IterateParams::new(from_key, to_key).no_values(),
+            |key, _| {
+                document_ids.push(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                Ok(true)
+            },
+        )
+        .await)?;
+
+    let total = document_ids.len();
+    for document_ids in document_ids.chunks(CHUNK_SIZE) {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(COUNTER_COLLECTION);
+        for &document_id in document_ids {
+            let value = dispatch!(source, store => store
+                .get_counter(ValueKey {
+                    account_id,
+                    collection: COUNTER_COLLECTION,
+                    document_id,
+                    class: ValueClass::Property(COUNTER_PROPERTY),
+                })
+                .await)?;
+            batch.ops.push(Operation::DocumentId { document_id });
+            batch.ops.push(Operation::Value {
+                class: ValueClass::Property(COUNTER_PROPERTY),
+                op: ValueOp::AtomicAdd(value),
+            });
+        }
+        dispatch!(target, store => store.write(batch.build()).await)?;
+    }
+
+    Ok(total)
+}
+
+// Property counters are stored in the shared counter subspace
+fn counter_range(account_id: u32) -> (AnyKey<Vec<u8>>, AnyKey<Vec<u8>>) {
+    let key = |field: u8| AnyKey {
+        subspace: SUBSPACE_COUNTER,
+        key: KeySerializer::new(U32_LEN + 2)
+            .write(account_id)
+            .write(COUNTER_COLLECTION)
+            .write(field)
+            .finalize(),
+    };
+
+    (key(COUNTER_PROPERTY), key(COUNTER_PROPERTY + 1))
+}
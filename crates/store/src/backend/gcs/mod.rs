@@ -0,0 +1,363 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Display, io::Write, ops::Range, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use parking_lot::Mutex;
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_TYPE, RANGE},
+    Client, Method, RequestBuilder, StatusCode,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{RsaKeyPair, RSA_PKCS1_SHA256},
+};
+use serde::Deserialize;
+use utils::{
+    codec::base32_custom::Base32Writer,
+    config::{utils::AsKey, Config},
+};
+
+use crate::write::now;
+
+use super::http_range;
+
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+pub struct GcsStore {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    credentials: Option<ServiceAccount>,
+    token: Mutex<Option<AccessToken>>,
+    prefix: Option<String>,
+    max_retries: u32,
+    retry_wait: Duration,
+}
+
+struct ServiceAccount {
+    client_email: String,
+    token_uri: String,
+    key_pair: RsaKeyPair,
+}
+
+#[derive(Clone)]
+struct AccessToken {
+    token: String,
+    expires: u64,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountFile {
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl GcsStore {
+    pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let bucket = config.value_require((&prefix, "bucket"))?.to_string();
+        let endpoint = config
+            .value((&prefix, "endpoint"))
+            .unwrap_or(DEFAULT_ENDPOINT)
+            .trim_end_matches('/')
+            .to_string();
+        let credentials = if let Some(contents) = config.value((&prefix, "credentials")) {
+            let contents = contents.to_string();
+            match ServiceAccount::parse(&contents) {
+                Ok(credentials) => Some(credentials),
+                Err(err) => {
+                    config.new_build_error((&prefix, "credentials"), err);
+                    return None;
+                }
+            }
+        } else {
+            None
+        };
+        let timeout = config
+            .property_or_default::<Duration>((&prefix, "timeout"), "30s")
+            .unwrap_or_else(|| Duration::from_secs(30));
+
+        Some(GcsStore {
+            client: Client::builder()
+                .timeout(timeout)
+                .danger_accept_invalid_certs(
+                    config
+                        .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
+                        .unwrap_or(false),
+                )
+                .build()
+                .map_err(|err| {
+                    config.new_build_error(
+                        prefix.as_str(),
+                        format!("Failed to create HTTP client: {err}"),
+                    )
+                })
+                .ok()?,
+            endpoint,
+            bucket,
+            credentials,
+            token: Mutex::new(None),
+            prefix: config.value((&prefix, "key-prefix")).map(|s| s.to_string()),
+            max_retries: config
+                .property_or_default((&prefix, "max-retries"), "3")
+                .unwrap_or(3),
+            retry_wait: config
+                .property_or_default::<Duration>((&prefix, "retry-wait"), "500ms")
+                .unwrap_or_else(|| Duration::from_millis(500)),
+        })
+    }
+
+    pub(crate) async fn get_blob(
+        &self,
+        key: &[u8],
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        // Empty ranges cannot be expressed as a Range header
+        if range.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+
+        let url = format!(
+            "{}/storage/v1/b/{}/o/{}?alt=media",
+            self.endpoint,
+            self.bucket,
+            self.build_key(key)
+        );
+        let range = http_range(range);
+        let response = self
+            .send(|client| {
+                let request = client.request(Method::GET, &url);
+                if let Some(range) = &range {
+                    request.header(RANGE, range)
+                } else {
+                    request
+                }
+            })
+            .await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            // Range starts beyond the end of the object
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(Some(Vec::new())),
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(|bytes| Some(bytes.to_vec()))
+                .map_err(into_error),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        let url = format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            self.endpoint,
+            self.bucket,
+            self.build_key(key)
+        );
+        let response = self
+            .send(|client| {
+                client
+                    .request(Method::POST, &url)
+                    .header(CONTENT_TYPE, "application/octet-stream")
+                    .body(data.to_vec())
+            })
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let url = format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.endpoint,
+            self.bucket,
+            self.build_key(key)
+        );
+        let response = self
+            .send(|client| client.request(Method::DELETE, &url))
+            .await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(status_error(status, response).await),
+        }
+    }
+
+    async fn send(
+        &self,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> trc::Result<reqwest::Response> {
+        let mut retry = 0;
+        loop {
+            let mut request = build(&self.client);
+            if let Some(token) = self.access_token().await? {
+                request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+            }
+
+            match request.send().await {
+                Ok(response)
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && retry == 0
+                        && self.credentials.is_some() =>
+                {
+                    // Token revoked or expired early, obtain a new one
+                    *self.token.lock() = None;
+                }
+                Ok(response)
+                    if retry < self.max_retries
+                        && (response.status().is_server_error()
+                            || response.status() == StatusCode::TOO_MANY_REQUESTS) => {}
+                Ok(response) => return Ok(response),
+                Err(err) if retry < self.max_retries && (err.is_timeout() || err.is_connect()) => {}
+                Err(err) => return Err(into_error(err)),
+            }
+
+            retry += 1;
+            tokio::time::sleep(self.retry_wait * retry).await;
+        }
+    }
+
+    async fn access_token(&self) -> trc::Result<Option<String>> {
+        let Some(credentials) = &self.credentials else {
+            return Ok(None);
+        };
+
+        if let Some(token) = self.token.lock().as_ref() {
+            if token.expires > now() + 60 {
+                return Ok(Some(token.token.clone()));
+            }
+        }
+
+        let response = self
+            .client
+            .post(&credentials.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", credentials.build_jwt()?.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(into_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(status, response).await);
+        }
+        let response =
+            serde_json::from_slice::<TokenResponse>(&response.bytes().await.map_err(into_error)?)
+                .map_err(into_error)?;
+        let token = AccessToken {
+            token: response.access_token,
+            expires: now() + response.expires_in.unwrap_or(3600),
+        };
+        let result = token.token.clone();
+        *self.token.lock() = Some(token);
+
+        Ok(Some(result))
+    }
+
+    fn build_key(&self, key: &[u8]) -> String {
+        if let Some(prefix) = &self.prefix {
+            let mut writer =
+                Base32Writer::with_raw_capacity(prefix.len() + (key.len().div_ceil(4) * 5));
+            writer.push_string(prefix);
+            writer.write_all(key).unwrap();
+            encode_object_name(&writer.finalize())
+        } else {
+            Base32Writer::from_bytes(key).finalize()
+        }
+    }
+}
+
+impl ServiceAccount {
+    fn parse(contents: &str) -> Result<Self, String> {
+        let account = serde_json::from_str::<ServiceAccountFile>(contents)
+            .map_err(|err| format!("Failed to parse service account JSON: {err}"))?;
+        let key = pem::parse(account.private_key.as_bytes())
+            .map_err(|err| format!("Failed to parse service account private key: {err}"))?;
+        let key_pair = RsaKeyPair::from_pkcs8(key.contents())
+            .map_err(|err| format!("Invalid service account private key: {err}"))?;
+
+        Ok(ServiceAccount {
+            client_email: account.client_email,
+            token_uri: account
+                .token_uri
+                .unwrap_or_else(|| "https://oauth2.googleapis.com/token".to_string()),
+            key_pair,
+        })
+    }
+
+    fn build_jwt(&self) -> trc::Result<String> {
+        let issued_at = now();
+        let mut jwt = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#);
+        jwt.push('.');
+        jwt.push_str(
+            &URL_SAFE_NO_PAD.encode(
+                serde_json::json!({
+                    "iss": self.client_email,
+                    "scope": SCOPE,
+                    "aud": self.token_uri,
+                    "iat": issued_at,
+                    "exp": issued_at + 3600,
+                })
+                .to_string(),
+            ),
+        );
+
+        let mut signature = vec![0; self.key_pair.public().modulus_len()];
+        self.key_pair
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                jwt.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|err| trc::StoreEvent::CryptoError.reason(err))?;
+        jwt.push('.');
+        jwt.push_str(&URL_SAFE_NO_PAD.encode(signature));
+
+        Ok(jwt)
+    }
+}
+
+fn encode_object_name(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+async fn status_error(status: StatusCode, response: reqwest::Response) -> trc::Error {
+    trc::StoreEvent::GcsError
+        .reason(response.text().await.unwrap_or_default())
+        .ctx(trc::Key::Code, status.as_u16())
+}
+
+#[inline(always)]
+fn into_error(err: impl Display) -> trc::Error {
+    trc::StoreEvent::GcsError.reason(err)
+}
@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+#[cfg(feature = "azure")]
+pub mod azure;
 #[cfg(feature = "enterprise")]
 pub mod composite;
 #[cfg(feature = "elastic")]
@@ -11,6 +13,8 @@ pub mod elastic;
 #[cfg(feature = "foundation")]
 pub mod foundationdb;
 pub mod fs;
+#[cfg(feature = "gcs")]
+pub mod gcs;
 pub mod memory;
 #[cfg(feature = "mysql")]
 pub mod mysql;
@@ -24,10 +28,20 @@ pub mod rocksdb;
 pub mod s3;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
+pub mod tiered;
 
 pub const MAX_TOKEN_LENGTH: usize = (u8::MAX >> 1) as usize;
 pub const MAX_TOKEN_MASK: usize = MAX_TOKEN_LENGTH - 1;
 
+#[cfg(any(feature = "azure", feature = "gcs"))]
+pub(crate) fn http_range(range: std::ops::Range<usize>) -> Option<String> {
+    match (range.start, range.end) {
+        (0, usize::MAX) => None,
+        (start, usize::MAX) => Some(format!("bytes={start}-")),
+        (start, end) => Some(format!("bytes={}-{}", start, end.saturating_sub(1))),
+    }
+}
+
 #[allow(dead_code)]
 fn deserialize_i64_le(key: &[u8], bytes: &[u8]) -> trc::Result<i64> {
     Ok(i64::from_le_bytes(bytes[..].try_into().map_err(|_| {
@@ -108,6 +108,8 @@ impl MysqlStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_BLOB_TIER,
+            SUBSPACE_SNOOZE,
         ] {
             let table = char::from(table);
             conn.query_drop(format!(
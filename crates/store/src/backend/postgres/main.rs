@@ -96,6 +96,8 @@ impl PostgresStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_BLOB_TIER,
+            SUBSPACE_SNOOZE,
         ] {
             let table = char::from(table);
             conn.execute(
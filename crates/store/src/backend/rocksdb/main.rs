@@ -90,6 +90,8 @@ impl RocksDbStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_BLOB_TIER,
+            SUBSPACE_SNOOZE,
         ] {
             let cf_opts = Options::default();
             cfs.push(ColumnFamilyDescriptor::new(
@@ -107,6 +107,8 @@ impl SqliteStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_BLOB_TIER,
+            SUBSPACE_SNOOZE,
         ] {
             let table = char::from(table);
             conn.execute(
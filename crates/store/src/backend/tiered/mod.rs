@@ -0,0 +1,332 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{ops::Range, time::Duration};
+
+use trc::{AddContext, StoreEvent};
+use utils::{
+    config::{utils::AsKey, Config},
+    BlobHash, BLOB_HASH_LEN,
+};
+
+use crate::{
+    write::{key::DeserializeBigEndian, now, BatchBuilder, BlobOp, ValueClass},
+    BlobBackend, Deserialize, IterateParams, Serialize, Store, Stores, ValueKey, U64_LEN,
+};
+
+pub struct TieredBlob {
+    pub hot: BlobBackend,
+    pub cold: BlobBackend,
+    pub store: Store,
+    pub offload_after: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobTier {
+    Hot = 0,
+    Cold = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobTierInfo {
+    pub tier: BlobTier,
+    pub created: u64,
+}
+
+impl TieredBlob {
+    pub fn open(config: &mut Config, prefix: impl AsKey, stores: &Stores) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let hot = Self::blob_backend(config, (&prefix, "hot"), stores)?;
+        let cold = Self::blob_backend(config, (&prefix, "cold"), stores)?;
+        let store_id = config
+            .value((&prefix, "store"))
+            .or_else(|| config.value("storage.data"))
+            .map(|id| id.to_string());
+        let store = if let Some(store) = store_id
+            .as_ref()
+            .and_then(|store_id| stores.stores.get(store_id))
+        {
+            store.clone()
+        } else {
+            config.new_build_error(
+                (&prefix, "store"),
+                format!(
+                    "Data store {} not found",
+                    store_id.as_deref().unwrap_or_default()
+                ),
+            );
+            return None;
+        };
+
+        Some(TieredBlob {
+            hot,
+            cold,
+            store,
+            offload_after: config
+                .property_or_default::<Duration>((&prefix, "offload.after"), "90d")
+                .unwrap_or(Duration::from_secs(90 * 86400))
+                .as_secs(),
+        })
+    }
+
+    fn blob_backend(config: &mut Config, key: impl AsKey, stores: &Stores) -> Option<BlobBackend> {
+        let key = key.as_key();
+        let store_id = config.value_require(&key)?.to_string();
+        match stores.blob_stores.get(&store_id) {
+            Some(store) if matches!(store.backend, BlobBackend::Tiered(_)) => {
+                config.new_build_error(key, "Tiered blob stores cannot be nested");
+                None
+            }
+            Some(store) => Some(store.backend.clone()),
+            None => {
+                config.new_build_error(key, format!("Blob store {store_id} not found"));
+                None
+            }
+        }
+    }
+
+    pub async fn get_blob(
+        &self,
+        key: &[u8],
+        read_range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let tier = match BlobHash::try_from_hash_slice(key) {
+            Ok(hash) => self
+                .tier_info(&hash)
+                .await
+                .caused_by(trc::location!())?
+                .map(|info| info.tier),
+            Err(_) => None,
+        };
+
+        if tier != Some(BlobTier::Cold) {
+            if let Some(data) = get_blob(&self.hot, key, read_range.clone()).await? {
+                return Ok(Some(data));
+            }
+        }
+
+        // Blobs moved while being read, or written before the tier was tracked
+        get_blob(&self.cold, key, read_range).await
+    }
+
+    pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        let hash = BlobHash::try_from_hash_slice(key).ok();
+        let tier_info = if let Some(hash) = &hash {
+            self.tier_info(hash).await.caused_by(trc::location!())?
+        } else {
+            None
+        };
+
+        if tier_info.is_some_and(|info| info.tier == BlobTier::Cold) {
+            // Content-addressed blob already offloaded
+            return Ok(());
+        }
+
+        put_blob(&self.hot, key, data).await?;
+
+        // Deduplicated blobs keep their original creation time
+        if let (Some(hash), None) = (hash, tier_info) {
+            self.set_tier_info(
+                hash,
+                BlobTierInfo {
+                    tier: BlobTier::Hot,
+                    created: now(),
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let deleted_hot = delete_blob(&self.hot, key).await?;
+        let deleted_cold = delete_blob(&self.cold, key).await?;
+
+        if let Ok(hash) = BlobHash::try_from_hash_slice(key) {
+            self.store
+                .write(
+                    BatchBuilder::new()
+                        .clear(BlobOp::Tier { hash })
+                        .build_batch(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(deleted_hot || deleted_cold)
+    }
+
+    pub async fn tier_info(&self, hash: &BlobHash) -> trc::Result<Option<BlobTierInfo>> {
+        self.store
+            .get_value::<BlobTierInfo>(ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: ValueClass::Blob(BlobOp::Tier { hash: hash.clone() }),
+            })
+            .await
+    }
+
+    async fn set_tier_info(&self, hash: BlobHash, info: BlobTierInfo) -> trc::Result<()> {
+        self.store
+            .write(
+                BatchBuilder::new()
+                    .set(BlobOp::Tier { hash }, info.serialize())
+                    .build_batch(),
+            )
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+
+    pub async fn offload_blobs(&self) -> trc::Result<()> {
+        // Find blobs in the hot tier older than the offload threshold
+        let from_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Tier {
+                hash: BlobHash::default(),
+            }),
+        };
+        let to_key = ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Tier {
+                hash: BlobHash::new_max(),
+            }),
+        };
+        let offload_before = now().saturating_sub(self.offload_after);
+        let mut offload = Vec::new();
+        self.store
+            .iterate(
+                IterateParams::new(from_key, to_key).ascending(),
+                |key, value| {
+                    let info = BlobTierInfo::deserialize(value)?;
+                    if info.tier == BlobTier::Hot && info.created <= offload_before {
+                        offload.push((
+                            BlobHash::try_from_hash_slice(key.get(0..BLOB_HASH_LEN).ok_or_else(
+                                || trc::Error::corrupted_key(key, None, trc::location!()),
+                            )?)
+                            .unwrap(),
+                            info,
+                        ));
+                    }
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        // Copy to the cold tier, then remove from the hot tier
+        for (hash, info) in offload {
+            let Some(data) = get_blob(&self.hot, hash.as_ref(), 0..usize::MAX).await? else {
+                continue;
+            };
+            put_blob(&self.cold, hash.as_ref(), &data).await?;
+            self.set_tier_info(
+                hash.clone(),
+                BlobTierInfo {
+                    tier: BlobTier::Cold,
+                    created: info.created,
+                },
+            )
+            .await?;
+            delete_blob(&self.hot, hash.as_ref()).await?;
+
+            trc::event!(
+                Store(StoreEvent::BlobOffload),
+                Key = hash.as_slice(),
+                Size = data.len(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+async fn get_blob(
+    backend: &BlobBackend,
+    key: &[u8],
+    read_range: Range<usize>,
+) -> trc::Result<Option<Vec<u8>>> {
+    match backend {
+        BlobBackend::Store(store) => store.get_blob(key, read_range).await,
+        BlobBackend::Fs(store) => store.get_blob(key, read_range).await,
+        #[cfg(feature = "s3")]
+        BlobBackend::S3(store) => store.get_blob(key, read_range).await,
+        #[cfg(feature = "azure")]
+        BlobBackend::Azure(store) => store.get_blob(key, read_range).await,
+        #[cfg(feature = "gcs")]
+        BlobBackend::Gcs(store) => store.get_blob(key, read_range).await,
+        #[cfg(feature = "enterprise")]
+        BlobBackend::Composite(store) => store.get_blob(key, read_range).await,
+        BlobBackend::Tiered(_) => Err(StoreEvent::NotSupported.into()),
+    }
+    .caused_by(trc::location!())
+}
+
+async fn put_blob(backend: &BlobBackend, key: &[u8], data: &[u8]) -> trc::Result<()> {
+    match backend {
+        BlobBackend::Store(store) => store.put_blob(key, data).await,
+        BlobBackend::Fs(store) => store.put_blob(key, data).await,
+        #[cfg(feature = "s3")]
+        BlobBackend::S3(store) => store.put_blob(key, data).await,
+        #[cfg(feature = "azure")]
+        BlobBackend::Azure(store) => store.put_blob(key, data).await,
+        #[cfg(feature = "gcs")]
+        BlobBackend::Gcs(store) => store.put_blob(key, data).await,
+        #[cfg(feature = "enterprise")]
+        BlobBackend::Composite(store) => store.put_blob(key, data).await,
+        BlobBackend::Tiered(_) => Err(StoreEvent::NotSupported.into()),
+    }
+    .caused_by(trc::location!())
+}
+
+async fn delete_blob(backend: &BlobBackend, key: &[u8]) -> trc::Result<bool> {
+    match backend {
+        BlobBackend::Store(store) => store.delete_blob(key).await,
+        BlobBackend::Fs(store) => store.delete_blob(key).await,
+        #[cfg(feature = "s3")]
+        BlobBackend::S3(store) => store.delete_blob(key).await,
+        #[cfg(feature = "azure")]
+        BlobBackend::Azure(store) => store.delete_blob(key).await,
+        #[cfg(feature = "gcs")]
+        BlobBackend::Gcs(store) => store.delete_blob(key).await,
+        #[cfg(feature = "enterprise")]
+        BlobBackend::Composite(store) => store.delete_blob(key).await,
+        BlobBackend::Tiered(_) => Err(StoreEvent::NotSupported.into()),
+    }
+    .caused_by(trc::location!())
+}
+
+impl Serialize for BlobTierInfo {
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(U64_LEN + 1);
+        bytes.push(self.tier as u8);
+        bytes.extend_from_slice(&self.created.to_be_bytes());
+        bytes
+    }
+}
+
+impl Deserialize for BlobTierInfo {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        Ok(BlobTierInfo {
+            tier: match bytes.first() {
+                Some(0) => BlobTier::Hot,
+                Some(1) => BlobTier::Cold,
+                _ => {
+                    return Err(trc::StoreEvent::DataCorruption
+                        .caused_by(trc::location!())
+                        .ctx(trc::Key::Value, bytes))
+                }
+            },
+            created: bytes.deserialize_be_u64(1)?,
+        })
+    }
+}
@@ -11,12 +11,18 @@ use utils::config::{cron::SimpleCron, utils::ParseValue, Config};
 use crate::{
     backend::fs::FsStore,
     write::purge::{PurgeSchedule, PurgeStore},
-    BlobStore, CompressionAlgo, FtsStore, LookupStore, QueryStore, Store, Stores,
+    BlobBackend, BlobStore, CompressionAlgo, FtsStore, LookupStore, QueryStore, Store, Stores,
 };
 
 #[cfg(feature = "s3")]
 use crate::backend::s3::S3Store;
 
+#[cfg(feature = "azure")]
+use crate::backend::azure::AzureStore;
+
+#[cfg(feature = "gcs")]
+use crate::backend::gcs::GcsStore;
+
 #[cfg(feature = "postgres")]
 use crate::backend::postgres::PostgresStore;
 
@@ -53,7 +59,6 @@ impl Stores {
 
     pub async fn parse_stores(&mut self, config: &mut Config) {
         let is_reload = !self.stores.is_empty();
-        #[cfg(feature = "enterprise")]
         let mut composite_stores = Vec::new();
         let store_ids = config
             .sub_keys("store", ".type")
@@ -194,6 +199,20 @@ impl Stores {
                             .insert(store_id, db.with_compression(compression_algo));
                     }
                 }
+                #[cfg(feature = "azure")]
+                "azure" => {
+                    if let Some(db) = AzureStore::open(config, prefix).await.map(BlobStore::from) {
+                        self.blob_stores
+                            .insert(store_id, db.with_compression(compression_algo));
+                    }
+                }
+                #[cfg(feature = "gcs")]
+                "gcs" => {
+                    if let Some(db) = GcsStore::open(config, prefix).await.map(BlobStore::from) {
+                        self.blob_stores
+                            .insert(store_id, db.with_compression(compression_algo));
+                    }
+                }
                 #[cfg(feature = "elastic")]
                 "elasticsearch" => {
                     if let Some(db) = ElasticSearchStore::open(config, prefix)
@@ -213,7 +232,10 @@ impl Stores {
                     }
                 }
                 #[cfg(feature = "enterprise")]
-                "sql-read-replica" | "distributed-blob" => {
+                "sql-read-replica" | "distributed-blob" | "sharded" => {
+                    composite_stores.push((store_id, protocol));
+                }
+                "tiered-blob" => {
                     composite_stores.push((store_id, protocol));
                 }
                 unknown => {
@@ -225,7 +247,6 @@ impl Stores {
             }
         }
 
-        #[cfg(feature = "enterprise")]
         for (id, protocol) in composite_stores {
             let prefix = ("store", id.as_str());
             let compression = config
@@ -235,7 +256,7 @@ impl Stores {
                 )
                 .unwrap_or(CompressionAlgo::None);
             match protocol.as_str() {
-                #[cfg(any(feature = "postgres", feature = "mysql"))]
+                #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 "sql-read-replica" => {
                     if let Some(db) = crate::backend::composite::read_replica::SQLReadReplica::open(
                         config,
@@ -255,6 +276,27 @@ impl Stores {
                         self.lookup_stores.insert(id.to_string(), db.into());
                     }
                 }
+                #[cfg(feature = "enterprise")]
+                "sharded" => {
+                    if let Some(db) = crate::backend::composite::sharded::ShardedStore::open(
+                        config,
+                        prefix,
+                        self,
+                        config.is_active_store(&id),
+                    )
+                    .await
+                    {
+                        let db = Store::Sharded(db.into());
+                        self.stores.insert(id.to_string(), db.clone());
+                        self.fts_stores.insert(id.to_string(), db.clone().into());
+                        self.blob_stores.insert(
+                            id.to_string(),
+                            BlobStore::from(db.clone()).with_compression(compression),
+                        );
+                        self.lookup_stores.insert(id.to_string(), db.into());
+                    }
+                }
+                #[cfg(feature = "enterprise")]
                 "distributed-blob" => {
                     if let Some(db) =
                         crate::backend::composite::distributed_blob::DistributedBlob::open(
@@ -268,6 +310,16 @@ impl Stores {
                         self.blob_stores.insert(id, store);
                     }
                 }
+                "tiered-blob" => {
+                    if let Some(db) = crate::backend::tiered::TieredBlob::open(config, prefix, self)
+                    {
+                        let store = BlobStore {
+                            backend: crate::BlobBackend::Tiered(db.into()),
+                            compression,
+                        };
+                        self.blob_stores.insert(id, store);
+                    }
+                }
                 _ => (),
             }
         }
@@ -351,6 +403,20 @@ impl Stores {
                 });
             }
         }
+        for (store_id, store) in &self.blob_stores {
+            if let BlobBackend::Tiered(store) = &store.backend {
+                self.purge_schedules.push(PurgeSchedule {
+                    cron: config
+                        .property_or_default::<SimpleCron>(
+                            ("store", store_id.as_str(), "offload.frequency"),
+                            "0 2 *",
+                        )
+                        .unwrap_or_else(|| SimpleCron::parse_value("0 2 *").unwrap()),
+                    store_id: store_id.clone(),
+                    store: PurgeStore::Tiered(store.clone()),
+                });
+            }
+        }
         for (store_id, store) in &self.lookup_stores {
             if matches!(store, LookupStore::Store(_)) {
                 self.purge_schedules.push(PurgeSchedule {
@@ -32,13 +32,20 @@ impl BlobStore {
                 Store::RocksDb(store) => store.get_blob(key, read_range).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.get_blob(key, read_range).await,
+                #[cfg(feature = "enterprise")]
+                Store::Sharded(store) => store.get_blob(key, read_range).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
             },
             BlobBackend::Fs(store) => store.get_blob(key, read_range).await,
             #[cfg(feature = "s3")]
             BlobBackend::S3(store) => store.get_blob(key, read_range).await,
+            #[cfg(feature = "azure")]
+            BlobBackend::Azure(store) => store.get_blob(key, read_range).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.get_blob(key, read_range).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.get_blob(key, read_range).await,
+            BlobBackend::Tiered(store) => store.get_blob(key, read_range).await,
         };
 
         trc::event!(
@@ -112,13 +119,20 @@ impl BlobStore {
                 Store::RocksDb(store) => store.put_blob(key, data.as_ref()).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.put_blob(key, data.as_ref()).await,
+                #[cfg(feature = "enterprise")]
+                Store::Sharded(store) => store.put_blob(key, data.as_ref()).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
             },
             BlobBackend::Fs(store) => store.put_blob(key, data.as_ref()).await,
             #[cfg(feature = "s3")]
             BlobBackend::S3(store) => store.put_blob(key, data.as_ref()).await,
+            #[cfg(feature = "azure")]
+            BlobBackend::Azure(store) => store.put_blob(key, data.as_ref()).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.put_blob(key, data.as_ref()).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.put_blob(key, data.as_ref()).await,
+            BlobBackend::Tiered(store) => store.put_blob(key, data.as_ref()).await,
         }
         .caused_by(trc::location!());
 
@@ -148,13 +162,20 @@ impl BlobStore {
                 Store::RocksDb(store) => store.delete_blob(key).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.delete_blob(key).await,
+                #[cfg(feature = "enterprise")]
+                Store::Sharded(store) => store.delete_blob(key).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
             },
             BlobBackend::Fs(store) => store.delete_blob(key).await,
             #[cfg(feature = "s3")]
             BlobBackend::S3(store) => store.delete_blob(key).await,
+            #[cfg(feature = "azure")]
+            BlobBackend::Azure(store) => store.delete_blob(key).await,
+            #[cfg(feature = "gcs")]
+            BlobBackend::Gcs(store) => store.delete_blob(key).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.delete_blob(key).await,
+            BlobBackend::Tiered(store) => store.delete_blob(key).await,
         }
         .caused_by(trc::location!());
 
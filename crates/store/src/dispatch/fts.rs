@@ -61,6 +61,24 @@ impl FtsStore {
         .caused_by(trc::location!())
     }
 
+    /// Returns the documents present in the index, or `None` when the
+    /// backend cannot enumerate them.
+    pub async fn indexed_documents(
+        &self,
+        account_id: u32,
+        collection: u8,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        match self {
+            FtsStore::Store(store) => store
+                .fts_indexed_documents(account_id, collection)
+                .await
+                .map(Some),
+            #[cfg(feature = "elastic")]
+            FtsStore::ElasticSearch(_) => Ok(None),
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn remove_all(&self, account_id: u32) -> trc::Result<()> {
         match self {
             FtsStore::Store(store) => store.fts_remove_all(account_id).await,
@@ -28,6 +28,8 @@ impl Store {
             Self::RocksDb(_) => "rocksdb",
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(_) => "read_replica",
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(_) => "sharded",
             Self::None => "none",
         }
     }
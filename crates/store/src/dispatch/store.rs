@@ -19,7 +19,8 @@ use crate::{
         Operation, ReportClass, ValueClass, ValueOp,
     },
     BitmapKey, Deserialize, IterateParams, Key, Store, ValueKey, SUBSPACE_BITMAP_ID,
-    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_INDEXES, SUBSPACE_LOGS, U32_LEN,
+    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_SNOOZE,
+    U32_LEN,
 };
 
 use super::DocumentSet;
@@ -52,6 +53,8 @@ impl Store {
             Self::RocksDb(store) => store.get_value(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_value(key).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.get_value(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -74,6 +77,8 @@ impl Store {
             Self::RocksDb(store) => store.get_bitmap(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_bitmap(key).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.get_bitmap(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -120,6 +125,8 @@ impl Store {
             Self::RocksDb(store) => store.iterate(params, cb).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.iterate(params, cb).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.iterate(params, cb).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!());
@@ -149,6 +156,8 @@ impl Store {
             Self::RocksDb(store) => store.get_counter(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_counter(key).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.get_counter(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -214,6 +223,8 @@ impl Store {
                 Self::RocksDb(store) => store.write(batch).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Self::SQLReadReplica(store) => store.write(batch).await,
+                #[cfg(feature = "enterprise")]
+                Self::Sharded(store) => store.write(batch).await,
                 Self::None => Err(trc::StoreEvent::NotConfigured.into()),
             }
             .caused_by(trc::location!())?;
@@ -261,6 +272,8 @@ impl Store {
             Self::RocksDb(store) => store.write(batch).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.write(batch).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.write(batch).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         };
 
@@ -317,6 +330,8 @@ impl Store {
             Self::RocksDb(store) => store.purge_store().await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.purge_store().await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.purge_store().await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -336,6 +351,8 @@ impl Store {
             Self::RocksDb(store) => store.delete_range(from, to).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.delete_range(from, to).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.delete_range(from, to).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -427,6 +444,7 @@ impl Store {
             SUBSPACE_BITMAP_TEXT,
             SUBSPACE_LOGS,
             SUBSPACE_INDEXES,
+            SUBSPACE_SNOOZE,
         ] {
             self.delete_range(
                 AnyKey {
@@ -509,6 +527,8 @@ impl Store {
             Self::RocksDb(store) => store.get_blob(key, range).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_blob(key, range).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.get_blob(key, range).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -528,6 +548,8 @@ impl Store {
             Self::RocksDb(store) => store.put_blob(key, data).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.put_blob(key, data).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.put_blob(key, data).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -547,6 +569,8 @@ impl Store {
             Self::RocksDb(store) => store.delete_blob(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.delete_blob(key).await,
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(store) => store.delete_blob(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
         .caused_by(trc::location!())
@@ -581,6 +605,8 @@ impl Store {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_BLOB_TIER,
+            SUBSPACE_SNOOZE,
         ] {
             self.delete_range(
                 AnyKey {
@@ -767,6 +793,8 @@ impl Store {
             (SUBSPACE_TELEMETRY_SPAN, true),
             (SUBSPACE_TELEMETRY_METRIC, true),
             (SUBSPACE_TELEMETRY_INDEX, true),
+            (SUBSPACE_BLOB_TIER, true),
+            (SUBSPACE_SNOOZE, true),
         ] {
             let from_key = crate::write::AnyKey {
                 subspace,
@@ -15,6 +15,7 @@ use nlp::{
     },
     tokenizers::word::WordTokenizer,
 };
+use roaring::RoaringBitmap;
 
 use crate::{
     backend::MAX_TOKEN_LENGTH,
@@ -334,4 +335,46 @@ impl Store {
 
         Ok(())
     }
+
+    pub async fn fts_indexed_documents(
+        &self,
+        account_id: u32,
+        collection: u8,
+    ) -> trc::Result<RoaringBitmap> {
+        let mut document_ids = RoaringBitmap::new();
+        self.iterate(
+            IterateParams::new(
+                ValueKey {
+                    account_id,
+                    collection,
+                    document_id: 0,
+                    class: ValueClass::FtsIndex(BitmapHash {
+                        hash: [0; 8],
+                        len: 1,
+                    }),
+                },
+                ValueKey {
+                    account_id: account_id + 1,
+                    collection,
+                    document_id: 0,
+                    class: ValueClass::FtsIndex(BitmapHash {
+                        hash: [0; 8],
+                        len: 1,
+                    }),
+                },
+            )
+            .no_values(),
+            |key, _| {
+                // Keys from all collections share the same range
+                if key.get(key.len() - U32_LEN - 1) == Some(&collection) {
+                    document_ids.insert(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                }
+
+                Ok(true)
+            },
+        )
+        .await?;
+
+        Ok(document_ids)
+    }
 }
@@ -25,6 +25,12 @@ use write::{purge::PurgeSchedule, BitmapClass, ValueClass};
 #[cfg(feature = "s3")]
 use backend::s3::S3Store;
 
+#[cfg(feature = "azure")]
+use backend::azure::AzureStore;
+
+#[cfg(feature = "gcs")]
+use backend::gcs::GcsStore;
+
 #[cfg(feature = "postgres")]
 use backend::postgres::PostgresStore;
 
@@ -60,6 +66,11 @@ pub(crate) const WITH_SUBSPACE: u32 = 1;
 pub trait Key: Sync + Send + Clone {
     fn serialize(&self, flags: u32) -> Vec<u8>;
     fn subspace(&self) -> u8;
+
+    /// Returns the account owning the key, if it holds account data
+    fn account(&self) -> Option<u32> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -150,9 +161,13 @@ pub const SUBSPACE_FTS_INDEX: u8 = b'g';
 pub const SUBSPACE_TELEMETRY_SPAN: u8 = b'o';
 pub const SUBSPACE_TELEMETRY_INDEX: u8 = b'w';
 pub const SUBSPACE_TELEMETRY_METRIC: u8 = b'x';
+pub const SUBSPACE_BLOB_TIER: u8 = b'y';
+pub const SUBSPACE_SNOOZE: u8 = b'z';
 
-pub const SUBSPACE_RESERVED_1: u8 = b'y';
-pub const SUBSPACE_RESERVED_2: u8 = b'z';
+// Mailbox message counters (Collection::Mailbox, Property::EmailIds) are
+// stored in the counter subspace rather than with the account properties
+pub const COUNTER_COLLECTION: u8 = 1;
+pub const COUNTER_PROPERTY: u8 = 84;
 
 #[derive(Clone)]
 pub struct IterateParams<T: Key> {
@@ -186,6 +201,8 @@ pub enum Store {
     RocksDb(Arc<RocksDbStore>),
     #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
     SQLReadReplica(Arc<backend::composite::read_replica::SQLReadReplica>),
+    #[cfg(feature = "enterprise")]
+    Sharded(Arc<backend::composite::sharded::ShardedStore>),
     #[default]
     None,
 }
@@ -208,8 +225,13 @@ pub enum BlobBackend {
     Fs(Arc<FsStore>),
     #[cfg(feature = "s3")]
     S3(Arc<S3Store>),
+    #[cfg(feature = "azure")]
+    Azure(Arc<AzureStore>),
+    #[cfg(feature = "gcs")]
+    Gcs(Arc<GcsStore>),
     #[cfg(feature = "enterprise")]
     Composite(Arc<backend::composite::distributed_blob::DistributedBlob>),
+    Tiered(Arc<backend::tiered::TieredBlob>),
 }
 
 #[derive(Clone)]
@@ -288,6 +310,26 @@ impl From<S3Store> for BlobStore {
     }
 }
 
+#[cfg(feature = "azure")]
+impl From<AzureStore> for BlobStore {
+    fn from(store: AzureStore) -> Self {
+        BlobStore {
+            backend: BlobBackend::Azure(Arc::new(store)),
+            compression: CompressionAlgo::None,
+        }
+    }
+}
+
+#[cfg(feature = "gcs")]
+impl From<GcsStore> for BlobStore {
+    fn from(store: GcsStore) -> Self {
+        BlobStore {
+            backend: BlobBackend::Gcs(Arc::new(store)),
+            compression: CompressionAlgo::None,
+        }
+    }
+}
+
 #[cfg(feature = "elastic")]
 impl From<ElasticSearchStore> for FtsStore {
     fn from(store: ElasticSearchStore) -> Self {
@@ -691,6 +733,7 @@ impl Store {
         match self {
             #[cfg(any(feature = "postgres", feature = "mysql"))]
             Store::SQLReadReplica(_) => true,
+            Store::Sharded(_) => true,
             _ => false,
         }
     }
@@ -699,6 +742,29 @@ impl Store {
     pub fn is_enterprise_store(&self) -> bool {
         false
     }
+
+    #[cfg(feature = "enterprise")]
+    pub fn shard_version(&self) -> u8 {
+        match self {
+            Store::Sharded(store) => store.version(),
+            _ => 0,
+        }
+    }
+
+    #[cfg(not(feature = "enterprise"))]
+    pub fn shard_version(&self) -> u8 {
+        0
+    }
+
+    #[cfg(feature = "enterprise")]
+    pub fn invalidate_shard_cache(&self) {
+        if let Store::Sharded(store) = self {
+            store.invalidate_cache();
+        }
+    }
+
+    #[cfg(not(feature = "enterprise"))]
+    pub fn invalidate_shard_cache(&self) {}
 }
 
 impl std::fmt::Debug for Store {
@@ -716,6 +782,8 @@ impl std::fmt::Debug for Store {
             Self::RocksDb(_) => f.debug_tuple("RocksDb").finish(),
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(_) => f.debug_tuple("SQLReadReplica").finish(),
+            #[cfg(feature = "enterprise")]
+            Self::Sharded(_) => f.debug_tuple("Sharded").finish(),
             Self::None => f.debug_tuple("None").finish(),
         }
     }
@@ -741,6 +809,8 @@ impl Stores {
             #[cfg(any(feature = "postgres", feature = "mysql"))]
             self.stores
                 .retain(|_, store| !matches!(store, Store::SQLReadReplica(_)));
+            self.stores
+                .retain(|_, store| !matches!(store, Store::Sharded(_)));
             self.blob_stores
                 .retain(|_, store| !matches!(store.backend, BlobBackend::Composite(_)));
         }
@@ -206,6 +206,10 @@ impl BatchBuilder {
         })
     }
 
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.ops.is_empty()
             || !self.ops.iter().any(|op| {
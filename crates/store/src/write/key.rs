@@ -8,13 +8,14 @@ use std::convert::TryInto;
 use utils::{codec::leb128::Leb128_, BLOB_HASH_LEN};
 
 use crate::{
-    BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ValueKey, SUBSPACE_ACL,
-    SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_BLOB_LINK,
-    SUBSPACE_BLOB_RESERVE, SUBSPACE_COUNTER, SUBSPACE_DIRECTORY, SUBSPACE_FTS_INDEX,
-    SUBSPACE_FTS_QUEUE, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_LOOKUP_VALUE, SUBSPACE_PROPERTY,
-    SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA, SUBSPACE_REPORT_IN,
-    SUBSPACE_REPORT_OUT, SUBSPACE_SETTINGS, SUBSPACE_TELEMETRY_INDEX, SUBSPACE_TELEMETRY_METRIC,
-    SUBSPACE_TELEMETRY_SPAN, U32_LEN, U64_LEN, WITH_SUBSPACE,
+    BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ValueKey, COUNTER_COLLECTION,
+    COUNTER_PROPERTY, SUBSPACE_ACL, SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT,
+    SUBSPACE_BLOB_LINK, SUBSPACE_BLOB_RESERVE, SUBSPACE_BLOB_TIER, SUBSPACE_COUNTER,
+    SUBSPACE_DIRECTORY, SUBSPACE_FTS_INDEX, SUBSPACE_FTS_QUEUE, SUBSPACE_INDEXES, SUBSPACE_LOGS,
+    SUBSPACE_LOOKUP_VALUE, SUBSPACE_PROPERTY, SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE,
+    SUBSPACE_QUOTA, SUBSPACE_REPORT_IN, SUBSPACE_REPORT_OUT, SUBSPACE_SETTINGS, SUBSPACE_SNOOZE,
+    SUBSPACE_TELEMETRY_INDEX, SUBSPACE_TELEMETRY_METRIC, SUBSPACE_TELEMETRY_SPAN, U32_LEN, U64_LEN,
+    WITH_SUBSPACE,
 };
 
 use super::{
@@ -181,6 +182,10 @@ impl Key for IndexKeyPrefix {
     fn subspace(&self) -> u8 {
         SUBSPACE_INDEXES
     }
+
+    fn account(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
 }
 
 impl IndexKeyPrefix {
@@ -194,6 +199,10 @@ impl Key for LogKey {
         SUBSPACE_LOGS
     }
 
+    fn account(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         {
             if (flags & WITH_SUBSPACE) != 0 {
@@ -214,6 +223,10 @@ impl<T: AsRef<ValueClass<u32>> + Sync + Send + Clone> Key for ValueKey<T> {
         self.class.as_ref().subspace(self.collection)
     }
 
+    fn account(&self) -> Option<u32> {
+        self.class.as_ref().account(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         self.class.as_ref().serialize(
             self.account_id,
@@ -272,6 +285,7 @@ impl<T: ResolveId> ValueClass<T> {
                 .write(collection)
                 .write(document_id)
                 .write::<&[u8]>(queue.hash.as_ref()),
+            ValueClass::Snooze(due) => serializer.write(account_id).write(*due).write(document_id),
             ValueClass::Blob(op) => match op {
                 BlobOp::Reserve { hash, until } => serializer
                     .write(account_id)
@@ -292,6 +306,7 @@ impl<T: ResolveId> ValueClass<T> {
                     .write((*id >> 32) as u32)
                     .write(u8::MAX)
                     .write(*id as u32),
+                BlobOp::Tier { hash } => serializer.write::<&[u8]>(hash.as_ref()),
             },
             ValueClass::Config(key) => serializer.write(key.as_slice()),
             ValueClass::Lookup(lookup) => match lookup {
@@ -305,6 +320,10 @@ impl<T: ResolveId> ValueClass<T> {
                     .write(2u8)
                     .write_leb128(uid.resolve_id(assigned_ids)),
                 DirectoryClass::UsedQuota(uid) => serializer.write(4u8).write_leb128(*uid),
+                DirectoryClass::Shard(uid) => serializer.write(7u8).write_leb128(*uid),
+                DirectoryClass::PasswordState(uid) => serializer
+                    .write(8u8)
+                    .write_leb128(uid.resolve_id(assigned_ids)),
                 DirectoryClass::MemberOf {
                     principal_id,
                     member_of,
@@ -390,6 +409,10 @@ impl<T: AsRef<[u8]> + Sync + Send + Clone> Key for IndexKey<T> {
         SUBSPACE_INDEXES
     }
 
+    fn account(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         let key = self.key.as_ref();
         {
@@ -414,6 +437,10 @@ impl<T: AsRef<BitmapClass<u32>> + Sync + Send + Clone> Key for BitmapKey<T> {
         self.class.as_ref().subspace()
     }
 
+    fn account(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         self.class.as_ref().serialize(
             self.account_id,
@@ -515,6 +542,10 @@ impl<T: AsRef<[u8]> + Sync + Send + Clone> Key for AnyKey<T> {
     fn subspace(&self) -> u8 {
         self.subspace
     }
+
+    fn account(&self) -> Option<u32> {
+        key_account(self.subspace, self.key.as_ref())
+    }
 }
 
 impl<T> ValueClass<T> {
@@ -533,7 +564,10 @@ impl<T> ValueClass<T> {
             | ValueClass::Config(v) => v.len(),
             ValueClass::Directory(d) => match d {
                 DirectoryClass::NameToId(v) | DirectoryClass::EmailToId(v) => v.len(),
-                DirectoryClass::Principal(_) | DirectoryClass::UsedQuota(_) => U32_LEN,
+                DirectoryClass::Principal(_)
+                | DirectoryClass::UsedQuota(_)
+                | DirectoryClass::Shard(_)
+                | DirectoryClass::PasswordState(_) => U32_LEN,
                 DirectoryClass::Members { .. } | DirectoryClass::MemberOf { .. } => U32_LEN * 2,
             },
             ValueClass::Blob(op) => match op {
@@ -541,8 +575,10 @@ impl<T> ValueClass<T> {
                 BlobOp::Commit { .. } | BlobOp::Link { .. } | BlobOp::LinkId { .. } => {
                     BLOB_HASH_LEN + U32_LEN * 2 + 2
                 }
+                BlobOp::Tier { .. } => BLOB_HASH_LEN + 1,
             },
             ValueClass::FtsQueue { .. } => BLOB_HASH_LEN + U64_LEN * 2,
+            ValueClass::Snooze(_) => U64_LEN + U32_LEN * 2,
             ValueClass::Queue(q) => match q {
                 QueueClass::Message(_) => U64_LEN,
                 QueueClass::MessageEvent(_) => U64_LEN * 2,
@@ -564,10 +600,20 @@ impl<T> ValueClass<T> {
         }
     }
 
+    pub fn account(&self, account_id: u32) -> Option<u32> {
+        match self {
+            ValueClass::Property(_) | ValueClass::FtsIndex(_) | ValueClass::Snooze(_) => {
+                Some(account_id)
+            }
+            ValueClass::Any(any) => key_account(any.subspace, &any.key),
+            _ => None,
+        }
+    }
+
     pub fn subspace(&self, collection: u8) -> u8 {
         match self {
             ValueClass::Property(field) => {
-                if *field == 84 && collection == 1 {
+                if *field == COUNTER_PROPERTY && collection == COUNTER_COLLECTION {
                     SUBSPACE_COUNTER
                 } else {
                     SUBSPACE_PROPERTY
@@ -576,11 +622,13 @@ impl<T> ValueClass<T> {
             ValueClass::Acl(_) => SUBSPACE_ACL,
             ValueClass::FtsIndex(_) => SUBSPACE_FTS_INDEX,
             ValueClass::FtsQueue { .. } => SUBSPACE_FTS_QUEUE,
+            ValueClass::Snooze(_) => SUBSPACE_SNOOZE,
             ValueClass::Blob(op) => match op {
                 BlobOp::Reserve { .. } => SUBSPACE_BLOB_RESERVE,
                 BlobOp::Commit { .. } | BlobOp::Link { .. } | BlobOp::LinkId { .. } => {
                     SUBSPACE_BLOB_LINK
                 }
+                BlobOp::Tier { .. } => SUBSPACE_BLOB_TIER,
             },
             ValueClass::Config(_) => SUBSPACE_SETTINGS,
             ValueClass::Lookup(lookup) => match lookup {
@@ -615,7 +663,7 @@ impl<T> ValueClass<T> {
             ValueClass::Directory(DirectoryClass::UsedQuota(_))
             | ValueClass::Lookup(LookupClass::Counter(_))
             | ValueClass::Queue(QueueClass::QuotaCount(_) | QueueClass::QuotaSize(_)) => true,
-            ValueClass::Property(84) if collection == 1 => true, // TODO: Find a more elegant way to do this
+            ValueClass::Property(COUNTER_PROPERTY) if collection == COUNTER_COLLECTION => true,
             _ => false,
         }
     }
@@ -673,3 +721,13 @@ impl Deserialize for ReportEvent {
         })
     }
 }
+
+pub(crate) fn key_account(subspace: u8, key: &[u8]) -> Option<u32> {
+    match subspace {
+        SUBSPACE_BITMAP_ID | SUBSPACE_BITMAP_TAG | SUBSPACE_BITMAP_TEXT | SUBSPACE_INDEXES
+        | SUBSPACE_LOGS | SUBSPACE_PROPERTY | SUBSPACE_FTS_INDEX | SUBSPACE_SNOOZE => {
+            key.deserialize_be_u32(0).ok()
+        }
+        _ => None,
+    }
+}
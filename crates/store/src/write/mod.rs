@@ -148,6 +148,7 @@ pub enum ValueClass<T> {
     Lookup(LookupClass),
     FtsIndex(BitmapHash),
     FtsQueue(FtsQueueClass),
+    Snooze(u64),
     Directory(DirectoryClass<T>),
     Blob(BlobOp),
     Config(Vec<u8>),
@@ -183,6 +184,8 @@ pub enum DirectoryClass<T> {
     Members { principal_id: T, has_member: T },
     Principal(T),
     UsedQuota(u32),
+    Shard(u32),
+    PasswordState(T),
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
@@ -249,6 +252,7 @@ pub enum BlobOp {
     Commit { hash: BlobHash },
     Link { hash: BlobHash },
     LinkId { hash: BlobHash, id: u64 },
+    Tier { hash: BlobHash },
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
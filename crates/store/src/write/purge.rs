@@ -4,19 +4,20 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use tokio::sync::watch;
 use trc::PurgeEvent;
 use utils::config::cron::SimpleCron;
 
-use crate::{BlobStore, LookupStore, Store};
+use crate::{backend::tiered::TieredBlob, BlobStore, LookupStore, Store};
 
 #[derive(Clone)]
 pub enum PurgeStore {
     Data(Store),
     Blobs { store: Store, blob_store: BlobStore },
     Lookup(LookupStore),
+    Tiered(Arc<TieredBlob>),
 }
 
 #[derive(Clone)]
@@ -60,6 +61,7 @@ impl PurgeSchedule {
                         store.purge_blobs(blob_store.clone()).await
                     }
                     PurgeStore::Lookup(store) => store.purge_lookup_store().await,
+                    PurgeStore::Tiered(store) => store.offload_blobs().await,
                 };
 
                 if let Err(err) = result {
@@ -81,6 +83,7 @@ impl PurgeStore {
             PurgeStore::Data(_) => "data",
             PurgeStore::Blobs { .. } => "blobs",
             PurgeStore::Lookup(_) => "lookup",
+            PurgeStore::Tiered(_) => "tiered",
         }
     }
 }
@@ -91,6 +94,7 @@ impl Display for PurgeStore {
             PurgeStore::Data(_) => write!(f, "bitmaps"),
             PurgeStore::Blobs { .. } => write!(f, "blobs"),
             PurgeStore::Lookup(_) => write!(f, "expired keys"),
+            PurgeStore::Tiered(_) => write!(f, "aged blobs"),
         }
     }
 }
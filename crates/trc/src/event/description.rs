@@ -174,7 +174,10 @@ impl HousekeeperEvent {
             HousekeeperEvent::Schedule => "Housekeeper task scheduled",
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
+            HousekeeperEvent::RecalculateQuotas => "Recalculating quotas",
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::SyncDirectory => "Synchronizing directory",
+            HousekeeperEvent::WakeSnoozed => "Waking up snoozed messages",
         }
     }
 
@@ -185,7 +188,16 @@ impl HousekeeperEvent {
             HousekeeperEvent::Schedule => "A housekeeper task has been scheduled",
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
+            HousekeeperEvent::RecalculateQuotas => {
+                "The server is recalculating the used quota of accounts and tenants"
+            }
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::SyncDirectory => {
+                "The server is importing principals from an external directory"
+            }
+            HousekeeperEvent::WakeSnoozed => {
+                "Snoozed messages that reached their due time are returned to their mailbox"
+            }
         }
     }
 }
@@ -1275,6 +1287,7 @@ impl PurgeEvent {
             PurgeEvent::PurgeActive => "Active purge in progress",
             PurgeEvent::AutoExpunge => "Auto-expunge executed",
             PurgeEvent::TombstoneCleanup => "Tombstone cleanup executed",
+            PurgeEvent::AccountDeleted => "Account deleted",
         }
     }
 
@@ -1287,6 +1300,9 @@ impl PurgeEvent {
             PurgeEvent::PurgeActive => "An active purge is in progress",
             PurgeEvent::AutoExpunge => "Auto-expunge has been executed",
             PurgeEvent::TombstoneCleanup => "Tombstone cleanup has been executed",
+            PurgeEvent::AccountDeleted => {
+                "An account pending deletion has been removed after its grace period"
+            }
         }
     }
 }
@@ -1538,6 +1554,8 @@ impl StoreEvent {
             StoreEvent::ElasticsearchError => "ElasticSearch error",
             StoreEvent::RedisError => "Redis error",
             StoreEvent::S3Error => "S3 error",
+            StoreEvent::AzureError => "Azure error",
+            StoreEvent::GcsError => "Google Cloud Storage error",
             StoreEvent::FilesystemError => "Filesystem error",
             StoreEvent::PoolError => "Connection pool error",
             StoreEvent::DataCorruption => "Data corruption detected",
@@ -1548,14 +1566,21 @@ impl StoreEvent {
             StoreEvent::NotSupported => "Operation not supported by store",
             StoreEvent::UnexpectedError => "Unexpected store error",
             StoreEvent::CryptoError => "Store crypto error",
+            StoreEvent::AccountLocked => "Account locked",
             StoreEvent::BlobMissingMarker => "Blob missing marker",
+            StoreEvent::QuotaMismatch => "Quota mismatch",
             StoreEvent::SqlQuery => "SQL query executed",
             StoreEvent::LdapQuery => "LDAP query executed",
             StoreEvent::LdapBind => "LDAP bind operation",
+            StoreEvent::LdapModify => "LDAP modify operation",
+            StoreEvent::DirectorySyncChange => "Directory synchronization change",
+            StoreEvent::DirectorySyncComplete => "Directory synchronization completed",
             StoreEvent::DataWrite => "Write batch operation",
             StoreEvent::BlobRead => "Blob read operation",
             StoreEvent::BlobWrite => "Blob write operation",
             StoreEvent::BlobDelete => "Blob delete operation",
+            StoreEvent::BlobOffload => "Blob moved to cold storage",
+            StoreEvent::AccountMoved => "Account moved",
             StoreEvent::DataIterate => "Data store iteration operation",
         }
     }
@@ -1572,6 +1597,8 @@ impl StoreEvent {
             StoreEvent::ElasticsearchError => "An ElasticSearch error occurred",
             StoreEvent::RedisError => "A Redis error occurred",
             StoreEvent::S3Error => "An S3 error occurred",
+            StoreEvent::AzureError => "An Azure Blob Storage error occurred",
+            StoreEvent::GcsError => "A Google Cloud Storage error occurred",
             StoreEvent::FilesystemError => "A filesystem error occurred",
             StoreEvent::PoolError => "A connection pool error occurred",
             StoreEvent::DataCorruption => "Data corruption was detected",
@@ -1582,14 +1609,27 @@ impl StoreEvent {
             StoreEvent::NotSupported => "The operation is not supported by the store",
             StoreEvent::UnexpectedError => "An unexpected store error occurred",
             StoreEvent::CryptoError => "A store crypto error occurred",
+            StoreEvent::AccountLocked => {
+                "The account is being moved to a different shard and cannot be modified"
+            }
             StoreEvent::BlobMissingMarker => "The blob is missing a marker",
+            StoreEvent::QuotaMismatch => {
+                "The stored used quota of an account or tenant does not match its actual usage"
+            }
             StoreEvent::SqlQuery => "An SQL query was executed",
             StoreEvent::LdapQuery => "An LDAP query was executed",
             StoreEvent::LdapBind => "An LDAP bind operation was executed",
+            StoreEvent::LdapModify => "An LDAP entry was modified",
+            StoreEvent::DirectorySyncChange => {
+                "A principal was created, updated, disabled or deleted by a directory synchronization"
+            }
+            StoreEvent::DirectorySyncComplete => "A directory synchronization has completed",
             StoreEvent::DataWrite => "A write batch operation was executed",
             StoreEvent::BlobRead => "A blob read operation was executed",
             StoreEvent::BlobWrite => "A blob write operation was executed",
             StoreEvent::BlobDelete => "A blob delete operation was executed",
+            StoreEvent::BlobOffload => "A blob was moved from the hot tier to the cold tier",
+            StoreEvent::AccountMoved => "An account was moved to a different shard",
             StoreEvent::DataIterate => "A data store iteration operation was executed",
         }
     }
@@ -1603,6 +1643,7 @@ impl MessageIngestEvent {
             MessageIngestEvent::ImapAppend => "Message appended via IMAP",
             MessageIngestEvent::JmapAppend => "Message appended via JMAP",
             MessageIngestEvent::Duplicate => "Skipping duplicate message",
+            MessageIngestEvent::Forward => "Message forwarded",
             MessageIngestEvent::Error => "Message ingestion error",
         }
     }
@@ -1614,6 +1655,9 @@ impl MessageIngestEvent {
             MessageIngestEvent::ImapAppend => "The message has been appended via IMAP",
             MessageIngestEvent::JmapAppend => "The message has been appended via JMAP",
             MessageIngestEvent::Duplicate => "The message is a duplicate and has been skipped",
+            MessageIngestEvent::Forward => {
+                "A copy of the message was forwarded to the account's forwarding addresses"
+            }
             MessageIngestEvent::Error => "An error occurred while ingesting the message",
         }
     }
@@ -1740,6 +1784,8 @@ impl AuthEvent {
             AuthEvent::Failed => "Authentication failed",
             AuthEvent::MissingTotp => "Missing TOTP for authentication",
             AuthEvent::TooManyAttempts => "Too many authentication attempts",
+            AuthEvent::AccountLocked => "Account locked",
+            AuthEvent::PasswordExpired => "Password expired",
             AuthEvent::Error => "Authentication error",
             AuthEvent::TokenExpired => "OAuth token expired",
             AuthEvent::ClientRegistration => "OAuth Client registration",
@@ -1752,6 +1798,10 @@ impl AuthEvent {
             AuthEvent::Failed => "Failed authentication",
             AuthEvent::MissingTotp => "TOTP is missing for authentication",
             AuthEvent::TooManyAttempts => "Too many authentication attempts have been made",
+            AuthEvent::AccountLocked => {
+                "The account has been temporarily locked after too many failed authentication attempts"
+            }
+            AuthEvent::PasswordExpired => "The account password has expired and must be changed",
             AuthEvent::Error => "An error occurred with authentication",
             AuthEvent::TokenExpired => "OAuth authentication token has expired",
             AuthEvent::ClientRegistration => "OAuth client successfully registered",
@@ -17,9 +17,12 @@ impl EventType {
                 | StoreEvent::BlobRead
                 | StoreEvent::BlobWrite
                 | StoreEvent::BlobDelete
+                | StoreEvent::BlobOffload
                 | StoreEvent::SqlQuery
                 | StoreEvent::LdapQuery
-                | StoreEvent::LdapBind => Level::Trace,
+                | StoreEvent::LdapBind
+                | StoreEvent::LdapModify => Level::Trace,
+                StoreEvent::DirectorySyncChange | StoreEvent::DirectorySyncComplete => Level::Info,
                 StoreEvent::NotFound => Level::Debug,
                 StoreEvent::AssertValueFailed
                 | StoreEvent::FoundationdbError
@@ -31,6 +34,8 @@ impl EventType {
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
+                | StoreEvent::AzureError
+                | StoreEvent::GcsError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -39,8 +44,10 @@ impl EventType {
                 | StoreEvent::NotConfigured
                 | StoreEvent::NotSupported
                 | StoreEvent::UnexpectedError
-                | StoreEvent::CryptoError => Level::Error,
-                StoreEvent::BlobMissingMarker => Level::Warn,
+                | StoreEvent::CryptoError
+                | StoreEvent::AccountLocked => Level::Error,
+                StoreEvent::BlobMissingMarker | StoreEvent::QuotaMismatch => Level::Warn,
+                StoreEvent::AccountMoved => Level::Info,
             },
             EventType::Jmap(_) => Level::Debug,
             EventType::Imap(event) => match event {
@@ -225,9 +232,11 @@ impl EventType {
             },
             EventType::Manage(_) => Level::Debug,
             EventType::Auth(cause) => match cause {
-                AuthEvent::Failed | AuthEvent::TokenExpired => Level::Debug,
+                AuthEvent::Failed | AuthEvent::TokenExpired | AuthEvent::PasswordExpired => {
+                    Level::Debug
+                }
                 AuthEvent::MissingTotp => Level::Trace,
-                AuthEvent::TooManyAttempts => Level::Warn,
+                AuthEvent::TooManyAttempts | AuthEvent::AccountLocked => Level::Warn,
                 AuthEvent::Error => Level::Error,
                 AuthEvent::Success | AuthEvent::ClientRegistration => Level::Info,
             },
@@ -268,6 +277,7 @@ impl EventType {
                 PurgeEvent::Finished => Level::Debug,
                 PurgeEvent::Running => Level::Info,
                 PurgeEvent::Error => Level::Error,
+                PurgeEvent::AccountDeleted => Level::Info,
                 PurgeEvent::PurgeActive
                 | PurgeEvent::AutoExpunge
                 | PurgeEvent::TombstoneCleanup => Level::Debug,
@@ -372,9 +382,11 @@ impl EventType {
                 HousekeeperEvent::Start
                 | HousekeeperEvent::PurgeAccounts
                 | HousekeeperEvent::PurgeSessions
+                | HousekeeperEvent::RecalculateQuotas
                 | HousekeeperEvent::PurgeStore
+                | HousekeeperEvent::SyncDirectory
                 | HousekeeperEvent::Stop => Level::Info,
-                HousekeeperEvent::Schedule => Level::Debug,
+                HousekeeperEvent::Schedule | HousekeeperEvent::WakeSnoozed => Level::Debug,
             },
             EventType::FtsIndex(event) => match event {
                 FtsIndexEvent::Index => Level::Info,
@@ -529,7 +541,8 @@ impl EventType {
                 | MessageIngestEvent::Spam
                 | MessageIngestEvent::ImapAppend
                 | MessageIngestEvent::JmapAppend
-                | MessageIngestEvent::Duplicate => Level::Info,
+                | MessageIngestEvent::Duplicate
+                | MessageIngestEvent::Forward => Level::Info,
                 MessageIngestEvent::Error => Level::Error,
             },
             EventType::Security(_) => Level::Info,
@@ -440,6 +440,8 @@ impl EventType {
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
+                | StoreEvent::AzureError
+                | StoreEvent::GcsError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -450,12 +452,16 @@ impl EventType {
                 | StoreEvent::NotSupported
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError
+                | StoreEvent::AccountLocked
                 | StoreEvent::BlobMissingMarker
+                | StoreEvent::QuotaMismatch
                 | StoreEvent::DataWrite
                 | StoreEvent::DataIterate
                 | StoreEvent::BlobRead
                 | StoreEvent::BlobWrite
-                | StoreEvent::BlobDelete,
+                | StoreEvent::BlobDelete
+                | StoreEvent::BlobOffload
+                | StoreEvent::AccountMoved,
             ) => true,
             EventType::MessageIngest(_) => true,
             EventType::Jmap(
@@ -533,6 +539,7 @@ impl EventType {
                 AuthEvent::Success
                 | AuthEvent::Failed
                 | AuthEvent::TooManyAttempts
+                | AuthEvent::AccountLocked
                 | AuthEvent::Error,
             ) => true,
             EventType::Config(_) => false,
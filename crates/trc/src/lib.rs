@@ -229,7 +229,10 @@ pub enum HousekeeperEvent {
     Schedule,
     PurgeAccounts,
     PurgeSessions,
+    RecalculateQuotas,
     PurgeStore,
+    SyncDirectory,
+    WakeSnoozed,
 }
 
 #[event_type]
@@ -711,6 +714,7 @@ pub enum PurgeEvent {
     PurgeActive,
     AutoExpunge,
     TombstoneCleanup,
+    AccountDeleted,
 }
 
 #[event_type]
@@ -826,6 +830,8 @@ pub enum StoreEvent {
     ElasticsearchError,
     RedisError,
     S3Error,
+    AzureError,
+    GcsError,
     FilesystemError,
     PoolError,
     DataCorruption,
@@ -836,9 +842,11 @@ pub enum StoreEvent {
     NotSupported,
     UnexpectedError,
     CryptoError,
+    AccountLocked,
 
     // Warnings
     BlobMissingMarker,
+    QuotaMismatch,
 
     // Traces
     DataWrite,
@@ -846,9 +854,14 @@ pub enum StoreEvent {
     BlobRead,
     BlobWrite,
     BlobDelete,
+    BlobOffload,
+    AccountMoved,
     SqlQuery,
     LdapQuery,
     LdapBind,
+    LdapModify,
+    DirectorySyncChange,
+    DirectorySyncComplete,
 }
 
 #[event_type]
@@ -859,6 +872,7 @@ pub enum MessageIngestEvent {
     ImapAppend,
     JmapAppend,
     Duplicate,
+    Forward,
     Error,
 }
 
@@ -926,6 +940,8 @@ pub enum AuthEvent {
     TokenExpired,
     MissingTotp,
     TooManyAttempts,
+    AccountLocked,
+    PasswordExpired,
     ClientRegistration,
     Error,
 }
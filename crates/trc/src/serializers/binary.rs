@@ -861,6 +861,22 @@ impl EventType {
             EventType::Limit(LimitEvent::TenantQuota) => 553,
             EventType::Auth(AuthEvent::TokenExpired) => 554,
             EventType::Auth(AuthEvent::ClientRegistration) => 555,
+            EventType::Store(StoreEvent::BlobOffload) => 556,
+            EventType::Store(StoreEvent::AzureError) => 557,
+            EventType::Store(StoreEvent::GcsError) => 558,
+            EventType::Store(StoreEvent::AccountLocked) => 559,
+            EventType::Store(StoreEvent::AccountMoved) => 560,
+            EventType::Housekeeper(HousekeeperEvent::RecalculateQuotas) => 561,
+            EventType::Store(StoreEvent::QuotaMismatch) => 562,
+            EventType::Auth(AuthEvent::AccountLocked) => 563,
+            EventType::Auth(AuthEvent::PasswordExpired) => 564,
+            EventType::MessageIngest(MessageIngestEvent::Forward) => 565,
+            EventType::Store(StoreEvent::LdapModify) => 566,
+            EventType::Housekeeper(HousekeeperEvent::SyncDirectory) => 567,
+            EventType::Store(StoreEvent::DirectorySyncChange) => 568,
+            EventType::Store(StoreEvent::DirectorySyncComplete) => 569,
+            EventType::Purge(PurgeEvent::AccountDeleted) => 570,
+            EventType::Housekeeper(HousekeeperEvent::WakeSnoozed) => 571,
         }
     }
 
@@ -1462,6 +1478,22 @@ impl EventType {
             553 => Some(EventType::Limit(LimitEvent::TenantQuota)),
             554 => Some(EventType::Auth(AuthEvent::TokenExpired)),
             555 => Some(EventType::Auth(AuthEvent::ClientRegistration)),
+            556 => Some(EventType::Store(StoreEvent::BlobOffload)),
+            557 => Some(EventType::Store(StoreEvent::AzureError)),
+            558 => Some(EventType::Store(StoreEvent::GcsError)),
+            559 => Some(EventType::Store(StoreEvent::AccountLocked)),
+            560 => Some(EventType::Store(StoreEvent::AccountMoved)),
+            561 => Some(EventType::Housekeeper(HousekeeperEvent::RecalculateQuotas)),
+            562 => Some(EventType::Store(StoreEvent::QuotaMismatch)),
+            563 => Some(EventType::Auth(AuthEvent::AccountLocked)),
+            564 => Some(EventType::Auth(AuthEvent::PasswordExpired)),
+            565 => Some(EventType::MessageIngest(MessageIngestEvent::Forward)),
+            566 => Some(EventType::Store(StoreEvent::LdapModify)),
+            567 => Some(EventType::Housekeeper(HousekeeperEvent::SyncDirectory)),
+            568 => Some(EventType::Store(StoreEvent::DirectorySyncChange)),
+            569 => Some(EventType::Store(StoreEvent::DirectorySyncComplete)),
+            570 => Some(EventType::Purge(PurgeEvent::AccountDeleted)),
+            571 => Some(EventType::Housekeeper(HousekeeperEvent::WakeSnoozed)),
             _ => None,
         }
     }
@@ -0,0 +1,142 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::kerberos::PrincipalMapping;
+
+#[test]
+fn kerberos_principal_mapping() {
+    let mapping = PrincipalMapping {
+        realms: vec!["EXAMPLE.ORG".to_string()],
+        strip_realm: true,
+    };
+    for (principal, expected) in [
+        ("john@EXAMPLE.ORG", Some("john")),
+        ("John.Doe@example.org", Some("john.doe")),
+        ("john@OTHER.ORG", None),
+        ("imap/mail.example.org@EXAMPLE.ORG", None),
+        ("john/admin@EXAMPLE.ORG", None),
+        ("@EXAMPLE.ORG", None),
+        ("john@", None),
+        ("john", None),
+    ] {
+        assert_eq!(
+            mapping.map(principal).as_deref(),
+            expected,
+            "failed for {principal}"
+        );
+    }
+
+    let mapping = PrincipalMapping {
+        realms: vec!["EXAMPLE.ORG".to_string(), "PARTNER.ORG".to_string()],
+        strip_realm: false,
+    };
+    assert_eq!(
+        mapping.map("John@EXAMPLE.ORG").as_deref(),
+        Some("john@example.org")
+    );
+    assert_eq!(
+        mapping.map("jane@PARTNER.ORG").as_deref(),
+        Some("jane@partner.org")
+    );
+    assert_eq!(mapping.map("jane@OTHER.ORG"), None);
+
+    // Principals from unlisted realms are never mapped
+    let mapping = PrincipalMapping {
+        realms: vec![],
+        strip_realm: true,
+    };
+    assert_eq!(mapping.map("john@EXAMPLE.ORG"), None);
+}
+
+// Requires a local MIT KDC for the EXAMPLE.ORG realm, a keytab containing
+// "imap/localhost@EXAMPLE.ORG" and "HTTP/localhost@EXAMPLE.ORG" referenced by
+// KRB5_KTNAME and a ticket for "john@EXAMPLE.ORG" in the default credential cache.
+#[cfg(feature = "kerberos")]
+#[test]
+fn kerberos_gssapi() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use common::auth::kerberos::{GssapiSession, GssapiStep, KerberosConfig};
+    use libgssapi::{
+        context::{ClientCtx, CtxFlags, SecurityContext},
+        credential::{Cred, CredUsage},
+        name::Name,
+        oid::{GSS_MECH_KRB5, GSS_MECH_SPNEGO, GSS_NT_HOSTBASED_SERVICE},
+    };
+
+    let config = KerberosConfig {
+        mapping: PrincipalMapping {
+            realms: vec!["EXAMPLE.ORG".to_string()],
+            strip_realm: true,
+        },
+        credentials: Cred::acquire(None, None, CredUsage::Accept, None).unwrap(),
+    };
+
+    // SASL GSSAPI exchange, including the security layer negotiation
+    let mut server = GssapiSession::sasl(&config);
+    let mut client = ClientCtx::new(
+        None,
+        Name::new(b"imap@localhost", Some(&GSS_NT_HOSTBASED_SERVICE)).unwrap(),
+        CtxFlags::GSS_C_MUTUAL_FLAG,
+        Some(&GSS_MECH_KRB5),
+    );
+    let mut challenge: Option<Vec<u8>> = None;
+    let result = loop {
+        let response = if !client.is_complete() {
+            client
+                .step(challenge.as_deref(), None)
+                .unwrap()
+                .map(|token| token.to_vec())
+                .unwrap_or_default()
+        } else {
+            let layer = client.unwrap(challenge.as_deref().unwrap()).unwrap();
+            assert_eq!(layer[0] & 0x01, 0x01);
+            let mut response = vec![0x01, 0, 0, 0];
+            response.extend_from_slice(b"john");
+            client.wrap(false, &response).unwrap().to_vec()
+        };
+
+        match server.step(&STANDARD.encode(&response)).unwrap() {
+            GssapiStep::Continue(token) => {
+                challenge = Some(STANDARD.decode(token).unwrap());
+            }
+            result => break result,
+        }
+    };
+    assert_eq!(
+        result,
+        GssapiStep::Authenticated {
+            principal: "john@EXAMPLE.ORG".to_string(),
+            authz_id: Some("john".to_string()),
+            response: None,
+        }
+    );
+    assert_eq!(config.mapping.map("john@EXAMPLE.ORG").unwrap(), "john");
+
+    // HTTP Negotiate completes in a single round trip
+    let mut server = GssapiSession::negotiate(&config);
+    let mut client = ClientCtx::new(
+        None,
+        Name::new(b"HTTP@localhost", Some(&GSS_NT_HOSTBASED_SERVICE)).unwrap(),
+        CtxFlags::GSS_C_MUTUAL_FLAG,
+        Some(&GSS_MECH_SPNEGO),
+    );
+    let token = client.step(None, None).unwrap().unwrap();
+    match server.step(&STANDARD.encode(&token[..])).unwrap() {
+        GssapiStep::Authenticated {
+            principal,
+            authz_id,
+            response,
+        } => {
+            assert_eq!(principal, "john@EXAMPLE.ORG");
+            assert_eq!(authz_id, None);
+            assert!(response.is_some());
+        }
+        step => panic!("Unexpected step {step:?}"),
+    }
+
+    // Tokens are rejected once the exchange is complete
+    assert!(server.step("").is_err());
+}
@@ -6,7 +6,10 @@
 
 use std::fmt::Debug;
 
-use directory::{backend::internal::manage::ManageDirectory, QueryBy, Type, ROLE_USER};
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField, PrincipalUpdate, PrincipalValue},
+    QueryBy, Type, ROLE_USER,
+};
 use mail_send::Credentials;
 
 use crate::directory::{map_account_ids, DirectoryTest, IntoTestPrincipal, TestPrincipal};
@@ -242,6 +245,58 @@ async fn ldap_directory() {
         core.expn(&handle, "john@example.org", 0).await.unwrap(),
         Vec::<String>::new(),
     );
+
+    // Write-back support
+    assert!(handle.supports_updates(&[
+        PrincipalUpdate::set(
+            PrincipalField::Secrets,
+            PrincipalValue::StringList(vec!["abcde".to_string()])
+        ),
+        PrincipalUpdate::add_item(
+            PrincipalField::Emails,
+            PrincipalValue::String("john.new@example.org".to_string())
+        ),
+        PrincipalUpdate::set(PrincipalField::Quota, PrincipalValue::Integer(1024)),
+        PrincipalUpdate::set(
+            PrincipalField::Description,
+            PrincipalValue::String("Johnny".to_string())
+        ),
+    ]));
+    assert!(!handle.supports_updates(&[PrincipalUpdate::add_item(
+        PrincipalField::Secrets,
+        PrincipalValue::String("otpauth://totp/john".to_string())
+    )]));
+    assert!(!handle.supports_updates(&[PrincipalUpdate::set(
+        PrincipalField::Roles,
+        PrincipalValue::StringList(vec!["admin".to_string()])
+    )]));
+    assert!(!handle.supports_updates(&[PrincipalUpdate::add_item(
+        PrincipalField::Secrets,
+        PrincipalValue::String("$6$rounds=5000$salt$hash".to_string())
+    )]));
+
+    // Changes written back are visible when reading the entry
+    for description in ["Johnny", "John Doe"] {
+        assert!(handle
+            .write_back(
+                "john",
+                &[PrincipalUpdate::set(
+                    PrincipalField::Description,
+                    PrincipalValue::String(description.to_string())
+                )]
+            )
+            .await
+            .unwrap());
+        assert_eq!(
+            handle
+                .query(QueryBy::Name("john"), false)
+                .await
+                .unwrap()
+                .unwrap()
+                .description(),
+            Some(description)
+        );
+    }
 }
 
 fn compare_sorted<T: Eq + Debug>(v1: Vec<T>, v2: Vec<T>) {
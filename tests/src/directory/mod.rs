@@ -6,10 +6,12 @@
 
 pub mod imap;
 pub mod internal;
+pub mod kerberos;
 pub mod ldap;
 pub mod oidc;
 pub mod smtp;
 pub mod sql;
+pub mod sync;
 
 use common::{config::smtp::session::AddressMapping, Core, Server};
 use directory::{
@@ -39,6 +41,11 @@ store = "foundationdb"
 type = "sql"
 store = "sqlite"
 
+[directory."sqlite".sync]
+enable = true
+frequency = "0 * *"
+deletion = "disable"
+
 [directory."sqlite".columns]
 name = "name"
 description = "description"
@@ -66,6 +73,7 @@ emails = "SELECT address FROM emails WHERE name = ? AND type != 'list' ORDER BY
 verify = "SELECT address FROM emails WHERE address LIKE '%' || ? || '%' AND type = 'primary' ORDER BY address LIMIT 5"
 expand = "SELECT p.address FROM emails AS p JOIN emails AS l ON p.name = l.name WHERE p.type = 'primary' AND l.address = ? AND l.type = 'list' ORDER BY p.address LIMIT 50"
 domains = "SELECT 1 FROM emails WHERE address LIKE '%@' || ? LIMIT 1"
+list = "SELECT name FROM accounts WHERE active = true ORDER BY name"
 
 [storage]
 lookup = "sqlite"
@@ -100,6 +108,7 @@ emails = "SELECT address FROM emails WHERE name = $1 AND type != 'list' ORDER BY
 verify = "SELECT address FROM emails WHERE address LIKE '%' || $1 || '%' AND type = 'primary' ORDER BY address LIMIT 5"
 expand = "SELECT p.address FROM emails AS p JOIN emails AS l ON p.name = l.name WHERE p.type = 'primary' AND l.address = $1 AND l.type = 'list' ORDER BY p.address LIMIT 50"
 domains = "SELECT 1 FROM emails WHERE address LIKE '%@' || $1 LIMIT 1"
+list = "SELECT name FROM accounts WHERE active = true ORDER BY name"
 
 ##############################################################################
 
@@ -131,6 +140,7 @@ emails = "SELECT address FROM emails WHERE name = ? AND type != 'list' ORDER BY
 verify = "SELECT address FROM emails WHERE address LIKE CONCAT('%', ?, '%') AND type = 'primary' ORDER BY address LIMIT 5"
 expand = "SELECT p.address FROM emails AS p JOIN emails AS l ON p.name = l.name WHERE p.type = 'primary' AND l.address = ? AND l.type = 'list' ORDER BY p.address LIMIT 50"
 domains = "SELECT 1 FROM emails WHERE address LIKE CONCAT('%@', ?) LIMIT 1"
+list = "SELECT name FROM accounts WHERE active = true ORDER BY name"
 
 ##############################################################################
 
@@ -167,6 +177,11 @@ email-alias = "givenName"
 quota = "diskQuota"
 class = "objectClass"
 
+[directory."ldap".write-back]
+enable = true
+password.method = "password-modify"
+attributes.description = "principalName"
+
 ##############################################################################
 
 [directory."imap"]
@@ -484,6 +484,20 @@ impl DirectoryStore {
             .unwrap();
     }
 
+    pub async fn set_test_active(&self, login: &str, active: bool) {
+        self.store
+            .query::<usize>(
+                if self.is_postgresql() {
+                    "UPDATE accounts SET active = $1 where name = $2"
+                } else {
+                    "UPDATE accounts SET active = ? where name = ?"
+                },
+                vec![active.into(), login.into()],
+            )
+            .await
+            .unwrap();
+    }
+
     pub async fn add_to_group(&self, login: &str, group: &str) {
         self.store
             .query::<usize>(
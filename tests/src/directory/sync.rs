@@ -0,0 +1,207 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use directory::{
+    backend::internal::{lookup::DirectoryStore as _, manage::ManageDirectory, PrincipalField},
+    core::sync::{DeletionPolicy, SyncAction, SyncReport},
+    Permission, QueryBy, Type,
+};
+use mail_send::Credentials;
+
+use crate::directory::{map_account_ids, DirectoryTest};
+
+use super::DirectoryStore;
+
+#[tokio::test]
+async fn directory_sync() {
+    let mut config = DirectoryTest::new("sqlite".into()).await;
+    let mut handle = config.directories.directories.remove("sqlite").unwrap();
+    let store = DirectoryStore {
+        store: config.stores.lookup_stores.remove("sqlite").unwrap(),
+    };
+    let base_store = config.stores.stores.get("sqlite").unwrap();
+
+    // Create external directory
+    base_store.destroy().await;
+    store.create_test_directory().await;
+    store.create_test_user("john", "12345", "John Doe").await;
+    store.create_test_user("jane", "abcde", "Jane Doe").await;
+    store.create_test_group("sales", "Sales Team").await;
+    store.add_to_group("john", "sales").await;
+    store.add_to_group("jane", "sales").await;
+    store
+        .link_test_address("john", "john@example.org", "primary")
+        .await;
+    store
+        .link_test_address("john", "jdoe@example.org", "alias")
+        .await;
+    store
+        .link_test_address("jane", "jane@example.org", "primary")
+        .await;
+
+    // A dry run reports the changes without applying them
+    let report = handle.synchronize(true).await.unwrap();
+    assert!(report.dry_run);
+    assert_eq!(
+        actions(&report),
+        vec![
+            ("sales", SyncAction::Create),
+            ("admin", SyncAction::Create),
+            ("jane", SyncAction::Create),
+            ("john", SyncAction::Create),
+        ]
+    );
+    let john = report.changes.iter().find(|c| c.name == "john").unwrap();
+    assert_eq!(john.id, None);
+    assert!(john
+        .updates
+        .iter()
+        .any(|u| u.field == PrincipalField::Secrets && u.value.as_str() == Some("[redacted]")));
+    assert!(base_store.get_principal_id("john").await.unwrap().is_none());
+
+    // Import principals
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(report.changes.len(), 4);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let principal = base_store
+        .query(
+            QueryBy::Credentials(&Credentials::Plain {
+                username: "john".to_string(),
+                secret: "12345".to_string(),
+            }),
+            true,
+        )
+        .await
+        .unwrap()
+        .expect("john should authenticate against the internal directory");
+    assert_eq!(principal.description(), Some("John Doe"));
+    assert_eq!(
+        principal.get_str_array(PrincipalField::Emails),
+        Some(
+            &[
+                "john@example.org".to_string(),
+                "jdoe@example.org".to_string()
+            ][..]
+        )
+    );
+    assert_eq!(
+        principal.get_int_array(PrincipalField::MemberOf),
+        Some(
+            &map_account_ids(base_store, vec!["sales"])
+                .await
+                .into_iter()
+                .map(u64::from)
+                .collect::<Vec<_>>()[..]
+        )
+    );
+    assert_eq!(
+        base_store
+            .get_principal_info("sales")
+            .await
+            .unwrap()
+            .unwrap()
+            .typ,
+        Type::Group
+    );
+
+    // Synchronizing again is a no-op
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(report.changes, vec![], "{:?}", report.changes);
+
+    // Local accounts with the same name are not taken over
+    let bob_id = base_store
+        .get_or_create_principal_id("bob", Type::Individual)
+        .await
+        .unwrap();
+    store
+        .create_test_user("bob", "secret", "Bob External")
+        .await;
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(report.changes, vec![], "{:?}", report.changes);
+    assert_eq!(
+        report
+            .errors
+            .iter()
+            .map(|err| err.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["bob"]
+    );
+    let bob = base_store
+        .query(QueryBy::Id(bob_id), true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(bob.description(), None);
+    assert_eq!(bob.get_str_array(PrincipalField::Secrets), None);
+    store.set_test_active("bob", false).await;
+    assert_eq!(handle.synchronize(false).await.unwrap().changes, vec![]);
+    assert!(base_store.get_principal_id("bob").await.unwrap().is_some());
+
+    // Changes in the external directory are imported
+    store.create_test_user("jane", "abcde", "Jane Smith").await;
+    store.remove_from_group("jane", "sales").await;
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(actions(&report), vec![("jane", SyncAction::Update)]);
+    let jane = base_store
+        .query(QueryBy::Name("jane"), true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(jane.description(), Some("Jane Smith"));
+    assert_eq!(jane.get_int_array(PrincipalField::MemberOf), None);
+
+    // Removed accounts are disabled and enabled again when they return
+    store.set_test_active("john", false).await;
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(actions(&report), vec![("john", SyncAction::Disable)]);
+    assert!(is_disabled(base_store, "john").await);
+    assert_eq!(handle.synchronize(false).await.unwrap().changes, vec![]);
+    store.set_test_active("john", true).await;
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(actions(&report), vec![("john", SyncAction::Enable)]);
+    assert!(!is_disabled(base_store, "john").await);
+
+    // Or deleted, depending on the policy
+    Arc::get_mut(&mut handle)
+        .unwrap()
+        .sync
+        .as_mut()
+        .unwrap()
+        .deletion = DeletionPolicy::Delete;
+    store.set_test_active("jane", false).await;
+    let report = handle.synchronize(false).await.unwrap();
+    assert_eq!(actions(&report), vec![("jane", SyncAction::Delete)]);
+    assert!(base_store.get_principal_id("jane").await.unwrap().is_none());
+
+    // An empty result set is never treated as a removal of all principals
+    for name in ["admin", "john", "sales"] {
+        store.set_test_active(name, false).await;
+    }
+    assert!(handle.synchronize(false).await.is_err());
+    assert!(base_store.get_principal_id("john").await.unwrap().is_some());
+}
+
+fn actions(report: &SyncReport) -> Vec<(&str, SyncAction)> {
+    report
+        .changes
+        .iter()
+        .map(|change| (change.name.as_str(), change.action))
+        .collect()
+}
+
+async fn is_disabled(store: &store::Store, name: &str) -> bool {
+    store
+        .query(QueryBy::Name(name), false)
+        .await
+        .unwrap()
+        .unwrap()
+        .has_int_value(
+            PrincipalField::DisabledPermissions,
+            Permission::Authenticate.id() as u64,
+        )
+}
@@ -484,3 +484,207 @@ pub async fn test(params: &mut JMAPTest) {
     destroy_all_mailboxes(params).await;
     assert_is_empty(server).await;
 }
+
+pub async fn test_convert(params: &mut JMAPTest) {
+    println!("Running Blob/convert tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    server.core.storage.data.blob_expire_all().await;
+
+    let response = jmap_json_request(
+        r#"[[
+            "Blob/upload",
+            {
+             "accountId": "$$",
+             "create": {
+              "png": {
+               "data": [{ "data:asBase64": "%PNG%" }],
+               "type": "image/png"
+              },
+              "pdf": {
+               "data": [{ "data:asBase64": "%PDF%" }],
+               "type": "application/pdf"
+              },
+              "txt": {
+               "data": [{ "data:asText": "Not an image" }],
+               "type": "text/plain"
+              }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id.to_string())
+        .replace("%PNG%", TEST_PNG)
+        .replace("%PDF%", TEST_PDF),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let uploaded = response
+        .pointer("/methodResponses/0/1/created")
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    let response = jmap_json_request(
+        r#"[[
+            "Blob/convert",
+            {
+             "accountId": "$$",
+             "create": {
+              "thumb": { "blobId": "%PNG%", "type": "image/webp", "width": 16, "height": 16 },
+              "cached": { "blobId": "%PNG%", "type": "image/webp", "width": 16, "height": 16 },
+              "preview": { "blobId": "%PDF%", "width": 20 },
+              "original": { "blobId": "%PNG%", "width": 4096, "height": 4096 },
+              "text": { "blobId": "%TXT%" },
+              "gif": { "blobId": "%PNG%", "type": "image/gif" }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id.to_string())
+        .replace("%PNG%", uploaded["png"]["id"].as_str().unwrap())
+        .replace("%PDF%", uploaded["pdf"]["id"].as_str().unwrap())
+        .replace("%TXT%", uploaded["txt"]["id"].as_str().unwrap()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let created = response
+        .pointer("/methodResponses/0/1/created")
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    for (id, type_, width, height) in [
+        ("thumb", "image/webp", 16, 8),
+        ("cached", "image/webp", 16, 8),
+        ("preview", "image/jpeg", 20, 10),
+        ("original", "image/jpeg", 64, 32),
+    ] {
+        let object = &created[id];
+        assert_eq!(object["type"], type_, "{id}: {response:#?}");
+        assert_eq!(object["width"], width, "{id}: {response:#?}");
+        assert_eq!(object["height"], height, "{id}: {response:#?}");
+    }
+    assert_eq!(created["thumb"]["size"], created["cached"]["size"]);
+    for id in ["text", "gif"] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/0/1/notCreated/{id}/type"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            "invalidProperties",
+            "{id}: {response:#?}"
+        );
+    }
+
+    // Derived blobs can be downloaded
+    let response = jmap_json_request(
+        r#"[[
+            "Blob/get",
+            {
+             "accountId": "$$",
+             "ids": ["%%"],
+             "properties": ["data:asBase64", "size"]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id.to_string())
+        .replace("%%", created["thumb"]["id"].as_str().unwrap()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer("/methodResponses/0/1/list/0/data:asBase64")
+            .and_then(|v| v.as_str())
+            .is_some_and(|data| data.starts_with("UklGR")),
+        "Response: {response:#?}"
+    );
+
+    server.core.storage.data.blob_expire_all().await;
+
+    // Text-only PDFs and oversized images are rejected
+    let response = jmap_json_request(
+        r#"[[
+            "Blob/upload",
+            {
+             "accountId": "$$",
+             "create": {
+              "textpdf": {
+               "data": [{ "data:asBase64": "%TEXT_PDF%" }],
+               "type": "application/pdf"
+              },
+              "huge": {
+               "data": [{ "data:asBase64": "%HUGE_PNG%" }],
+               "type": "image/png"
+              }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id.to_string())
+        .replace("%TEXT_PDF%", TEST_TEXT_PDF)
+        .replace("%HUGE_PNG%", TEST_HUGE_PNG),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let uploaded = response
+        .pointer("/methodResponses/0/1/created")
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    let response = jmap_json_request(
+        r#"[[
+            "Blob/convert",
+            {
+             "accountId": "$$",
+             "create": {
+              "textpdf": { "blobId": "%TEXT_PDF%" },
+              "huge": { "blobId": "%HUGE_PNG%" }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id.to_string())
+        .replace("%TEXT_PDF%", uploaded["textpdf"]["id"].as_str().unwrap())
+        .replace("%HUGE_PNG%", uploaded["huge"]["id"].as_str().unwrap()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for (id, type_) in [("textpdf", "invalidProperties"), ("huge", "tooLarge")] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/0/1/notCreated/{id}/type"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            type_,
+            "{id}: {response:#?}"
+        );
+    }
+
+    // Remove test data
+    params.client.set_default_account_id(account_id.to_string());
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+// 64x32 RGB gradient
+const TEST_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAEAAAAAgCAIAAAAt/+nTAAAA/0lEQVR42u3PkaKDABQA0GAQBEEQBEEQBEEwCIJBEARBEARBEAyCQTAIgsEgCIIgCIIgCIIgCIIgCIIgGARBEATvG57f8wcHQRDkdruhKIphGI7jBEGQJElRFE3TDMOwLMtxHM/zgiDc73dRFCVJejwesiwriqKqqqZpuq4bhmGapmVZtm07jvN8Pl3Xfb1enue932/f94Mg+Hw+3+83DMMoiuI4TpIkTdMsy/I8L4qiLMuqquq6bpqmbduu6/q+H4ZhHMdpmuZ5XpZlXdff77dt277vx3Gc53ldFwIBCEAAAhCAAAQgAAEIQAACEIAABCAAAQhAAAIQgAAE/hv4A3Nr0KY2r8CQAAAAAElFTkSuQmCC";
+
+// Single page PDF with a 40x20 grayscale image
+const TEST_PDF: &str = "JVBERi0xLjQKMSAwIG9iago8PCAvVHlwZSAvQ2F0YWxvZyAvUGFnZXMgMiAwIFIgPj4KZW5kb2JqCjIgMCBvYmoKPDwgL1R5cGUgL1BhZ2VzIC9LaWRzIFszIDAgUl0gL0NvdW50IDEgPj4KZW5kb2JqCjMgMCBvYmoKPDwgL1R5cGUgL1BhZ2UgL1BhcmVudCAyIDAgUiAvTWVkaWFCb3ggWzAgMCA0MCAyMF0gL1Jlc291cmNlcyA8PCAvWE9iamVjdCA8PCAvSW0wIDQgMCBSID4+ID4+IC9Db250ZW50cyA1IDAgUiA+PgplbmRvYmoKNCAwIG9iago8PCAvVHlwZSAvWE9iamVjdCAvU3VidHlwZSAvSW1hZ2UgL1dpZHRoIDQwIC9IZWlnaHQgMjAgL0NvbG9yU3BhY2UgL0RldmljZUdyYXkgL0JpdHNQZXJDb21wb25lbnQgOCAvRmlsdGVyIC9GbGF0ZURlY29kZSAvTGVuZ3RoIDU4ID4+CnN0cmVhbQp42mNg4xGSkFPRMjCzcfLwC4lKSMspqqhr6Zowbc6iFeu27Dpw7MylG/eevGIYVTeqbgSqAwBibm2wCmVuZHN0cmVhbQplbmRvYmoKNSAwIG9iago8PCAvTGVuZ3RoIDI3ID4+CnN0cmVhbQpxIDQwIDAgMCAyMCAwIDAgY20gL0ltMCBEbyBRCmVuZHN0cmVhbQplbmRvYmoKeHJlZgowIDYKMDAwMDAwMDAwMCA2NTUzNSBmIAowMDAwMDAwMDA5IDAwMDAwIG4gCjAwMDAwMDAwNTggMDAwMDAgbiAKMDAwMDAwMDExNSAwMDAwMCBuIAowMDAwMDAwMjQzIDAwMDAwIG4gCjAwMDAwMDA0NjggMDAwMDAgbiAKdHJhaWxlcgo8PCAvU2l6ZSA2IC9Sb290IDEgMCBSID4+CnN0YXJ0eHJlZgo1NDYKJSVFT0YK";
+
+// Single page PDF containing only text
+const TEST_TEXT_PDF: &str = "JVBERi0xLjQKMSAwIG9iago8PCAvVHlwZSAvQ2F0YWxvZyAvUGFnZXMgMiAwIFIgPj4KZW5kb2JqCjIgMCBvYmoKPDwgL1R5cGUgL1BhZ2VzIC9LaWRzIFszIDAgUl0gL0NvdW50IDEgPj4KZW5kb2JqCjMgMCBvYmoKPDwgL1R5cGUgL1BhZ2UgL1BhcmVudCAyIDAgUiAvTWVkaWFCb3ggWzAgMCAyMDAgNTBdIC9SZXNvdXJjZXMgPDwgL0ZvbnQgPDwgL0YxIDQgMCBSID4+ID4+IC9Db250ZW50cyA1IDAgUiA+PgplbmRvYmoKNCAwIG9iago8PCAvVHlwZSAvRm9udCAvU3VidHlwZSAvVHlwZTEgL0Jhc2VGb250IC9IZWx2ZXRpY2EgPj4KZW5kb2JqCjUgMCBvYmoKPDwgL0xlbmd0aCAzNSA+PgpzdHJlYW0KQlQgL0YxIDEyIFRmIDEwIDIwIFRkIChIZWxsbykgVGogRVQKZW5kc3RyZWFtCmVuZG9iagp4cmVmCjAgNgowMDAwMDAwMDAwIDY1NTM1IGYgCjAwMDAwMDAwMDkgMDAwMDAgbiAKMDAwMDAwMDA1OCAwMDAwMCBuIAowMDAwMDAwMTE1IDAwMDAwIG4gCjAwMDAwMDAyNDAgMDAwMDAgbiAKMDAwMDAwMDMxMCAwMDAwMCBuIAp0cmFpbGVyCjw8IC9TaXplIDYgL1Jvb3QgMSAwIFIgPj4Kc3RhcnR4cmVmCjM5NQolJUVPRgo=";
+
+// 20000x1 RGB PNG, wider than the decoder accepts
+const TEST_HUGE_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAATiAAAAABCAIAAAC01gnZAAAAUUlEQVR42u3BMQEAAADCoPVPbQ0PoAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAADODOphAAGbMjHtAAAAAElFTkSuQmCC";
@@ -0,0 +1,355 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore, manage::ManageDirectory, PrincipalField, PrincipalUpdate,
+        PrincipalValue,
+    },
+    Permission, Principal, QueryBy, Type,
+};
+
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, scim::ScimClient},
+};
+
+use super::{enterprise::List, JMAPTest, ManagementApi};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running domain administration tests...");
+    let server = params.server.clone();
+    let store = &server.core.storage.data;
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Create a domain administrator for scoped.org
+    store
+        .create_test_domains(&["scoped.org", "other.org"])
+        .await;
+    let admin_id = api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "da_admin")
+                .with_field(PrincipalField::Secrets, "da-secret")
+                .with_field(PrincipalField::Emails, "da_admin@scoped.org")
+                .with_field(PrincipalField::Roles, vec!["domain-admin".to_string()])
+                .with_field(PrincipalField::DomainScope, vec!["Scoped.org".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    let access_token = server.get_access_token(admin_id).await.unwrap();
+    assert_eq!(access_token.domain_scope, vec!["scoped.org".to_string()]);
+    assert!(access_token.has_permission(Permission::IndividualCreate));
+    assert!(!access_token.has_permission(Permission::DomainCreate));
+
+    // The scope can only reference existing domains
+    api.patch::<()>(
+        "/api/principal/da_admin",
+        &vec![PrincipalUpdate::add_item(
+            PrincipalField::DomainScope,
+            PrincipalValue::String("unknown.org".to_string()),
+        )],
+    )
+    .await
+    .unwrap()
+    .expect_error("notFound");
+
+    // Create principals outside the scope and a global administrator inside it
+    let outside_id = store
+        .create_test_user("da_outside", "1234", "Outsider", &["da_outside@other.org"])
+        .await;
+    let outside_group_id = store
+        .create_test_group("da_outside_group", "Outsiders", &["da_group@other.org"])
+        .await;
+    let boss_id = api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "da_boss")
+                .with_field(PrincipalField::Emails, "da_boss@scoped.org")
+                .with_field(PrincipalField::Roles, vec!["admin".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+
+    // Domain administrators can create principals within their domains
+    let da_api = ManagementApi::new(8899, "da_admin", "da-secret");
+    let john_id = da_api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "da_john")
+                .with_field(PrincipalField::Secrets, "john-secret")
+                .with_field(PrincipalField::Emails, "da_john@scoped.org")
+                .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    let group_id = da_api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Group)
+                .with_field(PrincipalField::Name, "da_sales")
+                .with_field(PrincipalField::Emails, "da_sales@scoped.org")
+                .with_field(PrincipalField::Members, vec!["da_john".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+
+    // But not outside them, nor grant privileges
+    for principal in [
+        Principal::new(u32::MAX, Type::Individual)
+            .with_field(PrincipalField::Name, "da_jane")
+            .with_field(PrincipalField::Emails, "da_jane@other.org"),
+        Principal::new(u32::MAX, Type::Individual).with_field(PrincipalField::Name, "da_jane"),
+        Principal::new(u32::MAX, Type::Individual)
+            .with_field(PrincipalField::Name, "da_jane")
+            .with_field(PrincipalField::Emails, "da_jane@scoped.org")
+            .with_field(PrincipalField::Roles, vec!["admin".to_string()]),
+        Principal::new(u32::MAX, Type::Individual)
+            .with_field(PrincipalField::Name, "da_jane")
+            .with_field(PrincipalField::Emails, "da_jane@scoped.org")
+            .with_field(PrincipalField::DomainScope, vec!["other.org".to_string()]),
+        Principal::new(u32::MAX, Type::Group)
+            .with_field(PrincipalField::Name, "da_everyone")
+            .with_field(PrincipalField::Emails, "da_everyone@scoped.org")
+            .with_field(PrincipalField::MemberFilter, "domain = \"other.org\""),
+        Principal::new(u32::MAX, Type::Domain).with_field(PrincipalField::Name, "new.org"),
+    ] {
+        da_api
+            .post::<u32>("/api/principal", &principal)
+            .await
+            .unwrap()
+            .expect_request_error("Forbidden");
+    }
+
+    // Domains are compared case-insensitively
+    let mixed_id = da_api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "da_mixed")
+                .with_field(PrincipalField::Emails, "da_mixed@Scoped.ORG")
+                .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    da_api
+        .delete::<()>("/api/principal/da_mixed")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(store.get_principal(mixed_id).await.unwrap().is_none());
+
+    // Only principals within the scope are listed
+    let list = da_api
+        .get::<List<Principal>>("/api/principal?types=individual,group")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let mut names = list
+        .items
+        .iter()
+        .map(|p| p.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["da_admin", "da_boss", "da_john", "da_sales"]);
+    assert_eq!(list.total, 4);
+
+    // Principals outside the scope are not found
+    da_api
+        .get::<Principal>("/api/principal/da_john")
+        .await
+        .unwrap()
+        .unwrap_data();
+    for name in ["da_outside", "da_outside_group"] {
+        da_api
+            .get::<Principal>(&format!("/api/principal/{name}"))
+            .await
+            .unwrap()
+            .expect_error("notFound");
+    }
+    da_api
+        .delete::<()>("/api/principal/da_outside")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+
+    // Administrators within the scope cannot be managed
+    da_api
+        .patch::<()>(
+            "/api/principal/da_boss",
+            &vec![PrincipalUpdate::set(
+                PrincipalField::Secrets,
+                PrincipalValue::String("hijacked".to_string()),
+            )],
+        )
+        .await
+        .unwrap()
+        .expect_request_error("Forbidden");
+
+    // Updates must stay within the scope
+    for update in [
+        PrincipalUpdate::add_item(
+            PrincipalField::Emails,
+            PrincipalValue::String("john@other.org".to_string()),
+        ),
+        PrincipalUpdate::add_item(
+            PrincipalField::Roles,
+            PrincipalValue::String("admin".to_string()),
+        ),
+        PrincipalUpdate::add_item(
+            PrincipalField::EnabledPermissions,
+            PrincipalValue::String(Permission::SettingsUpdate.name().to_string()),
+        ),
+    ] {
+        da_api
+            .patch::<()>("/api/principal/da_john", &vec![update])
+            .await
+            .unwrap()
+            .expect_request_error("Forbidden");
+    }
+    da_api
+        .patch::<()>(
+            "/api/principal/da_sales",
+            &vec![PrincipalUpdate::set(
+                PrincipalField::MemberFilter,
+                PrincipalValue::String("domain = \"other.org\"".to_string()),
+            )],
+        )
+        .await
+        .unwrap()
+        .expect_request_error("Forbidden");
+    da_api
+        .patch::<()>(
+            "/api/principal/da_john",
+            &vec![PrincipalUpdate::add_item(
+                PrincipalField::MemberOf,
+                PrincipalValue::String("da_outside_group".to_string()),
+            )],
+        )
+        .await
+        .unwrap()
+        .expect_error("notFound");
+    da_api
+        .patch::<()>(
+            "/api/principal/da_john",
+            &vec![
+                PrincipalUpdate::add_item(
+                    PrincipalField::Emails,
+                    PrincipalValue::String("john.doe@scoped.org".to_string()),
+                ),
+                PrincipalUpdate::set(
+                    PrincipalField::Description,
+                    PrincipalValue::String("John Doe".to_string()),
+                ),
+            ],
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        store
+            .query(QueryBy::Id(john_id), false)
+            .await
+            .unwrap()
+            .unwrap()
+            .get_str_array(PrincipalField::Emails),
+        Some(
+            &[
+                "da_john@scoped.org".to_string(),
+                "john.doe@scoped.org".to_string()
+            ][..]
+        )
+    );
+
+    // SCIM provisioning is subject to the same scope
+    let scim = ScimClient::new_basic("da_admin", "da-secret");
+    let (status, list) = scim.request(Method::GET, "/Users", None).await;
+    assert_eq!(status, StatusCode::OK, "{list}");
+    let mut names = list["Resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["userName"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["da_admin", "da_boss", "da_john"]);
+    let (_, list) = scim
+        .request(
+            Method::GET,
+            "/Users?filter=userName%20eq%20%22da_outside%22",
+            None,
+        )
+        .await;
+    assert_eq!(list["totalResults"], 0, "{list}");
+    for method in [Method::GET, Method::DELETE] {
+        let (status, _) = scim
+            .request(method, &format!("/Users/{outside_id}"), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    let (status, _) = scim
+        .request(Method::GET, &format!("/Users/{boss_id}"), None)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = scim
+        .request(
+            Method::POST,
+            "/Users",
+            json!({
+                "userName": "da_jane",
+                "emails": [{"value": "da_jane@other.org", "primary": true}]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = scim
+        .request(
+            Method::PATCH,
+            &format!("/Users/{john_id}"),
+            json!({
+                "Operations": [{"op": "add", "path": "emails", "value": [{"value": "john@other.org"}]}]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(store.get_principal(outside_id).await.unwrap().is_some());
+
+    // Deleting principals within the scope is allowed
+    da_api
+        .delete::<()>("/api/principal/da_sales")
+        .await
+        .unwrap()
+        .unwrap_data();
+    da_api
+        .delete::<()>("/api/principal/da_john")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(store.get_principal(group_id).await.unwrap().is_none());
+
+    // Remove test data
+    for id in [admin_id, boss_id, outside_id, outside_group_id] {
+        store.delete_principal(QueryBy::Id(id)).await.unwrap();
+    }
+    for domain in ["scoped.org", "other.org"] {
+        store.delete_principal(QueryBy::Name(domain)).await.unwrap();
+    }
+    server.directory().groups.clear();
+    assert_is_empty(server).await;
+}
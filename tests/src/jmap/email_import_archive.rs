@@ -0,0 +1,297 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+
+use super::JMAPTest;
+
+const TEST_MBOX: &str = concat!(
+    "From alice@example.com Sat Jan  3 01:05:34 2004\n",
+    "From: alice@example.com\n",
+    "Subject: First message\n",
+    "\n",
+    "Hello\n",
+    ">From the archive\n",
+    "\n",
+    "From bob@example.com Sun Jan  4 11:15:00 2004\n",
+    "From: bob@example.com\n",
+    "Subject: Second message\n",
+    "\n",
+    "World\n",
+    "\n",
+    "From carol@example.com Mon Jan  5 18:45:12 2004\n",
+    "From: carol@example.com\n",
+    "Subject: Third message\n",
+    "\n",
+    "Goodbye\n",
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email archive import tests...");
+
+    // Create test account
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Upload an mbox file and an invalid zip archive
+    let response = request(
+        &account_id,
+        &serde_json::json!([[
+            "Blob/upload",
+            {
+                "accountId": "$$",
+                "create": {
+                    "mbox": { "data": [{ "data:asText": TEST_MBOX }] },
+                    "zip": { "data": [{ "data:asText": "This is not a zip file" }] }
+                }
+            },
+            "0"
+        ]])
+        .to_string(),
+    )
+    .await;
+    let created = &response["methodResponses"][0][1]["created"];
+    let mbox_blob_id = created["mbox"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let zip_blob_id = created["zip"]["id"].as_str().unwrap().to_string();
+
+    // Archives can be previewed with Email/parse
+    let response = request(
+        &account_id,
+        &r#"[[ "Email/parse", {
+            "accountId": "$$",
+            "blobIds": ["$m", "$z"],
+            "format": "mbox",
+            "properties": ["subject", "size"]
+          }, "0" ]]"#
+            .replace("$m", &mbox_blob_id)
+            .replace("$z", &zip_blob_id),
+    )
+    .await;
+    let mut subjects = response["methodResponses"][0][1]["parsed"]
+        .as_object()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .values()
+        .map(|email| email["subject"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    subjects.sort();
+    assert_eq!(
+        subjects,
+        ["First message", "Second message", "Third message"],
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][0][1]["notParsable"],
+        serde_json::json!([zip_blob_id]),
+        "{response:#?}"
+    );
+
+    // Import both archives into the inbox
+    let response = request(
+        &account_id,
+        r#"[[ "Mailbox/query", {
+            "accountId": "$$",
+            "filter": { "role": "inbox" }
+          }, "0" ]]"#,
+    )
+    .await;
+    let inbox_id = response["methodResponses"][0][1]["ids"][0]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let response = request(
+        &account_id,
+        &r##"[[ "Email/import", {
+            "accountId": "$$",
+            "emails": {
+              "mbox": {
+                "blobId": "$m",
+                "mailboxIds": { "$i": true },
+                "keywords": { "$seen": true },
+                "format": "mbox"
+              },
+              "zip": {
+                "blobId": "$z",
+                "mailboxIds": { "$i": true },
+                "format": "zip"
+              }
+            }
+          }, "0" ],
+          [ "EmailImportTask/get", {
+            "accountId": "$$",
+            "ids": ["#mbox", "#zip"],
+            "properties": ["format", "blobId", "mailboxIds"]
+          }, "1" ]]"##
+            .replace("$m", &mbox_blob_id)
+            .replace("$z", &zip_blob_id)
+            .replace("$i", &inbox_id),
+    )
+    .await;
+    let created = &response["methodResponses"][0][1]["created"];
+    let mbox_task_id = created["mbox"]["taskId"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let zip_task_id = created["zip"]["taskId"].as_str().unwrap().to_string();
+    let list = &response["methodResponses"][1][1]["list"];
+    assert_eq!(list[0]["id"], mbox_task_id, "{response:#?}");
+    assert_eq!(list[0]["format"], "mbox", "{response:#?}");
+    assert_eq!(list[0]["blobId"], mbox_blob_id, "{response:#?}");
+    assert_eq!(list[0]["mailboxIds"][&inbox_id], true, "{response:#?}");
+    assert_eq!(list[1]["id"], zip_task_id, "{response:#?}");
+    assert_eq!(list[1]["format"], "zip", "{response:#?}");
+
+    // Wait for both imports to finish
+    let mut tasks = Value::Null;
+    for _ in 0..50 {
+        let response = request(
+            &account_id,
+            r#"[[ "EmailImportTask/get", {
+                "accountId": "$$"
+              }, "0" ]]"#,
+        )
+        .await;
+        tasks = response["methodResponses"][0][1]["list"].clone();
+        if tasks
+            .as_array()
+            .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+            .iter()
+            .all(|task| !matches!(task["status"].as_str(), Some("pending" | "running")))
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let task = |id: &str| {
+        tasks
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|task| task["id"] == id)
+            .unwrap_or_else(|| panic!("Task {id} not found: {tasks:#?}"))
+            .clone()
+    };
+    let mbox_task = task(&mbox_task_id);
+    assert_eq!(mbox_task["status"], "completed", "{mbox_task:#?}");
+    assert_eq!(mbox_task["totalEmails"], 3, "{mbox_task:#?}");
+    assert_eq!(mbox_task["importedEmails"], 3, "{mbox_task:#?}");
+    assert_eq!(mbox_task["failedEmails"], 0, "{mbox_task:#?}");
+    assert_eq!(mbox_task["error"], Value::Null, "{mbox_task:#?}");
+    let zip_task = task(&zip_task_id);
+    assert_eq!(zip_task["status"], "failed", "{zip_task:#?}");
+    assert_eq!(zip_task["importedEmails"], 0, "{zip_task:#?}");
+    assert!(
+        zip_task["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("zip")),
+        "{zip_task:#?}"
+    );
+
+    // Imported messages are in the inbox with the requested keywords
+    let response = request(
+        &account_id,
+        r##"[[ "Email/query", {
+            "accountId": "$$",
+            "sort": [{ "property": "receivedAt", "isAscending": true }]
+          }, "0" ],
+          [ "Email/get", {
+            "accountId": "$$",
+            "#ids": {
+              "resultOf": "0",
+              "name": "Email/query",
+              "path": "/ids"
+            },
+            "properties": ["subject", "keywords", "mailboxIds", "receivedAt"]
+          }, "1" ]]"##,
+    )
+    .await;
+    let emails = response["methodResponses"][1][1]["list"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    assert_eq!(emails.len(), 3, "{response:#?}");
+    for (email, (subject, received_at)) in emails.iter().zip([
+        ("First message", "2004-01-03T01:05:34Z"),
+        ("Second message", "2004-01-04T11:15:00Z"),
+        ("Third message", "2004-01-05T18:45:12Z"),
+    ]) {
+        assert_eq!(email["subject"], subject, "{response:#?}");
+        assert_eq!(email["receivedAt"], received_at, "{response:#?}");
+        assert_eq!(email["keywords"]["$seen"], true, "{response:#?}");
+        assert_eq!(email["mailboxIds"][&inbox_id], true, "{response:#?}");
+    }
+
+    // Tasks cannot be created or modified by clients, only destroyed
+    let response = request(
+        &account_id,
+        &r#"[[ "EmailImportTask/set", {
+            "accountId": "$$",
+            "create": { "t": { "format": "mbox" } },
+            "update": { "$a": { "status": "running" } },
+            "destroy": ["$a", "$b"]
+          }, "0" ],
+          [ "EmailImportTask/get", {
+            "accountId": "$$"
+          }, "1" ]]"#
+            .replace("$a", &mbox_task_id)
+            .replace("$b", &zip_task_id),
+    )
+    .await;
+    let set_response = &response["methodResponses"][0][1];
+    assert_eq!(
+        set_response["notCreated"]["t"]["type"], "forbidden",
+        "{response:#?}"
+    );
+    assert_eq!(
+        set_response["notUpdated"][&mbox_task_id]["type"], "forbidden",
+        "{response:#?}"
+    );
+    assert_eq!(
+        set_response["destroyed"],
+        serde_json::json!([mbox_task_id, zip_task_id]),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["list"],
+        serde_json::json!([]),
+        "{response:#?}"
+    );
+
+    // Clean up
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
@@ -0,0 +1,220 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap::email::snooze::EmailSnooze;
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+use store::ahash::AHashMap;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email snooze tests...");
+
+    // Create test account
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Deliver test messages
+    let mut lmtp = SmtpConnection::connect().await;
+    for subject in ["Wake me up", "Not yet", "Move me"] {
+        lmtp.ingest(
+            "bill@example.com",
+            &["jdoe@example.com"],
+            &format!(
+                "From: bill@example.com\r\nTo: jdoe@example.com\r\nSubject: {subject}\r\n\r\nTest\r\n"
+            ),
+        )
+        .await;
+    }
+
+    // Create the Snoozed and Later mailboxes
+    let response = request(
+        &account_id,
+        r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "create": {
+                "s": { "name": "Snoozed", "role": "snoozed" },
+                "l": { "name": "Later" }
+            }
+          }, "0" ],
+          [ "Mailbox/query", {
+            "accountId": "$$",
+            "filter": { "role": "inbox" }
+          }, "1" ]]"#,
+    )
+    .await;
+    let snoozed_id = response["methodResponses"][0][1]["created"]["s"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let later_id = response["methodResponses"][0][1]["created"]["l"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let inbox_id = response["methodResponses"][1][1]["ids"][0]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let emails = get_emails(&account_id).await;
+    assert_eq!(emails.len(), 3, "{emails:#?}");
+
+    // Snoozing moves messages to the Snoozed mailbox
+    let response = request(
+        &account_id,
+        &r#"[[ "Email/set", {
+            "accountId": "$$",
+            "update": {
+                "$a": { "keywords/$seen": true, "snoozed": { "until": "2000-01-01T00:00:00Z" } },
+                "$b": { "snoozed": { "until": "2100-01-01T00:00:00Z" } },
+                "$c": { "snoozed": { "until": "2000-01-01T00:00:00Z", "moveToMailboxId": "zzzz" } }
+            }
+          }, "0" ]]"#
+            .replace("$a", emails["Wake me up"]["id"].as_str().unwrap())
+            .replace("$b", emails["Not yet"]["id"].as_str().unwrap())
+            .replace("$c", emails["Move me"]["id"].as_str().unwrap()),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .map_or(0, |updated| updated.len()),
+        2,
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][0][1]["notUpdated"]
+            .as_object()
+            .map_or(0, |not_updated| not_updated.len()),
+        1,
+        "{response:#?}"
+    );
+    let emails = get_emails(&account_id).await;
+    for subject in ["Wake me up", "Not yet"] {
+        let email = &emails[subject];
+        assert_eq!(mailbox_ids(email), vec![snoozed_id.as_str()], "{email:#?}");
+        assert_eq!(email["keywords"]["$snoozed"], true, "{email:#?}");
+        assert!(email["snoozed"]["until"].is_string(), "{email:#?}");
+    }
+    assert_eq!(
+        emails["Not yet"]["snoozed"]["until"],
+        "2100-01-01T00:00:00Z"
+    );
+    assert_eq!(mailbox_ids(&emails["Move me"]), vec![inbox_id.as_str()]);
+    assert_eq!(emails["Move me"]["snoozed"], Value::Null);
+
+    // Snooze again with a valid target mailbox
+    let response = request(
+        &account_id,
+        &r#"[[ "Email/set", {
+            "accountId": "$$",
+            "update": {
+                "$c": { "snoozed": { "until": "2000-01-01T00:00:00Z", "moveToMailboxId": "$l" } }
+            }
+          }, "0" ]]"#
+            .replace("$c", emails["Move me"]["id"].as_str().unwrap())
+            .replace("$l", &later_id),
+    )
+    .await;
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .is_some_and(|updated| updated.len() == 1),
+        "{response:#?}"
+    );
+
+    // Due messages are moved back as unread
+    server.wake_snoozed().await.unwrap();
+    let emails = get_emails(&account_id).await;
+    let email = &emails["Wake me up"];
+    assert_eq!(mailbox_ids(email), vec![inbox_id.as_str()], "{email:#?}");
+    assert_eq!(email["keywords"]["$seen"], Value::Null, "{email:#?}");
+    assert_eq!(email["keywords"]["$snoozed"], Value::Null, "{email:#?}");
+    assert_eq!(email["snoozed"], Value::Null, "{email:#?}");
+    let email = &emails["Move me"];
+    assert_eq!(mailbox_ids(email), vec![later_id.as_str()], "{email:#?}");
+    assert_eq!(email["snoozed"], Value::Null, "{email:#?}");
+    let email = &emails["Not yet"];
+    assert_eq!(mailbox_ids(email), vec![snoozed_id.as_str()], "{email:#?}");
+    assert_eq!(email["keywords"]["$snoozed"], true, "{email:#?}");
+
+    // Cancelling a snooze leaves the message in place
+    request(
+        &account_id,
+        &r#"[[ "Email/set", {
+            "accountId": "$$",
+            "update": { "$b": { "snoozed": null } }
+          }, "0" ]]"#
+            .replace("$b", emails["Not yet"]["id"].as_str().unwrap()),
+    )
+    .await;
+    let emails = get_emails(&account_id).await;
+    let email = &emails["Not yet"];
+    assert_eq!(mailbox_ids(email), vec![snoozed_id.as_str()], "{email:#?}");
+    assert_eq!(email["keywords"]["$snoozed"], Value::Null, "{email:#?}");
+    assert_eq!(email["snoozed"], Value::Null, "{email:#?}");
+
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
+
+async fn get_emails(account_id: &Id) -> AHashMap<String, Value> {
+    let mut response = request(
+        account_id,
+        r#"[[ "Email/get", {
+            "accountId": "$$",
+            "properties": ["id", "subject", "mailboxIds", "keywords", "snoozed"]
+          }, "0" ]]"#,
+    )
+    .await;
+
+    match response["methodResponses"][0][1]["list"].take() {
+        Value::Array(list) => list
+            .into_iter()
+            .map(|email| (email["subject"].as_str().unwrap().to_string(), email))
+            .collect(),
+        _ => panic!("Unexpected response: {response:#?}"),
+    }
+}
+
+fn mailbox_ids(email: &Value) -> Vec<&str> {
+    email["mailboxIds"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(|id| id.as_str())
+        .collect()
+}
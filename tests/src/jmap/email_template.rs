@@ -0,0 +1,322 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Instant;
+
+use common::core::BuildServer;
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        email_submission::{expect_message_delivery, expect_nothing, spawn_mock_smtp_server},
+        jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email template tests...");
+
+    // Create test account
+    let server = params.server.inner.build_server();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Start mock SMTP server
+    let (mut smtp_rx, smtp_settings) = spawn_mock_smtp_server();
+    server.core.smtp.resolvers.dns.ipv4_add(
+        "localhost",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + std::time::Duration::from_secs(10),
+    );
+
+    // Obtain default identity
+    let response = request(
+        &account_id,
+        r#"[[ "Identity/get", {
+            "accountId": "$$"
+          }, "0" ]]"#,
+    )
+    .await;
+    let identity_id = response["methodResponses"][0][1]["list"][0]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+
+    // Templates are validated on creation
+    let response = request(
+        &account_id,
+        r##"[[ "EmailTemplate/set", {
+            "accountId": "$$",
+            "create": {
+              "tpl": {
+                "name": "Welcome",
+                "subject": "Hello {{ name }}",
+                "textBody": "Dear {{name}}, your code is {{code}}.",
+                "htmlBody": "<p>Dear {{name}}, your code is <b>{{code}}</b>.</p>"
+              },
+              "unterminated": {
+                "subject": "Hello {{ name",
+                "textBody": "Hi"
+              },
+              "no-body": {
+                "subject": "Hello"
+              }
+            }
+          }, "0" ],
+          [ "EmailTemplate/get", {
+            "accountId": "$$",
+            "ids": ["#tpl"]
+          }, "1" ]]"##,
+    )
+    .await;
+    let set_response = &response["methodResponses"][0][1];
+    let template_id = set_response["created"]["tpl"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    for (id, property) in [("unterminated", "subject"), ("no-body", "textBody")] {
+        assert_eq!(
+            set_response["notCreated"][id]["type"], "invalidProperties",
+            "{response:#?}"
+        );
+        assert_eq!(
+            set_response["notCreated"][id]["properties"][0], property,
+            "{response:#?}"
+        );
+    }
+    let template = &response["methodResponses"][1][1]["list"][0];
+    assert_eq!(template["id"], template_id, "{response:#?}");
+    assert_eq!(template["name"], "Welcome", "{response:#?}");
+    assert_eq!(template["subject"], "Hello {{ name }}", "{response:#?}");
+
+    // Send the template to multiple recipients
+    let response = request(
+        &account_id,
+        &r##"[[ "EmailSubmission/merge", {
+            "accountId": "$$",
+            "templateId": "$t",
+            "identityId": "$i",
+            "variables": { "code": "default" },
+            "recipients": {
+              "jane": {
+                "email": "jane@remote.org",
+                "name": "Jane",
+                "variables": { "code": "<1234>" }
+              },
+              "bill": {
+                "email": "bill@remote.org",
+                "name": "Bill"
+              },
+              "invalid": {
+                "email": "not-an-address"
+              }
+            }
+          }, "0" ],
+          [ "EmailSubmission/get", {
+            "accountId": "$$",
+            "ids": ["#jane", "#bill"],
+            "properties": ["emailId", "undoStatus"]
+          }, "1" ]]"##
+            .replace("$t", &template_id)
+            .replace("$i", &identity_id),
+    )
+    .await;
+    let merge_response = &response["methodResponses"][0][1];
+    for id in ["jane", "bill"] {
+        let created = &merge_response["created"][id];
+        assert!(created["id"].is_string(), "{response:#?}");
+        assert!(created["emailId"].is_string(), "{response:#?}");
+        assert!(created["threadId"].is_string(), "{response:#?}");
+        assert_eq!(created["undoStatus"], "final", "{response:#?}");
+    }
+    assert_eq!(
+        merge_response["notCreated"]["invalid"]["type"], "invalidProperties",
+        "{response:#?}"
+    );
+    let submissions = &response["methodResponses"][1][1]["list"];
+    assert_eq!(
+        submissions[0]["emailId"], merge_response["created"]["jane"]["emailId"],
+        "{response:#?}"
+    );
+    assert_eq!(
+        submissions[1]["emailId"], merge_response["created"]["bill"]["emailId"],
+        "{response:#?}"
+    );
+
+    // Each recipient receives its own rendered copy
+    let mut messages = vec![
+        expect_message_delivery(&mut smtp_rx).await,
+        expect_message_delivery(&mut smtp_rx).await,
+    ];
+    messages.sort_by(|a, b| b.rcpt_to.cmp(&a.rcpt_to));
+    let jane = &messages[0];
+    assert_eq!(jane.mail_from, "<jdoe@example.com>");
+    assert_eq!(jane.rcpt_to, ["<jane@remote.org>"]);
+    for needle in [
+        "Subject: Hello Jane",
+        "Dear Jane, your code is <1234>.",
+        "your code is <b>&lt;1234&gt;</b>",
+    ] {
+        assert!(jane.message.contains(needle), "{needle}: {}", jane.message);
+    }
+    let bill = &messages[1];
+    assert_eq!(bill.rcpt_to, ["<bill@remote.org>"]);
+    for needle in ["Subject: Hello Bill", "Dear Bill, your code is default."] {
+        assert!(bill.message.contains(needle), "{needle}: {}", bill.message);
+    }
+
+    // Missing variables and scheduled sending
+    let response = request(
+        &account_id,
+        &r##"[[ "EmailSubmission/merge", {
+            "accountId": "$$",
+            "templateId": "$t",
+            "identityId": "$i",
+            "sendAt": "2079-11-20T05:00:00Z",
+            "recipients": {
+              "later": {
+                "email": "later@remote.org",
+                "name": "Later",
+                "variables": { "code": "5678" }
+              },
+              "missing": {
+                "email": "missing@remote.org"
+              }
+            }
+          }, "0" ]]"##
+            .replace("$t", &template_id)
+            .replace("$i", &identity_id),
+    )
+    .await;
+    let merge_response = &response["methodResponses"][0][1];
+    let scheduled_id = merge_response["created"]["later"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    assert_eq!(
+        merge_response["created"]["later"]["sendAt"], "2079-11-20T05:00:00Z",
+        "{response:#?}"
+    );
+    assert_eq!(
+        merge_response["created"]["later"]["undoStatus"], "pending",
+        "{response:#?}"
+    );
+    assert_eq!(
+        merge_response["notCreated"]["missing"]["type"], "invalidProperties",
+        "{response:#?}"
+    );
+    assert!(
+        merge_response["notCreated"]["missing"]["description"]
+            .as_str()
+            .is_some_and(|description| description.contains("code")),
+        "{response:#?}"
+    );
+    smtp_settings.lock().do_stop = true;
+    expect_nothing(&mut smtp_rx).await;
+
+    // Sent copies are stored in the Sent mailbox
+    let response = request(
+        &account_id,
+        r#"[[ "Mailbox/query", {
+            "accountId": "$$",
+            "filter": { "role": "sent" }
+          }, "0" ]]"#,
+    )
+    .await;
+    let sent_id = response["methodResponses"][0][1]["ids"][0]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let response = request(
+        &account_id,
+        &r#"[[ "Email/query", {
+            "accountId": "$$",
+            "filter": { "inMailbox": "$m" }
+          }, "0" ]]"#
+            .replace("$m", &sent_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["ids"]
+            .as_array()
+            .map(|ids| ids.len()),
+        Some(3),
+        "{response:#?}"
+    );
+
+    // Cancel the scheduled submission and remove test data
+    let response = request(
+        &account_id,
+        &r#"[[ "EmailSubmission/set", {
+            "accountId": "$$",
+            "update": { "$s": { "undoStatus": "canceled" } }
+          }, "0" ],
+          [ "EmailTemplate/set", {
+            "accountId": "$$",
+            "destroy": ["$t"]
+          }, "1" ],
+          [ "Identity/set", {
+            "accountId": "$$",
+            "destroy": ["$i"]
+          }, "2" ],
+          [ "EmailSubmission/query", {
+            "accountId": "$$"
+          }, "3" ]]"#
+            .replace("$s", &scheduled_id)
+            .replace("$t", &template_id)
+            .replace("$i", &identity_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"][&scheduled_id],
+        Value::Null,
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["destroyed"],
+        serde_json::json!([template_id]),
+        "{response:#?}"
+    );
+    let submission_ids = response["methodResponses"][3][1]["ids"].clone();
+    request(
+        &account_id,
+        &r#"[[ "EmailSubmission/set", {
+            "accountId": "$$",
+            "destroy": $ids
+          }, "0" ]]"#
+            .replace("$ids", &submission_ids.to_string()),
+    )
+    .await;
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
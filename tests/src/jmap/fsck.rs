@@ -0,0 +1,142 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{backend::internal::manage::ManageDirectory, QueryBy};
+use jmap::{
+    mailbox::INBOX_ID,
+    services::fsck::{FsckIssueType, StoreCheck},
+    JmapMethods,
+};
+use jmap_proto::types::{collection::Collection, id::Id, property::Property};
+use store::write::{BatchBuilder, DirectoryClass, TagValue, F_CLEAR};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, wait_for_index},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running store consistency checks...");
+    let server = params.server.clone();
+    let client = &mut params.client;
+    let inbox_id = Id::from(INBOX_ID).to_string();
+
+    // Create test account and messages
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_test_user(
+            "jdoe@example.com",
+            "12345",
+            "John Doe",
+            &["jdoe@example.com"],
+        )
+        .await;
+    client.set_default_account_id(Id::from(account_id));
+    let mut message_ids = Vec::new();
+    for num in 0..3 {
+        message_ids.push(
+            Id::from_bytes(
+                client
+                    .email_import(
+                        format!(
+                            concat!(
+                                "From: bill@example.com\r\n",
+                                "To: jdoe@example.com\r\n",
+                                "Subject: TPS Report #{}\r\n",
+                                "\r\n",
+                                "I'm going to need those TPS reports ASAP."
+                            ),
+                            num
+                        )
+                        .into_bytes(),
+                        [&inbox_id],
+                        None::<Vec<&str>>,
+                        None,
+                    )
+                    .await
+                    .unwrap()
+                    .take_id()
+                    .as_bytes(),
+            )
+            .unwrap(),
+        );
+    }
+    wait_for_index(&server).await;
+
+    // A consistent account should report no issues
+    let report = server.fsck(Some(account_id), None, false).await.unwrap();
+    assert_eq!(report.accounts, 1);
+    assert_eq!(report.issues, vec![]);
+
+    // Corrupt the mailbox membership and the quota counter
+    let document_id = message_ids[0].document_id();
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .update_document(document_id)
+        .tag(Property::MailboxIds, INBOX_ID, F_CLEAR);
+    batch.add(DirectoryClass::UsedQuota(account_id), 12345);
+    server.store().write(batch.build()).await.unwrap();
+
+    // Both issues should be reported but not repaired
+    let report = server.fsck(Some(account_id), None, false).await.unwrap();
+    let mut issues = report
+        .issues
+        .iter()
+        .map(|issue| (issue.typ, issue.repaired))
+        .collect::<Vec<_>>();
+    issues.sort_unstable_by_key(|(typ, _)| *typ as u8);
+    assert_eq!(
+        issues,
+        vec![
+            (FsckIssueType::MailboxMembership, false),
+            (FsckIssueType::QuotaMismatch, false)
+        ],
+        "{:?}",
+        report.issues
+    );
+    assert!(report
+        .issues
+        .iter()
+        .any(|issue| issue.typ == FsckIssueType::MailboxMembership
+            && issue.details.contains("missing 1 ")));
+
+    // Repair and make sure the account is consistent again
+    let report = server.fsck(Some(account_id), None, true).await.unwrap();
+    assert_eq!(report.issues.len(), 2);
+    assert!(report.issues.iter().all(|issue| issue.repaired));
+    let report = server.fsck(None, None, false).await.unwrap();
+    assert_eq!(report.issues, vec![]);
+    assert_eq!(
+        server
+            .get_tag(
+                account_id,
+                Collection::Email,
+                Property::MailboxIds,
+                TagValue::Id(INBOX_ID)
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .len(),
+        3
+    );
+
+    // Delete account
+    server
+        .core
+        .storage
+        .data
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
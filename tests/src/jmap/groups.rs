@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashSet;
+use directory::{
+    backend::internal::{
+        manage::{ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+    },
+    Principal, QueryBy, Type,
+};
+
+use crate::{directory::internal::TestInternalDirectory, jmap::assert_is_empty};
+
+use super::{JMAPTest, ManagementApi};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running nested and dynamic group tests...");
+    let server = params.server.clone();
+    let store = &server.core.storage.data;
+
+    // Create test accounts and groups
+    let alice_id = store
+        .create_test_user("grp_alice", "1234", "Alice", &["grp_alice@example.com"])
+        .await;
+    let bob_id = store
+        .create_test_user("grp_bob", "1234", "Bob", &["grp_bob@example.com"])
+        .await;
+    let eng_id = store
+        .create_test_group("grp_eng", "Engineering", &["grp_eng@example.com"])
+        .await;
+    let staff_id = store
+        .create_test_group("grp_staff", "Staff", &["grp_staff@example.com"])
+        .await;
+    let all_id = store
+        .create_test_group("grp_all", "Everyone", &["grp_all@example.com"])
+        .await;
+    store.add_to_group("grp_alice", "grp_eng").await;
+    store.add_to_group("grp_eng", "grp_staff").await;
+    store.add_to_group("grp_staff", "grp_all").await;
+
+    // Group membership is transitive
+    assert_eq!(
+        member_of(&server, alice_id).await,
+        AHashSet::from_iter([eng_id, staff_id, all_id])
+    );
+    assert_eq!(member_of(&server, bob_id).await, AHashSet::new());
+
+    // Membership cycles are rejected
+    for (principal, field, value) in [
+        ("grp_all", PrincipalField::MemberOf, "grp_eng"),
+        ("grp_eng", PrincipalField::Members, "grp_all"),
+        ("grp_eng", PrincipalField::MemberOf, "grp_eng"),
+    ] {
+        let err = store
+            .update_principal(UpdatePrincipal::by_name(principal).with_updates(vec![
+                PrincipalUpdate::add_item(field, PrincipalValue::String(value.to_string())),
+            ]))
+            .await
+            .unwrap_err();
+        assert!(
+            err.matches(trc::EventType::Manage(trc::ManageEvent::Error)),
+            "{err:?}"
+        );
+    }
+
+    // Dynamic groups match principals by expression
+    let err = store
+        .create_principal(
+            Principal::new(0, Type::Group)
+                .with_field(PrincipalField::Name, "grp_invalid")
+                .with_field(PrincipalField::MemberFilter, "domain ="),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.matches(trc::EventType::Manage(trc::ManageEvent::Error)),
+        "{err:?}"
+    );
+    let dyn_id = store
+        .create_principal(
+            Principal::new(0, Type::Group)
+                .with_field(PrincipalField::Name, "grp_dynamic")
+                .with_field(
+                    PrincipalField::MemberFilter,
+                    "domain = \"example.com\" and role = user and not (name = grp_bob)",
+                ),
+            None,
+        )
+        .await
+        .unwrap();
+    store.add_to_group("grp_dynamic", "grp_staff").await;
+    server.directory().groups.clear();
+    assert_eq!(
+        member_of(&server, alice_id).await,
+        AHashSet::from_iter([eng_id, staff_id, all_id, dyn_id])
+    );
+    assert_eq!(member_of(&server, bob_id).await, AHashSet::new());
+
+    // Nested mailing lists are expanded recursively
+    let list1_id = store
+        .create_test_list("grp_list1@example.com", "List 1", &["grp_bob"])
+        .await;
+    let list2_id = store
+        .create_test_list("grp_list2@example.com", "List 2", &["grp_alice"])
+        .await;
+    store
+        .update_principal(UpdatePrincipal::by_id(list1_id).with_updates(vec![
+            PrincipalUpdate::add_item(
+                PrincipalField::Members,
+                PrincipalValue::String("grp_list2@example.com".to_string()),
+            ),
+        ]))
+        .await
+        .unwrap();
+    assert!(store
+        .update_principal(UpdatePrincipal::by_id(list2_id).with_updates(vec![
+            PrincipalUpdate::add_item(
+                PrincipalField::Members,
+                PrincipalValue::String("grp_list1@example.com".to_string()),
+            )
+        ]),)
+        .await
+        .is_err());
+    assert_eq!(
+        rcpt_ids(&server, "grp_list1@example.com").await,
+        AHashSet::from_iter([alice_id, bob_id])
+    );
+    assert_eq!(
+        server
+            .directory()
+            .expn("grp_list1@example.com")
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<AHashSet<_>>(),
+        AHashSet::from_iter([
+            "grp_alice@example.com".to_string(),
+            "grp_bob@example.com".to_string()
+        ])
+    );
+
+    // Dynamic mailing lists
+    let dyn_list_id = store
+        .create_test_list("grp_dynlist@example.com", "Dynamic list", &[])
+        .await;
+    store
+        .update_principal(UpdatePrincipal::by_id(dyn_list_id).with_updates(vec![
+            PrincipalUpdate::set(
+                PrincipalField::MemberFilter,
+                PrincipalValue::String("email = \"grp_*@example.com\"".to_string()),
+            ),
+        ]))
+        .await
+        .unwrap();
+    server.directory().groups.clear();
+    assert_eq!(
+        rcpt_ids(&server, "grp_dynlist@example.com").await,
+        AHashSet::from_iter([alice_id, bob_id])
+    );
+
+    // Changes to individual accounts update the members of dynamic lists
+    ManagementApi::new(8899, "admin", "secret")
+        .patch::<()>(
+            "/api/principal/grp_bob",
+            &vec![PrincipalUpdate::set(
+                PrincipalField::Emails,
+                PrincipalValue::StringList(vec!["grp-bob@example.com".to_string()]),
+            )],
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        rcpt_ids(&server, "grp_dynlist@example.com").await,
+        AHashSet::from_iter([alice_id])
+    );
+
+    // Remove test data
+    for id in [
+        dyn_list_id,
+        list1_id,
+        list2_id,
+        dyn_id,
+        alice_id,
+        bob_id,
+        eng_id,
+        staff_id,
+        all_id,
+    ] {
+        store.delete_principal(QueryBy::Id(id)).await.unwrap();
+    }
+    server.directory().groups.clear();
+    assert_is_empty(server).await;
+}
+
+async fn member_of(server: &common::Server, account_id: u32) -> AHashSet<u32> {
+    server
+        .get_access_token(account_id)
+        .await
+        .unwrap()
+        .member_of
+        .into_iter()
+        .collect()
+}
+
+async fn rcpt_ids(server: &common::Server, address: &str) -> AHashSet<u32> {
+    server
+        .directory()
+        .email_to_ids(address)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,153 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{auth::AuthRequest, core::BuildServer, Server};
+use directory::{
+    backend::internal::{PrincipalField, PrincipalUpdate, PrincipalValue},
+    AccountStatus, Permission, Principal, Type,
+};
+use jmap::email::delete::EmailDeletion;
+use jmap_proto::types::id::Id;
+
+use crate::jmap::{assert_is_empty, jmap_json_request};
+
+use super::{JMAPTest, ManagementApi, Response};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running account lifecycle tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Create an active account
+    let account_id = api
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "lifecycle_user")
+                .with_field(PrincipalField::Secrets, "lifecycle-secret")
+                .with_field(PrincipalField::Emails, "lifecycle@example.org")
+                .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(get_status(&api).await, AccountStatus::Active);
+    assert_auth(&server).await.unwrap();
+
+    // Invalid states are rejected
+    match set_status(&api, "frozen").await {
+        Response::Error { details, .. } => {
+            assert_eq!(details.as_deref(), Some("Invalid status value"));
+        }
+        _ => panic!("Expected status error"),
+    }
+
+    // Read-only accounts can authenticate but not send
+    set_status(&api, "read-only").await.unwrap_data();
+    assert_eq!(get_status(&api).await, AccountStatus::ReadOnly);
+    assert_auth(&server).await.unwrap();
+    let access_token = server.get_access_token(account_id).await.unwrap();
+    assert!(access_token.has_permission(Permission::EmailReceive));
+    assert!(!access_token.has_permission(Permission::EmailSend));
+    assert!(!access_token.has_permission(Permission::JmapEmailSubmissionSet));
+    assert!(!access_token.has_permission(Permission::JmapEmailSubmissionMerge));
+    let response = jmap_json_request(
+        r#"[[ "EmailSubmission/merge", {
+            "accountId": "$$",
+            "templateId": "a",
+            "identityId": "a",
+            "recipients": { "jane": { "email": "jane@remote.org" } }
+          }, "0" ]]"#
+            .replace("$$", &Id::from(account_id).to_string()),
+        "lifecycle_user",
+        "lifecycle-secret",
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["type"], "forbidden",
+        "{response:#?}"
+    );
+
+    // Suspended accounts cannot authenticate but keep receiving mail
+    set_status(&api, "suspended").await.unwrap_data();
+    assert_eq!(get_status(&api).await, AccountStatus::Suspended);
+    assert_auth(&server).await.unwrap_err();
+    let access_token = server.get_access_token(account_id).await.unwrap();
+    assert!(access_token.has_permission(Permission::EmailReceive));
+    assert!(!access_token.has_permission(Permission::Authenticate));
+
+    // Reactivated accounts regain their permissions
+    set_status(&api, "active").await.unwrap_data();
+    assert_eq!(get_status(&api).await, AccountStatus::Active);
+    assert_auth(&server).await.unwrap();
+    assert!(server
+        .get_access_token(account_id)
+        .await
+        .unwrap()
+        .has_permission(Permission::EmailSend));
+
+    // Accounts pending deletion bounce mail and are kept during the grace period
+    set_status(&api, "pending-deletion").await.unwrap_data();
+    assert_eq!(get_status(&api).await, AccountStatus::PendingDeletion);
+    assert_auth(&server).await.unwrap_err();
+    assert!(!server
+        .get_access_token(account_id)
+        .await
+        .unwrap()
+        .has_permission(Permission::EmailReceive));
+    server.purge_accounts().await;
+    assert_eq!(get_status(&api).await, AccountStatus::PendingDeletion);
+
+    // And removed by the housekeeper once it expires
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.account_lifecycle.deletion_grace_period = Duration::ZERO;
+    params.server.inner.shared_core.store(core.into());
+    params.server.inner.build_server().purge_accounts().await;
+    api.get::<Principal>("/api/principal/lifecycle_user")
+        .await
+        .unwrap()
+        .expect_error("notFound");
+
+    // Restore settings
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.account_lifecycle = Default::default();
+    params.server.inner.shared_core.store(core.into());
+    assert_is_empty(params.server.clone()).await;
+}
+
+async fn get_status(api: &ManagementApi) -> AccountStatus {
+    api.get::<Principal>("/api/principal/lifecycle_user")
+        .await
+        .unwrap()
+        .unwrap_data()
+        .status()
+}
+
+async fn set_status(api: &ManagementApi, status: &str) -> Response<()> {
+    api.patch::<()>(
+        "/api/principal/lifecycle_user",
+        &vec![PrincipalUpdate::set(
+            PrincipalField::Status,
+            PrincipalValue::String(status.to_string()),
+        )],
+    )
+    .await
+    .unwrap()
+}
+
+async fn assert_auth(server: &Server) -> trc::Result<()> {
+    server
+        .authenticate(&AuthRequest::from_plain(
+            "lifecycle_user",
+            "lifecycle-secret",
+            0,
+            "127.0.0.1".parse().unwrap(),
+        ))
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,374 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Instant;
+
+use common::core::BuildServer;
+use jmap_proto::types::id::Id;
+use serde_json::{json, Value};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        delivery::SmtpConnection,
+        email_submission::{expect_message_delivery, expect_nothing, spawn_mock_smtp_server},
+        jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Mail Rules tests...");
+
+    // Create test account
+    let server = params.server.inner.build_server();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Start mock SMTP server
+    let (mut smtp_rx, smtp_settings) = spawn_mock_smtp_server();
+    server.core.smtp.resolvers.dns.ipv4_add(
+        "localhost",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + std::time::Duration::from_secs(10),
+    );
+
+    // Create a target mailbox
+    let response = request(
+        &account_id,
+        r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "create": { "reports": { "name": "Reports" } }
+          }, "0" ]]"#,
+    )
+    .await;
+    let mailbox_id = response["methodResponses"][0][1]["created"]["reports"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+
+    // Rules referencing unknown mailboxes are rejected
+    let response = request(
+        &account_id,
+        r#"[[ "MailRules/set", {
+            "accountId": "$$",
+            "create": {
+              "rules": {
+                "rules": [{
+                  "name": "Invalid",
+                  "conditions": [{ "field": "from", "operator": "is", "value": "a@b.org" }],
+                  "actions": [{ "type": "move", "mailboxId": "zzzzzz" }]
+                }]
+              }
+            }
+          }, "0" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notCreated"]["rules"]["type"], "invalidProperties",
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][0][1]["notCreated"]["rules"]["properties"][0], "rules",
+        "{response:#?}"
+    );
+
+    // Create rules
+    let rules = json!([
+        {
+            "name": "TPS reports",
+            "isEnabled": true,
+            "match": "any",
+            "conditions": [
+                { "field": "subject", "operator": "contains", "value": "TPS" },
+                { "field": "header", "header": "X-Report", "operator": "is", "value": "yes" }
+            ],
+            "actions": [
+                { "type": "flag", "keyword": "$flagged" },
+                { "type": "move", "mailboxId": mailbox_id }
+            ],
+            "stop": true
+        },
+        {
+            "name": "Disabled",
+            "isEnabled": false,
+            "match": "all",
+            "conditions": [
+                { "field": "size", "operator": "over", "value": "1K" }
+            ],
+            "actions": [
+                { "type": "discard" }
+            ],
+            "stop": false
+        },
+        {
+            "name": "Forward Bill",
+            "isEnabled": true,
+            "match": "all",
+            "conditions": [
+                { "field": "from", "operator": "is", "value": "bill@remote.org" }
+            ],
+            "actions": [
+                { "type": "forward", "email": "jdoe@remote.org", "keepCopy": true }
+            ],
+            "stop": false
+        }
+    ]);
+    let response = request(
+        &account_id,
+        &r#"[[ "MailRules/set", {
+            "accountId": "$$",
+            "create": { "rules": { "rules": $rules } }
+          }, "0" ],
+          [ "MailRules/get", {
+            "accountId": "$$"
+          }, "1" ]]"#
+            .replace("$rules", &rules.to_string()),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["created"]["rules"]["id"], "singleton",
+        "{response:#?}"
+    );
+    let result = &response["methodResponses"][1][1]["list"][0];
+    assert_eq!(result["isEnabled"], true, "{response:#?}");
+    assert_eq!(result["rules"], rules, "{response:#?}");
+
+    // The managed script can only be modified through MailRules/set
+    let response = request(
+        &account_id,
+        r#"[[ "SieveScript/query", {
+            "accountId": "$$",
+            "filter": { "name": "mailrules" }
+          }, "0" ]]"#,
+    )
+    .await;
+    let script_id = response["methodResponses"][0][1]["ids"][0]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let response = request(
+        &account_id,
+        &r#"[[ "SieveScript/set", {
+            "accountId": "$$",
+            "update": { "$s": { "name": "renamed" } },
+            "create": { "new": { "name": "mailrules" } }
+          }, "0" ]]"#
+            .replace("$s", &script_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notUpdated"][&script_id]["type"], "forbidden",
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][0][1]["notCreated"]["new"]["type"], "forbidden",
+        "{response:#?}"
+    );
+
+    // Matching messages are flagged and moved
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "lumbergh@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: lumbergh@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: TPS Report\r\n",
+            "\r\n",
+            "Did you get the memo about the new cover sheets?",
+        ),
+    )
+    .await;
+    let response = mailbox_emails(&account_id, &mailbox_id).await;
+    let emails = &response["methodResponses"][1][1]["list"];
+    assert_eq!(
+        emails.as_array().map(|list| list.len()),
+        Some(1),
+        "{response:#?}"
+    );
+    assert_eq!(emails[0]["keywords"]["$flagged"], true, "{response:#?}");
+
+    // Disabled rules are not applied
+    let response = request(
+        &account_id,
+        r#"[[ "MailRules/set", {
+            "accountId": "$$",
+            "update": { "singleton": { "isEnabled": false } }
+          }, "0" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"],
+        json!({ "singleton": null }),
+        "{response:#?}"
+    );
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: TPS Report\r\n",
+            "\r\n",
+            "Yeah, I'm also going to need you to come in on Sunday.",
+        ),
+    )
+    .await;
+    expect_nothing(&mut smtp_rx).await;
+    let response = inbox_emails(&account_id).await;
+    assert_eq!(
+        response["methodResponses"][1][1]["ids"]
+            .as_array()
+            .map(|ids| ids.len()),
+        Some(1),
+        "{response:#?}"
+    );
+
+    // Forwarded messages keep a copy in the Inbox
+    let response = request(
+        &account_id,
+        r#"[[ "MailRules/set", {
+            "accountId": "$$",
+            "update": { "singleton": { "isEnabled": true } }
+          }, "0" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"],
+        json!({ "singleton": null }),
+        "{response:#?}"
+    );
+    smtp_settings.lock().do_stop = true;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Saturday\r\n",
+            "\r\n",
+            "I'm going to need you to go ahead and come in on Saturday.",
+        ),
+    )
+    .await;
+    lmtp.quit().await;
+    let message = expect_message_delivery(&mut smtp_rx).await;
+    assert_eq!(message.rcpt_to, ["<jdoe@remote.org>"]);
+    assert!(
+        message.message.contains("Subject: Saturday"),
+        "{}",
+        message.message
+    );
+    let response = inbox_emails(&account_id).await;
+    assert_eq!(
+        response["methodResponses"][1][1]["ids"]
+            .as_array()
+            .map(|ids| ids.len()),
+        Some(2),
+        "{response:#?}"
+    );
+
+    // Remove test data
+    let response = request(
+        &account_id,
+        r#"[[ "MailRules/set", {
+            "accountId": "$$",
+            "destroy": ["singleton"]
+          }, "0" ],
+          [ "MailRules/get", {
+            "accountId": "$$"
+          }, "1" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["destroyed"],
+        json!(["singleton"]),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["notFound"],
+        json!(["singleton"]),
+        "{response:#?}"
+    );
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn inbox_emails(account_id: &Id) -> Value {
+    let response = request(
+        account_id,
+        r#"[[ "Mailbox/query", {
+            "accountId": "$$",
+            "filter": { "role": "inbox" }
+          }, "0" ]]"#,
+    )
+    .await;
+    let inbox_id = response["methodResponses"][0][1]["ids"][0]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+
+    request(
+        account_id,
+        &r#"[[ "Mailbox/get", {
+            "accountId": "$$",
+            "ids": ["$m"],
+            "properties": ["name"]
+          }, "0" ],
+          [ "Email/query", {
+            "accountId": "$$",
+            "filter": { "inMailbox": "$m" }
+          }, "1" ]]"#
+            .replace("$m", &inbox_id),
+    )
+    .await
+}
+
+async fn mailbox_emails(account_id: &Id, mailbox_id: &str) -> Value {
+    request(
+        account_id,
+        &r##"[[ "Email/query", {
+            "accountId": "$$",
+            "filter": { "inMailbox": "$m" }
+          }, "0" ],
+          [ "Email/get", {
+            "accountId": "$$",
+            "#ids": {
+              "resultOf": "0",
+              "name": "Email/query",
+              "path": "/ids"
+            },
+            "properties": ["keywords"]
+          }, "1" ]]"##
+            .replace("$m", mailbox_id),
+    )
+    .await
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
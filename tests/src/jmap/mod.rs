@@ -59,27 +59,44 @@ pub mod auth_oauth;
 pub mod blob;
 pub mod crypto;
 pub mod delivery;
+pub mod domain_admin;
 pub mod email_changes;
 pub mod email_copy;
 pub mod email_get;
+pub mod email_import_archive;
 pub mod email_parse;
 pub mod email_query;
 pub mod email_query_changes;
 pub mod email_search_snippet;
+pub mod email_template;
 pub mod email_set;
+pub mod email_snooze;
 pub mod email_submission;
 pub mod enterprise;
 pub mod event_source;
+pub mod fsck;
+pub mod groups;
+pub mod lifecycle;
+pub mod mail_rules;
 pub mod mailbox;
+pub mod password_policy;
 pub mod permissions;
 pub mod purge;
 pub mod push_subscription;
 pub mod quota;
+pub mod quota_recalculation;
+pub mod saved_search;
+pub mod scim;
+pub mod self_service;
+pub mod share_notification;
 pub mod sieve_script;
+pub mod smime;
 pub mod stress_test;
 pub mod thread_get;
 pub mod thread_merge;
+pub mod thread_mute;
 pub mod vacation_response;
+pub mod webauthn;
 pub mod webhooks;
 pub mod websocket;
 
@@ -397,6 +414,24 @@ pub async fn jmap_tests() {
     blob::test(&mut params).await;*/
     permissions::test(&params).await;
     purge::test(&mut params).await;
+    fsck::test(&mut params).await;
+    quota_recalculation::test(&mut params).await;
+    scim::test(&mut params).await;
+    password_policy::test(&mut params).await;
+    webauthn::test(&mut params).await;
+    self_service::test(&mut params).await;
+    groups::test(&mut params).await;
+    domain_admin::test(&mut params).await;
+    lifecycle::test(&mut params).await;
+    smime::test(&mut params).await;
+    email_snooze::test(&mut params).await;
+    share_notification::test(&mut params).await;
+    email_import_archive::test(&mut params).await;
+    mail_rules::test(&mut params).await;
+    email_template::test(&mut params).await;
+    saved_search::test(&mut params).await;
+    thread_mute::test(&mut params).await;
+    blob::test_convert(&mut params).await;
     enterprise::test(&mut params).await;
 
     if delete {
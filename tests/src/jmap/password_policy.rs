@@ -0,0 +1,184 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{
+    auth::{
+        password::{AccountLockout, PasswordPolicy},
+        AuthRequest,
+    },
+    core::BuildServer,
+    Server,
+};
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField, PrincipalUpdate, PrincipalValue},
+    Principal, QueryBy, Type,
+};
+
+use crate::jmap::assert_is_empty;
+
+use super::{JMAPTest, ManagementApi, Response};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running password policy tests...");
+
+    // Enable password rules and account lockout
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.password_policy = PasswordPolicy {
+        min_length: 8,
+        require_uppercase: true,
+        require_digit: true,
+        history: 2,
+        ..Default::default()
+    };
+    core.jmap.account_lockout = AccountLockout {
+        max_attempts: 3,
+        window: Duration::from_secs(3600),
+        duration: Duration::from_secs(3600),
+    }
+    .into();
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    // Weak passwords are rejected, including those resembling a hash
+    for password in [
+        "Short1",
+        "password123",
+        "PASSWORDabc",
+        "$weak",
+        "_weak",
+        "{SHA}",
+        "{weak}",
+    ] {
+        assert_policy_error(
+            api.post::<u32>("/api/principal", &new_user(password))
+                .await
+                .unwrap(),
+        );
+    }
+    let account_id = api
+        .post::<u32>("/api/principal", &new_user("Password123"))
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_auth(&server, "Password123").await.unwrap();
+
+    // Recently used passwords cannot be reused
+    assert_policy_error(set_password(&api, "Password123").await);
+    set_password(&api, "Password456").await.unwrap_data();
+    assert_policy_error(set_password(&api, "Password123").await);
+    set_password(&api, "Password789").await.unwrap_data();
+    assert_policy_error(set_password(&api, "Password123").await);
+    set_password(&api, "Password000").await.unwrap_data();
+    set_password(&api, "Password123").await.unwrap_data();
+
+    // Repeated failures lock the account, even for valid credentials
+    for attempt in 1..=3 {
+        let err = assert_auth(&server, "wrong-pass").await.unwrap_err();
+        if attempt < 3 {
+            assert!(err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)));
+        } else {
+            assert!(err.matches(trc::EventType::Auth(trc::AuthEvent::AccountLocked)));
+        }
+    }
+    assert!(assert_auth(&server, "Password123")
+        .await
+        .unwrap_err()
+        .matches(trc::EventType::Auth(trc::AuthEvent::AccountLocked)));
+    match ManagementApi::new(8899, "policy_user", "Password123")
+        .get::<serde_json::Value>("/api/account/auth")
+        .await
+        .unwrap()
+    {
+        Response::RequestError(err) => {
+            assert_eq!(err.status, 401);
+            assert_eq!(err.title.as_deref(), Some("Account Locked"));
+        }
+        _ => panic!("Expected request error"),
+    }
+
+    // Administrators can unlock accounts
+    api.delete::<()>("/api/principal/policy_user/lockout")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_auth(&server, "Password123").await.unwrap();
+
+    // Expired passwords are rejected until they are changed
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.password_policy.max_age = Some(Duration::from_secs(2));
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(assert_auth(&server, "Password123")
+        .await
+        .unwrap_err()
+        .matches(trc::EventType::Auth(trc::AuthEvent::PasswordExpired)));
+    set_password(&api, "Password321").await.unwrap_data();
+    assert_auth(&server, "Password321").await.unwrap();
+
+    // Restore settings and remove test account
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.password_policy = PasswordPolicy::default();
+    core.jmap.account_lockout = None;
+    params.server.inner.shared_core.store(core.into());
+    server
+        .core
+        .storage
+        .data
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(params.server.clone()).await;
+}
+
+fn new_user(password: &str) -> Principal {
+    Principal::new(u32::MAX, Type::Individual)
+        .with_field(PrincipalField::Name, "policy_user")
+        .with_field(PrincipalField::Secrets, password)
+        .with_field(PrincipalField::Roles, vec!["user".to_string()])
+}
+
+async fn set_password(api: &ManagementApi, password: &str) -> Response<()> {
+    api.patch::<()>(
+        "/api/principal/policy_user",
+        &vec![
+            PrincipalUpdate::remove_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(String::new()),
+            ),
+            PrincipalUpdate::add_item(
+                PrincipalField::Secrets,
+                PrincipalValue::String(password.to_string()),
+            ),
+        ],
+    )
+    .await
+    .unwrap()
+}
+
+async fn assert_auth(server: &Server, password: &str) -> trc::Result<()> {
+    server
+        .authenticate(&AuthRequest::from_plain(
+            "policy_user",
+            password,
+            0,
+            "127.0.0.1".parse().unwrap(),
+        ))
+        .await
+        .map(|_| ())
+}
+
+fn assert_policy_error<T>(response: Response<T>) {
+    match response {
+        Response::Error { details, .. } => {
+            assert_eq!(details.as_deref(), Some("Password policy violation"));
+        }
+        _ => panic!("Expected policy error"),
+    }
+}
@@ -7,7 +7,7 @@
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     time::Duration,
 };
@@ -15,7 +15,13 @@ use std::{
 use base64::{engine::general_purpose, Engine};
 use common::{config::server::Listeners, listener::SessionData, Core, Data, Inner};
 use ece::EcKeyComponents;
-use hyper::{body, header::CONTENT_ENCODING, server::conn::http1, service::service_fn, StatusCode};
+use hyper::{
+    body,
+    header::{AUTHORIZATION, CONTENT_ENCODING},
+    server::conn::http1,
+    service::service_fn,
+    StatusCode,
+};
 use hyper_util::rt::TokioIo;
 use jmap::{
     api::{
@@ -34,7 +40,10 @@ use utils::config::Config;
 use crate::{
     add_test_certs,
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, jmap_json_request,
+        mailbox::destroy_all_mailboxes, test_account_login,
+    },
     AssertConfig,
 };
 
@@ -94,6 +103,7 @@ pub async fn test(params: &mut JMAPTest) {
         auth_secret: auth_secret.to_vec(),
         tx: event_tx,
         fail_requests: false.into(),
+        last_headers: Default::default(),
     });
 
     // Start mock push server
@@ -140,6 +150,13 @@ pub async fn test(params: &mut JMAPTest) {
         .await
         .unwrap();
 
+    // Email summaries require encryption
+    assert_eq!(
+        set_email_summaries(&push_id).await["methodResponses"][0][1]["notUpdated"]
+            [push_id.as_str()]["type"],
+        "invalidProperties"
+    );
+
     // Create a mailbox and expect a state change
     let mailbox_id = client
         .mailbox_create("PushSubscription Test", None::<String>, Role::None)
@@ -221,6 +238,50 @@ pub async fn test(params: &mut JMAPTest) {
     assert_state(&mut event_rx, &account_id, &[DataType::Mailbox]).await;
     expect_nothing(&mut event_rx).await;
 
+    // State changes are sent with normal urgency and collapse under the same topic
+    let headers = push_server.last_headers.lock().unwrap().take().unwrap();
+    assert_eq!(headers.urgency, "normal");
+    assert!(headers.topic.is_some_and(|topic| !topic.is_empty()));
+    assert_eq!(headers.ttl, "86400");
+
+    // New messages include a summary when requested
+    assert!(
+        set_email_summaries(&push_id).await["methodResponses"][0][1]["updated"]
+            .as_object()
+            .unwrap()
+            .contains_key(push_id.as_str())
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    SmtpConnection::connect()
+        .await
+        .ingest(
+            "bill@example.com",
+            &["jdoe@example.com"],
+            concat!(
+                "From: Bill <bill@example.com>\r\n",
+                "To: jdoe@example.com\r\n",
+                "Subject: Lunch today?\r\n",
+                "\r\n",
+                "Are you free at noon?\r\n"
+            ),
+        )
+        .await;
+    let state_change = expect_push(&mut event_rx).await.unwrap_state_change();
+    assert!(state_change
+        .changed
+        .get(&account_id)
+        .unwrap()
+        .contains_key(&DataType::EmailDelivery));
+    let summary = state_change.email_summaries.first().unwrap();
+    assert_eq!(summary.account_id, account_id);
+    assert_eq!(summary.subject.as_deref(), Some("Lunch today?"));
+    assert_eq!(summary.from[0].name.as_deref(), Some("Bill"));
+    assert_eq!(summary.from[0].email, "bill@example.com");
+    assert_eq!(summary.preview, "Are you free at noon?");
+    let headers = push_server.last_headers.lock().unwrap().take().unwrap();
+    assert_eq!(headers.urgency, "high");
+    assert_eq!(headers.topic, None);
+
     // Destroy mailbox
     client.push_subscription_destroy(&push_id).await.unwrap();
     client.mailbox_destroy(&mailbox_id, true).await.unwrap();
@@ -245,6 +306,14 @@ pub struct PushServer {
     auth_secret: Vec<u8>,
     tx: mpsc::Sender<PushMessage>,
     fail_requests: AtomicBool,
+    last_headers: Mutex<Option<PushHeaders>>,
+}
+
+#[derive(Debug)]
+struct PushHeaders {
+    urgency: String,
+    topic: Option<String>,
+    ttl: String,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -309,6 +378,25 @@ impl common::listener::SessionManager for SessionManager {
                                 .into_http_response()
                                 .build());
                             }
+                            // Requests must be signed with the server's VAPID key
+                            assert_vapid(
+                                req.headers()
+                                    .get(AUTHORIZATION)
+                                    .expect("Missing VAPID authorization")
+                                    .to_str()
+                                    .unwrap(),
+                            );
+                            let header = |name: &str| {
+                                req.headers()
+                                    .get(name)
+                                    .map(|value| value.to_str().unwrap().to_string())
+                            };
+                            *push.last_headers.lock().unwrap() = Some(PushHeaders {
+                                urgency: header("Urgency").unwrap(),
+                                topic: header("Topic"),
+                                ttl: header("TTL").unwrap(),
+                            });
+
                             let is_encrypted = req
                                 .headers()
                                 .get(CONTENT_ENCODING)
@@ -386,6 +474,44 @@ async fn assert_state(event_rx: &mut mpsc::Receiver<PushMessage>, id: &Id, state
     );
 }
 
+async fn set_email_summaries(push_id: &str) -> serde_json::Value {
+    jmap_json_request(
+        r#"[[ "PushSubscription/set", {
+            "update": {
+                "$$": { "emailSummaries": true }
+            }
+          }, "0" ]]"#
+            .replace("$$", push_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
+
+fn assert_vapid(authorization: &str) {
+    let (token, key) = authorization
+        .strip_prefix("vapid t=")
+        .and_then(|value| value.split_once(", k="))
+        .unwrap();
+    let (payload, signature) = token.rsplit_once('.').unwrap();
+    ring::signature::UnparsedPublicKey::new(
+        &ring::signature::ECDSA_P256_SHA256_FIXED,
+        general_purpose::URL_SAFE_NO_PAD.decode(key).unwrap(),
+    )
+    .verify(
+        payload.as_bytes(),
+        &general_purpose::URL_SAFE_NO_PAD.decode(signature).unwrap(),
+    )
+    .unwrap();
+    let claims: serde_json::Value = serde_json::from_slice(
+        &general_purpose::URL_SAFE_NO_PAD
+            .decode(payload.split_once('.').unwrap().1)
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(claims["aud"], "https://127.0.0.1:9000");
+}
+
 #[test]
 fn ece_roundtrip() {
     for len in [1, 2, 5, 16, 256, 1024, 2048, 4096, 1024 * 1024] {
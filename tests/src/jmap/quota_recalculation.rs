@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField, PrincipalValue},
+    Principal, QueryBy, Type,
+};
+use jmap::{
+    mailbox::INBOX_ID,
+    services::quota::{QuotaMismatchType, QuotaRecalculation},
+    JmapMethods,
+};
+use jmap_proto::types::id::Id;
+use store::write::{BatchBuilder, DirectoryClass};
+
+use crate::{directory::internal::TestInternalDirectory, jmap::assert_is_empty};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running quota recalculation tests...");
+    let server = params.server.clone();
+    let client = &mut params.client;
+    let inbox_id = Id::from(INBOX_ID).to_string();
+
+    // Create test account and messages
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_test_user(
+            "jdoe@example.com",
+            "12345",
+            "John Doe",
+            &["jdoe@example.com"],
+        )
+        .await;
+    client.set_default_account_id(Id::from(account_id));
+    for num in 0..2 {
+        client
+            .email_import(
+                format!(
+                    concat!(
+                        "From: bill@example.com\r\n",
+                        "To: jdoe@example.com\r\n",
+                        "Subject: TPS Report #{}\r\n",
+                        "\r\n",
+                        "I'm going to need those TPS reports ASAP."
+                    ),
+                    num
+                )
+                .into_bytes(),
+                [&inbox_id],
+                None::<Vec<&str>>,
+                None,
+            )
+            .await
+            .unwrap();
+    }
+    let used_quota = server.get_used_quota(account_id).await.unwrap();
+    assert!(used_quota > 0);
+
+    // Create a tenant with a member
+    let store = server.core.storage.data.clone();
+    let tenant_id = store
+        .create_principal(
+            Principal::new(u32::MAX, Type::Tenant).with_field(PrincipalField::Name, "quota-tenant"),
+            None,
+        )
+        .await
+        .unwrap();
+    store
+        .create_principal(
+            Principal::new(u32::MAX, Type::Domain)
+                .with_field(PrincipalField::Name, "quota.org")
+                .with_field(
+                    PrincipalField::Tenant,
+                    PrincipalValue::String("quota-tenant".to_string()),
+                ),
+            None,
+        )
+        .await
+        .unwrap();
+    let member_id = store
+        .create_principal(
+            Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "jane@quota.org")
+                .with_field(
+                    PrincipalField::Emails,
+                    PrincipalValue::StringList(vec!["jane@quota.org".to_string()]),
+                )
+                .with_field(
+                    PrincipalField::Tenant,
+                    PrincipalValue::String("quota-tenant".to_string()),
+                ),
+            None,
+        )
+        .await
+        .unwrap();
+
+    // Consistent quotas should report no mismatches
+    let report = server.recalculate_quotas(None, None, false).await.unwrap();
+    assert!(report.accounts >= 2);
+    assert!(report.tenants >= 1);
+    assert_eq!(report.mismatches, vec![]);
+
+    // Corrupt the account, member and tenant counters
+    let mut batch = BatchBuilder::new();
+    batch
+        .add(DirectoryClass::UsedQuota(account_id), 1000)
+        .add(DirectoryClass::UsedQuota(member_id), 200)
+        .add(DirectoryClass::UsedQuota(tenant_id), 500);
+    store.write(batch.build()).await.unwrap();
+
+    // Recalculating a single account should not verify tenants
+    let report = server
+        .recalculate_quotas(Some(account_id), None, false)
+        .await
+        .unwrap();
+    assert_eq!(report.accounts, 1);
+    assert_eq!(report.tenants, 0);
+    assert_eq!(report.mismatches.len(), 1);
+    let mismatch = &report.mismatches[0];
+    assert_eq!(mismatch.id, account_id);
+    assert_eq!(mismatch.typ, QuotaMismatchType::Account);
+    assert_eq!(mismatch.stored, used_quota + 1000);
+    assert_eq!(mismatch.actual, used_quota);
+    assert!(!mismatch.repaired);
+
+    // Tenant scoped recalculations only include the tenant and its members
+    let report = server
+        .recalculate_quotas(None, Some(tenant_id), false)
+        .await
+        .unwrap();
+    assert_eq!(report.accounts, 1);
+    assert_eq!(report.tenants, 1);
+    let mut mismatches = report
+        .mismatches
+        .iter()
+        .map(|m| (m.id, m.typ, m.stored, m.actual))
+        .collect::<Vec<_>>();
+    mismatches.sort_unstable_by_key(|(id, ..)| *id);
+    let mut expected = vec![
+        (member_id, QuotaMismatchType::Account, 200, 0),
+        (tenant_id, QuotaMismatchType::Tenant, 500, 0),
+    ];
+    expected.sort_unstable_by_key(|(id, ..)| *id);
+    assert_eq!(mismatches, expected);
+
+    // Repair all quotas
+    let report = server.recalculate_quotas(None, None, true).await.unwrap();
+    assert_eq!(report.mismatches.len(), 3, "{:?}", report.mismatches);
+    assert!(report.mismatches.iter().all(|m| m.repaired));
+    let report = server.recalculate_quotas(None, None, false).await.unwrap();
+    assert_eq!(report.mismatches, vec![]);
+    assert_eq!(server.get_used_quota(account_id).await.unwrap(), used_quota);
+    assert_eq!(server.get_used_quota(member_id).await.unwrap(), 0);
+    assert_eq!(server.get_used_quota(tenant_id).await.unwrap(), 0);
+
+    // Delete principals
+    for name in ["jane@quota.org", "quota.org", "quota-tenant"] {
+        store.delete_principal(QueryBy::Name(name)).await.unwrap();
+    }
+    store
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
@@ -0,0 +1,228 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use imap_proto::ResponseType;
+use jmap::mailbox::INBOX_ID;
+use jmap_proto::types::id::Id;
+use serde_json::{json, Value};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    imap::{AssertResult, ImapConnection, Type},
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Saved Search tests...");
+
+    // Create test account
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Import test messages
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    for (subject, keywords) in [
+        ("TPS report", vec!["$flagged"]),
+        ("Cover sheets", vec!["$flagged", "$seen"]),
+        ("Saturday", vec![]),
+    ] {
+        params
+            .client
+            .email_import(
+                format!(
+                    concat!(
+                        "From: bill@example.com\r\n",
+                        "To: jdoe@example.com\r\n",
+                        "Subject: {}\r\n",
+                        "\r\n",
+                        "I'm going to need you to go ahead and come in on Saturday.",
+                    ),
+                    subject
+                )
+                .into_bytes(),
+                [&inbox_id],
+                keywords.into(),
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    // Invalid filters and duplicate names are rejected
+    let response = request(
+        &account_id,
+        r#"[[ "SavedSearch/set", {
+            "accountId": "$$",
+            "create": {
+              "flagged": { "name": "Flagged", "filter": { "hasKeyword": "$flagged" } },
+              "duplicate": { "name": "Flagged", "filter": { "notKeyword": "$seen" } },
+              "invalid": { "name": "Invalid", "filter": { "noSuchCondition": true } },
+              "nested": { "name": "Saved/Nested", "filter": { "hasKeyword": "$seen" } }
+            }
+          }, "0" ]]"#,
+    )
+    .await;
+    let search_id = response["methodResponses"][0][1]["created"]["flagged"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    for (id, property) in [
+        ("duplicate", "name"),
+        ("invalid", "filter"),
+        ("nested", "name"),
+    ] {
+        assert_eq!(
+            response["methodResponses"][0][1]["notCreated"][id]["type"], "invalidProperties",
+            "{response:#?}"
+        );
+        assert_eq!(
+            response["methodResponses"][0][1]["notCreated"][id]["properties"][0], property,
+            "{response:#?}"
+        );
+    }
+
+    // Results are computed by the query engine
+    let response = request(
+        &account_id,
+        r#"[[ "SavedSearch/get", {
+            "accountId": "$$"
+          }, "0" ]]"#,
+    )
+    .await;
+    let result = &response["methodResponses"][0][1]["list"][0];
+    assert_eq!(result["name"], "Flagged", "{response:#?}");
+    assert_eq!(
+        result["filter"],
+        json!({ "hasKeyword": "$flagged" }),
+        "{response:#?}"
+    );
+    assert_eq!(result["totalEmails"], 2, "{response:#?}");
+    assert_eq!(result["unreadEmails"], 1, "{response:#?}");
+
+    // Saved searches are listed as read-only IMAP mailboxes
+    let mut imap = ImapConnection::connect(b"_x ").await;
+    imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+    imap.send("LOGIN \"jdoe@example.com\" \"12345\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("LIST \"\" \"*\" RETURN (STATUS (MESSAGES UNSEEN))")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\\NoSelect) \"/\" \"Saved Searches\"")
+        .assert_contains("\"Saved Searches/Flagged\" (MESSAGES 2 UNSEEN 1)");
+    imap.send("SELECT \"Saved Searches/Flagged\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("[READ-ONLY]")
+        .assert_contains("* 2 EXISTS");
+    imap.send("FETCH 1:* (FLAGS)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_count("\\Flagged", 2);
+    // Sequence numbers follow the document ids, which depend on previous tests
+    imap.send("SEARCH UNSEEN").await;
+    let response = imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    assert!(
+        response
+            .iter()
+            .any(|line| matches!(line.as_str(), "* SEARCH 1" | "* SEARCH 2")),
+        "{response:?}"
+    );
+    imap.send("STORE 1 +FLAGS (\\Seen)").await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("CANNOT");
+    imap.send("MOVE 1 INBOX").await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("CANNOT");
+    imap.send("DELETE \"Saved Searches/Flagged\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("CANNOT");
+    imap.send("CREATE \"Saved Searches/Other\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_response_code("CANNOT");
+
+    // Changing the filter is reflected in the virtual mailbox
+    let response = request(
+        &account_id,
+        &r#"[[ "SavedSearch/set", {
+            "accountId": "$$",
+            "update": { "$s": { "filter": { "notKeyword": "$flagged" } } }
+          }, "0" ]]"#
+            .replace("$s", &search_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"],
+        json!({ &search_id: null }),
+        "{response:#?}"
+    );
+    imap.send("UNSELECT").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("STATUS \"Saved Searches/Flagged\" (MESSAGES UNSEEN)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("(MESSAGES 1 UNSEEN 1)");
+
+    // Remove test data
+    let response = request(
+        &account_id,
+        &r#"[[ "SavedSearch/set", {
+            "accountId": "$$",
+            "destroy": ["$s"]
+          }, "0" ],
+          [ "SavedSearch/get", {
+            "accountId": "$$",
+            "ids": ["$s"]
+          }, "1" ]]"#
+            .replace("$s", &search_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["destroyed"],
+        json!([&search_id]),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["notFound"],
+        json!([&search_id]),
+        "{response:#?}"
+    );
+    imap.send("LIST \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_count("Saved Searches", 0);
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
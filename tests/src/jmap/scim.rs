@@ -0,0 +1,379 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use directory::{
+    backend::internal::{lookup::DirectoryStore, manage::ManageDirectory, PrincipalField},
+    Permission, Principal, QueryBy, Type,
+};
+use reqwest::{header::AUTHORIZATION, Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::jmap::assert_is_empty;
+
+use super::JMAPTest;
+
+pub struct ScimClient {
+    authorization: String,
+}
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running SCIM tests...");
+    let server = params.server.clone();
+    let store = server.core.storage.data.clone();
+
+    // Create an API key allowed to manage users and groups
+    store
+        .create_principal(
+            Principal::new(u32::MAX, Type::ApiKey)
+                .with_field(PrincipalField::Name, "scim-key")
+                .with_field(PrincipalField::Secrets, "scim-secret")
+                .with_field(
+                    PrincipalField::EnabledPermissions,
+                    [
+                        Permission::Authenticate,
+                        Permission::IndividualList,
+                        Permission::IndividualGet,
+                        Permission::IndividualCreate,
+                        Permission::IndividualUpdate,
+                        Permission::IndividualDelete,
+                        Permission::GroupList,
+                        Permission::GroupGet,
+                        Permission::GroupCreate,
+                        Permission::GroupUpdate,
+                        Permission::GroupDelete,
+                    ]
+                    .iter()
+                    .map(|p| p.name().to_string())
+                    .collect::<Vec<_>>(),
+                ),
+            None,
+        )
+        .await
+        .unwrap();
+    let scim = ScimClient::new("scim-key", "scim-secret");
+
+    // Requests without an API key are rejected
+    let (status, _) = ScimClient::new("scim-key", "wrong-secret")
+        .request(Method::GET, "/Users", None)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Discovery endpoints
+    let (status, config) = scim
+        .request(Method::GET, "/ServiceProviderConfig", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(config["patch"]["supported"], true);
+    let (_, types) = scim.request(Method::GET, "/ResourceTypes", None).await;
+    assert_eq!(types["totalResults"], 2);
+
+    // Create users
+    let (status, jane) = scim
+        .request(
+            Method::POST,
+            "/Users",
+            json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": "Jane@Example.com",
+                "name": {"givenName": "Jane", "familyName": "Doe"},
+                "emails": [
+                    {"value": "jane.doe@example.com", "type": "work"},
+                    {"value": "jane@example.com", "type": "work", "primary": true}
+                ],
+                "password": "secret-pass",
+                "active": true,
+                "urn:ietf:params:scim:schemas:extension:stalwart:2.0:User": {
+                    "quota": 1024
+                }
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{jane}");
+    let jane_id = jane["id"].as_str().unwrap().to_string();
+    assert_eq!(jane["userName"], "jane@example.com");
+    assert_eq!(jane["displayName"], "Jane Doe");
+    assert_eq!(jane["emails"][0]["value"], "jane@example.com");
+    assert_eq!(jane["emails"][0]["primary"], true);
+    assert_eq!(jane["emails"][1]["value"], "jane.doe@example.com");
+    assert_eq!(
+        jane["urn:ietf:params:scim:schemas:extension:stalwart:2.0:User"]["quota"],
+        1024
+    );
+    assert!(jane.get("password").is_none());
+
+    let (status, bill) = scim
+        .request(
+            Method::POST,
+            "/Users",
+            json!({
+                "userName": "bill@example.com",
+                "displayName": "Bill Foobar",
+                "emails": [{"value": "bill@example.com", "primary": "True"}],
+                "active": "False"
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{bill}");
+    let bill_id = bill["id"].as_str().unwrap().to_string();
+    assert_eq!(bill["active"], false);
+
+    // Duplicate users are rejected
+    let (status, error) = scim
+        .request(
+            Method::POST,
+            "/Users",
+            json!({"userName": "jane@example.com"}).into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["scimType"], "uniqueness");
+
+    // Filtering and pagination
+    let (_, list) = scim
+        .request(
+            Method::GET,
+            "/Users?filter=userName%20eq%20%22JANE@example.com%22",
+            None,
+        )
+        .await;
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], jane_id.as_str());
+    let (_, list) = scim
+        .request(
+            Method::GET,
+            "/Users?filter=emails%20co%20%22example.com%22%20and%20active%20eq%20false",
+            None,
+        )
+        .await;
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], bill_id.as_str());
+    let (_, list) = scim
+        .request(
+            Method::GET,
+            "/Users?filter=userName%20sw%20%22%22&startIndex=2&count=1",
+            None,
+        )
+        .await;
+    assert!(list["totalResults"].as_u64().unwrap() >= 2);
+    assert_eq!(list["startIndex"], 2);
+    assert_eq!(list["itemsPerPage"], 1);
+    let (status, error) = scim
+        .request(Method::GET, "/Users?filter=userName%20xx%20%22a%22", None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["scimType"], "invalidFilter");
+
+    // Disabled accounts cannot authenticate
+    assert!(!server
+        .get_access_token(bill_id.parse::<u32>().unwrap())
+        .await
+        .unwrap()
+        .has_permission(Permission::Authenticate));
+
+    // Patch users using the Entra ID and Okta flavors
+    let (status, jane) = scim
+        .request(
+            Method::PATCH,
+            &format!("/Users/{jane_id}"),
+            json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [
+                    {"op": "Replace", "path": "displayName", "value": "Jane Smith"},
+                    {"op": "Add", "path": "emails[type eq \"home\"].value", "value": "jane.smith@example.com"},
+                    {"op": "Remove", "path": "emails[value eq \"jane.doe@example.com\"]"},
+                    {"op": "replace", "value": {"active": "False"}},
+                    {"op": "replace", "path": "urn:ietf:params:scim:schemas:extension:stalwart:2.0:User:quota", "value": 2048}
+                ]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{jane}");
+    assert_eq!(jane["displayName"], "Jane Smith");
+    assert_eq!(jane["active"], false);
+    assert_eq!(
+        jane["emails"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["value"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["jane@example.com", "jane.smith@example.com"]
+    );
+    assert_eq!(
+        jane["urn:ietf:params:scim:schemas:extension:stalwart:2.0:User"]["quota"],
+        2048
+    );
+    let principal = store
+        .query(QueryBy::Id(jane_id.parse().unwrap()), false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(principal.quota(), 2048);
+    assert_eq!(principal.description(), Some("Jane Smith"));
+
+    // Email addresses must belong to a local domain
+    let (status, error) = scim
+        .request(
+            Method::PATCH,
+            &format!("/Users/{jane_id}"),
+            json!({
+                "Operations": [{"op": "add", "path": "emails", "value": [{"value": "jane@home.org"}]}]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["scimType"], "invalidValue");
+
+    // Reactivate user with a PUT request
+    let mut jane = jane;
+    jane["active"] = Value::Bool(true);
+    let (status, jane) = scim
+        .request(Method::PUT, &format!("/Users/{jane_id}"), jane.into())
+        .await;
+    assert_eq!(status, StatusCode::OK, "{jane}");
+    assert_eq!(jane["active"], true);
+
+    // Create group with members
+    let (status, group) = scim
+        .request(
+            Method::POST,
+            "/Groups",
+            json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+                "displayName": "Sales",
+                "members": [{"value": jane_id}]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{group}");
+    let group_id = group["id"].as_str().unwrap().to_string();
+    assert_eq!(group["displayName"], "Sales");
+    assert_eq!(group["members"][0]["value"], jane_id.as_str());
+
+    // Group membership is reported on users
+    let (_, jane) = scim
+        .request(Method::GET, &format!("/Users/{jane_id}"), None)
+        .await;
+    assert_eq!(jane["groups"][0]["value"], group_id.as_str());
+    assert_eq!(jane["groups"][0]["display"], "Sales");
+
+    // Add and remove members
+    let (status, group) = scim
+        .request(
+            Method::PATCH,
+            &format!("/Groups/{group_id}"),
+            json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [
+                    {"op": "add", "path": "members", "value": [{"value": bill_id}]},
+                    {"op": "remove", "path": format!("members[value eq \"{jane_id}\"]")}
+                ]
+            })
+            .into(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{group}");
+    assert_eq!(group["members"].as_array().unwrap().len(), 1);
+    assert_eq!(group["members"][0]["value"], bill_id.as_str());
+    let (_, list) = scim
+        .request(
+            Method::GET,
+            "/Groups?filter=displayName%20eq%20%22sales%22",
+            None,
+        )
+        .await;
+    assert_eq!(list["totalResults"], 1);
+
+    // Unknown resources
+    let (status, error) = scim.request(Method::GET, "/Users/999999", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        error["schemas"][0],
+        "urn:ietf:params:scim:api:messages:2.0:Error"
+    );
+    let (status, _) = scim
+        .request(Method::GET, &format!("/Users/{group_id}"), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Delete resources
+    for path in [
+        format!("/Groups/{group_id}"),
+        format!("/Users/{jane_id}"),
+        format!("/Users/{bill_id}"),
+    ] {
+        let (status, _) = scim.request(Method::DELETE, &path, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = scim.request(Method::GET, &path, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    store
+        .delete_principal(QueryBy::Name("scim-key"))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
+
+impl ScimClient {
+    pub fn new(name: &str, secret: &str) -> Self {
+        Self {
+            authorization: format!(
+                "Bearer api_{}",
+                STANDARD.encode(format!("{name}:{secret}").as_bytes())
+            ),
+        }
+    }
+
+    pub fn new_basic(name: &str, secret: &str) -> Self {
+        Self {
+            authorization: format!(
+                "Basic {}",
+                STANDARD.encode(format!("{name}:{secret}").as_bytes())
+            ),
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = reqwest::Client::builder()
+            .timeout(Duration::from_millis(500))
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap()
+            .request(method, format!("https://127.0.0.1:8899/scim/v2{path}"))
+            .header(AUTHORIZATION, &self.authorization);
+        if let Some(body) = body {
+            request = request
+                .header("Content-Type", "application/scim+json")
+                .body(body.to_string());
+        }
+
+        let response = request.send().await.unwrap();
+        let status = response.status();
+        let bytes = response.bytes().await.unwrap();
+        (
+            status,
+            if !bytes.is_empty() {
+                serde_json::from_slice(&bytes)
+                    .unwrap_or_else(|err| panic!("{err}: {}", String::from_utf8_lossy(&bytes)))
+            } else {
+                Value::Null
+            },
+        )
+    }
+}
@@ -0,0 +1,355 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use common::{
+    auth::{
+        oauth::GrantType,
+        self_service::{AliasPolicy, AppPassword},
+        AuthRequest,
+    },
+    core::BuildServer,
+    Server,
+};
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore, manage::ManageDirectory, PrincipalField, SpecialSecrets,
+    },
+    Principal, QueryBy, Type,
+};
+use jmap::api::management::{
+    account::{AccountProfile, AccountProfileUpdate},
+    principal::AccountAuthRequest,
+};
+use jmap_proto::types::id::Id;
+use serde_json::{json, Value};
+use totp_rs::TOTP;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        delivery::SmtpConnection,
+        email_submission::{
+            assert_message_delivery, expect_nothing, spawn_mock_smtp_server, MockMessage,
+        },
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::{JMAPTest, ManagementApi, Response};
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running self-service tests...");
+
+    // Allow a single alias within the domain of the user
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.self_service.alias_policy = AliasPolicy::SameDomain;
+    core.jmap.self_service.max_aliases = 1;
+    params.server.inner.shared_core.store(core.into());
+    let server = params.server.inner.build_server();
+
+    server
+        .core
+        .storage
+        .data
+        .create_test_domains(&["ss_jane@example.com"])
+        .await;
+    let account_id = ManagementApi::new(8899, "admin", "secret")
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "ss_jane")
+                .with_field(PrincipalField::Secrets, "ss-secret")
+                .with_field(PrincipalField::Emails, "ss_jane@example.com")
+                .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    let api = ManagementApi::new(8899, "ss_jane", "ss-secret");
+
+    // Update display name and forwarding addresses
+    api.patch::<()>(
+        "/api/account/profile",
+        &AccountProfileUpdate {
+            description: Some("Jane Doe".to_string()),
+            forward_to: Some(vec!["Jane@Remote.org".to_string()]),
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert_error(
+        api.patch::<()>(
+            "/api/account/profile",
+            &json!({"forwardTo": ["ss_jane@example.com"]}),
+        )
+        .await
+        .unwrap(),
+        "Invalid forwarding address",
+    );
+    let profile = api
+        .get::<AccountProfile>("/api/account/profile")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(profile.description.as_deref(), Some("Jane Doe"));
+    assert_eq!(profile.emails, vec!["ss_jane@example.com".to_string()]);
+    assert_eq!(profile.forward_to, vec!["jane@remote.org".to_string()]);
+
+    // Incoming messages are forwarded, keeping a local copy
+    let (mut smtp_rx, smtp_settings) = spawn_mock_smtp_server();
+    server.core.smtp.resolvers.dns.ipv4_add(
+        "localhost",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + Duration::from_secs(10),
+    );
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["ss_jane@example.com"],
+        concat!(
+            "Delivered-To: ss_jane@example.com\r\n",
+            "From: bill@remote.org\r\n",
+            "To: ss_jane@example.com\r\n",
+            "Subject: Looping message\r\n",
+            "\r\n",
+            "This message was already forwarded."
+        ),
+    )
+    .await;
+    expect_nothing(&mut smtp_rx).await;
+    smtp_settings.lock().do_stop = true;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["ss_jane@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: ss_jane@example.com\r\n",
+            "Subject: TPS Report\r\n",
+            "\r\n",
+            "I'm going to need those TPS reports ASAP."
+        ),
+    )
+    .await;
+    lmtp.quit().await;
+    assert_message_delivery(
+        &mut smtp_rx,
+        MockMessage::new(
+            "<bill@remote.org>",
+            ["<jane@remote.org>"],
+            "@Delivered-To: ss_jane@example.com",
+        ),
+    )
+    .await;
+
+    // Aliases are subject to the domain policy
+    api.post::<()>(
+        "/api/account/aliases",
+        &json!({"address": "ss_jane.doe@example.com"}),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    for address in ["jane@remote.org", "ss_j.doe@example.com"] {
+        assert_error(
+            api.post::<()>("/api/account/aliases", &json!({"address": address}))
+                .await
+                .unwrap(),
+            "Alias policy violation",
+        );
+    }
+    assert_error(
+        api.delete::<()>("/api/account/aliases/ss_jane@example.com")
+            .await
+            .unwrap(),
+        "Cannot remove primary address",
+    );
+    assert_eq!(
+        api.get::<AccountProfile>("/api/account/profile")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .emails,
+        vec![
+            "ss_jane@example.com".to_string(),
+            "ss_jane.doe@example.com".to_string()
+        ]
+    );
+    api.delete::<()>("/api/account/aliases/ss_jane.doe@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+
+    // Create an app password and track its usage
+    assert_error(
+        api.post::<Value>("/api/account/app-passwords", &json!({"name": "my$app"}))
+            .await
+            .unwrap(),
+        "Invalid app password name",
+    );
+    let app_password = api
+        .post::<Value>("/api/account/app-passwords", &json!({"name": "phone"}))
+        .await
+        .unwrap()
+        .unwrap_data()["password"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        app_passwords(&api).await,
+        vec![AppPassword {
+            name: "phone".to_string(),
+            last_used: None
+        }]
+    );
+    assert!(authenticate(&server, &app_password).await);
+    assert!(app_passwords(&api).await[0].last_used.is_some());
+    api.delete::<()>("/api/account/app-passwords/phone")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(!authenticate(&server, &app_password).await);
+    assert_eq!(app_passwords(&api).await, vec![]);
+
+    // Revoking sessions invalidates issued tokens, including those issued in the same second
+    let token = server
+        .encode_access_token(GrantType::AccessToken, account_id, "webadmin", 3600)
+        .await
+        .unwrap();
+    assert!(server.validate_access_token(None, &token).await.is_ok());
+    api.delete::<()>("/api/account/sessions")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(server.validate_access_token(None, &token).await.is_err());
+
+    // Tokens issued after the revocation are accepted
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let token = server
+        .encode_access_token(GrantType::AccessToken, account_id, "webadmin", 3600)
+        .await
+        .unwrap();
+    assert!(server.validate_access_token(None, &token).await.is_ok());
+
+    // Enrol TOTP
+    let url = api
+        .post::<Value>("/api/account/totp/enroll", &json!({}))
+        .await
+        .unwrap()
+        .unwrap_data()["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let totp = TOTP::from_url(&url).unwrap();
+    assert!(authenticate(&server, "ss-secret").await);
+    let recovery_codes = api
+        .post::<Value>(
+            "/api/account/totp/confirm",
+            &json!({"code": totp.generate_current().unwrap()}),
+        )
+        .await
+        .unwrap()
+        .unwrap_data()["recoveryCodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(recovery_codes.len(), 10);
+    assert!(!authenticate(&server, "ss-secret").await);
+    assert!(
+        authenticate(
+            &server,
+            &format!("ss-secret${}", totp.generate_current().unwrap())
+        )
+        .await
+    );
+
+    // Recovery codes can only be used once
+    let recovery_code = format!("ss-secret${}", recovery_codes[0]);
+    assert!(authenticate(&server, &recovery_code).await);
+    assert!(!authenticate(&server, &recovery_code).await);
+    let api = ManagementApi::new(
+        8899,
+        "ss_jane",
+        &format!("ss-secret${}", totp.generate_current().unwrap()),
+    );
+    assert_eq!(
+        api.get::<Value>("/api/account/totp/recovery-codes")
+            .await
+            .unwrap()
+            .unwrap_data()["remaining"],
+        9
+    );
+
+    // Disabling TOTP removes the recovery codes
+    api.post::<()>(
+        "/api/account/auth",
+        &vec![AccountAuthRequest::DisableOtpAuth { url: None }],
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert!(!server
+        .core
+        .storage
+        .data
+        .query(QueryBy::Id(account_id), false)
+        .await
+        .unwrap()
+        .unwrap()
+        .iter_str(PrincipalField::Secrets)
+        .any(|secret| secret.is_otp_auth() || secret.is_recovery_code()));
+    assert!(authenticate(&server, "ss-secret").await);
+
+    // Remove test data
+    params
+        .client
+        .set_default_account_id(Id::from(account_id).to_string());
+    destroy_all_mailboxes(params).await;
+    server
+        .core
+        .storage
+        .data
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.self_service = Default::default();
+    params.server.inner.shared_core.store(core.into());
+    assert_is_empty(server).await;
+}
+
+async fn app_passwords(api: &ManagementApi) -> Vec<AppPassword> {
+    api.get::<Vec<AppPassword>>("/api/account/app-passwords")
+        .await
+        .unwrap()
+        .unwrap_data()
+}
+
+async fn authenticate(server: &Server, secret: &str) -> bool {
+    server
+        .authenticate(&AuthRequest::from_plain(
+            "ss_jane",
+            secret,
+            0,
+            "127.0.0.1".parse().unwrap(),
+        ))
+        .await
+        .is_ok()
+}
+
+fn assert_error<T>(response: Response<T>, expected: &str) {
+    match response {
+        Response::Error { details, .. } => {
+            assert_eq!(details.as_deref(), Some(expected));
+        }
+        _ => panic!("Expected error {expected:?}"),
+    }
+}
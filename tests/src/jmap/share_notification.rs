@@ -0,0 +1,280 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Share notification tests...");
+
+    // Create test accounts
+    let server = params.server.clone();
+    let sharer_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "sharer@example.com",
+                "12345",
+                "Sharer User",
+                &["sharer@example.com"],
+            )
+            .await,
+    );
+    let sharee_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "sharee@example.com",
+                "12345",
+                "Sharee User",
+                &["sharee@example.com"],
+            )
+            .await,
+    );
+
+    // No notifications before anything is shared
+    let response = request(
+        "sharee@example.com",
+        &sharee_id,
+        r#"[[ "ShareNotification/get", {
+            "accountId": "$$"
+          }, "0" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"],
+        serde_json::json!([]),
+        "{response:#?}"
+    );
+
+    // Create a mailbox and share it
+    let response = request(
+        "sharer@example.com",
+        &sharer_id,
+        r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "create": { "p": { "name": "Projects" } }
+          }, "0" ]]"#,
+    )
+    .await;
+    let mailbox_id = response["methodResponses"][0][1]["created"]["p"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let response = request(
+        "sharer@example.com",
+        &sharer_id,
+        &r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "update": { "$m": { "acl/sharee@example.com": ["read", "readItems"] } }
+          }, "0" ]]"#
+            .replace("$m", &mailbox_id),
+    )
+    .await;
+    assert_updated(&response, &mailbox_id);
+
+    // The sharee receives a notification
+    let response = request(
+        "sharee@example.com",
+        &sharee_id,
+        r#"[[ "ShareNotification/get", {
+            "accountId": "$$"
+          }, "0" ]]"#,
+    )
+    .await;
+    let list = response["methodResponses"][0][1]["list"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    assert_eq!(list.len(), 1, "{response:#?}");
+    let notification = &list[0];
+    let first_id = notification["id"].as_str().unwrap().to_string();
+    let state = response["methodResponses"][0][1]["state"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(notification["objectType"], "Mailbox", "{notification:#?}");
+    assert_eq!(
+        notification["objectAccountId"],
+        sharer_id.to_string(),
+        "{notification:#?}"
+    );
+    assert_eq!(notification["objectId"], mailbox_id, "{notification:#?}");
+    assert_eq!(notification["name"], "Projects", "{notification:#?}");
+    assert_eq!(
+        notification["changedBy"]["name"], "Sharer User",
+        "{notification:#?}"
+    );
+    assert_eq!(
+        notification["changedBy"]["email"], "sharer@example.com",
+        "{notification:#?}"
+    );
+    assert_eq!(notification["oldRights"], Value::Null, "{notification:#?}");
+    assert_eq!(
+        notification["newRights"]["mayReadItems"], true,
+        "{notification:#?}"
+    );
+    assert_eq!(
+        notification["newRights"]["mayDelete"], false,
+        "{notification:#?}"
+    );
+
+    // The sharer does not receive a notification
+    let response = request(
+        "sharer@example.com",
+        &sharer_id,
+        r#"[[ "ShareNotification/get", {
+            "accountId": "$$"
+          }, "0" ]]"#,
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"],
+        serde_json::json!([]),
+        "{response:#?}"
+    );
+
+    // Read access is enough to subscribe to the shared mailbox
+    let response = request(
+        "sharee@example.com",
+        &sharer_id,
+        &r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "update": { "$m": { "isSubscribed": true } }
+          }, "0" ],
+          [ "Mailbox/set", {
+            "accountId": "$$",
+            "update": { "$m": { "name": "Renamed" } }
+          }, "1" ]]"#
+            .replace("$m", &mailbox_id),
+    )
+    .await;
+    assert_updated(&response, &mailbox_id);
+    assert_eq!(
+        response["methodResponses"][1][1]["notUpdated"][&mailbox_id]["type"], "forbidden",
+        "{response:#?}"
+    );
+
+    // Revoking access creates a new notification
+    request(
+        "sharer@example.com",
+        &sharer_id,
+        &r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "update": { "$m": { "acl/sharee@example.com": null } }
+          }, "0" ]]"#
+            .replace("$m", &mailbox_id),
+    )
+    .await;
+    let response = request(
+        "sharee@example.com",
+        &sharee_id,
+        &r##"[[ "ShareNotification/changes", {
+            "accountId": "$$",
+            "sinceState": "$s"
+          }, "0" ],
+          [ "ShareNotification/query", {
+            "accountId": "$$",
+            "filter": { "objectType": "Mailbox" },
+            "sort": [{ "property": "created", "isAscending": true }]
+          }, "1" ],
+          [ "ShareNotification/get", {
+            "accountId": "$$",
+            "#ids": {
+              "resultOf": "0",
+              "name": "ShareNotification/changes",
+              "path": "/created"
+            },
+            "properties": ["oldRights", "newRights"]
+          }, "2" ]]"##
+            .replace("$s", &state),
+    )
+    .await;
+    let created = response["methodResponses"][0][1]["created"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    assert_eq!(created.len(), 1, "{response:#?}");
+    let second_id = created[0].as_str().unwrap().to_string();
+    // Both notifications may share the same creation second
+    let mut ids = response["methodResponses"][1][1]["ids"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .iter()
+        .filter_map(|id| id.as_str())
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    let mut expected_ids = vec![first_id.as_str(), second_id.as_str()];
+    expected_ids.sort_unstable();
+    assert_eq!(ids, expected_ids, "{response:#?}");
+    let notification = &response["methodResponses"][2][1]["list"][0];
+    assert_eq!(
+        notification["oldRights"]["mayReadItems"], true,
+        "{notification:#?}"
+    );
+    assert_eq!(notification["newRights"], Value::Null, "{notification:#?}");
+
+    // Notifications cannot be created by clients, only destroyed
+    let response = request(
+        "sharee@example.com",
+        &sharee_id,
+        &r#"[[ "ShareNotification/set", {
+            "accountId": "$$",
+            "create": { "n": { "objectType": "Mailbox" } },
+            "destroy": ["$a", "$b"]
+          }, "0" ],
+          [ "ShareNotification/get", {
+            "accountId": "$$"
+          }, "1" ]]"#
+            .replace("$a", &first_id)
+            .replace("$b", &second_id),
+    )
+    .await;
+    let set_response = &response["methodResponses"][0][1];
+    assert_eq!(
+        set_response["notCreated"]["n"]["type"], "forbidden",
+        "{response:#?}"
+    );
+    assert_eq!(
+        set_response["destroyed"],
+        serde_json::json!([first_id, second_id]),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["list"],
+        serde_json::json!([]),
+        "{response:#?}"
+    );
+
+    // Clean up
+    for account_id in [sharer_id, sharee_id] {
+        params.client.set_default_account_id(account_id);
+        destroy_all_mailboxes(params).await;
+    }
+    assert_is_empty(server).await;
+}
+
+async fn request(login: &str, account_id: &Id, body: &str) -> Value {
+    jmap_json_request(body.replace("$$", &account_id.to_string()), login, "12345").await
+}
+
+fn assert_updated(response: &Value, id: &str) {
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .is_some_and(|updated| updated.contains_key(id)),
+        "{response:#?}"
+    );
+}
@@ -0,0 +1,229 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use common::config::jmap::smime::SmimeVerifyConfig;
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+use store::ahash::AHashMap;
+use utils::config::Config;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running S/MIME verification tests...");
+
+    // Create test account
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Enable S/MIME verification
+    let mut config = Config::new(format!(
+        "[jmap.smime]\nenable = true\ntrust-store = '''{}'''\n",
+        resource("ca.pem")
+    ))
+    .unwrap();
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.smime_verify = SmimeVerifyConfig::parse(&mut config);
+    assert!(core.jmap.smime_verify.is_some());
+    params.server.inner.shared_core.store(core.into());
+
+    // Deliver signed, tampered, forged and unsigned messages
+    let detached = resource("detached.eml");
+    let mut lmtp = SmtpConnection::connect().await;
+    for message in [
+        detached.clone(),
+        resource("opaque.eml"),
+        resource("untrusted.eml"),
+        detached
+            .replace("Subject: Signed report", "Subject: Tampered report")
+            .replace("numbers are attached", "numbers are missing"),
+        detached
+            .replace("Subject: Signed report", "Subject: Forged report")
+            .replace("Bill <bill@example.com>", "Mallory <mallory@example.com>"),
+        detached
+            .replace("Subject: Signed report", "Subject: Anonymous report")
+            .replace("From: Bill <bill@example.com>\r\n", ""),
+        resource("legacy.eml"),
+        concat!(
+            "From: bill@example.com\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Plain report\r\n",
+            "\r\n",
+            "The quarterly numbers are attached.\r\n",
+        )
+        .to_string(),
+    ] {
+        lmtp.ingest("bill@example.com", &["jdoe@example.com"], &message)
+            .await;
+    }
+
+    // Verification results are stored at delivery
+    let emails = get_emails(&account_id).await;
+    assert_eq!(emails.len(), 8, "{emails:#?}");
+    for subject in ["Signed report", "Opaque report"] {
+        let email = &emails[subject];
+        assert_eq!(email["smimeStatus"], "signed/verified", "{email:#?}");
+        assert_eq!(email["smimeStatusAtDelivery"], "signed/verified");
+        assert_eq!(email["smimeErrors"], Value::Null);
+        assert!(email["smimeVerifiedAt"].is_string(), "{email:#?}");
+    }
+    for (subject, error) in [
+        ("Untrusted report", "is not issued by a trusted authority"),
+        ("Tampered report", "Message digest does not match"),
+        ("Forged report", "does not match the sender address"),
+        ("Anonymous report", "has no sender address"),
+        ("Legacy report", "SHA-1 signatures are not accepted"),
+    ] {
+        let email = &emails[subject];
+        assert_eq!(email["smimeStatus"], "signed/failed", "{email:#?}");
+        assert_eq!(email["smimeStatusAtDelivery"], "signed/failed");
+        assert!(
+            email["smimeErrors"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|err| err.as_str().unwrap().contains(error)),
+            "{email:#?}"
+        );
+    }
+    let email = &emails["Plain report"];
+    for property in [
+        "smimeStatus",
+        "smimeStatusAtDelivery",
+        "smimeErrors",
+        "smimeVerifiedAt",
+    ] {
+        assert_eq!(email[property], Value::Null, "{email:#?}");
+    }
+
+    // Filter by S/MIME status
+    assert_eq!(query(&account_id, r#"{"hasSmime": true}"#).await, 7);
+    assert_eq!(query(&account_id, r#"{"hasSmime": false}"#).await, 1);
+    assert_eq!(query(&account_id, r#"{"hasVerifiedSmime": true}"#).await, 2);
+    assert_eq!(
+        query(&account_id, r#"{"hasVerifiedSmime": false}"#).await,
+        6
+    );
+    assert_eq!(
+        query(&account_id, r#"{"hasVerifiedSmimeAtDelivery": true}"#).await,
+        2
+    );
+
+    // Expired results are verified again against the current trust store
+    // and stored with the message
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    let smime_config = core.jmap.smime_verify.clone().unwrap();
+    core.jmap.smime_verify = Some(SmimeVerifyConfig {
+        trust_store: Arc::new(vec![]),
+        cache_expiry: Duration::ZERO,
+        ..smime_config.clone()
+    });
+    params.server.inner.shared_core.store(core.into());
+    let emails = get_emails(&account_id).await;
+    for subject in ["Signed report", "Opaque report"] {
+        let email = &emails[subject];
+        assert_eq!(email["smimeStatus"], "signed/failed", "{email:#?}");
+        assert_eq!(email["smimeStatusAtDelivery"], "signed/verified");
+    }
+    assert_eq!(query(&account_id, r#"{"hasVerifiedSmime": true}"#).await, 0);
+    assert_eq!(
+        query(&account_id, r#"{"hasVerifiedSmimeAtDelivery": true}"#).await,
+        2
+    );
+
+    // Stored results are returned until they expire
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.smime_verify = Some(SmimeVerifyConfig {
+        cache_expiry: Duration::from_secs(86400),
+        ..smime_config
+    });
+    params.server.inner.shared_core.store(core.into());
+    let emails = get_emails(&account_id).await;
+    for subject in ["Signed report", "Opaque report"] {
+        let email = &emails[subject];
+        assert_eq!(email["smimeStatus"], "signed/failed", "{email:#?}");
+        assert_eq!(email["smimeStatusAtDelivery"], "signed/verified");
+    }
+
+    // Restore settings
+    let mut core = params.server.inner.shared_core.load_full().as_ref().clone();
+    core.jmap.smime_verify = None;
+    params.server.inner.shared_core.store(core.into());
+
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn get_emails(account_id: &Id) -> AHashMap<String, Value> {
+    let mut response = jmap_json_request(
+        r#"[[ "Email/get", {
+            "accountId": "$$",
+            "properties": ["subject", "smimeStatus", "smimeStatusAtDelivery",
+                           "smimeErrors", "smimeVerifiedAt"]
+          }, "0" ]]"#
+            .replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+
+    match response["methodResponses"][0][1]["list"].take() {
+        Value::Array(list) => list
+            .into_iter()
+            .map(|email| (email["subject"].as_str().unwrap().to_string(), email))
+            .collect(),
+        _ => panic!("Unexpected response: {response:#?}"),
+    }
+}
+
+async fn query(account_id: &Id, filter: &str) -> usize {
+    let response = jmap_json_request(
+        r#"[[ "Email/query", { "accountId": "$a", "filter": $f }, "0" ]]"#
+            .replace("$a", &account_id.to_string())
+            .replace("$f", filter),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+
+    response["methodResponses"][0][1]["ids"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .len()
+}
+
+fn resource(name: &str) -> String {
+    std::fs::read_to_string(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources")
+            .join("smime")
+            .join(name),
+    )
+    .unwrap()
+}
@@ -0,0 +1,247 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap::mailbox::INBOX_ID;
+use jmap_proto::types::id::Id;
+use serde_json::{json, Value};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Thread mute tests...");
+
+    // Create test account
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    );
+    params.client.set_default_account_id(account_id);
+
+    // Create the Archive and Projects mailboxes
+    let response = request(
+        &account_id,
+        r#"[[ "Mailbox/set", {
+            "accountId": "$$",
+            "create": {
+                "a": { "name": "Archive", "role": "archive" },
+                "p": { "name": "Projects" }
+            }
+          }, "0" ]]"#,
+    )
+    .await;
+    let archive_id = response["methodResponses"][0][1]["created"]["a"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let projects_id = response["methodResponses"][0][1]["created"]["p"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+    let inbox_id = Id::from(INBOX_ID).to_string();
+
+    // Deliver the first message of the thread
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Message-ID: <tps-1@remote.org>\r\n",
+            "Subject: TPS reports\r\n",
+            "\r\n",
+            "Did you get the memo about the new cover sheets?",
+        ),
+    )
+    .await;
+    let response = mailbox_emails(&account_id, &inbox_id).await;
+    let thread_id = response["methodResponses"][1][1]["list"][0]["threadId"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"))
+        .to_string();
+
+    // Threads cannot be created or destroyed
+    let response = request(
+        &account_id,
+        &r#"[[ "Thread/set", {
+            "accountId": "$$",
+            "create": { "new": { "isMuted": true } },
+            "destroy": ["$t"]
+          }, "0" ]]"#
+            .replace("$t", &thread_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notCreated"]["new"]["type"], "forbidden",
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][0][1]["notDestroyed"][&thread_id]["type"], "forbidden",
+        "{response:#?}"
+    );
+
+    // Mute the thread
+    let response = request(
+        &account_id,
+        &r#"[[ "Thread/set", {
+            "accountId": "$$",
+            "update": { "$t": { "isMuted": true } }
+          }, "0" ],
+          [ "Thread/get", {
+            "accountId": "$$",
+            "ids": ["$t"]
+          }, "1" ]]"#
+            .replace("$t", &thread_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"],
+        json!({ &thread_id: null }),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["list"][0]["isMuted"], true,
+        "{response:#?}"
+    );
+
+    // Replies to muted threads skip the Inbox
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Message-ID: <tps-2@remote.org>\r\n",
+            "In-Reply-To: <tps-1@remote.org>\r\n",
+            "References: <tps-1@remote.org>\r\n",
+            "Subject: Re: TPS reports\r\n",
+            "\r\n",
+            "Yeah, I'm going to need you to go ahead and use the new cover sheets.",
+        ),
+    )
+    .await;
+    let response = mailbox_emails(&account_id, &archive_id).await;
+    let emails = &response["methodResponses"][1][1]["list"];
+    assert_eq!(
+        emails.as_array().map(|list| list.len()),
+        Some(1),
+        "{response:#?}"
+    );
+    assert_eq!(emails[0]["threadId"], thread_id, "{response:#?}");
+    let response = mailbox_emails(&account_id, &inbox_id).await;
+    assert_eq!(
+        response["methodResponses"][1][1]["list"]
+            .as_array()
+            .map(|list| list.len()),
+        Some(1),
+        "{response:#?}"
+    );
+
+    // Unknown mailboxes are rejected
+    let response = request(
+        &account_id,
+        &r#"[[ "Thread/set", {
+            "accountId": "$$",
+            "update": { "$t": { "mailboxIds/zzzzzz": true } }
+          }, "0" ]]"#
+            .replace("$t", &thread_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notUpdated"][&thread_id]["properties"][0], "mailboxIds",
+        "{response:#?}"
+    );
+
+    // Keywords and mailboxes are applied to every email in the thread
+    let response = request(
+        &account_id,
+        &r#"[[ "Thread/set", {
+            "accountId": "$$",
+            "update": { "$t": {
+                "keywords/$seen": true,
+                "mailboxIds/$p": true,
+                "isMuted": false
+            } }
+          }, "0" ],
+          [ "Thread/get", {
+            "accountId": "$$",
+            "ids": ["$t"]
+          }, "1" ]]"#
+            .replace("$t", &thread_id)
+            .replace("$p", &projects_id),
+    )
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["updated"],
+        json!({ &thread_id: null }),
+        "{response:#?}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["list"][0]["isMuted"], false,
+        "{response:#?}"
+    );
+    let response = mailbox_emails(&account_id, &projects_id).await;
+    let emails = response["methodResponses"][1][1]["list"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response:#?}"));
+    assert_eq!(emails.len(), 2, "{response:#?}");
+    for email in emails {
+        assert_eq!(email["threadId"], thread_id, "{response:#?}");
+        assert_eq!(email["keywords"]["$seen"], true, "{response:#?}");
+    }
+
+    // Remove test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn mailbox_emails(account_id: &Id, mailbox_id: &str) -> Value {
+    request(
+        account_id,
+        &r##"[[ "Email/query", {
+            "accountId": "$$",
+            "filter": { "inMailbox": "$m" }
+          }, "0" ],
+          [ "Email/get", {
+            "accountId": "$$",
+            "#ids": {
+              "resultOf": "0",
+              "name": "Email/query",
+              "path": "/ids"
+            },
+            "properties": ["threadId", "keywords", "mailboxIds"]
+          }, "1" ]]"##
+            .replace("$m", mailbox_id),
+    )
+    .await
+}
+
+async fn request(account_id: &Id, body: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", &account_id.to_string()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
@@ -0,0 +1,396 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use common::auth::AuthRequest;
+use directory::{
+    backend::internal::{lookup::DirectoryStore, manage::ManageDirectory, PrincipalField},
+    Principal, QueryBy, Type,
+};
+use jmap::api::management::principal::{AccountAuthRequest, AccountAuthResponse};
+use ring::{
+    digest::{digest, SHA256},
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+};
+use serde_json::{json, Value};
+
+use crate::jmap::assert_is_empty;
+
+use super::{JMAPTest, ManagementApi, Response};
+
+const RP_ID: &str = "127.0.0.1";
+const ORIGIN: &str = "https://127.0.0.1:8899";
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running WebAuthn tests...");
+
+    let account_id = ManagementApi::new(8899, "admin", "secret")
+        .post::<u32>(
+            "/api/principal",
+            &Principal::new(u32::MAX, Type::Individual)
+                .with_field(PrincipalField::Name, "passkey_user")
+                .with_field(PrincipalField::Secrets, "passkey-secret")
+                .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    let user = ApiHelper(ManagementApi::new(8899, "passkey_user", "passkey-secret"));
+    let authenticator = Authenticator::new();
+
+    // Register a passkey
+    let options = user.post("/api/account/passkey/options", json!({})).await;
+    assert_eq!(options["rp"]["id"], RP_ID);
+    assert_eq!(
+        options["user"]["id"],
+        URL_SAFE_NO_PAD.encode(account_id.to_be_bytes())
+    );
+    let registration = authenticator.attestation(options["challenge"].as_str().unwrap());
+    let response = user
+        .post(
+            "/api/account/passkey/register",
+            json!({"name": "laptop", "credential": registration}),
+        )
+        .await;
+    assert_eq!(response["id"], authenticator.credential_id());
+
+    // Challenges cannot be reused
+    user.expect_error(
+        "/api/account/passkey/register",
+        json!({"name": "laptop2", "credential": registration}),
+    )
+    .await;
+
+    // Passkeys from a different origin are rejected
+    let options = user.post("/api/account/passkey/options", json!({})).await;
+    let mut registration = authenticator.attestation(options["challenge"].as_str().unwrap());
+    registration["response"]["clientDataJSON"] = Value::String(
+        URL_SAFE_NO_PAD.encode(
+            json!({
+                "type": "webauthn.create",
+                "challenge": options["challenge"],
+                "origin": "https://evil.example.org",
+            })
+            .to_string(),
+        ),
+    );
+    user.expect_error(
+        "/api/account/passkey/register",
+        json!({"name": "evil", "credential": registration}),
+    )
+    .await;
+    assert_eq!(passkeys(&user).await, vec!["laptop".to_string()]);
+
+    // App passwords skip 2FA and cannot be used to register passkeys
+    let app_password = user
+        .post("/api/account/app-passwords", json!({"name": "phone"}))
+        .await["password"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let app_user = ManagementApi::new(8899, "passkey_user", &app_password);
+    for (query, body) in [
+        ("/api/account/passkey/options", json!({})),
+        (
+            "/api/account/passkey/register",
+            json!({"name": "phone", "credential": registration}),
+        ),
+    ] {
+        match app_user.post::<Value>(query, &body).await.unwrap() {
+            Response::Error { details, .. } => {
+                assert_eq!(
+                    details.as_deref(),
+                    Some("Passkey registration not allowed using app passwords")
+                );
+            }
+            _ => panic!("Expected app password error"),
+        }
+    }
+    user.0
+        .delete::<()>("/api/account/app-passwords/phone")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(passkeys(&user).await, vec!["laptop".to_string()]);
+
+    // Log in using a discoverable passkey
+    let challenge = user.post("/auth/webauthn/challenge", json!({})).await["challenge"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let assertion = authenticator.assertion(&challenge, 1, Some(account_id));
+    let response = user
+        .post(
+            "/auth/webauthn/code",
+            json!({"clientId": "webadmin", "credential": assertion}),
+        )
+        .await;
+    assert!(!response["code"].as_str().unwrap().is_empty());
+
+    // Log in using a passkey bound to a username
+    let challenge = user
+        .post(
+            "/auth/webauthn/challenge",
+            json!({"username": "passkey_user"}),
+        )
+        .await;
+    assert_eq!(
+        challenge["allowCredentials"][0]["id"],
+        authenticator.credential_id()
+    );
+    let assertion = authenticator.assertion(challenge["challenge"].as_str().unwrap(), 2, None);
+
+    // Unknown usernames receive a stable decoy credential
+    let mut decoys = Vec::new();
+    for _ in 0..2 {
+        let decoy = user
+            .post(
+                "/auth/webauthn/challenge",
+                json!({"username": "ghost_user"}),
+            )
+            .await;
+        assert_eq!(decoy["allowCredentials"].as_array().unwrap().len(), 1);
+        decoys.push(
+            decoy["allowCredentials"][0]["id"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        );
+    }
+    assert_eq!(decoys[0], decoys[1]);
+    assert_ne!(decoys[0], authenticator.credential_id());
+    user.post(
+        "/auth/webauthn/code",
+        json!({"clientId": "webadmin", "credential": assertion}),
+    )
+    .await;
+
+    // Replayed signature counters and invalid signatures are rejected
+    for (counter, tamper) in [(2, false), (3, true)] {
+        let challenge = user.post("/auth/webauthn/challenge", json!({})).await["challenge"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let mut assertion = authenticator.assertion(&challenge, counter, Some(account_id));
+        if tamper {
+            assertion["response"]["signature"] =
+                Value::String(URL_SAFE_NO_PAD.encode(authenticator.sign(b"something else")));
+        }
+        match user
+            .0
+            .post::<Value>(
+                "/auth/webauthn/code",
+                &json!({"clientId": "webadmin", "credential": assertion}),
+            )
+            .await
+            .unwrap()
+        {
+            Response::RequestError(err) => assert_eq!(err.status, 401),
+            _ => panic!("Expected authentication failure"),
+        }
+    }
+
+    // Passwords keep working and passkeys are not accepted as passwords
+    let server = params.server.clone();
+    let credential = server
+        .core
+        .storage
+        .data
+        .query(QueryBy::Id(account_id), false)
+        .await
+        .unwrap()
+        .unwrap()
+        .iter_str(PrincipalField::Secrets)
+        .find(|secret| secret.starts_with("$webauthn$laptop$"))
+        .unwrap()
+        .to_string();
+    assert!(credential.ends_with("$2"), "{credential}");
+    for (secret, expect_success) in [("passkey-secret", true), (credential.as_str(), false)] {
+        assert_eq!(
+            server
+                .authenticate(&AuthRequest::from_plain(
+                    "passkey_user",
+                    secret,
+                    0,
+                    "127.0.0.1".parse().unwrap(),
+                ))
+                .await
+                .is_ok(),
+            expect_success
+        );
+    }
+
+    // Remove passkey
+    user.0
+        .post::<()>(
+            "/api/account/auth",
+            &vec![AccountAuthRequest::RemovePasskey {
+                name: "laptop".to_string(),
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(passkeys(&user).await, Vec::<String>::new());
+
+    server
+        .core
+        .storage
+        .data
+        .delete_principal(QueryBy::Id(account_id))
+        .await
+        .unwrap();
+    assert_is_empty(server).await;
+}
+
+async fn passkeys(user: &ApiHelper) -> Vec<String> {
+    user.0
+        .get::<AccountAuthResponse>("/api/account/auth")
+        .await
+        .unwrap()
+        .unwrap_data()
+        .passkeys
+}
+
+struct ApiHelper(ManagementApi);
+
+impl ApiHelper {
+    async fn post(&self, query: &str, body: Value) -> Value {
+        self.0
+            .post::<Value>(query, &body)
+            .await
+            .unwrap()
+            .unwrap_data()
+    }
+
+    async fn expect_error(&self, query: &str, body: Value) {
+        match self.0.post::<Value>(query, &body).await.unwrap() {
+            Response::Error { details, .. } => {
+                assert_eq!(details.as_deref(), Some("Passkey registration failed"));
+            }
+            _ => panic!("Expected registration error"),
+        }
+    }
+}
+
+struct Authenticator {
+    key: EcdsaKeyPair,
+    id: Vec<u8>,
+    rng: SystemRandom,
+}
+
+impl Authenticator {
+    fn new() -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        Authenticator {
+            key: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap(),
+            id: (0..16).collect(),
+            rng,
+        }
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.id)
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.key.sign(&self.rng, message).unwrap().as_ref().to_vec()
+    }
+
+    fn attestation(&self, challenge: &str) -> Value {
+        // COSE EC2 key: {1: 2, 3: -7, -1: 1, -2: x, -3: y}
+        let point = self.key.public_key().as_ref();
+        let mut cose_key = vec![0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21];
+        cbor_bytes(&mut cose_key, &point[1..33]);
+        cose_key.push(0x22);
+        cbor_bytes(&mut cose_key, &point[33..65]);
+
+        let mut auth_data = auth_data(0x41, 0);
+        auth_data.extend_from_slice(&[0u8; 16]);
+        auth_data.extend_from_slice(&(self.id.len() as u16).to_be_bytes());
+        auth_data.extend_from_slice(&self.id);
+        auth_data.extend_from_slice(&cose_key);
+
+        // {"fmt": "none", "attStmt": {}, "authData": auth_data}
+        let mut attestation = vec![0xa3];
+        cbor_text(&mut attestation, "fmt");
+        cbor_text(&mut attestation, "none");
+        cbor_text(&mut attestation, "attStmt");
+        attestation.push(0xa0);
+        cbor_text(&mut attestation, "authData");
+        cbor_bytes(&mut attestation, &auth_data);
+
+        json!({
+            "id": self.credential_id(),
+            "response": {
+                "clientDataJSON": client_data("webauthn.create", challenge),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation),
+            },
+        })
+    }
+
+    fn assertion(&self, challenge: &str, counter: u32, user_id: Option<u32>) -> Value {
+        let client_data = client_data("webauthn.get", challenge);
+        let auth_data = auth_data(0x01, counter);
+        let mut message = auth_data.clone();
+        message.extend_from_slice(
+            digest(&SHA256, &URL_SAFE_NO_PAD.decode(&client_data).unwrap()).as_ref(),
+        );
+
+        json!({
+            "id": self.credential_id(),
+            "response": {
+                "clientDataJSON": client_data,
+                "authenticatorData": URL_SAFE_NO_PAD.encode(auth_data),
+                "signature": URL_SAFE_NO_PAD.encode(self.sign(&message)),
+                "userHandle": user_id.map(|id| URL_SAFE_NO_PAD.encode(id.to_be_bytes())),
+            },
+        })
+    }
+}
+
+fn client_data(typ: &str, challenge: &str) -> String {
+    URL_SAFE_NO_PAD.encode(
+        json!({
+            "type": typ,
+            "challenge": challenge,
+            "origin": ORIGIN,
+            "crossOrigin": false,
+        })
+        .to_string(),
+    )
+}
+
+fn auth_data(flags: u8, counter: u32) -> Vec<u8> {
+    let mut auth_data = digest(&SHA256, RP_ID.as_bytes()).as_ref().to_vec();
+    auth_data.push(flags);
+    auth_data.extend_from_slice(&counter.to_be_bytes());
+    auth_data
+}
+
+fn cbor_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    cbor_header(buf, 2, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn cbor_text(buf: &mut Vec<u8>, text: &str) {
+    cbor_header(buf, 3, text.len());
+    buf.extend_from_slice(text.as_bytes());
+}
+
+fn cbor_header(buf: &mut Vec<u8>, major: u8, len: usize) {
+    match len {
+        0..=23 => buf.push((major << 5) | len as u8),
+        24..=255 => buf.extend_from_slice(&[(major << 5) | 24, len as u8]),
+        _ => {
+            buf.push((major << 5) | 25);
+            buf.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+}
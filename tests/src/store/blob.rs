@@ -6,8 +6,9 @@
 
 use ahash::AHashMap;
 use store::{
+    backend::tiered::BlobTier,
     write::{blob::BlobQuota, now, BatchBuilder, BlobOp},
-    BlobClass, BlobStore, Serialize, Stores,
+    BlobBackend, BlobClass, BlobStore, Serialize, Stores,
 };
 use utils::{config::Config, BlobHash};
 
@@ -403,6 +404,112 @@ pub async fn blob_tests() {
     temp_dir.delete();
 }
 
+const TIERED_CONFIG: &str = r#"
+[store."sqlite"]
+type = "sqlite"
+path = "{TMP}/sqlite.db"
+
+[store."hot"]
+type = "fs"
+path = "{TMP}/hot"
+
+[store."cold"]
+type = "fs"
+path = "{TMP}/cold"
+
+[store."tiered"]
+type = "tiered-blob"
+hot = "hot"
+cold = "cold"
+store = "sqlite"
+offload.after = "1s"
+"#;
+
+#[tokio::test]
+pub async fn tiered_blob_tests() {
+    let temp_dir = TempDir::new("tiered_blob_tests", true);
+    let mut config = Config::new(
+        TIERED_CONFIG.replace("{TMP}", temp_dir.path.as_path().to_str().unwrap()),
+    )
+    .unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let blob_store = stores.blob_stores.get("tiered").unwrap().clone();
+    let hot = stores.blob_stores.get("hot").unwrap().clone();
+    let cold = stores.blob_stores.get("cold").unwrap().clone();
+    let tiered = match &blob_store.backend {
+        BlobBackend::Tiered(tiered) => tiered.clone(),
+        _ => panic!("Expected tiered blob store"),
+    };
+
+    // Basic read, write and delete operations
+    test_store(blob_store.clone()).await;
+
+    // New blobs are written to the hot tier
+    let hash = BlobHash::from(b"tiered".as_slice());
+    blob_store.put_blob(hash.as_ref(), b"tiered").await.unwrap();
+    assert_eq!(
+        tiered.tier_info(&hash).await.unwrap().unwrap().tier,
+        BlobTier::Hot
+    );
+    assert!(hot
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(cold
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Writing the same blob again keeps its original creation time
+    let created = tiered.tier_info(&hash).await.unwrap().unwrap().created;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    blob_store.put_blob(hash.as_ref(), b"tiered").await.unwrap();
+    assert_eq!(
+        tiered.tier_info(&hash).await.unwrap().unwrap().created,
+        created
+    );
+
+    // Aged blobs are moved to the cold tier
+    tiered.offload_blobs().await.unwrap();
+    assert_eq!(
+        tiered.tier_info(&hash).await.unwrap().unwrap().tier,
+        BlobTier::Cold
+    );
+    assert!(hot
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(cold
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_some());
+
+    // Reads are transparent across tiers
+    assert_eq!(
+        blob_store
+            .get_blob(hash.as_ref(), 1..4)
+            .await
+            .unwrap()
+            .unwrap(),
+        b"ier"
+    );
+
+    // Deleting removes the blob and its tier information
+    assert!(blob_store.delete_blob(hash.as_ref()).await.unwrap());
+    assert!(tiered.tier_info(&hash).await.unwrap().is_none());
+    assert!(blob_store
+        .get_blob(hash.as_ref(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_none());
+
+    temp_dir.delete();
+}
+
 async fn test_store(store: BlobStore) {
     // Test small blob
     const DATA: &[u8] = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. Fusce erat nisl, dignissim a porttitor id, varius nec arcu. Sed mauris.";
@@ -10,6 +10,7 @@ pub mod import_export;
 pub mod lookup;
 pub mod ops;
 pub mod query;
+pub mod sharded;
 
 use std::io::Read;
 
@@ -31,6 +32,18 @@ region = "eu-central-1"
 endpoint = "http://localhost:9000"
 bucket = "tmp"
 
+[store."azure"]
+type = "azure"
+account = "devstoreaccount1"
+access-key = "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw=="
+endpoint = "http://127.0.0.1:10000/devstoreaccount1"
+container = "tmp"
+
+[store."gcs"]
+type = "gcs"
+endpoint = "http://localhost:4443"
+bucket = "tmp"
+
 [store."fs"]
 type = "fs"
 path = "{TMP}"
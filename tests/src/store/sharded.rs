@@ -0,0 +1,316 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    auth::{ResourceToken, TenantInfo},
+    Core,
+};
+use jmap::email::ingest::{EmailIngest, IngestEmail, IngestSource};
+use jmap_proto::types::collection::Collection;
+use mail_parser::MessageParser;
+use store::{
+    write::{BatchBuilder, BitmapClass, BlobOp, DirectoryClass, TagValue, ValueClass},
+    BitmapKey, LookupStore, Store, Stores, ValueKey,
+};
+use utils::config::Config;
+
+use crate::{smtp::TestSMTP, store::TempDir};
+
+const CONFIG: &str = r#"
+[store."primary"]
+type = "sqlite"
+path = "{TMP}/primary.db"
+
+[store."secondary"]
+type = "sqlite"
+path = "{TMP}/secondary.db"
+
+[store."sharded"]
+type = "sharded"
+primary = "primary"
+shards = ["secondary"]
+move.delay = "1ms"
+
+[storage]
+data = "sharded"
+blob = "primary"
+fts = "primary"
+lookup = "primary"
+"#;
+
+const TEST_MESSAGE: &str = concat!(
+    "From: john@example.org\r\n",
+    "To: jane@example.org\r\n",
+    "Subject: Sharded store test\r\n",
+    "\r\n",
+    "This message is stored on the secondary shard.\r\n"
+);
+
+#[tokio::test]
+pub async fn sharded_store_tests() {
+    let temp_dir = TempDir::new("sharded_store_tests", true);
+    let mut config =
+        Config::new(CONFIG.replace("{TMP}", temp_dir.path.as_path().to_str().unwrap())).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let store = stores.stores.get("sharded").unwrap().clone();
+    let primary = stores.stores.get("primary").unwrap().clone();
+    let secondary = stores.stores.get("secondary").unwrap().clone();
+    let Store::Sharded(sharded) = &store else {
+        panic!("Expected sharded store");
+    };
+
+    // Unassigned accounts live on the primary store
+    assert_eq!(sharded.account_shard_id(0).await.unwrap(), None);
+    assert_eq!(
+        sharded.assign_account(0, "primary").await.unwrap(),
+        "primary"
+    );
+    assert_eq!(
+        sharded.assign_account(1, "secondary").await.unwrap(),
+        "secondary"
+    );
+    assert_eq!(
+        sharded.assign_account(1, "primary").await.unwrap(),
+        "secondary"
+    );
+    assert!(sharded.assign_account(2, "unknown").await.is_err());
+
+    // Batches creating documents on multiple shards are rejected
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0u8)
+        .create_document()
+        .set(ValueClass::Property(1), b"account 0".to_vec())
+        .with_account_id(1)
+        .create_document()
+        .set(ValueClass::Property(1), b"account 1".to_vec());
+    assert!(store.write(batch.build()).await.is_err());
+
+    // Batches spanning multiple shards are split
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0u8)
+        .create_document_with_id(0)
+        .set(ValueClass::Property(1), b"account 0".to_vec())
+        .set(ValueClass::Config(b"sharded".to_vec()), b"global".to_vec());
+    store.write(batch.build()).await.unwrap();
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(1)
+        .with_collection(0u8)
+        .add(DirectoryClass::UsedQuota(1), 100)
+        .create_document()
+        .set(ValueClass::Property(1), b"account 1".to_vec())
+        .tag(2u8, TagValue::Text(b"seen".to_vec()), 0)
+        .set(
+            ValueClass::Blob(BlobOp::Link {
+                hash: Default::default(),
+            }),
+            Vec::new(),
+        );
+    let assigned_ids = store.write(batch.build()).await.unwrap();
+    assert_eq!(assigned_ids.document_ids.len(), 1);
+    let document_id = assigned_ids.document_ids[0];
+    assert_eq!(
+        primary
+            .get_value::<()>(ValueKey {
+                account_id: 1,
+                collection: 0,
+                document_id,
+                class: ValueClass::Blob(BlobOp::Link {
+                    hash: Default::default(),
+                }),
+            })
+            .await
+            .unwrap(),
+        Some(())
+    );
+
+    // Data should be routed to the account shard
+    for (db, account_id, expected) in [
+        (&store, 0, Some("account 0")),
+        (&store, 1, Some("account 1")),
+        (&primary, 0, Some("account 0")),
+        (&primary, 1, None),
+        (&secondary, 0, None),
+        (&secondary, 1, Some("account 1")),
+    ] {
+        let doc_id = if account_id == 1 { document_id } else { 0 };
+        assert_eq!(
+            db.get_value::<String>(property_key(account_id, doc_id))
+                .await
+                .unwrap()
+                .as_deref(),
+            expected,
+            "account {account_id} on {db:?}"
+        );
+    }
+    for db in [&store, &secondary] {
+        assert_eq!(
+            db.get_bitmap(seen_key(1))
+                .await
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![document_id]
+        );
+    }
+    assert_eq!(
+        primary
+            .get_value::<String>(ValueKey::from(ValueClass::Config(b"sharded".to_vec())))
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("global")
+    );
+    // Ingest a message for an account on the secondary shard, quotas and
+    // blob links are kept on the primary store
+    assert_eq!(
+        sharded.assign_account(4, "secondary").await.unwrap(),
+        "secondary"
+    );
+    let server =
+        TestSMTP::from_core(Core::parse(&mut config, stores, Default::default()).await).server;
+    let ingested = server
+        .email_ingest(IngestEmail {
+            raw_message: TEST_MESSAGE.as_bytes(),
+            message: MessageParser::new().parse(TEST_MESSAGE.as_bytes()),
+            resource: ResourceToken {
+                account_id: 4,
+                quota: 0,
+                tenant: Some(TenantInfo { id: 3, quota: 0 }),
+            },
+            mailbox_ids: vec![0],
+            keywords: vec![],
+            received_at: None,
+            source: IngestSource::Smtp,
+            encrypt: false,
+            session_id: 0,
+        })
+        .await
+        .unwrap();
+    let email_id = ingested.id.document_id();
+    let email_ids = BitmapKey::document_ids(4, Collection::Email);
+    assert_eq!(
+        secondary
+            .get_bitmap(email_ids.clone())
+            .await
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![email_id]
+    );
+    assert_eq!(primary.get_bitmap(email_ids).await.unwrap(), None);
+    for (account_id, quota) in [
+        (1, 100),
+        (3, ingested.size as i64),
+        (4, ingested.size as i64),
+    ] {
+        assert_eq!(
+            primary
+                .get_counter(DirectoryClass::UsedQuota(account_id))
+                .await
+                .unwrap(),
+            quota
+        );
+    }
+    assert_eq!(
+        primary
+            .get_value::<()>(ValueKey {
+                account_id: 4,
+                collection: Collection::Email.into(),
+                document_id: email_id,
+                class: ValueClass::Blob(BlobOp::Link {
+                    hash: ingested.blob_id.hash.clone(),
+                }),
+            })
+            .await
+            .unwrap(),
+        Some(())
+    );
+
+    // Accounts locked by another node cannot be moved
+    let lookup = LookupStore::Store(primary.clone());
+    lookup
+        .counter_incr(b"shard:1".to_vec(), 1, Some(60), false)
+        .await
+        .unwrap();
+    assert!(sharded.move_account(&lookup, 1, "primary").await.is_err());
+    assert_eq!(
+        sharded.account_shard_id(1).await.unwrap(),
+        Some("secondary")
+    );
+    lookup.counter_delete(b"shard:1".to_vec()).await.unwrap();
+
+    // Move the account to the primary store
+    let version = sharded.version();
+    assert!(sharded.move_account(&lookup, 1, "primary").await.unwrap());
+    assert!(!sharded.move_account(&lookup, 1, "primary").await.unwrap());
+    assert_ne!(sharded.version(), version);
+    assert_eq!(sharded.account_shard_id(1).await.unwrap(), Some("primary"));
+    assert_eq!(
+        secondary
+            .get_value::<String>(property_key(1, document_id))
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(secondary.get_bitmap(seen_key(1)).await.unwrap(), None);
+    for db in [&store, &primary] {
+        assert_eq!(
+            db.get_value::<String>(property_key(1, document_id))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("account 1")
+        );
+        assert_eq!(
+            db.get_bitmap(seen_key(1))
+                .await
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![document_id]
+        );
+        assert_eq!(
+            db.get_bitmap(BitmapKey::document_ids(1, 0u8))
+                .await
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![document_id]
+        );
+    }
+
+    temp_dir.delete();
+}
+
+fn property_key(account_id: u32, document_id: u32) -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id,
+        collection: 0,
+        document_id,
+        class: ValueClass::Property(1),
+    }
+}
+
+fn seen_key(account_id: u32) -> BitmapKey<BitmapClass<u32>> {
+    BitmapKey {
+        account_id,
+        collection: 0,
+        class: BitmapClass::Tag {
+            field: 2,
+            value: TagValue::Text(b"seen".to_vec()),
+        },
+        document_id: 0,
+    }
+}